@@ -72,13 +72,19 @@ impl PipeClient {
         };
 
         // Ensure that scatter-gather array has the requested size.
-        assert_eq!(sga.sga_segs[0].sgaseg_len as usize, size);
+        let sga_len: usize = sga.sga_segs[..sga.sga_numsegs as usize]
+            .iter()
+            .map(|seg| seg.sgaseg_len as usize)
+            .sum();
+        assert_eq!(sga_len, size);
 
         // Fill in scatter-gather array.
-        let ptr: *mut u8 = sga.sga_segs[0].sgaseg_buf as *mut u8;
-        let len: usize = sga.sga_segs[0].sgaseg_len as usize;
-        let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
-        slice.fill(value);
+        for seg in &sga.sga_segs[..sga.sga_numsegs as usize] {
+            let ptr: *mut u8 = seg.sgaseg_buf as *mut u8;
+            let len: usize = seg.sgaseg_len as usize;
+            let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
+            slice.fill(value);
+        }
 
         Ok(sga)
     }
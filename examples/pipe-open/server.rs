@@ -69,7 +69,10 @@ impl PipeServer {
             demi_opcode_t::DEMI_OPC_FAILED => return Ok(self.handle_fail(&qr)?),
             _ => anyhow::bail!("unexpected operation result"),
         };
-        n += sga.sga_segs[0].sgaseg_len as usize;
+        n += sga.sga_segs[..sga.sga_numsegs as usize]
+            .iter()
+            .map(|seg| seg.sgaseg_len as usize)
+            .sum::<usize>();
 
         self.libos.sgafree(sga)?;
 
@@ -40,9 +40,12 @@ fn mksga(libos: &mut LibOS, size: usize, value: u8) -> Result<demi_sgarray_t> {
     };
 
     // Ensure that scatter-gather array has the requested size.
-    if sga.sga_segs[0].sgaseg_len as usize != size {
+    let seglen: usize = sga.sga_segs[..sga.sga_numsegs as usize]
+        .iter()
+        .map(|seg| seg.sgaseg_len as usize)
+        .sum();
+    if seglen != size {
         freesga(libos, sga);
-        let seglen: usize = sga.sga_segs[0].sgaseg_len as usize;
         anyhow::bail!(
             "failed to allocate scatter-gather array: expected size={:?} allocated size={:?}",
             size,
@@ -51,10 +54,12 @@ fn mksga(libos: &mut LibOS, size: usize, value: u8) -> Result<demi_sgarray_t> {
     }
 
     // Fill in scatter-gather array.
-    let ptr: *mut u8 = sga.sga_segs[0].sgaseg_buf as *mut u8;
-    let len: usize = sga.sga_segs[0].sgaseg_len as usize;
-    let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
-    slice.fill(value);
+    for seg in &sga.sga_segs[..sga.sga_numsegs as usize] {
+        let ptr: *mut u8 = seg.sgaseg_buf as *mut u8;
+        let len: usize = seg.sgaseg_len as usize;
+        let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
+        slice.fill(value);
+    }
 
     Ok(sga)
 }
@@ -65,21 +70,20 @@ fn mksga(libos: &mut LibOS, size: usize, value: u8) -> Result<demi_sgarray_t> {
 
 /// Sanity checks the contents of a scatter-gather array.
 fn chksga(sga: &demi_sgarray_t, expected_value: u8) -> Result<usize> {
-    let recvbuf: &[u8] = unsafe {
-        slice::from_raw_parts(
-            sga.sga_segs[0].sgaseg_buf as *const u8,
-            sga.sga_segs[0].sgaseg_len as usize,
-        )
-    };
+    let mut nbytes: usize = 0;
+    for seg in &sga.sga_segs[..sga.sga_numsegs as usize] {
+        let recvbuf: &[u8] = unsafe { slice::from_raw_parts(seg.sgaseg_buf as *const u8, seg.sgaseg_len as usize) };
 
-    // Sanity received data.
-    for x in &recvbuf[..] {
-        if *x != expected_value {
-            demikernel::ensure_eq!(*x, expected_value);
+        // Sanity received data.
+        for x in &recvbuf[..] {
+            if *x != expected_value {
+                demikernel::ensure_eq!(*x, expected_value);
+            }
         }
+        nbytes += recvbuf.len();
     }
 
-    Ok(recvbuf.len())
+    Ok(nbytes)
 }
 
 //======================================================================================================================
@@ -189,7 +189,10 @@ impl Application {
                     let sockqd: QDesc = qr.qr_qd.into();
                     let sga: demi_sgarray_t = unsafe { qr.qr_value.sga };
 
-                    num_bytes += sga.sga_segs[0].sgaseg_len as usize;
+                    num_bytes += sga.sga_segs[..sga.sga_numsegs as usize]
+                        .iter()
+                        .map(|seg| seg.sgaseg_len as usize)
+                        .sum::<usize>();
 
                     if let Err(e) = self.libos.sgafree(sga) {
                         println!("ERROR: sgafree() failed (error={:?})", e);
@@ -58,9 +58,12 @@ fn mksga(libos: &mut LibOS, size: usize, value: u8) -> Result<demi_sgarray_t> {
     };
 
     // Ensure that allocated the array has the requested size.
-    if sga.sga_segs[0].sgaseg_len as usize != size {
+    let seglen: usize = sga.sga_segs[..sga.sga_numsegs as usize]
+        .iter()
+        .map(|seg| seg.sgaseg_len as usize)
+        .sum();
+    if seglen != size {
         freesga(libos, sga);
-        let seglen: usize = sga.sga_segs[0].sgaseg_len as usize;
         anyhow::bail!(
             "failed to allocate scatter-gather array: expected size={:?} allocated size={:?}",
             size,
@@ -69,13 +72,15 @@ fn mksga(libos: &mut LibOS, size: usize, value: u8) -> Result<demi_sgarray_t> {
     }
 
     // Fill in the array.
-    let ptr: *mut u8 = sga.sga_segs[0].sgaseg_buf as *mut u8;
-    let len: usize = sga.sga_segs[0].sgaseg_len as usize;
-    let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
     let mut fill: u8 = value;
-    for x in slice {
-        *x = fill;
-        fill = (fill % (u8::MAX - 1) + 1) as u8;
+    for seg in &sga.sga_segs[..sga.sga_numsegs as usize] {
+        let ptr: *mut u8 = seg.sgaseg_buf as *mut u8;
+        let len: usize = seg.sgaseg_len as usize;
+        let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
+        for x in slice {
+            *x = fill;
+            fill = (fill % (u8::MAX - 1) + 1) as u8;
+        }
     }
 
     Ok(sga)
@@ -143,12 +148,14 @@ fn pop_and_wait(libos: &mut LibOS, sockqd: QDesc, recvbuf: &mut [u8]) -> Result<
         };
 
         // Copy data.
-        let ptr: *mut u8 = sga.sga_segs[0].sgaseg_buf as *mut u8;
-        let len: usize = sga.sga_segs[0].sgaseg_len as usize;
-        let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
-        for x in slice {
-            recvbuf[index] = *x;
-            index += 1;
+        for seg in &sga.sga_segs[..sga.sga_numsegs as usize] {
+            let ptr: *mut u8 = seg.sgaseg_buf as *mut u8;
+            let len: usize = seg.sgaseg_len as usize;
+            let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
+            for x in slice {
+                recvbuf[index] = *x;
+                index += 1;
+            }
         }
 
         if let Err(e) = libos.sgafree(sga) {
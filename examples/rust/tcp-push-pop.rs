@@ -58,9 +58,12 @@ fn mksga(libos: &mut LibOS, size: usize, value: u8) -> Result<demi_sgarray_t> {
     };
 
     // Ensure that allocated array has the requested size.
-    if sga.sga_segs[0].sgaseg_len as usize != size {
+    let seglen: usize = sga.sga_segs[..sga.sga_numsegs as usize]
+        .iter()
+        .map(|seg| seg.sgaseg_len as usize)
+        .sum();
+    if seglen != size {
         freesga(libos, sga);
-        let seglen: usize = sga.sga_segs[0].sgaseg_len as usize;
         anyhow::bail!(
             "failed to allocate scatter-gather array: expected size={:?} allocated size={:?}",
             size,
@@ -69,10 +72,12 @@ fn mksga(libos: &mut LibOS, size: usize, value: u8) -> Result<demi_sgarray_t> {
     }
 
     // Fill in the array.
-    let ptr: *mut u8 = sga.sga_segs[0].sgaseg_buf as *mut u8;
-    let len: usize = sga.sga_segs[0].sgaseg_len as usize;
-    let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
-    slice.fill(value);
+    for seg in &sga.sga_segs[..sga.sga_numsegs as usize] {
+        let ptr: *mut u8 = seg.sgaseg_buf as *mut u8;
+        let len: usize = seg.sgaseg_len as usize;
+        let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
+        slice.fill(value);
+    }
 
     Ok(sga)
 }
@@ -162,15 +167,18 @@ impl TcpServer {
             };
 
             // Sanity check received data.
-            let ptr: *mut u8 = self.sga.expect("should be a valid sgarray").sga_segs[0].sgaseg_buf as *mut u8;
-            let len: usize = self.sga.expect("should be a valid sgarray").sga_segs[0].sgaseg_len as usize;
-            let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
+            let sga: demi_sgarray_t = self.sga.expect("should be a valid sgarray");
+            for seg in &sga.sga_segs[..sga.sga_numsegs as usize] {
+                let ptr: *mut u8 = seg.sgaseg_buf as *mut u8;
+                let len: usize = seg.sgaseg_len as usize;
+                let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
 
-            for x in slice {
-                demikernel::ensure_eq!(*x, fill_char);
-            }
+                for x in slice {
+                    demikernel::ensure_eq!(*x, fill_char);
+                }
 
-            i += self.sga.expect("should be a valid sgarray").sga_segs[0].sgaseg_len as usize;
+                i += len;
+            }
 
             match self.libos.sgafree(self.sga.expect("should be a valid sgarray")) {
                 Ok(_) => self.sga = None,
@@ -257,7 +265,11 @@ impl TcpClient {
                 Ok(qr) => anyhow::bail!("unexpected opcode: {:?}", qr.qr_opcode),
                 Err(e) => anyhow::bail!("operation failed: {:?}", e.cause),
             };
-            i += self.sga.expect("should be a valid sgarray").sga_segs[0].sgaseg_len as usize;
+            let sga: demi_sgarray_t = self.sga.expect("should be a valid sgarray");
+            i += sga.sga_segs[..sga.sga_numsegs as usize]
+                .iter()
+                .map(|seg| seg.sgaseg_len as usize)
+                .sum::<usize>();
 
             match self.libos.sgafree(self.sga.expect("should be a valid sgarray")) {
                 Ok(_) => self.sga = None,
@@ -136,7 +136,10 @@ impl Application {
             match self.libos.wait(qt, None) {
                 Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_POP => {
                     let sga: demi_sgarray_t = unsafe { qr.qr_value.sga };
-                    num_bytes += sga.sga_segs[0].sgaseg_len as usize;
+                    num_bytes += sga.sga_segs[..sga.sga_numsegs as usize]
+                        .iter()
+                        .map(|seg| seg.sgaseg_len as usize)
+                        .sum::<usize>();
                     if let Err(e) = self.libos.sgafree(sga) {
                         println!("ERROR: sgafree() failed (error={:?})", e);
                         println!("WARN: leaking sga");
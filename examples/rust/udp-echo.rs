@@ -179,7 +179,10 @@ impl Application {
                             anyhow::bail!("could not parse sockaddr: {}", e)
                         },
                     };
-                    num_bytes += sga.sga_segs[0].sgaseg_len as usize;
+                    num_bytes += sga.sga_segs[..sga.sga_numsegs as usize]
+                        .iter()
+                        .map(|seg| seg.sgaseg_len as usize)
+                        .sum::<usize>();
                     // Push packet back.
                     let qt: QToken = match self.libos.pushto(sockqd, &sga, saddr) {
                         Ok(qt) => qt,
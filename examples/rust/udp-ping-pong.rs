@@ -53,9 +53,12 @@ fn mksga(libos: &mut LibOS, size: usize, value: u8) -> Result<demi_sgarray_t> {
     };
 
     // Ensure that allocated array has the requested size.
-    if sga.sga_segs[0].sgaseg_len as usize != size {
+    let seglen: usize = sga.sga_segs[..sga.sga_numsegs as usize]
+        .iter()
+        .map(|seg| seg.sgaseg_len as usize)
+        .sum();
+    if seglen != size {
         freesga(libos, sga);
-        let seglen: usize = sga.sga_segs[0].sgaseg_len as usize;
         anyhow::bail!(
             "failed to allocate scatter-gather array: expected size={:?} allocated size={:?}",
             size,
@@ -63,10 +66,12 @@ fn mksga(libos: &mut LibOS, size: usize, value: u8) -> Result<demi_sgarray_t> {
         );
     }
     // Fill in the array.
-    let ptr: *mut u8 = sga.sga_segs[0].sgaseg_buf as *mut u8;
-    let len: usize = sga.sga_segs[0].sgaseg_len as usize;
-    let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
-    slice.fill(value);
+    for seg in &sga.sga_segs[..sga.sga_numsegs as usize] {
+        let ptr: *mut u8 = seg.sgaseg_buf as *mut u8;
+        let len: usize = seg.sgaseg_len as usize;
+        let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
+        slice.fill(value);
+    }
 
     Ok(sga)
 }
@@ -124,12 +129,15 @@ impl UdpServer {
             };
 
             // Sanity check received data.
-            let ptr: *mut u8 = self.sga.expect("should be a valid sgarray").sga_segs[0].sgaseg_buf as *mut u8;
-            let len: usize = self.sga.expect("should be a valid sgarray").sga_segs[0].sgaseg_len as usize;
-            let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
-            for x in slice {
-                if *x != fill_char {
-                    anyhow::bail!("fill check failed: expected={:?} received={:?}", fill_char, *x);
+            let sga: demi_sgarray_t = self.sga.expect("should be a valid sgarray");
+            for seg in &sga.sga_segs[..sga.sga_numsegs as usize] {
+                let ptr: *mut u8 = seg.sgaseg_buf as *mut u8;
+                let len: usize = seg.sgaseg_len as usize;
+                let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
+                for x in slice {
+                    if *x != fill_char {
+                        anyhow::bail!("fill check failed: expected={:?} received={:?}", fill_char, *x);
+                    }
                 }
             }
 
@@ -234,12 +242,15 @@ impl UdpClient {
                     self.sga = unsafe { Some(qr.qr_value.sga) };
 
                     // Sanity check received data.
-                    let ptr: *mut u8 = self.sga.expect("should be a valid sgarray").sga_segs[0].sgaseg_buf as *mut u8;
-                    let len: usize = self.sga.expect("should be a valid sgarray").sga_segs[0].sgaseg_len as usize;
-                    let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
-                    for x in slice {
-                        if *x != fill_char {
-                            anyhow::bail!("fill check failed: expected={:?} received={:?}", fill_char, *x);
+                    let sga: demi_sgarray_t = self.sga.expect("should be a valid sgarray");
+                    for seg in &sga.sga_segs[..sga.sga_numsegs as usize] {
+                        let ptr: *mut u8 = seg.sgaseg_buf as *mut u8;
+                        let len: usize = seg.sgaseg_len as usize;
+                        let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
+                        for x in slice {
+                            if *x != fill_char {
+                                anyhow::bail!("fill check failed: expected={:?} received={:?}", fill_char, *x);
+                            }
                         }
                     }
 
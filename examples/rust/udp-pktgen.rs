@@ -261,7 +261,10 @@ impl Application {
                     },
                 };
 
-                num_bytes += sga.sga_segs[0].sgaseg_len as usize;
+                num_bytes += sga.sga_segs[..sga.sga_numsegs as usize]
+                    .iter()
+                    .map(|seg| seg.sgaseg_len as usize)
+                    .sum::<usize>();
 
                 if let Err(e) = self.libos.sgafree(sga) {
                     println!("ERROR: sgafree() failed (error={:?})", e);
@@ -280,12 +283,15 @@ impl Application {
         };
 
         // Ensure that allocated array has the requested size.
-        if sga.sga_segs[0].sgaseg_len as usize != size {
+        let seglen: usize = sga.sga_segs[..sga.sga_numsegs as usize]
+            .iter()
+            .map(|seg| seg.sgaseg_len as usize)
+            .sum();
+        if seglen != size {
             if let Err(e) = self.libos.sgafree(sga) {
                 println!("ERROR: sgafree() failed (error={:?})", e);
                 println!("WARN: leaking sga");
             };
-            let seglen: usize = sga.sga_segs[0].sgaseg_len as usize;
             anyhow::bail!(
                 "failed to allocate scatter-gather array: expected size={:?} allocated size={:?}",
                 size,
@@ -293,10 +299,12 @@ impl Application {
             );
         }
         // Fill in the array.
-        let ptr: *mut u8 = sga.sga_segs[0].sgaseg_buf as *mut u8;
-        let len: usize = sga.sga_segs[0].sgaseg_len as usize;
-        let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
-        slice.fill(value);
+        for seg in &sga.sga_segs[..sga.sga_numsegs as usize] {
+            let ptr: *mut u8 = seg.sgaseg_buf as *mut u8;
+            let len: usize = seg.sgaseg_len as usize;
+            let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
+            slice.fill(value);
+        }
 
         Ok(sga)
     }
@@ -54,9 +54,12 @@ fn mksga(libos: &mut LibOS, size: usize, value: u8) -> Result<demi_sgarray_t> {
     };
 
     // Ensure that allocated array has the requested size.
-    if sga.sga_segs[0].sgaseg_len as usize != size {
+    let seglen: usize = sga.sga_segs[..sga.sga_numsegs as usize]
+        .iter()
+        .map(|seg| seg.sgaseg_len as usize)
+        .sum();
+    if seglen != size {
         freesga(libos, sga);
-        let seglen: usize = sga.sga_segs[0].sgaseg_len as usize;
         anyhow::bail!(
             "failed to allocate scatter-gather array: expected size={:?} allocated size={:?}",
             size,
@@ -65,10 +68,12 @@ fn mksga(libos: &mut LibOS, size: usize, value: u8) -> Result<demi_sgarray_t> {
     }
 
     // Fill in the array.
-    let ptr: *mut u8 = sga.sga_segs[0].sgaseg_buf as *mut u8;
-    let len: usize = sga.sga_segs[0].sgaseg_len as usize;
-    let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
-    slice.fill(value);
+    for seg in &sga.sga_segs[..sga.sga_numsegs as usize] {
+        let ptr: *mut u8 = seg.sgaseg_buf as *mut u8;
+        let len: usize = seg.sgaseg_len as usize;
+        let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
+        slice.fill(value);
+    }
 
     Ok(sga)
 }
@@ -127,11 +132,13 @@ impl UdpServer {
 
             if let Some(sga) = self.sga {
                 // Sanity check received data.
-                let ptr: *mut u8 = sga.sga_segs[0].sgaseg_buf as *mut u8;
-                let len: usize = sga.sga_segs[0].sgaseg_len as usize;
-                let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
-                for x in slice {
-                    demikernel::ensure_eq!(*x, fill_char);
+                for seg in &sga.sga_segs[..sga.sga_numsegs as usize] {
+                    let ptr: *mut u8 = seg.sgaseg_buf as *mut u8;
+                    let len: usize = seg.sgaseg_len as usize;
+                    let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
+                    for x in slice {
+                        demikernel::ensure_eq!(*x, fill_char);
+                    }
                 }
                 // Free up the scatter-gather array.
                 match self.libos.sgafree(sga) {
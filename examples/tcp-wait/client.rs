@@ -112,9 +112,12 @@ impl TcpClient {
             match self.libos.wait(pop_qt, Some(DEFAULT_TIMEOUT)) {
                 Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_POP && qr.qr_ret == 0 => {
                     let sga: demi_sgarray_t = unsafe { qr.qr_value.sga };
-                    let sgaseg_len: u32 = sga.sga_segs[0].sgaseg_len;
+                    let sga_len: usize = sga.sga_segs[..sga.sga_numsegs as usize]
+                        .iter()
+                        .map(|seg| seg.sgaseg_len as usize)
+                        .sum();
                     self.libos.sgafree(sga)?;
-                    if sgaseg_len == 0 {
+                    if sga_len == 0 {
                         // In this testing program, the server does not terminate the connection before the client does.
                         // Therefore, pop() cannot successfully receive a zero-length scatter-gather array.
                         anyhow::bail!("pop() should not sucessfully terminate");
@@ -177,9 +180,12 @@ impl TcpClient {
             match self.libos.wait(pop_qt, Some(DEFAULT_TIMEOUT)) {
                 Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_POP && qr.qr_ret == 0 => {
                     let sga: demi_sgarray_t = unsafe { qr.qr_value.sga };
-                    let sgaseg_len: u32 = sga.sga_segs[0].sgaseg_len;
+                    let sga_len: usize = sga.sga_segs[..sga.sga_numsegs as usize]
+                        .iter()
+                        .map(|seg| seg.sgaseg_len as usize)
+                        .sum();
                     self.libos.sgafree(sga)?;
-                    if sgaseg_len == 0 {
+                    if sga_len == 0 {
                         // In this testing program, the server does not terminate the connection before the client does.
                         // Therefore, pop() cannot successfully receive a zero-length scatter-gather array.
                         anyhow::bail!("pop() should not sucessfully terminate");
@@ -238,9 +244,12 @@ impl TcpClient {
             match self.libos.wait(pop_qt, Some(DEFAULT_TIMEOUT)) {
                 Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_POP && qr.qr_ret == 0 => {
                     let sga: demi_sgarray_t = unsafe { qr.qr_value.sga };
-                    let sgaseg_len: u32 = sga.sga_segs[0].sgaseg_len;
+                    let sga_len: usize = sga.sga_segs[..sga.sga_numsegs as usize]
+                        .iter()
+                        .map(|seg| seg.sgaseg_len as usize)
+                        .sum();
                     self.libos.sgafree(sga)?;
-                    if sgaseg_len == 0 {
+                    if sga_len == 0 {
                         // In this testing program, the server does not terminate the connection before the client does.
                         // Therefore, pop() cannot successfully receive a zero-length scatter-gather array.
                         anyhow::bail!("pop() should not sucessfully terminate");
@@ -275,13 +284,19 @@ impl TcpClient {
         };
 
         // Ensure that scatter-gather array has the requested size.
-        assert!(sga.sga_segs[0].sgaseg_len as usize == size);
+        let sga_len: usize = sga.sga_segs[..sga.sga_numsegs as usize]
+            .iter()
+            .map(|seg| seg.sgaseg_len as usize)
+            .sum();
+        assert!(sga_len == size);
 
         // Fill in scatter-gather array.
-        let ptr: *mut u8 = sga.sga_segs[0].sgaseg_buf as *mut u8;
-        let len: usize = sga.sga_segs[0].sgaseg_len as usize;
-        let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
-        slice.fill(value);
+        for seg in &sga.sga_segs[..sga.sga_numsegs as usize] {
+            let ptr: *mut u8 = seg.sgaseg_buf as *mut u8;
+            let len: usize = seg.sgaseg_len as usize;
+            let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
+            slice.fill(value);
+        }
 
         Ok(sga)
     }
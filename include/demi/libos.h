@@ -32,6 +32,26 @@ extern "C"
     ATTR_NONNULL(1)
     extern int demi_init(_In_ const struct demi_args *args);
 
+    /**
+     * @brief Initializes an additional LibOS instance that is independent from other instances.
+     *
+     * @param instance_out Storage location for the handle of the LibOS instance.
+     * @param args         Args
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1)
+    extern int demi_init_instance(_Out_ demi_instance_t *instance_out, _In_ const struct demi_args *args);
+
+    /**
+     * @brief Selects the LibOS instance that subsequent calls of the calling thread are issued to.
+     *
+     * @param instance Handle of the target LibOS instance.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_select_instance(_In_ demi_instance_t instance);
+
     /**
      * @brief Creates a new memory I/O queue.
      *
@@ -123,6 +143,16 @@ extern "C"
      */
     extern int demi_close(_In_ int qd);
 
+    /**
+     * @brief Shuts down receiving, sending or both on a connected socket I/O queue.
+     *
+     * @param qd  Target I/O queue descriptor.
+     * @param how Direction to shut down (SHUT_RD, SHUT_WR or SHUT_RDWR).
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_shutdown(_In_ int qd, _In_ int how);
+
     /**
      * @brief Asynchronously pushes a scatter-gather array to an I/O queue.
      *
@@ -200,6 +230,17 @@ extern "C"
     ATTR_NONNULL(2)
     extern int demi_getpeername(_In_ int qd, _Out_writes_to_(addrlen, *addrlen) struct sockaddr *addr, _In_ socklen_t *addrlen);
 
+    /**
+     * @brief Returns the local address to which qd is bound.
+     *
+     * @param addr    Local address is returned in this parameter.
+     * @param addrlen Indicates the amount of space pointed to by addr
+     *
+     * @return On success, zero is returned. On failure, a positive error code is returned.
+     */
+    ATTR_NONNULL(2)
+    extern int demi_getsockname(_In_ int qd, _Out_writes_to_(addrlen, *addrlen) struct sockaddr *addr, _In_ socklen_t *addrlen);
+
 #ifdef __cplusplus
 }
 #endif
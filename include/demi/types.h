@@ -25,7 +25,7 @@ extern "C"
 /**
  * @brief Maximum number of segments in a scatter-gather array.
  */
-#define DEMI_SGARRAY_MAXSIZE 1
+#define DEMI_SGARRAY_MAXSIZE 4
 
     /**
      * @brief An I/O queue token.
@@ -131,6 +131,11 @@ extern "C"
 #pragma pack(pop)
 #endif
 
+    /**
+     * @brief An opaque handle to a LibOS instance.
+     */
+    typedef uint32_t demi_instance_t;
+
     // Callback Function.
     typedef void (*demi_callback_t)(const char *, uint32_t, uint64_t);
 
@@ -145,9 +150,11 @@ extern "C"
         struct __attribute__((__packed__)) demi_args
 #endif
     {
-        int argc;                 /**< Number of command-line arguments. */
-        char *const *argv;        /**< Command-line Arguments.           */
-        demi_callback_t callback; /**< Callback Function.                */
+        int argc;                 /**< Number of command-line arguments.                 */
+        char *const *argv;        /**< Command-line Arguments.                           */
+        demi_callback_t callback; /**< Callback Function.                                */
+        const char *libos_name;   /**< Name of the LibOS (NULL to read LIBOS).           */
+        const char *config;       /**< Configuration in YAML (NULL to read CONFIG_PATH). */
     };
 
 #ifdef __cplusplus
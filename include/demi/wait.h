@@ -57,6 +57,15 @@ extern "C"
     extern int demi_wait_next_n(_Out_writes_to_(num_qrs, *ready_offset) demi_qresult_t *qr_out, _In_ int num_qrs,
                                 _Out_ int *num_qrs_out, _In_opt_ const struct timespec *timeout);
 
+    /**
+     * @brief Cancels an asynchronous I/O operation that has not completed yet.
+     *
+     * @param qt I/O queue token of the target operation to cancel.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_cancel(_In_ demi_qtoken_t qt);
+
 #ifdef __cplusplus
 }
 #endif
@@ -43,7 +43,9 @@ pub enum DemikernelSyscall {
     PushTo(PushToArgs, i32),
     Pop(PopArgs, i32),
     Close(CloseArgs, i32),
+    Shutdown(ShutdownArgs, i32),
     Wait(WaitArgs, i32),
+    SetSockOpt(SetSockOptArgs, i32),
     Unsupported,
 }
 
@@ -59,7 +61,9 @@ impl Debug for DemikernelSyscall {
             DemikernelSyscall::PushTo(args, _ret) => write!(f, "demi_pushto({:?})", args),
             DemikernelSyscall::Pop(args, _ret) => write!(f, "demi_pop({:?})", args),
             DemikernelSyscall::Close(args, _ret) => write!(f, "demi_close({:?})", args),
+            DemikernelSyscall::Shutdown(args, _ret) => write!(f, "demi_shutdown({:?})", args),
             DemikernelSyscall::Wait(args, _ret) => write!(f, "demi_wait({:?})", args),
+            DemikernelSyscall::SetSockOpt(args, _ret) => write!(f, "demi_setsockopt({:?})", args),
             DemikernelSyscall::Unsupported => write!(f, "Unsupported"),
         }
     }
@@ -147,6 +151,20 @@ pub struct CloseArgs {
     pub qd: u32,
 }
 
+#[derive(Clone, Debug)]
+pub struct ShutdownArgs {
+    pub qd: u32,
+    pub how: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct SetSockOptArgs {
+    pub qd: u32,
+    pub level: String,
+    pub name: String,
+    pub value: i32,
+}
+
 #[derive(Clone, Debug)]
 pub enum PacketEvent {
     Tcp(PacketDirection, TcpPacket),
@@ -197,6 +215,7 @@ pub enum TcpOption {
     Mss(u16),
     WindowScale(u8),
     SackOk,
+    Sack(Vec<(u32, u32)>),
     Timestamp(u32, u32),
     EndOfOptions,
 }
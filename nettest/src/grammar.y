@@ -103,6 +103,10 @@ Syscall -> DemikernelSyscall
             let ret = glue::parse_ret_code(&$6).unwrap();
             DemikernelSyscall::Close($3, ret)
       }
+      | 'SHUTDOWN' 'LPAREN' ShutdownArgs 'RPAREN' 'EQUALS' Expression {
+            let ret = glue::parse_ret_code(&$6).unwrap();
+            DemikernelSyscall::Shutdown($3, ret)
+      }
       | 'WRITE' 'LPAREN' WriteArgs 'RPAREN' 'EQUALS' Expression {
             let ret = glue::parse_ret_code(&$6).unwrap();
             DemikernelSyscall::Push($3, ret)
@@ -118,8 +122,9 @@ Syscall -> DemikernelSyscall
       | 'GETSOCKOPT' 'LPAREN' SyscallArgs 'RPAREN' 'EQUALS' Expression {
             DemikernelSyscall::Unsupported
       }
-      | 'SETSOCKOPT' 'LPAREN' SyscallArgs 'RPAREN' 'EQUALS' Expression {
-            DemikernelSyscall::Unsupported
+      | 'SETSOCKOPT' 'LPAREN' SetSockOptArgs 'RPAREN' 'EQUALS' Expression {
+            let ret = glue::parse_ret_code(&$6).unwrap();
+            DemikernelSyscall::SetSockOpt($3, ret)
       }
       ;
 
@@ -274,6 +279,50 @@ CloseArgs -> glue::CloseArgs
       }
       ;
 
+ShutdownArgs -> glue::ShutdownArgs
+      : 'INTEGER' 'COMMA' 'IDENTIFIER' {
+            let qd = {
+                  let v = $1.map_err(|_| ()).unwrap();
+                  glue::parse_int($lexer.span_str(v.span())).unwrap()
+            };
+            let how = {
+                  let v = $3.map_err(|_| ()).unwrap();
+                  $lexer.span_str(v.span()).to_string()
+            };
+            glue::ShutdownArgs {
+                  qd,
+                  how,
+            }
+      }
+      ;
+
+SetSockOptArgs -> glue::SetSockOptArgs
+      : 'INTEGER' 'COMMA' 'IDENTIFIER' 'COMMA' 'IDENTIFIER' 'COMMA' 'LBRACKET' 'INTEGER' 'RBRACKET' 'COMMA' 'INTEGER' {
+            let qd = {
+                  let v = $1.map_err(|_| ()).unwrap();
+                  glue::parse_int($lexer.span_str(v.span())).unwrap()
+            };
+            let level = {
+                  let v = $3.map_err(|_| ()).unwrap();
+                  $lexer.span_str(v.span()).to_string()
+            };
+            let name = {
+                  let v = $5.map_err(|_| ()).unwrap();
+                  $lexer.span_str(v.span()).to_string()
+            };
+            let value = {
+                  let v = $8.map_err(|_| ()).unwrap();
+                  glue::parse_int($lexer.span_str(v.span())).unwrap()
+            } as i32;
+            glue::SetSockOptArgs {
+                  qd,
+                  level,
+                  name,
+                  value,
+            }
+      }
+      ;
+
 OptionalEndTime -> Option<std::time::Duration>
       : {
             None
@@ -476,6 +525,9 @@ TcpOption -> glue::TcpOption
       | 'SACKOK' {
             glue::TcpOption::SackOk
       }
+      | 'SACK' SackBlockList {
+            glue::TcpOption::Sack($2)
+      }
       | 'TIMESTAMP' 'VAL' 'INTEGER' 'ECR' 'INTEGER' {
             let v = $3.map_err(|_| ()).unwrap();
             let tsval = glue::parse_int($lexer.span_str(v.span())).unwrap();
@@ -485,6 +537,29 @@ TcpOption -> glue::TcpOption
       }
       ;
 
+SackBlockList -> Vec<(u32, u32)>
+      : SackBlock {
+            let mut v = Vec::new();
+            v.insert(0, $1);
+            v
+      }
+      | SackBlock SackBlockList {
+            let mut v = $2;
+            v.insert(0, $1);
+            v
+      }
+      ;
+
+SackBlock -> (u32, u32)
+      : 'INTEGER' 'COLON' 'INTEGER' {
+            let v = $1.map_err(|_| ()).unwrap();
+            let begin = glue::parse_int($lexer.span_str(v.span())).unwrap();
+            let v = $3.map_err(|_| ()).unwrap();
+            let end = glue::parse_int($lexer.span_str(v.span())).unwrap();
+            (begin, end)
+      }
+      ;
+
 UdpPacket -> glue::UdpPacket
       : 'LEN' 'INTEGER' {
             let v = $2.map_err(|_| ()).unwrap();
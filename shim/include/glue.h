@@ -37,6 +37,8 @@ extern int __demi_getsockopt(int sockfd, int level, int optname,
 extern int __demi_setsockopt(int sockfd, int level, int optname,
         const void *optval, socklen_t optlen);
 extern int __demi_getpeername(int qd, struct sockaddr *addr, socklen_t *addrlen);
+extern int __demi_getsockname(int qd, struct sockaddr *addr, socklen_t *addrlen);
+extern int __demi_shutdown(int qd, int how);
 
 extern int __init(void);
 extern int __socket(int domain, int type, int protocol);
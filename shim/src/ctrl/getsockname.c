@@ -4,6 +4,7 @@
 #include "../log.h"
 #include "../qman.h"
 #include "../utils.h"
+#include <glue.h>
 #include <demi/libos.h>
 #include <errno.h>
 #include <sys/socket.h>
@@ -32,10 +33,12 @@ int __getsockname(int sockfd, struct sockaddr *addr, socklen_t *addrlen)
 
     TRACE("sockfd=%d, addr=%p, addrlen=%p", sockfd, (void *)addr, (void *)addrlen);
 
-    // TODO: Hook in demi_getsockname().
-    UNUSED(addr);
-    UNUSED(addrlen);
-    ret = ENOSYS;
+    ret = __demi_getsockname(sockfd, addr, addrlen);
+    if (ret != 0)
+    {
+        errno = ret;
+        return -1;
+    }
 
     return (ret);
 }
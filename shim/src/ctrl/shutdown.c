@@ -31,9 +31,12 @@ int __shutdown(int sockfd, int how)
 
     TRACE("sockfd=%d, how=%d", sockfd, how);
 
-    // TODO: Hook in demi_shutdown().
-    UNUSED(how);
-    ret = 0;
+    ret = __demi_shutdown(sockfd, how);
+    if (ret != 0)
+    {
+        errno = ret;
+        return -1;
+    }
 
     return (ret);
 }
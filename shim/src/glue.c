@@ -134,3 +134,13 @@ int __demi_getpeername(int qd, struct sockaddr *addr, socklen_t *addrlen)
 {
     DEMI_CALL(int, demi_getpeername, qd, addr, addrlen);
 }
+
+int __demi_getsockname(int qd, struct sockaddr *addr, socklen_t *addrlen)
+{
+    DEMI_CALL(int, demi_getsockname, qd, addr, addrlen);
+}
+
+int __demi_shutdown(int qd, int how)
+{
+    DEMI_CALL(int, demi_shutdown, qd, how);
+}
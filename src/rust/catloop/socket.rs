@@ -37,6 +37,7 @@ use ::std::{
     mem,
     net::{
         Ipv4Addr,
+        Shutdown,
         SocketAddr,
         SocketAddrV4,
     },
@@ -77,6 +78,9 @@ pub struct MemorySocket {
     rng: SmallRng,
     /// SO_LINGER option, which dictates how long to wait for the connection to close.
     options: TcpSocketOptions,
+    /// Did the application shut down receiving or sending on this connection?
+    receive_shutdown: bool,
+    send_shutdown: bool,
 }
 
 pub struct SharedMemorySocket(SharedObject<MemorySocket>);
@@ -103,6 +107,8 @@ impl SharedMemorySocket {
             #[cfg(not(debug_assertions))]
             rng: SmallRng::from_entropy(),
             options: TcpSocketOptions::new(config)?,
+            receive_shutdown: false,
+            send_shutdown: false,
         })))
     }
 
@@ -124,6 +130,8 @@ impl SharedMemorySocket {
             #[cfg(not(debug_assertions))]
             rng: SmallRng::from_entropy(),
             options: options.clone(),
+            receive_shutdown: false,
+            send_shutdown: false,
         })))
     }
 
@@ -133,6 +141,21 @@ impl SharedMemorySocket {
             SocketOption::Linger(linger) => self.options.set_linger(linger),
             SocketOption::KeepAlive(keepalive) => self.options.set_keepalive(keepalive),
             SocketOption::NoDelay(nodelay) => self.options.set_nodelay(nodelay),
+            SocketOption::CongestionControl(_) | SocketOption::MaxPacingRate(_) => {
+                let cause: &str = "congestion control options cannot be set on this socket";
+                error!("set_socket_option(): {}", cause);
+                return Err(Fail::new(libc::ENOPROTOOPT, cause));
+            },
+            SocketOption::RecvBufSize(_)
+            | SocketOption::SendBufSize(_)
+            | SocketOption::Broadcast(_)
+            | SocketOption::ReuseAddr(_)
+            | SocketOption::AddMembership(..)
+            | SocketOption::DropMembership(..) => {
+                let cause: String = format!("option cannot be set on this socket (option={:?})", option);
+                error!("set_socket_option(): {}", cause);
+                return Err(Fail::new(libc::ENOPROTOOPT, &cause));
+            },
         }
         Ok(())
     }
@@ -144,6 +167,21 @@ impl SharedMemorySocket {
             SocketOption::Linger(_) => Ok(SocketOption::Linger(self.options.get_linger())),
             SocketOption::KeepAlive(_) => Ok(SocketOption::KeepAlive(self.options.get_keepalive())),
             SocketOption::NoDelay(_) => Ok(SocketOption::NoDelay(self.options.get_nodelay())),
+            SocketOption::CongestionControl(_) | SocketOption::MaxPacingRate(_) => {
+                let cause: &str = "congestion control options cannot be queried on this socket";
+                error!("get_socket_option(): {}", cause);
+                Err(Fail::new(libc::ENOPROTOOPT, cause))
+            },
+            SocketOption::RecvBufSize(_)
+            | SocketOption::SendBufSize(_)
+            | SocketOption::Broadcast(_)
+            | SocketOption::ReuseAddr(_)
+            | SocketOption::AddMembership(..)
+            | SocketOption::DropMembership(..) => {
+                let cause: String = format!("option cannot be queried on this socket (option={:?})", option);
+                error!("get_socket_option(): {}", cause);
+                Err(Fail::new(libc::ENOPROTOOPT, &cause))
+            },
         }
     }
 
@@ -160,6 +198,30 @@ impl SharedMemorySocket {
         }
     }
 
+    /// Gets address to which socket is bound.
+    pub fn getsockname(&self) -> Result<SocketAddrV4, Fail> {
+        match self.local {
+            Some(addr) => Ok(addr),
+            None => {
+                let cause: &str = "socket is not bound";
+                error!("getsockname(): {:?}", cause);
+                Err(Fail::new(libc::EINVAL, cause))
+            },
+        }
+    }
+
+    /// Shuts down receiving, sending or both on this connection. Memory queues only carry an end of file when they
+    /// close, so the remote does not learn about it.
+    pub fn shutdown(&mut self, how: Shutdown) -> Result<(), Fail> {
+        if how != Shutdown::Read {
+            self.send_shutdown = true;
+        }
+        if how != Shutdown::Write {
+            self.receive_shutdown = true;
+        }
+        Ok(())
+    }
+
     /// Binds the target socket to `local` address.
     /// TODO: Should probably move the create of the duplex pipe to listen.
     pub fn bind(&mut self, local: SocketAddrV4, catmem: &mut SharedCatmemLibOS) -> Result<(), Fail> {
@@ -259,16 +321,20 @@ impl SharedMemorySocket {
                     return Err(e);
                 },
             };
+            // Keep track of the pipe, so that it can be closed if this connect fails or is cancelled.
+            self.catmem_qd = Some(new_qd);
             // Send an ack to the server over the new pipe.
             if let Err(e) = send_ack(catmem.clone(), new_qd, &request_id).await {
+                if let Err(e) = self.cancel_connect(&mut catmem) {
+                    warn!("connect(): leaking pipe ({:?})", e);
+                }
                 return Err(e);
             }
             Ok((new_qd, remote))
         };
 
         match result {
-            Ok((new_qd, remote)) => {
-                self.catmem_qd = Some(new_qd);
+            Ok((_, remote)) => {
                 self.remote = Some(remote);
                 Ok(())
             },
@@ -276,6 +342,17 @@ impl SharedMemorySocket {
         }
     }
 
+    /// Rolls back a connect that was cancelled before it completed by closing the pipe that it opened, if any.
+    pub fn cancel_connect(&mut self, catmem: &mut SharedCatmemLibOS) -> Result<(), Fail> {
+        if self.remote.is_some() {
+            return Ok(());
+        }
+        match self.catmem_qd.take() {
+            Some(qd) => catmem.close(qd),
+            None => Ok(()),
+        }
+    }
+
     /// Closes `socket`.
     pub async fn close(&mut self, catmem: SharedCatmemLibOS) -> Result<(), Fail> {
         if let Some(qd) = self.catmem_qd {
@@ -310,6 +387,11 @@ impl SharedMemorySocket {
 
     /// Asynchronous code for pushing to the underlying Catmem transport.
     pub async fn push(&mut self, catmem: SharedCatmemLibOS, buf: &mut DemiBuffer) -> Result<(), Fail> {
+        if self.send_shutdown {
+            let cause: &str = "cannot send after shutting down sending";
+            error!("push(): {:?}", cause);
+            return Err(Fail::new(libc::EPIPE, cause));
+        }
         // It is safe to unwrap here, because we have just checked for the socket state
         // and by construction it should be connected. If not, the socket state machine
         // was not correctly driven.
@@ -317,7 +399,8 @@ impl SharedMemorySocket {
         // TODO: Remove the copy eventually.
         match catmem.push_coroutine(qd, buf.clone()).await {
             (_, OperationResult::Push) => {
-                buf.trim(buf.len())?;
+                // Clear out the original buffer, which may be a chain.
+                *buf = DemiBuffer::new(0);
                 Ok(())
             },
             (_, OperationResult::Failed(e)) => Err(e),
@@ -331,6 +414,9 @@ impl SharedMemorySocket {
         catmem: SharedCatmemLibOS,
         size: usize,
     ) -> Result<(Option<SocketAddr>, DemiBuffer), Fail> {
+        if self.receive_shutdown {
+            return Ok((None, DemiBuffer::new(0)));
+        }
         // It is safe to unwrap here, because we have just checked for the socket state
         // and by construction it should be connected. If not, the socket state machine
         // was not correctly driven.
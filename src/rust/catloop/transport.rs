@@ -28,6 +28,7 @@ use ::socket2::{
 use ::std::{
     net::{
         Ipv4Addr,
+        Shutdown,
         SocketAddr,
         SocketAddrV4,
     },
@@ -52,8 +53,8 @@ pub struct CatloopTransport {
     runtime: SharedDemiRuntime,
     /// Configuration.
     config: Config,
-    /// Local IP.
-    local_ipv4_addr: Ipv4Addr,
+    /// Local IPs.
+    local_ipv4_addrs: Vec<Ipv4Addr>,
 }
 
 #[derive(Clone)]
@@ -65,12 +66,17 @@ pub struct SharedCatloopTransport(SharedObject<CatloopTransport>);
 
 impl SharedCatloopTransport {
     pub fn new(config: &Config, runtime: SharedDemiRuntime) -> Result<Self, Fail> {
+        // Binds are checked against the local IPv4 addresses, so the configuration must set the primary one.
+        if config.local_ipv4_addr().is_none() {
+            return Err(Config::missing_option("local_ipv4_addr"));
+        }
+
         Ok(Self(SharedObject::new(CatloopTransport {
-            catmem: SharedCatmemLibOS::new(config, runtime.clone()),
+            catmem: SharedCatmemLibOS::new(config, runtime.clone())?,
             runtime,
             config: config.clone(),
             // Save this here so we can be sure to throw an error before we try to bind.
-            local_ipv4_addr: config.local_ipv4_addr()?,
+            local_ipv4_addrs: config.local_ipv4_addrs(),
         })))
     }
 }
@@ -105,8 +111,18 @@ impl NetworkTransport for SharedCatloopTransport {
     fn getpeername(
         &mut self,
         sd: &mut Self::SocketDescriptor
-    ) -> Result<SocketAddrV4, Fail> {
-        sd.getpeername()
+    ) -> Result<SocketAddr, Fail> {
+        Ok(sd.getpeername()?.into())
+    }
+
+    /// Gets the address to which this socket is bound.
+    fn getsockname(&mut self, sd: &mut Self::SocketDescriptor) -> Result<SocketAddr, Fail> {
+        Ok(sd.getsockname()?.into())
+    }
+
+    /// Shuts down receiving, sending or both on a connected socket.
+    fn shutdown(&mut self, sd: &mut Self::SocketDescriptor, how: Shutdown) -> Result<(), Fail> {
+        sd.shutdown(how)
     }
 
     /// Binds a socket to a local endpoint. This function contains the libOS-level functionality needed to bind a
@@ -114,7 +130,7 @@ impl NetworkTransport for SharedCatloopTransport {
     fn bind(&mut self, sd: &mut Self::SocketDescriptor, local: SocketAddr) -> Result<(), Fail> {
         // Check if we are binding to a non-local address.
         let local: SocketAddrV4 = unwrap_socketaddr(local)?;
-        if self.local_ipv4_addr != *local.ip() {
+        if !self.local_ipv4_addrs.contains(local.ip()) {
             let cause: String = format!("cannot bind to non-local address (sd={:?})", sd);
             error!("bind(): {}", cause);
             return Err(Fail::new(libc::EADDRNOTAVAIL, &cause));
@@ -152,6 +168,11 @@ impl NetworkTransport for SharedCatloopTransport {
         sd.connect(self.catmem.clone(), remote).await
     }
 
+    /// Rolls back a connect that was cancelled before it completed.
+    fn cancel_connect(&mut self, sd: &mut Self::SocketDescriptor) -> Result<(), Fail> {
+        sd.cancel_connect(&mut self.catmem)
+    }
+
     /// Asynchronous code to close a queue. This function returns a coroutine that runs asynchronously to close a queue
     /// and the underlying Catmem queue and performs any necessary multi-queue operations at the libOS-level after
     /// the close succeeds or fails.
@@ -21,6 +21,7 @@ use crate::{
             MemoryRuntime,
         },
         queue::downcast_queue,
+        submission::Submitter,
         types::{
             demi_opcode_t,
             demi_qr_value_t,
@@ -37,6 +38,7 @@ use crate::{
 use ::futures::FutureExt;
 use ::std::{
     mem,
+    net::SocketAddr,
     ops::{
         Deref,
         DerefMut,
@@ -44,6 +46,15 @@ use ::std::{
     time::Duration,
 };
 
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Shortest and longest sleeps of an idle Catmem LibOS. Peers do not signal when they push to a shared ring, so an
+/// idle LibOS backs off exponentially between these bounds, and wakes up at most [MAX_IDLE_SLEEP] after a push.
+const MIN_IDLE_SLEEP: Duration = Duration::from_micros(1);
+const MAX_IDLE_SLEEP: Duration = Duration::from_millis(1);
+
 //======================================================================================================================
 // Structures
 //======================================================================================================================
@@ -70,8 +81,21 @@ impl CatmemLibOS {
 /// Associate Functions for the shared Catmem LibOS
 impl SharedCatmemLibOS {
     /// Instantiates a shared Catmem LibOS.
-    pub fn new(_config: &Config, runtime: SharedDemiRuntime) -> Self {
-        Self(SharedObject::new(CatmemLibOS::new(runtime)))
+    pub fn new(config: &Config, mut runtime: SharedDemiRuntime) -> Result<Self, Fail> {
+        if let Some(idle_streak) = config.adaptive_polling() {
+            let submitter: Submitter = runtime.submitter();
+            runtime.set_idle_handler(
+                idle_streak,
+                Box::new(move |timeout: Duration, idle_sleeps: usize| {
+                    let backoff: Duration = MIN_IDLE_SLEEP
+                        .saturating_mul(1 << idle_sleeps.min(u32::BITS as usize - 1))
+                        .min(MAX_IDLE_SLEEP);
+                    // Sleep on the doorbell, so that work that other threads submit cuts the sleep short.
+                    submitter.doorbell().wait(backoff.min(timeout));
+                }),
+            );
+        }
+        Ok(Self(SharedObject::new(CatmemLibOS::new(runtime))))
     }
 
     /// Creates a new memory queue.
@@ -118,7 +142,7 @@ impl SharedCatmemLibOS {
             let coroutine = Box::pin(self.clone().close_coroutine(qd).fuse());
             self.runtime
                 .clone()
-                .insert_io_coroutine("Catmem::async_close", coroutine)
+                .insert_io_coroutine("Catmem::async_close", qd, coroutine)
         };
 
         queue.async_close(coroutine_constructor)
@@ -165,7 +189,7 @@ impl SharedCatmemLibOS {
 
         let coroutine = Box::pin(self.clone().push_coroutine(qd, buf).fuse());
 
-        self.runtime.clone().insert_io_coroutine("Catmem::push", coroutine)
+        self.runtime.clone().insert_io_coroutine("Catmem::push", qd, coroutine)
     }
 
     pub async fn push_coroutine(self, qd: QDesc, buf: DemiBuffer) -> (QDesc, OperationResult) {
@@ -190,7 +214,7 @@ impl SharedCatmemLibOS {
 
         let coroutine = Box::pin(self.clone().pop_coroutine(qd, size).fuse());
 
-        self.runtime.clone().insert_io_coroutine("Catmem::pop", coroutine)
+        self.runtime.clone().insert_io_coroutine("Catmem::pop", qd, coroutine)
     }
 
     pub async fn pop_coroutine(self, qd: QDesc, size: Option<usize>) -> (QDesc, OperationResult) {
@@ -227,11 +251,21 @@ impl SharedCatmemLibOS {
             |qt, qd, result| acceptor(self.create_result(result, qd, qt)), timeout)
     }
 
+    /// Cancels a pending I/O operation, so that waiting on its queue token fails with ECANCELED.
+    pub fn cancel(&mut self, qt: QToken) -> Result<(), Fail> {
+        self.runtime.cancel(qt)
+    }
+
     /// Waits for any operation in an I/O queue.
     pub fn poll(&mut self) {
         self.runtime.poll()
     }
 
+    /// Returns the runtime of the LibOS.
+    pub fn get_runtime(&self) -> SharedDemiRuntime {
+        self.runtime.clone()
+    }
+
     pub fn create_result(&self, result: OperationResult, qd: QDesc, qt: QToken) -> demi_qresult_t {
         match result {
             OperationResult::Connect => unreachable!("Memory libOSes do not support connect"),
@@ -245,7 +279,7 @@ impl SharedCatmemLibOS {
             },
             OperationResult::Pop(addr, bytes) => match self.into_sgarray(bytes) {
                 Ok(mut sga) => {
-                    if let Some(addr) = addr {
+                    if let Some(SocketAddr::V4(addr)) = addr {
                         sga.sga_addr = socketaddrv4_to_sockaddr(&addr);
                     }
                     let qr_value: demi_qr_value_t = demi_qr_value_t { sga };
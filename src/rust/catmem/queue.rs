@@ -171,28 +171,32 @@ impl SharedCatmemQueue {
 
     /// This function tries to push [buf] to the shared memory ring. If the queue is connected to the pop end, then
     /// this function returns an error.
-    pub async fn do_push(&mut self, mut buf: DemiBuffer) -> Result<(), Fail> {
-        loop {
-            match self.ring.try_push(&buf) {
-                Ok(len) if len == buf.len() => {
-                    trace!("data written ({:?}/{:?} bytes)", buf.len(), buf.len());
-                    return Ok(());
-                },
-                Ok(len) if len < buf.len() => {
-                    expect_ok!(buf.adjust(len), "should be able to split remaining bytes");
-                    continue;
-                },
-                Ok(len) => unreachable!(
-                    "should not be possible to write more than in the buffer (len={:?})",
-                    len
-                ),
-                Err(e) if DemiRuntime::should_retry(e.errno) => {
-                    // Operation not completed. Check if it was cancelled.
-                    poll_yield().await;
-                },
-                Err(e) => return Err(e),
+    pub async fn do_push(&mut self, buf: DemiBuffer) -> Result<(), Fail> {
+        // Write the buffers of a chain one after the other.
+        for mut buf in buf.into_segments() {
+            loop {
+                match self.ring.try_push(&buf) {
+                    Ok(len) if len == buf.len() => {
+                        trace!("data written ({:?}/{:?} bytes)", buf.len(), buf.len());
+                        break;
+                    },
+                    Ok(len) if len < buf.len() => {
+                        expect_ok!(buf.adjust(len), "should be able to split remaining bytes");
+                        continue;
+                    },
+                    Ok(len) => unreachable!(
+                        "should not be possible to write more than in the buffer (len={:?})",
+                        len
+                    ),
+                    Err(e) if DemiRuntime::should_retry(e.errno) => {
+                        // Operation not completed. Check if it was cancelled.
+                        poll_yield().await;
+                    },
+                    Err(e) => return Err(e),
+                }
             }
         }
+        Ok(())
     }
 
     /// Generic function for spawning a control-path coroutine on [self].
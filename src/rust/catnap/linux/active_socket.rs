@@ -22,7 +22,11 @@ use crate::{
 use ::socket2::Socket;
 use ::std::{
     cmp::min,
-    io,
+    collections::VecDeque,
+    io::{
+        self,
+        IoSlice,
+    },
     mem::MaybeUninit,
     net::SocketAddr,
 };
@@ -34,7 +38,10 @@ use ::std::{
 /// This structure represents outgoing packets.
 struct Outgoing {
     addr: Option<SocketAddr>,
-    buf: DemiBuffer,
+    /// Buffers that are left to send, as the pushed data may span several of them.
+    segments: VecDeque<DemiBuffer>,
+    /// Whether some of the data was already sent.
+    started: bool,
     result: SharedAsyncValue<Option<Result<(), Fail>>>,
 }
 
@@ -45,6 +52,8 @@ pub struct ActiveSocketData {
     send_queue: AsyncQueue<Outgoing>,
     recv_queue: AsyncQueue<Result<(Option<SocketAddr>, DemiBuffer), Fail>>,
     closed: bool,
+    /// Epoll events that were last taken as wanted by this socket.
+    watched_events: u32,
 }
 
 //======================================================================================================================
@@ -58,6 +67,7 @@ impl ActiveSocketData {
             send_queue: AsyncQueue::default(),
             recv_queue: AsyncQueue::default(),
             closed: false,
+            watched_events: 0,
         }
     }
 
@@ -67,41 +77,72 @@ impl ActiveSocketData {
     pub fn poll_send(&mut self) {
         if let Some(Outgoing {
             addr,
-            mut buf,
+            mut segments,
+            started,
             mut result,
         }) = self.send_queue.try_pop()
         {
             // A dummy request to detect when the socket has connected.
-            if buf.is_empty() {
+            if segments.is_empty() {
                 result.set(Some(Ok(())));
                 return;
             }
-            // Try to send the buffer.
-            let io_result: Result<usize, io::Error> = match addr {
-                Some(addr) => self.socket.send_to(&buf, &addr.clone().into()),
-                None => self.socket.send(&buf),
+            // Try to send the buffers with a single gather write.
+            let io_result: Result<usize, io::Error> = {
+                let io_slices: Vec<IoSlice> = segments.iter().map(|segment| IoSlice::new(segment)).collect();
+                match addr {
+                    Some(addr) => self.socket.send_to_vectored(&io_slices, &addr.into()),
+                    None => self.socket.send_vectored(&io_slices),
+                }
             };
             match io_result {
                 // Operation completed.
                 Ok(nbytes) => {
-                    trace!("data pushed ({:?}/{:?} bytes)", nbytes, buf.len());
-                    expect_ok!(
-                        buf.adjust(nbytes as usize),
-                        "OS should not have sent more bytes than in the buffer"
+                    trace!(
+                        "data pushed ({:?}/{:?} bytes)",
+                        nbytes,
+                        segments.iter().map(|segment| segment.len()).sum::<usize>()
                     );
-                    if buf.is_empty() {
-                        // Done sending this buffer
+                    // Drop the buffers that went out and adjust the one that only partially did.
+                    let mut remaining: usize = nbytes;
+                    while remaining > 0 {
+                        let segment: &mut DemiBuffer = segments
+                            .front_mut()
+                            .expect("OS should not have sent more bytes than in the buffers");
+                        if segment.len() <= remaining {
+                            remaining -= segment.len();
+                            segments.pop_front();
+                        } else {
+                            expect_ok!(
+                                segment.adjust(remaining),
+                                "OS should not have sent more bytes than in the buffer"
+                            );
+                            remaining = 0;
+                        }
+                    }
+                    if segments.is_empty() {
+                        // Done sending these buffers.
                         result.set(Some(Ok(())));
                     } else {
-                        // Only sent part of the buffer so try again later.
-                        self.send_queue.push_front(Outgoing { addr, buf, result });
+                        // Only sent part of the buffers so try again later.
+                        self.send_queue.push_front(Outgoing {
+                            addr,
+                            segments,
+                            started: true,
+                            result,
+                        });
                     }
                 },
                 Err(e) => {
                     let errno: i32 = get_libc_err(e);
                     if DemiRuntime::should_retry(errno) {
-                        // Put the buffer back and try again later.
-                        self.send_queue.push_front(Outgoing { addr, buf, result });
+                        // Put the buffers back and try again later.
+                        self.send_queue.push_front(Outgoing {
+                            addr,
+                            segments,
+                            started,
+                            result,
+                        });
                     } else {
                         let cause: String = format!("failed to send on socket: {:?}", errno);
                         error!("poll_send(): {}", cause);
@@ -147,14 +188,33 @@ impl ActiveSocketData {
         }
     }
 
-    /// Pushes data to the socket. Blocks until completion.
-    pub async fn push(&mut self, addr: Option<SocketAddr>, buf: DemiBuffer) -> Result<(), Fail> {
-        let mut result: SharedAsyncValue<Option<Result<(), Fail>>> = SharedAsyncValue::new(None);
+    /// Queues data to push to the socket. Returns the value that holds the result of the push once it completes.
+    pub fn push(&mut self, addr: Option<SocketAddr>, buf: DemiBuffer) -> SharedAsyncValue<Option<Result<(), Fail>>> {
+        let result: SharedAsyncValue<Option<Result<(), Fail>>> = SharedAsyncValue::new(None);
+        // Empty buffers have nothing to send, so an empty buffer turns into the dummy request.
+        let segments: VecDeque<DemiBuffer> = buf
+            .into_segments()
+            .into_iter()
+            .filter(|segment| !segment.is_empty())
+            .collect();
         self.send_queue.push(Outgoing {
             addr,
-            buf,
+            segments,
+            started: false,
             result: result.clone(),
         });
+        result
+    }
+
+    /// Withdraws the push that holds its result in [result] if it has not started sending yet. A push that already
+    /// sent part of its data goes on, as withdrawing the rest of it would corrupt the stream.
+    pub fn cancel_push(&mut self, result: &SharedAsyncValue<Option<Result<(), Fail>>>) {
+        self.send_queue
+            .retain(|outgoing: &Outgoing| outgoing.started || !outgoing.result.ptr_eq(result));
+    }
+
+    /// Blocks until the push that holds its result in [result] completes.
+    pub async fn wait_for_push(mut result: SharedAsyncValue<Option<Result<(), Fail>>>) -> Result<(), Fail> {
         loop {
             match result.get() {
                 Some(result) => return result,
@@ -181,6 +241,29 @@ impl ActiveSocketData {
         Ok((addr, incoming))
     }
 
+    /// Returns the epoll events that this socket is waiting for: incoming data until the other end closes, and
+    /// outgoing readiness while pushes are pending.
+    pub fn get_wanted_events(&self) -> u32 {
+        let mut events: u32 = 0;
+        if !self.closed {
+            events |= libc::EPOLLIN as u32;
+        }
+        if !self.send_queue.is_empty() {
+            events |= libc::EPOLLOUT as u32;
+        }
+        events
+    }
+
+    /// Returns the epoll events that this socket is waiting for if they changed since they were last taken.
+    pub fn take_changed_events(&mut self) -> Option<u32> {
+        let events: u32 = self.get_wanted_events();
+        if events == self.watched_events {
+            return None;
+        }
+        self.watched_events = events;
+        Some(events)
+    }
+
     pub fn get_socket(&self) -> &Socket {
         &self.socket
     }
@@ -188,4 +271,9 @@ impl ActiveSocketData {
     pub fn get_mut_socket(&mut self) -> &mut Socket {
         &mut self.socket
     }
+
+    /// Takes the socket out of this structure, dropping any pending data.
+    pub fn into_socket(self) -> Socket {
+        self.socket
+    }
 }
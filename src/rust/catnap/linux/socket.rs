@@ -10,6 +10,7 @@ use crate::{
         active_socket::ActiveSocketData,
         passive_socket::PassiveSocketData,
     },
+    collections::async_value::SharedAsyncValue,
     expect_some,
     runtime::{
         fail::Fail,
@@ -19,6 +20,7 @@ use crate::{
 };
 use ::socket2::Socket;
 use ::std::{
+    mem,
     net::SocketAddr,
     ops::{
         Deref,
@@ -82,6 +84,16 @@ impl SharedSocketData {
         self.set_socket_data(SocketData::Active(ActiveSocketData::new(socket)));
     }
 
+    /// Moves an active socket back to an inactive one, after a connect on it was cancelled.
+    pub fn move_socket_to_inactive(&mut self) {
+        let socket: Socket = match mem::replace(self.deref_mut(), SocketData::Inactive(None)) {
+            SocketData::Active(data) => data.into_socket(),
+            SocketData::Inactive(socket) => expect_some!(socket, "should have data"),
+            SocketData::Passive(_) => unreachable!("should not be able to move a passive socket to an inactive one"),
+        };
+        self.set_socket_data(SocketData::Inactive(Some(socket)));
+    }
+
     /// Gets a reference to the actual Socket for reading the socket's metadata (mostly the raw file descriptor).
     pub fn get_socket<'a>(&'a self) -> &'a Socket {
         let _self: &'a SocketData = self.as_ref();
@@ -104,20 +116,37 @@ impl SharedSocketData {
         }
     }
 
+    /// Gets the epoll events that an active socket is waiting for if they changed since they were last taken, or None
+    /// otherwise.
+    pub fn take_changed_events(&mut self) -> Option<u32> {
+        match self.deref_mut() {
+            SocketData::Active(data) => data.take_changed_events(),
+            _ => None,
+        }
+    }
+
     /// An internal function for moving sockets between states.
     fn set_socket_data(&mut self, data: SocketData) {
         *self.deref_mut() = data;
     }
 
-    /// Push some data to an active established connection.
-    pub async fn push(&mut self, addr: Option<SocketAddr>, buf: DemiBuffer) -> Result<(), Fail> {
+    /// Queue some data to push to an active established connection. Returns the value that holds the result of the
+    /// push.
+    pub fn push(&mut self, addr: Option<SocketAddr>, buf: DemiBuffer) -> SharedAsyncValue<Option<Result<(), Fail>>> {
         match self.deref_mut() {
             SocketData::Inactive(_) => unreachable!("Cannot write to an inactive socket"),
-            SocketData::Active(data) => data.push(addr, buf).await,
+            SocketData::Active(data) => data.push(addr, buf),
             SocketData::Passive(_) => unreachable!("Cannot write to a passive socket"),
         }
     }
 
+    /// Withdraws the push that holds its result in [result] if it has not started sending yet.
+    pub fn cancel_push(&mut self, result: &SharedAsyncValue<Option<Result<(), Fail>>>) {
+        if let SocketData::Active(data) = self.deref_mut() {
+            data.cancel_push(result);
+        }
+    }
+
     /// Accept a new connection on an passive listening socket.
     pub async fn accept(&mut self) -> Result<(Socket, SocketAddr), Fail> {
         match self.deref_mut() {
@@ -14,12 +14,15 @@ mod socket;
 //======================================================================================================================
 
 use crate::{
-    catnap::transport::socket::{
-        SharedSocketData,
-        SocketData,
+    catnap::transport::{
+        active_socket::ActiveSocketData,
+        socket::{
+            SharedSocketData,
+            SocketData,
+        },
     },
+    collections::async_value::SharedAsyncValue,
     demikernel::config::Config,
-    expect_ok,
     expect_some,
     runtime::{
         fail::Fail,
@@ -29,6 +32,7 @@ use crate::{
         },
         network::{
             socket::option::{
+                CongestionControlAlgorithm,
                 SocketOption,
                 TcpSocketOptions,
             },
@@ -46,15 +50,16 @@ use ::slab::Slab;
 use ::socket2::{
     Domain,
     Protocol,
+    SockAddr,
     Socket,
     Type,
 };
 use ::std::{
     io,
+    mem,
     net::{
         Shutdown,
         SocketAddr,
-        SocketAddrV4,
     },
     ops::{
         Deref,
@@ -64,6 +69,7 @@ use ::std::{
         AsRawFd,
         RawFd,
     },
+    time::Duration,
 };
 
 //======================================================================================================================
@@ -73,6 +79,15 @@ use ::std::{
 // Set to the max number of file descriptors that can be open without increasing the number on Linux.
 const EPOLL_BATCH_SIZE: usize = 1024;
 
+// Epoll data of the doorbell of the runtime, which is not a socket.
+const DOORBELL_EVENT: u64 = u64::MAX;
+
+// Generic Linux number of the SO_MAX_PACING_RATE socket option, which libc does not export.
+const SO_MAX_PACING_RATE: libc::c_int = 47;
+
+// Maximum length of the name of a congestion control algorithm on Linux.
+const TCP_CA_NAME_MAX: usize = 16;
+
 //======================================================================================================================
 // Structures
 //======================================================================================================================
@@ -83,12 +98,27 @@ pub struct CatnapTransport {
     socket_table: Slab<SharedSocketData>,
     runtime: SharedDemiRuntime,
     options: TcpSocketOptions,
+    /// Whether active sockets only watch for the epoll events that they are waiting for, so that the runtime can block
+    /// on epoll while idle. Otherwise, they watch for all events, as epoll is level-triggered and reports writable
+    /// sockets right away.
+    watch_wanted_events: bool,
+    /// Whether the epoll coroutine took socket events since the runtime last went idle. These events may have woken
+    /// coroutines that did not run yet, so the runtime should not block.
+    took_events: bool,
 }
 
 /// Shared network transport across coroutines.
 #[derive(Clone)]
 pub struct SharedCatnapTransport(SharedObject<CatnapTransport>);
 
+/// Withdraws a push from the send queue of its socket if the coroutine that waits for the push is dropped before the
+/// push completes, which happens when the push is cancelled.
+struct PushGuard {
+    transport: SharedCatnapTransport,
+    sd: SockDesc,
+    result: SharedAsyncValue<Option<Result<(), Fail>>>,
+}
+
 /// Short-hand for our socket descriptor.
 type SockDesc = <SharedCatnapTransport as NetworkTransport>::SocketDescriptor;
 
@@ -99,6 +129,11 @@ type SockDesc = <SharedCatnapTransport as NetworkTransport>::SocketDescriptor;
 impl SharedCatnapTransport {
     /// Create a new Linux-based network transport.
     pub fn new(config: &Config, runtime: &mut SharedDemiRuntime) -> Result<Self, Fail> {
+        // Sockets may only bind to local IPv4 addresses, so we need at least the primary one.
+        if config.local_ipv4_addr().is_none() {
+            return Err(Config::missing_option("local_ipv4_addr"));
+        }
+
         // Create epoll socket.
         // Linux ignores the size argument to epoll, it just has to be more than 0.
         let epoll_fd: RawFd = match unsafe { libc::epoll_create(10) } {
@@ -110,17 +145,40 @@ impl SharedCatnapTransport {
         };
 
         // Set up background task for polling epoll API.
+        let idle_streak: Option<usize> = config.adaptive_polling();
         let me: Self = Self(SharedObject::new(CatnapTransport {
             epoll_fd,
             socket_table: Slab::<SharedSocketData>::new(),
             runtime: runtime.clone(),
             options: TcpSocketOptions::new(config)?,
+            watch_wanted_events: idle_streak.is_some(),
+            took_events: false,
         }));
         let mut me2: Self = me.clone();
         runtime.insert_background_coroutine(
             "bgc::catnap::transport::epoll",
             Box::pin(async move { me2.poll().await }.fuse()),
         )?;
+
+        // When idle, block until a socket is ready or another thread submits work to the runtime.
+        if let Some(idle_streak) = idle_streak {
+            let doorbell_fd: RawFd = runtime.submitter().doorbell().as_raw_fd();
+            let mut epoll_event: libc::epoll_event = libc::epoll_event {
+                events: libc::EPOLLIN as u32,
+                u64: DOORBELL_EVENT,
+            };
+            if unsafe { libc::epoll_ctl(epoll_fd, libc::EPOLL_CTL_ADD, doorbell_fd, &mut epoll_event) } != 0 {
+                let errno: libc::c_int = unsafe { *libc::__errno_location() };
+                let cause: String = format!("failed to register doorbell (fd={:?}, errno={:?})", doorbell_fd, errno);
+                error!("new(): {}", cause);
+                return Err(Fail::new(errno, &cause));
+            }
+            let mut me3: Self = me.clone();
+            runtime.set_idle_handler(
+                idle_streak,
+                Box::new(move |timeout: Duration, _: usize| me3.wait_idle(timeout)),
+            );
+        }
         Ok(me)
     }
 
@@ -165,6 +223,69 @@ impl SharedCatnapTransport {
         }
     }
 
+    /// This function changes the events that epoll watches on the socket.
+    fn modify_epoll(&mut self, sd: &SockDesc, events: u32) -> Result<(), Fail> {
+        let fd: RawFd = self.raw_fd_from_sd(sd);
+        let mut epoll_event: libc::epoll_event = libc::epoll_event {
+            events,
+            u64: *sd as u64,
+        };
+        match unsafe { libc::epoll_ctl(self.epoll_fd, libc::EPOLL_CTL_MOD, fd, &mut epoll_event) } {
+            0 => Ok(()),
+            _ => {
+                let errno: libc::c_int = unsafe { *libc::__errno_location() };
+                let cause: String = format!("failed to modify epoll (fd={:?}, errno={:?})", fd, errno);
+                error!("modify_epoll(): {}", cause);
+                Err(Fail::new(errno, &cause))
+            },
+        }
+    }
+
+    /// This function registers a handler for incoming and outgoing I/O on an active socket.
+    fn register_active_epoll(&mut self, sd: &SockDesc) -> Result<(), Fail> {
+        let all_events: u32 = (libc::EPOLLIN | libc::EPOLLOUT) as u32;
+        let events: u32 = match self.data_from_sd(sd).take_changed_events() {
+            Some(events) if self.watch_wanted_events => events,
+            _ => all_events,
+        };
+        self.register_epoll(sd, events)
+    }
+
+    /// This function makes an active socket watch for the epoll events that it is waiting for, if they changed. It is
+    /// called whenever the send queue of the socket may have changed.
+    fn update_epoll(&mut self, sd: &SockDesc) {
+        if !self.watch_wanted_events {
+            return;
+        }
+        if let Some(events) = self.data_from_sd(sd).take_changed_events() {
+            if let Err(e) = self.modify_epoll(sd, events) {
+                warn!("update_epoll(): cannot watch socket (sd={:?}, error={:?})", sd, e);
+            }
+        }
+    }
+
+    /// Pushes [buf] to an active socket and blocks until the push completes.
+    async fn push_and_wait(&mut self, sd: &SockDesc, addr: Option<SocketAddr>, buf: DemiBuffer) -> Result<(), Fail> {
+        let result: SharedAsyncValue<Option<Result<(), Fail>>> = self.data_from_sd(sd).push(addr, buf);
+        self.update_epoll(sd);
+        let _guard: PushGuard = PushGuard {
+            transport: self.clone(),
+            sd: *sd,
+            result: result.clone(),
+        };
+        ActiveSocketData::wait_for_push(result).await
+    }
+
+    /// Blocks until a socket has an event that we wait for, another thread submits work to the runtime, or [timeout]
+    /// expires. Does not block if socket events were taken since the last call, as the coroutines that they woke may
+    /// not have run yet.
+    fn wait_idle(&mut self, timeout: Duration) {
+        if mem::take(&mut self.took_events) {
+            return;
+        }
+        wait_epoll(self.epoll_fd, timeout);
+    }
+
     /// Background function for checking for epoll events.
     async fn poll(&mut self) {
         let mut events: Vec<libc::epoll_event> = Vec::with_capacity(EPOLL_BATCH_SIZE);
@@ -196,7 +317,12 @@ impl SharedCatnapTransport {
                 },
             };
             while let Some(event) = events.pop() {
+                // The runtime quiets its doorbell and takes submissions by itself.
+                if event.u64 == DOORBELL_EVENT {
+                    continue;
+                }
                 let offset: usize = event.u64 as usize;
+                self.took_events = true;
                 if event.events & (libc::EPOLLIN as u32) != 0 {
                     // Wake pop.
                     expect_some!(
@@ -226,6 +352,8 @@ impl SharedCatnapTransport {
                     )
                     .poll_out();
                 }
+                // Sending may have drained the send queue.
+                self.update_epoll(&offset);
             }
             // Yield for one iteration.
             poll_yield().await;
@@ -252,11 +380,40 @@ impl SharedCatnapTransport {
 // Standalone functions
 //======================================================================================================================
 
+/// Blocks until events are ready on the epoll socket [epoll_fd] or [timeout] expires, without taking the events.
+fn wait_epoll(epoll_fd: RawFd, timeout: Duration) {
+    let mut pollfd: libc::pollfd = libc::pollfd {
+        fd: epoll_fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    // Unlike epoll_wait, ppoll sleeps with sub-millisecond precision, which keeps timers on time.
+    let timeout: libc::timespec = libc::timespec {
+        tv_sec: timeout.as_secs().min(libc::time_t::MAX as u64) as libc::time_t,
+        tv_nsec: timeout.subsec_nanos() as libc::c_long,
+    };
+    if unsafe { libc::ppoll(&mut pollfd, 1, &timeout, ::std::ptr::null()) } < 0 {
+        let errno: libc::c_int = unsafe { *libc::__errno_location() };
+        if errno != libc::EINTR {
+            warn!("wait_epoll(): ppoll failed (errno={:?})", errno);
+        }
+    }
+}
+
 /// Internal function to extract the raw OS error code.
 fn get_libc_err(e: io::Error) -> i32 {
     expect_some!(e.raw_os_error(), "should have an os error code")
 }
 
+/// Gets the name under which Linux knows [congestion_control]. Linux has no algorithm that turns congestion control
+/// off, so setting [CongestionControlAlgorithm::None] fails with ENOENT.
+fn kernel_congestion_control_name(congestion_control: CongestionControlAlgorithm) -> &'static str {
+    match congestion_control {
+        CongestionControlAlgorithm::NewReno => "reno",
+        congestion_control => congestion_control.as_str(),
+    }
+}
+
 //======================================================================================================================
 // Trait implementation
 //======================================================================================================================
@@ -349,7 +506,7 @@ impl NetworkTransport for SharedCatnapTransport {
             Type::STREAM => self.socket_table.insert(SharedSocketData::new_inactive(socket)),
             Type::DGRAM => {
                 let new_sd: Self::SocketDescriptor = self.socket_table.insert(SharedSocketData::new_active(socket));
-                self.register_epoll(&new_sd, (libc::EPOLLIN | libc::EPOLLOUT) as u32)?;
+                self.register_active_epoll(&new_sd)?;
                 new_sd
             },
             _ => unreachable!("We should have returned an error by now"),
@@ -392,6 +549,108 @@ impl NetworkTransport for SharedCatnapTransport {
                     Ok(())
                 }
             },
+            SocketOption::RecvBufSize(size) => {
+                if let Err(e) = socket.set_recv_buffer_size(size) {
+                    let errno: i32 = get_libc_err(e);
+                    let cause: String = format!("SO_RCVBUF failed: {:?}", errno);
+                    error!("set_socket_option(): {}", cause);
+                    Err(Fail::new(errno, &cause))
+                } else {
+                    Ok(())
+                }
+            },
+            SocketOption::SendBufSize(size) => {
+                if let Err(e) = socket.set_send_buffer_size(size) {
+                    let errno: i32 = get_libc_err(e);
+                    let cause: String = format!("SO_SNDBUF failed: {:?}", errno);
+                    error!("set_socket_option(): {}", cause);
+                    Err(Fail::new(errno, &cause))
+                } else {
+                    Ok(())
+                }
+            },
+            SocketOption::Broadcast(broadcast) => {
+                if let Err(e) = socket.set_broadcast(broadcast) {
+                    let errno: i32 = get_libc_err(e);
+                    let cause: String = format!("SO_BROADCAST failed: {:?}", errno);
+                    error!("set_socket_option(): {}", cause);
+                    Err(Fail::new(errno, &cause))
+                } else {
+                    Ok(())
+                }
+            },
+            SocketOption::ReuseAddr(reuse) => {
+                if let Err(e) = socket.set_reuse_address(reuse) {
+                    let errno: i32 = get_libc_err(e);
+                    let cause: String = format!("SO_REUSEADDR failed: {:?}", errno);
+                    error!("set_socket_option(): {}", cause);
+                    Err(Fail::new(errno, &cause))
+                } else {
+                    Ok(())
+                }
+            },
+            SocketOption::AddMembership(group, interface) => {
+                if let Err(e) = socket.join_multicast_v4(&group, &interface) {
+                    let errno: i32 = get_libc_err(e);
+                    let cause: String = format!("IP_ADD_MEMBERSHIP failed: {:?}", errno);
+                    error!("set_socket_option(): {}", cause);
+                    Err(Fail::new(errno, &cause))
+                } else {
+                    Ok(())
+                }
+            },
+            SocketOption::DropMembership(group, interface) => {
+                if let Err(e) = socket.leave_multicast_v4(&group, &interface) {
+                    let errno: i32 = get_libc_err(e);
+                    let cause: String = format!("IP_DROP_MEMBERSHIP failed: {:?}", errno);
+                    error!("set_socket_option(): {}", cause);
+                    Err(Fail::new(errno, &cause))
+                } else {
+                    Ok(())
+                }
+            },
+            SocketOption::CongestionControl(congestion_control) => {
+                let name: &str = kernel_congestion_control_name(congestion_control);
+                if unsafe {
+                    libc::setsockopt(
+                        socket.as_raw_fd(),
+                        libc::IPPROTO_TCP,
+                        libc::TCP_CONGESTION,
+                        name.as_ptr() as *const libc::c_void,
+                        name.len() as libc::socklen_t,
+                    )
+                } < 0
+                {
+                    let errno: i32 = get_libc_err(io::Error::last_os_error());
+                    let cause: String = format!("TCP_CONGESTION failed: {:?}", errno);
+                    error!("set_socket_option(): {}", cause);
+                    Err(Fail::new(errno, &cause))
+                } else {
+                    Ok(())
+                }
+            },
+            SocketOption::MaxPacingRate(max_pacing_rate) => {
+                // Linux treats the all-ones rate as no limit.
+                let optval: u64 = max_pacing_rate.unwrap_or(u64::MAX);
+                let optval_len: libc::socklen_t = mem::size_of_val(&optval) as libc::socklen_t;
+                if unsafe {
+                    libc::setsockopt(
+                        socket.as_raw_fd(),
+                        libc::SOL_SOCKET,
+                        SO_MAX_PACING_RATE,
+                        &optval as *const _ as *const libc::c_void,
+                        optval_len,
+                    )
+                } < 0
+                {
+                    let errno: i32 = get_libc_err(io::Error::last_os_error());
+                    let cause: String = format!("SO_MAX_PACING_RATE failed: {:?}", errno);
+                    error!("set_socket_option(): {}", cause);
+                    Err(Fail::new(errno, &cause))
+                } else {
+                    Ok(())
+                }
+            },
         }
     }
 
@@ -432,17 +691,117 @@ impl NetworkTransport for SharedCatnapTransport {
                     Err(Fail::new(errno, &cause))
                 },
             },
+            SocketOption::RecvBufSize(_) => match socket.recv_buffer_size() {
+                Ok(size) => Ok(SocketOption::RecvBufSize(size)),
+                Err(e) => {
+                    let errno: i32 = get_libc_err(e);
+                    let cause: String = format!("SO_RCVBUF failed: {:?}", errno);
+                    error!("get_socket_option(): {}", cause);
+                    Err(Fail::new(errno, &cause))
+                },
+            },
+            SocketOption::SendBufSize(_) => match socket.send_buffer_size() {
+                Ok(size) => Ok(SocketOption::SendBufSize(size)),
+                Err(e) => {
+                    let errno: i32 = get_libc_err(e);
+                    let cause: String = format!("SO_SNDBUF failed: {:?}", errno);
+                    error!("get_socket_option(): {}", cause);
+                    Err(Fail::new(errno, &cause))
+                },
+            },
+            SocketOption::Broadcast(_) => match socket.broadcast() {
+                Ok(broadcast) => Ok(SocketOption::Broadcast(broadcast)),
+                Err(e) => {
+                    let errno: i32 = get_libc_err(e);
+                    let cause: String = format!("SO_BROADCAST failed: {:?}", errno);
+                    error!("get_socket_option(): {}", cause);
+                    Err(Fail::new(errno, &cause))
+                },
+            },
+            SocketOption::ReuseAddr(_) => match socket.reuse_address() {
+                Ok(reuse) => Ok(SocketOption::ReuseAddr(reuse)),
+                Err(e) => {
+                    let errno: i32 = get_libc_err(e);
+                    let cause: String = format!("SO_REUSEADDR failed: {:?}", errno);
+                    error!("get_socket_option(): {}", cause);
+                    Err(Fail::new(errno, &cause))
+                },
+            },
+            SocketOption::CongestionControl(_) => {
+                let mut name: [u8; TCP_CA_NAME_MAX] = [0; TCP_CA_NAME_MAX];
+                let mut name_len: libc::socklen_t = name.len() as libc::socklen_t;
+                if unsafe {
+                    libc::getsockopt(
+                        socket.as_raw_fd(),
+                        libc::IPPROTO_TCP,
+                        libc::TCP_CONGESTION,
+                        name.as_mut_ptr() as *mut libc::c_void,
+                        &mut name_len,
+                    )
+                } < 0
+                {
+                    let errno: i32 = get_libc_err(io::Error::last_os_error());
+                    let cause: String = format!("TCP_CONGESTION failed: {:?}", errno);
+                    error!("get_socket_option(): {}", cause);
+                    return Err(Fail::new(errno, &cause));
+                }
+                // The kernel pads the name with null bytes.
+                let name: &[u8] = &name[..name_len as usize];
+                let name: &[u8] = &name[..name.iter().position(|b| *b == 0).unwrap_or(name.len())];
+                match std::str::from_utf8(name) {
+                    Ok(name) => Ok(SocketOption::CongestionControl(name.parse()?)),
+                    Err(_) => {
+                        let cause: String = format!("invalid congestion control algorithm name (name={:?})", name);
+                        error!("get_socket_option(): {}", cause);
+                        Err(Fail::new(libc::EINVAL, &cause))
+                    },
+                }
+            },
+            SocketOption::MaxPacingRate(_) => {
+                let mut optval: u64 = 0;
+                let mut optval_len: libc::socklen_t = mem::size_of_val(&optval) as libc::socklen_t;
+                if unsafe {
+                    libc::getsockopt(
+                        socket.as_raw_fd(),
+                        libc::SOL_SOCKET,
+                        SO_MAX_PACING_RATE,
+                        &mut optval as *mut _ as *mut libc::c_void,
+                        &mut optval_len,
+                    )
+                } < 0
+                {
+                    let errno: i32 = get_libc_err(io::Error::last_os_error());
+                    let cause: String = format!("SO_MAX_PACING_RATE failed: {:?}", errno);
+                    error!("get_socket_option(): {}", cause);
+                    return Err(Fail::new(errno, &cause));
+                }
+                // Kernels before Linux 4.20 only return the lower 32 bits of the rate.
+                let unlimited: u64 = if optval_len as usize == mem::size_of::<u32>() {
+                    u32::MAX as u64
+                } else {
+                    u64::MAX
+                };
+                match optval {
+                    rate if rate == unlimited => Ok(SocketOption::MaxPacingRate(None)),
+                    rate => Ok(SocketOption::MaxPacingRate(Some(rate))),
+                }
+            },
+            SocketOption::AddMembership(..) | SocketOption::DropMembership(..) => {
+                let cause: &str = "multicast memberships cannot be queried";
+                error!("get_socket_option(): {}", cause);
+                Err(Fail::new(libc::ENOPROTOOPT, cause))
+            },
         }
     }
 
     // Gets peer name of connected socket.
-    fn getpeername(&mut self, sd: &mut Self::SocketDescriptor) -> Result<SocketAddrV4, Fail> {
+    fn getpeername(&mut self, sd: &mut Self::SocketDescriptor) -> Result<SocketAddr, Fail> {
         let socket: &mut Socket = self.socket_from_sd(sd);
         match socket.peer_addr() {
-            Ok(addr) => match addr.as_socket_ipv4() {
-                Some(ipv4_addr) => Ok(ipv4_addr),
+            Ok(addr) => match addr.as_socket() {
+                Some(ip_addr) => Ok(ip_addr),
                 None => {
-                    let cause: String = format!("invalid IPv4 address");
+                    let cause: String = format!("invalid IP address");
                     error!("getpeername(): {}", cause);
                     Err(Fail::new(libc::EINVAL, &cause))
                 },
@@ -456,6 +815,39 @@ impl NetworkTransport for SharedCatnapTransport {
         }
     }
 
+    /// Gets the local address of a socket.
+    fn getsockname(&mut self, sd: &mut Self::SocketDescriptor) -> Result<SocketAddr, Fail> {
+        let socket: &mut Socket = self.socket_from_sd(sd);
+        match socket.local_addr() {
+            Ok(addr) => match addr.as_socket() {
+                Some(ip_addr) => Ok(ip_addr),
+                None => {
+                    let cause: &str = "invalid IP address";
+                    error!("getsockname(): {}", cause);
+                    Err(Fail::new(libc::EINVAL, cause))
+                },
+            },
+            Err(e) => {
+                let errno: i32 = get_libc_err(e);
+                let cause: String = format!("failed to get socket name (errno={:?})", errno);
+                error!("getsockname(): {}", cause);
+                Err(Fail::new(errno, &cause))
+            },
+        }
+    }
+
+    /// Shuts down receiving, sending or both on a connected socket. The kernel wakes pending pops with an end of file
+    /// and sends a FIN on stream sockets.
+    fn shutdown(&mut self, sd: &mut Self::SocketDescriptor, how: Shutdown) -> Result<(), Fail> {
+        if let Err(e) = self.socket_from_sd(sd).shutdown(how) {
+            let errno: i32 = get_libc_err(e);
+            let cause: String = format!("failed to shut down socket (how={:?}, errno={:?})", how, errno);
+            error!("shutdown(): {}", cause);
+            return Err(Fail::new(errno, &cause));
+        }
+        Ok(())
+    }
+
     /// Binds a socket to [local] on the underlying network transport.
     fn bind(&mut self, sd: &mut Self::SocketDescriptor, local: SocketAddr) -> Result<(), Fail> {
         timer!("catnap::linux::transport::bind");
@@ -535,7 +927,7 @@ impl NetworkTransport for SharedCatnapTransport {
 
         let new_data: SharedSocketData = SharedSocketData::new_active(new_socket);
         let new_sd: usize = self.socket_table.insert(new_data);
-        self.register_epoll(&new_sd, (libc::EPOLLIN | libc::EPOLLOUT) as u32)?;
+        self.register_active_epoll(&new_sd)?;
         Ok((new_sd, addr))
     }
 
@@ -544,7 +936,7 @@ impl NetworkTransport for SharedCatnapTransport {
     async fn connect(&mut self, sd: &mut Self::SocketDescriptor, remote: SocketAddr) -> Result<(), Fail> {
         timer!("catnap::linux::transport::connect");
         self.data_from_sd(sd).move_socket_to_active();
-        self.register_epoll(&sd, (libc::EPOLLIN | libc::EPOLLOUT) as u32)?;
+        self.register_active_epoll(&sd)?;
 
         loop {
             match self.socket_from_sd(sd).connect(&remote.into()) {
@@ -553,7 +945,7 @@ impl NetworkTransport for SharedCatnapTransport {
                     // Check the return error code.
                     let errno: i32 = get_libc_err(e);
                     if DemiRuntime::should_retry(errno) {
-                        self.data_from_sd(sd).push(None, DemiBuffer::new(0)).await?;
+                        self.push_and_wait(sd, None, DemiBuffer::new(0)).await?;
                     } else {
                         let cause: String = format!("failed to connect on socket: {:?}", errno);
                         error!("connect(): {}", cause);
@@ -564,6 +956,31 @@ impl NetworkTransport for SharedCatnapTransport {
         }
     }
 
+    /// Roll back a connect that was cancelled before it completed. This dissolves the association that the connect may
+    /// have started to set up (see connect(2)) and turns a stream socket back into an inactive one.
+    fn cancel_connect(&mut self, sd: &mut Self::SocketDescriptor) -> Result<(), Fail> {
+        timer!("catnap::linux::transport::cancel_connect");
+        let unspecified: SockAddr = unsafe {
+            let mut storage: libc::sockaddr_storage = mem::zeroed();
+            storage.ss_family = libc::AF_UNSPEC as libc::sa_family_t;
+            SockAddr::new(storage, mem::size_of::<libc::sa_family_t>() as libc::socklen_t)
+        };
+        if let Err(e) = self.socket_from_sd(sd).connect(&unspecified) {
+            let errno: i32 = get_libc_err(e);
+            let cause: String = format!("failed to roll back connect on socket: {:?}", errno);
+            error!("cancel_connect(): {}", cause);
+            return Err(Fail::new(errno, &cause));
+        }
+
+        // Datagram sockets are always active, whereas stream sockets only became active to connect.
+        let is_stream: bool = matches!(self.socket_from_sd(sd).r#type(), Ok(Type::STREAM));
+        if is_stream && matches!(self.data_from_sd(sd).deref().deref(), SocketData::Active(_)) {
+            self.unregister_epoll(sd, (libc::EPOLLIN | libc::EPOLLOUT) as u32)?;
+            self.data_from_sd(sd).move_socket_to_inactive();
+        }
+        Ok(())
+    }
+
     /// Close the socket and block until close completes.
     async fn close(&mut self, sd: &mut Self::SocketDescriptor) -> Result<(), Fail> {
         timer!("catnap::linux::transport::close");
@@ -607,9 +1024,9 @@ impl NetworkTransport for SharedCatnapTransport {
     ) -> Result<(), Fail> {
         timer!("catnap::linux::transport::push");
         {
-            self.data_from_sd(sd).push(addr, buf.clone()).await?;
-            // Clear out the original buffer.
-            expect_ok!(buf.trim(buf.len()), "Should be able to empty the buffer");
+            self.push_and_wait(sd, addr, buf.clone()).await?;
+            // Clear out the original buffer, which may be a chain.
+            *buf = DemiBuffer::new(0);
             Ok(())
         }
     }
@@ -660,3 +1077,14 @@ impl NetworkTransport for SharedCatnapTransport {
 }
 
 impl MemoryRuntime for SharedCatnapTransport {}
+
+impl Drop for PushGuard {
+    fn drop(&mut self) {
+        // There is nothing to withdraw once the push completed or the socket was closed.
+        if self.result.get().is_some() || !self.transport.socket_table.contains(self.sd) {
+            return;
+        }
+        self.transport.data_from_sd(&self.sd).cancel_push(&self.result);
+        self.transport.update_epoll(&self.sd);
+    }
+}
@@ -11,6 +11,7 @@ use std::{
     net::{
         Ipv4Addr,
         Ipv6Addr,
+        Shutdown,
         SocketAddr,
         SocketAddrV4,
         SocketAddrV6,
@@ -41,9 +42,17 @@ use windows::{
             WSASendTo,
             FROM_PROTOCOL_INFO,
             INVALID_SOCKET,
+            IN_ADDR,
+            IN_ADDR_0,
+            IPPROTO_IP,
             IPPROTO_TCP,
+            IP_ADD_MEMBERSHIP,
+            IP_DROP_MEMBERSHIP,
+            IP_MREQ,
             LINGER,
             SD_BOTH,
+            SD_RECEIVE,
+            SD_SEND,
             SIO_KEEPALIVE_VALS,
             SOCKADDR,
             SOCKADDR_IN,
@@ -53,12 +62,17 @@ use windows::{
             SOCKET,
             SOCKET_ERROR,
             SOL_SOCKET,
+            SO_BROADCAST,
             SO_KEEPALIVE,
             SO_LINGER,
             SO_PROTOCOL_INFOW,
+            SO_RCVBUF,
+            SO_REUSEADDR,
+            SO_SNDBUF,
             SO_UPDATE_ACCEPT_CONTEXT,
             SO_UPDATE_CONNECT_CONTEXT,
             TCP_NODELAY,
+            WINSOCK_SHUTDOWN_HOW,
             WSABUF,
             WSAEINVAL,
             WSAPROTOCOL_INFOW,
@@ -220,6 +234,11 @@ impl Socket {
         addr
     }
 
+    /// Get local address to which socket is bound
+    pub fn getsockname(&self) -> Result<SocketAddrV4, Fail> {
+        WinsockRuntime::getsockname(self.s)
+    }
+
     /// Set TCP keepalive socket options.
     pub fn set_tcp_keepalive(&self, keepalive_params: &tcp_keepalive) -> Result<(), Fail> {
         unsafe { WinsockRuntime::do_setsockopt(self.s, SOL_SOCKET, SO_KEEPALIVE, Some(&keepalive_params.onoff)) }?;
@@ -255,6 +274,81 @@ impl Socket {
         }
     }
 
+    /// Set the size of the receive buffer.
+    pub fn set_recv_buf_size(&self, size: usize) -> Result<(), Fail> {
+        let value: i32 = i32::try_from(size).unwrap_or(i32::MAX);
+        unsafe { WinsockRuntime::do_setsockopt(self.s, SOL_SOCKET, SO_RCVBUF, Some(&value)) }?;
+        Ok(())
+    }
+
+    /// Get the size of the receive buffer.
+    pub fn get_recv_buf_size(&self) -> Result<usize, Fail> {
+        let value: i32 = unsafe { WinsockRuntime::do_getsockopt(self.s, SOL_SOCKET, SO_RCVBUF) }?;
+        Ok(value as usize)
+    }
+
+    /// Set the size of the send buffer.
+    pub fn set_send_buf_size(&self, size: usize) -> Result<(), Fail> {
+        let value: i32 = i32::try_from(size).unwrap_or(i32::MAX);
+        unsafe { WinsockRuntime::do_setsockopt(self.s, SOL_SOCKET, SO_SNDBUF, Some(&value)) }?;
+        Ok(())
+    }
+
+    /// Get the size of the send buffer.
+    pub fn get_send_buf_size(&self) -> Result<usize, Fail> {
+        let value: i32 = unsafe { WinsockRuntime::do_getsockopt(self.s, SOL_SOCKET, SO_SNDBUF) }?;
+        Ok(value as usize)
+    }
+
+    /// Allow or forbid sending datagrams to broadcast addresses.
+    pub fn set_broadcast(&self, enabled: bool) -> Result<(), Fail> {
+        let value: BOOL = if enabled { TRUE } else { FALSE };
+        unsafe { WinsockRuntime::do_setsockopt(self.s, SOL_SOCKET, SO_BROADCAST, Some(&value)) }?;
+        Ok(())
+    }
+
+    /// Get broadcast socket option.
+    pub fn get_broadcast(&self) -> Result<bool, Fail> {
+        match unsafe { WinsockRuntime::do_getsockopt(self.s, SOL_SOCKET, SO_BROADCAST) }? {
+            FALSE => Ok(false),
+            _ => Ok(true),
+        }
+    }
+
+    /// Allow or forbid other sockets to bind to the same local address.
+    pub fn set_reuse_addr(&self, enabled: bool) -> Result<(), Fail> {
+        let value: BOOL = if enabled { TRUE } else { FALSE };
+        unsafe { WinsockRuntime::do_setsockopt(self.s, SOL_SOCKET, SO_REUSEADDR, Some(&value)) }?;
+        Ok(())
+    }
+
+    /// Get address reuse socket option.
+    pub fn get_reuse_addr(&self) -> Result<bool, Fail> {
+        match unsafe { WinsockRuntime::do_getsockopt(self.s, SOL_SOCKET, SO_REUSEADDR) }? {
+            FALSE => Ok(false),
+            _ => Ok(true),
+        }
+    }
+
+    /// Join ([add] is true) or leave the IPv4 multicast [group] on the interface with address [interface].
+    pub fn set_multicast_membership(&self, group: Ipv4Addr, interface: Ipv4Addr, add: bool) -> Result<(), Fail> {
+        let mreq: IP_MREQ = IP_MREQ {
+            imr_multiaddr: IN_ADDR {
+                S_un: IN_ADDR_0 {
+                    S_addr: u32::from_ne_bytes(group.octets()),
+                },
+            },
+            imr_interface: IN_ADDR {
+                S_un: IN_ADDR_0 {
+                    S_addr: u32::from_ne_bytes(interface.octets()),
+                },
+            },
+        };
+        let optname: i32 = if add { IP_ADD_MEMBERSHIP } else { IP_DROP_MEMBERSHIP };
+        unsafe { WinsockRuntime::do_setsockopt(self.s, IPPROTO_IP.0, optname, Some(&mreq)) }?;
+        Ok(())
+    }
+
     /// Make a new socket like some template socket.
     pub fn new_like(template: &Socket) -> Result<Socket, Fail> {
         // Safety: SO_PROTOCOL_INFOW fills out a WSAPROTOCOL_INFOW structure.
@@ -288,14 +382,19 @@ impl Socket {
     /// Call once the overlapped operation started by `start_disconnect` has completed to finish disconnecting and
     /// shutdown the socket.
     pub fn finish_disconnect(&self, result: OverlappedResult) -> Result<(), Fail> {
-        self.shutdown().and(result.ok())
+        self.shutdown(Shutdown::Both).and(result.ok())
     }
 
-    /// Shutdown communication on the socket. For better asynchronous behavior on connection-oriented sockets,
-    /// `start_disconnect` will start an asynchronous disconnect operation. If the socket is not disconnected prior to
-    /// this call, this call may block for socket teardown, depending on the linger settings.
-    pub fn shutdown(&self) -> Result<(), Fail> {
-        if unsafe { shutdown(self.s, SD_BOTH) } == 0 {
+    /// Shutdown receiving, sending or both on the socket. For better asynchronous behavior on connection-oriented
+    /// sockets, `start_disconnect` will start an asynchronous disconnect operation. If the socket is not disconnected
+    /// prior to a full shutdown, this call may block for socket teardown, depending on the linger settings.
+    pub fn shutdown(&self, how: Shutdown) -> Result<(), Fail> {
+        let how: WINSOCK_SHUTDOWN_HOW = match how {
+            Shutdown::Read => SD_RECEIVE,
+            Shutdown::Write => SD_SEND,
+            Shutdown::Both => SD_BOTH,
+        };
+        if unsafe { shutdown(self.s, how) } == 0 {
             Ok(())
         } else {
             Err(expect_last_wsa_error().into())
@@ -12,6 +12,7 @@ mod winsock;
 
 use std::{
     net::{
+        Shutdown,
         SocketAddr,
         SocketAddrV4,
     },
@@ -95,6 +96,11 @@ pub struct SharedCatnapTransport(SharedObject<CatnapTransport>);
 impl SharedCatnapTransport {
     /// Create a new transport instance.
     pub fn new(config: &Config, runtime: &mut SharedDemiRuntime) -> Result<Self, Fail> {
+        // Sockets may only bind to local IPv4 addresses, so we need at least the primary one.
+        if config.local_ipv4_addr().is_none() {
+            return Err(Config::missing_option("local_ipv4_addr"));
+        }
+
         let me: Self = Self(SharedObject::new(CatnapTransport {
             winsock: expect_ok!(WinsockRuntime::new(), "failed to initialize WinSock"),
             iocp: expect_ok!(IoCompletionPort::new(), "failed to setup I/O completion port"),
@@ -161,6 +167,17 @@ impl NetworkTransport for SharedCatnapTransport {
             SocketOption::Linger(linger) => socket.set_linger(linger),
             SocketOption::KeepAlive(tcp_keepalive) => socket.set_tcp_keepalive(&tcp_keepalive),
             SocketOption::NoDelay(nagle_enabled) => socket.set_nagle(nagle_enabled),
+            SocketOption::RecvBufSize(size) => socket.set_recv_buf_size(size),
+            SocketOption::SendBufSize(size) => socket.set_send_buf_size(size),
+            SocketOption::Broadcast(broadcast) => socket.set_broadcast(broadcast),
+            SocketOption::ReuseAddr(reuse) => socket.set_reuse_addr(reuse),
+            SocketOption::AddMembership(group, interface) => socket.set_multicast_membership(group, interface, true),
+            SocketOption::DropMembership(group, interface) => socket.set_multicast_membership(group, interface, false),
+            SocketOption::CongestionControl(_) | SocketOption::MaxPacingRate(_) => {
+                let cause: &str = "congestion control options cannot be set on this socket";
+                error!("set_socket_option(): {}", cause);
+                Err(Fail::new(libc::ENOPROTOOPT, cause))
+            },
         }
     }
 
@@ -176,14 +193,28 @@ impl NetworkTransport for SharedCatnapTransport {
             SocketOption::Linger(_) => Ok(SocketOption::Linger(socket.get_linger()?)),
             SocketOption::KeepAlive(_) => Ok(SocketOption::KeepAlive(socket.get_tcp_keepalive()?)),
             SocketOption::NoDelay(_) => Ok(SocketOption::NoDelay(socket.get_nagle()?)),
+            SocketOption::RecvBufSize(_) => Ok(SocketOption::RecvBufSize(socket.get_recv_buf_size()?)),
+            SocketOption::SendBufSize(_) => Ok(SocketOption::SendBufSize(socket.get_send_buf_size()?)),
+            SocketOption::Broadcast(_) => Ok(SocketOption::Broadcast(socket.get_broadcast()?)),
+            SocketOption::ReuseAddr(_) => Ok(SocketOption::ReuseAddr(socket.get_reuse_addr()?)),
+            SocketOption::AddMembership(..) | SocketOption::DropMembership(..) => {
+                let cause: &str = "multicast memberships cannot be queried";
+                error!("get_socket_option(): {}", cause);
+                Err(Fail::new(libc::ENOPROTOOPT, cause))
+            },
+            SocketOption::CongestionControl(_) | SocketOption::MaxPacingRate(_) => {
+                let cause: &str = "congestion control options cannot be queried on this socket";
+                error!("get_socket_option(): {}", cause);
+                Err(Fail::new(libc::ENOPROTOOPT, cause))
+            },
         }
     }
 
     // Gets address of peer connected to socket
-    fn getpeername(&mut self, socket: &mut Self::SocketDescriptor) -> Result<SocketAddrV4, Fail> {
+    fn getpeername(&mut self, socket: &mut Self::SocketDescriptor) -> Result<SocketAddr, Fail> {
         let addr: Result<SocketAddrV4, Fail> = socket.getpeername();
         match addr {
-            Ok(addr) => Ok(addr),
+            Ok(addr) => Ok(addr.into()),
             Err(_) => {
                 let cause: String = format!("failed to get peer address (errno={:?})", unsafe { WSAGetLastError() });
                 error!("getpeername(): {:?}", cause);
@@ -192,9 +223,26 @@ impl NetworkTransport for SharedCatnapTransport {
         }
     }
 
+    // Gets local address to which socket is bound
+    fn getsockname(&mut self, socket: &mut Self::SocketDescriptor) -> Result<SocketAddr, Fail> {
+        match socket.getsockname() {
+            Ok(addr) => Ok(addr.into()),
+            Err(_) => {
+                let cause: String = format!("failed to get local address (errno={:?})", unsafe { WSAGetLastError() });
+                error!("getsockname(): {:?}", cause);
+                Err(Fail::new(libc::EINVAL, &cause))
+            },
+        }
+    }
+
+    /// Shut down receiving, sending or both on the specified socket.
+    fn shutdown(&mut self, socket: &mut Self::SocketDescriptor, how: Shutdown) -> Result<(), Fail> {
+        socket.shutdown(how)
+    }
+
     /// Synchronously shut down the specified socket.
     fn hard_close(&mut self, socket: &mut Self::SocketDescriptor) -> Result<(), Fail> {
-        socket.shutdown()
+        socket.shutdown(Shutdown::Both)
     }
 
     /// Asynchronously disconnect and shut down a socket.
@@ -208,7 +256,7 @@ impl NetworkTransport for SharedCatnapTransport {
         }
         .await
         {
-            Err(err) if err.errno == libc::ENOTCONN => match socket.shutdown() {
+            Err(err) if err.errno == libc::ENOTCONN => match socket.shutdown(Shutdown::Both) {
                 Err(err) if err.errno == libc::ENOTCONN => Ok(()),
                 r => r,
             },
@@ -266,6 +314,14 @@ impl NetworkTransport for SharedCatnapTransport {
         .await
     }
 
+    /// Roll back a connect that was cancelled before it completed. Winsock leaves a socket whose ConnectEx was
+    /// cancelled in an indeterminate state, so the socket can only be closed.
+    fn cancel_connect(&mut self, _socket: &mut Self::SocketDescriptor) -> Result<(), Fail> {
+        let cause: &str = "cannot connect again after a cancelled connect";
+        warn!("cancel_connect(): {}", cause);
+        Err(Fail::new(libc::ENOTSUP, cause))
+    }
+
     /// Pop data from the socket into `buf`. This method will return the remote address iff the socket is not connected.
     async fn pop(
         &mut self,
@@ -305,6 +361,10 @@ impl NetworkTransport for SharedCatnapTransport {
         buf: &mut DemiBuffer,
         addr: Option<SocketAddr>,
     ) -> Result<(), Fail> {
+        // Overlapped sends take a single buffer, so copy chains into one.
+        if buf.num_segments() > 1 {
+            *buf = buf.clone().linearize()?;
+        }
         loop {
             let result: Result<usize, Fail> = unsafe {
                 self.0.iocp.do_io(
@@ -43,6 +43,7 @@ use windows::{
         getsockopt,
         setsockopt,
         getpeername,
+        getsockname,
         WSACleanup,
         WSAIoctl,
         WSASocketW,
@@ -322,6 +323,24 @@ impl WinsockRuntime {
         }
     }
 
+    /// Gets local ip and port from SOCKADDR_IN and converts to SocketAddrV4
+    pub fn getsockname(s: SOCKET) -> Result<SocketAddrV4, Fail> {
+        let mut sockaddr_in: SOCKADDR_IN = SOCKADDR_IN::default();
+        let sockaddr_ptr: *mut SOCKADDR = (&mut sockaddr_in as *mut SOCKADDR_IN).cast::<SOCKADDR>();
+        let mut namelen: i32 = std::mem::size_of::<SOCKADDR_IN>() as i32;
+
+        if unsafe { getsockname(s, sockaddr_ptr, &mut namelen) } == 0 {
+            let port: u16 = u16::from_be(sockaddr_in.sin_port);
+            let addr: IN_ADDR_0_0 = unsafe { sockaddr_in.sin_addr.S_un.S_un_b };
+            Ok(SocketAddrV4::new(
+                Ipv4Addr::new(addr.s_b1, addr.s_b2, addr.s_b3, addr.s_b4),
+                port,
+            ))
+        } else {
+            Err(expect_last_wsa_error())
+        }
+    }
+
     /// Get or initialize a new `SocketExtensions` instance for a  socket. Extensions are stored by socket provider,
     /// which may be shared by multiple sockets.
     fn get_or_init_extensions(&mut self, s: SOCKET) -> Result<Rc<SocketExtensions>, Fail> {
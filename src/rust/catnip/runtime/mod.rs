@@ -26,6 +26,8 @@ use crate::{
             rte_eth_dev_get_mtu,
             rte_eth_dev_info_get,
             rte_eth_dev_is_valid_port,
+            rte_eth_dev_rss_reta_update,
+            rte_eth_dev_set_mc_addr_list,
             rte_eth_dev_set_mtu,
             rte_eth_dev_start,
             rte_eth_find_next_owned_by,
@@ -34,6 +36,9 @@ use crate::{
             rte_eth_macaddr_get,
             rte_eth_promiscuous_enable,
             rte_eth_rss_ip,
+            rte_eth_rss_reta_entry64,
+            rte_eth_rss_tcp,
+            rte_eth_rss_udp,
             rte_eth_rx_burst,
             rte_eth_rx_mq_mode_RTE_ETH_MQ_RX_RSS as RTE_ETH_MQ_RX_RSS,
             rte_eth_rx_offload_tcp_cksum,
@@ -54,11 +59,18 @@ use crate::{
             RTE_ETH_DEV_NO_OWNER,
             RTE_ETH_LINK_FULL_DUPLEX,
             RTE_ETH_LINK_UP,
+            RTE_ETH_RETA_GROUP_SIZE,
             RTE_PKTMBUF_HEADROOM,
         },
         memory::DemiBuffer,
         network::{
             consts::RECEIVE_BATCH_SIZE,
+            rss::{
+                FlowSteering,
+                FrameReplication,
+                RSS_KEY,
+                RSS_KEY_LEN,
+            },
             types::MacAddress,
         },
         SharedObject,
@@ -67,6 +79,7 @@ use crate::{
 };
 use ::arrayvec::ArrayVec;
 use ::std::{
+    collections::HashMap,
     ffi::CString,
     mem,
     mem::MaybeUninit,
@@ -75,6 +88,10 @@ use ::std::{
         Deref,
         DerefMut,
     },
+    sync::{
+        Mutex,
+        MutexGuard,
+    },
     time::Duration,
 };
 
@@ -86,13 +103,44 @@ use ::std::{
 pub struct DPDKRuntime {
     mm: MemoryManager,
     port_id: u16,
+    /// RX/TX queue pair of the port that this runtime owns.
+    queue_id: u16,
     link_addr: MacAddress,
     ipv4_addr: Ipv4Addr,
+    /// Flow steering of [queue_id], if the port spreads flows over several queues.
+    flow_steering: Option<FlowSteering>,
+    /// Handoff of the frames that reach the wrong queue to the other queues, if the port has several queues.
+    frame_replication: Option<FrameReplication>,
+    /// Multicast link addresses that the port accepts frames for.
+    multicast_link_addrs: Vec<MacAddress>,
 }
 
 #[derive(Clone)]
 pub struct SharedDPDKRuntime(SharedObject<DPDKRuntime>);
 
+/// DPDK port, which is initialized by the first runtime that uses it and shared by the runtimes that own its queues.
+struct DPDKPort {
+    port_id: u16,
+    link_addr: MacAddress,
+    /// Number of entries in the redirection table of the port.
+    reta_size: u16,
+    /// Memory managers of the queues. Each one is taken by the runtime that owns the queue.
+    queues: Vec<Option<MemoryManager>>,
+    /// Handoff of the frames that reach the wrong queue to the other queues, for each queue.
+    frame_replications: Vec<FrameReplication>,
+}
+
+// Safety: the memory pool of a queue is only used by the runtime that owns the queue.
+unsafe impl Send for DPDKPort {}
+
+//======================================================================================================================
+// Static Variables
+//======================================================================================================================
+
+/// DPDK ports that runtimes of this process use, by port identifier. This is set once the first runtime has initialized
+/// the DPDK environment abstraction layer (EAL), which can only be initialized once per process.
+static DPDK_PORTS: Mutex<Option<HashMap<u16, DPDKPort>>> = Mutex::new(None);
+
 //======================================================================================================================
 // Associate Functions
 //======================================================================================================================
@@ -100,46 +148,109 @@ pub struct SharedDPDKRuntime(SharedObject<DPDKRuntime>);
 /// Associate Functions for DPDK Runtime
 impl SharedDPDKRuntime {
     pub fn new(config: &Config) -> Result<Self, Fail> {
-        let tcp_offload: Option<bool> = match config.tcp_checksum_offload() {
-            Ok(offload) => Some(offload),
+        let tcp_offload: Option<bool> = config.tcp_checksum_offload();
+        if tcp_offload.is_none() {
+            warn!("No setting for TCP checksum offload. Turning off by default.");
+        }
+
+        let udp_offload: Option<bool> = config.udp_checksum_offload();
+        if udp_offload.is_none() {
+            warn!("No setting for UDP checksum offload. Turning off by default.");
+        }
+
+        let num_queues: u16 = config.num_queues();
+        let queue_id: u16 = config.queue_id();
+
+        // The first runtime initializes the EAL, using its own EAL arguments. The first runtime that uses a port sets
+        // up all queues of the port, and the other runtimes that use the port take their queue.
+        let mut dpdk_ports: MutexGuard<Option<HashMap<u16, DPDKPort>>> = match DPDK_PORTS.lock() {
+            Ok(dpdk_ports) => dpdk_ports,
             Err(_) => {
-                warn!("No setting for TCP checksum offload. Turning off by default.");
-                None
+                let cause: &str = "DPDK ports are poisoned";
+                error!("new(): {}", cause);
+                return Err(Fail::new(libc::EIO, cause));
             },
         };
+        if dpdk_ports.is_none() {
+            Self::initialize_eal(
+                &config
+                    .eal_init_args()
+                    .ok_or_else(|| Config::missing_option("eal_init"))?,
+            )?;
+            *dpdk_ports = Some(HashMap::new());
+        }
+        let ports: &mut HashMap<u16, DPDKPort> = expect_some!(dpdk_ports.as_mut(), "EAL should be initialized");
 
-        let udp_offload: Option<bool> = match config.udp_checksum_offload() {
-            Ok(offload) => Some(offload),
-            Err(_) => {
-                warn!("No setting for UDP checksum offload. Turning off by default.");
-                None
+        let port_id: u16 = match config.port_id() {
+            Some(port_id) => port_id,
+            None => unsafe { rte_eth_find_next_owned_by(0, RTE_ETH_DEV_NO_OWNER as u64) as u16 },
+        };
+        if !ports.contains_key(&port_id) {
+            let port: DPDKPort = Self::initialize_dpdk(
+                port_id,
+                config
+                    .enable_jumbo_frames()
+                    .ok_or_else(|| Config::missing_option("enable_jumbo_frames"))?,
+                config.mtu().ok_or_else(|| Config::missing_option("mtu"))?,
+                tcp_offload.unwrap_or(false),
+                udp_offload.unwrap_or(false),
+                num_queues,
+            )?;
+            ports.insert(port_id, port);
+        }
+        let port: &mut DPDKPort = expect_some!(ports.get_mut(&port_id), "DPDK port should be initialized");
+
+        if port.queues.len() != num_queues as usize {
+            let cause: String = format!(
+                "number of queues does not match the port (num_queues={}, port queues={})",
+                num_queues,
+                port.queues.len()
+            );
+            error!("new(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        let mm: MemoryManager = match port.queues.get_mut(queue_id as usize) {
+            Some(queue) => match queue.take() {
+                Some(mm) => mm,
+                None => {
+                    let cause: String = format!("queue is already in use (queue_id={})", queue_id);
+                    error!("new(): {}", cause);
+                    return Err(Fail::new(libc::EBUSY, &cause));
+                },
+            },
+            None => {
+                let cause: String = format!("invalid queue (queue_id={}, num_queues={})", queue_id, num_queues);
+                error!("new(): {}", cause);
+                return Err(Fail::new(libc::EINVAL, &cause));
             },
         };
 
-        let (mm, port_id, link_addr): (MemoryManager, u16, MacAddress) = Self::initialize_dpdk(
-            &config.eal_init_args()?,
-            config.enable_jumbo_frames()?,
-            config.mtu()?,
-            tcp_offload.unwrap_or(false),
-            udp_offload.unwrap_or(false),
-        )?;
+        // A single queue receives all flows, so there is nothing to steer or replicate.
+        let (flow_steering, frame_replication): (Option<FlowSteering>, Option<FrameReplication>) = if num_queues > 1 {
+            (
+                Some(FlowSteering::new(RSS_KEY, port.reta_size, num_queues, queue_id)?),
+                Some(port.frame_replications[queue_id as usize].clone()),
+            )
+        } else {
+            (None, None)
+        };
 
         Ok(Self(SharedObject::<DPDKRuntime>::new(DPDKRuntime {
             mm,
-            port_id,
-            link_addr,
-            ipv4_addr: config.local_ipv4_addr()?,
+            port_id: port.port_id,
+            queue_id,
+            link_addr: port.link_addr,
+            ipv4_addr: config
+                .local_ipv4_addr()
+                .ok_or_else(|| Config::missing_option("local_ipv4_addr"))?,
+            flow_steering,
+            frame_replication,
+            multicast_link_addrs: Vec::new(),
         })))
     }
 
-    /// Initializes DPDK.
-    fn initialize_dpdk(
-        eal_init_args: &[CString],
-        use_jumbo_frames: bool,
-        mtu: u16,
-        tcp_checksum_offload: bool,
-        udp_checksum_offload: bool,
-    ) -> Result<(MemoryManager, u16, MacAddress), Fail> {
+    /// Initializes the DPDK environment abstraction layer (EAL) with [eal_init_args].
+    fn initialize_eal(eal_init_args: &[CString]) -> Result<(), Fail> {
         std::env::set_var("MLX5_SHUT_UP_BF", "1");
         std::env::set_var("MLX5_SINGLE_THREADED", "1");
         std::env::set_var("MLX4_SINGLE_THREADED", "1");
@@ -148,7 +259,7 @@ impl SharedDPDKRuntime {
         if ret < 0 {
             let rte_errno: libc::c_int = unsafe { dpdk_rs::rte_errno() };
             let cause: String = format!("EAL initialization failed (rte_errno={:?})", rte_errno);
-            error!("initialize_dpdk(): {}", cause);
+            error!("initialize_eal(): {}", cause);
             return Err(Fail::new(libc::EIO, &cause));
         }
         let nb_ports: u16 = unsafe { rte_eth_dev_count_avail() };
@@ -156,6 +267,23 @@ impl SharedDPDKRuntime {
             return Err(Fail::new(libc::EIO, "No ethernet ports available"));
         }
         trace!("DPDK reports that {} ports (interfaces) are available.", nb_ports);
+        Ok(())
+    }
+
+    /// Sets up [num_queues] RX/TX queue pairs on the port [port_id].
+    fn initialize_dpdk(
+        port_id: u16,
+        use_jumbo_frames: bool,
+        mtu: u16,
+        tcp_checksum_offload: bool,
+        udp_checksum_offload: bool,
+        num_queues: u16,
+    ) -> Result<DPDKPort, Fail> {
+        if unsafe { rte_eth_dev_is_valid_port(port_id) } == 0 {
+            let cause: String = format!("invalid DPDK port (port_id={})", port_id);
+            error!("initialize_dpdk(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
 
         let max_body_size: usize = if use_jumbo_frames {
             (RTE_ETHER_MAX_JUMBO_FRAME_LEN + RTE_PKTMBUF_HEADROOM) as usize
@@ -163,20 +291,22 @@ impl SharedDPDKRuntime {
             DEFAULT_MAX_BODY_SIZE
         };
 
-        let memory_manager = match MemoryManager::new(max_body_size) {
-            Ok(manager) => manager,
-            Err(e) => {
-                let cause: String = format!("Failed to set up memory manager: {:?}", e);
-                error!("initialize_dpdk(): {}", cause);
-                return Err(Fail::new(libc::EIO, &cause));
-            },
-        };
+        // Each queue receives into its own memory pool.
+        let mut memory_managers: Vec<MemoryManager> = Vec::with_capacity(num_queues as usize);
+        for queue_id in 0..num_queues {
+            match MemoryManager::new(&format!("body_pool_{}_{}", port_id, queue_id), max_body_size) {
+                Ok(manager) => memory_managers.push(manager),
+                Err(e) => {
+                    let cause: String = format!("Failed to set up memory manager: {:?}", e);
+                    error!("initialize_dpdk(): {}", cause);
+                    return Err(Fail::new(libc::EIO, &cause));
+                },
+            }
+        }
 
-        let owner: u64 = RTE_ETH_DEV_NO_OWNER as u64;
-        let port_id: u16 = unsafe { rte_eth_find_next_owned_by(0, owner) as u16 };
-        Self::initialize_dpdk_port(
+        let reta_size: u16 = Self::initialize_dpdk_port(
             port_id,
-            &memory_manager,
+            &memory_managers,
             use_jumbo_frames,
             mtu,
             tcp_checksum_offload,
@@ -200,20 +330,27 @@ impl SharedDPDKRuntime {
             return Err(Fail::new(libc::EINVAL, &cause));
         }
 
-        Ok((memory_manager, port_id, local_link_addr))
+        Ok(DPDKPort {
+            port_id,
+            link_addr: local_link_addr,
+            reta_size,
+            queues: memory_managers.into_iter().map(Some).collect(),
+            frame_replications: FrameReplication::new(num_queues)?,
+        })
     }
 
-    /// Initializes a DPDK port.
+    /// Initializes a DPDK port with one RX/TX queue pair for each of [memory_managers], and returns the size of its
+    /// redirection table. Flows are spread over the queues as in [FlowSteering].
     fn initialize_dpdk_port(
         port_id: u16,
-        memory_manager: &MemoryManager,
+        memory_managers: &[MemoryManager],
         use_jumbo_frames: bool,
         mtu: u16,
         tcp_checksum_offload: bool,
         udp_checksum_offload: bool,
-    ) -> Result<(), Fail> {
-        let rx_rings: u16 = 1;
-        let tx_rings: u16 = 1;
+    ) -> Result<u16, Fail> {
+        let rx_rings: u16 = memory_managers.len() as u16;
+        let tx_rings: u16 = memory_managers.len() as u16;
         let rx_ring_size: u16 = 2048;
         let tx_ring_size: u16 = 2048;
         let nb_rxd: u16 = rx_ring_size;
@@ -234,6 +371,35 @@ impl SharedDPDKRuntime {
         };
 
         println!("dev_info: {:?}", dev_info);
+        if rx_rings > dev_info.max_rx_queues || tx_rings > dev_info.max_tx_queues {
+            let cause: String = format!(
+                "too many queues (queues={}, max_rx_queues={}, max_tx_queues={})",
+                rx_rings, dev_info.max_rx_queues, dev_info.max_tx_queues
+            );
+            error!("initialize_dpdk_port(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        if rx_rings > 1 && dev_info.reta_size < rx_rings {
+            let cause: String = format!("port cannot spread flows over {} queues", rx_rings);
+            error!("initialize_dpdk_port(): {}", cause);
+            return Err(Fail::new(libc::ENOTSUP, &cause));
+        }
+        // Ephemeral ports are picked by the hash of the whole flow, so a port that leaves the ports of TCP or UDP
+        // packets out of the hash would deliver connections to the wrong queue.
+        let l4_rss_hf: u64 = unsafe { (rte_eth_rss_tcp() | rte_eth_rss_udp()) as u64 };
+        if rx_rings > 1 && l4_rss_hf & !dev_info.flow_type_rss_offloads != 0 {
+            let cause: String = format!(
+                "port cannot hash the ports of TCP and UDP packets (flow_type_rss_offloads={:#x})",
+                dev_info.flow_type_rss_offloads
+            );
+            error!("initialize_dpdk_port(): {}", cause);
+            return Err(Fail::new(libc::ENOTSUP, &cause));
+        }
+
+        // The NIC hashes flows with our key, so that we know which queue receives each of them. The hash covers the
+        // ports of TCP and UDP packets, and the addresses of other IP packets.
+        let mut rss_key: Vec<u8> = RSS_KEY.to_vec();
+        rss_key.resize((dev_info.hash_key_size as usize).max(RSS_KEY_LEN), 0);
         let mut port_conf: rte_eth_conf = unsafe { MaybeUninit::zeroed().assume_init() };
         port_conf.rxmode.max_lro_pkt_size = if use_jumbo_frames {
             RTE_ETHER_MAX_JUMBO_FRAME_LEN
@@ -247,7 +413,11 @@ impl SharedDPDKRuntime {
             port_conf.rxmode.offloads |= unsafe { rte_eth_rx_offload_udp_cksum() as u64 };
         }
         port_conf.rxmode.mq_mode = RTE_ETH_MQ_RX_RSS;
-        port_conf.rx_adv_conf.rss_conf.rss_hf = unsafe { rte_eth_rss_ip() as u64 } | dev_info.flow_type_rss_offloads;
+        port_conf.rx_adv_conf.rss_conf.rss_key = rss_key.as_mut_ptr();
+        port_conf.rx_adv_conf.rss_conf.rss_key_len = rss_key.len() as u8;
+        port_conf.rx_adv_conf.rss_conf.rss_hf =
+            unsafe { (rte_eth_rss_ip() | rte_eth_rss_tcp() | rte_eth_rss_udp()) as u64 }
+                & dev_info.flow_type_rss_offloads;
 
         port_conf.txmode.mq_mode = RTE_ETH_MQ_TX_NONE;
         if tcp_checksum_offload {
@@ -305,7 +475,7 @@ impl SharedDPDKRuntime {
                     nb_rxd,
                     socket_id,
                     &rx_conf as *const _,
-                    memory_manager.body_pool(),
+                    memory_managers[i as usize].body_pool(),
                 ) != 0
                 {
                     let cause: String = format!("Failed to set up rx queue");
@@ -328,6 +498,10 @@ impl SharedDPDKRuntime {
             rte_eth_promiscuous_enable(port_id);
         }
 
+        if rx_rings > 1 {
+            Self::initialize_dpdk_reta(port_id, dev_info.reta_size, rx_rings)?;
+        }
+
         if unsafe { rte_eth_dev_is_valid_port(port_id) } == 0 {
             let cause: String = format!("Invalid port id");
             error!("initialize_dpdk_port(): {}", cause);
@@ -364,6 +538,26 @@ impl SharedDPDKRuntime {
             retry_count -= 1;
         }
 
+        Ok(dev_info.reta_size)
+    }
+
+    /// Programs the redirection table of the port, which has [reta_size] entries, to spread flows over [num_queues].
+    fn initialize_dpdk_reta(port_id: u16, reta_size: u16, num_queues: u16) -> Result<(), Fail> {
+        let flow_steering: FlowSteering = FlowSteering::new(RSS_KEY, reta_size, num_queues, 0)?;
+        let group_size: u16 = RTE_ETH_RETA_GROUP_SIZE as u16;
+        let mut reta_conf: Vec<rte_eth_rss_reta_entry64> =
+            vec![unsafe { MaybeUninit::zeroed().assume_init() }; reta_size.div_ceil(group_size) as usize];
+        for index in 0..reta_size {
+            let entry: &mut rte_eth_rss_reta_entry64 = &mut reta_conf[(index / group_size) as usize];
+            entry.mask |= 1 << (index % group_size);
+            entry.reta[(index % group_size) as usize] = flow_steering.reta_entry(index);
+        }
+        let ret: libc::c_int = unsafe { rte_eth_dev_rss_reta_update(port_id, reta_conf.as_mut_ptr(), reta_size) };
+        if ret != 0 {
+            let cause: String = format!("failed to update redirection table (ret={:?})", ret);
+            error!("initialize_dpdk_reta(): {}", cause);
+            return Err(Fail::new(libc::EIO, &cause));
+        }
         Ok(())
     }
 
@@ -374,6 +568,36 @@ impl SharedDPDKRuntime {
     pub fn get_ip_addr(&self) -> Ipv4Addr {
         self.ipv4_addr
     }
+
+    /// Returns the flow steering of the queue that this runtime owns, if the port spreads flows over several queues.
+    pub fn get_flow_steering(&self) -> Option<FlowSteering> {
+        self.flow_steering.clone()
+    }
+
+    /// Returns the handle through which the queue that this runtime owns hands frames to the other queues of the port,
+    /// if it has several.
+    pub fn get_frame_replication(&self) -> Option<FrameReplication> {
+        self.frame_replication.clone()
+    }
+
+    /// Programs the multicast filter of the port with [multicast_link_addrs], which replaces the previous list.
+    fn set_multicast_link_addrs(&mut self, multicast_link_addrs: Vec<MacAddress>) -> Result<(), Fail> {
+        let mut mc_addr_set: Vec<rte_ether_addr> = multicast_link_addrs
+            .iter()
+            .map(|link_addr: &MacAddress| rte_ether_addr {
+                addr_bytes: link_addr.to_array(),
+            })
+            .collect();
+        let ret: libc::c_int =
+            unsafe { rte_eth_dev_set_mc_addr_list(self.port_id, mc_addr_set.as_mut_ptr(), mc_addr_set.len() as u32) };
+        if ret != 0 {
+            let cause: String = format!("failed to set multicast addresses (ret={:?})", ret);
+            error!("set_multicast_link_addrs(): {}", cause);
+            return Err(Fail::new(libc::EIO, &cause));
+        }
+        self.multicast_link_addrs = multicast_link_addrs;
+        Ok(())
+    }
 }
 
 //======================================================================================================================
@@ -400,20 +624,32 @@ impl PhysicalLayer for SharedDPDKRuntime {
         timer!("catnip::runtime::transmit");
         // Grab the packet and copy it if necessary. In general, this copy will happen for small packets without
         // payloads because we allocate actual data-carrying application buffers from the DPDK pool.
+        // Heap-allocated chains are copied into a chain of mbufs, as the NIC transmits multi-segment packets.
         let outgoing_pkt: DemiBuffer = match pkt {
             buf if buf.is_dpdk_allocated() => buf,
             buf => {
-                let mut mbuf: DemiBuffer = self.mm.alloc_body_mbuf().expect("should be able to allocate mbuf");
-                debug_assert!(buf.len() < mbuf.len());
-                mbuf.trim(mbuf.len() - buf.len()).expect("Should be able to trim");
-                mbuf.copy_from_slice(&buf);
+                let mut outgoing_pkt: Option<DemiBuffer> = None;
+                for segment in buf.segments() {
+                    let mut remaining: &[u8] = segment;
+                    while !remaining.is_empty() {
+                        let mut mbuf: DemiBuffer = self.mm.alloc_body_mbuf().expect("should be able to allocate mbuf");
+                        let chunk_len: usize = remaining.len().min(mbuf.len());
+                        mbuf.trim(mbuf.len() - chunk_len).expect("Should be able to trim");
+                        mbuf.copy_from_slice(&remaining[..chunk_len]);
+                        remaining = &remaining[chunk_len..];
+                        match outgoing_pkt {
+                            Some(ref mut outgoing_pkt) => outgoing_pkt.append(mbuf)?,
+                            None => outgoing_pkt = Some(mbuf),
+                        }
+                    }
+                }
 
-                mbuf
+                expect_some!(outgoing_pkt, "packet cannot be empty")
             },
         };
 
         let mut mbuf_ptr: *mut rte_mbuf = expect_some!(outgoing_pkt.into_mbuf(), "mbuf cannot be empty");
-        let num_sent: u16 = unsafe { rte_eth_tx_burst(self.port_id, 0, &mut mbuf_ptr, 1) };
+        let num_sent: u16 = unsafe { rte_eth_tx_burst(self.port_id, self.queue_id, &mut mbuf_ptr, 1) };
         debug_assert_eq!(num_sent, 1);
         Ok(())
     }
@@ -423,17 +659,56 @@ impl PhysicalLayer for SharedDPDKRuntime {
 
         let mut out = ArrayVec::new();
         let mut packets: [*mut rte_mbuf; RECEIVE_BATCH_SIZE] = unsafe { mem::zeroed() };
-        let nb_rx = unsafe { rte_eth_rx_burst(self.port_id, 0, packets.as_mut_ptr(), RECEIVE_BATCH_SIZE as u16) };
+        let nb_rx = unsafe {
+            rte_eth_rx_burst(
+                self.port_id,
+                self.queue_id,
+                packets.as_mut_ptr(),
+                RECEIVE_BATCH_SIZE as u16,
+            )
+        };
         assert!(nb_rx as usize <= RECEIVE_BATCH_SIZE);
 
         {
             for &packet in &packets[..nb_rx as usize] {
                 // Safety: `packet` is a valid pointer to a properly initialized `rte_mbuf` struct.
                 let buf: DemiBuffer = unsafe { DemiBuffer::from_mbuf(packet) };
+                if let (Some(flow_steering), Some(frame_replication)) = (&self.flow_steering, &self.frame_replication) {
+                    // ICMP errors belong to the queue of the flow that they report on.
+                    if let Some(queue_id) = flow_steering.queue_for_icmp_error(&buf) {
+                        if queue_id != self.queue_id {
+                            frame_replication.forward(queue_id, &buf);
+                            continue;
+                        }
+                    }
+                    frame_replication.replicate(&buf);
+                }
                 out.push(buf);
             }
         }
 
+        // Receive the frames that the other queues handed to this one, as far as the batch has room.
+        if let Some(ref frame_replication) = self.frame_replication {
+            while !out.is_full() {
+                match frame_replication.try_take() {
+                    Some(frame) => out.push(DemiBuffer::from_slice(&frame)?),
+                    None => break,
+                }
+            }
+        }
+
         Ok(out)
     }
+
+    fn join_multicast_group(&mut self, link_addr: MacAddress) -> Result<(), Fail> {
+        let mut multicast_link_addrs: Vec<MacAddress> = self.multicast_link_addrs.clone();
+        multicast_link_addrs.push(link_addr);
+        self.set_multicast_link_addrs(multicast_link_addrs)
+    }
+
+    fn leave_multicast_group(&mut self, link_addr: MacAddress) -> Result<(), Fail> {
+        let mut multicast_link_addrs: Vec<MacAddress> = self.multicast_link_addrs.clone();
+        multicast_link_addrs.retain(|other: &MacAddress| *other != link_addr);
+        self.set_multicast_link_addrs(multicast_link_addrs)
+    }
 }
@@ -13,7 +13,6 @@ use crate::{
         RawSocketAddr,
     },
     demi_sgarray_t,
-    demi_sgaseg_t,
     demikernel::config::Config,
     expect_ok,
     inetstack::protocols::{
@@ -25,6 +24,8 @@ use crate::{
         fail::Fail,
         limits,
         memory::{
+            alloc_buffer_chain,
+            sgarray_from_buffer,
             DemiBuffer,
             MemoryRuntime,
         },
@@ -37,7 +38,6 @@ use crate::{
     },
 };
 use ::arrayvec::ArrayVec;
-use ::libc::c_void;
 use ::std::{
     fs,
     mem::{
@@ -70,7 +70,11 @@ impl LinuxRuntime {
     /// Instantiates a Linux Runtime.
     pub fn new(config: &Config) -> Result<Self, Fail> {
         let mac_addr: [u8; 6] = [0; 6];
-        let ifindex: i32 = match Self::get_ifindex(&config.local_interface_name()?) {
+        let ifindex: i32 = match Self::get_ifindex(
+            &config
+                .local_interface_name()
+                .ok_or_else(|| Config::missing_option("linux_interface_name"))?,
+        ) {
             Ok(ifindex) => ifindex,
             Err(_) => return Err(Fail::new(libc::EINVAL, "could not parse ifindex")),
         };
@@ -79,8 +83,12 @@ impl LinuxRuntime {
         socket.bind(&sockaddr)?;
 
         Ok(Self {
-            link_addr: config.local_link_addr()?,
-            ipv4_addr: config.local_ipv4_addr()?,
+            link_addr: config
+                .local_link_addr()
+                .ok_or_else(|| Config::missing_option("local_link_addr"))?,
+            ipv4_addr: config
+                .local_ipv4_addr()
+                .ok_or_else(|| Config::missing_option("local_ipv4_addr"))?,
             ifindex,
             socket: SharedObject::<RawSocket>::new(socket),
         })
@@ -111,38 +119,12 @@ impl LinuxRuntime {
 impl MemoryRuntime for LinuxRuntime {
     /// Allocates a scatter-gather array.
     fn sgaalloc(&self, size: usize) -> Result<demi_sgarray_t, Fail> {
-        // TODO: Allocate an array of buffers if requested size is too large for a single buffer.
-
-        // We can't allocate a zero-sized buffer.
-        if size == 0 {
-            let cause: String = format!("cannot allocate a zero-sized buffer");
-            error!("sgaalloc(): {}", cause);
-            return Err(Fail::new(libc::EINVAL, &cause));
-        }
-
-        // We can't allocate more than a single buffer.
-        if size > u16::MAX as usize {
-            return Err(Fail::new(libc::EINVAL, "size too large for a single demi_sgaseg_t"));
-        }
-
-        // First allocate the underlying DemiBuffer.
         // Always allocate with header space for now even if we do not need it.
-        let buf: DemiBuffer = DemiBuffer::new_with_headroom(size as u16, MAX_HEADER_SIZE as u16);
-
-        // Create a scatter-gather segment to expose the DemiBuffer to the user.
-        let data: *const u8 = buf.as_ptr();
-        let sga_seg: demi_sgaseg_t = demi_sgaseg_t {
-            sgaseg_buf: data as *mut c_void,
-            sgaseg_len: size as u32,
-        };
-
-        // Create and return a new scatter-gather array (which inherits the DemiBuffer's reference).
-        Ok(demi_sgarray_t {
-            sga_buf: buf.into_raw().as_ptr() as *mut c_void,
-            sga_numsegs: 1,
-            sga_segs: [sga_seg],
-            sga_addr: unsafe { mem::zeroed() },
-        })
+        let max_segment_size: usize = u16::MAX as usize - MAX_HEADER_SIZE;
+        let buf: DemiBuffer = alloc_buffer_chain(size, max_segment_size, |segment_size: u16| {
+            Ok(DemiBuffer::new_with_headroom(segment_size, MAX_HEADER_SIZE as u16))
+        })?;
+        sgarray_from_buffer(buf)
     }
 }
 
@@ -153,6 +135,9 @@ impl Runtime for LinuxRuntime {}
 impl PhysicalLayer for LinuxRuntime {
     /// Transmits a single [PacketBuf].
     fn transmit(&mut self, pkt: DemiBuffer) -> Result<(), Fail> {
+        // The raw socket takes a contiguous frame.
+        let pkt: DemiBuffer = pkt.linearize()?;
+
         // We clone the packet so as to not remove the ethernet header from the outgoing message.
         let header = Ethernet2Header::parse_and_strip(&mut pkt.clone()).unwrap();
         let dest_addr_arr: [u8; 6] = header.dst_addr().to_array();
@@ -204,4 +189,16 @@ impl PhysicalLayer for LinuxRuntime {
             Ok(ArrayVec::new())
         }
     }
+
+    /// Joins the multicast group of [link_addr] on the interface.
+    fn join_multicast_group(&mut self, link_addr: MacAddress) -> Result<(), Fail> {
+        self.socket
+            .set_multicast_membership(self.ifindex, &link_addr.to_array(), true)
+    }
+
+    /// Leaves the multicast group of [link_addr] on the interface.
+    fn leave_multicast_group(&mut self, link_addr: MacAddress) -> Result<(), Fail> {
+        self.socket
+            .set_multicast_membership(self.ifindex, &link_addr.to_array(), false)
+    }
 }
@@ -62,6 +62,42 @@ impl RawSocket {
         Ok(())
     }
 
+    /// Adds ([add] is true) or drops a membership of the interface with index [ifindex] in the multicast group of
+    /// [mac_addr], so that the interface receives frames that are sent to it.
+    pub fn set_multicast_membership(&self, ifindex: i32, mac_addr: &[u8; 6], add: bool) -> Result<(), Fail> {
+        // Pad MAC address.
+        let mut addr: [u8; 8] = [0_u8; 8];
+        addr[..6].copy_from_slice(mac_addr);
+        let mreq: libc::packet_mreq = libc::packet_mreq {
+            mr_ifindex: ifindex,
+            mr_type: libc::PACKET_MR_MULTICAST as u16,
+            mr_alen: libc::ETH_ALEN as u16,
+            mr_address: addr,
+        };
+        let optname: i32 = if add {
+            libc::PACKET_ADD_MEMBERSHIP
+        } else {
+            libc::PACKET_DROP_MEMBERSHIP
+        };
+
+        let ret: i32 = unsafe {
+            libc::setsockopt(
+                self.0,
+                libc::SOL_PACKET,
+                optname,
+                &mreq as *const libc::packet_mreq as *const libc::c_void,
+                mem::size_of::<libc::packet_mreq>() as Socklen,
+            )
+        };
+
+        // Check if we failed to change the membership of the underlying raw socket.
+        if ret == -1 {
+            return Err(Fail::new(libc::EAGAIN, "failed to set multicast membership"));
+        }
+
+        Ok(())
+    }
+
     /// Sends data through a raw socket.
     pub fn sendto(&self, buf: &[u8], rawaddr: &RawSocketAddr) -> Result<usize, Fail> {
         let buf_len: usize = buf.len();
@@ -15,7 +15,6 @@ use crate::{
         },
     },
     demi_sgarray_t,
-    demi_sgaseg_t,
     demikernel::config::Config,
     inetstack::protocols::{
         layer1::PhysicalLayer,
@@ -24,6 +23,8 @@ use crate::{
     runtime::{
         fail::Fail,
         memory::{
+            alloc_buffer_chain,
+            sgarray_from_buffer,
             DemiBuffer,
             MemoryRuntime,
         },
@@ -33,13 +34,9 @@ use crate::{
     },
 };
 use ::arrayvec::ArrayVec;
-use ::libc::c_void;
-use ::std::{
-    borrow::{
-        Borrow,
-        BorrowMut,
-    },
-    mem,
+use ::std::borrow::{
+    Borrow,
+    BorrowMut,
 };
 
 //======================================================================================================================
@@ -70,7 +67,9 @@ impl SharedCatpowderRuntime {
 
     /// Instantiates a new XDP runtime.
     pub fn new(config: &Config) -> Result<Self, Fail> {
-        let ifindex: u32 = config.local_interface_index()?;
+        let ifindex: u32 = config
+            .local_interface_index()
+            .ok_or_else(|| Config::missing_option("xdp_interface_index"))?;
         const QUEUEID: u32 = 0; // We do no use RSS, thus queue id is always 0.
 
         trace!("Creating XDP runtime.");
@@ -87,7 +86,7 @@ impl SharedCatpowderRuntime {
 impl PhysicalLayer for SharedCatpowderRuntime {
     /// Transmits a packet.
     fn transmit(&mut self, pkt: DemiBuffer) -> Result<(), Fail> {
-        let pkt_size: usize = pkt.len();
+        let pkt_size: usize = pkt.total_len();
         trace!("transmit(): pkt_size={:?}", pkt_size);
         if pkt_size >= u16::MAX as usize {
             let cause = format!("packet is too large: {:?}", pkt_size);
@@ -95,6 +94,9 @@ impl PhysicalLayer for SharedCatpowderRuntime {
             return Err(Fail::new(libc::ENOTSUP, &cause));
         }
 
+        // The transmit ring takes a contiguous frame.
+        let pkt: DemiBuffer = pkt.linearize()?;
+
         let mut idx: u32 = 0;
 
         if self.0.borrow_mut().tx.reserve_tx(Self::RING_LENGTH, &mut idx) != Self::RING_LENGTH {
@@ -174,38 +176,12 @@ impl CatpowderRuntimeInner {
 impl MemoryRuntime for SharedCatpowderRuntime {
     /// Allocates a scatter-gather array.
     fn sgaalloc(&self, size: usize) -> Result<demi_sgarray_t, Fail> {
-        // TODO: Allocate an array of buffers if requested size is too large for a single buffer.
-
-        // We can't allocate a zero-sized buffer.
-        if size == 0 {
-            let cause: String = format!("cannot allocate a zero-sized buffer");
-            error!("sgaalloc(): {}", cause);
-            return Err(Fail::new(libc::EINVAL, &cause));
-        }
-
-        // We can't allocate more than a single buffer.
-        if size > u16::MAX as usize {
-            return Err(Fail::new(libc::EINVAL, "size too large for a single demi_sgaseg_t"));
-        }
-
-        // First allocate the underlying DemiBuffer.
         // Always allocate with header space for now even if we do not need it.
-        let buf: DemiBuffer = DemiBuffer::new_with_headroom(size as u16, MAX_HEADER_SIZE as u16);
-
-        // Create a scatter-gather segment to expose the DemiBuffer to the user.
-        let data: *const u8 = buf.as_ptr();
-        let sga_seg: demi_sgaseg_t = demi_sgaseg_t {
-            sgaseg_buf: data as *mut c_void,
-            sgaseg_len: size as u32,
-        };
-
-        // Create and return a new scatter-gather array (which inherits the DemiBuffer's reference).
-        Ok(demi_sgarray_t {
-            sga_buf: buf.into_raw().as_ptr() as *mut c_void,
-            sga_numsegs: 1,
-            sga_segs: [sga_seg],
-            sga_addr: unsafe { mem::zeroed() },
-        })
+        let max_segment_size: usize = u16::MAX as usize - MAX_HEADER_SIZE;
+        let buf: DemiBuffer = alloc_buffer_chain(size, max_segment_size, |segment_size: u16| {
+            Ok(DemiBuffer::new_with_headroom(segment_size, MAX_HEADER_SIZE as u16))
+        })?;
+        sgarray_from_buffer(buf)
     }
 }
 
@@ -91,6 +91,11 @@ impl<T> AsyncQueue<T> {
         self.queue.pop_front()
     }
 
+    /// Remove the items for which [f] returns false.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, f: F) {
+        self.queue.retain(f)
+    }
+
     /// Get the length of the queue.
     pub fn len(&self) -> usize {
         self.queue.len()
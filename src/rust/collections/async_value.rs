@@ -93,6 +93,11 @@ impl<T: Clone> SharedAsyncValue<T> {
     pub fn new(value: T) -> Self {
         Self(SharedObject::<AsyncValue<T>>::new(AsyncValue::<T>::new(value)))
     }
+
+    /// Returns true if both references share the same value.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        self.0.ptr_eq(&other.0)
+    }
 }
 
 //======================================================================================================================
@@ -6,10 +6,7 @@
 //======================================================================================================================
 
 use crate::{
-    collections::{
-        raw_array,
-        ring::Ring,
-    },
+    collections::raw_array,
     runtime::{
         fail::Fail,
         DemiRuntime,
@@ -31,6 +28,9 @@ use ::std::{
 
 use crate::timer;
 
+#[cfg(feature = "catmem-libos")]
+use crate::collections::ring::Ring;
+
 //======================================================================================================================
 // Constants
 //======================================================================================================================
@@ -63,9 +63,9 @@ const HEADER_SIZE: usize = 2;
 /// 5. If the message is valid (i.e., the message header is non-zero), it must exist between the push_offset and
 ///    pop_offset. As a result, the pop_offset can never overtake the push_offset.
 /// 6. If the length header is zero, the data in the buffer may be valid but if it is valid, it is locked and should
-/// not be modified or read.
+///    not be modified or read.
 /// 7. If the 16 bytes pointed to by pop_offset are zero, then there is either another ongoing pop or there is no valid
-/// data in the buffer.
+///    data in the buffer.
 pub struct ConcurrentRingBuffer {
     // Indexes the first empty byte where buffers can be enqueued.
     push_offset: *mut usize,
@@ -258,7 +258,9 @@ impl ConcurrentRingBuffer {
         let buffer_ptr: *mut u8 = unsafe { self.buffer.get_mut() }.as_mut_ptr();
         let header_ptr: *mut u16 = unsafe { buffer_ptr.add(offset) } as *mut u16;
         let header: &AtomicU16 = unsafe { &*header_ptr.cast() };
-        header.swap(val as u16, atomic::Ordering::Relaxed) as usize
+        // The header publishes the payload to other threads, so writes to the payload must not be reordered after it
+        // and reads from the payload must not be reordered before it.
+        header.swap(val as u16, atomic::Ordering::AcqRel) as usize
     }
 
     /// Given a [push_offset] and [pop_offset] into the ring buffer, return available space for writing data. Always
@@ -333,6 +335,7 @@ impl ConcurrentRingBuffer {
     }
 }
 
+#[cfg(feature = "catmem-libos")]
 impl Ring for ConcurrentRingBuffer {
     /// Constructs a ring buffer from raw parts. [size] indicates the size of the raw parts, while [capacity] indicates
     /// the amount of storage space in bytes that should be available. [size] must be at least large enough to hold
@@ -405,7 +408,7 @@ fn peek(ptr: *mut usize) -> usize {
 /// Compares and increments the value at [ptr] only if it has not changed since the last time we read it.
 fn check_and_set(ptr: *mut usize, current: usize, new: usize) -> Result<usize, usize> {
     let ptr: &AtomicUsize = unsafe { &*ptr.cast() };
-    ptr.compare_exchange_weak(current, new, atomic::Ordering::Acquire, atomic::Ordering::Relaxed)
+    ptr.compare_exchange_weak(current, new, atomic::Ordering::AcqRel, atomic::Ordering::Relaxed)
 }
 
 /// Align to [HEADER_SIZE] for the header offset.
@@ -449,12 +452,14 @@ impl Drop for ConcurrentRingBuffer {
 
 #[cfg(test)]
 mod test {
+    #[cfg(feature = "catmem-libos")]
+    use crate::collections::concurrent_ring::Ring;
     use crate::collections::concurrent_ring::{
         ConcurrentRingBuffer,
-        Ring,
         HEADER_SIZE,
     };
     use ::anyhow::Result;
+    #[cfg(feature = "catmem-libos")]
     use ::core::mem;
     use ::std::thread;
     use std::{
@@ -484,6 +489,7 @@ mod test {
     }
 
     /// Constructs a ring buffer from raw parts.
+    #[cfg(feature = "catmem-libos")]
     fn do_from_raw(ptr: *mut u8, size: usize) -> Result<ConcurrentRingBuffer> {
         let ring: ConcurrentRingBuffer = match ConcurrentRingBuffer::from_raw_parts(true, ptr, size) {
             Ok(ring) => ring,
@@ -604,6 +610,7 @@ mod test {
 
     /// Tests if we succeed to construct a ring buffer from raw parts.
     #[test]
+    #[cfg(feature = "catmem-libos")]
     fn from_raw_parts() -> Result<()> {
         const SIZE: usize = RING_BUFFER_CAPACITY + 2 * mem::size_of::<usize>();
         let mut array: [u8; SIZE] = [0; SIZE];
@@ -756,6 +763,7 @@ mod test {
 
     /// Tets if we succeed to sequentially enqueue and dequeue elements to/from a constructed ring buffer.
     #[test]
+    #[cfg(feature = "catmem-libos")]
     fn enqueue_dequeue_sequential_raw() -> Result<()> {
         const LENGTH: usize = RING_BUFFER_CAPACITY + 2 * mem::size_of::<usize>();
         const SIZE: usize = LENGTH * mem::size_of::<u8>();
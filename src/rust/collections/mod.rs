@@ -3,16 +3,17 @@
 
 pub mod async_queue;
 pub mod async_value;
+pub mod concurrent_ring;
 pub mod hashttlcache;
 pub mod id_map;
 pub mod intrusive;
 pub mod pin_slab;
+pub mod raw_array;
+pub mod timing_wheel;
 
 cfg_if! {
     if #[cfg(feature = "catmem-libos")] {
-        pub mod raw_array;
         pub mod ring;
         pub mod shared_ring;
-        pub mod concurrent_ring;
     }
 }
@@ -89,6 +89,7 @@ impl<T> RawArray<T> {
     }
 
     /// Gets a mutable slice to the underlying data in the target raw array.
+    #[allow(clippy::mut_from_ref)]
     pub unsafe fn get_mut(&self) -> &mut [T] {
         timer!("collections::raw_array::get_mut");
         slice::from_raw_parts_mut(self.ptr.as_ptr(), self.cap)
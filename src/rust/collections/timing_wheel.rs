@@ -0,0 +1,544 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! This module implements a hierarchical timing wheel (see Varghese and Lauck, "Hashed and Hierarchical Timing Wheels",
+//! SOSP '87). Inserting and removing a timeout takes constant time, which matters because every TCP connection arms and
+//! cancels retransmission and delayed-ACK timeouts all the time.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use ::std::time::{
+    Duration,
+    Instant,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Granularity of the wheel. Timeouts never fire early, but they may be examined up to one tick before they expire.
+const TICK: Duration = Duration::from_millis(1);
+
+/// Number of slots per level, as a power of two. With 64 slots, the occupancy of a level fits in a [u64].
+const SLOTS_PER_LEVEL_LOG2: u32 = 6;
+const SLOTS_PER_LEVEL: usize = 1 << SLOTS_PER_LEVEL_LOG2;
+
+/// Number of levels. Each level covers 64 times the range of the level below, so six levels cover 2^36 ticks (about
+/// two years). Timeouts that are further away are parked in the top level and re-examined when it rotates.
+const NUM_LEVELS: usize = 6;
+
+/// Maximum number of ticks that a timeout may be away from the current time of the wheel.
+const MAX_TICKS: u64 = 1 << (SLOTS_PER_LEVEL_LOG2 * NUM_LEVELS as u32);
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Handle to a timeout, which is used to remove it from the wheel. Handles of timeouts that already fired or were
+/// removed are stale and are ignored.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TimerKey {
+    index: usize,
+    generation: u64,
+}
+
+/// A timeout in the wheel. Entries are linked in a doubly-linked list per slot, so that they can be unlinked in
+/// constant time.
+struct Entry<T> {
+    /// The time out.
+    expiry: Instant,
+    /// The value of the timeout, or [None] if this entry is free.
+    value: Option<T>,
+    /// Generation of the entry, which tells apart the timeouts that reuse this entry.
+    generation: u64,
+    /// Level and slot of the list that this entry is linked in.
+    level: usize,
+    slot: usize,
+    /// Neighbors in the list.
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
+/// A level of the wheel.
+struct Level {
+    /// Heads of the lists of entries in each slot.
+    slots: [Option<usize>; SLOTS_PER_LEVEL],
+    /// Bitmap of the slots that have entries.
+    occupied: u64,
+}
+
+/// Hierarchical timing wheel that holds values until their timeout expires.
+pub struct TimingWheel<T> {
+    /// Instant that corresponds to tick zero.
+    origin: Instant,
+    /// Number of ticks that the wheel has been advanced to.
+    elapsed: u64,
+    /// Levels of the wheel, from the finest to the coarsest.
+    levels: Vec<Level>,
+    /// Storage of entries.
+    entries: Vec<Entry<T>>,
+    /// Indexes of the free entries.
+    free: Vec<usize>,
+    /// Generation to hand out to the next timeout.
+    next_generation: u64,
+    /// Number of timeouts in the wheel.
+    len: usize,
+    /// Timeouts that expired while advancing the wheel, kept around to reuse the allocation.
+    expired: Vec<(Instant, u64, T)>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl<T> TimingWheel<T> {
+    /// Creates an empty wheel whose time starts at [now].
+    pub fn new(now: Instant) -> Self {
+        Self {
+            origin: now,
+            elapsed: 0,
+            levels: (0..NUM_LEVELS).map(|_| Level::default()).collect(),
+            entries: Vec::new(),
+            free: Vec::new(),
+            next_generation: 0,
+            len: 0,
+            expired: Vec::new(),
+        }
+    }
+
+    /// Drops all timeouts and restarts the time of the wheel at [now].
+    pub fn reset(&mut self, now: Instant) {
+        for level in self.levels.iter_mut() {
+            *level = Level::default();
+        }
+        self.entries.clear();
+        self.free.clear();
+        self.len = 0;
+        self.origin = now;
+        self.elapsed = 0;
+    }
+
+    /// Returns the number of timeouts in the wheel.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Queries whether there are no timeouts in the wheel.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts [value] with a timeout that expires at [expiry] and returns a handle to remove it.
+    pub fn insert(&mut self, expiry: Instant, value: T) -> TimerKey {
+        let generation: u64 = self.next_generation;
+        self.next_generation += 1;
+        let entry: Entry<T> = Entry {
+            expiry,
+            value: Some(value),
+            generation,
+            level: 0,
+            slot: 0,
+            prev: None,
+            next: None,
+        };
+        let index: usize = match self.free.pop() {
+            Some(index) => {
+                self.entries[index] = entry;
+                index
+            },
+            None => {
+                self.entries.push(entry);
+                self.entries.len() - 1
+            },
+        };
+        self.link(index);
+        self.len += 1;
+        TimerKey { index, generation }
+    }
+
+    /// Removes the timeout referred to by [key] and returns its value, if it has not fired yet.
+    pub fn remove(&mut self, key: TimerKey) -> Option<T> {
+        match self.entries.get(key.index) {
+            Some(entry) if entry.generation == key.generation && entry.value.is_some() => (),
+            _ => return None,
+        }
+        self.unlink(key.index);
+        self.release(key.index)
+    }
+
+    /// Moves the time of the wheel forward to [now] and calls [fire] on the values of all timeouts that expired, in
+    /// order of expiry.
+    pub fn advance<F: FnMut(T)>(&mut self, now: Instant, mut fire: F) {
+        let target: u64 = self.ticks(now);
+        let mut not_expired: Vec<usize> = Vec::new();
+
+        // Visit occupied slots in order until we catch up with the target tick.
+        while let Some((level, slot, deadline)) = self.next_expiration() {
+            if deadline > target {
+                break;
+            }
+            self.elapsed = self.elapsed.max(deadline);
+            let mut head: Option<usize> = self.levels[level].slots[slot].take();
+            self.levels[level].occupied &= !(1 << slot);
+            while let Some(index) = head {
+                head = self.entries[index].next;
+                let expiry: Instant = self.entries[index].expiry;
+                if expiry <= now {
+                    let generation: u64 = self.entries[index].generation;
+                    if let Some(value) = self.release(index) {
+                        self.expired.push((expiry, generation, value));
+                    }
+                } else if self.ticks(expiry) <= target {
+                    // This timeout expires later within the current tick, so we look at it again next time.
+                    not_expired.push(index);
+                } else {
+                    // Cascade the timeout down to a finer level.
+                    self.link(index);
+                }
+            }
+        }
+        self.elapsed = self.elapsed.max(target);
+        for index in not_expired {
+            self.link(index);
+        }
+
+        // Fire expired timeouts in the order in which they were armed when they expire at the same time.
+        self.expired
+            .sort_unstable_by_key(|(expiry, generation, _): &(Instant, u64, T)| (*expiry, *generation));
+        for (_, _, value) in self.expired.drain(..) {
+            fire(value);
+        }
+    }
+
+    /// Returns a time by which the earliest timeout in the wheel expires, if there is any timeout. This is never later
+    /// than the expiry of that timeout, but it may be earlier for timeouts in coarse levels, which are only sorted out
+    /// when the wheel advances.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.next_expiration().map(|(_, _, deadline): (usize, usize, u64)| {
+            self.origin + Duration::from_nanos(deadline * TICK.as_nanos() as u64)
+        })
+    }
+
+    /// Converts [instant] into ticks since the origin of the wheel.
+    fn ticks(&self, instant: Instant) -> u64 {
+        (instant.saturating_duration_since(self.origin).as_nanos() / TICK.as_nanos()) as u64
+    }
+
+    /// Returns the level in which a timeout that expires at tick [when] goes.
+    fn level_for(&self, when: u64) -> usize {
+        // The level is given by the most significant digit in which the tick differs from the current time.
+        let masked: u64 = (self.elapsed ^ when) | (SLOTS_PER_LEVEL as u64 - 1);
+        let significant: u32 = u64::BITS - 1 - masked.leading_zeros();
+        ((significant / SLOTS_PER_LEVEL_LOG2) as usize).min(NUM_LEVELS - 1)
+    }
+
+    /// Links the entry at [index] in the slot that matches its timeout.
+    fn link(&mut self, index: usize) {
+        let when: u64 = self
+            .ticks(self.entries[index].expiry)
+            .clamp(self.elapsed, self.elapsed + MAX_TICKS - 1);
+        let level: usize = self.level_for(when);
+        let slot: usize = ((when >> (SLOTS_PER_LEVEL_LOG2 * level as u32)) as usize) & (SLOTS_PER_LEVEL - 1);
+        let head: Option<usize> = self.levels[level].slots[slot];
+        if let Some(head) = head {
+            self.entries[head].prev = Some(index);
+        }
+        let entry: &mut Entry<T> = &mut self.entries[index];
+        entry.level = level;
+        entry.slot = slot;
+        entry.prev = None;
+        entry.next = head;
+        self.levels[level].slots[slot] = Some(index);
+        self.levels[level].occupied |= 1 << slot;
+    }
+
+    /// Unlinks the entry at [index] from its slot.
+    fn unlink(&mut self, index: usize) {
+        let (level, slot, prev, next): (usize, usize, Option<usize>, Option<usize>) = {
+            let entry: &Entry<T> = &self.entries[index];
+            (entry.level, entry.slot, entry.prev, entry.next)
+        };
+        match prev {
+            Some(prev) => self.entries[prev].next = next,
+            None => self.levels[level].slots[slot] = next,
+        }
+        if let Some(next) = next {
+            self.entries[next].prev = prev;
+        }
+        if self.levels[level].slots[slot].is_none() {
+            self.levels[level].occupied &= !(1 << slot);
+        }
+    }
+
+    /// Frees the entry at [index], which must not be linked, and returns its value.
+    fn release(&mut self, index: usize) -> Option<T> {
+        let value: Option<T> = self.entries[index].value.take();
+        if value.is_some() {
+            self.free.push(index);
+            self.len -= 1;
+        }
+        value
+    }
+
+    /// Returns the level, the slot and the starting tick of the next occupied slot.
+    fn next_expiration(&self) -> Option<(usize, usize, u64)> {
+        // Timeouts in finer levels always expire before those in coarser levels.
+        for (level, entries) in self.levels.iter().enumerate() {
+            if entries.occupied == 0 {
+                continue;
+            }
+            let slot_range: u64 = 1 << (SLOTS_PER_LEVEL_LOG2 * level as u32);
+            let level_range: u64 = slot_range << SLOTS_PER_LEVEL_LOG2;
+            let now_slot: u32 = ((self.elapsed / slot_range) % SLOTS_PER_LEVEL as u64) as u32;
+            let slot: usize =
+                ((entries.occupied.rotate_right(now_slot).trailing_zeros() + now_slot) as usize) % SLOTS_PER_LEVEL;
+            let mut deadline: u64 = (self.elapsed / level_range) * level_range + slot as u64 * slot_range;
+            if deadline < self.elapsed {
+                // The top level wraps around, so a slot behind the current one is in the next rotation.
+                deadline += level_range;
+            }
+            return Some((level, slot, deadline));
+        }
+        None
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Default for Level {
+    fn default() -> Self {
+        Self {
+            slots: [None; SLOTS_PER_LEVEL],
+            occupied: 0,
+        }
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::collections::timing_wheel::{
+        TimerKey,
+        TimingWheel,
+    };
+    use ::anyhow::Result;
+    use ::std::{
+        cmp::Reverse,
+        collections::BinaryHeap,
+        time::{
+            Duration,
+            Instant,
+        },
+    };
+    use ::test::{
+        black_box,
+        Bencher,
+    };
+
+    /// Number of outstanding timeouts in benchmarks, which stands for the retransmission timeouts of open connections.
+    const BENCH_NUM_TIMEOUTS: usize = 10_000;
+
+    /// Advances [wheel] to [now] and returns the values that fired.
+    fn advance(wheel: &mut TimingWheel<usize>, now: Instant) -> Vec<usize> {
+        let mut fired: Vec<usize> = Vec::new();
+        wheel.advance(now, |value: usize| fired.push(value));
+        fired
+    }
+
+    #[test]
+    fn test_timing_wheel_fires_in_order() -> Result<()> {
+        let now: Instant = Instant::now();
+        let mut wheel: TimingWheel<usize> = TimingWheel::new(now);
+        wheel.insert(now + Duration::from_secs(3), 3);
+        wheel.insert(now + Duration::from_millis(1500), 1);
+        wheel.insert(now + Duration::from_secs(100), 4);
+        wheel.insert(now + Duration::from_secs(2), 2);
+        crate::ensure_eq!(wheel.len(), 4);
+
+        crate::ensure_eq!(advance(&mut wheel, now + Duration::from_secs(1)), vec![]);
+        crate::ensure_eq!(advance(&mut wheel, now + Duration::from_secs(5)), vec![1, 2, 3]);
+        crate::ensure_eq!(advance(&mut wheel, now + Duration::from_secs(99)), vec![]);
+        crate::ensure_eq!(advance(&mut wheel, now + Duration::from_secs(100)), vec![4]);
+        crate::ensure_eq!(wheel.is_empty(), true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_timing_wheel_never_fires_early() -> Result<()> {
+        let now: Instant = Instant::now();
+        let mut wheel: TimingWheel<usize> = TimingWheel::new(now);
+        let expiry: Instant = now + Duration::from_micros(2500);
+        wheel.insert(expiry, 1);
+
+        // Move time within the tick of the timeout, but before it.
+        crate::ensure_eq!(advance(&mut wheel, now + Duration::from_micros(2100)), vec![]);
+        crate::ensure_eq!(advance(&mut wheel, now + Duration::from_micros(2499)), vec![]);
+        crate::ensure_eq!(advance(&mut wheel, expiry), vec![1]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_timing_wheel_remove() -> Result<()> {
+        let now: Instant = Instant::now();
+        let mut wheel: TimingWheel<usize> = TimingWheel::new(now);
+        let key1: TimerKey = wheel.insert(now + Duration::from_millis(10), 1);
+        let key2: TimerKey = wheel.insert(now + Duration::from_millis(10), 2);
+        let key3: TimerKey = wheel.insert(now + Duration::from_secs(10), 3);
+
+        crate::ensure_eq!(wheel.remove(key1), Some(1));
+        crate::ensure_eq!(wheel.remove(key1), None);
+        crate::ensure_eq!(wheel.remove(key3), Some(3));
+        crate::ensure_eq!(advance(&mut wheel, now + Duration::from_secs(20)), vec![2]);
+
+        // Keys of timeouts that fired are stale, even if their entry is reused.
+        crate::ensure_eq!(wheel.remove(key2), None);
+        let key4: TimerKey = wheel.insert(now + Duration::from_secs(30), 4);
+        crate::ensure_eq!(wheel.remove(key2), None);
+        crate::ensure_eq!(wheel.remove(key4), Some(4));
+        crate::ensure_eq!(wheel.is_empty(), true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_timing_wheel_far_future() -> Result<()> {
+        let now: Instant = Instant::now();
+        let mut wheel: TimingWheel<usize> = TimingWheel::new(now);
+        let far: Instant = now + Duration::from_secs(5 * 365 * 24 * 3600);
+        wheel.insert(far, 1);
+
+        crate::ensure_eq!(
+            advance(&mut wheel, now + Duration::from_secs(3 * 365 * 24 * 3600)),
+            vec![]
+        );
+        crate::ensure_eq!(advance(&mut wheel, far - Duration::from_millis(1)), vec![]);
+        crate::ensure_eq!(advance(&mut wheel, far), vec![1]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_timing_wheel_next_deadline() -> Result<()> {
+        let now: Instant = Instant::now();
+        let mut wheel: TimingWheel<usize> = TimingWheel::new(now);
+        crate::ensure_eq!(wheel.next_deadline(), None);
+
+        // Deadlines of timeouts in the finest level are exact to the tick.
+        let key: TimerKey = wheel.insert(now + Duration::from_millis(20), 1);
+        crate::ensure_eq!(wheel.next_deadline(), Some(now + Duration::from_millis(20)));
+
+        // Deadlines of timeouts in coarser levels are never late, and get exact as the wheel advances.
+        wheel.insert(now + Duration::from_secs(10), 2);
+        wheel.remove(key);
+        let deadline: Option<Instant> = wheel.next_deadline();
+        crate::ensure_eq!(
+            deadline.is_some_and(|deadline: Instant| deadline <= now + Duration::from_secs(10)),
+            true
+        );
+        let mut fired: Vec<usize> = Vec::new();
+        while let Some(deadline) = wheel.next_deadline() {
+            fired.extend(advance(&mut wheel, deadline));
+        }
+        crate::ensure_eq!(fired, vec![2]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_timing_wheel_reset() -> Result<()> {
+        let now: Instant = Instant::now();
+        let mut wheel: TimingWheel<usize> = TimingWheel::new(now);
+        let key: TimerKey = wheel.insert(now + Duration::from_millis(10), 1);
+        wheel.reset(now);
+        crate::ensure_eq!(wheel.is_empty(), true);
+        crate::ensure_eq!(wheel.remove(key), None);
+        crate::ensure_eq!(advance(&mut wheel, now + Duration::from_secs(1)), vec![]);
+
+        Ok(())
+    }
+
+    /// Arms and cancels a timeout while many others are outstanding, as TCP does on every segment.
+    #[bench]
+    fn bench_timing_wheel_insert_remove(b: &mut Bencher) {
+        let now: Instant = Instant::now();
+        let mut wheel: TimingWheel<usize> = TimingWheel::new(now);
+        for i in 0..BENCH_NUM_TIMEOUTS {
+            wheel.insert(now + Duration::from_millis(i as u64), i);
+        }
+
+        b.iter(|| {
+            let key: TimerKey = wheel.insert(now + Duration::from_millis(200), 0);
+            black_box(wheel.remove(key));
+        });
+    }
+
+    /// Same as [bench_timing_wheel_insert_remove], but with the binary heap that the timer used to have.
+    #[bench]
+    fn bench_binary_heap_insert_remove(b: &mut Bencher) {
+        let now: Instant = Instant::now();
+        let mut heap: BinaryHeap<Reverse<(Instant, usize)>> = BinaryHeap::new();
+        for i in 0..BENCH_NUM_TIMEOUTS {
+            heap.push(Reverse((now + Duration::from_millis(i as u64), i)));
+        }
+
+        b.iter(|| {
+            let id: usize = BENCH_NUM_TIMEOUTS;
+            heap.push(Reverse((now + Duration::from_millis(200), id)));
+            heap.retain(|entry: &Reverse<(Instant, usize)>| entry.0 .1 != id);
+            black_box(heap.len());
+        });
+    }
+
+    /// Moves time forward by a tick with many outstanding timeouts, rearming each one that fires.
+    #[bench]
+    fn bench_timing_wheel_advance(b: &mut Bencher) {
+        let mut now: Instant = Instant::now();
+        let mut wheel: TimingWheel<usize> = TimingWheel::new(now);
+        for i in 0..BENCH_NUM_TIMEOUTS {
+            wheel.insert(now + Duration::from_millis((i % 1000) as u64), i);
+        }
+
+        let mut fired: Vec<usize> = Vec::new();
+        b.iter(|| {
+            now += Duration::from_millis(1);
+            wheel.advance(now, |value: usize| fired.push(value));
+            for value in fired.drain(..) {
+                wheel.insert(now + Duration::from_secs(1), value);
+            }
+        });
+    }
+
+    /// Same as [bench_timing_wheel_advance], but with the binary heap that the timer used to have.
+    #[bench]
+    fn bench_binary_heap_advance(b: &mut Bencher) {
+        let mut now: Instant = Instant::now();
+        let mut heap: BinaryHeap<Reverse<(Instant, usize)>> = BinaryHeap::new();
+        for i in 0..BENCH_NUM_TIMEOUTS {
+            heap.push(Reverse((now + Duration::from_millis((i % 1000) as u64), i)));
+        }
+
+        let mut fired: Vec<usize> = Vec::new();
+        b.iter(|| {
+            now += Duration::from_millis(1);
+            while let Some(Reverse((expiry, value))) = heap.peek() {
+                if now < *expiry {
+                    break;
+                }
+                fired.push(*value);
+                heap.pop();
+            }
+            for value in fired.drain(..) {
+                heap.push(Reverse((now + Duration::from_secs(1), value)));
+            }
+        });
+    }
+}
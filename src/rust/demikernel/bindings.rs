@@ -6,16 +6,26 @@
 //======================================================================================================================
 
 use crate::{
-    demikernel::libos::{
-        name::LibOSName,
-        LibOS,
+    demikernel::{
+        config::Config,
+        libos::{
+            name::LibOSName,
+            LibOS,
+        },
     },
     pal::{
         constants::{
             AF_INET,
             AF_INET6,
+            SHUT_RD,
+            SHUT_RDWR,
+            SHUT_WR,
             SOL_SOCKET,
+            SO_BROADCAST,
             SO_LINGER,
+            SO_RCVBUF,
+            SO_REUSEADDR,
+            SO_SNDBUF,
         },
         data_structures::{
             self,
@@ -34,31 +44,38 @@ use crate::{
         types::{
             demi_args_t,
             demi_callback_t,
+            demi_instance_t,
             demi_qresult_t,
             demi_qtoken_t,
             demi_sgarray_t,
             demi_sgaseg_t,
+            DEMI_SGARRAY_MAXLEN,
         },
         QToken,
     },
     SocketOption,
 };
 use ::libc::{
+    c_char,
     c_int,
     c_void,
     sockaddr,
 };
 use ::socket2::SockAddr;
 use ::std::{
-    cell::RefCell,
+    cell::{
+        RefCell,
+        RefMut,
+    },
+    collections::HashMap,
     ffi::CStr,
     mem::{
         self,
         MaybeUninit,
     },
     net::{
+        Shutdown,
         SocketAddr,
-        SocketAddrV4,
     },
     ptr,
     slice,
@@ -71,7 +88,33 @@ use ::std::{
 
 thread_local! {
 /// Demikernel state.
-    static DEMIKERNEL: RefCell<Option<LibOS>> = RefCell::new(None);
+    static DEMIKERNEL: RefCell<Instances> = RefCell::new(Instances::default());
+}
+
+/// LibOS instances of a thread.
+#[derive(Default)]
+struct Instances {
+    /// LibOS instances, indexed by their handles.
+    libos: HashMap<demi_instance_t, LibOS>,
+    /// Handle of the instance that system calls are issued to.
+    current: Option<demi_instance_t>,
+    /// Handle to hand out to the next instance.
+    next: demi_instance_t,
+}
+
+impl Instances {
+    /// Takes ownership of [libos] and returns its handle.
+    fn insert(&mut self, libos: LibOS) -> demi_instance_t {
+        let instance: demi_instance_t = self.next;
+        self.next += 1;
+        self.libos.insert(instance, libos);
+        instance
+    }
+
+    /// Returns the instance that system calls are issued to.
+    fn current_mut(&mut self) -> Option<&mut LibOS> {
+        self.libos.get_mut(&self.current?)
+    }
 }
 
 //======================================================================================================================
@@ -84,13 +127,8 @@ pub extern "C" fn demi_init(args: *const demi_args_t) -> c_int {
     logging::initialize();
     trace!("demi_init()");
 
-    let libos_name: LibOSName = match LibOSName::from_env() {
-        Ok(libos_name) => libos_name.into(),
-        Err(e) => panic!("{:?}", e),
-    };
-
     // Check if demikernel has already been initialized and return
-    let ret: i32 = DEMIKERNEL.with(|demikernel| match *demikernel.borrow() {
+    let ret: i32 = DEMIKERNEL.with(|demikernel| match demikernel.borrow().current {
         Some(_) => libc::EEXIST,
         None => 0,
     });
@@ -99,30 +137,72 @@ pub extern "C" fn demi_init(args: *const demi_args_t) -> c_int {
         return ret;
     }
 
-    // Parse arguments.
-    let perf_callback: Option<demi_callback_t> = if args.is_null() {
-        None
-    } else {
-        let args: &demi_args_t = unsafe { &*args };
-        args.callback
-    };
-
-    // Create LibOS.
-    match LibOS::new(libos_name, perf_callback) {
+    match new_libos(args) {
         Ok(libos) => {
             DEMIKERNEL.with(move |demikernel| {
-                *demikernel.borrow_mut() = Some(libos);
+                let mut demikernel: RefMut<Instances> = demikernel.borrow_mut();
+                demikernel.current = Some(demikernel.insert(libos));
             });
         },
         Err(e) => {
             trace!("demi_init() failed: {:?}", e);
-            return -e.errno;
+            return e.errno;
+        },
+    };
+
+    0
+}
+
+//======================================================================================================================
+// init_instance
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_init_instance(instance_out: *mut demi_instance_t, args: *const demi_args_t) -> c_int {
+    logging::initialize();
+    trace!("demi_init_instance()");
+
+    // Check for invalid storage location.
+    if instance_out.is_null() {
+        warn!("demi_init_instance() instance_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    match new_libos(args) {
+        Ok(libos) => {
+            let instance: demi_instance_t = DEMIKERNEL.with(move |demikernel| demikernel.borrow_mut().insert(libos));
+            unsafe { *instance_out = instance };
+        },
+        Err(e) => {
+            trace!("demi_init_instance() failed: {:?}", e);
+            return e.errno;
         },
     };
 
     0
 }
 
+//======================================================================================================================
+// select_instance
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_select_instance(instance: demi_instance_t) -> c_int {
+    trace!("demi_select_instance() instance={:?}", instance);
+
+    DEMIKERNEL.with(|demikernel| match demikernel.try_borrow_mut() {
+        Ok(mut demikernel) if demikernel.libos.contains_key(&instance) => {
+            demikernel.current = Some(instance);
+            0
+        },
+        Ok(_) => {
+            warn!("demi_select_instance() invalid instance (instance={:?})", instance);
+            libc::EBADF
+        },
+        Err(_) => libc::EBUSY,
+    })
+}
+
 //======================================================================================================================
 // create
 //======================================================================================================================
@@ -416,6 +496,40 @@ pub extern "C" fn demi_close(qd: c_int) -> c_int {
     }
 }
 
+//======================================================================================================================
+// shutdown
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_shutdown(qd: c_int, how: c_int) -> c_int {
+    trace!("demi_shutdown()");
+
+    // Check inputs.
+    let how: Shutdown = match how {
+        SHUT_RD => Shutdown::Read,
+        SHUT_WR => Shutdown::Write,
+        SHUT_RDWR => Shutdown::Both,
+        _ => {
+            warn!("demi_shutdown(): invalid shutdown direction (how={:?})", how);
+            return libc::EINVAL;
+        },
+    };
+
+    // Issue shutdown operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.shutdown(qd.into(), how) {
+        Ok(..) => 0,
+        Err(e) => {
+            trace!("demi_shutdown() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
 //======================================================================================================================
 // pushto
 //======================================================================================================================
@@ -711,6 +825,30 @@ pub extern "C" fn demi_wait_next_n(
         Err(e) => e.errno,
     }
 }
+
+//======================================================================================================================
+// cancel
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_cancel(qt: demi_qtoken_t) -> c_int {
+    trace!("demi_cancel() {:?}", qt);
+
+    // Issue cancel operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.cancel(qt.into()) {
+        Ok(()) => 0,
+        Err(e) => {
+            trace!("demi_cancel() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
 //======================================================================================================================
 // sgaalloc
 //======================================================================================================================
@@ -726,7 +864,7 @@ pub extern "C" fn demi_sgaalloc(size: libc::size_t) -> demi_sgarray_t {
             sga_segs: [demi_sgaseg_t {
                 sgaseg_buf: ptr::null_mut() as *mut c_void,
                 sgaseg_len: 0,
-            }; 1],
+            }; DEMI_SGARRAY_MAXLEN],
             sga_addr: unsafe { mem::zeroed() },
         }
     };
@@ -783,11 +921,65 @@ pub extern "C" fn demi_sgafree(sga: *mut demi_sgarray_t) -> c_int {
 // getsockname
 //======================================================================================================================
 
-#[allow(unused)]
 #[no_mangle]
-pub extern "C" fn demi_getsockname(qd: c_int, saddr: *mut sockaddr, size: *mut Socklen) -> c_int {
-    // TODO: Implement this system call.
-    libc::ENOSYS
+pub extern "C" fn demi_getsockname(qd: c_int, addr: *mut data_structures::SockAddr, addrlen: *mut Socklen) -> c_int {
+    trace!("demi_getsockname()");
+
+    // Check for invalid storage locations.
+    if addr.is_null() {
+        warn!("demi_getsockname(): addr value is a null pointer");
+        return libc::EINVAL;
+    }
+
+    if addrlen.is_null() {
+        warn!("demi_getsockname(): addrlen value is a null pointer");
+        return libc::EINVAL;
+    }
+
+    let expected_len = mem::size_of::<data_structures::SockAddrIn>() as Socklen;
+
+    if unsafe { *addrlen != expected_len } {
+        warn!("demi_getsockname(): addrlen does not match size of SockAddrIn");
+        return libc::EINVAL;
+    }
+
+    // Issue sockname operation on socket.
+    let ret: Result<SocketAddr, Fail> = match do_syscall(|libos| libos.getsockname(qd.into())) {
+        Ok(result) => result,
+        Err(e) => {
+            trace!("demi_getsockname() failed: {:?}", e);
+            return e.errno;
+        },
+    };
+
+    match ret {
+        // The C API only has room for IPv4 addresses.
+        Ok(SocketAddr::V6(sockaddr)) => {
+            warn!("demi_getsockname(): cannot return IPv6 address (addr={:?})", sockaddr);
+            libc::EAFNOSUPPORT
+        },
+        Ok(SocketAddr::V4(sockaddr)) => {
+            let result: data_structures::SockAddr = socketaddrv4_to_sockaddr(&sockaddr);
+            let result_length: usize = mem::size_of::<data_structures::SockAddr>();
+            unsafe {
+                if (result_length as Socklen) < *addrlen {
+                    *addrlen = result_length as Socklen;
+                }
+
+                ptr::copy_nonoverlapping(
+                    &result as *const data_structures::SockAddr as *const c_void,
+                    addr as *mut c_void,
+                    *addrlen as usize,
+                );
+            }
+
+            0
+        },
+        Err(e) => {
+            trace!("demi_getsockname() failed: {:?}", e);
+            e.errno
+        },
+    }
 }
 
 //======================================================================================================================
@@ -830,8 +1022,32 @@ pub extern "C" fn demi_setsockopt(
                 _ => SocketOption::Linger(Some(Duration::from_secs(linger.l_linger as u64))),
             }
         },
+        SO_RCVBUF | SO_SNDBUF | SO_BROADCAST | SO_REUSEADDR => {
+            // Check for invalid storage locations.
+            if optval.is_null() {
+                error!("demi_setsockopt(): option value is a null pointer");
+                return libc::EINVAL;
+            }
+
+            if optlen as usize != mem::size_of::<c_int>() {
+                warn!("demi_setsockopt(): option len is incorrect");
+                return libc::EINVAL;
+            }
+
+            let value: c_int = unsafe { *(optval as *const c_int) };
+            match optname {
+                SO_RCVBUF | SO_SNDBUF if value < 0 => {
+                    warn!("demi_setsockopt(): buffer size is negative (value={:?})", value);
+                    return libc::EINVAL;
+                },
+                SO_RCVBUF => SocketOption::RecvBufSize(value as usize),
+                SO_SNDBUF => SocketOption::SendBufSize(value as usize),
+                SO_BROADCAST => SocketOption::Broadcast(value != 0),
+                _ => SocketOption::ReuseAddr(value != 0),
+            }
+        },
         _ => {
-            error!("demi_setsockopt(): only SO_LINGER is supported right now");
+            error!("demi_setsockopt(): unsupported socket option (optname={:?})", optname);
             return libc::ENOPROTOOPT;
         },
     };
@@ -876,8 +1092,12 @@ pub extern "C" fn demi_getsockopt(
 
     let opt: SocketOption = match optname {
         SO_LINGER => SocketOption::Linger(None),
+        SO_RCVBUF => SocketOption::RecvBufSize(0),
+        SO_SNDBUF => SocketOption::SendBufSize(0),
+        SO_BROADCAST => SocketOption::Broadcast(false),
+        SO_REUSEADDR => SocketOption::ReuseAddr(false),
         _ => {
-            error!("demi_getsockopt(): only SO_LINGER is supported right now");
+            error!("demi_getsockopt(): unsupported socket option (optname={:?})", optname);
             return libc::ENOPROTOOPT;
         },
     };
@@ -904,8 +1124,8 @@ pub extern "C" fn demi_getsockopt(
 
     match ret {
         Ok(option) => {
-            // Unpack the value based on the option. We only support linger right now.
-            match option {
+            // Unpack the value based on the option.
+            let value: c_int = match option {
                 SocketOption::Linger(linger) => {
                     let result: Linger = match linger {
                         Some(linger) => Linger {
@@ -927,13 +1147,24 @@ pub extern "C" fn demi_getsockopt(
                         ptr::copy(&result as *const Linger as *const c_void, optval, result_length);
                         *optlen = result_length as Socklen;
                     }
+                    return 0;
+                },
+                SocketOption::RecvBufSize(size) | SocketOption::SendBufSize(size) => {
+                    size.min(c_int::MAX as usize) as c_int
                 },
+                SocketOption::Broadcast(enabled) | SocketOption::ReuseAddr(enabled) => enabled as c_int,
                 _ => {
-                    let cause: String = format!("Only SO_LINGER is supported right now");
-                    error!("demi_setsockopt(): {}", cause);
+                    let cause: String = format!("unexpected option (option={:?})", option);
+                    error!("demi_getsockopt(): {}", cause);
                     return libc::EINVAL;
                 },
             };
+
+            let result_length: usize = mem::size_of::<c_int>();
+            unsafe {
+                ptr::copy(&value as *const c_int as *const c_void, optval, result_length);
+                *optlen = result_length as Socklen;
+            }
             0
         },
         Err(e) => {
@@ -970,7 +1201,7 @@ pub extern "C" fn demi_getpeername(qd: c_int, addr: *mut data_structures::SockAd
     }
 
     // Issue peername operation on socket.
-    let ret: Result<SocketAddrV4, Fail> = match do_syscall(|libos| libos.getpeername(qd.into())) {
+    let ret: Result<SocketAddr, Fail> = match do_syscall(|libos| libos.getpeername(qd.into())) {
         Ok(result) => result,
         Err(e) => {
             trace!("demi_getpeername() failed: {:?}", e);
@@ -979,7 +1210,12 @@ pub extern "C" fn demi_getpeername(qd: c_int, addr: *mut data_structures::SockAd
     };
 
     match ret {
-        Ok(sockaddr) => {
+        // The C API only has room for IPv4 addresses.
+        Ok(SocketAddr::V6(sockaddr)) => {
+            warn!("demi_getpeername(): cannot return IPv6 address (addr={:?})", sockaddr);
+            libc::EAFNOSUPPORT
+        },
+        Ok(SocketAddr::V4(sockaddr)) => {
             let result: data_structures::SockAddr = socketaddrv4_to_sockaddr(&sockaddr);
             let result_length: usize = mem::size_of::<data_structures::SockAddr>();
             unsafe {
@@ -988,9 +1224,11 @@ pub extern "C" fn demi_getpeername(qd: c_int, addr: *mut data_structures::SockAd
                 }
 
                 // Need to pass dst a as c_void pointer or else we get a stack-smashing error
-                ptr::copy_nonoverlapping(&result as *const data_structures::SockAddr as *const c_void,
-                        addr as *mut c_void,
-                        *addrlen as usize);
+                ptr::copy_nonoverlapping(
+                    &result as *const data_structures::SockAddr as *const c_void,
+                    addr as *mut c_void,
+                    *addrlen as usize,
+                );
             }
 
             return 0;
@@ -1006,10 +1244,43 @@ pub extern "C" fn demi_getpeername(qd: c_int, addr: *mut data_structures::SockAd
 // Standalone Functions
 //======================================================================================================================
 
+/// Instantiates a LibOS with the name and configuration in [args], falling back to the environment for anything that
+/// is missing.
+fn new_libos(args: *const demi_args_t) -> Result<LibOS, Fail> {
+    // Parse arguments.
+    let (perf_callback, libos_name, config): (Option<demi_callback_t>, *const c_char, *const c_char) = if args.is_null()
+    {
+        (None, ptr::null(), ptr::null())
+    } else {
+        let args: &demi_args_t = unsafe { &*args };
+        (args.callback, args.libos_name, args.config)
+    };
+
+    // Fall back to the LIBOS environment variable if no LibOS name is given.
+    let libos_name: LibOSName = if libos_name.is_null() {
+        LibOSName::from_env()?
+    } else {
+        match unsafe { CStr::from_ptr(libos_name) }.to_str() {
+            Ok(name) => name.parse()?,
+            Err(_) => return Err(Fail::new(libc::EINVAL, "libos name is not valid UTF-8")),
+        }
+    };
+
+    // Fall back to the CONFIG_PATH environment variable if no configuration is given.
+    if config.is_null() {
+        return LibOS::new(libos_name, perf_callback);
+    }
+    let config: Config = match unsafe { CStr::from_ptr(config) }.to_str() {
+        Ok(config) => config.parse()?,
+        Err(_) => return Err(Fail::new(libc::EINVAL, "configuration is not valid UTF-8")),
+    };
+    LibOS::new_with_config(libos_name, config, perf_callback)
+}
+
 /// Issues a system call.
 fn do_syscall<T>(f: impl FnOnce(&mut LibOS) -> T) -> Result<T, Fail> {
     DEMIKERNEL.with(|demikernel| match demikernel.try_borrow_mut() {
-        Ok(mut libos) => match libos.as_mut() {
+        Ok(mut demikernel) => match demikernel.current_mut() {
             Some(libos) => Ok(f(libos)),
             None => Err(Fail::new(libc::ENOSYS, "Demikernel is not initialized")),
         },
@@ -1068,6 +1339,7 @@ mod test {
         SocketAddrV6,
     };
     use std::{
+        ffi::CString,
         mem,
         net::SocketAddr,
         os::raw::c_void,
@@ -1084,8 +1356,11 @@ mod test {
 
     use crate::{
         demikernel::bindings::{
+            demi_close,
             demi_getsockopt,
             demi_init,
+            demi_init_instance,
+            demi_select_instance,
             demi_setsockopt,
             demi_socket,
             sockaddr_to_socketaddr,
@@ -1275,4 +1550,99 @@ mod test {
 
         Ok(())
     }
+
+    #[cfg(feature = "catnap-libos")]
+    #[test]
+    fn test_init_with_args() -> anyhow::Result<()> {
+        use crate::runtime::types::demi_args_t;
+        let libos_name: CString = CString::new("catnap")?;
+        let config: CString = CString::new("demikernel:\n  local_ipv4_addr: 127.0.0.1\n")?;
+        let args: demi_args_t = demi_args_t {
+            libos_name: libos_name.as_ptr(),
+            config: config.as_ptr(),
+            ..Default::default()
+        };
+        let result: c_int = demi_init(&args);
+        ensure_eq!(result, 0);
+
+        let mut qd: c_int = 0;
+        let result: c_int = demi_socket(
+            &mut qd as *mut c_int,
+            Domain::IPV4.into(),
+            Type::STREAM.into(),
+            Protocol::TCP.into(),
+        );
+        ensure_eq!(result, 0);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "catnap-libos")]
+    #[test]
+    fn test_init_instances() -> anyhow::Result<()> {
+        use crate::runtime::types::{
+            demi_args_t,
+            demi_instance_t,
+        };
+        let libos_name: CString = CString::new("catnap")?;
+        let config: CString = CString::new("demikernel:\n  local_ipv4_addr: 127.0.0.1\n")?;
+        let args: demi_args_t = demi_args_t {
+            libos_name: libos_name.as_ptr(),
+            config: config.as_ptr(),
+            ..Default::default()
+        };
+
+        // Calls fail until an instance is selected.
+        let mut instance1: demi_instance_t = 0;
+        let mut instance2: demi_instance_t = 0;
+        ensure_eq!(demi_init_instance(&mut instance1, &args), 0);
+        ensure_eq!(demi_init_instance(&mut instance2, &args), 0);
+        ensure_neq!(instance1, instance2);
+        let mut qd: c_int = 0;
+        ensure_eq!(
+            demi_socket(&mut qd, Domain::IPV4.into(), Type::STREAM.into(), 0),
+            libc::ENOSYS
+        );
+
+        // Each instance hands out its own queue descriptors.
+        ensure_eq!(demi_select_instance(instance1), 0);
+        let mut qd1: c_int = 0;
+        ensure_eq!(demi_socket(&mut qd1, Domain::IPV4.into(), Type::STREAM.into(), 0), 0);
+        ensure_eq!(demi_select_instance(instance2), 0);
+        let mut qd2: c_int = 0;
+        ensure_eq!(demi_socket(&mut qd2, Domain::IPV4.into(), Type::STREAM.into(), 0), 0);
+        ensure_eq!(qd1, qd2);
+        ensure_eq!(demi_close(qd2), 0);
+        ensure_eq!(demi_close(qd2), libc::EBADF);
+        ensure_eq!(demi_select_instance(instance1), 0);
+        ensure_eq!(demi_close(qd1), 0);
+
+        // Invalid instances cannot be selected.
+        ensure_eq!(demi_select_instance(instance2 + 1), libc::EBADF);
+        ensure_eq!(demi_init_instance(ptr::null_mut(), &args), libc::EINVAL);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_init_with_bad_args() -> anyhow::Result<()> {
+        use crate::runtime::types::demi_args_t;
+        let libos_name: CString = CString::new("catfish")?;
+        let args: demi_args_t = demi_args_t {
+            libos_name: libos_name.as_ptr(),
+            ..Default::default()
+        };
+        ensure_eq!(demi_init(&args), libc::EINVAL);
+
+        let libos_name: CString = CString::new("catnap")?;
+        let config: CString = CString::new("demikernel:\n  local_ipv4_addr: [\n")?;
+        let args: demi_args_t = demi_args_t {
+            libos_name: libos_name.as_ptr(),
+            config: config.as_ptr(),
+            ..Default::default()
+        };
+        ensure_eq!(demi_init(&args), libc::EINVAL);
+
+        Ok(())
+    }
 }
@@ -7,7 +7,16 @@
 
 use crate::{
     pal::data_structures::KeepAlive,
-    runtime::fail::Fail,
+    runtime::{
+        fail::Fail,
+        network::{
+            socket::option::CongestionControlAlgorithm,
+            types::{
+                Ipv4Interface,
+                Ipv4Route,
+            },
+        },
+    },
     MacAddress,
 };
 #[cfg(any(feature = "catnip-libos"))]
@@ -16,12 +25,16 @@ use ::std::{
     collections::HashMap,
     fs::File,
     io::Read,
-    net::Ipv4Addr,
+    net::{
+        Ipv4Addr,
+        Ipv6Addr,
+    },
     ops::Index,
     str::FromStr,
     time::Duration,
 };
 use ::yaml_rust::{
+    yaml::Hash,
     Yaml,
     YamlLoader,
 };
@@ -34,23 +47,81 @@ use yaml_rust::yaml::Array;
 
 // Global Demikernel options. These apply to all libOSes
 mod global_config {
+    use super::OptionType;
+
     pub const SECTION_NAME: &str = "demikernel";
     // Local IPv4 addr.
     pub const LOCAL_IPV4_ADDR: &str = "local_ipv4_addr";
+    // Secondary local IPv4 addrs.
+    pub const SECONDARY_IPV4_ADDRS: &str = "secondary_ipv4_addrs";
+    // Local IPv6 addr.
+    pub const LOCAL_IPV6_ADDR: &str = "local_ipv6_addr";
     // Local network MAC address.
     pub const LOCAL_LINK_ADDR: &str = "local_link_addr";
+    // Adaptive polling of idle runtimes.
+    pub const ADAPTIVE_POLLING: &str = "adaptive_polling";
+
+    pub const SCHEMA: &[(&str, OptionType)] = &[
+        (LOCAL_IPV4_ADDR, OptionType::Str),
+        (SECONDARY_IPV4_ADDRS, OptionType::Array),
+        (LOCAL_IPV6_ADDR, OptionType::Str),
+        (LOCAL_LINK_ADDR, OptionType::Str),
+        (
+            ADAPTIVE_POLLING,
+            OptionType::Section(&[
+                ("enabled", OptionType::Bool),
+                ("idle_streak", OptionType::Int(1, i64::MAX)),
+            ]),
+        ),
+    ];
 }
 
 // Default TCP socket options. These apply to all libOSes.
 mod tcp_socket_options {
+    use super::OptionType;
+
     pub const SECTION_NAME: &str = "tcp_socket_options";
     pub const KEEP_ALIVE: &str = "keepalive";
     pub const LINGER: &str = "linger";
     pub const NO_DELAY: &str = "nodelay";
+    pub const CONGESTION_CONTROL: &str = "congestion_control";
+
+    // Keepalive durations must fit in the 32-bit millisecond timeouts of the socket option on Windows.
+    pub const KEEP_ALIVE_TIME_TYPE: OptionType = OptionType::Int(0, u32::MAX as i64);
+
+    // Linger durations must fit in the 16-bit timeout of the socket option.
+    pub const LINGER_TIME_TYPE: OptionType = OptionType::Int(0, u16::MAX as i64);
+
+    pub const SCHEMA: &[(&str, OptionType)] = &[
+        (
+            KEEP_ALIVE,
+            OptionType::Section(&[
+                ("enabled", OptionType::Bool),
+                ("time_millis", KEEP_ALIVE_TIME_TYPE),
+                ("interval", KEEP_ALIVE_TIME_TYPE),
+                ("probes", OptionType::NON_NEGATIVE_INT),
+            ]),
+        ),
+        (
+            LINGER,
+            OptionType::Section(&[("enabled", OptionType::Bool), ("time_seconds", LINGER_TIME_TYPE)]),
+        ),
+        (NO_DELAY, OptionType::Bool),
+        (CONGESTION_CONTROL, OptionType::Str),
+    ];
 }
 
 // TCP stack configurations. These only apply to the inetstack.
 mod inetstack_config {
+    use super::OptionType;
+    use crate::runtime::network::consts::{
+        MAX_MSS,
+        MAX_MTU,
+        MAX_WINDOW_SCALE,
+        MIN_MSS,
+        MIN_MTU,
+    };
+
     pub const SECTION_NAME: &str = "inetstack_config";
     pub const ARP_TABLE: &str = "arp_table";
     pub const ARP_CACHE_TTL: &str = "arp_cache_ttl";
@@ -58,272 +129,583 @@ mod inetstack_config {
     pub const ARP_REQUEST_RETRIES: &str = "arp_request_retries";
     pub const MTU: &str = "mtu";
     pub const MSS: &str = "mss";
+    pub const WINDOW_SCALE: &str = "window_scale";
     pub const ENABLE_JUMBO_FRAMES: &str = "enable_jumbo_frames";
     pub const UDP_CHECKSUM_OFFLOAD: &str = "udp_checksum_offload";
     pub const TCP_CHECKSUM_OFFLOAD: &str = "tcp_checksum_offload";
+    pub const TCP_SYN_COOKIES: &str = "tcp_syn_cookies";
+    pub const ICMPV4_ERROR_RATE: &str = "icmpv4_error_rate";
+    pub const ICMPV4_ERROR_BURST: &str = "icmpv4_error_burst";
+    pub const DEFAULT_GATEWAY: &str = "default_gateway";
+    pub const ROUTES: &str = "routes";
+
+    pub const MTU_TYPE: OptionType = OptionType::Int(MIN_MTU as i64, MAX_MTU as i64);
+
+    pub const SCHEMA: &[(&str, OptionType)] = &[
+        (ARP_TABLE, OptionType::Hash),
+        (ARP_CACHE_TTL, OptionType::NON_NEGATIVE_INT),
+        (ARP_REQUEST_TIMEOUT, OptionType::NON_NEGATIVE_INT),
+        (ARP_REQUEST_RETRIES, OptionType::NON_NEGATIVE_INT),
+        (MTU, MTU_TYPE),
+        (MSS, OptionType::Int(MIN_MSS as i64, MAX_MSS as i64)),
+        (WINDOW_SCALE, OptionType::Int(0, MAX_WINDOW_SCALE as i64)),
+        (ENABLE_JUMBO_FRAMES, OptionType::Bool),
+        (UDP_CHECKSUM_OFFLOAD, OptionType::Bool),
+        (TCP_CHECKSUM_OFFLOAD, OptionType::Bool),
+        (TCP_SYN_COOKIES, OptionType::Bool),
+        (ICMPV4_ERROR_RATE, OptionType::Int(0, u32::MAX as i64)),
+        (ICMPV4_ERROR_BURST, OptionType::Int(0, u32::MAX as i64)),
+        (DEFAULT_GATEWAY, OptionType::Str),
+        (
+            ROUTES,
+            OptionType::SectionArray(&[
+                ("destination", OptionType::Str),
+                ("gateway", OptionType::Str),
+                ("mtu", MTU_TYPE),
+            ]),
+        ),
+    ];
 }
 
-// DPDK options. These only apply to catnip.
-#[cfg(any(feature = "catnip-libos"))]
+// DPDK options. These only apply to catnip, but other libOSes accept them.
 mod dpdk_config {
+    use super::OptionType;
+
     pub const SECTION_NAME: &str = "dpdk";
     pub const EAL_INIT_ARGS: &str = "eal_init";
+    pub const PORT_ID: &str = "port_id";
+    pub const NUM_QUEUES: &str = "num_queues";
+    pub const QUEUE_ID: &str = "queue_id";
+
+    pub const SCHEMA: &[(&str, OptionType)] = &[
+        (EAL_INIT_ARGS, OptionType::Array),
+        (PORT_ID, OptionType::Int(0, u16::MAX as i64)),
+        (NUM_QUEUES, OptionType::Int(1, u16::MAX as i64)),
+        (QUEUE_ID, OptionType::Int(0, u16::MAX as i64 - 1)),
+    ];
 }
 
-// Raw socket option. Local network interface name. This only applies to catpowder right now.
-#[cfg(feature = "catpowder-libos")]
+// Raw socket option. Local network interface name. This only applies to catpowder right now, but other libOSes accept
+// it.
 mod raw_socket_config {
+    use super::OptionType;
+
     pub const SECTION_NAME: &str = "raw_socket";
-    #[cfg(target_os = "linux")]
     pub const LOCAL_INTERFACE_NAME: &str = "linux_interface_name";
-    #[cfg(target_os = "windows")]
     pub const LOCAL_INTERFACE_INDEX: &str = "xdp_interface_index";
+
+    pub const SCHEMA: &[(&str, OptionType)] = &[
+        (LOCAL_INTERFACE_NAME, OptionType::Str),
+        (LOCAL_INTERFACE_INDEX, OptionType::Int(0, u32::MAX as i64)),
+    ];
 }
 
+/// Sections of the configuration file and the options that each of them takes.
+const SCHEMA: &[(&str, OptionType)] = &[
+    (global_config::SECTION_NAME, OptionType::Section(global_config::SCHEMA)),
+    (
+        tcp_socket_options::SECTION_NAME,
+        OptionType::Section(tcp_socket_options::SCHEMA),
+    ),
+    (
+        inetstack_config::SECTION_NAME,
+        OptionType::Section(inetstack_config::SCHEMA),
+    ),
+    (dpdk_config::SECTION_NAME, OptionType::Section(dpdk_config::SCHEMA)),
+    (
+        raw_socket_config::SECTION_NAME,
+        OptionType::Section(raw_socket_config::SCHEMA),
+    ),
+];
+
 //======================================================================================================================
 // Structures
 //======================================================================================================================
 
-/// Demikernel configuration.
+/// Demikernel configuration. The configuration is parsed and validated once and for all when it is loaded, so reading
+/// options does not fail. Options that the configuration may leave out and that have no default value are optional.
 #[derive(Clone, Debug)]
-pub struct Config(pub Yaml);
+pub struct Config {
+    /// Local IPv4 addresses with the lengths of their subnet masks, if set. The primary one comes first.
+    local_ipv4_interfaces: Vec<Ipv4Interface>,
+    local_ipv6_addr: Option<Ipv6Addr>,
+    local_link_addr: Option<MacAddress>,
+    /// Idle streak of adaptive polling, if enabled.
+    adaptive_polling: Option<usize>,
+    tcp_keepalive_enabled: Option<bool>,
+    tcp_keepalive_time: Option<Duration>,
+    tcp_keepalive_interval: Option<Duration>,
+    tcp_keepalive_probes: Option<usize>,
+    /// Linger duration, if enabled.
+    linger: Option<Option<Duration>>,
+    no_delay: Option<bool>,
+    congestion_control: Option<CongestionControlAlgorithm>,
+    arp_table: Option<HashMap<Ipv4Addr, MacAddress>>,
+    arp_cache_ttl: Duration,
+    arp_request_timeout: Duration,
+    arp_request_retries: usize,
+    default_gateway: Option<Ipv4Addr>,
+    ipv4_routes: Vec<Ipv4Route>,
+    mtu: Option<u16>,
+    mss: Option<usize>,
+    window_scale: Option<u8>,
+    tcp_checksum_offload: Option<bool>,
+    tcp_syn_cookies: Option<bool>,
+    icmpv4_error_rate: Option<u32>,
+    icmpv4_error_burst: Option<u32>,
+    udp_checksum_offload: Option<bool>,
+    enable_jumbo_frames: Option<bool>,
+    #[cfg(all(feature = "catpowder-libos", target_os = "linux"))]
+    local_interface_name: Option<String>,
+    #[cfg(all(feature = "catpowder-libos", target_os = "windows"))]
+    local_interface_index: Option<u32>,
+    #[cfg(feature = "catnip-libos")]
+    eal_init_args: Option<Vec<CString>>,
+    #[cfg(feature = "catnip-libos")]
+    port_id: Option<u16>,
+    #[cfg(feature = "catnip-libos")]
+    num_queues: u16,
+    #[cfg(feature = "catnip-libos")]
+    queue_id: u16,
+}
+
+/// Type of an option in the configuration file.
+#[derive(Clone, Copy, Debug)]
+enum OptionType {
+    Bool,
+    /// Integer within an inclusive range.
+    Int(i64, i64),
+    Str,
+    Array,
+    Hash,
+    /// Subsection that takes the given options.
+    Section(&'static [(&'static str, OptionType)]),
+    /// Array of subsections that take the given options.
+    SectionArray(&'static [(&'static str, OptionType)]),
+}
 
 //======================================================================================================================
 // Associated Functions
 //======================================================================================================================
 
+/// Associated constants for types of configuration options.
+impl OptionType {
+    const NON_NEGATIVE_INT: OptionType = OptionType::Int(0, i64::MAX);
+}
+
 /// Common associated functions for Demikernel configuration object.
 impl Config {
-    /// Reads a configuration file into a [Config] object.
+    /// Reads a configuration file into a [Config] object. The configuration is validated as in [Config::from_str].
     pub fn new(config_path: String) -> Result<Self, Fail> {
         let mut config_s: String = String::new();
-        File::open(config_path).unwrap().read_to_string(&mut config_s).unwrap();
-        let config: Vec<Yaml> = YamlLoader::load_from_str(&config_s).unwrap();
-        let config_obj: &Yaml = match &config[..] {
-            &[ref c] => c,
-            _ => return Err(Fail::new(libc::EINVAL, "Wrong number of config objects")),
-        };
+        if let Err(e) = File::open(&config_path).and_then(|mut file: File| file.read_to_string(&mut config_s)) {
+            let cause: String = format!(
+                "failed to read configuration file (path={:?}, error={:?})",
+                config_path, e
+            );
+            error!("new(): {}", cause);
+            return Err(Fail::new(e.raw_os_error().unwrap_or(libc::EINVAL), &cause));
+        }
+        Self::from_str(&config_s)
+    }
+
+    /// Global config: Gets the primary local IPv4 address, if set.
+    pub fn local_ipv4_addr(&self) -> Option<Ipv4Addr> {
+        self.local_ipv4_interfaces
+            .first()
+            .map(|interface: &Ipv4Interface| interface.get_addr())
+    }
+
+    /// Global config: Gets all local IPv4 addresses, starting with the primary one.
+    pub fn local_ipv4_addrs(&self) -> Vec<Ipv4Addr> {
+        self.local_ipv4_interfaces
+            .iter()
+            .map(|interface: &Ipv4Interface| interface.get_addr())
+            .collect()
+    }
+
+    /// Global config: Gets all local IPv4 addresses along with the lengths of their subnet masks, starting with the
+    /// primary one.
+    pub fn local_ipv4_interfaces(&self) -> Vec<Ipv4Interface> {
+        self.local_ipv4_interfaces.clone()
+    }
+
+    /// Global config: Gets the local IPv6 address, if configured or derived from the local link address.
+    pub fn local_ipv6_addr(&self) -> Option<Ipv6Addr> {
+        self.local_ipv6_addr
+    }
+
+    /// Global config: Gets the local link address, if configured.
+    pub fn local_link_addr(&self) -> Option<MacAddress> {
+        self.local_link_addr
+    }
+
+    /// Global config: Gets the idle streak of adaptive polling if enabled; otherwise, None. A runtime that completes no
+    /// task for that many scheduling quanta in a row blocks until it may have work again, instead of busy-polling.
+    pub fn adaptive_polling(&self) -> Option<usize> {
+        self.adaptive_polling
+    }
+
+    /// Tcp socket option: Gets TCP keepalive settings as a `tcp_keepalive` structure, if set.
+    pub fn tcp_keepalive(&self) -> Option<KeepAlive> {
+        let onoff: bool = self.tcp_keepalive_enabled?;
+
+        #[cfg(target_os = "linux")]
+        return Some(onoff);
+
+        // Both durations were checked to fit in 32 bits when the configuration was loaded.
+        #[cfg(target_os = "windows")]
+        Some(KeepAlive {
+            onoff: if onoff { 1 } else { 0 },
+            keepalivetime: self.tcp_keepalive_time?.as_millis() as u32,
+            keepaliveinterval: self.tcp_keepalive_interval?.as_millis() as u32,
+        })
+    }
+
+    /// Tcp socket option: Gets the idle time before sending TCP keepalive probes, if set.
+    pub fn tcp_keepalive_time(&self) -> Option<Duration> {
+        self.tcp_keepalive_time
+    }
+
+    /// Tcp socket option: Gets the interval between TCP keepalive probes, if set.
+    pub fn tcp_keepalive_interval(&self) -> Option<Duration> {
+        self.tcp_keepalive_interval
+    }
+
+    /// Tcp socket option: Gets the number of unanswered TCP keepalive probes before dropping the connection, if set.
+    pub fn tcp_keepalive_probes(&self) -> Option<usize> {
+        self.tcp_keepalive_probes
+    }
+
+    /// Tcp socket option: Gets socket linger settings, if set. The inner value is Some(_) if lingering is enabled;
+    /// otherwise, None. The linger duration is no larger than u16::MAX seconds.
+    pub fn linger(&self) -> Option<Option<Duration>> {
+        self.linger
+    }
+
+    /// Tcp socket option: Gets the setting to enable or disable Nagle's algorithm, if set.
+    pub fn no_delay(&self) -> Option<bool> {
+        self.no_delay
+    }
 
-        Ok(Self { 0: config_obj.clone() })
+    /// Tcp socket option: Gets the congestion control algorithm that TCP connections use, if set.
+    pub fn congestion_control(&self) -> Option<CongestionControlAlgorithm> {
+        self.congestion_control
     }
 
-    fn get_global_config(&self) -> Result<&Yaml, Fail> {
-        Self::get_subsection(&self.0, global_config::SECTION_NAME)
+    /// Tcp Config: Gets the ARP table. If no ARP table is present, then ARP is disabled.
+    pub fn arp_table(&self) -> Option<HashMap<Ipv4Addr, MacAddress>> {
+        self.arp_table.clone()
     }
 
-    fn get_tcp_socket_options(&self) -> Result<&Yaml, Fail> {
-        Self::get_subsection(&self.0, tcp_socket_options::SECTION_NAME)
+    /// Tcp Config: Gets the ARP cache TTL. This is zero if ARP is disabled.
+    pub fn arp_cache_ttl(&self) -> Duration {
+        self.arp_cache_ttl
     }
 
-    fn get_inetstack_config(&self) -> Result<&Yaml, Fail> {
-        Self::get_subsection(&self.0, inetstack_config::SECTION_NAME)
+    /// Tcp Config: Gets the ARP request timeout. This is zero if ARP is disabled.
+    pub fn arp_request_timeout(&self) -> Duration {
+        self.arp_request_timeout
+    }
+
+    /// Tcp Config: Gets the number of ARP request retries. This is zero if ARP is disabled.
+    pub fn arp_request_retries(&self) -> usize {
+        self.arp_request_retries
+    }
+
+    /// Inetstack config: Gets the default gateway. If there is none, there is no default route.
+    pub fn default_gateway(&self) -> Option<Ipv4Addr> {
+        self.default_gateway
+    }
+
+    /// Inetstack config: Gets the IPv4 routes. Routes without a gateway are on the local link. If no routes are
+    /// present, then all destinations that the default gateway does not cover are on the local link.
+    pub fn ipv4_routes(&self) -> Vec<Ipv4Route> {
+        self.ipv4_routes.clone()
+    }
+
+    #[cfg(all(feature = "catpowder-libos", target_os = "linux"))]
+    /// Global config: Gets the local interface name, if set.
+    pub fn local_interface_name(&self) -> Option<String> {
+        self.local_interface_name.clone()
+    }
+
+    #[cfg(all(feature = "catpowder-libos", target_os = "windows"))]
+    /// Global config: Gets the local interface index, if set.
+    pub fn local_interface_index(&self) -> Option<u32> {
+        self.local_interface_index
     }
 
     #[cfg(feature = "catnip-libos")]
-    fn get_dpdk_config(&self) -> Result<&Yaml, Fail> {
-        Self::get_subsection(&self.0, dpdk_config::SECTION_NAME)
+    /// DPDK Config: Gets the DPDK EAL arguments, if set.
+    pub fn eal_init_args(&self) -> Option<Vec<CString>> {
+        self.eal_init_args.clone()
     }
 
-    #[cfg(feature = "catpowder-libos")]
-    fn get_raw_socket_config(&self) -> Result<&Yaml, Fail> {
-        Self::get_subsection(&self.0, raw_socket_config::SECTION_NAME)
+    #[cfg(feature = "catnip-libos")]
+    /// DPDK Config: Gets the DPDK port that this LibOS instance uses, if set. Otherwise, it uses the first port.
+    pub fn port_id(&self) -> Option<u16> {
+        self.port_id
     }
 
-    /// Global config: Reads the local IPv4 address parameter from the environment variable first and then the
-    /// underlying configuration file.
-    pub fn local_ipv4_addr(&self) -> Result<Ipv4Addr, Fail> {
-        let local_ipv4_addr: Ipv4Addr = if let Some(addr) = Self::get_typed_env_option(global_config::LOCAL_IPV4_ADDR)?
-        {
-            addr
-        } else {
-            Self::get_typed_str_option(
-                self.get_global_config()?,
-                global_config::LOCAL_IPV4_ADDR,
-                |val: &str| match val.parse() {
-                    Ok(local_addr) => Some(local_addr),
-                    _ => None,
-                },
-            )?
-        };
+    #[cfg(feature = "catnip-libos")]
+    /// DPDK Config: Gets the number of RX/TX queue pairs over which the NIC spreads flows, each owned by one LibOS
+    /// instance. This defaults to a single queue.
+    pub fn num_queues(&self) -> u16 {
+        self.num_queues
+    }
 
-        if local_ipv4_addr.is_unspecified() || local_ipv4_addr.is_broadcast() {
-            let cause: String = format!("Invalid IPv4 address");
-            error!("local_ipv4_addr(): {:?}", cause);
-            return Err(Fail::new(libc::EINVAL, &cause));
-        }
-        Ok(local_ipv4_addr)
+    #[cfg(feature = "catnip-libos")]
+    /// DPDK Config: Gets the RX/TX queue pair that this LibOS instance owns. This defaults to the first queue.
+    pub fn queue_id(&self) -> u16 {
+        self.queue_id
     }
 
-    /// Reads the "local link address" parameter from the enviroment variable first and then underlying configuration
-    /// file.
-    pub fn local_link_addr(&self) -> Result<MacAddress, Fail> {
-        // Parse local MAC address.
-        if let Some(addr) = Self::get_typed_env_option(global_config::LOCAL_LINK_ADDR)? {
-            Ok(addr)
-        } else {
-            Self::get_typed_str_option(
-                self.get_global_config()?,
-                global_config::LOCAL_LINK_ADDR,
-                |val: &str| match MacAddress::parse_str(val) {
-                    Ok(local_addr) => Some(local_addr),
-                    _ => None,
-                },
-            )
-        }
+    /// Gets the "MTU" parameter, if set.
+    pub fn mtu(&self) -> Option<u16> {
+        self.mtu
     }
 
-    /// Tcp socket option: Reads TCP keepalive settings as a `tcp_keepalive` structure from "tcp_keepalive" subsection.
-    pub fn tcp_keepalive(&self) -> Result<KeepAlive, Fail> {
-        let section: &Yaml = Self::get_subsection(self.get_tcp_socket_options()?, tcp_socket_options::KEEP_ALIVE)?;
-        let onoff: bool = Self::get_bool_option(section, "enabled")?;
+    /// Gets the "MSS" parameter, if set.
+    pub fn mss(&self) -> Option<usize> {
+        self.mss
+    }
 
-        #[cfg(target_os = "windows")]
-        // This indicates how long to keep the socket alive. By default, this is 2 hours on Windows.
-        // README: https://learn.microsoft.com/en-us/windows/win32/winsock/sio-keepalive-vals
-        let keepalivetime: u32 = Self::get_int_option(section, "time_millis")?;
-        #[cfg(target_os = "windows")]
-        // This indicates how often to send keep alive messages. By default, this is 1 second on Windows.
-        let keepaliveinterval: u32 = Self::get_int_option(section, "interval")?;
+    /// Gets the "window scale" parameter, which is the shift count that we advertise for TCP receive windows, if set.
+    pub fn window_scale(&self) -> Option<u8> {
+        self.window_scale
+    }
 
-        #[cfg(target_os = "linux")]
-        return Ok(onoff);
+    /// Gets the "TCP_CHECKSUM_OFFLOAD" parameter, if set.
+    pub fn tcp_checksum_offload(&self) -> Option<bool> {
+        self.tcp_checksum_offload
+    }
 
-        #[cfg(target_os = "windows")]
-        Ok(KeepAlive {
-            onoff: if onoff { 1 } else { 0 },
-            keepalivetime,
-            keepaliveinterval,
-        })
+    /// Gets the "TCP_SYN_COOKIES" parameter, if set.
+    pub fn tcp_syn_cookies(&self) -> Option<bool> {
+        self.tcp_syn_cookies
     }
 
-    /// Tcp socket option: Reads socket linger settings from "linger" subsection. Returned value is Some(_) if enabled;
-    /// otherwise, None. The linger duration will be no larger than u16::MAX seconds.
-    pub fn linger(&self) -> Result<Option<Duration>, Fail> {
-        let linger: u64 = if let Some(linger) = Self::get_typed_env_option(tcp_socket_options::LINGER)? {
-            linger
-        } else {
-            let section: &Yaml = Self::get_subsection(self.get_tcp_socket_options()?, tcp_socket_options::LINGER)?;
-            if Self::get_bool_option(section, "enabled")? {
-                Self::get_int_option(section, "time_seconds")?
-            } else {
-                return Ok(None);
-            }
-        };
-        Ok(Some(Duration::from_secs(linger)))
+    /// Gets the number of ICMPv4 error messages that we may send per second, if set.
+    pub fn icmpv4_error_rate(&self) -> Option<u32> {
+        self.icmpv4_error_rate
     }
 
-    /// Tcp socket option: Reads the setting to enable or disable Nagle's algorithm.
-    pub fn no_delay(&self) -> Result<bool, Fail> {
-        if let Some(nodelay) = Self::get_typed_env_option(tcp_socket_options::NO_DELAY)? {
-            Ok(nodelay)
-        } else {
-            Self::get_bool_option(self.get_tcp_socket_options()?, tcp_socket_options::NO_DELAY)
-        }
+    /// Gets the number of ICMPv4 error messages that we may send back to back, if set.
+    pub fn icmpv4_error_burst(&self) -> Option<u32> {
+        self.icmpv4_error_burst
     }
 
-    /// Tcp Config: Reads the "ARP table" parameter from the underlying configuration file. If no ARP table is present,
-    /// then ARP is disabled. This cannot be passed in as an environment variable.
-    pub fn arp_table(&self) -> Result<Option<HashMap<Ipv4Addr, MacAddress>>, Fail> {
-        if let Ok(arp_table) = Self::get_typed_option(
-            self.get_inetstack_config()?,
-            inetstack_config::ARP_TABLE,
-            |yaml: &Yaml| yaml.as_hash(),
-        ) {
-            let mut result: HashMap<Ipv4Addr, MacAddress> =
-                HashMap::<Ipv4Addr, MacAddress>::with_capacity(arp_table.len());
-            for (k, v) in arp_table {
-                let link_addr: MacAddress = match k.as_str() {
-                    Some(link_string) => MacAddress::parse_str(link_string)?,
-                    None => {
-                        let cause: String = format!("Couldn't parse ARP table link_addr in config");
-                        error!("arp_table(): {:?}", cause);
-                        return Err(Fail::new(libc::EINVAL, &cause));
-                    },
-                };
-                let ipv4_addr: Ipv4Addr = match v.as_str() {
-                    Some(ip_string) => match ip_string.parse() {
-                        Ok(ip) => ip,
-                        Err(e) => {
-                            let cause: String = format!("Couldn't parse ARP table ip_addr in config: {:?}", e);
-                            error!("arp_table(): {:?}", cause);
-                            return Err(Fail::new(libc::EINVAL, &cause));
-                        },
-                    },
-                    None => return Err(Fail::new(libc::EINVAL, "Couldn't find ARP table link_addr in config")),
-                };
-                result.insert(ipv4_addr, link_addr);
-            }
-            return Ok(Some(result));
+    /// Gets the "UDP_CHECKSUM_OFFLOAD" parameter, if set.
+    pub fn udp_checksum_offload(&self) -> Option<bool> {
+        self.udp_checksum_offload
+    }
+
+    /// Gets the "USE_JUMBO" parameter, if set.
+    pub fn enable_jumbo_frames(&self) -> Option<bool> {
+        self.enable_jumbo_frames
+    }
+
+    /// Fails for the option [name] that a component requires but the configuration does not set.
+    pub fn missing_option(name: &str) -> Fail {
+        let cause: String = format!("missing configuration option \"{}\"", name);
+        error!("missing_option(): {}", cause);
+        Fail::new(libc::EINVAL, &cause)
+    }
+
+    //======================================================================================================================
+    // Static Functions
+    //======================================================================================================================
+
+    /// Global config: Reads all local IPv4 addresses along with the lengths of their subnet masks. The primary address
+    /// comes first and is read from the environment variable first and then the configuration file. It is followed by
+    /// the ones in the "secondary IPv4 addresses" parameter, which is optional and cannot be passed in as an
+    /// environment variable. Addresses are in CIDR notation, and a plain address is alone in its subnet. The primary
+    /// address is optional, as not every LibOS needs one, but secondary addresses require it.
+    fn parse_local_ipv4_interfaces(yaml: &Yaml) -> Result<Vec<Ipv4Interface>, Fail> {
+        let section: &Yaml = &yaml[global_config::SECTION_NAME];
+        let primary: Ipv4Interface =
+            match Self::get_str_env_option(section, global_config::LOCAL_IPV4_ADDR, |val: &str| {
+                Ipv4Interface::parse(val).ok()
+            })? {
+                Some(interface) => interface,
+                None if section[global_config::SECONDARY_IPV4_ADDRS].is_array() => {
+                    return Err(Self::missing_option(global_config::LOCAL_IPV4_ADDR))
+                },
+                None => return Ok(Vec::new()),
+            };
+
+        let mut result: Vec<Ipv4Interface> = vec![primary];
+        let addrs: &Vec<Yaml> = match section[global_config::SECONDARY_IPV4_ADDRS] {
+            Yaml::Array(ref addrs) => addrs,
+            _ => return Ok(result),
         };
-        Ok(None)
+        for addr in addrs {
+            let interface: Ipv4Interface = match addr.as_str().map(Ipv4Interface::parse) {
+                Some(Ok(interface)) => interface,
+                _ => {
+                    let cause: String = format!("invalid secondary IPv4 address (addr={:?})", addr);
+                    error!("parse_local_ipv4_interfaces(): {}", cause);
+                    return Err(Fail::new(libc::EINVAL, &cause));
+                },
+            };
+            if result
+                .iter()
+                .any(|other: &Ipv4Interface| other.get_addr() == interface.get_addr())
+            {
+                let cause: String = format!("duplicate IPv4 address (addr={:?})", interface.get_addr());
+                error!("parse_local_ipv4_interfaces(): {}", cause);
+                return Err(Fail::new(libc::EINVAL, &cause));
+            }
+            result.push(interface);
+        }
+        Ok(result)
     }
 
-    pub fn arp_cache_ttl(&self) -> Result<Duration, Fail> {
-        let ttl: u64 = if let Some(ttl) = Self::get_typed_env_option(inetstack_config::ARP_CACHE_TTL)? {
-            ttl
-        } else {
-            Self::get_int_option(self.get_inetstack_config()?, inetstack_config::ARP_CACHE_TTL)?
+    /// Global config: Reads the local IPv6 address parameter from the environment variable first and then the
+    /// configuration file. This parameter is optional: if it is not present, the link-local address derived from the
+    /// local link address is used instead (see RFC 4291 Appendix A).
+    fn parse_local_ipv6_addr(yaml: &Yaml, local_link_addr: Option<MacAddress>) -> Result<Option<Ipv6Addr>, Fail> {
+        let local_ipv6_addr: Ipv6Addr = match Self::get_str_env_option(
+            &yaml[global_config::SECTION_NAME],
+            global_config::LOCAL_IPV6_ADDR,
+            |val: &str| val.parse().ok(),
+        )? {
+            Some(addr) => addr,
+            None => match local_link_addr {
+                Some(local_link_addr) => Self::link_local_ipv6_addr(local_link_addr),
+                None => return Ok(None),
+            },
         };
-        Ok(Duration::from_secs(ttl))
+
+        if local_ipv6_addr.is_unspecified() || local_ipv6_addr.is_multicast() || local_ipv6_addr.is_loopback() {
+            let cause: &str = "Invalid IPv6 address";
+            error!("parse_local_ipv6_addr(): {:?}", cause);
+            return Err(Fail::new(libc::EINVAL, cause));
+        }
+        Ok(Some(local_ipv6_addr))
     }
 
-    pub fn arp_request_timeout(&self) -> Result<Duration, Fail> {
-        let timeout: u64 = if let Some(timeout) = Self::get_typed_env_option(inetstack_config::ARP_REQUEST_TIMEOUT)? {
-            timeout
+    /// Global config: Reads adaptive polling settings from the "adaptive_polling" subsection. This subsection is
+    /// optional and defaults to busy-polling.
+    fn parse_adaptive_polling(yaml: &Yaml) -> Result<Option<usize>, Fail> {
+        let section: &Yaml = &yaml[global_config::SECTION_NAME][global_config::ADAPTIVE_POLLING];
+        if let Yaml::BadValue = section {
+            return Ok(None);
+        }
+        if Self::get_bool_option(section, "enabled")? {
+            Ok(Some(Self::get_int_option(section, "idle_streak")?))
         } else {
-            Self::get_int_option(self.get_inetstack_config()?, inetstack_config::ARP_REQUEST_TIMEOUT)?
-        };
-        Ok(Duration::from_secs(timeout))
+            Ok(None)
+        }
     }
 
-    pub fn arp_request_retries(&self) -> Result<usize, Fail> {
-        let retries: usize = if let Some(retries) = Self::get_typed_env_option(inetstack_config::ARP_REQUEST_RETRIES)? {
-            retries
+    /// Tcp socket option: Reads socket linger settings from the environment variable first and then the "linger"
+    /// subsection.
+    fn parse_linger(yaml: &Yaml) -> Result<Option<Option<Duration>>, Fail> {
+        if let Some(linger) = Self::get_typed_env_option::<u64>(tcp_socket_options::LINGER)? {
+            // Unlike values in the configuration file, environment variables are not checked against the schema.
+            Self::check_option(
+                tcp_socket_options::LINGER,
+                &Yaml::Integer(linger as i64),
+                tcp_socket_options::LINGER_TIME_TYPE,
+            )?;
+            return Ok(Some(Some(Duration::from_secs(linger))));
+        }
+        let section: &Yaml = &yaml[tcp_socket_options::SECTION_NAME][tcp_socket_options::LINGER];
+        if let Yaml::BadValue = section {
+            return Ok(None);
+        }
+        if Self::get_bool_option(section, "enabled")? {
+            let linger: u64 = Self::get_int_option(section, "time_seconds")?;
+            Ok(Some(Some(Duration::from_secs(linger))))
         } else {
-            Self::get_int_option(self.get_inetstack_config()?, inetstack_config::ARP_REQUEST_RETRIES)?
+            Ok(Some(None))
+        }
+    }
+
+    /// Tcp Config: Reads the "ARP table" parameter from the configuration file. This cannot be passed in as an
+    /// environment variable.
+    fn parse_arp_table(yaml: &Yaml) -> Result<Option<HashMap<Ipv4Addr, MacAddress>>, Fail> {
+        let arp_table: &Hash = match yaml[inetstack_config::SECTION_NAME][inetstack_config::ARP_TABLE] {
+            Yaml::Hash(ref arp_table) => arp_table,
+            _ => return Ok(None),
         };
-        Ok(retries)
+        let mut result: HashMap<Ipv4Addr, MacAddress> = HashMap::<Ipv4Addr, MacAddress>::with_capacity(arp_table.len());
+        for (k, v) in arp_table {
+            let link_addr: MacAddress = match k.as_str() {
+                Some(link_string) => MacAddress::parse_str(link_string)?,
+                None => {
+                    let cause: String = format!("Couldn't parse ARP table link_addr in config");
+                    error!("parse_arp_table(): {:?}", cause);
+                    return Err(Fail::new(libc::EINVAL, &cause));
+                },
+            };
+            let ipv4_addr: Ipv4Addr = match v.as_str() {
+                Some(ip_string) => match ip_string.parse() {
+                    Ok(ip) => ip,
+                    Err(e) => {
+                        let cause: String = format!("Couldn't parse ARP table ip_addr in config: {:?}", e);
+                        error!("parse_arp_table(): {:?}", cause);
+                        return Err(Fail::new(libc::EINVAL, &cause));
+                    },
+                },
+                None => return Err(Fail::new(libc::EINVAL, "Couldn't find ARP table link_addr in config")),
+            };
+            result.insert(ipv4_addr, link_addr);
+        }
+        Ok(Some(result))
     }
 
-    #[cfg(all(feature = "catpowder-libos", target_os = "linux"))]
-    /// Global config: Reads the "local interface name" parameter from the environment variable and then the underlying
-    /// configuration file.
-    pub fn local_interface_name(&self) -> Result<String, Fail> {
-        // Parse local MAC address.
-        if let Some(addr) = Self::get_typed_env_option(raw_socket_config::LOCAL_INTERFACE_NAME)? {
-            Ok(addr)
-        } else {
-            Self::get_typed_str_option(
-                self.get_raw_socket_config()?,
-                raw_socket_config::LOCAL_INTERFACE_NAME,
-                |val: &str| Some(val.to_string()),
-            )
+    /// Tcp Config: Reads an ARP parameter from the environment variable first and then the configuration file. ARP
+    /// parameters are required if there is an ARP table; otherwise, they default to zero.
+    fn parse_arp_option<T: FromStr + TryFrom<i64> + Default>(
+        yaml: &Yaml,
+        name: &str,
+        arp_enabled: bool,
+    ) -> Result<T, Fail> {
+        match Self::get_int_env_option(&yaml[inetstack_config::SECTION_NAME], name)? {
+            Some(value) => Ok(value),
+            None if arp_enabled => Err(Self::missing_option(name)),
+            None => Ok(T::default()),
         }
     }
 
-    #[cfg(all(feature = "catpowder-libos", target_os = "windows"))]
-    /// Global config: Reads the "local interface index" parameter from the environment variable and then the underlying
-    /// configuration file.
-    pub fn local_interface_index(&self) -> Result<u32, Fail> {
-        // Parse local MAC address.
-        if let Some(addr) = Self::get_typed_env_option(raw_socket_config::LOCAL_INTERFACE_INDEX)? {
-            Ok(addr)
-        } else {
-            Self::get_int_option(self.get_raw_socket_config()?, raw_socket_config::LOCAL_INTERFACE_INDEX)
+    /// Inetstack config: Reads the "routes" parameter from the configuration file. Each route has a "destination" in
+    /// CIDR notation, and optionally the "gateway" that forwards datagrams to it and the "mtu" to use on it. This
+    /// cannot be passed in as an environment variable.
+    fn parse_ipv4_routes(yaml: &Yaml) -> Result<Vec<Ipv4Route>, Fail> {
+        let routes: &Vec<Yaml> = match yaml[inetstack_config::SECTION_NAME][inetstack_config::ROUTES] {
+            Yaml::Array(ref routes) => routes,
+            _ => return Ok(Vec::new()),
+        };
+
+        let mut result: Vec<Ipv4Route> = Vec::<Ipv4Route>::with_capacity(routes.len());
+        for route in routes {
+            let (destination, prefix_len): (Ipv4Addr, u8) =
+                Self::get_typed_str_option(route, "destination", |val: &str| Ipv4Route::parse_destination(val).ok())?;
+            let gateway: Option<Ipv4Addr> = match route["gateway"] {
+                Yaml::BadValue => None,
+                _ => Some(Self::get_typed_str_option(route, "gateway", |val: &str| {
+                    val.parse().ok()
+                })?),
+            };
+            let mtu: Option<u16> = match route["mtu"] {
+                Yaml::BadValue => None,
+                _ => Some(Self::get_int_option(route, "mtu")?),
+            };
+            result.push(Ipv4Route::new(destination, prefix_len, gateway, mtu)?);
         }
+        Ok(result)
     }
 
     #[cfg(feature = "catnip-libos")]
-    /// DPDK Config: Reads the "DPDK EAL" parameter the underlying configuration file.
-    pub fn eal_init_args(&self) -> Result<Vec<CString>, Fail> {
-        let args: &Array = Self::get_typed_option(
-            self.get_dpdk_config()?,
-            dpdk_config::EAL_INIT_ARGS,
-            |yaml: &Yaml| match yaml {
-                Yaml::Array(ref arr) => Some(arr),
-                _ => None,
-            },
-        )?;
+    /// DPDK Config: Reads the "DPDK EAL" parameter from the configuration file.
+    fn parse_eal_init_args(yaml: &Yaml) -> Result<Option<Vec<CString>>, Fail> {
+        let args: &Array = match yaml[dpdk_config::SECTION_NAME][dpdk_config::EAL_INIT_ARGS] {
+            Yaml::Array(ref args) => args,
+            _ => return Ok(None),
+        };
 
         let mut result: Vec<CString> = Vec::<CString>::with_capacity(args.len());
         for arg in args {
@@ -332,74 +714,157 @@ impl Config {
                     Ok(cstring) => result.push(cstring),
                     Err(e) => {
                         let cause: String = format!("Non string argument: {:?}", e);
-                        error!("eal_init_args(): {}", cause);
+                        error!("parse_eal_init_args(): {}", cause);
                         return Err(Fail::new(libc::EINVAL, &cause));
                     },
                 },
                 None => {
                     let cause: String = format!("Non string argument");
-                    error!("eal_init_args(): {}", cause);
+                    error!("parse_eal_init_args(): {}", cause);
                     return Err(Fail::new(libc::EINVAL, &cause));
                 },
             }
         }
-        Ok(result)
+        Ok(Some(result))
     }
 
-    /// Gets the "MTU" parameter from environment variables.
-    pub fn mtu(&self) -> Result<u16, Fail> {
-        // Parse local MAC address.
-        if let Some(addr) = Self::get_typed_env_option(inetstack_config::MTU)? {
-            Ok(addr)
-        } else {
-            Self::get_int_option(self.get_inetstack_config()?, inetstack_config::MTU)
+    /// Checks the options in the subsection [yaml] against [schema]. Options must be known and have the expected type,
+    /// and integers must be in range. Options are identified by their [path] in error messages.
+    fn check_section(yaml: &Yaml, path: &str, schema: &[(&str, OptionType)]) -> Result<(), Fail> {
+        let section: &Hash = match yaml.as_hash() {
+            Some(section) => section,
+            None => {
+                let cause: String = format!("parameter \"{}\" has unexpected type", path);
+                error!("check_section(): {}", cause);
+                return Err(Fail::new(libc::EINVAL, &cause));
+            },
+        };
+        for (key, value) in section {
+            let name: &str = match key.as_str() {
+                Some(name) => name,
+                None => {
+                    let cause: String = format!("invalid configuration option name in \"{}\" (key={:?})", path, key);
+                    error!("check_section(): {}", cause);
+                    return Err(Fail::new(libc::EINVAL, &cause));
+                },
+            };
+            let path: String = if path.is_empty() {
+                name.to_string()
+            } else {
+                format!("{}.{}", path, name)
+            };
+            let option: Option<&(&str, OptionType)> =
+                schema.iter().find(|(option, _): &&(&str, OptionType)| *option == name);
+            let option_type: OptionType = match option {
+                Some((_, option_type)) => *option_type,
+                None => {
+                    let cause: String = format!("unknown configuration option \"{}\"", path);
+                    error!("check_section(): {}", cause);
+                    return Err(Fail::new(libc::EINVAL, &cause));
+                },
+            };
+            Self::check_option(&path, value, option_type)?;
         }
+        Ok(())
     }
 
-    /// Gets the "MSS" parameter from environment variables.
-    pub fn mss(&self) -> Result<usize, Fail> {
-        Self::get_int_option(self.get_inetstack_config()?, inetstack_config::MSS)
+    /// Checks that the option at [path] has the type [option_type].
+    fn check_option(path: &str, value: &Yaml, option_type: OptionType) -> Result<(), Fail> {
+        let has_type: bool = match (option_type, value) {
+            (OptionType::Bool, Yaml::Boolean(_)) => true,
+            (OptionType::Int(min, max), Yaml::Integer(value)) => {
+                if *value < min || *value > max {
+                    let cause: String = format!(
+                        "parameter \"{}\" is out of range (value={:?}, min={:?}, max={:?})",
+                        path, value, min, max
+                    );
+                    error!("check_option(): {}", cause);
+                    return Err(Fail::new(libc::ERANGE, &cause));
+                }
+                true
+            },
+            (OptionType::Str, Yaml::String(_)) => true,
+            (OptionType::Array, Yaml::Array(_)) => true,
+            (OptionType::Hash, Yaml::Hash(_)) => true,
+            (OptionType::Section(schema), _) => return Self::check_section(value, path, schema),
+            (OptionType::SectionArray(schema), Yaml::Array(sections)) => {
+                for (i, section) in sections.iter().enumerate() {
+                    Self::check_section(section, &format!("{}[{}]", path, i), schema)?;
+                }
+                true
+            },
+            _ => false,
+        };
+        if !has_type {
+            let cause: String = format!(
+                "parameter \"{}\" has unexpected type (expected={:?})",
+                path, option_type
+            );
+            error!("check_option(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        Ok(())
     }
 
-    /// Gets the "TCP_CHECKSUM_OFFLOAD" parameter from environment variables.
-    pub fn tcp_checksum_offload(&self) -> Result<bool, Fail> {
-        Self::get_bool_option(self.get_inetstack_config()?, inetstack_config::TCP_CHECKSUM_OFFLOAD)
+    /// Reads the integer option [index] from the environment variable first and then the subsection [yaml] of the
+    /// configuration file, or None if neither sets it.
+    fn get_int_env_option<T: FromStr + TryFrom<i64>>(yaml: &Yaml, index: &str) -> Result<Option<T>, Fail> {
+        match Self::get_typed_env_option(index)? {
+            Some(value) => Ok(Some(value)),
+            None => Self::get_optional_int_option(yaml, index),
+        }
     }
 
-    /// Gets the "UDP_CHECKSUM_OFFLOAD" parameter from environment variables.
-    pub fn udp_checksum_offload(&self) -> Result<bool, Fail> {
-        Self::get_bool_option(self.get_inetstack_config()?, inetstack_config::UDP_CHECKSUM_OFFLOAD)
+    /// Reads the boolean option [index] from the environment variable first and then the subsection [yaml] of the
+    /// configuration file, or None if neither sets it.
+    fn get_bool_env_option(yaml: &Yaml, index: &str) -> Result<Option<bool>, Fail> {
+        match Self::get_typed_env_option(index)? {
+            Some(value) => Ok(Some(value)),
+            None => Self::get_optional_bool_option(yaml, index),
+        }
     }
 
-    /// Gets the "USE_JUMBO" parameter from environment variables.
-    pub fn enable_jumbo_frames(&self) -> Result<bool, Fail> {
-        Self::get_bool_option(self.get_inetstack_config()?, inetstack_config::ENABLE_JUMBO_FRAMES)
+    /// Reads the string option [index] from the environment variable first and then the subsection [yaml] of the
+    /// configuration file, and parses it with [parser]. Returns None if neither sets it.
+    fn get_str_env_option<T, Parser>(yaml: &Yaml, index: &str, parser: Parser) -> Result<Option<T>, Fail>
+    where
+        Parser: Fn(&str) -> Option<T>,
+    {
+        if let Some(value) = Self::get_typed_env_option::<String>(index)? {
+            return match parser(&value) {
+                Some(value) => Ok(Some(value)),
+                None => {
+                    let message: String = format!("parameter {} has unexpected type", index);
+                    Err(Fail::new(libc::EINVAL, message.as_str()))
+                },
+            };
+        }
+        match yaml[index] {
+            Yaml::BadValue => Ok(None),
+            _ => Ok(Some(Self::get_typed_str_option(yaml, index, parser)?)),
+        }
     }
 
-    //======================================================================================================================
-    // Static Functions
-    //======================================================================================================================
+    /// Same as `Self::get_int_option`, but returns None if the option is missing.
+    fn get_optional_int_option<T: TryFrom<i64>>(yaml: &Yaml, index: &str) -> Result<Option<T>, Fail> {
+        match yaml[index] {
+            Yaml::BadValue => Ok(None),
+            _ => Ok(Some(Self::get_int_option(yaml, index)?)),
+        }
+    }
 
-    /// Similar to `require_typed_option` using `Yaml::as_hash` receiver. This method returns a `&Yaml` instead of
-    /// yaml::Hash, and Yaml is more natural for indexing.
-    fn get_subsection<'a>(yaml: &'a Yaml, index: &str) -> Result<&'a Yaml, Fail> {
-        let section: &'a Yaml = Self::get_option(yaml, index)?;
-        match section {
-            Yaml::Hash(_) => Ok(section),
-            _ => {
-                let message: String = format!("parameter \"{}\" has unexpected type", index);
-                Err(Fail::new(libc::EINVAL, message.as_str()))
-            },
+    /// Same as `Self::get_bool_option`, but returns None if the option is missing.
+    fn get_optional_bool_option(yaml: &Yaml, index: &str) -> Result<Option<bool>, Fail> {
+        match yaml[index] {
+            Yaml::BadValue => Ok(None),
+            _ => Ok(Some(Self::get_bool_option(yaml, index)?)),
         }
     }
 
     /// Index `yaml` to find the value at `index`, validating that the index exists.
     fn get_option<'a>(yaml: &'a Yaml, index: &str) -> Result<&'a Yaml, Fail> {
         match yaml.index(index) {
-            Yaml::BadValue => {
-                let message: String = format!("missing configuration option \"{}\"", index);
-                Err(Fail::new(libc::EINVAL, message.as_str()))
-            },
+            Yaml::BadValue => Err(Self::missing_option(index)),
             value => Ok(value),
         }
     }
@@ -465,4 +930,346 @@ impl Config {
     fn get_bool_option(yaml: &Yaml, index: &str) -> Result<bool, Fail> {
         Self::get_typed_option(yaml, index, &Yaml::as_bool)
     }
+
+    /// Builds the link-local IPv6 address for [link_addr] using its modified EUI-64 interface identifier.
+    fn link_local_ipv6_addr(link_addr: MacAddress) -> Ipv6Addr {
+        let mac: [u8; 6] = link_addr.octets();
+        Ipv6Addr::from([
+            0xfe,
+            0x80,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            mac[0] ^ 0x02,
+            mac[1],
+            mac[2],
+            0xff,
+            0xfe,
+            mac[3],
+            mac[4],
+            mac[5],
+        ])
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+/// Parses a [Config] from the contents of a configuration file, and validates it once and for all. Unknown options,
+/// options of unexpected type, out-of-range integers and malformed values are reported as errors rather than when they
+/// are first used. Environment variables that override options are read here as well.
+impl FromStr for Config {
+    type Err = Fail;
+
+    fn from_str(config_s: &str) -> Result<Self, Self::Err> {
+        let config: Vec<Yaml> = match YamlLoader::load_from_str(config_s) {
+            Ok(config) => config,
+            Err(e) => {
+                let cause: String = format!("failed to parse configuration (error={:?})", e.to_string());
+                error!("from_str(): {}", cause);
+                return Err(Fail::new(libc::EINVAL, &cause));
+            },
+        };
+        let yaml: &Yaml = match &config[..] {
+            &[ref c] => c,
+            _ => return Err(Fail::new(libc::EINVAL, "Wrong number of config objects")),
+        };
+        Self::check_section(yaml, "", SCHEMA)?;
+
+        let global: &Yaml = &yaml[global_config::SECTION_NAME];
+        let tcp_socket: &Yaml = &yaml[tcp_socket_options::SECTION_NAME];
+        let keepalive: &Yaml = &tcp_socket[tcp_socket_options::KEEP_ALIVE];
+        let inetstack: &Yaml = &yaml[inetstack_config::SECTION_NAME];
+
+        let local_link_addr: Option<MacAddress> =
+            Self::get_str_env_option(global, global_config::LOCAL_LINK_ADDR, |val: &str| {
+                MacAddress::parse_str(val).ok()
+            })?;
+
+        let mtu: Option<u16> = Self::get_int_env_option(inetstack, inetstack_config::MTU)?;
+        if let Some(mtu) = mtu {
+            // Unlike values in the configuration file, environment variables are not checked against the schema.
+            Self::check_option(
+                inetstack_config::MTU,
+                &Yaml::Integer(mtu as i64),
+                inetstack_config::MTU_TYPE,
+            )?;
+        }
+
+        let arp_table: Option<HashMap<Ipv4Addr, MacAddress>> = Self::parse_arp_table(yaml)?;
+        let arp_enabled: bool = arp_table.is_some();
+
+        Ok(Self {
+            local_ipv4_interfaces: Self::parse_local_ipv4_interfaces(yaml)?,
+            local_ipv6_addr: Self::parse_local_ipv6_addr(yaml, local_link_addr)?,
+            local_link_addr,
+            adaptive_polling: Self::parse_adaptive_polling(yaml)?,
+            tcp_keepalive_enabled: Self::get_optional_bool_option(keepalive, "enabled")?,
+            tcp_keepalive_time: Self::get_optional_int_option(keepalive, "time_millis")?.map(Duration::from_millis),
+            tcp_keepalive_interval: Self::get_optional_int_option(keepalive, "interval")?.map(Duration::from_millis),
+            tcp_keepalive_probes: Self::get_optional_int_option(keepalive, "probes")?,
+            linger: Self::parse_linger(yaml)?,
+            no_delay: Self::get_bool_env_option(tcp_socket, tcp_socket_options::NO_DELAY)?,
+            congestion_control: Self::get_str_env_option(
+                tcp_socket,
+                tcp_socket_options::CONGESTION_CONTROL,
+                |val: &str| val.parse().ok(),
+            )?,
+            arp_table,
+            arp_cache_ttl: Duration::from_secs(Self::parse_arp_option(
+                yaml,
+                inetstack_config::ARP_CACHE_TTL,
+                arp_enabled,
+            )?),
+            arp_request_timeout: Duration::from_secs(Self::parse_arp_option(
+                yaml,
+                inetstack_config::ARP_REQUEST_TIMEOUT,
+                arp_enabled,
+            )?),
+            arp_request_retries: Self::parse_arp_option(yaml, inetstack_config::ARP_REQUEST_RETRIES, arp_enabled)?,
+            default_gateway: Self::get_str_env_option(inetstack, inetstack_config::DEFAULT_GATEWAY, |val: &str| {
+                val.parse().ok()
+            })?,
+            ipv4_routes: Self::parse_ipv4_routes(yaml)?,
+            mtu,
+            mss: Self::get_optional_int_option(inetstack, inetstack_config::MSS)?,
+            window_scale: Self::get_optional_int_option(inetstack, inetstack_config::WINDOW_SCALE)?,
+            tcp_checksum_offload: Self::get_optional_bool_option(inetstack, inetstack_config::TCP_CHECKSUM_OFFLOAD)?,
+            tcp_syn_cookies: Self::get_optional_bool_option(inetstack, inetstack_config::TCP_SYN_COOKIES)?,
+            icmpv4_error_rate: Self::get_optional_int_option(inetstack, inetstack_config::ICMPV4_ERROR_RATE)?,
+            icmpv4_error_burst: Self::get_optional_int_option(inetstack, inetstack_config::ICMPV4_ERROR_BURST)?,
+            udp_checksum_offload: Self::get_optional_bool_option(inetstack, inetstack_config::UDP_CHECKSUM_OFFLOAD)?,
+            enable_jumbo_frames: Self::get_optional_bool_option(inetstack, inetstack_config::ENABLE_JUMBO_FRAMES)?,
+            #[cfg(all(feature = "catpowder-libos", target_os = "linux"))]
+            local_interface_name: Self::get_str_env_option(
+                &yaml[raw_socket_config::SECTION_NAME],
+                raw_socket_config::LOCAL_INTERFACE_NAME,
+                |val: &str| Some(val.to_string()),
+            )?,
+            #[cfg(all(feature = "catpowder-libos", target_os = "windows"))]
+            local_interface_index: Self::get_int_env_option(
+                &yaml[raw_socket_config::SECTION_NAME],
+                raw_socket_config::LOCAL_INTERFACE_INDEX,
+            )?,
+            #[cfg(feature = "catnip-libos")]
+            eal_init_args: Self::parse_eal_init_args(yaml)?,
+            #[cfg(feature = "catnip-libos")]
+            port_id: Self::get_optional_int_option(&yaml[dpdk_config::SECTION_NAME], dpdk_config::PORT_ID)?,
+            #[cfg(feature = "catnip-libos")]
+            num_queues: Self::get_optional_int_option(&yaml[dpdk_config::SECTION_NAME], dpdk_config::NUM_QUEUES)?
+                .unwrap_or(1),
+            #[cfg(feature = "catnip-libos")]
+            queue_id: Self::get_optional_int_option(&yaml[dpdk_config::SECTION_NAME], dpdk_config::QUEUE_ID)?
+                .unwrap_or(0),
+        })
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        demikernel::config::Config,
+        runtime::fail::Fail,
+    };
+    use ::anyhow::Result;
+    use ::std::{
+        net::Ipv4Addr,
+        str::FromStr,
+    };
+
+    /// Minimal configuration that the tests below build upon.
+    const CONFIG: &str = "
+demikernel:
+  local_ipv4_addr: 192.168.1.1
+  local_link_addr: \"12:23:45:67:89:ab\"
+inetstack_config:
+  mtu: 1500
+  mss: 1450
+";
+
+    /// Parses [CONFIG] with [extra] appended to its inetstack section and returns the error code, if any.
+    fn parse_with(extra: &str) -> Option<i32> {
+        Config::from_str(&format!("{}  {}\n", CONFIG, extra))
+            .err()
+            .map(|e| e.errno)
+    }
+
+    /// Tests that a valid configuration is parsed.
+    #[test]
+    fn test_config_from_str() -> Result<()> {
+        let config: Config = match Config::from_str(CONFIG) {
+            Ok(config) => config,
+            Err(e) => anyhow::bail!("failed to parse configuration: {:?}", e),
+        };
+        crate::ensure_eq!(config.mtu(), Some(1500));
+        crate::ensure_eq!(config.mss(), Some(1450));
+        crate::ensure_eq!(config.window_scale(), None);
+        crate::ensure_eq!(parse_with("window_scale: 7"), None);
+        crate::ensure_eq!(config.icmpv4_error_rate(), None);
+        let config: Config =
+            Config::from_str(&format!("{}  icmpv4_error_rate: 10\n  icmpv4_error_burst: 2\n", CONFIG))?;
+        crate::ensure_eq!(config.icmpv4_error_rate(), Some(10));
+        crate::ensure_eq!(config.icmpv4_error_burst(), Some(2));
+
+        // Only LibOSes that build their own network stack need a link address.
+        let config: Config = Config::from_str(&CONFIG.replace("  local_link_addr: \"12:23:45:67:89:ab\"\n", ""))?;
+        crate::ensure_eq!(config.local_link_addr(), None);
+        crate::ensure_eq!(config.local_ipv6_addr(), None);
+
+        Ok(())
+    }
+
+    /// Tests that unknown options are reported.
+    #[test]
+    fn test_config_unknown_option() -> Result<()> {
+        crate::ensure_eq!(parse_with("mtuu: 1500"), Some(libc::EINVAL));
+        crate::ensure_eq!(
+            Config::from_str(&format!("{}inetstack:\n  mtu: 1500\n", CONFIG))
+                .err()
+                .map(|e| e.errno),
+            Some(libc::EINVAL)
+        );
+        crate::ensure_eq!(
+            parse_with("routes:\n    - destination: \"10.0.0.0/8\"\n      gw: \"192.168.1.254\""),
+            Some(libc::EINVAL)
+        );
+
+        Ok(())
+    }
+
+    /// Tests that options of unexpected type are reported.
+    #[test]
+    fn test_config_unexpected_type() -> Result<()> {
+        crate::ensure_eq!(parse_with("tcp_syn_cookies: 1"), Some(libc::EINVAL));
+        crate::ensure_eq!(parse_with("arp_cache_ttl: \"15\""), Some(libc::EINVAL));
+        crate::ensure_eq!(
+            Config::from_str("demikernel: 192.168.1.1\n").err().map(|e| e.errno),
+            Some(libc::EINVAL)
+        );
+
+        Ok(())
+    }
+
+    /// Tests that out-of-range options are reported.
+    #[test]
+    fn test_config_out_of_range() -> Result<()> {
+        crate::ensure_eq!(parse_with("window_scale: 15"), Some(libc::ERANGE));
+        crate::ensure_eq!(parse_with("arp_request_retries: -1"), Some(libc::ERANGE));
+        crate::ensure_eq!(
+            Config::from_str(&CONFIG.replace("mtu: 1500", "mtu: 65536"))
+                .err()
+                .map(|e| e.errno),
+            Some(libc::ERANGE)
+        );
+        crate::ensure_eq!(
+            Config::from_str(&CONFIG.replace("mss: 1450", "mss: 100"))
+                .err()
+                .map(|e| e.errno),
+            Some(libc::ERANGE)
+        );
+        crate::ensure_eq!(
+            parse_with("routes:\n    - destination: \"10.0.0.0/8\"\n      mtu: 20"),
+            Some(libc::ERANGE)
+        );
+
+        Ok(())
+    }
+
+    /// Tests that adaptive polling is optional and reads the idle streak when enabled.
+    #[test]
+    fn test_config_adaptive_polling() -> Result<()> {
+        let with_adaptive_polling = |section: &str| -> Result<Config, Fail> {
+            Config::from_str(&CONFIG.replace(
+                "inetstack_config:",
+                &format!("  adaptive_polling:\n{}\ninetstack_config:", section),
+            ))
+        };
+        crate::ensure_eq!(Config::from_str(CONFIG)?.adaptive_polling(), None);
+        crate::ensure_eq!(
+            with_adaptive_polling("    enabled: true\n    idle_streak: 16")?.adaptive_polling(),
+            Some(16)
+        );
+        crate::ensure_eq!(
+            with_adaptive_polling("    enabled: false\n    idle_streak: 16")?.adaptive_polling(),
+            None
+        );
+        crate::ensure_eq!(
+            with_adaptive_polling("    enabled: true\n    idle_streak: 0")
+                .err()
+                .map(|e| e.errno),
+            Some(libc::ERANGE)
+        );
+
+        Ok(())
+    }
+
+    /// Tests that missing required options are reported when the configuration is parsed.
+    #[test]
+    fn test_config_missing_option() -> Result<()> {
+        // Not every LibOS needs a local IPv4 address, but secondary addresses do.
+        let config: Config = Config::from_str(&CONFIG.replace("  local_ipv4_addr: 192.168.1.1\n", ""))?;
+        crate::ensure_eq!(config.local_ipv4_addr(), None);
+        crate::ensure_eq!(config.local_ipv4_addrs(), Vec::<Ipv4Addr>::new());
+        crate::ensure_eq!(
+            Config::from_str(&CONFIG.replace(
+                "  local_ipv4_addr: 192.168.1.1\n",
+                "  secondary_ipv4_addrs: [\"10.0.0.1\"]\n"
+            ))
+            .err()
+            .map(|e| e.errno),
+            Some(libc::EINVAL)
+        );
+        crate::ensure_eq!(
+            parse_with("arp_table:\n    \"12:23:45:67:89:ac\": \"192.168.1.2\""),
+            Some(libc::EINVAL)
+        );
+        crate::ensure_eq!(
+            parse_with(
+                "arp_table:\n    \"12:23:45:67:89:ac\": \"192.168.1.2\"\n  arp_cache_ttl: 15\n  arp_request_timeout: \
+                 20\n  arp_request_retries: 5"
+            ),
+            None
+        );
+
+        Ok(())
+    }
+
+    /// Tests that options that fail to parse are reported when the configuration is parsed.
+    #[test]
+    fn test_config_bad_value() -> Result<()> {
+        crate::ensure_eq!(parse_with("default_gateway: \"192.168.1\""), Some(libc::EINVAL));
+        crate::ensure_eq!(
+            Config::from_str(&CONFIG.replace("192.168.1.1", "192.168.1.256"))
+                .err()
+                .map(|e| e.errno),
+            Some(libc::EINVAL)
+        );
+
+        Ok(())
+    }
+
+    /// Tests that malformed and missing configuration files are reported.
+    #[test]
+    fn test_config_bad_file() -> Result<()> {
+        crate::ensure_eq!(
+            Config::from_str("demikernel: [\n").err().map(|e| e.errno),
+            Some(libc::EINVAL)
+        );
+        crate::ensure_eq!(
+            Config::new("/nonexistent/demikernel.yaml".to_string())
+                .err()
+                .map(|e| e.errno),
+            Some(libc::ENOENT)
+        );
+
+        Ok(())
+    }
 }
@@ -13,6 +13,7 @@ use crate::runtime::{
     },
     QDesc,
     QToken,
+    SharedDemiRuntime,
 };
 use ::std::time::Duration;
 
@@ -26,6 +27,7 @@ use crate::runtime::memory::MemoryRuntime;
 //======================================================================================================================
 
 /// Associated functions for Memory LibOSes.
+#[derive(Clone)]
 pub enum MemoryLibOS {
     #[cfg(feature = "catmem-libos")]
     Catmem(SharedCatmemLibOS),
@@ -130,6 +132,17 @@ impl MemoryLibOS {
         }
     }
 
+    /// Cancels a pending I/O operation.
+    #[allow(unreachable_patterns, unused_variables)]
+    pub fn cancel(&mut self, qt: QToken) -> Result<(), Fail> {
+        trace!("cancel(): qt={:?}", qt);
+        match self {
+            #[cfg(feature = "catmem-libos")]
+            MemoryLibOS::Catmem(libos) => libos.cancel(qt),
+            _ => unreachable!("unknown memory libos"),
+        }
+    }
+
 
     /// Allocates a scatter-gather array.
     #[allow(unreachable_patterns, unused_variables)]
@@ -151,6 +164,16 @@ impl MemoryLibOS {
         }
     }
 
+    /// Returns the runtime of the LibOS.
+    #[allow(unreachable_patterns, unused_variables)]
+    pub fn get_runtime(&self) -> SharedDemiRuntime {
+        match self {
+            #[cfg(feature = "catmem-libos")]
+            MemoryLibOS::Catmem(libos) => libos.get_runtime(),
+            _ => unreachable!("unknown memory libos"),
+        }
+    }
+
     /// Waits for any operation in an I/O queue.
     #[allow(unreachable_patterns, unused_variables)]
     pub fn poll(&mut self) {
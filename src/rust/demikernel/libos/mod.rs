@@ -43,7 +43,16 @@ use crate::{
         fail::Fail,
         limits,
         logging,
+        memory::{
+            free_sgarray,
+            sgarray_from_buffer,
+            DemiBuffer,
+        },
         network::socket::option::SocketOption,
+        submission::{
+            PushHandler,
+            Submitter,
+        },
         types::{
             demi_callback_t,
             demi_qresult_t,
@@ -58,8 +67,8 @@ use crate::{
 use ::std::{
     env,
     net::{
+        Shutdown,
         SocketAddr,
-        SocketAddrV4,
     },
     time::Duration,
 };
@@ -79,6 +88,7 @@ use crate::catnap::transport::SharedCatnapTransport;
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(120);
 
 /// LibOS
+#[derive(Clone)]
 pub enum LibOS {
     /// Network LibOS
     #[cfg(any(
@@ -99,10 +109,8 @@ pub enum LibOS {
 
 /// Associated functions for LibOS.
 impl LibOS {
-    /// Instantiates a new LibOS.
-    pub fn new(libos_name: LibOSName, _perf_callback: Option<demi_callback_t>) -> Result<Self, Fail> {
-        timer!("demikernel::new");
-
+    /// Instantiates a new LibOS with the configuration file that the `CONFIG_PATH` environment variable points to.
+    pub fn new(libos_name: LibOSName, perf_callback: Option<demi_callback_t>) -> Result<Self, Fail> {
         logging::initialize();
 
         // Read in configuration file.
@@ -116,12 +124,26 @@ impl LibOS {
             },
         };
 
+        let config: Config = Config::new(config_path)?;
+        Self::new_with_config(libos_name, config, perf_callback)
+    }
+
+    /// Instantiates a new LibOS with a configuration that the caller has already loaded. This does not read any
+    /// environment variables, so applications that have their own configuration system may embed Demikernel with it.
+    pub fn new_with_config(
+        libos_name: LibOSName,
+        config: Config,
+        _perf_callback: Option<demi_callback_t>,
+    ) -> Result<Self, Fail> {
+        timer!("demikernel::new");
+
+        logging::initialize();
+
         #[cfg(feature = "profiler")]
         if let Some(callback) = _perf_callback {
             set_callback(callback)
         };
 
-        let config: Config = Config::new(config_path)?;
         #[allow(unused_mut)]
         let mut runtime: SharedDemiRuntime = SharedDemiRuntime::default();
         // Instantiate LibOS.
@@ -131,7 +153,7 @@ impl LibOS {
             LibOSName::Catnap => Self::NetworkLibOS(NetworkLibOSWrapper::Catnap(SharedNetworkLibOS::<
                 SharedCatnapTransport,
             >::new(
-                config.local_ipv4_addr()?,
+                config.local_ipv4_addrs(),
                 runtime.clone(),
                 SharedCatnapTransport::new(&config, &mut runtime)?,
             ))),
@@ -140,43 +162,83 @@ impl LibOS {
             LibOSName::Catpowder => {
                 let layer1_endpoint: SharedCatpowderRuntime = SharedCatpowderRuntime::new(&config)?;
                 // This is our transport for Catpowder.
-                let inetstack: SharedInetStack =
-                    SharedInetStack::new(&config, runtime.clone(), layer1_endpoint).unwrap();
+                let inetstack: SharedInetStack = SharedInetStack::new(&config, runtime.clone(), layer1_endpoint)?;
                 Self::NetworkLibOS(NetworkLibOSWrapper::Catpowder(
-                    SharedNetworkLibOS::<SharedInetStack>::new(config.local_ipv4_addr()?, runtime, inetstack),
+                    SharedNetworkLibOS::<SharedInetStack>::new(config.local_ipv4_addrs(), runtime.clone(), inetstack),
                 ))
             },
             #[cfg(feature = "catnip-libos")]
             LibOSName::Catnip => {
                 // TODO: Remove some of these clones once we are done merging the libOSes.
                 let layer1_endpoint: SharedDPDKRuntime = SharedDPDKRuntime::new(&config)?;
-                let inetstack: SharedInetStack =
-                    SharedInetStack::new(&config, runtime.clone(), layer1_endpoint).unwrap();
+                // Ephemeral ports must be chosen so that connections come back to the queue of this LibOS, and packets
+                // of the flows of other queues must be handed to them.
+                if let (Some(flow_steering), Some(frame_replication)) = (
+                    layer1_endpoint.get_flow_steering(),
+                    layer1_endpoint.get_frame_replication(),
+                ) {
+                    runtime.set_flow_steering(flow_steering, frame_replication);
+                }
+                let inetstack: SharedInetStack = SharedInetStack::new(&config, runtime.clone(), layer1_endpoint)?;
 
                 Self::NetworkLibOS(NetworkLibOSWrapper::Catnip(SharedNetworkLibOS::<SharedInetStack>::new(
-                    config.local_ipv4_addr()?,
-                    runtime,
+                    config.local_ipv4_addrs(),
+                    runtime.clone(),
                     inetstack,
                 )))
             },
             #[cfg(feature = "catmem-libos")]
             LibOSName::Catmem => {
-                Self::MemoryLibOS(MemoryLibOS::Catmem(SharedCatmemLibOS::new(&config, runtime.clone())))
+                Self::MemoryLibOS(MemoryLibOS::Catmem(SharedCatmemLibOS::new(&config, runtime.clone())?))
             },
             #[cfg(feature = "catloop-libos")]
             LibOSName::Catloop => Self::NetworkLibOS(NetworkLibOSWrapper::Catloop(SharedNetworkLibOS::<
                 SharedCatloopTransport,
             >::new(
-                config.local_ipv4_addr()?,
+                config.local_ipv4_addrs(),
                 runtime.clone(),
                 SharedCatloopTransport::new(&config, runtime.clone())?,
             ))),
-            _ => panic!("unsupported libos"),
+            _ => return Err(Fail::new(libc::ENOTSUP, "unsupported libos")),
         };
 
         Ok(libos)
     }
 
+    /// Returns a handle through which other threads push to the queues of this LibOS or run closures on its runtime.
+    /// Submitted work runs the next time that this LibOS polls or waits.
+    pub fn submitter(&self) -> Submitter {
+        self.get_runtime().submitter()
+    }
+
+    /// Returns the runtime of this LibOS.
+    fn get_runtime(&self) -> SharedDemiRuntime {
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.get_runtime(),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(libos) => libos.get_runtime(),
+        }
+    }
+
+    /// Runs [f] with a push handler installed in the runtime, so that pushes that other threads submit reach this
+    /// LibOS while it polls or waits. The handler holds a clone of this LibOS, so it is only installed for the call.
+    fn with_push_handler<R, F: FnOnce(&mut Self) -> R>(&mut self, f: F) -> R {
+        let mut runtime: SharedDemiRuntime = self.get_runtime();
+        let mut push_libos: LibOS = self.clone();
+        let prev: Option<PushHandler> = runtime.replace_push_handler(Some(Box::new(move |qd: QDesc, data: &[u8]| {
+            push_libos.push_slice(qd, data)
+        })));
+        let result: R = f(self);
+        runtime.replace_push_handler(prev);
+        result
+    }
+
     /// Creates a new memory queue and connect to consumer end.
     #[allow(unused_variables)]
     pub fn create_pipe(&mut self, name: &str) -> Result<QDesc, Fail> {
@@ -307,8 +369,8 @@ impl LibOS {
         result
     }
 
-    pub fn getpeername(&mut self, sockqd: QDesc) -> Result<SocketAddrV4, Fail> {
-        let result: Result<SocketAddrV4, Fail> = {
+    pub fn getpeername(&mut self, sockqd: QDesc) -> Result<SocketAddr, Fail> {
+        let result: Result<SocketAddr, Fail> = {
             match self {
                 #[cfg(any(
                     feature = "catnap-libos",
@@ -331,6 +393,56 @@ impl LibOS {
         result
     }
 
+    /// Gets the local address to which the socket is bound.
+    pub fn getsockname(&mut self, sockqd: QDesc) -> Result<SocketAddr, Fail> {
+        let result: Result<SocketAddr, Fail> = {
+            match self {
+                #[cfg(any(
+                    feature = "catnap-libos",
+                    feature = "catnip-libos",
+                    feature = "catpowder-libos",
+                    feature = "catloop-libos"
+                ))]
+                LibOS::NetworkLibOS(libos) => libos.getsockname(sockqd),
+                #[cfg(feature = "catmem-libos")]
+                LibOS::MemoryLibOS(_) => {
+                    let cause: String = format!("Sockname is not supported on memory liboses");
+                    error!("getsockname(): {}", cause);
+                    Err(Fail::new(libc::ENOTSUP, &cause))
+                },
+            }
+        };
+
+        self.poll();
+
+        result
+    }
+
+    /// Shuts down receiving, sending or both on a connected socket.
+    pub fn shutdown(&mut self, sockqd: QDesc, how: Shutdown) -> Result<(), Fail> {
+        let result: Result<(), Fail> = {
+            match self {
+                #[cfg(any(
+                    feature = "catnap-libos",
+                    feature = "catnip-libos",
+                    feature = "catpowder-libos",
+                    feature = "catloop-libos"
+                ))]
+                LibOS::NetworkLibOS(libos) => libos.shutdown(sockqd, how),
+                #[cfg(feature = "catmem-libos")]
+                LibOS::MemoryLibOS(_) => {
+                    let cause: String = format!("Shutdown is not supported on memory liboses");
+                    error!("shutdown(): {}", cause);
+                    Err(Fail::new(libc::ENOTSUP, &cause))
+                },
+            }
+        };
+
+        self.poll();
+
+        result
+    }
+
     /// Binds a socket to a local address.
     #[allow(unused_variables)]
     pub fn bind(&mut self, sockqd: QDesc, local: SocketAddr) -> Result<(), Fail> {
@@ -501,6 +613,25 @@ impl LibOS {
         result
     }
 
+    /// Pushes a copy of [data] to an I/O queue on behalf of another thread. Unlike [LibOS::push], this does not poll,
+    /// because it runs while the runtime polls.
+    fn push_slice(&mut self, qd: QDesc, data: &[u8]) -> Result<QToken, Fail> {
+        let sga: demi_sgarray_t = sgarray_from_buffer(DemiBuffer::from_slice(data)?)?;
+        let result: Result<QToken, Fail> = match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.push(qd, &sga),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(libos) => libos.push(qd, &sga),
+        };
+        free_sgarray(sga)?;
+        result
+    }
+
     /// Pushes a scatter-gather array to a UDP socket.
     #[allow(unused_variables)]
     pub fn pushto(&mut self, qd: QDesc, sga: &demi_sgarray_t, to: SocketAddr) -> Result<QToken, Fail> {
@@ -561,7 +692,7 @@ impl LibOS {
     /// This is just a single-token convenience wrapper for wait_any().
     pub fn wait(&mut self, qt: QToken, timeout: Option<Duration>) -> Result<demi_qresult_t, Fail> {
         // No profiling scope here because we may enter a coroutine scope.
-        match self {
+        self.with_push_handler(|libos: &mut LibOS| match libos {
             #[cfg(any(
                 feature = "catnap-libos",
                 feature = "catnip-libos",
@@ -571,13 +702,13 @@ impl LibOS {
             LibOS::NetworkLibOS(libos) => libos.wait(qt, timeout.unwrap_or(DEFAULT_TIMEOUT)),
             #[cfg(feature = "catmem-libos")]
             LibOS::MemoryLibOS(libos) => libos.wait(qt, timeout.unwrap_or(DEFAULT_TIMEOUT)),
-        }
+        })
     }
 
     /// Waits for any of the given pending I/O operations to complete or a timeout to expire.
     pub fn wait_any(&mut self, qts: &[QToken], timeout: Option<Duration>) -> Result<(usize, demi_qresult_t), Fail> {
         // No profiling scope here because we may enter a coroutine scope.
-        match self {
+        self.with_push_handler(|libos: &mut LibOS| match libos {
             #[cfg(any(
                 feature = "catnap-libos",
                 feature = "catnip-libos",
@@ -587,7 +718,7 @@ impl LibOS {
             LibOS::NetworkLibOS(libos) => libos.wait_any(qts, timeout.unwrap_or(DEFAULT_TIMEOUT)),
             #[cfg(feature = "catmem-libos")]
             LibOS::MemoryLibOS(libos) => libos.wait_any(qts, timeout.unwrap_or(DEFAULT_TIMEOUT)),
-        }
+        })
     }
 
     /// Waits in a loop until the next task is complete, passing the result to `acceptor`. This process continues until
@@ -600,7 +731,7 @@ impl LibOS {
         timeout: Option<Duration>,
     ) -> Result<(), Fail> {
         // No profiling scope here because we may enter a coroutine scope.
-        match self {
+        self.with_push_handler(|libos: &mut LibOS| match libos {
             #[cfg(any(
                 feature = "catnap-libos",
                 feature = "catnip-libos",
@@ -610,6 +741,23 @@ impl LibOS {
             LibOS::NetworkLibOS(libos) => libos.wait_next_n(acceptor, timeout.unwrap_or(DEFAULT_TIMEOUT)),
             #[cfg(feature = "catmem-libos")]
             LibOS::MemoryLibOS(libos) => libos.wait_next_n(acceptor, timeout.unwrap_or(DEFAULT_TIMEOUT)),
+        })
+    }
+
+    /// Cancels a pending I/O operation, so that waiting on its queue token fails with ECANCELED. Operations that
+    /// already completed cannot be cancelled, and waiting on their queue token returns their result as usual.
+    pub fn cancel(&mut self, qt: QToken) -> Result<(), Fail> {
+        timer!("demikernel::cancel");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.cancel(qt),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(libos) => libos.cancel(qt),
         }
     }
 
@@ -655,7 +803,7 @@ impl LibOS {
 
     pub fn poll(&mut self) {
         // No profiling scope here because we may enter a coroutine scope.
-        match self {
+        self.with_push_handler(|libos: &mut LibOS| match libos {
             #[cfg(any(
                 feature = "catnap-libos",
                 feature = "catnip-libos",
@@ -665,6 +813,6 @@ impl LibOS {
             LibOS::NetworkLibOS(libos) => libos.poll(),
             #[cfg(feature = "catmem-libos")]
             LibOS::MemoryLibOS(libos) => libos.poll(),
-        }
+        })
     }
 }
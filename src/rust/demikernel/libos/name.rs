@@ -6,7 +6,10 @@
 //======================================================================================================================
 
 use crate::runtime::fail::Fail;
-use ::std::env;
+use ::std::{
+    env,
+    str::FromStr,
+};
 
 //======================================================================================================================
 // Structures
@@ -30,7 +33,7 @@ pub enum LibOSName {
 impl LibOSName {
     pub fn from_env() -> Result<Self, Fail> {
         match env::var("LIBOS") {
-            Ok(name) => Ok(name.into()),
+            Ok(name) => name.parse(),
             Err(_) => Err(Fail::new(libc::EINVAL, "missing value for LIBOS environment variable")),
         }
     }
@@ -40,16 +43,22 @@ impl LibOSName {
 // Trait Implementations
 //======================================================================================================================
 
-/// Conversion trait implementation for LibOSName.
-impl From<String> for LibOSName {
-    fn from(str: String) -> Self {
+/// Parsing trait implementation for LibOSName.
+impl FromStr for LibOSName {
+    type Err = Fail;
+
+    fn from_str(str: &str) -> Result<Self, Self::Err> {
         match str.to_lowercase().as_str() {
-            "catpowder" => LibOSName::Catpowder,
-            "catnap" => LibOSName::Catnap,
-            "catnip" => LibOSName::Catnip,
-            "catmem" => LibOSName::Catmem,
-            "catloop" => LibOSName::Catloop,
-            _ => panic!("unknown libos"),
+            "catpowder" => Ok(LibOSName::Catpowder),
+            "catnap" => Ok(LibOSName::Catnap),
+            "catnip" => Ok(LibOSName::Catnip),
+            "catmem" => Ok(LibOSName::Catmem),
+            "catloop" => Ok(LibOSName::Catloop),
+            _ => {
+                let cause: String = format!("unknown libos (name={:?})", str);
+                error!("from_str(): {}", cause);
+                Err(Fail::new(libc::EINVAL, &cause))
+            },
         }
     }
 }
@@ -6,7 +6,10 @@
 //======================================================================================================================
 
 use crate::{
-    demikernel::libos::network::queue::SharedNetworkQueue,
+    demikernel::libos::network::queue::{
+        ConnectGuard,
+        SharedNetworkQueue,
+    },
     expect_ok,
     expect_some,
     pal::{
@@ -23,7 +26,6 @@ use crate::{
                 SocketId,
             },
             transport::NetworkTransport,
-            unwrap_socketaddr,
         },
         queue::{
             downcast_queue,
@@ -53,9 +55,10 @@ use ::socket2::{
 use ::std::{
     mem,
     net::{
+        IpAddr,
         Ipv4Addr,
+        Shutdown,
         SocketAddr,
-        SocketAddrV4,
     },
     ops::{
         Deref,
@@ -79,7 +82,8 @@ use crate::pal::linux::socketaddrv4_to_sockaddr;
 /// Catnap libOS. All state is kept in the [runtime] and [qtable].
 /// TODO: Move [qtable] into [runtime] so all state is contained in the PosixRuntime.
 pub struct NetworkLibOS<T: NetworkTransport> {
-    local_ipv4_addr: Ipv4Addr,
+    /// Local IPv4 addresses that sockets may bind to.
+    local_ipv4_addrs: Vec<Ipv4Addr>,
     /// Underlying runtime.
     runtime: SharedDemiRuntime,
     /// Underlying network transport.
@@ -96,9 +100,9 @@ pub struct SharedNetworkLibOS<T: NetworkTransport>(SharedObject<NetworkLibOS<T>>
 /// Associate Functions for Catnap LibOS
 impl<T: NetworkTransport> SharedNetworkLibOS<T> {
     /// Instantiates a Catnap LibOS.
-    pub fn new(local_ipv4_addr: Ipv4Addr, runtime: SharedDemiRuntime, transport: T) -> Self {
+    pub fn new(local_ipv4_addrs: Vec<Ipv4Addr>, runtime: SharedDemiRuntime, transport: T) -> Self {
         Self(SharedObject::new(NetworkLibOS::<T> {
-            local_ipv4_addr,
+            local_ipv4_addrs,
             runtime: runtime.clone(),
             transport,
         }))
@@ -110,7 +114,7 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
         trace!("socket() domain={:?}, type={:?}, protocol={:?}", domain, typ, _protocol);
 
         // Parse communication domain.
-        if domain != Domain::IPV4 {
+        if domain != Domain::IPV4 && domain != Domain::IPV6 {
             return Err(Fail::new(libc::ENOTSUP, "communication domain not supported"));
         }
 
@@ -144,31 +148,46 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
     }
 
     /// Gets the peer address connected to the scoket.
-    pub fn getpeername(&mut self, qd: QDesc) -> Result<SocketAddrV4, Fail> {
+    pub fn getpeername(&mut self, qd: QDesc) -> Result<SocketAddr, Fail> {
         trace!("getpeername() qd={:?}", qd);
 
         // Issue operation.
         self.get_shared_queue(&qd)?.getpeername()
     }
 
+    /// Gets the local address to which the socket is bound.
+    pub fn getsockname(&mut self, qd: QDesc) -> Result<SocketAddr, Fail> {
+        trace!("getsockname() qd={:?}", qd);
+
+        // Issue operation.
+        self.get_shared_queue(&qd)?.getsockname()
+    }
+
+    /// Shuts down receiving, sending or both on a connected socket.
+    pub fn shutdown(&mut self, qd: QDesc, how: Shutdown) -> Result<(), Fail> {
+        trace!("shutdown() qd={:?}, how={:?}", qd, how);
+
+        // Issue operation.
+        self.get_shared_queue(&qd)?.shutdown(how)
+    }
+
     /// Binds a socket to a local endpoint. This function contains the libOS-level functionality needed to bind a
     /// SharedNetworkQueue to a local address.
     pub fn bind(&mut self, qd: QDesc, mut local: SocketAddr) -> Result<(), Fail> {
         trace!("bind() qd={:?}, local={:?}", qd, local);
 
-        // We only support IPv4 addresses right now.
-        let localv4: SocketAddrV4 = unwrap_socketaddr(local)?;
-
-        // Check address that we are using to bind. We only support the wildcard address for UDP sockets right now.
+        // Check address that we are using to bind. We only support the wildcard address and multicast groups for UDP
+        // sockets right now. IPv6 addresses are checked against the local address by the network transport.
         // FIXME: https://github.com/demikernel/demikernel/issues/189
-        match *localv4.ip() {
-            Ipv4Addr::UNSPECIFIED if self.get_shared_queue(&qd)?.get_qtype() == QType::UdpSocket => (),
-            Ipv4Addr::UNSPECIFIED => {
+        match local.ip() {
+            addr if (addr.is_unspecified() || addr.is_multicast())
+                && self.get_shared_queue(&qd)?.get_qtype() == QType::UdpSocket => {},
+            addr if addr.is_unspecified() => {
                 let cause: String = format!("cannot bind to wildcard address (qd={:?})", qd);
                 error!("bind(): {}", cause);
                 return Err(Fail::new(libc::ENOTSUP, &cause));
             },
-            addr if addr != self.local_ipv4_addr => {
+            IpAddr::V4(addr) if !self.local_ipv4_addrs.contains(&addr) => {
                 let cause: String = format!("cannot bind to non-local address: {:?}", addr);
                 error!("bind(): {}", &cause);
                 return Err(Fail::new(libc::EADDRNOTAVAIL, &cause));
@@ -196,8 +215,9 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
             }
         }
 
-        // Check wether the address is in use.
-        if self.runtime.addr_in_use(localv4) {
+        // Check wether the address is in use. Sockets that allow address reuse leave this check to the transport, which
+        // knows whether every other socket that is bound to the address allows it as well.
+        if self.runtime.addr_in_use(local) && !self.reuses_addr(qd)? {
             let cause: String = format!("address is already bound to a socket (qd={:?}", qd);
             error!("bind(): {}", &cause);
             return Err(Fail::new(libc::EADDRINUSE, &cause));
@@ -214,8 +234,7 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
             Err(e)
         } else {
             // Insert into address to queue descriptor table.
-            self.runtime
-                .insert_socket_id_to_qd(SocketId::Passive(localv4.clone()), qd);
+            self.runtime.insert_socket_id_to_qd(SocketId::Passive(local), qd);
             Ok(())
         }
     }
@@ -247,7 +266,7 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
             let coroutine = Box::pin(self.clone().accept_coroutine(qd).fuse());
             self.runtime
                 .clone()
-                .insert_io_coroutine("ioc::network::libos::accept", coroutine)
+                .insert_io_coroutine("ioc::network::libos::accept", qd, coroutine)
         };
 
         queue.accept(coroutine_constructor)
@@ -266,18 +285,22 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
         };
         // Wait for the accept operation to complete.
         match queue.accept_coroutine().await {
-            Ok(new_queue) => {
+            Ok(mut new_queue) => {
                 // TODO: Do we need to add this to the socket id to queue descriptor table?
                 // It is safe to call except here because the new queue is connected and it should be connected to a
                 // remote address.
                 let addr: SocketAddr =
                     expect_some!(new_queue.remote(), "An accepted socket must have a remote address");
+                // The application could not learn the address of the connection, so do not hand it out.
+                if let Err(e) = socketaddr_to_sockaddr(&addr) {
+                    warn!("accept() listening_qd={:?}: {:?}", qd, &e);
+                    if let Err(e) = new_queue.hard_close() {
+                        error!("accept(): failed to close accepted socket (error={:?})", e);
+                    }
+                    return (qd, OperationResult::Failed(e));
+                }
                 let new_qd: QDesc = self.runtime.alloc_queue(new_queue);
-                // FIXME: add IPv6 support; https://github.com/microsoft/demikernel/issues/935
-                (
-                    qd,
-                    OperationResult::Accept((new_qd, expect_ok!(unwrap_socketaddr(addr), "we only support IPv4"))),
-                )
+                (qd, OperationResult::Accept((new_qd, addr)))
             },
             Err(e) => {
                 warn!("accept() listening_qd={:?}: {:?}", qd, &e);
@@ -295,10 +318,13 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
         // FIXME: add IPv6 support; https://github.com/microsoft/demikernel/issues/935
         let mut queue: SharedNetworkQueue<T> = self.get_shared_queue(&qd)?;
         let coroutine_constructor = || -> Result<QToken, Fail> {
-            let coroutine = Box::pin(self.clone().connect_coroutine(qd, remote).fuse());
+            // The guard is created here rather than in the coroutine, so that it also rolls back a connect that is
+            // cancelled before the coroutine first runs.
+            let guard: ConnectGuard<T> = ConnectGuard::new(self.get_shared_queue(&qd)?);
+            let coroutine = Box::pin(self.clone().connect_coroutine(qd, remote, guard).fuse());
             self.runtime
                 .clone()
-                .insert_io_coroutine("ioc::network::libos::connect", coroutine)
+                .insert_io_coroutine("ioc::network::libos::connect", qd, coroutine)
         };
 
         queue.connect(coroutine_constructor)
@@ -307,7 +333,12 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
     /// Asynchronous code to establish a connection to a remote endpoint. This function returns a coroutine that runs
     /// asynchronously to connect a queue and performs any necessary multi-queue operations at the libOS-level after
     /// the connect succeeds or fails.
-    async fn connect_coroutine(self, qd: QDesc, remote: SocketAddr) -> (QDesc, OperationResult) {
+    async fn connect_coroutine(
+        self,
+        qd: QDesc,
+        remote: SocketAddr,
+        _guard: ConnectGuard<T>,
+    ) -> (QDesc, OperationResult) {
         // Grab the queue, make sure it hasn't been closed in the meantime.
         // This will bump the Rc refcount so the coroutine can have it's own reference to the shared queue data
         // structure and the SharedNetworkQueue will not be freed until this coroutine finishes.
@@ -338,7 +369,7 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
             let coroutine = Box::pin(self.clone().close_coroutine(qd).fuse());
             self.runtime
                 .clone()
-                .insert_io_coroutine("ioc::network::libos::close", coroutine)
+                .insert_io_coroutine("ioc::network::libos::close", qd, coroutine)
         };
 
         queue.close(coroutine_constructor)
@@ -360,11 +391,10 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
             Ok(()) => {
                 // If the queue was bound, remove from the socket id to queue descriptor table.
                 if let Some(local) = queue.local() {
-                    // FIXME: add IPv6 support; https://github.com/microsoft/demikernel/issues/935
-                    self.runtime.remove_socket_id_to_qd(&SocketId::Passive(expect_ok!(
-                        unwrap_socketaddr(local),
-                        "we only support IPv4"
-                    )));
+                    // Other sockets may have bound to the same address since.
+                    if self.runtime.get_qd_from_socket_id(&SocketId::Passive(local)) == Some(qd) {
+                        self.runtime.remove_socket_id_to_qd(&SocketId::Passive(local));
+                    }
 
                     // Check if this is an ephemeral port.
                     if SharedDemiRuntime::is_private_ephemeral_port(local.port()) {
@@ -407,7 +437,7 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
             let coroutine = Box::pin(self.clone().push_coroutine(qd, buf).fuse());
             self.runtime
                 .clone()
-                .insert_io_coroutine("ioc::network::libos::push", coroutine)
+                .insert_io_coroutine("ioc::network::libos::push", qd, coroutine)
         };
 
         queue.push(coroutine_constructor)
@@ -450,7 +480,7 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
             let coroutine = Box::pin(self.clone().pushto_coroutine(qd, buf, remote).fuse());
             self.runtime
                 .clone()
-                .insert_io_coroutine("ioc::network::libos::pushto", coroutine)
+                .insert_io_coroutine("ioc::network::libos::pushto", qd, coroutine)
         };
 
         queue.push(coroutine_constructor)
@@ -489,7 +519,9 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
         let mut queue: SharedNetworkQueue<T> = self.get_shared_queue(&qd)?;
         let coroutine_constructor = || -> Result<QToken, Fail> {
             let coroutine = Box::pin(self.clone().pop_coroutine(qd, size).fuse());
-            self.runtime.clone().insert_io_coroutine("ioc::network::libos::pop", coroutine)
+            self.runtime
+                .clone()
+                .insert_io_coroutine("ioc::network::libos::pop", qd, coroutine)
         };
 
         queue.pop(coroutine_constructor)
@@ -509,11 +541,7 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
 
         // Wait for pop to complete.
         match queue.pop_coroutine(size).await {
-            // FIXME: add IPv6 support; https://github.com/microsoft/demikernel/issues/935
-            Ok((Some(addr), buf)) => (
-                qd,
-                OperationResult::Pop(Some(expect_ok!(unwrap_socketaddr(addr), "we only support IPv4")), buf),
-            ),
+            Ok((Some(addr), buf)) => (qd, OperationResult::Pop(Some(addr), buf)),
             Ok((None, buf)) => (qd, OperationResult::Pop(None, buf)),
             Err(e) => {
                 warn!("pop() qd={:?}: {:?}", qd, &e);
@@ -555,6 +583,12 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
             .wait_next_n(|qt, qd, result| acceptor(self.create_result(result, qd, qt)), timeout)
     }
 
+    /// Cancels a pending I/O operation, so that waiting on its queue token fails with ECANCELED.
+    pub fn cancel(&mut self, qt: QToken) -> Result<(), Fail> {
+        trace!("cancel(): qt={:?}", qt);
+        self.runtime.cancel(qt)
+    }
+
     pub fn create_result(&self, result: OperationResult, qd: QDesc, qt: QToken) -> demi_qresult_t {
         match result {
             OperationResult::Connect => demi_qresult_t {
@@ -565,7 +599,10 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
                 qr_value: unsafe { mem::zeroed() },
             },
             OperationResult::Accept((new_qd, addr)) => {
-                let saddr: SockAddr = socketaddrv4_to_sockaddr(&addr);
+                let saddr: SockAddr = match socketaddr_to_sockaddr(&addr) {
+                    Ok(saddr) => saddr,
+                    Err(e) => return self.create_result(OperationResult::Failed(e), qd, qt),
+                };
                 let qr_value: demi_qr_value_t = demi_qr_value_t {
                     ares: demi_accept_result_t {
                         qd: new_qd.into(),
@@ -590,7 +627,15 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
             OperationResult::Pop(addr, bytes) => match self.transport.into_sgarray(bytes) {
                 Ok(mut sga) => {
                     if let Some(addr) = addr {
-                        sga.sga_addr = socketaddrv4_to_sockaddr(&addr);
+                        match socketaddr_to_sockaddr(&addr) {
+                            Ok(saddr) => sga.sga_addr = saddr,
+                            Err(e) => {
+                                if let Err(e) = self.transport.sgafree(sga) {
+                                    error!("pop(): failed to release scatter-gather array (error={:?})", e);
+                                }
+                                return self.create_result(OperationResult::Failed(e), qd, qt);
+                            },
+                        }
                     }
                     let qr_value: demi_qr_value_t = demi_qr_value_t { sga };
                     demi_qresult_t {
@@ -647,6 +692,18 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
         self.transport.sgafree(sga)
     }
 
+    /// Queries whether or not the socket referred to by [qd] allows other sockets to bind to the same address.
+    fn reuses_addr(&self, qd: QDesc) -> Result<bool, Fail> {
+        match self
+            .get_shared_queue(&qd)?
+            .get_socket_option(SocketOption::ReuseAddr(false))
+        {
+            Ok(SocketOption::ReuseAddr(reuse_addr)) => Ok(reuse_addr),
+            // Sockets that do not support the option do not allow it.
+            _ => Ok(false),
+        }
+    }
+
     /// This function gets a shared queue reference out of the I/O queue table. The type if a ref counted pointer to the
     /// queue itself.
     fn get_shared_queue(&self, qd: &QDesc) -> Result<SharedNetworkQueue<T>, Fail> {
@@ -699,3 +756,20 @@ impl<T: NetworkTransport> DerefMut for SharedNetworkLibOS<T> {
         self.0.deref_mut()
     }
 }
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Converts [addr] into the socket address that is returned to the application. The C API only has room for IPv4
+/// addresses, so IPv6 addresses are not supported.
+fn socketaddr_to_sockaddr(addr: &SocketAddr) -> Result<SockAddr, Fail> {
+    match addr {
+        SocketAddr::V4(addr) => Ok(socketaddrv4_to_sockaddr(addr)),
+        SocketAddr::V6(addr) => {
+            let cause: String = format!("cannot return IPv6 address to the application (addr={:?})", addr);
+            warn!("socketaddr_to_sockaddr(): {}", cause);
+            Err(Fail::new(libc::EAFNOSUPPORT, &cause))
+        },
+    }
+}
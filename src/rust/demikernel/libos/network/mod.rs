@@ -24,12 +24,13 @@ use crate::{
         },
         QDesc,
         QToken,
+        SharedDemiRuntime,
     },
 };
 use ::std::{
     net::{
+        Shutdown,
         SocketAddr,
-        SocketAddrV4,
     },
     time::Duration,
 };
@@ -47,6 +48,7 @@ use crate::catnap::transport::SharedCatnapTransport;
 //======================================================================================================================
 
 /// Network LIBOS.
+#[derive(Clone)]
 pub enum NetworkLibOSWrapper {
     #[cfg(feature = "catpowder-libos")]
     Catpowder(SharedNetworkLibOS<SharedInetStack>),
@@ -112,7 +114,7 @@ impl NetworkLibOSWrapper {
     }
 
     /// Gets the address of the peer connected to the socket.
-    pub fn getpeername(&mut self, sockqd: QDesc) -> Result<SocketAddrV4, Fail> {
+    pub fn getpeername(&mut self, sockqd: QDesc) -> Result<SocketAddr, Fail> {
         match self {
             #[cfg(feature = "catpowder-libos")]
             NetworkLibOSWrapper::Catpowder(libos) => libos.getpeername(sockqd),
@@ -125,6 +127,34 @@ impl NetworkLibOSWrapper {
         }
     }
 
+    /// Gets the local address to which the socket is bound.
+    pub fn getsockname(&mut self, sockqd: QDesc) -> Result<SocketAddr, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.getsockname(sockqd),
+            #[cfg(all(feature = "catnap-libos"))]
+            NetworkLibOSWrapper::Catnap(libos) => libos.getsockname(sockqd),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.getsockname(sockqd),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.getsockname(sockqd),
+        }
+    }
+
+    /// Shuts down receiving, sending or both on a connected socket.
+    pub fn shutdown(&mut self, sockqd: QDesc, how: Shutdown) -> Result<(), Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.shutdown(sockqd, how),
+            #[cfg(all(feature = "catnap-libos"))]
+            NetworkLibOSWrapper::Catnap(libos) => libos.shutdown(sockqd, how),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.shutdown(sockqd, how),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.shutdown(sockqd, how),
+        }
+    }
+
     /// Binds a socket to a local address.
     pub fn bind(&mut self, sockqd: QDesc, local: SocketAddr) -> Result<(), Fail> {
         match self {
@@ -300,6 +330,34 @@ impl NetworkLibOSWrapper {
         }
     }
 
+    /// Cancels a pending I/O operation.
+    pub fn cancel(&mut self, qt: QToken) -> Result<(), Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.cancel(qt),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.cancel(qt),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.cancel(qt),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.cancel(qt),
+        }
+    }
+
+    /// Returns the runtime of the LibOS.
+    pub fn get_runtime(&self) -> SharedDemiRuntime {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.get_runtime(),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.get_runtime(),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.get_runtime(),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.get_runtime(),
+        }
+    }
+
     /// Waits for any operation in an I/O queue.
     pub fn poll(&mut self) {
         match self {
@@ -36,8 +36,8 @@ use ::socket2::{
 use ::std::{
     any::Any,
     net::{
+        Shutdown,
         SocketAddr,
-        SocketAddrV4,
     },
     ops::{
         Deref,
@@ -69,6 +69,10 @@ pub struct NetworkQueue<T: NetworkTransport> {
 #[derive(Clone)]
 pub struct SharedNetworkQueue<T: NetworkTransport>(SharedObject<NetworkQueue<T>>);
 
+/// Rolls back the connect of a queue if the coroutine that runs it is dropped before the connect completes, which
+/// happens when the connect is cancelled.
+pub struct ConnectGuard<T: NetworkTransport>(SharedNetworkQueue<T>);
+
 //======================================================================================================================
 // Associated Functions
 //======================================================================================================================
@@ -115,10 +119,21 @@ impl<T: NetworkTransport> SharedNetworkQueue<T> {
     }
 
     /// Gets the peer address connected to the socket.
-    pub fn getpeername(&mut self) -> Result<SocketAddrV4, Fail> {
+    pub fn getpeername(&mut self) -> Result<SocketAddr, Fail> {
         self.transport.clone().getpeername(&mut self.socket)
     }
 
+    /// Gets the local address to which the socket is bound.
+    pub fn getsockname(&mut self) -> Result<SocketAddr, Fail> {
+        self.transport.clone().getsockname(&mut self.socket)
+    }
+
+    /// Shuts down receiving, sending or both on the connected queue.
+    pub fn shutdown(&mut self, how: Shutdown) -> Result<(), Fail> {
+        self.state_machine.may_shutdown()?;
+        self.transport.clone().shutdown(&mut self.socket, how)
+    }
+
     /// Binds the target queue to `local` address.
     pub fn bind(&mut self, local: SocketAddr) -> Result<(), Fail> {
         self.state_machine.prepare(SocketOp::Bind)?;
@@ -249,6 +264,26 @@ impl<T: NetworkTransport> SharedNetworkQueue<T> {
         }
     }
 
+    /// Rolls back a connect that was cancelled before it completed, so that this queue can connect again. If the
+    /// transport cannot roll back the connect, the queue can only be closed.
+    fn cancel_connect(&mut self) {
+        if !self.state_machine.is_connecting() {
+            return;
+        }
+        match self.transport.clone().cancel_connect(&mut self.socket) {
+            Ok(()) => {
+                let bound: bool = self.local.is_some();
+                self.state_machine.cancel_connect(bound);
+            },
+            Err(e) => {
+                warn!("cancel_connect(): socket can only be closed (error={:?})", e.cause);
+                if self.state_machine.prepare(SocketOp::Closed).is_ok() {
+                    self.state_machine.commit();
+                }
+            },
+        }
+    }
+
     /// Start an asynchronous coroutine to close this queue.
     pub fn close<F>(&mut self, coroutine_constructor: F) -> Result<QToken, Fail>
     where
@@ -402,6 +437,19 @@ impl<T: NetworkTransport> IoQueue for SharedNetworkQueue<T> {
     }
 }
 
+impl<T: NetworkTransport> ConnectGuard<T> {
+    pub fn new(queue: SharedNetworkQueue<T>) -> Self {
+        Self(queue)
+    }
+}
+
+impl<T: NetworkTransport> Drop for ConnectGuard<T> {
+    fn drop(&mut self) {
+        // A connect that completed, failed or was interrupted by a close has already left the connecting state.
+        self.0.cancel_connect();
+    }
+}
+
 impl<T: NetworkTransport> Deref for SharedNetworkQueue<T> {
     type Target = NetworkQueue<T>;
 
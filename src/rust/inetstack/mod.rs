@@ -37,7 +37,10 @@ use ::socket2::{
 use ::std::{
     collections::HashMap,
     hash::RandomState,
-    net::Ipv4Addr,
+    net::{
+        Ipv4Addr,
+        Ipv6Addr,
+    },
     time::Duration,
 };
 use protocols::{
@@ -50,8 +53,8 @@ use ::futures::FutureExt;
 use ::std::{
     fmt::Debug,
     net::{
+        Shutdown,
         SocketAddr,
-        SocketAddrV4,
     },
     ops::{
         Deref,
@@ -112,7 +115,7 @@ impl SharedInetStack {
         let layer2_endpoint: SharedLayer2Endpoint = SharedLayer2Endpoint::new(config, layer1_endpoint)?;
         let layer3_endpoint: SharedLayer3Endpoint =
             SharedLayer3Endpoint::new(config, runtime.clone(), layer2_endpoint, rng_seed)?;
-        let layer4_endpoint: Peer = Peer::new(config, runtime.clone(), layer3_endpoint, rng_seed)?;
+        let layer4_endpoint: Peer = Peer::new(config, runtime.clone(), layer3_endpoint)?;
         let me: Self = Self(SharedObject::<InetStack>::new(InetStack {
             runtime: runtime.clone(),
             layer4_endpoint,
@@ -149,6 +152,16 @@ impl SharedInetStack {
     pub fn export_arp_cache(&self) -> HashMap<Ipv4Addr, MacAddress, RandomState> {
         self.layer4_endpoint.export_arp_cache()
     }
+
+    #[cfg(test)]
+    pub async fn ndp_query(&mut self, addr: Ipv6Addr) -> Result<MacAddress, Fail> {
+        self.layer4_endpoint.ndp_query(addr).await
+    }
+
+    #[cfg(test)]
+    pub fn export_neighbor_cache(&self) -> HashMap<Ipv6Addr, MacAddress, RandomState> {
+        self.layer4_endpoint.export_neighbor_cache()
+    }
 }
 
 //======================================================================================================================
@@ -211,10 +224,19 @@ impl NetworkTransport for SharedInetStack {
         self.layer4_endpoint.get_socket_option(sd, option)
     }
 
-    fn getpeername(&mut self, sd: &mut Self::SocketDescriptor) -> Result<SocketAddrV4, Fail> {
+    fn getpeername(&mut self, sd: &mut Self::SocketDescriptor) -> Result<SocketAddr, Fail> {
         self.layer4_endpoint.getpeername(sd)
     }
 
+    fn getsockname(&mut self, sd: &mut Self::SocketDescriptor) -> Result<SocketAddr, Fail> {
+        self.layer4_endpoint.getsockname(sd)
+    }
+
+    /// Shuts down receiving, sending or both on a connected socket.
+    fn shutdown(&mut self, sd: &mut Self::SocketDescriptor, how: Shutdown) -> Result<(), Fail> {
+        self.layer4_endpoint.shutdown(sd, how)
+    }
+
     ///
     /// **Brief**
     ///
@@ -282,6 +304,11 @@ impl NetworkTransport for SharedInetStack {
         self.layer4_endpoint.connect(sd, remote).await
     }
 
+    /// Rolls back a connect that was cancelled before it completed.
+    fn cancel_connect(&mut self, sd: &mut Self::SocketDescriptor) -> Result<(), Fail> {
+        self.layer4_endpoint.cancel_connect(sd)
+    }
+
     ///
     /// **Brief**
     ///
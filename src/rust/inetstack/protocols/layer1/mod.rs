@@ -18,7 +18,10 @@ use crate::runtime::{
         DemiBuffer,
         MemoryRuntime,
     },
-    network::consts::RECEIVE_BATCH_SIZE,
+    network::{
+        consts::RECEIVE_BATCH_SIZE,
+        types::MacAddress,
+    },
 };
 
 //======================================================================================================================
@@ -33,4 +36,15 @@ pub trait PhysicalLayer: 'static + MemoryRuntime {
 
     /// Receives a batch of [DemiBuffer].
     fn receive(&mut self) -> Result<ArrayVec<DemiBuffer, RECEIVE_BATCH_SIZE>, Fail>;
+
+    /// Starts receiving frames that are sent to the multicast [link_addr]. Hardware that already hands us every frame
+    /// need not do anything.
+    fn join_multicast_group(&mut self, _link_addr: MacAddress) -> Result<(), Fail> {
+        Ok(())
+    }
+
+    /// Stops receiving frames that are sent to the multicast [link_addr].
+    fn leave_multicast_group(&mut self, _link_addr: MacAddress) -> Result<(), Fail> {
+        Ok(())
+    }
 }
@@ -37,9 +37,13 @@ use crate::{
     },
 };
 use ::arrayvec::ArrayVec;
-use ::std::ops::{
-    Deref,
-    DerefMut,
+use ::std::{
+    collections::HashMap,
+    net::Ipv4Addr,
+    ops::{
+        Deref,
+        DerefMut,
+    },
 };
 
 //======================================================================================================================
@@ -49,6 +53,8 @@ use ::std::ops::{
 pub struct Layer2Endpoint {
     layer1_endpoint: Box<dyn PhysicalLayer>,
     local_link_addr: MacAddress,
+    /// Multicast link addresses that we receive frames for, along with the number of groups that map to each of them.
+    multicast_link_addrs: HashMap<MacAddress, usize>,
 }
 
 #[derive(Clone)]
@@ -62,7 +68,10 @@ impl SharedLayer2Endpoint {
     pub fn new<P: PhysicalLayer>(config: &Config, layer1_endpoint: P) -> Result<Self, Fail> {
         Ok(Self(SharedObject::new(Layer2Endpoint {
             layer1_endpoint: Box::new(layer1_endpoint),
-            local_link_addr: config.local_link_addr()?,
+            local_link_addr: config
+                .local_link_addr()
+                .ok_or_else(|| Config::missing_option("local_link_addr"))?,
+            multicast_link_addrs: HashMap::new(),
         })))
     }
 
@@ -99,6 +108,10 @@ impl SharedLayer2Endpoint {
         self.transmit(remote_link_addr, EtherType2::Ipv4, pkt)
     }
 
+    pub fn transmit_ipv6_packet(&mut self, remote_link_addr: MacAddress, pkt: DemiBuffer) -> Result<(), Fail> {
+        self.transmit(remote_link_addr, EtherType2::Ipv6, pkt)
+    }
+
     fn transmit(
         &mut self,
         remote_link_addr: MacAddress,
@@ -113,6 +126,48 @@ impl SharedLayer2Endpoint {
     pub fn get_local_link_addr(&self) -> MacAddress {
         self.local_link_addr
     }
+
+    /// Starts receiving frames that are sent to the multicast [link_addr]. Several network-layer groups may map to the
+    /// same link address, so we only program the hardware for the first one.
+    pub fn join_multicast_group(&mut self, link_addr: MacAddress) -> Result<(), Fail> {
+        if let Some(count) = self.multicast_link_addrs.get_mut(&link_addr) {
+            *count += 1;
+            return Ok(());
+        }
+        self.layer1_endpoint.join_multicast_group(link_addr)?;
+        self.multicast_link_addrs.insert(link_addr, 1);
+        Ok(())
+    }
+
+    /// Stops receiving frames that are sent to the multicast [link_addr], once no network-layer group maps to it.
+    pub fn leave_multicast_group(&mut self, link_addr: MacAddress) -> Result<(), Fail> {
+        match self.multicast_link_addrs.get_mut(&link_addr) {
+            Some(count) if *count > 1 => {
+                *count -= 1;
+                Ok(())
+            },
+            Some(_) => {
+                self.multicast_link_addrs.remove(&link_addr);
+                self.layer1_endpoint.leave_multicast_group(link_addr)
+            },
+            None => {
+                let cause: String = format!("not a member of multicast group (link_addr={:?})", link_addr);
+                warn!("leave_multicast_group(): {}", cause);
+                Err(Fail::new(libc::EADDRNOTAVAIL, &cause))
+            },
+        }
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Computes the Ethernet address that the multicast [ipv4_addr] maps to, which carries its low-order 23 bits (see RFC
+/// 1112 Section 6.4).
+pub fn ipv4_multicast_link_addr(ipv4_addr: Ipv4Addr) -> MacAddress {
+    let octets: [u8; 4] = ipv4_addr.octets();
+    MacAddress::new([0x01, 0x00, 0x5e, octets[1] & 0x7f, octets[2], octets[3]])
 }
 
 //======================================================================================================================
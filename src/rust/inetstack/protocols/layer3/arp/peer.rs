@@ -62,7 +62,10 @@ use ::std::{
 ///
 pub struct ArpPeer {
     layer2_endpoint: SharedLayer2Endpoint,
+    /// Primary local IPv4 address, which we send our own requests from.
     local_ipv4_addr: Ipv4Addr,
+    /// All local IPv4 addresses, which we answer requests for.
+    local_ipv4_addrs: Vec<Ipv4Addr>,
     cache: ArpCache,
     waiters: HashMap<Ipv4Addr, LinkedList<Sender<MacAddress>>>,
     arp_config: ArpConfig,
@@ -95,7 +98,10 @@ impl SharedArpPeer {
 
         let peer: SharedArpPeer = Self(SharedObject::new(ArpPeer {
             layer2_endpoint,
-            local_ipv4_addr: config.local_ipv4_addr()?,
+            local_ipv4_addr: config
+                .local_ipv4_addr()
+                .ok_or_else(|| Config::missing_option("local_ipv4_addr"))?,
+            local_ipv4_addrs: config.local_ipv4_addrs(),
             cache,
             waiters: HashMap::default(),
             arp_config,
@@ -189,7 +195,7 @@ impl SharedArpPeer {
                 }
             };
             // from RFC 826: ?Am I the target protocol address?
-            if header.get_destination_protocol_addr() != self.local_ipv4_addr {
+            if !self.local_ipv4_addrs.contains(&header.get_destination_protocol_addr()) {
                 if !merge_flag {
                     // we didn't do something.
                     let cause: String = format!("unrecognized IP address");
@@ -223,7 +229,7 @@ impl SharedArpPeer {
                     let reply_hdr: ArpHeader = ArpHeader::new(
                         ArpOperation::Reply,
                         self.layer2_endpoint.get_local_link_addr(),
-                        self.local_ipv4_addr,
+                        header.get_destination_protocol_addr(),
                         header.get_sender_hardware_addr(),
                         header.get_sender_protocol_addr(),
                     );
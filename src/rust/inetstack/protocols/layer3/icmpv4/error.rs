@@ -0,0 +1,124 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::protocols::layer3::{
+        icmpv4::protocol::{
+            ICMPV4_UNREACHABLE_FRAGMENTATION_NEEDED,
+            ICMPV4_UNREACHABLE_PORT,
+            ICMPV4_UNREACHABLE_PROTOCOL,
+        },
+        ip::IpProtocol,
+        ipv4::Ipv4Header,
+    },
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+    },
+};
+use ::libc::{
+    EBADMSG,
+    ECONNREFUSED,
+    EHOSTUNREACH,
+};
+use ::std::net::SocketAddrV4;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Minimum number of payload bytes of the datagram that is embedded in an ICMPv4 error message (see RFC 792).
+const EMBEDDED_PAYLOAD_MIN_SIZE: usize = 8;
+
+/// Common MTUs to fall back on when a router does not report the MTU of the next hop (see RFC 1191 Section 7).
+const MTU_PLATEAUS: [u16; 10] = [32000, 17914, 8166, 4352, 2002, 1492, 1006, 508, 296, 68];
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Error that an ICMPv4 Destination Unreachable message reports about a datagram that we sent.
+#[derive(Clone, Debug)]
+pub struct Icmpv4Error {
+    /// Code of the Destination Unreachable message.
+    code: u8,
+    /// MTU of the next hop, as reported in Fragmentation Needed messages.
+    next_hop_mtu: u16,
+    /// Header of the datagram that we sent.
+    header: Ipv4Header,
+    /// Leading bytes of the payload of the datagram that we sent.
+    payload: DemiBuffer,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Icmpv4Error {
+    /// Parses the datagram that is embedded in a Destination Unreachable message with [code] and [next_hop_mtu] from
+    /// the message body in [buf].
+    pub fn parse(code: u8, next_hop_mtu: u16, mut buf: DemiBuffer) -> Result<Self, Fail> {
+        let header: Ipv4Header = Ipv4Header::parse_and_strip_embedded(&mut buf)?;
+        if buf.len() < EMBEDDED_PAYLOAD_MIN_SIZE {
+            return Err(Fail::new(EBADMSG, "embedded datagram too small"));
+        }
+        Ok(Self {
+            code,
+            next_hop_mtu,
+            header,
+            payload: buf,
+        })
+    }
+
+    /// Returns the protocol of the datagram that we sent.
+    pub fn get_protocol(&self) -> IpProtocol {
+        self.header.get_protocol()
+    }
+
+    /// Returns the local and remote endpoints of the datagram that we sent. This only makes sense for transport
+    /// protocols whose header starts with the source and destination ports, like TCP and UDP.
+    pub fn get_endpoints(&self) -> (SocketAddrV4, SocketAddrV4) {
+        let src_port: u16 = u16::from_be_bytes([self.payload[0], self.payload[1]]);
+        let dst_port: u16 = u16::from_be_bytes([self.payload[2], self.payload[3]]);
+        (
+            SocketAddrV4::new(self.header.get_src_addr(), src_port),
+            SocketAddrV4::new(self.header.get_dest_addr(), dst_port),
+        )
+    }
+
+    /// Returns the leading bytes of the payload of the datagram that we sent. There are at least 8 of them.
+    pub fn get_payload(&self) -> &[u8] {
+        &self.payload[..]
+    }
+
+    /// Returns the error that operations on the affected socket should fail with, or None if the message does not
+    /// report a failure. Refusals by the remote host map to ECONNREFUSED and anything else to EHOSTUNREACH.
+    pub fn get_errno(&self) -> Option<libc::c_int> {
+        match self.code {
+            ICMPV4_UNREACHABLE_PROTOCOL | ICMPV4_UNREACHABLE_PORT => Some(ECONNREFUSED),
+            ICMPV4_UNREACHABLE_FRAGMENTATION_NEEDED => None,
+            _ => Some(EHOSTUNREACH),
+        }
+    }
+
+    /// Returns the path MTU that a Fragmentation Needed message reports, or None for other messages. Routers that
+    /// predate RFC 1191 do not fill in the MTU of the next hop, so we guess it from the size of our datagram.
+    pub fn get_next_hop_mtu(&self) -> Option<u16> {
+        if self.code != ICMPV4_UNREACHABLE_FRAGMENTATION_NEEDED {
+            return None;
+        }
+        if self.next_hop_mtu != 0 {
+            return Some(self.next_hop_mtu);
+        }
+        let total_length: u16 = self.header.get_total_length();
+        MTU_PLATEAUS
+            .iter()
+            .find(|plateau| **plateau < total_length)
+            .copied()
+            .or(Some(MTU_PLATEAUS[MTU_PLATEAUS.len() - 1]))
+    }
+}
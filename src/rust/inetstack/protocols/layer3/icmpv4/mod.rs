@@ -1,12 +1,24 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
+mod error;
 mod header;
 mod peer;
 mod protocol;
+mod ratelimit;
 
 // Disable for now due to incorrect use of scheduler.
 // #[cfg(test)]
 // mod tests;
 
-pub use peer::SharedIcmpv4Peer;
+pub use self::{
+    error::Icmpv4Error,
+    peer::SharedIcmpv4Peer,
+    protocol::{
+        ICMPV4_UNREACHABLE_FRAGMENTATION_NEEDED,
+        ICMPV4_UNREACHABLE_HOST,
+        ICMPV4_UNREACHABLE_NET,
+        ICMPV4_UNREACHABLE_PORT,
+        ICMPV4_UNREACHABLE_PROTOCOL,
+    },
+};
@@ -12,6 +12,7 @@ use crate::{
         layer3::{
             arp::SharedArpPeer,
             icmpv4::{
+                error::Icmpv4Error,
                 header::{
                     Icmpv4Header,
                     ICMPV4_HEADER_SIZE,
@@ -20,12 +21,14 @@ use crate::{
                     Icmpv4Type2,
                     ICMPV4_ECHO_REQUEST_MESSAGE_SIZE,
                 },
+                ratelimit::Icmpv4RateLimiter,
             },
             ip::IpProtocol,
             ipv4::{
                 Ipv4Header,
                 IPV4_HEADER_MIN_SIZE,
             },
+            route::RoutingTable,
         },
     },
     runtime::{
@@ -45,7 +48,10 @@ use ::rand::{
     SeedableRng,
 };
 use ::std::{
-    collections::HashMap,
+    collections::{
+        HashMap,
+        VecDeque,
+    },
     net::Ipv4Addr,
     num::Wrapping,
     ops::{
@@ -62,6 +68,18 @@ use ::std::{
 /// Arbitrary time out for waiting for pings.
 const PING_TIMEOUT: Duration = Duration::from_secs(5);
 
+/// Maximum size of the ICMPv4 error messages that we send (see RFC 1812 Section 4.3.2.3).
+const ICMPV4_ERROR_MAX_SIZE: usize = 576;
+
+/// Maximum number of received ICMPv4 errors that we hold until the transport layer picks them up.
+const ICMPV4_ERROR_QUEUE_MAX_SIZE: usize = 64;
+
+/// Default number of ICMPv4 error messages that we may send per second.
+const DEFAULT_ICMPV4_ERROR_RATE: u32 = 1000;
+
+/// Default number of ICMPv4 error messages that we may send back to back.
+const DEFAULT_ICMPV4_ERROR_BURST: u32 = 50;
+
 //======================================================================================================================
 // Icmpv4Peer
 //======================================================================================================================
@@ -86,13 +104,23 @@ pub struct Icmpv4Peer {
     runtime: SharedDemiRuntime,
     /// Underlying Network Transport
     layer2_endpoint: SharedLayer2Endpoint,
+    /// Primary local IPv4 address, which we send our own requests from.
     local_ipv4_addr: Ipv4Addr,
 
     /// Underlying ARP Peer
     arp: SharedArpPeer,
 
+    /// Next hops of IPv4 destinations.
+    routing_table: RoutingTable,
+
     /// Incoming packets
-    recv_queue: AsyncQueue<(Ipv4Header, DemiBuffer)>,
+    recv_queue: AsyncQueue<(Ipv4Header, Icmpv4Header, DemiBuffer)>,
+
+    /// Incoming errors about datagrams that we sent, for the transport layer.
+    errors: VecDeque<Icmpv4Error>,
+
+    /// Limits the rate of the error messages that we send.
+    error_rate_limiter: Icmpv4RateLimiter,
 
     /// Sequence Number
     seq: Wrapping<u16>,
@@ -113,15 +141,26 @@ impl SharedIcmpv4Peer {
         mut runtime: SharedDemiRuntime,
         layer2_endpoint: SharedLayer2Endpoint,
         arp: SharedArpPeer,
+        routing_table: RoutingTable,
         rng_seed: [u8; 32],
     ) -> Result<Self, Fail> {
         let rng: SmallRng = SmallRng::from_seed(rng_seed);
+        let error_rate_limiter: Icmpv4RateLimiter = Icmpv4RateLimiter::new(
+            config.icmpv4_error_rate().unwrap_or(DEFAULT_ICMPV4_ERROR_RATE),
+            config.icmpv4_error_burst().unwrap_or(DEFAULT_ICMPV4_ERROR_BURST),
+            runtime.get_now(),
+        );
         let peer: SharedIcmpv4Peer = Self(SharedObject::new(Icmpv4Peer {
             runtime: runtime.clone(),
             layer2_endpoint: layer2_endpoint.clone(),
-            local_ipv4_addr: config.local_ipv4_addr()?,
+            local_ipv4_addr: config
+                .local_ipv4_addr()
+                .ok_or_else(|| Config::missing_option("local_ipv4_addr"))?,
             arp: arp.clone(),
-            recv_queue: AsyncQueue::<(Ipv4Header, DemiBuffer)>::default(),
+            routing_table,
+            recv_queue: AsyncQueue::<(Ipv4Header, Icmpv4Header, DemiBuffer)>::default(),
+            errors: VecDeque::<Icmpv4Error>::new(),
+            error_rate_limiter,
             seq: Wrapping(0),
             rng,
             inflight: HashMap::<(u16, u16), InflightRequest>::new(),
@@ -134,19 +173,11 @@ impl SharedIcmpv4Peer {
     /// Background task for replying to ICMP messages.
     async fn poll(mut self) {
         loop {
-            let (ipv4_hdr, mut buf): (Ipv4Header, DemiBuffer) = match self.recv_queue.pop(Some(PING_TIMEOUT)).await {
-                Ok(result) => result,
-                Err(_) => break,
-            };
-            let icmpv4_hdr: Icmpv4Header = match Icmpv4Header::parse_and_strip(&mut buf) {
-                Ok(header) => header,
-                Err(e) => {
-                    let cause = "Cannot parse ICMP header";
-                    warn!("{}: {:?}", cause, e);
-                    continue;
-                },
-            };
-            debug!("ICMPv4 received {:?}", icmpv4_hdr);
+            let (ipv4_hdr, icmpv4_hdr, mut buf): (Ipv4Header, Icmpv4Header, DemiBuffer) =
+                match self.recv_queue.pop(Some(PING_TIMEOUT)).await {
+                    Ok(result) => result,
+                    Err(_) => break,
+                };
             let (id, seq_num, dst_ipv4_addr) = match icmpv4_hdr.get_protocol() {
                 Icmpv4Type2::EchoRequest { id, seq_num } => (id, seq_num, ipv4_hdr.get_src_addr()),
                 Icmpv4Type2::EchoReply { id, seq_num } => {
@@ -163,7 +194,8 @@ impl SharedIcmpv4Peer {
                 },
             };
             debug!("initiating ARP query");
-            let dst_link_addr: MacAddress = match self.arp.query(dst_ipv4_addr).await {
+            let (next_hop_addr, _): (Ipv4Addr, u16) = self.routing_table.lookup(dst_ipv4_addr);
+            let dst_link_addr: MacAddress = match self.arp.query(next_hop_addr).await {
                 Ok(dst_link_addr) => dst_link_addr,
                 Err(e) => {
                     warn!("reply_to_ping({}, {}, {}) failed: {:?}", dst_ipv4_addr, id, seq_num, e);
@@ -172,8 +204,11 @@ impl SharedIcmpv4Peer {
             };
             debug!("ARP query complete ({} -> {})", dst_ipv4_addr, dst_link_addr);
             debug!("reply ping ({}, {}, {})", dst_ipv4_addr, id, seq_num);
-            // Send reply message.
-            let local_ipv4_addr: Ipv4Addr = self.local_ipv4_addr;
+            // Send reply message from the address that the request went to, unless it was a broadcast.
+            let local_ipv4_addr: Ipv4Addr = match ipv4_hdr.get_dest_addr() {
+                addr if addr.is_broadcast() || self.routing_table.is_directed_broadcast(addr) => self.local_ipv4_addr,
+                addr => addr,
+            };
             let icmp_hdr: Icmpv4Header = Icmpv4Header::new(Icmpv4Type2::EchoReply { id, seq_num }, 0);
             icmp_hdr.serialize_and_attach(&mut buf);
             let ipv4_hdr: Ipv4Header = Ipv4Header::new(local_ipv4_addr, dst_ipv4_addr, IpProtocol::ICMPv4);
@@ -185,9 +220,81 @@ impl SharedIcmpv4Peer {
         }
     }
 
-    /// Parses and handles a ICMP message.
-    pub fn receive(&mut self, ipv4_hdr: Ipv4Header, buf: DemiBuffer) {
-        self.recv_queue.push((ipv4_hdr, buf));
+    /// Parses and handles a ICMP message. Errors about datagrams that we sent are held until the transport layer picks
+    /// them up, everything else is handled in the background.
+    pub fn receive(&mut self, ipv4_hdr: Ipv4Header, mut buf: DemiBuffer) {
+        let icmpv4_hdr: Icmpv4Header = match Icmpv4Header::parse_and_strip(&mut buf) {
+            Ok(header) => header,
+            Err(e) => {
+                let cause = "Cannot parse ICMP header";
+                warn!("{}: {:?}", cause, e);
+                return;
+            },
+        };
+        debug!("ICMPv4 received {:?}", icmpv4_hdr);
+        match icmpv4_hdr.get_protocol() {
+            Icmpv4Type2::DestinationUnreachable { next_hop_mtu } => {
+                let error: Icmpv4Error = match Icmpv4Error::parse(icmpv4_hdr.get_code(), next_hop_mtu, buf) {
+                    Ok(error) => error,
+                    Err(e) => {
+                        warn!("dropping destination unreachable message: {:?}", e);
+                        return;
+                    },
+                };
+                if self.errors.len() >= ICMPV4_ERROR_QUEUE_MAX_SIZE {
+                    warn!("dropping destination unreachable message: too many pending errors");
+                    return;
+                }
+                self.errors.push_back(error);
+            },
+            _ => self.recv_queue.push((ipv4_hdr, icmpv4_hdr, buf)),
+        }
+    }
+
+    /// Takes the oldest error about a datagram that we sent, if any.
+    pub fn pop_error(&mut self) -> Option<Icmpv4Error> {
+        self.errors.pop_front()
+    }
+
+    /// Tells the source of the datagram with [quoted_hdr] that we could not deliver it (see RFC 792). [payload] holds
+    /// the payload of that datagram, which we quote in the message along with its header. This is best-effort: we do
+    /// not wait on ARP for it, and we drop the message if we already sent too many of them lately (see RFC 1812
+    /// Section 4.3.2.8).
+    pub fn send_destination_unreachable(
+        &mut self,
+        code: u8,
+        quoted_hdr: &Ipv4Header,
+        payload: &DemiBuffer,
+    ) -> Result<(), Fail> {
+        let now: Instant = self.runtime.get_now();
+        if !self.error_rate_limiter.try_consume(now) {
+            debug!("send_destination_unreachable(): rate limit exceeded, dropping message");
+            return Ok(());
+        }
+        let local_ipv4_addr: Ipv4Addr = quoted_hdr.get_dest_addr();
+        let remote_ipv4_addr: Ipv4Addr = quoted_hdr.get_src_addr();
+        let (next_hop_addr, _): (Ipv4Addr, u16) = self.routing_table.lookup(remote_ipv4_addr);
+        let dst_link_addr: MacAddress = match self.arp.try_query(next_hop_addr) {
+            Some(addr) => addr,
+            None => return Err(Fail::new(libc::EAGAIN, "destination not in ARP cache")),
+        };
+
+        // Quote the header of the datagram and as much of its payload as fits, which is at least the 64 bits that our
+        // peer needs to find the socket that sent it (see RFC 1812 Section 4.3.2.3).
+        let max_payload_size: usize = ICMPV4_ERROR_MAX_SIZE - ICMPV4_HEADER_SIZE - 2 * (IPV4_HEADER_MIN_SIZE as usize);
+        let quoted_size: usize = payload.len().min(max_payload_size);
+        let mut pkt: DemiBuffer = DemiBuffer::new_with_headroom(
+            quoted_size as u16,
+            (IPV4_HEADER_MIN_SIZE as usize * 2 + ICMPV4_HEADER_SIZE + ETHERNET2_HEADER_SIZE) as u16,
+        );
+        pkt.copy_from_slice(&payload[..quoted_size]);
+        quoted_hdr.serialize_embedded_and_attach(&mut pkt);
+
+        let icmp_hdr: Icmpv4Header = Icmpv4Header::new(Icmpv4Type2::DestinationUnreachable { next_hop_mtu: 0 }, code);
+        icmp_hdr.serialize_and_attach(&mut pkt);
+        let ipv4_hdr: Ipv4Header = Ipv4Header::new(local_ipv4_addr, remote_ipv4_addr, IpProtocol::ICMPv4);
+        ipv4_hdr.serialize_and_attach(&mut pkt);
+        self.layer2_endpoint.transmit_ipv4_packet(dst_link_addr, pkt)
     }
 
     /// Computes the identifier for an ICMP message.
@@ -226,7 +333,8 @@ impl SharedIcmpv4Peer {
 
         let t0: Instant = self.runtime.get_now();
         debug!("initiating ARP query");
-        let dst_link_addr: MacAddress = self.arp.query(dst_ipv4_addr).await?;
+        let (next_hop_addr, _): (Ipv4Addr, u16) = self.routing_table.lookup(dst_ipv4_addr);
+        let dst_link_addr: MacAddress = self.arp.query(next_hop_addr).await?;
         debug!("ARP query complete ({} -> {})", dst_ipv4_addr, dst_link_addr);
 
         let mut pkt: DemiBuffer = DemiBuffer::new_with_headroom(
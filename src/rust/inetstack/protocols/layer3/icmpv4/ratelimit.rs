@@ -0,0 +1,94 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+use ::std::time::Instant;
+
+// Token-bucket rate limiter for the ICMPv4 error messages that we send (see RFC 1812 Section 4.3.2.8).
+//
+// Tokens accumulate at the configured rate, up to the burst size, and every message that we send takes one token out
+// of the bucket.  Messages that find the bucket empty are dropped.  Tokens are kept in message-nanoseconds so that
+// refilling is exact.
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+#[derive(Debug)]
+pub struct Icmpv4RateLimiter {
+    // Tokens in the bucket, in message-nanoseconds.
+    tokens: u128,
+
+    // Size of the bucket, in message-nanoseconds.
+    burst_size: u128,
+
+    // Number of messages that we may send per second.
+    rate: u128,
+
+    // Time at which we last refilled the bucket.
+    last_refill: Instant,
+}
+
+impl Icmpv4RateLimiter {
+    /// Creates a rate limiter that lets [rate] messages per second through, and up to [burst] back to back. The bucket
+    /// starts full.
+    pub fn new(rate: u32, burst: u32, now: Instant) -> Self {
+        let burst_size: u128 = burst as u128 * NANOS_PER_SEC;
+        Self {
+            tokens: burst_size,
+            burst_size,
+            rate: rate as u128,
+            last_refill: now,
+        }
+    }
+
+    /// Takes a message out of the bucket. Returns false if the bucket is empty, in which case the message should be
+    /// dropped.
+    pub fn try_consume(&mut self, now: Instant) -> bool {
+        let elapsed: u128 = now.saturating_duration_since(self.last_refill).as_nanos();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst_size);
+        self.last_refill = now;
+
+        if self.tokens < NANOS_PER_SEC {
+            return false;
+        }
+        self.tokens -= NANOS_PER_SEC;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Icmpv4RateLimiter;
+    use ::anyhow::Result;
+    use ::std::time::{
+        Duration,
+        Instant,
+    };
+
+    // Test that the rate limiter lets a burst through and then one message per period of the rate.
+    #[test]
+    fn rate_limit_messages() -> Result<()> {
+        let now: Instant = Instant::now();
+        let mut limiter: Icmpv4RateLimiter = Icmpv4RateLimiter::new(100, 5, now);
+
+        // The bucket starts with room for a burst.
+        for _ in 0..5 {
+            crate::ensure_eq!(limiter.try_consume(now), true);
+        }
+        crate::ensure_eq!(limiter.try_consume(now), false);
+
+        // Then a message goes through every 10 ms at this rate.
+        let now: Instant = now + Duration::from_millis(5);
+        crate::ensure_eq!(limiter.try_consume(now), false);
+        let now: Instant = now + Duration::from_millis(5);
+        crate::ensure_eq!(limiter.try_consume(now), true);
+        crate::ensure_eq!(limiter.try_consume(now), false);
+
+        // An idle sender only gets to send a burst again.
+        let now: Instant = now + Duration::from_secs(1);
+        for _ in 0..5 {
+            crate::ensure_eq!(limiter.try_consume(now), true);
+        }
+        crate::ensure_eq!(limiter.try_consume(now), false);
+
+        Ok(())
+    }
+}
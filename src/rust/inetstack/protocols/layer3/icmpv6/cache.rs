@@ -0,0 +1,89 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    collections::hashttlcache::HashTtlCache,
+    runtime::network::types::MacAddress,
+};
+use ::std::{
+    net::Ipv6Addr,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+#[cfg(test)]
+use ::std::collections::HashMap;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+const DUMMY_MAC_ADDRESS: MacAddress = MacAddress::new([0; 6]);
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+#[derive(Debug)]
+struct Record {
+    link_addr: MacAddress,
+}
+
+///
+/// # Neighbor Cache
+/// Cache for IPv6 Addresses. If set to None, then neighbor discovery is disabled.
+///
+/// TODO: Implement the neighbor unreachability detection state machine (see RFC 4861 Section 7.3).
+pub struct NeighborCache(Option<HashTtlCache<Ipv6Addr, Record>>);
+
+//======================================================================================================================
+// Associate Functions
+//======================================================================================================================
+
+impl NeighborCache {
+    /// Creates a Neighbor Cache.
+    pub fn new(now: Instant, default_ttl: Option<Duration>, disable: bool) -> NeighborCache {
+        NeighborCache(if disable {
+            None
+        } else {
+            Some(HashTtlCache::<Ipv6Addr, Record>::new(now, default_ttl))
+        })
+    }
+
+    /// Caches an address resolution.
+    pub fn insert(&mut self, ipv6_addr: Ipv6Addr, link_addr: MacAddress) -> Option<MacAddress> {
+        if let Some(ref mut cache) = self.0 {
+            let record = Record { link_addr };
+            cache.insert(ipv6_addr, record).map(|r| r.link_addr)
+        } else {
+            None
+        }
+    }
+
+    /// Gets the MAC address of given IPv6 address.
+    pub fn get(&self, ipv6_addr: Ipv6Addr) -> Option<&MacAddress> {
+        if let Some(ref cache) = self.0 {
+            cache.get(&ipv6_addr).map(|r| &r.link_addr)
+        } else {
+            Some(&DUMMY_MAC_ADDRESS)
+        }
+    }
+
+    // Exports address resolutions that are stored in the neighbor cache.
+    #[cfg(test)]
+    pub fn export(&self) -> HashMap<Ipv6Addr, MacAddress> {
+        let mut map: HashMap<Ipv6Addr, MacAddress> = HashMap::default();
+        if let Some(ref cache) = self.0 {
+            for (k, v) in cache.iter() {
+                map.insert(*k, v.link_addr);
+            }
+        }
+        map
+    }
+}
@@ -0,0 +1,114 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::protocols::{
+        compute_generic_checksum,
+        fold16,
+        layer3::{
+            icmpv6::protocol::Icmpv6Type2,
+            ip::IpProtocol,
+            ipv6::Ipv6Header,
+        },
+    },
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+    },
+};
+use ::libc::EBADMSG;
+use ::std::net::Ipv6Addr;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Size of ICMPv6 Headers (in bytes)
+pub const ICMPV6_HEADER_SIZE: usize = 8;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+#[derive(Copy, Clone, Debug)]
+pub struct Icmpv6Header {
+    protocol: Icmpv6Type2,
+    code: u8,
+}
+
+//======================================================================================================================
+// Associate Functions
+//======================================================================================================================
+
+/// Associate Functions for Icmpv6Header
+impl Icmpv6Header {
+    /// Creates a header for a ICMPv6 Message.
+    pub fn new(icmpv6_type: Icmpv6Type2, code: u8) -> Self {
+        Self {
+            protocol: icmpv6_type,
+            code,
+        }
+    }
+
+    /// Strips and parses the ICMPv6 header from the packet in [buf]. Unlike ICMPv4, the checksum covers an IPv6
+    /// pseudo-header, so the addresses of the enclosing datagram are required.
+    pub fn parse_and_strip(src_addr: &Ipv6Addr, dst_addr: &Ipv6Addr, buf: &mut DemiBuffer) -> Result<Self, Fail> {
+        if buf.len() < ICMPV6_HEADER_SIZE {
+            return Err(Fail::new(EBADMSG, "ICMPv6 datagram too small for header"));
+        }
+        let hdr_buf: &[u8; ICMPV6_HEADER_SIZE] = &buf[..ICMPV6_HEADER_SIZE].try_into().unwrap();
+
+        let type_byte: u8 = hdr_buf[0];
+        let code: u8 = hdr_buf[1];
+        if Self::compute_checksum(src_addr, dst_addr, hdr_buf, &buf[ICMPV6_HEADER_SIZE..]) != 0 {
+            return Err(Fail::new(EBADMSG, "ICMPv6 checksum mismatch"));
+        }
+        let rest_of_header: &[u8; 4] = hdr_buf[4..8].try_into().unwrap();
+        let icmpv6_type: Icmpv6Type2 = Icmpv6Type2::parse(type_byte, rest_of_header)?;
+
+        buf.adjust(ICMPV6_HEADER_SIZE)?;
+        Ok(Self {
+            protocol: icmpv6_type,
+            code,
+        })
+    }
+
+    /// Serializes and prepends the ICMPv6 header into the packet in [buf]. This function assumes that the packet has
+    /// sufficient headroom to fit the ICMPv6 header.
+    pub fn serialize_and_attach(&self, buf: &mut DemiBuffer, src_addr: &Ipv6Addr, dst_addr: &Ipv6Addr) {
+        buf.prepend(ICMPV6_HEADER_SIZE).expect("Should have headroom");
+
+        let (type_byte, rest_of_header) = self.protocol.serialize();
+        buf[0] = type_byte;
+        buf[1] = self.code;
+        // Skip the checksum for now.
+        buf[2] = 0;
+        buf[3] = 0;
+        buf[4..8].copy_from_slice(&rest_of_header[..]);
+        let (hdr_buf, payload): (&[u8], &[u8]) = buf[..].split_at(ICMPV6_HEADER_SIZE);
+        let checksum: u16 = Self::compute_checksum(src_addr, dst_addr, hdr_buf, payload);
+        buf[2..4].copy_from_slice(&checksum.to_be_bytes());
+    }
+
+    /// Computes the checksum of the target ICMPv6 header, including the IPv6 pseudo-header (see RFC 4443 Section 2.3).
+    fn compute_checksum(src_addr: &Ipv6Addr, dst_addr: &Ipv6Addr, buf: &[u8], body: &[u8]) -> u16 {
+        let mut state: u32 =
+            Ipv6Header::compute_pseudo_header_checksum(src_addr, dst_addr, IpProtocol::ICMPv6, buf.len() + body.len());
+        state = compute_generic_checksum(buf, Some(state));
+        state = compute_generic_checksum(body, Some(state));
+
+        fold16(state)
+    }
+
+    pub fn get_protocol(&self) -> Icmpv6Type2 {
+        self.protocol
+    }
+
+    pub fn get_code(&self) -> u8 {
+        self.code
+    }
+}
@@ -0,0 +1,16 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+mod cache;
+mod header;
+mod ndp;
+mod peer;
+mod protocol;
+
+#[cfg(test)]
+mod tests;
+
+pub use self::{
+    ndp::solicited_node_multicast_addr,
+    peer::SharedIcmpv6Peer,
+};
@@ -0,0 +1,151 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    memory::DemiBuffer,
+    network::types::MacAddress,
+};
+use ::libc::EBADMSG;
+use ::std::net::Ipv6Addr;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Size of the target address carried in Neighbor Solicitation and Neighbor Advertisement messages (in bytes).
+const NDP_TARGET_ADDR_SIZE: usize = 16;
+
+/// Size of a link-layer address option for Ethernet (in bytes).
+const NDP_LINK_ADDR_OPTION_SIZE: usize = 8;
+
+/// Size of a Neighbor Solicitation or Neighbor Advertisement message body (in bytes).
+pub const NDP_MESSAGE_SIZE: usize = NDP_TARGET_ADDR_SIZE + NDP_LINK_ADDR_OPTION_SIZE;
+
+/// Option Type: Source Link-Layer Address.
+const NDP_OPTION_SOURCE_LINK_ADDR: u8 = 1;
+
+/// Option Type: Target Link-Layer Address.
+const NDP_OPTION_TARGET_LINK_ADDR: u8 = 2;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+///
+/// Neighbor Discovery Message
+///
+/// This is the body that follows the ICMPv6 header in Neighbor Solicitation and Neighbor Advertisement messages: the
+/// target address followed by an optional link-layer address option. Solicitations carry the link-layer address of the
+/// sender, while advertisements carry the link-layer address of the target. See RFC 4861 Sections 4.3 and 4.4.
+///
+#[derive(Copy, Clone, Debug)]
+pub struct NeighborDiscoveryMessage {
+    target_addr: Ipv6Addr,
+    link_addr: Option<MacAddress>,
+}
+
+//======================================================================================================================
+// Associate Functions
+//======================================================================================================================
+
+impl NeighborDiscoveryMessage {
+    pub fn new(target_addr: Ipv6Addr, link_addr: Option<MacAddress>) -> Self {
+        Self { target_addr, link_addr }
+    }
+
+    /// Parses a Neighbor Discovery message body from [buf]. Unknown options are skipped, as required by RFC 4861
+    /// Section 4.6.
+    pub fn parse(buf: &DemiBuffer) -> Result<Self, Fail> {
+        if buf.len() < NDP_TARGET_ADDR_SIZE {
+            return Err(Fail::new(EBADMSG, "NDP message too small"));
+        }
+        let target_addr: Ipv6Addr = Ipv6Addr::from(<[u8; 16]>::try_from(&buf[..NDP_TARGET_ADDR_SIZE]).unwrap());
+
+        let mut link_addr: Option<MacAddress> = None;
+        let mut options: &[u8] = &buf[NDP_TARGET_ADDR_SIZE..];
+        while !options.is_empty() {
+            if options.len() < 2 {
+                return Err(Fail::new(EBADMSG, "NDP option too small"));
+            }
+            let option_type: u8 = options[0];
+            // The option length is expressed in units of 8 octets and must not be zero.
+            let option_length: usize = options[1] as usize * 8;
+            if option_length == 0 || option_length > options.len() {
+                return Err(Fail::new(EBADMSG, "invalid NDP option length"));
+            }
+            match option_type {
+                NDP_OPTION_SOURCE_LINK_ADDR | NDP_OPTION_TARGET_LINK_ADDR
+                    if option_length == NDP_LINK_ADDR_OPTION_SIZE =>
+                {
+                    link_addr = Some(MacAddress::from_bytes(&options[2..8]));
+                },
+                _ => warn!(
+                    "ignoring NDP option (type={:?}, length={:?})",
+                    option_type, option_length
+                ),
+            }
+            options = &options[option_length..];
+        }
+
+        Ok(Self { target_addr, link_addr })
+    }
+
+    /// Serializes the target Neighbor Discovery message into a new packet. The link-layer address option is encoded as
+    /// a source link-layer address for solicitations and a target link-layer address for advertisements.
+    pub fn serialize(&self, is_solicitation: bool, headroom: usize) -> DemiBuffer {
+        let size: usize = match self.link_addr {
+            Some(_) => NDP_MESSAGE_SIZE,
+            None => NDP_TARGET_ADDR_SIZE,
+        };
+        let mut buf: DemiBuffer = DemiBuffer::new_with_headroom(size as u16, headroom as u16);
+        buf[..NDP_TARGET_ADDR_SIZE].copy_from_slice(&self.target_addr.octets());
+        if let Some(link_addr) = self.link_addr {
+            buf[NDP_TARGET_ADDR_SIZE] = if is_solicitation {
+                NDP_OPTION_SOURCE_LINK_ADDR
+            } else {
+                NDP_OPTION_TARGET_LINK_ADDR
+            };
+            buf[NDP_TARGET_ADDR_SIZE + 1] = (NDP_LINK_ADDR_OPTION_SIZE / 8) as u8;
+            buf[NDP_TARGET_ADDR_SIZE + 2..NDP_MESSAGE_SIZE].copy_from_slice(&link_addr.octets());
+        }
+        buf
+    }
+
+    pub fn get_target_addr(&self) -> Ipv6Addr {
+        self.target_addr
+    }
+
+    pub fn get_link_addr(&self) -> Option<MacAddress> {
+        self.link_addr
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Computes the solicited-node multicast address for [ipv6_addr] (see RFC 4291 Section 2.7.1).
+pub fn solicited_node_multicast_addr(ipv6_addr: &Ipv6Addr) -> Ipv6Addr {
+    let octets: [u8; 16] = ipv6_addr.octets();
+    Ipv6Addr::new(
+        0xff02,
+        0,
+        0,
+        0,
+        0,
+        1,
+        0xff00 | octets[13] as u16,
+        u16::from_be_bytes([octets[14], octets[15]]),
+    )
+}
+
+/// Computes the Ethernet address that [ipv6_addr] maps to when it is a multicast address (see RFC 2464 Section 7).
+pub fn multicast_link_addr(ipv6_addr: &Ipv6Addr) -> MacAddress {
+    let octets: [u8; 16] = ipv6_addr.octets();
+    MacAddress::new([0x33, 0x33, octets[12], octets[13], octets[14], octets[15]])
+}
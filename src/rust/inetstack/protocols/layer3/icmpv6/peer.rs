@@ -0,0 +1,512 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+use crate::{
+    collections::async_queue::AsyncQueue,
+    demikernel::config::Config,
+    expect_ok,
+    inetstack::protocols::{
+        layer2::{
+            SharedLayer2Endpoint,
+            ETHERNET2_HEADER_SIZE,
+        },
+        layer3::{
+            icmpv6::{
+                cache::NeighborCache,
+                header::{
+                    Icmpv6Header,
+                    ICMPV6_HEADER_SIZE,
+                },
+                ndp::{
+                    multicast_link_addr,
+                    solicited_node_multicast_addr,
+                    NeighborDiscoveryMessage,
+                },
+                protocol::{
+                    Icmpv6Type2,
+                    ICMPV6_ECHO_REQUEST_MESSAGE_SIZE,
+                },
+            },
+            ip::IpProtocol,
+            ipv6::{
+                Ipv6Header,
+                IPV6_HEADER_SIZE,
+            },
+        },
+    },
+    runtime::{
+        conditional_yield_with_timeout,
+        fail::Fail,
+        memory::DemiBuffer,
+        network::{
+            config::ArpConfig,
+            types::MacAddress,
+        },
+        SharedConditionVariable,
+        SharedDemiRuntime,
+        SharedObject,
+    },
+};
+use ::futures::{
+    channel::oneshot::{
+        channel,
+        Receiver,
+        Sender,
+    },
+    FutureExt,
+};
+use ::libc::ETIMEDOUT;
+use ::rand::{
+    prelude::SmallRng,
+    Rng,
+    SeedableRng,
+};
+use ::std::{
+    collections::{
+        HashMap,
+        LinkedList,
+    },
+    net::Ipv6Addr,
+    num::Wrapping,
+    ops::{
+        Deref,
+        DerefMut,
+    },
+    process,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Arbitrary time out for waiting for pings.
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Hop limit that Neighbor Discovery messages must carry (see RFC 4861 Section 7.1).
+const NDP_HOP_LIMIT: u8 = 255;
+
+/// Headroom required to prepend all headers to an ICMPv6 message.
+const ICMPV6_HEADROOM: usize = ICMPV6_HEADER_SIZE + IPV6_HEADER_SIZE as usize + ETHERNET2_HEADER_SIZE;
+
+//======================================================================================================================
+// Icmpv6Peer
+//======================================================================================================================
+
+enum InflightRequest {
+    Inflight(SharedConditionVariable),
+    Complete,
+}
+
+///
+/// Internet Control Message Protocol for IPv6 (ICMPv6)
+///
+/// This is a supporting protocol for the Internet Protocol version 6 (IPv6)
+/// suite. Besides error and informational messages, it carries the Neighbor
+/// Discovery Protocol (NDP), which replaces ARP for resolving the link-layer
+/// addresses of on-link neighbors.
+///
+/// ICMPv6 is defined in RFC 4443 and NDP is defined in RFC 4861.
+///
+pub struct Icmpv6Peer {
+    /// Shared DemiRuntime.
+    runtime: SharedDemiRuntime,
+    /// Underlying Network Transport
+    layer2_endpoint: SharedLayer2Endpoint,
+    local_ipv6_addr: Ipv6Addr,
+
+    /// Neighbor Cache
+    cache: NeighborCache,
+    /// Pending address resolutions.
+    waiters: HashMap<Ipv6Addr, LinkedList<Sender<MacAddress>>>,
+    /// Address resolution settings. These are shared with ARP.
+    arp_config: ArpConfig,
+
+    /// Incoming packets
+    recv_queue: AsyncQueue<(Ipv6Header, DemiBuffer)>,
+
+    /// Sequence Number
+    seq: Wrapping<u16>,
+
+    /// Random number generator
+    rng: SmallRng,
+
+    /// Inflight ping requests.
+    inflight: HashMap<(u16, u16), InflightRequest>,
+}
+
+#[derive(Clone)]
+pub struct SharedIcmpv6Peer(SharedObject<Icmpv6Peer>);
+
+impl SharedIcmpv6Peer {
+    /// Neighbor Discovery cleanup timeout.
+    const NDP_CLEANUP_TIMEOUT: Duration = Duration::from_secs(1);
+
+    pub fn new(
+        config: &Config,
+        mut runtime: SharedDemiRuntime,
+        layer2_endpoint: SharedLayer2Endpoint,
+        rng_seed: [u8; 32],
+    ) -> Result<Self, Fail> {
+        let arp_config: ArpConfig = ArpConfig::new(config)?;
+        let cache: NeighborCache = NeighborCache::new(
+            runtime.get_now(),
+            Some(arp_config.get_cache_ttl()),
+            arp_config.get_disable_arp(),
+        );
+        let rng: SmallRng = SmallRng::from_seed(rng_seed);
+        let peer: SharedIcmpv6Peer = Self(SharedObject::new(Icmpv6Peer {
+            runtime: runtime.clone(),
+            layer2_endpoint: layer2_endpoint.clone(),
+            local_ipv6_addr: config
+                .local_ipv6_addr()
+                .ok_or_else(|| Config::missing_option("local_ipv6_addr"))?,
+            cache,
+            waiters: HashMap::default(),
+            arp_config,
+            recv_queue: AsyncQueue::<(Ipv6Header, DemiBuffer)>::default(),
+            seq: Wrapping(0),
+            rng,
+            inflight: HashMap::<(u16, u16), InflightRequest>::new(),
+        }));
+        runtime.insert_background_coroutine(
+            "bgc::inetstack::icmpv6::background",
+            Box::pin(peer.clone().poll().fuse()),
+        )?;
+        Ok(peer)
+    }
+
+    /// Background task for replying to ICMPv6 messages.
+    async fn poll(mut self) {
+        loop {
+            let (ipv6_hdr, mut buf): (Ipv6Header, DemiBuffer) =
+                match self.recv_queue.pop(Some(Self::NDP_CLEANUP_TIMEOUT)).await {
+                    Ok(result) => result,
+                    Err(Fail { errno, cause: _ }) if errno == libc::ETIMEDOUT || errno == libc::EAGAIN => continue,
+                    Err(_) => break,
+                };
+            let src_ipv6_addr: Ipv6Addr = ipv6_hdr.get_src_addr();
+            let icmpv6_hdr: Icmpv6Header =
+                match Icmpv6Header::parse_and_strip(&src_ipv6_addr, &ipv6_hdr.get_dest_addr(), &mut buf) {
+                    Ok(header) => header,
+                    Err(e) => {
+                        let cause = "Cannot parse ICMPv6 header";
+                        warn!("{}: {:?}", cause, e);
+                        continue;
+                    },
+                };
+            debug!("ICMPv6 received {:?}", icmpv6_hdr);
+            match icmpv6_hdr.get_protocol() {
+                Icmpv6Type2::EchoRequest { id, seq_num } => self.reply_to_ping(src_ipv6_addr, id, seq_num, buf),
+                Icmpv6Type2::EchoReply { id, seq_num } => {
+                    match self.inflight.get_mut(&(id, seq_num)) {
+                        Some(InflightRequest::Inflight(condition_variable)) => condition_variable.signal(),
+                        _ => continue,
+                    }
+                    self.inflight.insert((id, seq_num), InflightRequest::Complete);
+                },
+                Icmpv6Type2::NeighborSolicitation => {
+                    if let Some(message) = Self::parse_ndp_message(&ipv6_hdr, &icmpv6_hdr, &buf) {
+                        self.reply_to_solicitation(src_ipv6_addr, message);
+                    }
+                },
+                Icmpv6Type2::NeighborAdvertisement { .. } => {
+                    if let Some(message) = Self::parse_ndp_message(&ipv6_hdr, &icmpv6_hdr, &buf) {
+                        if let Some(link_addr) = message.get_link_addr() {
+                            debug!("advertisement from `{}/{}`", message.get_target_addr(), link_addr);
+                            self.do_insert(message.get_target_addr(), link_addr);
+                        }
+                    }
+                },
+                _ => warn!("Unsupported ICMPv6 message: {:?}", icmpv6_hdr),
+            }
+        }
+    }
+
+    /// Validates and parses the body of a Neighbor Discovery message (see RFC 4861 Sections 7.1.1 and 7.1.2).
+    fn parse_ndp_message(
+        ipv6_hdr: &Ipv6Header,
+        icmpv6_hdr: &Icmpv6Header,
+        buf: &DemiBuffer,
+    ) -> Option<NeighborDiscoveryMessage> {
+        // Neighbor Discovery messages must not have been forwarded by a router.
+        if ipv6_hdr.get_hop_limit() != NDP_HOP_LIMIT || icmpv6_hdr.get_code() != 0 {
+            warn!("dropping NDP message: invalid hop limit or code");
+            return None;
+        }
+        match NeighborDiscoveryMessage::parse(buf) {
+            Ok(message) if !message.get_target_addr().is_multicast() => Some(message),
+            Ok(_) => {
+                warn!("dropping NDP message: multicast target address");
+                None
+            },
+            Err(e) => {
+                warn!("dropping NDP message: {:?}", e);
+                None
+            },
+        }
+    }
+
+    /// Answers a Neighbor Solicitation for our address with a Neighbor Advertisement (see RFC 4861 Section 7.2.4).
+    fn reply_to_solicitation(&mut self, src_ipv6_addr: Ipv6Addr, message: NeighborDiscoveryMessage) {
+        if message.get_target_addr() != self.local_ipv6_addr {
+            trace!(
+                "dropping neighbor solicitation (target={:?})",
+                message.get_target_addr()
+            );
+            return;
+        }
+
+        // Solicitations for duplicate address detection come from the unspecified address, so the answer is multicast
+        // to all nodes. Otherwise, learn the address of the sender and answer it directly.
+        let (dst_ipv6_addr, dst_link_addr, solicited): (Ipv6Addr, MacAddress, bool) = if src_ipv6_addr.is_unspecified()
+        {
+            let all_nodes: Ipv6Addr = Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 1);
+            (all_nodes, multicast_link_addr(&all_nodes), false)
+        } else {
+            match message.get_link_addr() {
+                Some(link_addr) => {
+                    self.do_insert(src_ipv6_addr, link_addr);
+                    (src_ipv6_addr, link_addr, true)
+                },
+                None => match self.cache.get(src_ipv6_addr) {
+                    Some(&link_addr) => (src_ipv6_addr, link_addr, true),
+                    None => {
+                        warn!(
+                            "dropping neighbor solicitation: unknown link address (src={:?})",
+                            src_ipv6_addr
+                        );
+                        return;
+                    },
+                },
+            }
+        };
+
+        let advertisement: Icmpv6Type2 = Icmpv6Type2::NeighborAdvertisement {
+            router: false,
+            solicited,
+            override_flag: true,
+        };
+        let local_link_addr: MacAddress = self.layer2_endpoint.get_local_link_addr();
+        let body: NeighborDiscoveryMessage = NeighborDiscoveryMessage::new(self.local_ipv6_addr, Some(local_link_addr));
+        let pkt: DemiBuffer = body.serialize(false, ICMPV6_HEADROOM);
+        debug!("Responding {:?} to {:?}", advertisement, dst_ipv6_addr);
+        if let Err(e) = self.transmit(advertisement, dst_ipv6_addr, dst_link_addr, pkt) {
+            // Ignore for now because the other end will retry.
+            warn!("Could not transmit message: {:?}", e);
+        }
+    }
+
+    /// Answers an Echo Request. The remote will have resolved our address before sending us the request, which places
+    /// it in our neighbor cache as well.
+    fn reply_to_ping(&mut self, dst_ipv6_addr: Ipv6Addr, id: u16, seq_num: u16, buf: DemiBuffer) {
+        let dst_link_addr: MacAddress = match self.cache.get(dst_ipv6_addr) {
+            Some(&link_addr) => link_addr,
+            None => {
+                warn!(
+                    "reply_to_ping({}, {}, {}) failed: address not resolved",
+                    dst_ipv6_addr, id, seq_num
+                );
+                return;
+            },
+        };
+        debug!("reply ping ({}, {}, {})", dst_ipv6_addr, id, seq_num);
+        if let Err(e) = self.transmit(
+            Icmpv6Type2::EchoReply { id, seq_num },
+            dst_ipv6_addr,
+            dst_link_addr,
+            buf,
+        ) {
+            warn!("Could not send packet: {:?}", e);
+        }
+    }
+
+    /// Attaches the ICMPv6 and IPv6 headers to [pkt] and sends it.
+    fn transmit(
+        &mut self,
+        icmpv6_type: Icmpv6Type2,
+        dst_ipv6_addr: Ipv6Addr,
+        dst_link_addr: MacAddress,
+        mut pkt: DemiBuffer,
+    ) -> Result<(), Fail> {
+        let icmpv6_hdr: Icmpv6Header = Icmpv6Header::new(icmpv6_type, 0);
+        icmpv6_hdr.serialize_and_attach(&mut pkt, &self.local_ipv6_addr, &dst_ipv6_addr);
+        let ipv6_hdr: Ipv6Header = Ipv6Header::new(self.local_ipv6_addr, dst_ipv6_addr, IpProtocol::ICMPv6);
+        ipv6_hdr.serialize_and_attach(&mut pkt);
+        self.layer2_endpoint.transmit_ipv6_packet(dst_link_addr, pkt)
+    }
+
+    /// Parses and handles a ICMPv6 message.
+    pub fn receive(&mut self, ipv6_hdr: Ipv6Header, buf: DemiBuffer) {
+        self.recv_queue.push((ipv6_hdr, buf));
+    }
+
+    /// Drops a waiter for a target IP address.
+    fn do_drop(&mut self, ipv6_addr: Ipv6Addr) {
+        self.waiters.remove(&ipv6_addr);
+    }
+
+    fn do_insert(&mut self, ipv6_addr: Ipv6Addr, link_addr: MacAddress) -> Option<MacAddress> {
+        if let Some(wait_queue) = self.waiters.remove(&ipv6_addr) {
+            for sender in wait_queue {
+                let _ = sender.send(link_addr);
+            }
+        }
+        self.cache.insert(ipv6_addr, link_addr)
+    }
+
+    async fn do_wait_link_addr(&mut self, ipv6_addr: Ipv6Addr) -> MacAddress {
+        let (tx, rx): (Sender<MacAddress>, Receiver<MacAddress>) = channel();
+        if let Some(&link_addr) = self.cache.get(ipv6_addr) {
+            let _ = tx.send(link_addr);
+        } else {
+            self.waiters.entry(ipv6_addr).or_default().push_back(tx);
+        }
+        expect_ok!(rx.await, "Dropped waiter?")
+    }
+
+    pub fn try_query(&self, ipv6_addr: Ipv6Addr) -> Option<MacAddress> {
+        self.cache.get(ipv6_addr).cloned()
+    }
+
+    /// Resolves the link-layer address of [ipv6_addr] by sending Neighbor Solicitations to its solicited-node
+    /// multicast address (see RFC 4861 Section 7.2.2).
+    pub async fn query(&mut self, ipv6_addr: Ipv6Addr) -> Result<MacAddress, Fail> {
+        if let Some(&link_addr) = self.cache.get(ipv6_addr) {
+            return Ok(link_addr);
+        }
+        let dst_ipv6_addr: Ipv6Addr = solicited_node_multicast_addr(&ipv6_addr);
+        let dst_link_addr: MacAddress = multicast_link_addr(&dst_ipv6_addr);
+        let body: NeighborDiscoveryMessage =
+            NeighborDiscoveryMessage::new(ipv6_addr, Some(self.layer2_endpoint.get_local_link_addr()));
+        let mut peer: SharedIcmpv6Peer = self.clone();
+        let result = {
+            for i in 0..self.arp_config.get_retry_count() + 1 {
+                let pkt: DemiBuffer = body.serialize(true, ICMPV6_HEADROOM);
+                if let Err(e) = self.transmit(Icmpv6Type2::NeighborSolicitation, dst_ipv6_addr, dst_link_addr, pkt) {
+                    warn!("Could not send packet: {:?}", e);
+                    continue;
+                }
+                let ndp_response = peer.do_wait_link_addr(ipv6_addr);
+
+                match conditional_yield_with_timeout(ndp_response, self.arp_config.get_request_timeout()).await {
+                    Ok(link_addr) => {
+                        debug!("NDP result available ({:?})", link_addr);
+                        return Ok(link_addr);
+                    },
+                    Err(_) => {
+                        warn!("NDP request timeout; attempt {}.", i + 1);
+                    },
+                }
+            }
+            let cause: String = format!("query(): query timeout (ipv6_addr={:?})", ipv6_addr);
+            error!("{}", &cause);
+            Err(Fail::new(ETIMEDOUT, &cause))
+        };
+
+        self.do_drop(ipv6_addr);
+
+        result
+    }
+
+    /// Computes the identifier for an ICMPv6 message.
+    fn make_id(&mut self) -> u16 {
+        let mut state: u32 = 0xFFFF;
+        for chunk in self.local_ipv6_addr.octets().chunks_exact(2) {
+            state += u16::from_be_bytes([chunk[0], chunk[1]]) as u32;
+        }
+
+        let pid_buf: [u8; 4] = process::id().to_be_bytes();
+        state += u16::from_be_bytes([pid_buf[0], pid_buf[1]]) as u32;
+        state += u16::from_be_bytes([pid_buf[2], pid_buf[3]]) as u32;
+
+        let nonce: [u8; 2] = self.rng.gen();
+        state += u16::from_be_bytes([nonce[0], nonce[1]]) as u32;
+
+        while state > 0xFFFF {
+            state -= 0xFFFF;
+        }
+        !state as u16
+    }
+
+    /// Computes sequence number for an ICMPv6 message.
+    fn make_seq_num(&mut self) -> u16 {
+        let Wrapping(seq_num) = self.seq;
+        self.seq += Wrapping(1);
+        seq_num
+    }
+
+    /// Sends a ping to a remote peer.
+    pub async fn ping(&mut self, dst_ipv6_addr: Ipv6Addr, timeout: Option<Duration>) -> Result<Duration, Fail> {
+        let id: u16 = self.make_id();
+        let seq_num: u16 = self.make_seq_num();
+        let echo_request: Icmpv6Type2 = Icmpv6Type2::EchoRequest { id, seq_num };
+
+        let t0: Instant = self.runtime.get_now();
+        debug!("initiating NDP query");
+        let dst_link_addr: MacAddress = self.query(dst_ipv6_addr).await?;
+        debug!("NDP query complete ({} -> {})", dst_ipv6_addr, dst_link_addr);
+
+        let pkt: DemiBuffer = DemiBuffer::new_with_headroom(ICMPV6_ECHO_REQUEST_MESSAGE_SIZE, ICMPV6_HEADROOM as u16);
+        if let Err(e) = self.transmit(echo_request, dst_ipv6_addr, dst_link_addr, pkt) {
+            let cause = format!("Could not send ping: {:?}", e);
+            warn!("{}", cause);
+            return Err(Fail::new(libc::EAGAIN, &cause));
+        }
+
+        let condition_variable: SharedConditionVariable = SharedConditionVariable::default();
+        self.inflight
+            .insert((id, seq_num), InflightRequest::Inflight(condition_variable));
+        match conditional_yield_with_timeout(
+            // Yield into the scheduler until the request completes.
+            async {
+                while let Some(request) = self.inflight.get(&(id, seq_num)) {
+                    match request {
+                        InflightRequest::Inflight(condition_variable) => condition_variable.clone().wait().await,
+                        InflightRequest::Complete => return,
+                    }
+                }
+            },
+            timeout.unwrap_or(PING_TIMEOUT),
+        )
+        .await
+        {
+            Ok(_) => {
+                self.inflight.remove(&(id, seq_num));
+                Ok(self.runtime.get_now() - t0)
+            },
+            Err(_) => {
+                let message: &str = "timer expired";
+                self.inflight.remove(&(id, seq_num));
+                error!("ping(): {}", message);
+                Err(Fail::new(libc::ETIMEDOUT, message))
+            },
+        }
+    }
+
+    #[cfg(test)]
+    pub fn export_cache(&self) -> HashMap<Ipv6Addr, MacAddress> {
+        self.cache.export()
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Deref for SharedIcmpv6Peer {
+    type Target = Icmpv6Peer;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl DerefMut for SharedIcmpv6Peer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
@@ -0,0 +1,129 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+use crate::runtime::fail::Fail;
+use ::libc::EBADMSG;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Size of ICMPv6 Echo Request Message (in bytes)
+pub const ICMPV6_ECHO_REQUEST_MESSAGE_SIZE: u16 = 56;
+
+/// Neighbor Advertisement Flag: Sender is a router.
+const NDP_FLAG_ROUTER: u8 = 0x80;
+
+/// Neighbor Advertisement Flag: Advertisement was sent in response to a Neighbor Solicitation.
+const NDP_FLAG_SOLICITED: u8 = 0x40;
+
+/// Neighbor Advertisement Flag: Advertisement should override an existing cache entry.
+const NDP_FLAG_OVERRIDE: u8 = 0x20;
+
+//======================================================================================================================
+// Icmpv6Type2
+//======================================================================================================================
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Icmpv6Type2 {
+    DestinationUnreachable,
+    PacketTooBig {
+        mtu: u32,
+    },
+    TimeExceeded,
+    ParameterProblem,
+    EchoRequest {
+        id: u16,
+        seq_num: u16,
+    },
+    EchoReply {
+        id: u16,
+        seq_num: u16,
+    },
+    RouterSolicitation,
+    RouterAdvertisement,
+    NeighborSolicitation,
+    NeighborAdvertisement {
+        router: bool,
+        solicited: bool,
+        override_flag: bool,
+    },
+    Redirect,
+}
+
+impl Icmpv6Type2 {
+    pub fn parse(type_byte: u8, rest_of_header: &[u8; 4]) -> Result<Self, Fail> {
+        use Icmpv6Type2::*;
+        match type_byte {
+            1 => Ok(DestinationUnreachable),
+            2 => {
+                let mtu: u32 = u32::from_be_bytes(*rest_of_header);
+                Ok(PacketTooBig { mtu })
+            },
+            3 => Ok(TimeExceeded),
+            4 => Ok(ParameterProblem),
+            128 => {
+                let id: u16 = u16::from_be_bytes([rest_of_header[0], rest_of_header[1]]);
+                let seq_num: u16 = u16::from_be_bytes([rest_of_header[2], rest_of_header[3]]);
+                Ok(EchoRequest { id, seq_num })
+            },
+            129 => {
+                let id: u16 = u16::from_be_bytes([rest_of_header[0], rest_of_header[1]]);
+                let seq_num: u16 = u16::from_be_bytes([rest_of_header[2], rest_of_header[3]]);
+                Ok(EchoReply { id, seq_num })
+            },
+            133 => Ok(RouterSolicitation),
+            134 => Ok(RouterAdvertisement),
+            135 => Ok(NeighborSolicitation),
+            136 => Ok(NeighborAdvertisement {
+                router: rest_of_header[0] & NDP_FLAG_ROUTER != 0,
+                solicited: rest_of_header[0] & NDP_FLAG_SOLICITED != 0,
+                override_flag: rest_of_header[0] & NDP_FLAG_OVERRIDE != 0,
+            }),
+            137 => Ok(Redirect),
+            _ => Err(Fail::new(EBADMSG, "invalid type byte")),
+        }
+    }
+
+    pub fn serialize(&self) -> (u8, [u8; 4]) {
+        use Icmpv6Type2::*;
+        let zero = [0u8; 4];
+        match self {
+            DestinationUnreachable => (1, zero),
+            PacketTooBig { mtu } => (2, mtu.to_be_bytes()),
+            TimeExceeded => (3, zero),
+            ParameterProblem => (4, zero),
+            EchoRequest { id, seq_num } => {
+                let [id1, id2] = id.to_be_bytes();
+                let [seq1, seq2] = seq_num.to_be_bytes();
+                (128, [id1, id2, seq1, seq2])
+            },
+            EchoReply { id, seq_num } => {
+                let [id1, id2] = id.to_be_bytes();
+                let [seq1, seq2] = seq_num.to_be_bytes();
+                (129, [id1, id2, seq1, seq2])
+            },
+            RouterSolicitation => (133, zero),
+            RouterAdvertisement => (134, zero),
+            NeighborSolicitation => (135, zero),
+            NeighborAdvertisement {
+                router,
+                solicited,
+                override_flag,
+            } => {
+                let mut flags: u8 = 0;
+                if *router {
+                    flags |= NDP_FLAG_ROUTER;
+                }
+                if *solicited {
+                    flags |= NDP_FLAG_SOLICITED;
+                }
+                if *override_flag {
+                    flags |= NDP_FLAG_OVERRIDE;
+                }
+                (136, [flags, 0, 0, 0])
+            },
+            Redirect => (137, zero),
+        }
+    }
+}
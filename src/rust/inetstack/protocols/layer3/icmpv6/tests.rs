@@ -0,0 +1,384 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::{
+        protocols::{
+            layer2::{
+                EtherType2,
+                Ethernet2Header,
+                ETHERNET2_HEADER_SIZE,
+            },
+            layer3::{
+                icmpv6::{
+                    header::{
+                        Icmpv6Header,
+                        ICMPV6_HEADER_SIZE,
+                    },
+                    ndp::{
+                        multicast_link_addr,
+                        solicited_node_multicast_addr,
+                        NeighborDiscoveryMessage,
+                        NDP_MESSAGE_SIZE,
+                    },
+                    protocol::Icmpv6Type2,
+                },
+                ip::IpProtocol,
+                ipv6::{
+                    Ipv6Header,
+                    IPV6_HEADER_SIZE,
+                },
+            },
+        },
+        test_helpers::{
+            self,
+            SharedEngine,
+            SharedTestPhysicalLayer,
+            ALICE_IPV6,
+            ALICE_MAC,
+            BOB_IPV6,
+        },
+        SharedInetStack,
+    },
+    runtime::{
+        memory::DemiBuffer,
+        network::types::MacAddress,
+    },
+    QToken,
+};
+use ::anyhow::Result;
+use ::futures::FutureExt;
+use ::std::{
+    collections::{
+        HashMap,
+        VecDeque,
+    },
+    net::Ipv6Addr,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Neighbor Solicitation retry count.
+const NDP_RETRY_COUNT: usize = 2;
+
+/// Neighbor Solicitation timeout.
+const NDP_REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Headroom for building ICMPv6 frames.
+const ICMPV6_HEADROOM: usize = ICMPV6_HEADER_SIZE + IPV6_HEADER_SIZE as usize + ETHERNET2_HEADER_SIZE;
+
+//======================================================================================================================
+// Unit-Tests for Happy Path
+//======================================================================================================================
+
+/// Serializes and parses back a Neighbor Solicitation.
+#[test]
+fn test_icmpv6_neighbor_solicitation_serialize_and_parse() -> Result<()> {
+    let icmpv6_type: Icmpv6Type2 = Icmpv6Type2::NeighborSolicitation;
+    let message: NeighborDiscoveryMessage = NeighborDiscoveryMessage::new(BOB_IPV6, Some(ALICE_MAC));
+    let dst_addr: Ipv6Addr = solicited_node_multicast_addr(&BOB_IPV6);
+    let mut buf: DemiBuffer = message.serialize(true, ICMPV6_HEADER_SIZE);
+    crate::ensure_eq!(buf.len(), NDP_MESSAGE_SIZE);
+    Icmpv6Header::new(icmpv6_type, 0).serialize_and_attach(&mut buf, &ALICE_IPV6, &dst_addr);
+
+    let header: Icmpv6Header = Icmpv6Header::parse_and_strip(&ALICE_IPV6, &dst_addr, &mut buf)?;
+    crate::ensure_eq!(header.get_protocol(), icmpv6_type);
+    crate::ensure_eq!(header.get_code(), 0);
+    let message: NeighborDiscoveryMessage = NeighborDiscoveryMessage::parse(&buf)?;
+    crate::ensure_eq!(message.get_target_addr(), BOB_IPV6);
+    crate::ensure_eq!(message.get_link_addr(), Some(ALICE_MAC));
+
+    Ok(())
+}
+
+/// Serializes and parses back a Neighbor Advertisement.
+#[test]
+fn test_icmpv6_neighbor_advertisement_serialize_and_parse() -> Result<()> {
+    let icmpv6_type: Icmpv6Type2 = Icmpv6Type2::NeighborAdvertisement {
+        router: false,
+        solicited: true,
+        override_flag: true,
+    };
+    let message: NeighborDiscoveryMessage = NeighborDiscoveryMessage::new(ALICE_IPV6, Some(ALICE_MAC));
+    let mut buf: DemiBuffer = message.serialize(false, ICMPV6_HEADER_SIZE);
+    Icmpv6Header::new(icmpv6_type, 0).serialize_and_attach(&mut buf, &ALICE_IPV6, &BOB_IPV6);
+
+    let header: Icmpv6Header = Icmpv6Header::parse_and_strip(&ALICE_IPV6, &BOB_IPV6, &mut buf)?;
+    crate::ensure_eq!(header.get_protocol(), icmpv6_type);
+    let message: NeighborDiscoveryMessage = NeighborDiscoveryMessage::parse(&buf)?;
+    crate::ensure_eq!(message.get_target_addr(), ALICE_IPV6);
+    crate::ensure_eq!(message.get_link_addr(), Some(ALICE_MAC));
+
+    Ok(())
+}
+
+/// Computes solicited-node multicast addresses and their link-layer mapping.
+#[test]
+fn test_icmpv6_solicited_node_multicast_addr() -> Result<()> {
+    let addr: Ipv6Addr = Ipv6Addr::new(0xfe80, 0, 0, 0, 0x0202, 0xb3ff, 0xfe1e, 0x8329);
+    let multicast_addr: Ipv6Addr = solicited_node_multicast_addr(&addr);
+    crate::ensure_eq!(multicast_addr, Ipv6Addr::new(0xff02, 0, 0, 0, 0, 1, 0xff1e, 0x8329));
+    crate::ensure_eq!(
+        multicast_link_addr(&multicast_addr),
+        MacAddress::new([0x33, 0x33, 0xff, 0x1e, 0x83, 0x29])
+    );
+
+    Ok(())
+}
+
+/// Tests immediate reply for a Neighbor Solicitation.
+#[test]
+fn ndp_immediate_reply() -> Result<()> {
+    let mut now: Instant = Instant::now();
+    let local_mac: MacAddress = test_helpers::ALICE_MAC;
+    let local_ipv6: Ipv6Addr = test_helpers::ALICE_IPV6;
+    let remote_mac: MacAddress = test_helpers::BOB_MAC;
+    let remote_ipv6: Ipv6Addr = test_helpers::BOB_IPV6;
+    let mut engine: SharedEngine = new_engine(now, test_helpers::ALICE_CONFIG_PATH)?;
+
+    // Create a Neighbor Solicitation for the local IP address.
+    let buf: DemiBuffer = build_neighbor_solicitation(&remote_mac, &remote_ipv6, &local_ipv6);
+
+    // Feed it to engine.
+    engine.push_frame(buf);
+
+    // Move clock forward and poll the engine.
+    now += Duration::from_micros(1);
+    engine.advance_clock(now);
+    engine.poll();
+
+    // Check if the neighbor cache learned the link-layer address of the sender.
+    let cache: HashMap<Ipv6Addr, MacAddress> = engine.get_transport().export_neighbor_cache();
+    crate::ensure_eq!(cache.get(&remote_ipv6), Some(&remote_mac));
+
+    // Check if a Neighbor Advertisement is sent back.
+    let mut buffers: VecDeque<DemiBuffer> = engine.pop_all_frames();
+    crate::ensure_eq!(buffers.len(), 1);
+    let mut pkt: DemiBuffer = buffers.pop_front().unwrap();
+
+    // Sanity check Ethernet header.
+    let eth2_header: Ethernet2Header = Ethernet2Header::parse_and_strip(&mut pkt)?;
+    crate::ensure_eq!(eth2_header.dst_addr(), remote_mac);
+    crate::ensure_eq!(eth2_header.src_addr(), local_mac);
+    crate::ensure_eq!(eth2_header.ether_type(), EtherType2::Ipv6);
+
+    // Sanity check IPv6 header.
+    let ipv6_header: Ipv6Header = Ipv6Header::parse_and_strip(&mut pkt)?;
+    crate::ensure_eq!(ipv6_header.get_src_addr(), local_ipv6);
+    crate::ensure_eq!(ipv6_header.get_dest_addr(), remote_ipv6);
+    crate::ensure_eq!(ipv6_header.get_next_header(), IpProtocol::ICMPv6);
+    crate::ensure_eq!(ipv6_header.get_hop_limit(), 255);
+
+    // Sanity check ICMPv6 header and Neighbor Advertisement.
+    let icmpv6_header: Icmpv6Header = Icmpv6Header::parse_and_strip(&local_ipv6, &remote_ipv6, &mut pkt)?;
+    crate::ensure_eq!(
+        icmpv6_header.get_protocol(),
+        Icmpv6Type2::NeighborAdvertisement {
+            router: false,
+            solicited: true,
+            override_flag: true,
+        }
+    );
+    let message: NeighborDiscoveryMessage = NeighborDiscoveryMessage::parse(&pkt)?;
+    crate::ensure_eq!(message.get_target_addr(), local_ipv6);
+    crate::ensure_eq!(message.get_link_addr(), Some(local_mac));
+
+    Ok(())
+}
+
+/// Tests no reply for a Neighbor Solicitation targeting another address.
+#[test]
+fn ndp_no_reply() -> Result<()> {
+    let mut now: Instant = Instant::now();
+    let remote_mac: MacAddress = test_helpers::BOB_MAC;
+    let remote_ipv6: Ipv6Addr = test_helpers::BOB_IPV6;
+    let other_remote_ipv6: Ipv6Addr = test_helpers::CARRIE_IPV6;
+    let mut engine: SharedEngine = new_engine(now, test_helpers::ALICE_CONFIG_PATH)?;
+
+    // Create a Neighbor Solicitation for a different IP address.
+    let buf: DemiBuffer = build_neighbor_solicitation(&remote_mac, &remote_ipv6, &other_remote_ipv6);
+
+    // Feed it to engine.
+    engine.push_frame(buf);
+
+    // Move clock forward and poll the engine.
+    now += Duration::from_micros(1);
+    engine.advance_clock(now);
+    engine.poll();
+
+    // Ensure that no reply message is output.
+    let buffers: VecDeque<DemiBuffer> = engine.pop_all_frames();
+    crate::ensure_eq!(buffers.len(), 0);
+
+    Ok(())
+}
+
+/// Tests that an ICMPv6 Echo Request is answered once the sender is a known neighbor.
+#[test]
+fn icmpv6_echo_reply() -> Result<()> {
+    let mut now: Instant = Instant::now();
+    let local_mac: MacAddress = test_helpers::ALICE_MAC;
+    let local_ipv6: Ipv6Addr = test_helpers::ALICE_IPV6;
+    let remote_mac: MacAddress = test_helpers::BOB_MAC;
+    let remote_ipv6: Ipv6Addr = test_helpers::BOB_IPV6;
+    let mut engine: SharedEngine = new_engine(now, test_helpers::ALICE_CONFIG_PATH)?;
+
+    // Resolve the remote peer first, so that the reply can be sent without a lookup.
+    engine.push_frame(build_neighbor_solicitation(&remote_mac, &remote_ipv6, &local_ipv6));
+    now += Duration::from_micros(1);
+    engine.advance_clock(now);
+    engine.poll();
+    crate::ensure_eq!(engine.pop_all_frames().len(), 1);
+
+    // Create an Echo Request and feed it to engine.
+    let echo_request: Icmpv6Type2 = Icmpv6Type2::EchoRequest { id: 7, seq_num: 1 };
+    let mut body: DemiBuffer = DemiBuffer::new_with_headroom(8, ICMPV6_HEADROOM as u16);
+    body.fill(0xa5);
+    let buf: DemiBuffer = build_icmpv6_frame(&remote_mac, &local_mac, &remote_ipv6, &local_ipv6, echo_request, body);
+    engine.push_frame(buf);
+
+    // Move clock forward and poll the engine.
+    now += Duration::from_micros(1);
+    engine.advance_clock(now);
+    engine.poll();
+
+    // Check if an Echo Reply is sent back with the same payload.
+    let mut buffers: VecDeque<DemiBuffer> = engine.pop_all_frames();
+    crate::ensure_eq!(buffers.len(), 1);
+    let mut pkt: DemiBuffer = buffers.pop_front().unwrap();
+    let eth2_header: Ethernet2Header = Ethernet2Header::parse_and_strip(&mut pkt)?;
+    crate::ensure_eq!(eth2_header.dst_addr(), remote_mac);
+    let ipv6_header: Ipv6Header = Ipv6Header::parse_and_strip(&mut pkt)?;
+    crate::ensure_eq!(ipv6_header.get_dest_addr(), remote_ipv6);
+    let icmpv6_header: Icmpv6Header = Icmpv6Header::parse_and_strip(&local_ipv6, &remote_ipv6, &mut pkt)?;
+    crate::ensure_eq!(
+        icmpv6_header.get_protocol(),
+        Icmpv6Type2::EchoReply { id: 7, seq_num: 1 }
+    );
+    crate::ensure_eq!(&pkt[..], &[0xa5; 8][..]);
+
+    Ok(())
+}
+
+//======================================================================================================================
+// Unit-Tests for Invalid Path
+//======================================================================================================================
+
+/// Parses an ICMPv6 message whose checksum does not cover the given pseudo-header.
+#[test]
+fn test_icmpv6_header_parse_invalid_checksum() -> Result<()> {
+    let mut buf: DemiBuffer = DemiBuffer::new_with_headroom(0, ICMPV6_HEADER_SIZE as u16);
+    let icmpv6_type: Icmpv6Type2 = Icmpv6Type2::EchoRequest { id: 1, seq_num: 2 };
+    Icmpv6Header::new(icmpv6_type, 0).serialize_and_attach(&mut buf, &ALICE_IPV6, &BOB_IPV6);
+
+    // Use a different destination address in the pseudo-header.
+    match Icmpv6Header::parse_and_strip(&ALICE_IPV6, &ALICE_IPV6, &mut buf) {
+        Ok(_) => anyhow::bail!("parsing should fail"),
+        Err(_) => Ok(()),
+    }
+}
+
+/// Parses a Neighbor Discovery message with a zero-length option.
+#[test]
+fn test_icmpv6_ndp_parse_invalid_option_length() -> Result<()> {
+    let message: NeighborDiscoveryMessage = NeighborDiscoveryMessage::new(BOB_IPV6, Some(ALICE_MAC));
+    let mut buf: DemiBuffer = message.serialize(true, 0);
+    buf[17] = 0;
+
+    match NeighborDiscoveryMessage::parse(&buf) {
+        Ok(_) => anyhow::bail!("parsing should fail"),
+        Err(_) => Ok(()),
+    }
+}
+
+/// Tests that a neighbor query fails after the configured number of retries.
+#[test]
+fn ndp_query_timeout() -> Result<()> {
+    let mut now: Instant = Instant::now();
+    let other_remote_ipv6: Ipv6Addr = test_helpers::CARRIE_IPV6;
+    let mut engine: SharedEngine = new_engine(now, test_helpers::ALICE_CONFIG_PATH)?;
+    let mut inetstack: SharedInetStack = engine.get_transport();
+    let coroutine = Box::pin(async move { inetstack.ndp_query(other_remote_ipv6).await }.fuse());
+    let qt: QToken = engine.get_runtime().clone().insert_coroutine("ndp query", coroutine)?;
+    engine.poll();
+    engine.poll();
+
+    for _ in 0..(NDP_RETRY_COUNT + 1) {
+        // Check if a Neighbor Solicitation is sent to the solicited-node multicast address.
+        let mut buffers: VecDeque<DemiBuffer> = engine.pop_all_frames();
+        crate::ensure_eq!(buffers.len(), 1);
+        let mut pkt: DemiBuffer = buffers.pop_front().unwrap();
+        let eth2_header: Ethernet2Header = Ethernet2Header::parse_and_strip(&mut pkt)?;
+        crate::ensure_eq!(
+            eth2_header.dst_addr(),
+            multicast_link_addr(&solicited_node_multicast_addr(&other_remote_ipv6))
+        );
+
+        // Move clock forward and poll the engine.
+        now += NDP_REQUEST_TIMEOUT;
+        engine.advance_clock(now);
+        engine.poll();
+        engine.poll();
+    }
+
+    // Ensure that no more solicitations are sent.
+    let buffers: VecDeque<DemiBuffer> = engine.pop_all_frames();
+    crate::ensure_eq!(buffers.len(), 0);
+
+    // Ensure that the query has failed with ETIMEDOUT.
+    match engine.wait(qt, Duration::from_secs(0)) {
+        Err(err) => crate::ensure_eq!(err.errno, libc::ETIMEDOUT),
+        Ok(_) => unreachable!("ndp query must fail with ETIMEDOUT"),
+    }
+
+    Ok(())
+}
+
+//======================================================================================================================
+// Test Helpers
+//======================================================================================================================
+
+/// Builds a Neighbor Solicitation for [target_ipv6] sent by [local_mac]/[local_ipv6].
+fn build_neighbor_solicitation(local_mac: &MacAddress, local_ipv6: &Ipv6Addr, target_ipv6: &Ipv6Addr) -> DemiBuffer {
+    let dst_ipv6: Ipv6Addr = solicited_node_multicast_addr(target_ipv6);
+    let message: NeighborDiscoveryMessage = NeighborDiscoveryMessage::new(*target_ipv6, Some(*local_mac));
+    build_icmpv6_frame(
+        local_mac,
+        &multicast_link_addr(&dst_ipv6),
+        local_ipv6,
+        &dst_ipv6,
+        Icmpv6Type2::NeighborSolicitation,
+        message.serialize(true, ICMPV6_HEADROOM),
+    )
+}
+
+/// Attaches ICMPv6, IPv6 and Ethernet headers to [body].
+fn build_icmpv6_frame(
+    src_mac: &MacAddress,
+    dst_mac: &MacAddress,
+    src_ipv6: &Ipv6Addr,
+    dst_ipv6: &Ipv6Addr,
+    icmpv6_type: Icmpv6Type2,
+    mut body: DemiBuffer,
+) -> DemiBuffer {
+    Icmpv6Header::new(icmpv6_type, 0).serialize_and_attach(&mut body, src_ipv6, dst_ipv6);
+    Ipv6Header::new(*src_ipv6, *dst_ipv6, IpProtocol::ICMPv6).serialize_and_attach(&mut body);
+    Ethernet2Header::new(*dst_mac, *src_mac, EtherType2::Ipv6).serialize_and_attach(&mut body);
+    body
+}
+
+/// Creates a new engine.
+fn new_engine(now: Instant, config_path: &str) -> Result<SharedEngine> {
+    let layer1_endpoint: SharedTestPhysicalLayer = SharedTestPhysicalLayer::new_test(now);
+    Ok(SharedEngine::new(config_path, layer1_endpoint, now)?)
+}
@@ -0,0 +1,223 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::protocols::{
+        compute_generic_checksum,
+        fold16,
+    },
+    runtime::fail::Fail,
+};
+use ::libc::EBADMSG;
+use ::std::{
+    net::Ipv4Addr,
+    time::Duration,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Size of IGMPv1/v2 messages and of the fixed part of IGMPv2 queries (in bytes).
+const IGMP_MESSAGE_SIZE: usize = 8;
+
+/// Size of the fixed part of IGMPv3 queries (in bytes).
+const IGMPV3_QUERY_MIN_SIZE: usize = 12;
+
+/// Size of the fixed part of IGMPv3 reports (in bytes).
+const IGMPV3_REPORT_HEADER_SIZE: usize = 8;
+
+/// Size of a group record without sources or auxiliary data in IGMPv3 reports (in bytes).
+const IGMPV3_GROUP_RECORD_SIZE: usize = 8;
+
+/// Message types (see RFC 3376 Section 4).
+const IGMP_TYPE_MEMBERSHIP_QUERY: u8 = 0x11;
+const IGMPV1_TYPE_MEMBERSHIP_REPORT: u8 = 0x12;
+const IGMPV2_TYPE_MEMBERSHIP_REPORT: u8 = 0x16;
+const IGMPV2_TYPE_LEAVE_GROUP: u8 = 0x17;
+const IGMPV3_TYPE_MEMBERSHIP_REPORT: u8 = 0x22;
+
+/// Group record types of IGMPv3 reports (see RFC 3376 Section 4.2.12).
+pub const IGMPV3_MODE_IS_EXCLUDE: u8 = 2;
+pub const IGMPV3_CHANGE_TO_INCLUDE_MODE: u8 = 3;
+pub const IGMPV3_CHANGE_TO_EXCLUDE_MODE: u8 = 4;
+
+/// Maximum response time of IGMPv1 queries, which do not carry one (see RFC 2236 Section 4).
+const IGMPV1_MAX_RESP_TIME: Duration = Duration::from_secs(10);
+
+/// Robustness variable and query interval that we advertise in IGMPv3 queries (see RFC 3376 Section 8).
+const IGMPV3_DEFAULT_QRV: u8 = 2;
+const IGMPV3_DEFAULT_QQIC: u8 = 125;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// IGMP version of a Membership Query, which tells us which version the querier on the link speaks.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum IgmpVersion {
+    V2,
+    V3,
+}
+
+/// IGMP Message (see RFC 2236 and RFC 3376).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IgmpMessage {
+    /// Membership Query for [group], or for all groups if it is unspecified.
+    Query {
+        version: IgmpVersion,
+        group: Ipv4Addr,
+        max_resp_time: Duration,
+    },
+    /// IGMPv1 or IGMPv2 Membership Report.
+    ReportV2 { group: Ipv4Addr },
+    /// IGMPv2 Leave Group.
+    Leave { group: Ipv4Addr },
+    /// IGMPv3 Membership Report with a record type and a multicast address for each group. We only report any-source
+    /// memberships, so records never carry sources.
+    ReportV3 { records: Vec<(u8, Ipv4Addr)> },
+}
+
+//======================================================================================================================
+// Associate Functions
+//======================================================================================================================
+
+/// Associate Functions for IGMP Messages
+impl IgmpMessage {
+    /// Returns the size of this message (in bytes).
+    pub fn compute_size(&self) -> usize {
+        match self {
+            IgmpMessage::Query {
+                version: IgmpVersion::V3,
+                ..
+            } => IGMPV3_QUERY_MIN_SIZE,
+            IgmpMessage::Query { .. } | IgmpMessage::ReportV2 { .. } | IgmpMessage::Leave { .. } => IGMP_MESSAGE_SIZE,
+            IgmpMessage::ReportV3 { records } => IGMPV3_REPORT_HEADER_SIZE + records.len() * IGMPV3_GROUP_RECORD_SIZE,
+        }
+    }
+
+    /// Parses the IGMP message in [buf].
+    pub fn parse(buf: &[u8]) -> Result<Self, Fail> {
+        if buf.len() < IGMP_MESSAGE_SIZE {
+            return Err(Fail::new(EBADMSG, "IGMP message too small"));
+        }
+        if fold16(compute_generic_checksum(buf, None)) != 0 {
+            return Err(Fail::new(EBADMSG, "IGMP checksum mismatch"));
+        }
+        let group: Ipv4Addr = Ipv4Addr::new(buf[4], buf[5], buf[6], buf[7]);
+        match buf[0] {
+            // The version of a query is told apart by its length and max response code (see RFC 3376 Section 7.1).
+            IGMP_TYPE_MEMBERSHIP_QUERY if buf.len() == IGMP_MESSAGE_SIZE => {
+                let max_resp_time: Duration = match buf[1] {
+                    0 => IGMPV1_MAX_RESP_TIME,
+                    code => Duration::from_millis(code as u64 * 100),
+                };
+                Ok(IgmpMessage::Query {
+                    version: IgmpVersion::V2,
+                    group,
+                    max_resp_time,
+                })
+            },
+            IGMP_TYPE_MEMBERSHIP_QUERY if buf.len() >= IGMPV3_QUERY_MIN_SIZE => Ok(IgmpMessage::Query {
+                version: IgmpVersion::V3,
+                group,
+                max_resp_time: Self::decode_max_resp_code(buf[1]),
+            }),
+            IGMP_TYPE_MEMBERSHIP_QUERY => Err(Fail::new(EBADMSG, "invalid IGMP query size")),
+            IGMPV1_TYPE_MEMBERSHIP_REPORT | IGMPV2_TYPE_MEMBERSHIP_REPORT => Ok(IgmpMessage::ReportV2 { group }),
+            IGMPV2_TYPE_LEAVE_GROUP => Ok(IgmpMessage::Leave { group }),
+            IGMPV3_TYPE_MEMBERSHIP_REPORT => {
+                let num_records: usize = u16::from_be_bytes([buf[6], buf[7]]) as usize;
+                let mut records: Vec<(u8, Ipv4Addr)> = Vec::with_capacity(num_records);
+                let mut offset: usize = IGMPV3_REPORT_HEADER_SIZE;
+                for _ in 0..num_records {
+                    if buf.len() < offset + IGMPV3_GROUP_RECORD_SIZE {
+                        return Err(Fail::new(EBADMSG, "IGMPv3 report too small"));
+                    }
+                    let record: &[u8] = &buf[offset..];
+                    let aux_data_len: usize = record[1] as usize * 4;
+                    let num_sources: usize = u16::from_be_bytes([record[2], record[3]]) as usize;
+                    records.push((record[0], Ipv4Addr::new(record[4], record[5], record[6], record[7])));
+                    offset += IGMPV3_GROUP_RECORD_SIZE + num_sources * 4 + aux_data_len;
+                }
+                Ok(IgmpMessage::ReportV3 { records })
+            },
+            _ => Err(Fail::new(EBADMSG, "unsupported IGMP message type")),
+        }
+    }
+
+    /// Serializes this message into [buf], which must be [compute_size] bytes long.
+    pub fn serialize(&self, buf: &mut [u8]) {
+        debug_assert_eq!(buf.len(), self.compute_size());
+        buf.fill(0);
+        match self {
+            IgmpMessage::Query {
+                version,
+                group,
+                max_resp_time,
+            } => {
+                buf[0] = IGMP_TYPE_MEMBERSHIP_QUERY;
+                buf[1] = match version {
+                    IgmpVersion::V2 => (max_resp_time.as_millis() / 100).min(u8::MAX as u128) as u8,
+                    IgmpVersion::V3 => Self::encode_max_resp_code(*max_resp_time),
+                };
+                buf[4..8].copy_from_slice(&group.octets());
+                if *version == IgmpVersion::V3 {
+                    buf[8] = IGMPV3_DEFAULT_QRV;
+                    buf[9] = IGMPV3_DEFAULT_QQIC;
+                }
+            },
+            IgmpMessage::ReportV2 { group } => {
+                buf[0] = IGMPV2_TYPE_MEMBERSHIP_REPORT;
+                buf[4..8].copy_from_slice(&group.octets());
+            },
+            IgmpMessage::Leave { group } => {
+                buf[0] = IGMPV2_TYPE_LEAVE_GROUP;
+                buf[4..8].copy_from_slice(&group.octets());
+            },
+            IgmpMessage::ReportV3 { records } => {
+                buf[0] = IGMPV3_TYPE_MEMBERSHIP_REPORT;
+                buf[6..8].copy_from_slice(&(records.len() as u16).to_be_bytes());
+                for (i, (record_type, group)) in records.iter().enumerate() {
+                    let offset: usize = IGMPV3_REPORT_HEADER_SIZE + i * IGMPV3_GROUP_RECORD_SIZE;
+                    buf[offset] = *record_type;
+                    buf[offset + 4..offset + 8].copy_from_slice(&group.octets());
+                }
+            },
+        }
+        let checksum: u16 = fold16(compute_generic_checksum(buf, None));
+        buf[2..4].copy_from_slice(&checksum.to_be_bytes());
+    }
+
+    /// Decodes the Max Resp Code of an IGMPv3 query, which is in tenths of a second and switches to a floating-point
+    /// format past 127 (see RFC 3376 Section 4.1.1).
+    fn decode_max_resp_code(code: u8) -> Duration {
+        let tenths: u64 = if code < 0x80 {
+            code as u64
+        } else {
+            let exp: u8 = (code >> 4) & 0x07;
+            let mant: u8 = code & 0x0f;
+            ((mant | 0x10) as u64) << (exp + 3)
+        };
+        Duration::from_millis(tenths * 100)
+    }
+
+    /// Encodes [max_resp_time] as the Max Resp Code of an IGMPv3 query, rounding down.
+    fn encode_max_resp_code(max_resp_time: Duration) -> u8 {
+        let tenths: u128 = max_resp_time.as_millis() / 100;
+        if tenths < 0x80 {
+            return tenths as u8;
+        }
+        for exp in 0..8u8 {
+            let mant: u128 = tenths >> (exp + 3);
+            if mant < 0x20 {
+                return 0x80 | (exp << 4) | (mant as u8 & 0x0f);
+            }
+        }
+        u8::MAX
+    }
+}
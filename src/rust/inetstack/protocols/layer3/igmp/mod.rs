@@ -0,0 +1,16 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+mod header;
+mod peer;
+
+#[cfg(test)]
+mod tests;
+
+pub use self::{
+    header::{
+        IgmpMessage,
+        IgmpVersion,
+    },
+    peer::SharedIgmpPeer,
+};
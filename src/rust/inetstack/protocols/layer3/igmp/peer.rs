@@ -0,0 +1,440 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    demikernel::config::Config,
+    inetstack::protocols::{
+        layer2::{
+            ipv4_multicast_link_addr,
+            SharedLayer2Endpoint,
+            ETHERNET2_HEADER_SIZE,
+        },
+        layer3::{
+            igmp::header::{
+                IgmpMessage,
+                IgmpVersion,
+                IGMPV3_CHANGE_TO_EXCLUDE_MODE,
+                IGMPV3_CHANGE_TO_INCLUDE_MODE,
+                IGMPV3_MODE_IS_EXCLUDE,
+            },
+            ip::IpProtocol,
+            ipv4::{
+                Ipv4Header,
+                IPV4_HEADER_MIN_SIZE,
+            },
+        },
+    },
+    runtime::{
+        conditional_yield_until,
+        fail::Fail,
+        memory::DemiBuffer,
+        SharedConditionVariable,
+        SharedDemiRuntime,
+        SharedObject,
+    },
+};
+use ::futures::FutureExt;
+use ::rand::{
+    prelude::SmallRng,
+    Rng,
+    SeedableRng,
+};
+use ::std::{
+    collections::HashMap,
+    net::Ipv4Addr,
+    ops::{
+        Deref,
+        DerefMut,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// All-systems group, which every host belongs to and never reports (see RFC 1112 Section 4).
+const IGMP_ALL_SYSTEMS_GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 1);
+
+/// All-routers group, which IGMPv2 Leave Group messages go to (see RFC 2236 Section 3).
+const IGMP_ALL_ROUTERS_GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 2);
+
+/// Group of IGMPv3-capable multicast routers, which IGMPv3 reports go to (see RFC 3376 Section 4.2.14).
+const IGMPV3_ROUTERS_GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 22);
+
+/// How long we keep speaking IGMPv2 after hearing an IGMPv2 query (see RFC 3376 Section 8.12).
+const IGMPV2_QUERIER_PRESENT_TIMEOUT: Duration = Duration::from_secs(260);
+
+/// How many times we send each unsolicited report, to make up for lost ones (see RFC 2236 Section 8.1 and RFC 3376
+/// Section 8.1).
+const IGMP_ROBUSTNESS_VARIABLE: usize = 2;
+
+/// Maximum delay between repetitions of an unsolicited report (see RFC 2236 Section 8.10 and RFC 3376 Section 8.11).
+const IGMPV2_UNSOLICITED_REPORT_INTERVAL: Duration = Duration::from_secs(10);
+const IGMPV3_UNSOLICITED_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Size of the Router Alert option that IGMP messages carry (in bytes).
+const IPV4_ROUTER_ALERT_SIZE: usize = 4;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Unsolicited report about joining or leaving a group that we still have to repeat.
+struct StateChange {
+    /// Whether we joined the group, rather than left it.
+    joined: bool,
+    /// How many more times we send the report.
+    retransmissions_left: usize,
+    /// When the next one is due.
+    due: Instant,
+}
+
+///
+/// Internet Group Management Protocol (IGMP)
+///
+/// Hosts use this protocol to tell the multicast routers on their link which IPv4 multicast groups they belong to. We
+/// act as a host in the sense of RFC 2236 (IGMPv2) and RFC 3376 (IGMPv3): we send unsolicited reports when joining and
+/// leaving groups and answer the queries of routers after a random delay. We speak IGMPv3, unless an IGMPv2 querier
+/// is present on the link.
+///
+pub struct IgmpPeer {
+    /// Shared DemiRuntime.
+    runtime: SharedDemiRuntime,
+    /// Underlying Network Transport
+    layer2_endpoint: SharedLayer2Endpoint,
+    /// Primary local IPv4 address, which we send reports from.
+    local_ipv4_addr: Ipv4Addr,
+    /// Groups that we belong to, along with the number of sockets that joined each of them.
+    groups: HashMap<Ipv4Addr, usize>,
+    /// Reports that we owe to queriers, along with when they are due.
+    pending_reports: HashMap<Ipv4Addr, Instant>,
+    /// Unsolicited reports that we still have to repeat.
+    pending_state_changes: HashMap<Ipv4Addr, StateChange>,
+    /// When we go back to IGMPv3 after hearing an IGMPv2 query, if we did.
+    v2_querier_present_until: Option<Instant>,
+    /// Wakes the background coroutine when a report gets scheduled.
+    report_scheduled: SharedConditionVariable,
+    /// Random number generator
+    rng: SmallRng,
+}
+
+#[derive(Clone)]
+pub struct SharedIgmpPeer(SharedObject<IgmpPeer>);
+
+//======================================================================================================================
+// Associate Functions
+//======================================================================================================================
+
+impl SharedIgmpPeer {
+    pub fn new(
+        config: &Config,
+        mut runtime: SharedDemiRuntime,
+        mut layer2_endpoint: SharedLayer2Endpoint,
+        rng_seed: [u8; 32],
+    ) -> Result<Self, Fail> {
+        // Every host receives the all-systems group, which routers send general queries to.
+        layer2_endpoint.join_multicast_group(ipv4_multicast_link_addr(IGMP_ALL_SYSTEMS_GROUP))?;
+        let peer: SharedIgmpPeer = Self(SharedObject::new(IgmpPeer {
+            runtime: runtime.clone(),
+            layer2_endpoint,
+            local_ipv4_addr: config
+                .local_ipv4_addr()
+                .ok_or_else(|| Config::missing_option("local_ipv4_addr"))?,
+            groups: HashMap::new(),
+            pending_reports: HashMap::new(),
+            pending_state_changes: HashMap::new(),
+            v2_querier_present_until: None,
+            report_scheduled: SharedConditionVariable::default(),
+            rng: SmallRng::from_seed(rng_seed),
+        }));
+        runtime
+            .insert_background_coroutine("bgc::inetstack::igmp::background", Box::pin(peer.clone().poll().fuse()))?;
+        Ok(peer)
+    }
+
+    /// Background task for answering queries and repeating unsolicited reports once they are due.
+    async fn poll(mut self) {
+        loop {
+            let now: Instant = self.runtime.get_now();
+            self.send_due_reports(now);
+            self.send_due_state_changes(now);
+            let next_due: Option<Instant> = self
+                .pending_reports
+                .values()
+                .copied()
+                .chain(
+                    self.pending_state_changes
+                        .values()
+                        .map(|state_change: &StateChange| state_change.due),
+                )
+                .min();
+            let mut report_scheduled: SharedConditionVariable = self.report_scheduled.clone();
+            // Either a new report got scheduled or the earliest one is due, so look again in both cases.
+            let _ = conditional_yield_until(report_scheduled.wait(), next_due).await;
+        }
+    }
+
+    /// Checks whether we receive datagrams that are sent to the multicast [group].
+    pub fn is_member(&self, group: Ipv4Addr) -> bool {
+        group == IGMP_ALL_SYSTEMS_GROUP || self.groups.contains_key(&group)
+    }
+
+    /// Joins the multicast [group] on behalf of a socket and tells the routers on the link the first time around.
+    pub fn join(&mut self, group: Ipv4Addr) -> Result<(), Fail> {
+        if !group.is_multicast() {
+            let cause: String = format!("not a multicast address (group={:?})", group);
+            error!("join(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        if let Some(count) = self.groups.get_mut(&group) {
+            *count += 1;
+            return Ok(());
+        }
+        self.layer2_endpoint
+            .join_multicast_group(ipv4_multicast_link_addr(group))?;
+        self.groups.insert(group, 1);
+        if group != IGMP_ALL_SYSTEMS_GROUP {
+            self.report_state_change(group, true);
+        }
+        Ok(())
+    }
+
+    /// Leaves the multicast [group] on behalf of a socket and tells the routers on the link once no socket is left.
+    pub fn leave(&mut self, group: Ipv4Addr) -> Result<(), Fail> {
+        match self.groups.get_mut(&group) {
+            Some(count) if *count > 1 => {
+                *count -= 1;
+                return Ok(());
+            },
+            Some(_) => {
+                self.groups.remove(&group);
+            },
+            None => {
+                let cause: String = format!("not a member of multicast group (group={:?})", group);
+                warn!("leave(): {}", cause);
+                return Err(Fail::new(libc::EADDRNOTAVAIL, &cause));
+            },
+        }
+        self.pending_reports.remove(&group);
+        if group != IGMP_ALL_SYSTEMS_GROUP {
+            self.report_state_change(group, false);
+        }
+        self.layer2_endpoint
+            .leave_multicast_group(ipv4_multicast_link_addr(group))
+    }
+
+    /// Parses and handles an IGMP message.
+    pub fn receive(&mut self, ipv4_hdr: Ipv4Header, buf: DemiBuffer) {
+        let message: IgmpMessage = match IgmpMessage::parse(&buf[..]) {
+            Ok(message) => message,
+            Err(e) => {
+                warn!("dropping IGMP message: {:?}", e);
+                return;
+            },
+        };
+        debug!("IGMP received {:?} from {:?}", message, ipv4_hdr.get_src_addr());
+        let now: Instant = self.runtime.get_now();
+        match message {
+            IgmpMessage::Query {
+                version,
+                group,
+                max_resp_time,
+            } => {
+                if version == IgmpVersion::V2 {
+                    self.v2_querier_present_until = Some(now + IGMPV2_QUERIER_PRESENT_TIMEOUT);
+                }
+                let groups: Vec<Ipv4Addr> = if group.is_unspecified() {
+                    self.groups.keys().copied().collect()
+                } else if self.groups.contains_key(&group) {
+                    vec![group]
+                } else {
+                    return;
+                };
+                for group in groups {
+                    if group != IGMP_ALL_SYSTEMS_GROUP {
+                        self.schedule_report(now, group, max_resp_time);
+                    }
+                }
+                self.report_scheduled.signal();
+            },
+            // Another member already answered the querier for this group, so IGMPv2 hosts keep quiet (see RFC 2236
+            // Section 3). IGMPv3 hosts always answer.
+            IgmpMessage::ReportV2 { group } if self.get_version(now) == IgmpVersion::V2 => {
+                self.pending_reports.remove(&group);
+            },
+            _ => {},
+        }
+    }
+
+    /// Tells the routers on the link that we [joined] or left [group], and schedules the repetitions of that report.
+    /// IGMPv2 only repeats membership reports, not Leave Group messages (see RFC 2236 Section 3).
+    fn report_state_change(&mut self, group: Ipv4Addr, joined: bool) {
+        let now: Instant = self.runtime.get_now();
+        let version: IgmpVersion = self.get_version(now);
+        self.transmit(Self::state_change_message(version, group, joined));
+        self.pending_state_changes.remove(&group);
+        if joined || version == IgmpVersion::V3 {
+            let due: Instant = now + self.unsolicited_report_delay(version);
+            self.pending_state_changes.insert(
+                group,
+                StateChange {
+                    joined,
+                    retransmissions_left: IGMP_ROBUSTNESS_VARIABLE - 1,
+                    due,
+                },
+            );
+            self.report_scheduled.signal();
+        }
+    }
+
+    /// Repeats the unsolicited reports that are due at [now].
+    fn send_due_state_changes(&mut self, now: Instant) {
+        let due_groups: Vec<Ipv4Addr> = self
+            .pending_state_changes
+            .iter()
+            .filter(|(_, state_change)| state_change.due <= now)
+            .map(|(group, _)| *group)
+            .collect();
+        let version: IgmpVersion = self.get_version(now);
+        for group in due_groups {
+            let delay: Duration = self.unsolicited_report_delay(version);
+            let state_change: &mut StateChange = match self.pending_state_changes.get_mut(&group) {
+                Some(state_change) => state_change,
+                None => continue,
+            };
+            let joined: bool = state_change.joined;
+            state_change.retransmissions_left -= 1;
+            state_change.due = now + delay;
+            if state_change.retransmissions_left == 0 {
+                self.pending_state_changes.remove(&group);
+            }
+            self.transmit(Self::state_change_message(version, group, joined));
+        }
+    }
+
+    /// Returns the message that tells [version] routers that we [joined] or left [group].
+    fn state_change_message(version: IgmpVersion, group: Ipv4Addr, joined: bool) -> IgmpMessage {
+        match (version, joined) {
+            (IgmpVersion::V3, true) => IgmpMessage::ReportV3 {
+                records: vec![(IGMPV3_CHANGE_TO_EXCLUDE_MODE, group)],
+            },
+            (IgmpVersion::V3, false) => IgmpMessage::ReportV3 {
+                records: vec![(IGMPV3_CHANGE_TO_INCLUDE_MODE, group)],
+            },
+            (IgmpVersion::V2, true) => IgmpMessage::ReportV2 { group },
+            (IgmpVersion::V2, false) => IgmpMessage::Leave { group },
+        }
+    }
+
+    /// Returns a random delay until the next repetition of an unsolicited report to [version] routers.
+    fn unsolicited_report_delay(&mut self, version: IgmpVersion) -> Duration {
+        let interval: Duration = match version {
+            IgmpVersion::V3 => IGMPV3_UNSOLICITED_REPORT_INTERVAL,
+            IgmpVersion::V2 => IGMPV2_UNSOLICITED_REPORT_INTERVAL,
+        };
+        Duration::from_millis(self.rng.gen_range(1..=interval.as_millis() as u64))
+    }
+
+    /// Schedules a report for [group] at a random time within [max_resp_time], unless one is due earlier already.
+    fn schedule_report(&mut self, now: Instant, group: Ipv4Addr, max_resp_time: Duration) {
+        let max_delay_ms: u64 = max_resp_time.as_millis() as u64;
+        let delay: Duration = match max_delay_ms {
+            0 => Duration::ZERO,
+            _ => Duration::from_millis(self.rng.gen_range(0..max_delay_ms)),
+        };
+        let due: Instant = now + delay;
+        self.pending_reports
+            .entry(group)
+            .and_modify(|pending: &mut Instant| *pending = (*pending).min(due))
+            .or_insert(due);
+    }
+
+    /// Sends the reports that are due at [now]. IGMPv3 reports for several groups go out in a single message.
+    fn send_due_reports(&mut self, now: Instant) {
+        let due_groups: Vec<Ipv4Addr> = self
+            .pending_reports
+            .iter()
+            .filter(|(_, due)| **due <= now)
+            .map(|(group, _)| *group)
+            .collect();
+        if due_groups.is_empty() {
+            return;
+        }
+        for group in &due_groups {
+            self.pending_reports.remove(group);
+        }
+        match self.get_version(now) {
+            IgmpVersion::V3 => {
+                let records: Vec<(u8, Ipv4Addr)> = due_groups
+                    .into_iter()
+                    .map(|group: Ipv4Addr| (IGMPV3_MODE_IS_EXCLUDE, group))
+                    .collect();
+                self.transmit(IgmpMessage::ReportV3 { records });
+            },
+            IgmpVersion::V2 => {
+                for group in due_groups {
+                    self.transmit(IgmpMessage::ReportV2 { group });
+                }
+            },
+        }
+    }
+
+    /// Returns the IGMP version that we speak at [now].
+    fn get_version(&self, now: Instant) -> IgmpVersion {
+        match self.v2_querier_present_until {
+            Some(until) if now < until => IgmpVersion::V2,
+            _ => IgmpVersion::V3,
+        }
+    }
+
+    /// Sends [message] to the routers on the link. IGMPv2 reports go to the group that they are about, Leave Group
+    /// messages to all routers and IGMPv3 reports to IGMPv3-capable routers. This is best-effort, like the rest of
+    /// IGMP.
+    fn transmit(&mut self, message: IgmpMessage) {
+        let dst_ipv4_addr: Ipv4Addr = match message {
+            IgmpMessage::ReportV2 { group } => group,
+            IgmpMessage::Leave { .. } => IGMP_ALL_ROUTERS_GROUP,
+            IgmpMessage::ReportV3 { .. } | IgmpMessage::Query { .. } => IGMPV3_ROUTERS_GROUP,
+        };
+        let mut pkt: DemiBuffer = DemiBuffer::new_with_headroom(
+            message.compute_size() as u16,
+            (IPV4_HEADER_MIN_SIZE as usize + IPV4_ROUTER_ALERT_SIZE + ETHERNET2_HEADER_SIZE) as u16,
+        );
+        message.serialize(&mut pkt[..]);
+        let ipv4_hdr: Ipv4Header =
+            Ipv4Header::new_with_router_alert(self.local_ipv4_addr, dst_ipv4_addr, IpProtocol::IGMP);
+        ipv4_hdr.serialize_and_attach(&mut pkt);
+        if let Err(e) = self
+            .layer2_endpoint
+            .transmit_ipv4_packet(ipv4_multicast_link_addr(dst_ipv4_addr), pkt)
+        {
+            warn!("could not send IGMP message: {:?}", e);
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Deref for SharedIgmpPeer {
+    type Target = IgmpPeer;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl DerefMut for SharedIgmpPeer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
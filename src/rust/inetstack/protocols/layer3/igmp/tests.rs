@@ -0,0 +1,350 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::{
+        protocols::{
+            layer2::{
+                ipv4_multicast_link_addr,
+                EtherType2,
+                Ethernet2Header,
+                ETHERNET2_HEADER_SIZE,
+            },
+            layer3::{
+                igmp::{
+                    header::{
+                        IGMPV3_CHANGE_TO_EXCLUDE_MODE,
+                        IGMPV3_CHANGE_TO_INCLUDE_MODE,
+                        IGMPV3_MODE_IS_EXCLUDE,
+                    },
+                    IgmpMessage,
+                    IgmpVersion,
+                },
+                ip::IpProtocol,
+                ipv4::{
+                    Ipv4Header,
+                    IPV4_HEADER_MIN_SIZE,
+                },
+            },
+        },
+        test_helpers::{
+            self,
+            SharedEngine,
+            SharedTestPhysicalLayer,
+        },
+    },
+    runtime::{
+        memory::DemiBuffer,
+        network::{
+            socket::option::SocketOption,
+            types::MacAddress,
+        },
+        QDesc,
+    },
+};
+use ::anyhow::Result;
+use ::std::{
+    collections::VecDeque,
+    net::Ipv4Addr,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Multicast group that the tests join.
+const GROUP: Ipv4Addr = Ipv4Addr::new(239, 1, 2, 3);
+
+/// Group that IGMPv3 reports go to.
+const IGMPV3_ROUTERS_GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 22);
+
+/// Group that general queries go to.
+const IGMP_ALL_SYSTEMS_GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 1);
+
+/// Maximum delay between repetitions of an unsolicited IGMPv3 report.
+const IGMPV3_UNSOLICITED_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+//======================================================================================================================
+// Tests
+//======================================================================================================================
+
+/// Tests that multicast addresses map to the Ethernet addresses that carry their low-order 23 bits.
+#[test]
+fn igmp_multicast_link_addr() -> Result<()> {
+    crate::ensure_eq!(
+        ipv4_multicast_link_addr(Ipv4Addr::new(224, 0, 0, 1)),
+        MacAddress::new([0x01, 0x00, 0x5e, 0x00, 0x00, 0x01])
+    );
+    // The high-order bit of the second octet does not make it into the link address.
+    crate::ensure_eq!(
+        ipv4_multicast_link_addr(Ipv4Addr::new(239, 129, 2, 3)),
+        MacAddress::new([0x01, 0x00, 0x5e, 0x01, 0x02, 0x03])
+    );
+    crate::ensure_eq!(
+        ipv4_multicast_link_addr(Ipv4Addr::new(224, 1, 2, 3)),
+        ipv4_multicast_link_addr(Ipv4Addr::new(239, 129, 2, 3))
+    );
+
+    Ok(())
+}
+
+/// Tests that IGMP messages survive a round trip through serialization and parsing.
+#[test]
+fn igmp_message_round_trip() -> Result<()> {
+    let messages: Vec<IgmpMessage> = vec![
+        IgmpMessage::Query {
+            version: IgmpVersion::V2,
+            group: Ipv4Addr::UNSPECIFIED,
+            max_resp_time: Duration::from_secs(10),
+        },
+        IgmpMessage::Query {
+            version: IgmpVersion::V3,
+            group: GROUP,
+            max_resp_time: Duration::from_millis(12_800),
+        },
+        IgmpMessage::ReportV2 { group: GROUP },
+        IgmpMessage::Leave { group: GROUP },
+        IgmpMessage::ReportV3 {
+            records: vec![
+                (IGMPV3_MODE_IS_EXCLUDE, GROUP),
+                (IGMPV3_CHANGE_TO_INCLUDE_MODE, Ipv4Addr::new(239, 3, 2, 1)),
+            ],
+        },
+    ];
+    for message in messages {
+        let mut buf: Vec<u8> = vec![0; message.compute_size()];
+        message.serialize(&mut buf);
+        crate::ensure_eq!(IgmpMessage::parse(&buf)?, message);
+    }
+
+    // Corrupted messages are dropped.
+    let mut buf: Vec<u8> = vec![0; 8];
+    IgmpMessage::ReportV2 { group: GROUP }.serialize(&mut buf);
+    buf[7] ^= 1;
+    crate::ensure_eq!(IgmpMessage::parse(&buf).is_err(), true);
+
+    Ok(())
+}
+
+/// Tests that joining a group programs the link-layer filter and sends an unsolicited IGMPv3 report, which gets
+/// repeated once.
+#[test]
+fn igmp_join_sends_report() -> Result<()> {
+    let mut now: Instant = Instant::now();
+    let mut engine: SharedEngine = new_engine(now)?;
+    crate::ensure_eq!(
+        engine.is_multicast_member(ipv4_multicast_link_addr(IGMP_ALL_SYSTEMS_GROUP)),
+        true
+    );
+
+    let qd: QDesc = join(&mut engine, GROUP)?;
+    crate::ensure_eq!(engine.is_multicast_member(ipv4_multicast_link_addr(GROUP)), true);
+
+    let expected_message: IgmpMessage = IgmpMessage::ReportV3 {
+        records: vec![(IGMPV3_CHANGE_TO_EXCLUDE_MODE, GROUP)],
+    };
+    let mut frames: VecDeque<DemiBuffer> = engine.pop_all_frames();
+    crate::ensure_eq!(frames.len(), 1);
+    let (dst_ipv4_addr, message): (Ipv4Addr, IgmpMessage) = parse_igmp_frame(frames.pop_front().unwrap())?;
+    crate::ensure_eq!(dst_ipv4_addr, IGMPV3_ROUTERS_GROUP);
+    crate::ensure_eq!(message, expected_message);
+
+    // The report gets repeated within the unsolicited report interval, and only once.
+    for expected_num_frames in [1, 0] {
+        now += IGMPV3_UNSOLICITED_REPORT_INTERVAL;
+        engine.advance_clock(now);
+        engine.poll();
+        engine.poll();
+        let mut frames: VecDeque<DemiBuffer> = engine.pop_all_frames();
+        crate::ensure_eq!(frames.len(), expected_num_frames);
+        if let Some(frame) = frames.pop_front() {
+            let (dst_ipv4_addr, message): (Ipv4Addr, IgmpMessage) = parse_igmp_frame(frame)?;
+            crate::ensure_eq!(dst_ipv4_addr, IGMPV3_ROUTERS_GROUP);
+            crate::ensure_eq!(message, expected_message);
+        }
+    }
+
+    // Joining the same group twice on one socket fails.
+    match engine.udp_set_socket_option(qd, SocketOption::AddMembership(GROUP, Ipv4Addr::UNSPECIFIED)) {
+        Err(e) if e.errno == libc::EADDRINUSE => {},
+        _ => anyhow::bail!("joining a group twice should fail with EADDRINUSE"),
+    }
+
+    Ok(())
+}
+
+/// Tests that a general query gets answered within its maximum response time.
+#[test]
+fn igmp_answer_general_query() -> Result<()> {
+    let mut now: Instant = Instant::now();
+    let mut engine: SharedEngine = new_engine(now)?;
+    join(&mut engine, GROUP)?;
+    skip_unsolicited_reports(&mut engine, &mut now);
+
+    engine.push_frame(build_igmp_frame(
+        IGMP_ALL_SYSTEMS_GROUP,
+        IgmpMessage::Query {
+            version: IgmpVersion::V3,
+            group: Ipv4Addr::UNSPECIFIED,
+            max_resp_time: Duration::from_secs(1),
+        },
+    ));
+    engine.poll();
+    engine.poll();
+
+    // Past the maximum response time, the report must be out.
+    now += Duration::from_secs(1);
+    engine.advance_clock(now);
+    engine.poll();
+    engine.poll();
+
+    let mut frames: VecDeque<DemiBuffer> = engine.pop_all_frames();
+    crate::ensure_eq!(frames.len(), 1);
+    let (dst_ipv4_addr, message): (Ipv4Addr, IgmpMessage) = parse_igmp_frame(frames.pop_front().unwrap())?;
+    crate::ensure_eq!(dst_ipv4_addr, IGMPV3_ROUTERS_GROUP);
+    crate::ensure_eq!(
+        message,
+        IgmpMessage::ReportV3 {
+            records: vec![(IGMPV3_MODE_IS_EXCLUDE, GROUP)]
+        }
+    );
+
+    Ok(())
+}
+
+/// Tests that we fall back to IGMPv2 when an IGMPv2 querier is present, and that reports of other members suppress
+/// ours.
+#[test]
+fn igmp_v2_querier_and_suppression() -> Result<()> {
+    let mut now: Instant = Instant::now();
+    let mut engine: SharedEngine = new_engine(now)?;
+    let qd: QDesc = join(&mut engine, GROUP)?;
+    skip_unsolicited_reports(&mut engine, &mut now);
+
+    // Another member answers the query before we do.
+    engine.push_frame(build_igmp_frame(
+        IGMP_ALL_SYSTEMS_GROUP,
+        IgmpMessage::Query {
+            version: IgmpVersion::V2,
+            group: Ipv4Addr::UNSPECIFIED,
+            max_resp_time: Duration::from_secs(1),
+        },
+    ));
+    engine.push_frame(build_igmp_frame(GROUP, IgmpMessage::ReportV2 { group: GROUP }));
+    engine.poll();
+    engine.poll();
+    now += Duration::from_secs(1);
+    engine.advance_clock(now);
+    engine.poll();
+    engine.poll();
+    crate::ensure_eq!(engine.pop_all_frames().len(), 0);
+
+    // Leaving the group sends an IGMPv2 Leave Group message to all routers.
+    engine.udp_set_socket_option(qd, SocketOption::DropMembership(GROUP, Ipv4Addr::UNSPECIFIED))?;
+    crate::ensure_eq!(engine.is_multicast_member(ipv4_multicast_link_addr(GROUP)), false);
+    let mut frames: VecDeque<DemiBuffer> = engine.pop_all_frames();
+    crate::ensure_eq!(frames.len(), 1);
+    let (dst_ipv4_addr, message): (Ipv4Addr, IgmpMessage) = parse_igmp_frame(frames.pop_front().unwrap())?;
+    crate::ensure_eq!(dst_ipv4_addr, Ipv4Addr::new(224, 0, 0, 2));
+    crate::ensure_eq!(message, IgmpMessage::Leave { group: GROUP });
+
+    Ok(())
+}
+
+/// Tests that closing a socket leaves the groups that it joined.
+#[test]
+fn igmp_close_leaves_groups() -> Result<()> {
+    let now: Instant = Instant::now();
+    let mut engine: SharedEngine = new_engine(now)?;
+    let qd: QDesc = join(&mut engine, GROUP)?;
+    engine.pop_all_frames();
+
+    engine.udp_close(qd)?;
+    crate::ensure_eq!(engine.is_multicast_member(ipv4_multicast_link_addr(GROUP)), false);
+    let mut frames: VecDeque<DemiBuffer> = engine.pop_all_frames();
+    crate::ensure_eq!(frames.len(), 1);
+    let (_, message): (Ipv4Addr, IgmpMessage) = parse_igmp_frame(frames.pop_front().unwrap())?;
+    crate::ensure_eq!(
+        message,
+        IgmpMessage::ReportV3 {
+            records: vec![(IGMPV3_CHANGE_TO_INCLUDE_MODE, GROUP)]
+        }
+    );
+
+    Ok(())
+}
+
+//======================================================================================================================
+// Helper Functions
+//======================================================================================================================
+
+/// Creates a new engine.
+fn new_engine(now: Instant) -> Result<SharedEngine> {
+    let layer1_endpoint: SharedTestPhysicalLayer = SharedTestPhysicalLayer::new_test(now);
+    Ok(SharedEngine::new(
+        &test_helpers::ALICE_CONFIG_PATH,
+        layer1_endpoint,
+        now,
+    )?)
+}
+
+/// Opens a UDP socket that joins [group].
+fn join(engine: &mut SharedEngine, group: Ipv4Addr) -> Result<QDesc> {
+    let qd: QDesc = engine.udp_socket()?;
+    engine.udp_set_socket_option(qd, SocketOption::AddMembership(group, Ipv4Addr::UNSPECIFIED))?;
+    Ok(qd)
+}
+
+/// Lets the repetitions of the unsolicited reports go out and drops them.
+fn skip_unsolicited_reports(engine: &mut SharedEngine, now: &mut Instant) {
+    *now += IGMPV3_UNSOLICITED_REPORT_INTERVAL;
+    engine.advance_clock(*now);
+    engine.poll();
+    engine.poll();
+    engine.pop_all_frames();
+}
+
+/// Builds a frame that Bob sends to [dst_ipv4_addr] with an IGMP [message].
+fn build_igmp_frame(dst_ipv4_addr: Ipv4Addr, message: IgmpMessage) -> DemiBuffer {
+    let mut pkt: DemiBuffer = DemiBuffer::new_with_headroom(
+        message.compute_size() as u16,
+        (IPV4_HEADER_MIN_SIZE as usize + 4 + ETHERNET2_HEADER_SIZE) as u16,
+    );
+    message.serialize(&mut pkt[..]);
+    let ipv4_header: Ipv4Header =
+        Ipv4Header::new_with_router_alert(test_helpers::BOB_IPV4, dst_ipv4_addr, IpProtocol::IGMP);
+    ipv4_header.serialize_and_attach(&mut pkt);
+    let eth2_header: Ethernet2Header = Ethernet2Header::new(
+        ipv4_multicast_link_addr(dst_ipv4_addr),
+        test_helpers::BOB_MAC,
+        EtherType2::Ipv4,
+    );
+    eth2_header.serialize_and_attach(&mut pkt);
+    pkt
+}
+
+/// Parses a frame that carries an IGMP message, checking that it stays on the link and alerts routers.
+fn parse_igmp_frame(mut pkt: DemiBuffer) -> Result<(Ipv4Addr, IgmpMessage)> {
+    let eth2_header: Ethernet2Header = Ethernet2Header::parse_and_strip(&mut pkt)?;
+    crate::ensure_eq!(eth2_header.ether_type(), EtherType2::Ipv4);
+    // The header carries the Router Alert option and a TTL of 1.
+    crate::ensure_eq!(pkt[0] & 0xF, 6);
+    crate::ensure_eq!(pkt[8], 1);
+    crate::ensure_eq!(&pkt[20..24], &[0x94, 0x04, 0x00, 0x00]);
+    let ipv4_header: Ipv4Header = Ipv4Header::parse_and_strip(&mut pkt)?;
+    crate::ensure_eq!(ipv4_header.get_protocol(), IpProtocol::IGMP);
+    crate::ensure_eq!(ipv4_header.get_src_addr(), test_helpers::ALICE_IPV4);
+    crate::ensure_eq!(
+        eth2_header.dst_addr(),
+        ipv4_multicast_link_addr(ipv4_header.get_dest_addr())
+    );
+    Ok((ipv4_header.get_dest_addr(), IgmpMessage::parse(&pkt[..])?))
+}
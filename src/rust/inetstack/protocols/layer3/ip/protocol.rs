@@ -11,16 +11,20 @@ use crate::runtime::fail::Fail;
 // Structures
 //======================================================================================================================
 
-/// Ipv4 Protocol
+/// IP Protocol (IPv4 Protocol field and IPv6 Next Header field)
 #[repr(u8)]
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum IpProtocol {
     /// Internet Control Message Protocol
     ICMPv4 = 0x01,
+    /// Internet Group Management Protocol
+    IGMP = 0x02,
     /// Transmission Control Protocol
     TCP = 0x06,
     /// User Datagram Protocol
     UDP = 0x11,
+    /// Internet Control Message Protocol for IPv6
+    ICMPv6 = 0x3a,
 }
 
 //======================================================================================================================
@@ -34,9 +38,11 @@ impl TryFrom<u8> for IpProtocol {
     fn try_from(value: u8) -> Result<Self, Self::Error> {
         match value {
             0x01 => Ok(IpProtocol::ICMPv4),
+            0x02 => Ok(IpProtocol::IGMP),
             0x06 => Ok(IpProtocol::TCP),
             0x11 => Ok(IpProtocol::UDP),
-            _ => Err(Fail::new(libc::ENOTSUP, "unsupported IPv4 protocol")),
+            0x3a => Ok(IpProtocol::ICMPv6),
+            _ => Err(Fail::new(libc::ENOTSUP, "unsupported IP protocol")),
         }
     }
 }
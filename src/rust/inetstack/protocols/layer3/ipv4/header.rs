@@ -37,6 +37,12 @@ const IPV4_IHL_NO_OPTIONS: u8 = (IPV4_HEADER_MIN_SIZE as u8) / 4;
 /// Default time to live value.
 const DEFAULT_IPV4_TTL: u8 = 255;
 
+/// Time to live value of multicast datagrams, which keeps them on the local link (see RFC 1112 Section 6.1).
+const MULTICAST_IPV4_TTL: u8 = 1;
+
+/// Router Alert option, which asks routers to look into the datagram (see RFC 2113).
+const IPV4_OPTION_ROUTER_ALERT: [u8; 4] = [0x94, 0x04, 0x00, 0x00];
+
 /// Version number for IPv4.
 const IPV4_VERSION: u8 = 4;
 
@@ -49,6 +55,12 @@ const IPV4_CTRL_FLAG_DF: u8 = 0x2;
 /// IPv4 Control Flag: More Fragments.
 const IPV4_CTRL_FLAG_MF: u8 = 0x1;
 
+/// Fragment offsets are expressed in units of 8 bytes (see RFC 791).
+pub const IPV4_FRAGMENT_UNIT_SIZE: usize = 8;
+
+/// Maximum size of an IPv4 datagram (in bytes).
+pub const IPV4_DATAGRAM_MAX_SIZE: usize = u16::MAX as usize;
+
 //======================================================================================================================
 // Structures
 //======================================================================================================================
@@ -65,7 +77,6 @@ pub struct Ipv4Header {
     /// Explicit Congestion Notification (2 bits).
     ecn: u8,
     /// Total length of the packet including header and data (16 bits).
-    #[allow(unused)]
     total_length: u16,
     /// Used to identify the datagram to which a fragment belongs (16 bits).
     identification: u16,
@@ -84,6 +95,8 @@ pub struct Ipv4Header {
     src_addr: Ipv4Addr,
     /// Destination IP address (32 bits).
     dst_addr: Ipv4Addr,
+    /// Carry the Router Alert option? We do not keep the options of datagrams that we receive.
+    router_alert: bool,
 }
 
 //======================================================================================================================
@@ -92,7 +105,7 @@ pub struct Ipv4Header {
 
 /// Associated Functions for IPv4 Headers
 impl Ipv4Header {
-    /// Instantiates an empty IPv4 header.
+    /// Instantiates an empty IPv4 header. Datagrams to multicast addresses do not leave the local link.
     pub fn new(src_addr: Ipv4Addr, dst_addr: Ipv4Addr, protocol: IpProtocol) -> Self {
         Self {
             version: IPV4_VERSION,
@@ -103,11 +116,44 @@ impl Ipv4Header {
             identification: 0,
             flags: IPV4_CTRL_FLAG_DF,
             fragment_offset: 0,
-            ttl: DEFAULT_IPV4_TTL,
+            ttl: if dst_addr.is_multicast() {
+                MULTICAST_IPV4_TTL
+            } else {
+                DEFAULT_IPV4_TTL
+            },
             protocol,
             header_checksum: 0,
             src_addr,
             dst_addr,
+            router_alert: false,
+        }
+    }
+
+    /// Instantiates an IPv4 header that carries the Router Alert option, as IGMP messages do (see RFC 3376 Section 4).
+    pub fn new_with_router_alert(src_addr: Ipv4Addr, dst_addr: Ipv4Addr, protocol: IpProtocol) -> Self {
+        Self {
+            ihl: IPV4_IHL_NO_OPTIONS + (IPV4_OPTION_ROUTER_ALERT.len() / 4) as u8,
+            router_alert: true,
+            ..Self::new(src_addr, dst_addr, protocol)
+        }
+    }
+
+    /// Instantiates an IPv4 header for a fragment of a larger datagram. [fragment_offset] is given in bytes and must
+    /// be a multiple of [IPV4_FRAGMENT_UNIT_SIZE].
+    pub fn new_fragment(
+        src_addr: Ipv4Addr,
+        dst_addr: Ipv4Addr,
+        protocol: IpProtocol,
+        identification: u16,
+        fragment_offset: usize,
+        more_fragments: bool,
+    ) -> Self {
+        debug_assert_eq!(fragment_offset % IPV4_FRAGMENT_UNIT_SIZE, 0);
+        Self {
+            identification,
+            flags: if more_fragments { IPV4_CTRL_FLAG_MF } else { 0 },
+            fragment_offset: (fragment_offset / IPV4_FRAGMENT_UNIT_SIZE) as u16,
+            ..Self::new(src_addr, dst_addr, protocol)
         }
     }
 
@@ -118,6 +164,16 @@ impl Ipv4Header {
 
     /// Parses and strips the IPv4 header from the packet in [buf].
     pub fn parse_and_strip(buf: &mut DemiBuffer) -> Result<Self, Fail> {
+        Self::parse(buf, false)
+    }
+
+    /// Parses and strips the IPv4 header of a datagram that is embedded in an ICMPv4 error message. Such datagrams are
+    /// truncated, so their payload is shorter than what the total length field says (see RFC 792).
+    pub fn parse_and_strip_embedded(buf: &mut DemiBuffer) -> Result<Self, Fail> {
+        Self::parse(buf, true)
+    }
+
+    fn parse(buf: &mut DemiBuffer, embedded: bool) -> Result<Self, Fail> {
         // The datagram should be as big as the header.
         if buf.len() < (IPV4_DATAGRAM_MIN_SIZE as usize) {
             return Err(Fail::new(EBADMSG, "ipv4 datagram too small"));
@@ -158,7 +214,7 @@ impl Ipv4Header {
             return Err(Fail::new(EBADMSG, "ipv4 datagram smaller than header"));
         }
         // NOTE: there may be padding bytes in the buffer.
-        if (total_length as usize) > buf.len() {
+        if !embedded && (total_length as usize) > buf.len() {
             return Err(Fail::new(EBADMSG, "ipv4 datagram size mismatch"));
         }
 
@@ -182,28 +238,32 @@ impl Ipv4Header {
             return Err(Fail::new(EBADMSG, "ipv4 datagram is marked as evil"));
         }
 
-        // TODO: drop this check once we support fragmentation.
-        if flags & IPV4_CTRL_FLAG_MF != 0 {
-            warn!("fragmentation is not supported flags={:?}", flags);
-            return Err(Fail::new(ENOTSUP, "ipv4 fragmentation is not supported"));
-        }
-
         // Fragment offset.
         let fragment_offset: u16 = u16::from_be_bytes([hdr_buf[6], hdr_buf[7]]) & 0x1fff;
-        // TODO: drop this check once we support fragmentation.
-        if fragment_offset != 0 {
-            warn!("fragmentation is not supported offset={:?}", fragment_offset);
-            return Err(Fail::new(ENOTSUP, "ipv4 fragmentation is not supported"));
+        let payload_length: usize = (total_length - hdr_size) as usize;
+        // All fragments but the last one must carry a multiple of 8 bytes (see RFC 791).
+        if flags & IPV4_CTRL_FLAG_MF != 0 && payload_length % IPV4_FRAGMENT_UNIT_SIZE != 0 {
+            return Err(Fail::new(EBADMSG, "ipv4 fragment size is not a multiple of 8"));
+        }
+        // The reassembled datagram must fit in the maximum datagram size.
+        if (hdr_size as usize) + (fragment_offset as usize) * IPV4_FRAGMENT_UNIT_SIZE + payload_length
+            > IPV4_DATAGRAM_MAX_SIZE
+        {
+            return Err(Fail::new(EBADMSG, "ipv4 fragment exceeds maximum datagram size"));
         }
 
         // Time to live.
         let time_to_live: u8 = hdr_buf[8];
-        if time_to_live == 0 {
+        // The datagram embedded in a Time Exceeded message ran out of time on its way.
+        if !embedded && time_to_live == 0 {
             return Err(Fail::new(EBADMSG, "ipv4 datagram too old"));
         }
 
         // Protocol.
         let protocol: IpProtocol = IpProtocol::try_from(hdr_buf[9])?;
+        if protocol == IpProtocol::ICMPv6 {
+            return Err(Fail::new(ENOTSUP, "ICMPv6 carried over IPv4"));
+        }
 
         // Header checksum.
         let header_checksum: u16 = u16::from_be_bytes([hdr_buf[10], hdr_buf[11]]);
@@ -220,8 +280,12 @@ impl Ipv4Header {
         // Destination address.
         let dst_addr: Ipv4Addr = Ipv4Addr::new(hdr_buf[16], hdr_buf[17], hdr_buf[18], hdr_buf[19]);
 
-        // Truncate datagram.
-        let padding_bytes: usize = buf.len() - (total_length as usize);
+        // Truncate datagram. Embedded datagrams are cut short instead of padded.
+        let padding_bytes: usize = if embedded {
+            0
+        } else {
+            buf.len() - (total_length as usize)
+        };
         buf.adjust(hdr_size as usize)?;
         buf.trim(padding_bytes)?;
 
@@ -239,16 +303,36 @@ impl Ipv4Header {
             header_checksum,
             src_addr,
             dst_addr,
+            router_alert: false,
         })
     }
 
     /// Serializes the IPv4 header and prepends it to the packet in [buf]. Assumes that there is enough headroom for
     /// the header.
     pub fn serialize_and_attach(&self, buf: &mut DemiBuffer) {
-        buf.prepend(IPV4_HEADER_MIN_SIZE as usize)
+        buf.prepend(self.compute_size()).expect("Should be sufficient headroom");
+        let pkt_size_bytes: usize = buf.total_len();
+        self.serialize(buf, pkt_size_bytes as u16);
+    }
+
+    /// Serializes the header of a datagram that we received and prepends it to the quote of that datagram in [buf], as
+    /// ICMPv4 error messages embed it (see RFC 792). Unlike [Ipv4Header::serialize_and_attach], this keeps the total
+    /// length of the original datagram. We do not keep the options of datagrams that we receive, so the quoted header
+    /// leaves them out and its total length shrinks accordingly.
+    pub fn serialize_embedded_and_attach(&self, buf: &mut DemiBuffer) {
+        let options_size: u16 = self.compute_size() as u16 - IPV4_HEADER_MIN_SIZE;
+        let header: Self = Self {
+            ihl: IPV4_IHL_NO_OPTIONS,
+            router_alert: false,
+            ..*self
+        };
+        buf.prepend(header.compute_size())
             .expect("Should be sufficient headroom");
-        let pkt_size_bytes: usize = buf.len();
+        header.serialize(buf, self.total_length - options_size);
+    }
 
+    /// Writes the IPv4 header with [total_length] at the start of [buf].
+    fn serialize(&self, buf: &mut DemiBuffer, total_length: u16) {
         // Version + IHL.
         buf[0] = (self.version << 4) | self.ihl;
 
@@ -256,7 +340,7 @@ impl Ipv4Header {
         buf[1] = (self.dscp << 2) | (self.ecn & 3);
 
         // Total Length.
-        buf[2..4].copy_from_slice(&(pkt_size_bytes as u16).to_be_bytes());
+        buf[2..4].copy_from_slice(&total_length.to_be_bytes());
 
         // Identification.
         buf[4..6].copy_from_slice(&self.identification.to_be_bytes());
@@ -278,6 +362,11 @@ impl Ipv4Header {
         // Destination Address.
         buf[16..20].copy_from_slice(&self.dst_addr.octets());
 
+        // Options.
+        if self.router_alert {
+            buf[20..24].copy_from_slice(&IPV4_OPTION_ROUTER_ALERT);
+        }
+
         // Header Checksum.
         let checksum: u16 = Self::compute_checksum(buf);
         buf[10..12].copy_from_slice(&checksum.to_be_bytes());
@@ -298,7 +387,32 @@ impl Ipv4Header {
         self.protocol
     }
 
-    /// Computes the checksum of the target IPv4 header.
+    /// Returns the total length field stored in the target IPv4 header.
+    pub fn get_total_length(&self) -> u16 {
+        self.total_length
+    }
+
+    /// Returns the identification field stored in the target IPv4 header.
+    pub fn get_identification(&self) -> u16 {
+        self.identification
+    }
+
+    /// Returns the fragment offset stored in the target IPv4 header (in bytes).
+    pub fn get_fragment_offset(&self) -> usize {
+        (self.fragment_offset as usize) * IPV4_FRAGMENT_UNIT_SIZE
+    }
+
+    /// Checks whether the MF (more fragments) flag is set in the target IPv4 header.
+    pub fn has_more_fragments(&self) -> bool {
+        self.flags & IPV4_CTRL_FLAG_MF != 0
+    }
+
+    /// Checks whether the target IPv4 header belongs to a fragment of a larger datagram.
+    pub fn is_fragment(&self) -> bool {
+        self.has_more_fragments() || self.fragment_offset != 0
+    }
+
+    /// Computes the checksum of the IPv4 header at the start of [buf], options included.
     pub fn compute_checksum(buf: &[u8]) -> u16 {
         let mut state: u32 = 0xffff;
 
@@ -308,13 +422,14 @@ impl Ipv4Header {
             warn!("compute_checksum: buffer is too small (len={})", buf.len());
             return 0;
         }
+        let hdr_size: usize = (((buf[0] & 0xF) as usize) << 2).clamp(IPV4_HEADER_MIN_SIZE as usize, buf.len());
 
         for i in 0..5 {
             state += u16::from_be_bytes([buf[2 * i], buf[2 * i + 1]]) as u32;
         }
         // Skip the 5th u16 since octets 10-12 are the header checksum, whose value should be zero when
         // computing a checksum.
-        for i in 6..10 {
+        for i in 6..(hdr_size / 2) {
             state += u16::from_be_bytes([buf[2 * i], buf[2 * i + 1]]) as u32;
         }
         while state > 0xffff {
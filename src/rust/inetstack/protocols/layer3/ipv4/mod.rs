@@ -2,6 +2,7 @@
 // Licensed under the MIT license.
 
 mod header;
+mod reassembly;
 
 #[cfg(test)]
 mod tests;
@@ -10,8 +11,17 @@ mod tests;
 // Exports
 //======================================================================================================================
 
-pub use self::header::{
-    Ipv4Header,
-    IPV4_HEADER_MAX_SIZE,
-    IPV4_HEADER_MIN_SIZE,
+pub use self::{
+    header::{
+        Ipv4Header,
+        IPV4_FRAGMENT_UNIT_SIZE,
+        IPV4_HEADER_MAX_SIZE,
+        IPV4_HEADER_MIN_SIZE,
+    },
+    reassembly::{
+        Ipv4Reassembler,
+        DEFAULT_REASSEMBLY_MAX_BYTES,
+        DEFAULT_REASSEMBLY_MAX_FLOWS,
+        DEFAULT_REASSEMBLY_TIMEOUT,
+    },
 };
@@ -0,0 +1,249 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::protocols::layer3::{
+        ip::IpProtocol,
+        ipv4::header::Ipv4Header,
+    },
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+    },
+};
+use ::libc::{
+    EBADMSG,
+    ENOBUFS,
+};
+use ::std::{
+    collections::HashMap,
+    net::Ipv4Addr,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Time to wait for all fragments of a datagram to arrive (see RFC 1122 Section 3.3.2).
+pub const DEFAULT_REASSEMBLY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Maximum number of payload bytes that may be buffered across all datagrams under reassembly.
+pub const DEFAULT_REASSEMBLY_MAX_BYTES: usize = 4 * 1024 * 1024;
+
+/// Maximum number of datagrams that may be under reassembly at the same time.
+pub const DEFAULT_REASSEMBLY_MAX_FLOWS: usize = 64;
+
+/// Maximum number of fragments accepted for a single datagram. This bounds the work done on datagrams that are split
+/// in many tiny fragments.
+const MAX_FRAGMENTS_PER_FLOW: usize = 128;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Fragments that belong to the same datagram share these fields (see RFC 791).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct FlowKey {
+    src_addr: Ipv4Addr,
+    dst_addr: Ipv4Addr,
+    protocol: IpProtocol,
+    identification: u16,
+}
+
+/// A fragment that is waiting to be reassembled.
+struct Fragment {
+    /// Offset of this fragment in the original datagram (in bytes).
+    offset: usize,
+    data: DemiBuffer,
+}
+
+/// A datagram under reassembly.
+struct Flow {
+    /// Time at which the first fragment of this datagram arrived.
+    created: Instant,
+    /// Header of the first fragment. We use it as the header of the reassembled datagram.
+    header: Option<Ipv4Header>,
+    /// Received fragments, sorted by offset and without overlaps.
+    fragments: Vec<Fragment>,
+    /// Payload size of the original datagram. This is only known once the last fragment arrives.
+    total_length: Option<usize>,
+    /// Number of payload bytes received so far.
+    buffered_bytes: usize,
+}
+
+/// Reassembly table for fragmented IPv4 datagrams.
+pub struct Ipv4Reassembler {
+    flows: HashMap<FlowKey, Flow>,
+    timeout: Duration,
+    max_bytes: usize,
+    max_flows: usize,
+    /// Number of payload bytes buffered across all flows.
+    buffered_bytes: usize,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Flow {
+    fn new(created: Instant) -> Self {
+        Self {
+            created,
+            header: None,
+            fragments: Vec::new(),
+            total_length: None,
+            buffered_bytes: 0,
+        }
+    }
+
+    /// Checks whether all fragments of this datagram have arrived. Since fragments never overlap, this is the case
+    /// when the number of received bytes matches the size of the datagram.
+    fn is_complete(&self) -> bool {
+        self.header.is_some() && self.total_length == Some(self.buffered_bytes)
+    }
+
+    /// Concatenates the fragments of this datagram.
+    fn assemble(self) -> (Ipv4Header, DemiBuffer) {
+        let mut buf: DemiBuffer = DemiBuffer::new(self.buffered_bytes as u16);
+        for fragment in self.fragments {
+            let end: usize = fragment.offset + fragment.data.len();
+            buf[fragment.offset..end].copy_from_slice(&fragment.data[..]);
+        }
+        (self.header.expect("first fragment should have been received"), buf)
+    }
+}
+
+impl Ipv4Reassembler {
+    /// Creates a reassembly table that drops incomplete datagrams after [timeout] and buffers at most [max_bytes] of
+    /// payload for at most [max_flows] datagrams.
+    pub fn new(timeout: Duration, max_bytes: usize, max_flows: usize) -> Self {
+        Self {
+            flows: HashMap::new(),
+            timeout,
+            max_bytes,
+            max_flows,
+            buffered_bytes: 0,
+        }
+    }
+
+    /// Adds a fragment to the reassembly table. If this was the last missing fragment of its datagram, returns the
+    /// reassembled datagram.
+    pub fn insert(
+        &mut self,
+        now: Instant,
+        header: Ipv4Header,
+        buf: DemiBuffer,
+    ) -> Result<Option<(Ipv4Header, DemiBuffer)>, Fail> {
+        self.expire(now);
+
+        let key: FlowKey = FlowKey {
+            src_addr: header.get_src_addr(),
+            dst_addr: header.get_dest_addr(),
+            protocol: header.get_protocol(),
+            identification: header.get_identification(),
+        };
+        let offset: usize = header.get_fragment_offset();
+        let end: usize = offset + buf.len();
+
+        // Enforce memory limits before buffering anything.
+        if self.buffered_bytes + buf.len() > self.max_bytes {
+            return Err(Fail::new(ENOBUFS, "ipv4 reassembly buffer is full"));
+        }
+        if !self.flows.contains_key(&key) && self.flows.len() >= self.max_flows {
+            return Err(Fail::new(ENOBUFS, "too many ipv4 datagrams under reassembly"));
+        }
+
+        let flow: &mut Flow = self.flows.entry(key).or_insert_with(|| Flow::new(now));
+
+        // Check that this fragment is consistent with the size of the datagram.
+        let is_consistent: bool = match (header.has_more_fragments(), flow.total_length) {
+            (true, Some(total_length)) => end <= total_length,
+            (false, Some(total_length)) => end == total_length,
+            (false, None) => flow
+                .fragments
+                .last()
+                .map_or(true, |last| last.offset + last.data.len() <= end),
+            (true, None) => true,
+        };
+        if !is_consistent {
+            self.remove(&key);
+            return Err(Fail::new(EBADMSG, "inconsistent ipv4 fragment length"));
+        }
+
+        // Find where this fragment goes and check that it does not overlap with its neighbors. Exact duplicates are
+        // ignored, but any other overlap invalidates the whole datagram (see RFC 5722).
+        let index: usize = flow.fragments.partition_point(|fragment| fragment.offset < offset);
+        if let Some(next) = flow.fragments.get(index) {
+            if next.offset == offset && next.data.len() == buf.len() {
+                return Ok(None);
+            }
+            if next.offset < end {
+                self.remove(&key);
+                return Err(Fail::new(EBADMSG, "overlapping ipv4 fragments"));
+            }
+        }
+        if let Some(prev) = index.checked_sub(1).and_then(|i| flow.fragments.get(i)) {
+            if prev.offset + prev.data.len() > offset {
+                self.remove(&key);
+                return Err(Fail::new(EBADMSG, "overlapping ipv4 fragments"));
+            }
+        }
+        if flow.fragments.len() >= MAX_FRAGMENTS_PER_FLOW {
+            self.remove(&key);
+            return Err(Fail::new(EBADMSG, "too many ipv4 fragments"));
+        }
+
+        // Buffer fragment.
+        if offset == 0 {
+            flow.header = Some(header);
+        }
+        if !header.has_more_fragments() {
+            flow.total_length = Some(end);
+        }
+        flow.buffered_bytes += buf.len();
+        self.buffered_bytes += buf.len();
+        flow.fragments.insert(index, Fragment { offset, data: buf });
+
+        if !flow.is_complete() {
+            return Ok(None);
+        }
+        let flow: Flow = self.remove(&key).expect("flow should exist");
+        Ok(Some(flow.assemble()))
+    }
+
+    /// Drops datagrams whose reassembly timer has expired.
+    pub fn expire(&mut self, now: Instant) {
+        let timeout: Duration = self.timeout;
+        let mut expired_bytes: usize = 0;
+        self.flows.retain(|key, flow| {
+            if now.saturating_duration_since(flow.created) < timeout {
+                return true;
+            }
+            warn!("ipv4 reassembly timed out (flow={:?})", key);
+            expired_bytes += flow.buffered_bytes;
+            false
+        });
+        self.buffered_bytes -= expired_bytes;
+    }
+
+    /// Returns the number of payload bytes that are buffered for reassembly.
+    #[cfg(test)]
+    pub fn get_buffered_bytes(&self) -> usize {
+        self.buffered_bytes
+    }
+
+    /// Removes a datagram from the reassembly table.
+    fn remove(&mut self, key: &FlowKey) -> Option<Flow> {
+        let flow: Flow = self.flows.remove(key)?;
+        self.buffered_bytes -= flow.buffered_bytes;
+        Some(flow)
+    }
+}
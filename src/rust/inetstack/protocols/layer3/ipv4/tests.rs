@@ -9,7 +9,11 @@ use crate::{
     inetstack::{
         protocols::layer3::{
             ip::IpProtocol,
-            ipv4::Ipv4Header,
+            ipv4::{
+                Ipv4Header,
+                Ipv4Reassembler,
+                IPV4_HEADER_MIN_SIZE,
+            },
         },
         test_helpers::{
             ALICE_IPV4,
@@ -19,6 +23,10 @@ use crate::{
     runtime::memory::DemiBuffer,
 };
 use ::anyhow::Result;
+use ::std::time::{
+    Duration,
+    Instant,
+};
 
 //======================================================================================================================
 // Helper Functions
@@ -78,6 +86,27 @@ fn build_ipv4_header(
     buf[10..12].copy_from_slice(&checksum.unwrap().to_be_bytes());
 }
 
+/// Builds an IPv4 fragment that carries [payload] and parses it back.
+fn build_fragment(
+    identification: u16,
+    fragment_offset: usize,
+    more_fragments: bool,
+    payload: &[u8],
+) -> Result<(Ipv4Header, DemiBuffer)> {
+    let mut buf: DemiBuffer = DemiBuffer::from_slice_with_headroom(payload, IPV4_HEADER_MIN_SIZE as usize)?;
+    Ipv4Header::new_fragment(
+        ALICE_IPV4,
+        BOB_IPV4,
+        IpProtocol::UDP,
+        identification,
+        fragment_offset,
+        more_fragments,
+    )
+    .serialize_and_attach(&mut buf);
+    let header: Ipv4Header = Ipv4Header::parse_and_strip(&mut buf)?;
+    Ok((header, buf))
+}
+
 //======================================================================================================================
 // Unit-Tests for Happy Path
 //======================================================================================================================
@@ -135,6 +164,140 @@ fn test_ipv4_header_parse_good() -> Result<()> {
     Ok(())
 }
 
+/// Parses a well-formed IPv4 fragment.
+#[test]
+fn test_ipv4_header_parse_fragment() -> Result<()> {
+    const HEADER_SIZE: usize = 20;
+    const PAYLOAD_SIZE: usize = 8;
+    const DATAGRAM_SIZE: usize = HEADER_SIZE + PAYLOAD_SIZE;
+    let mut buf: [u8; DATAGRAM_SIZE] = [0; DATAGRAM_SIZE];
+
+    // Second fragment of a datagram, with more fragments to come.
+    build_ipv4_header(
+        &mut buf,
+        4,
+        5,
+        0,
+        0,
+        DATAGRAM_SIZE as u16,
+        0x1d,
+        0x1,
+        1,
+        1,
+        IpProtocol::UDP as u8,
+        &ALICE_IPV4.octets(),
+        &BOB_IPV4.octets(),
+        None,
+    );
+
+    // Do it.
+    let mut buf: DemiBuffer = match DemiBuffer::from_slice(&buf) {
+        Ok(buf) => buf,
+        Err(e) => anyhow::bail!("'buf' should fit: {:?}", e),
+    };
+    let ipv4_hdr: Ipv4Header = Ipv4Header::parse_and_strip(&mut buf)?;
+    crate::ensure_eq!(ipv4_hdr.is_fragment(), true);
+    crate::ensure_eq!(ipv4_hdr.has_more_fragments(), true);
+    crate::ensure_eq!(ipv4_hdr.get_fragment_offset(), 8);
+    crate::ensure_eq!(ipv4_hdr.get_identification(), 0x1d);
+    crate::ensure_eq!(buf.len(), PAYLOAD_SIZE);
+
+    Ok(())
+}
+
+/// Parses the truncated datagram that an ICMPv4 error message quotes.
+#[test]
+fn test_ipv4_header_parse_embedded() -> Result<()> {
+    const HEADER_SIZE: usize = 20;
+    const PAYLOAD_SIZE: usize = 8;
+    const DATAGRAM_SIZE: usize = HEADER_SIZE + PAYLOAD_SIZE;
+    let mut buf: [u8; DATAGRAM_SIZE] = [0; DATAGRAM_SIZE];
+
+    // The original datagram was larger than what the message quotes, and routers may quote it with a TTL of zero.
+    build_ipv4_header(
+        &mut buf,
+        4,
+        5,
+        0,
+        0,
+        1500,
+        0,
+        0x2,
+        0,
+        0,
+        IpProtocol::TCP as u8,
+        &ALICE_IPV4.octets(),
+        &BOB_IPV4.octets(),
+        None,
+    );
+
+    let bytes: DemiBuffer = match DemiBuffer::from_slice(&buf) {
+        Ok(buf) => buf,
+        Err(e) => anyhow::bail!("'buf' should fit: {:?}", e),
+    };
+    let mut buf: DemiBuffer = bytes.clone();
+    crate::ensure_eq!(Ipv4Header::parse_and_strip(&mut buf).is_err(), true);
+
+    let mut buf: DemiBuffer = bytes;
+    let ipv4_hdr: Ipv4Header = Ipv4Header::parse_and_strip_embedded(&mut buf)?;
+    crate::ensure_eq!(ipv4_hdr.get_src_addr(), ALICE_IPV4);
+    crate::ensure_eq!(ipv4_hdr.get_protocol(), IpProtocol::TCP);
+    crate::ensure_eq!(ipv4_hdr.get_total_length(), 1500);
+    crate::ensure_eq!(buf.len(), PAYLOAD_SIZE);
+
+    Ok(())
+}
+
+/// Reassembles a datagram from fragments that arrive in order.
+#[test]
+fn test_ipv4_reassembly_in_order() -> Result<()> {
+    let now: Instant = Instant::now();
+    let data: Vec<u8> = (0..100).collect();
+    let mut reassembler: Ipv4Reassembler = Ipv4Reassembler::new(Duration::from_secs(30), 1024, 4);
+
+    let (header, buf): (Ipv4Header, DemiBuffer) = build_fragment(7, 0, true, &data[0..48])?;
+    crate::ensure_eq!(reassembler.insert(now, header, buf)?.is_none(), true);
+    let (header, buf): (Ipv4Header, DemiBuffer) = build_fragment(7, 48, true, &data[48..96])?;
+    crate::ensure_eq!(reassembler.insert(now, header, buf)?.is_none(), true);
+    let (header, buf): (Ipv4Header, DemiBuffer) = build_fragment(7, 96, false, &data[96..100])?;
+    let (header, buf): (Ipv4Header, DemiBuffer) = match reassembler.insert(now, header, buf)? {
+        Some(datagram) => datagram,
+        None => anyhow::bail!("datagram should be reassembled"),
+    };
+
+    crate::ensure_eq!(header.get_src_addr(), ALICE_IPV4);
+    crate::ensure_eq!(header.get_protocol(), IpProtocol::UDP);
+    crate::ensure_eq!(&buf[..], &data[..]);
+    crate::ensure_eq!(reassembler.get_buffered_bytes(), 0);
+
+    Ok(())
+}
+
+/// Reassembles a datagram from fragments that arrive out of order and duplicated.
+#[test]
+fn test_ipv4_reassembly_out_of_order() -> Result<()> {
+    let now: Instant = Instant::now();
+    let data: Vec<u8> = (0..100).collect();
+    let mut reassembler: Ipv4Reassembler = Ipv4Reassembler::new(Duration::from_secs(30), 1024, 4);
+
+    let (header, buf): (Ipv4Header, DemiBuffer) = build_fragment(7, 96, false, &data[96..100])?;
+    crate::ensure_eq!(reassembler.insert(now, header, buf)?.is_none(), true);
+    let (header, buf): (Ipv4Header, DemiBuffer) = build_fragment(7, 0, true, &data[0..48])?;
+    crate::ensure_eq!(reassembler.insert(now, header, buf)?.is_none(), true);
+    let (header, buf): (Ipv4Header, DemiBuffer) = build_fragment(7, 0, true, &data[0..48])?;
+    crate::ensure_eq!(reassembler.insert(now, header, buf)?.is_none(), true);
+    let (header, buf): (Ipv4Header, DemiBuffer) = build_fragment(7, 48, true, &data[48..96])?;
+    let (_, buf): (Ipv4Header, DemiBuffer) = match reassembler.insert(now, header, buf)? {
+        Some(datagram) => datagram,
+        None => anyhow::bail!("datagram should be reassembled"),
+    };
+
+    crate::ensure_eq!(&buf[..], &data[..]);
+    crate::ensure_eq!(reassembler.get_buffered_bytes(), 0);
+
+    Ok(())
+}
+
 //======================================================================================================================
 // Unit-Tests for Invalid Path
 //======================================================================================================================
@@ -511,18 +674,15 @@ fn test_ipv4_header_parse_unsupported_ecn() -> Result<()> {
     Ok(())
 }
 
-/// Parses a malformed IPv4 header with unsupported fragmentation fields.
-///
-/// TODO: Drop this test once we support fragmentation.
+/// Parses a fragment whose payload size is not a multiple of 8 bytes.
 #[test]
-fn test_ipv4_header_parse_unsupported_fragmentation() -> Result<()> {
+fn test_ipv4_header_parse_invalid_fragment_size() -> Result<()> {
     const HEADER_SIZE: usize = 20;
-    const PAYLOAD_SIZE: usize = 0;
+    const PAYLOAD_SIZE: usize = 5;
     const DATAGRAM_SIZE: usize = HEADER_SIZE + PAYLOAD_SIZE;
     let mut buf: [u8; DATAGRAM_SIZE] = [0; DATAGRAM_SIZE];
 
-    // Fragmented packets are unsupported.
-    // Fragments are detected by having either the MF bit set in Flags or a non-zero Fragment Offset field.
+    // Only the last fragment may have a size that is not a multiple of 8 bytes.
     let flags: u8 = 0x1; // Set MF bit.
     build_ipv4_header(
         &mut buf,
@@ -548,13 +708,21 @@ fn test_ipv4_header_parse_unsupported_fragmentation() -> Result<()> {
     };
 
     match Ipv4Header::parse_and_strip(&mut buf) {
-        Ok(_) => anyhow::bail!("parsed ipv4 header with Flags={:?}. Do we support it now?", flags,),
-        Err(_) => {},
-    };
+        Ok(_) => anyhow::bail!("parsed ipv4 fragment with invalid size={:?}", PAYLOAD_SIZE),
+        Err(_) => Ok(()),
+    }
+}
 
-    // Fragmented packets are unsupported.
-    // Fragments are detected by having either the MF bit set in Flags or a non-zero Fragment Offset field.
-    let fragment_offset: u16 = 1;
+/// Parses a fragment that would extend past the maximum datagram size.
+#[test]
+fn test_ipv4_header_parse_invalid_fragment_offset() -> Result<()> {
+    const HEADER_SIZE: usize = 20;
+    const PAYLOAD_SIZE: usize = 16;
+    const DATAGRAM_SIZE: usize = HEADER_SIZE + PAYLOAD_SIZE;
+    let mut buf: [u8; DATAGRAM_SIZE] = [0; DATAGRAM_SIZE];
+
+    // The largest fragment offset leaves room for only 7 bytes of payload.
+    let fragment_offset: u16 = 0x1fff;
     build_ipv4_header(
         &mut buf,
         4,
@@ -563,7 +731,7 @@ fn test_ipv4_header_parse_unsupported_fragmentation() -> Result<()> {
         0,
         DATAGRAM_SIZE as u16,
         0x1d,
-        0x2,
+        0,
         fragment_offset,
         1,
         IpProtocol::UDP as u8,
@@ -579,10 +747,7 @@ fn test_ipv4_header_parse_unsupported_fragmentation() -> Result<()> {
     };
 
     match Ipv4Header::parse_and_strip(&mut buf) {
-        Ok(_) => anyhow::bail!(
-            "parsed ipv4 header with fragment_offset={:?}. Do we support it now?",
-            fragment_offset,
-        ),
+        Ok(_) => anyhow::bail!("parsed ipv4 fragment with invalid offset={:?}", fragment_offset),
         Err(_) => Ok(()),
     }
 }
@@ -601,7 +766,7 @@ fn test_ipv4_header_parse_unsupported_protocol() -> Result<()> {
     for protocol in 0..143 {
         match protocol {
             // Skip supported protocols.
-            1 | 6 | 17 => continue,
+            1 | 2 | 6 | 17 => continue,
             _ => {
                 build_ipv4_header(
                     &mut buf,
@@ -636,3 +801,74 @@ fn test_ipv4_header_parse_unsupported_protocol() -> Result<()> {
 
     Ok(())
 }
+
+/// Drops a datagram whose fragments overlap.
+#[test]
+fn test_ipv4_reassembly_overlap() -> Result<()> {
+    let now: Instant = Instant::now();
+    let data: Vec<u8> = (0..100).collect();
+    let mut reassembler: Ipv4Reassembler = Ipv4Reassembler::new(Duration::from_secs(30), 1024, 4);
+
+    let (header, buf): (Ipv4Header, DemiBuffer) = build_fragment(7, 0, true, &data[0..48])?;
+    reassembler.insert(now, header, buf)?;
+    let (header, buf): (Ipv4Header, DemiBuffer) = build_fragment(7, 40, false, &data[40..100])?;
+    match reassembler.insert(now, header, buf) {
+        Err(e) => crate::ensure_eq!(e.errno, libc::EBADMSG),
+        Ok(_) => anyhow::bail!("overlapping fragments should be rejected"),
+    }
+
+    // All fragments of the datagram should have been dropped.
+    crate::ensure_eq!(reassembler.get_buffered_bytes(), 0);
+
+    Ok(())
+}
+
+/// Drops a datagram that is not reassembled before the timeout.
+#[test]
+fn test_ipv4_reassembly_timeout() -> Result<()> {
+    const TIMEOUT: Duration = Duration::from_secs(30);
+    let mut now: Instant = Instant::now();
+    let data: Vec<u8> = (0..100).collect();
+    let mut reassembler: Ipv4Reassembler = Ipv4Reassembler::new(TIMEOUT, 1024, 4);
+
+    let (header, buf): (Ipv4Header, DemiBuffer) = build_fragment(7, 0, true, &data[0..48])?;
+    reassembler.insert(now, header, buf)?;
+    crate::ensure_eq!(reassembler.get_buffered_bytes(), 48);
+
+    // The remaining fragments arrive too late.
+    now += TIMEOUT;
+    let (header, buf): (Ipv4Header, DemiBuffer) = build_fragment(7, 48, false, &data[48..100])?;
+    crate::ensure_eq!(reassembler.insert(now, header, buf)?.is_none(), true);
+    crate::ensure_eq!(reassembler.get_buffered_bytes(), 52);
+
+    Ok(())
+}
+
+/// Rejects fragments once the reassembly memory limits are reached.
+#[test]
+fn test_ipv4_reassembly_memory_limits() -> Result<()> {
+    let now: Instant = Instant::now();
+    let data: Vec<u8> = (0..100).collect();
+    let mut reassembler: Ipv4Reassembler = Ipv4Reassembler::new(Duration::from_secs(30), 64, 2);
+
+    // Byte limit.
+    let (header, buf): (Ipv4Header, DemiBuffer) = build_fragment(1, 0, true, &data[0..48])?;
+    reassembler.insert(now, header, buf)?;
+    let (header, buf): (Ipv4Header, DemiBuffer) = build_fragment(1, 48, true, &data[48..96])?;
+    match reassembler.insert(now, header, buf) {
+        Err(e) => crate::ensure_eq!(e.errno, libc::ENOBUFS),
+        Ok(_) => anyhow::bail!("fragment should not fit in the reassembly buffer"),
+    }
+
+    // Flow limit.
+    let (header, buf): (Ipv4Header, DemiBuffer) = build_fragment(2, 0, true, &data[0..8])?;
+    reassembler.insert(now, header, buf)?;
+    let (header, buf): (Ipv4Header, DemiBuffer) = build_fragment(3, 0, true, &data[0..8])?;
+    match reassembler.insert(now, header, buf) {
+        Err(e) => crate::ensure_eq!(e.errno, libc::ENOBUFS),
+        Ok(_) => anyhow::bail!("fragment should not fit in the reassembly table"),
+    }
+    crate::ensure_eq!(reassembler.get_buffered_bytes(), 56);
+
+    Ok(())
+}
@@ -0,0 +1,238 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::protocols::layer3::ip::IpProtocol,
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+    },
+};
+use ::libc::{
+    EBADMSG,
+    ENOTSUP,
+};
+use ::std::net::Ipv6Addr;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Size of IPv6 header (in bytes). Unlike IPv4, the IPv6 header has a fixed size and options are carried in extension
+/// headers.
+pub const IPV6_HEADER_SIZE: u16 = 40;
+
+/// Default hop limit value. Neighbor Discovery requires this to be 255 (see RFC 4861 Section 6.1.1), so we use it for
+/// all datagrams.
+const DEFAULT_IPV6_HOP_LIMIT: u8 = 255;
+
+/// Version number for IPv6.
+const IPV6_VERSION: u8 = 6;
+
+/// Next header values for IPv6 extension headers (see RFC 8200 Section 4).
+const IPV6_EXTENSION_HEADERS: [u8; 8] = [0, 43, 44, 50, 51, 60, 135, 139];
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// IPv6 Datagram Header
+#[derive(Debug, Copy, Clone)]
+pub struct Ipv6Header {
+    /// Internet header version (4 bits).
+    version: u8,
+    /// Traffic class (8 bits).
+    traffic_class: u8,
+    /// Flow label (20 bits).
+    flow_label: u32,
+    /// Length of the payload following this header, including extension headers (16 bits).
+    #[allow(unused)]
+    payload_length: u16,
+    /// Protocol used in the data portion of the datagram (8 bits).
+    next_header: IpProtocol,
+    /// Hop limit, decremented by each node that forwards the datagram (8 bits).
+    hop_limit: u8,
+    /// Source IP address (128 bits).
+    src_addr: Ipv6Addr,
+    /// Destination IP address (128 bits).
+    dst_addr: Ipv6Addr,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+/// Associated Functions for IPv6 Headers
+impl Ipv6Header {
+    /// Instantiates an empty IPv6 header.
+    pub fn new(src_addr: Ipv6Addr, dst_addr: Ipv6Addr, next_header: IpProtocol) -> Self {
+        Self {
+            version: IPV6_VERSION,
+            traffic_class: 0,
+            flow_label: 0,
+            payload_length: 0,
+            next_header,
+            hop_limit: DEFAULT_IPV6_HOP_LIMIT,
+            src_addr,
+            dst_addr,
+        }
+    }
+
+    /// Computes the size of the target IPv6 header.
+    pub fn compute_size(&self) -> usize {
+        IPV6_HEADER_SIZE as usize
+    }
+
+    /// Parses and strips the IPv6 header from the packet in [buf].
+    pub fn parse_and_strip(buf: &mut DemiBuffer) -> Result<Self, Fail> {
+        // The datagram should be as big as the header.
+        if buf.len() < (IPV6_HEADER_SIZE as usize) {
+            return Err(Fail::new(EBADMSG, "ipv6 datagram too small"));
+        }
+        let hdr_buf: &[u8] = &buf[..IPV6_HEADER_SIZE as usize];
+
+        // IP version number.
+        let version: u8 = hdr_buf[0] >> 4;
+        if version != IPV6_VERSION {
+            return Err(Fail::new(ENOTSUP, "unsupported IP version"));
+        }
+
+        // Traffic class.
+        let traffic_class: u8 = (hdr_buf[0] << 4) | (hdr_buf[1] >> 4);
+        if traffic_class != 0 {
+            warn!("ignoring traffic class field (traffic_class={:?})", traffic_class);
+        }
+
+        // Flow label.
+        let flow_label: u32 = u32::from_be_bytes([0, hdr_buf[1] & 0xf, hdr_buf[2], hdr_buf[3]]);
+
+        // Payload length.
+        let payload_length: u16 = u16::from_be_bytes([hdr_buf[4], hdr_buf[5]]);
+        // NOTE: there may be padding bytes in the buffer.
+        if (payload_length as usize) > buf.len() - (IPV6_HEADER_SIZE as usize) {
+            return Err(Fail::new(EBADMSG, "ipv6 datagram size mismatch"));
+        }
+
+        // Next header.
+        // TODO: Walk extension headers once we support any of them.
+        if IPV6_EXTENSION_HEADERS.contains(&hdr_buf[6]) {
+            warn!("extension headers are not supported next_header={:?}", hdr_buf[6]);
+            return Err(Fail::new(ENOTSUP, "ipv6 extension headers are not supported"));
+        }
+        let next_header: IpProtocol = IpProtocol::try_from(hdr_buf[6])?;
+        if next_header == IpProtocol::ICMPv4 {
+            return Err(Fail::new(ENOTSUP, "ICMPv4 carried over IPv6"));
+        }
+
+        // Hop limit.
+        let hop_limit: u8 = hdr_buf[7];
+        if hop_limit == 0 {
+            return Err(Fail::new(EBADMSG, "ipv6 datagram too old"));
+        }
+
+        // Source address.
+        let src_addr: Ipv6Addr = Ipv6Addr::from(<[u8; 16]>::try_from(&hdr_buf[8..24]).unwrap());
+
+        // Destination address.
+        let dst_addr: Ipv6Addr = Ipv6Addr::from(<[u8; 16]>::try_from(&hdr_buf[24..40]).unwrap());
+
+        // Truncate datagram.
+        let padding_bytes: usize = buf.len() - (IPV6_HEADER_SIZE as usize) - (payload_length as usize);
+        buf.adjust(IPV6_HEADER_SIZE as usize)?;
+        buf.trim(padding_bytes)?;
+
+        Ok(Self {
+            version,
+            traffic_class,
+            flow_label,
+            payload_length,
+            next_header,
+            hop_limit,
+            src_addr,
+            dst_addr,
+        })
+    }
+
+    /// Serializes the IPv6 header and prepends it to the packet in [buf]. Assumes that there is enough headroom for
+    /// the header.
+    pub fn serialize_and_attach(&self, buf: &mut DemiBuffer) {
+        buf.prepend(IPV6_HEADER_SIZE as usize)
+            .expect("Should be sufficient headroom");
+        let payload_size_bytes: usize = buf.total_len() - (IPV6_HEADER_SIZE as usize);
+
+        // Version, Traffic Class and Flow Label.
+        let first_word: u32 =
+            (self.version as u32) << 28 | (self.traffic_class as u32) << 20 | (self.flow_label & 0xfffff);
+        buf[0..4].copy_from_slice(&first_word.to_be_bytes());
+
+        // Payload Length.
+        buf[4..6].copy_from_slice(&(payload_size_bytes as u16).to_be_bytes());
+
+        // Next Header.
+        buf[6] = self.next_header as u8;
+
+        // Hop Limit.
+        buf[7] = self.hop_limit;
+
+        // Source Address.
+        buf[8..24].copy_from_slice(&self.src_addr.octets());
+
+        // Destination Address.
+        buf[24..40].copy_from_slice(&self.dst_addr.octets());
+    }
+
+    /// Returns the source address field stored in the target IPv6 header.
+    pub fn get_src_addr(&self) -> Ipv6Addr {
+        self.src_addr
+    }
+
+    /// Returns the destination address field stored in the target IPv6 header.
+    pub fn get_dest_addr(&self) -> Ipv6Addr {
+        self.dst_addr
+    }
+
+    /// Returns the next header field stored in the target IPv6 header.
+    pub fn get_next_header(&self) -> IpProtocol {
+        self.next_header
+    }
+
+    /// Returns the hop limit field stored in the target IPv6 header.
+    pub fn get_hop_limit(&self) -> u8 {
+        self.hop_limit
+    }
+
+    /// Computes the (unfolded) checksum of the IPv6 pseudo-header used by upper-layer protocols (see RFC 8200 Section
+    /// 8.1). The result may be used as the starting state for checksumming the upper-layer packet.
+    pub fn compute_pseudo_header_checksum(
+        src_addr: &Ipv6Addr,
+        dst_addr: &Ipv6Addr,
+        next_header: IpProtocol,
+        upper_layer_length: usize,
+    ) -> u32 {
+        let mut state: u32 = 0xffff;
+
+        // 1) Source address (16 bytes)
+        for chunk in src_addr.octets().chunks_exact(2) {
+            state += u16::from_be_bytes([chunk[0], chunk[1]]) as u32;
+        }
+
+        // 2) Destination address (16 bytes)
+        for chunk in dst_addr.octets().chunks_exact(2) {
+            state += u16::from_be_bytes([chunk[0], chunk[1]]) as u32;
+        }
+
+        // 3) Upper-layer packet length (4 bytes)
+        let length: [u8; 4] = (upper_layer_length as u32).to_be_bytes();
+        state += u16::from_be_bytes([length[0], length[1]]) as u32;
+        state += u16::from_be_bytes([length[2], length[3]]) as u32;
+
+        // 4) 3 bytes of zeros and next header (1 byte)
+        state += u16::from_be_bytes([0, next_header as u8]) as u32;
+
+        state
+    }
+}
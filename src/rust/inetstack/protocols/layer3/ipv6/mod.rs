@@ -0,0 +1,16 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+mod header;
+
+#[cfg(test)]
+mod tests;
+
+//======================================================================================================================
+// Exports
+//======================================================================================================================
+
+pub use self::header::{
+    Ipv6Header,
+    IPV6_HEADER_SIZE,
+};
@@ -0,0 +1,269 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::{
+        protocols::layer3::{
+            ip::IpProtocol,
+            ipv6::{
+                Ipv6Header,
+                IPV6_HEADER_SIZE,
+            },
+        },
+        test_helpers::{
+            ALICE_IPV6,
+            BOB_IPV6,
+        },
+    },
+    runtime::memory::DemiBuffer,
+};
+use ::anyhow::Result;
+
+//======================================================================================================================
+// Helper Functions
+//======================================================================================================================
+
+/// Builds an IPv6 header.
+/// NOTE: that we can use this function to create invalid IPv6 headers
+fn build_ipv6_header(
+    buf: &mut [u8],
+    version: u8,
+    traffic_class: u8,
+    flow_label: u32,
+    payload_length: u16,
+    next_header: u8,
+    hop_limit: u8,
+    src_addr: &[u8],
+    dest_addr: &[u8],
+) {
+    // Version + Traffic Class + Flow Label.
+    let first_word: u32 = ((version as u32 & 0xf) << 28) | ((traffic_class as u32) << 20) | (flow_label & 0xfffff);
+    buf[0..4].copy_from_slice(&first_word.to_be_bytes());
+
+    // Payload Length.
+    buf[4..6].copy_from_slice(&payload_length.to_be_bytes());
+
+    // Next Header.
+    buf[6] = next_header;
+
+    // Hop Limit.
+    buf[7] = hop_limit;
+
+    // Source address.
+    buf[8..24].copy_from_slice(src_addr);
+
+    // Destination address.
+    buf[24..40].copy_from_slice(dest_addr);
+}
+
+//======================================================================================================================
+// Unit-Tests for Happy Path
+//======================================================================================================================
+
+/// Parses a well-formed IPv6 header.
+#[test]
+fn test_ipv6_header_parse_good() -> Result<()> {
+    const HEADER_SIZE: usize = IPV6_HEADER_SIZE as usize;
+    const PAYLOAD_SIZE: usize = 8;
+    const PADDING_SIZE: usize = 4;
+    const DATAGRAM_SIZE: usize = HEADER_SIZE + PAYLOAD_SIZE;
+    let mut buf: [u8; DATAGRAM_SIZE + PADDING_SIZE] = [0; DATAGRAM_SIZE + PADDING_SIZE];
+    let data: [u8; PAYLOAD_SIZE] = [1, 2, 3, 4, 5, 6, 7, 8];
+    build_ipv6_header(
+        &mut buf[..HEADER_SIZE],
+        6,
+        0,
+        0,
+        PAYLOAD_SIZE as u16,
+        IpProtocol::UDP as u8,
+        1,
+        &ALICE_IPV6.octets(),
+        &BOB_IPV6.octets(),
+    );
+    buf[HEADER_SIZE..DATAGRAM_SIZE].copy_from_slice(&data);
+
+    // Do it. Trailing padding bytes should be trimmed off.
+    let mut buf: DemiBuffer = match DemiBuffer::from_slice(&buf) {
+        Ok(buf) => buf,
+        Err(e) => anyhow::bail!("'buf' should fit: {:?}", e),
+    };
+    match Ipv6Header::parse_and_strip(&mut buf) {
+        Ok(ipv6_hdr) => {
+            crate::ensure_eq!(ipv6_hdr.get_src_addr(), ALICE_IPV6);
+            crate::ensure_eq!(ipv6_hdr.get_dest_addr(), BOB_IPV6);
+            crate::ensure_eq!(ipv6_hdr.get_next_header(), IpProtocol::UDP);
+            crate::ensure_eq!(ipv6_hdr.get_hop_limit(), 1);
+            crate::ensure_eq!(buf.len(), PAYLOAD_SIZE);
+            crate::ensure_eq!(buf[..], data[..]);
+        },
+        Err(e) => anyhow::bail!("{:?}", e),
+    }
+
+    Ok(())
+}
+
+/// Serializes an IPv6 header and parses it back.
+#[test]
+fn test_ipv6_header_serialize_and_parse() -> Result<()> {
+    const PAYLOAD_SIZE: usize = 16;
+    let data: [u8; PAYLOAD_SIZE] = [0x5a; PAYLOAD_SIZE];
+    let mut buf: DemiBuffer = match DemiBuffer::from_slice_with_headroom(&data, IPV6_HEADER_SIZE as usize) {
+        Ok(buf) => buf,
+        Err(e) => anyhow::bail!("'buf' should fit: {:?}", e),
+    };
+    Ipv6Header::new(ALICE_IPV6, BOB_IPV6, IpProtocol::TCP).serialize_and_attach(&mut buf);
+    crate::ensure_eq!(buf.len(), IPV6_HEADER_SIZE as usize + PAYLOAD_SIZE);
+    crate::ensure_eq!(buf[0] >> 4, 6);
+    crate::ensure_eq!(u16::from_be_bytes([buf[4], buf[5]]) as usize, PAYLOAD_SIZE);
+
+    let ipv6_hdr: Ipv6Header = match Ipv6Header::parse_and_strip(&mut buf) {
+        Ok(ipv6_hdr) => ipv6_hdr,
+        Err(e) => anyhow::bail!("{:?}", e),
+    };
+    crate::ensure_eq!(ipv6_hdr.get_src_addr(), ALICE_IPV6);
+    crate::ensure_eq!(ipv6_hdr.get_dest_addr(), BOB_IPV6);
+    crate::ensure_eq!(ipv6_hdr.get_next_header(), IpProtocol::TCP);
+    crate::ensure_eq!(buf[..], data[..]);
+
+    Ok(())
+}
+
+//======================================================================================================================
+// Unit-Tests for Invalid Path
+//======================================================================================================================
+
+/// Parses a malformed IPv6 header with invalid version number.
+#[test]
+fn test_ipv6_header_parse_invalid_version() -> Result<()> {
+    const DATAGRAM_SIZE: usize = IPV6_HEADER_SIZE as usize;
+    let mut buf: [u8; DATAGRAM_SIZE] = [0; DATAGRAM_SIZE];
+
+    // Iterate over all invalid version numbers.
+    for version in [0, 1, 2, 3, 4, 5, 7, 8, 9, 10, 11, 12, 13, 14, 15] {
+        build_ipv6_header(
+            &mut buf,
+            version,
+            0,
+            0,
+            0,
+            IpProtocol::UDP as u8,
+            1,
+            &ALICE_IPV6.octets(),
+            &BOB_IPV6.octets(),
+        );
+
+        // Do it.
+        let mut buf: DemiBuffer = match DemiBuffer::from_slice(&buf) {
+            Ok(buf) => buf,
+            Err(e) => anyhow::bail!("'buf' should fit: {:?}", e),
+        };
+        match Ipv6Header::parse_and_strip(&mut buf) {
+            Ok(_) => anyhow::bail!("parsed ipv6 header with invalid version={:?}", version),
+            Err(_) => {},
+        };
+    }
+
+    Ok(())
+}
+
+/// Parses a malformed IPv6 header with a payload length that exceeds the datagram.
+#[test]
+fn test_ipv6_header_parse_invalid_payload_length() -> Result<()> {
+    const PAYLOAD_SIZE: usize = 8;
+    const DATAGRAM_SIZE: usize = IPV6_HEADER_SIZE as usize + PAYLOAD_SIZE;
+    let mut buf: [u8; DATAGRAM_SIZE] = [0; DATAGRAM_SIZE];
+
+    for payload_length in [PAYLOAD_SIZE as u16 + 1, 1500, u16::MAX] {
+        build_ipv6_header(
+            &mut buf,
+            6,
+            0,
+            0,
+            payload_length,
+            IpProtocol::UDP as u8,
+            1,
+            &ALICE_IPV6.octets(),
+            &BOB_IPV6.octets(),
+        );
+
+        // Do it.
+        let mut buf: DemiBuffer = match DemiBuffer::from_slice(&buf) {
+            Ok(buf) => buf,
+            Err(e) => anyhow::bail!("'buf' should fit: {:?}", e),
+        };
+        match Ipv6Header::parse_and_strip(&mut buf) {
+            Ok(_) => anyhow::bail!("parsed ipv6 header with invalid payload_length={:?}", payload_length),
+            Err(_) => {},
+        };
+    }
+
+    Ok(())
+}
+
+/// Parses a malformed IPv6 header with invalid hop limit.
+#[test]
+fn test_ipv6_header_parse_invalid_hop_limit() -> Result<()> {
+    const DATAGRAM_SIZE: usize = IPV6_HEADER_SIZE as usize;
+    let mut buf: [u8; DATAGRAM_SIZE] = [0; DATAGRAM_SIZE];
+    build_ipv6_header(
+        &mut buf,
+        6,
+        0,
+        0,
+        0,
+        IpProtocol::UDP as u8,
+        0,
+        &ALICE_IPV6.octets(),
+        &BOB_IPV6.octets(),
+    );
+
+    // Do it.
+    let mut buf: DemiBuffer = match DemiBuffer::from_slice(&buf) {
+        Ok(buf) => buf,
+        Err(e) => anyhow::bail!("'buf' should fit: {:?}", e),
+    };
+    match Ipv6Header::parse_and_strip(&mut buf) {
+        Ok(_) => anyhow::bail!("parsed ipv6 header with invalid hop_limit"),
+        Err(_) => {},
+    };
+
+    Ok(())
+}
+
+/// Parses an IPv6 header that is followed by extension headers.
+#[test]
+fn test_ipv6_header_parse_unsupported_extension_headers() -> Result<()> {
+    const DATAGRAM_SIZE: usize = IPV6_HEADER_SIZE as usize;
+    let mut buf: [u8; DATAGRAM_SIZE] = [0; DATAGRAM_SIZE];
+
+    // Hop-by-Hop, Routing, Fragment, ESP, AH, Destination Options, Mobility and HIP.
+    for next_header in [0, 43, 44, 50, 51, 60, 135, 139] {
+        build_ipv6_header(
+            &mut buf,
+            6,
+            0,
+            0,
+            0,
+            next_header,
+            1,
+            &ALICE_IPV6.octets(),
+            &BOB_IPV6.octets(),
+        );
+
+        // Do it.
+        let mut buf: DemiBuffer = match DemiBuffer::from_slice(&buf) {
+            Ok(buf) => buf,
+            Err(e) => anyhow::bail!("'buf' should fit: {:?}", e),
+        };
+        match Ipv6Header::parse_and_strip(&mut buf) {
+            Ok(_) => anyhow::bail!("parsed ipv6 header with extension header={:?}", next_header),
+            Err(e) => crate::ensure_eq!(e.errno, libc::ENOTSUP),
+        };
+    }
+
+    Ok(())
+}
@@ -7,24 +7,53 @@
 
 pub mod arp;
 pub mod icmpv4;
+pub mod icmpv6;
+pub mod igmp;
 pub mod ip;
 pub mod ipv4;
+pub mod ipv6;
+pub mod route;
 
 use arrayvec::ArrayVec;
 
 pub use self::{
     arp::SharedArpPeer,
-    icmpv4::SharedIcmpv4Peer,
+    icmpv4::{
+        Icmpv4Error,
+        SharedIcmpv4Peer,
+    },
+    icmpv6::{
+        solicited_node_multicast_addr,
+        SharedIcmpv6Peer,
+    },
+    igmp::SharedIgmpPeer,
     ip::IpProtocol,
-    ipv4::Ipv4Header,
+    ipv4::{
+        Ipv4Header,
+        Ipv4Reassembler,
+    },
+    ipv6::Ipv6Header,
+    route::RoutingTable,
 };
 
 use crate::{
     demi_sgarray_t,
     demikernel::config::Config,
-    inetstack::protocols::layer2::{
-        EtherType2,
-        SharedLayer2Endpoint,
+    inetstack::protocols::{
+        layer2::{
+            ipv4_multicast_link_addr,
+            EtherType2,
+            Ethernet2Header,
+            SharedLayer2Endpoint,
+            ETHERNET2_HEADER_SIZE,
+        },
+        layer3::ipv4::{
+            DEFAULT_REASSEMBLY_MAX_BYTES,
+            DEFAULT_REASSEMBLY_MAX_FLOWS,
+            DEFAULT_REASSEMBLY_TIMEOUT,
+            IPV4_FRAGMENT_UNIT_SIZE,
+            IPV4_HEADER_MIN_SIZE,
+        },
     },
     runtime::{
         fail::Fail,
@@ -32,7 +61,14 @@ use crate::{
             DemiBuffer,
             MemoryRuntime,
         },
-        network::consts::RECEIVE_BATCH_SIZE,
+        network::{
+            consts::RECEIVE_BATCH_SIZE,
+            types::{
+                Ipv4Interface,
+                Ipv4Route,
+                IPV4_PREFIX_MAX_LEN,
+            },
+        },
         SharedDemiRuntime,
         SharedObject,
     },
@@ -45,22 +81,42 @@ use ::std::{
     time::Duration,
 };
 use ::std::{
-    net::Ipv4Addr,
+    net::{
+        IpAddr,
+        Ipv4Addr,
+        Ipv6Addr,
+        SocketAddr,
+    },
     ops::{
         Deref,
         DerefMut,
     },
+    time::Instant,
 };
 
 //======================================================================================================================
 // Structures
 //======================================================================================================================
 
+/// Datagram for the transport layer, with its source and destination addresses, its protocol and, if it came over IPv4,
+/// its header.
+pub type ReceivedDatagram = (IpAddr, IpAddr, IpProtocol, Option<Ipv4Header>, DemiBuffer);
+
 pub struct Layer3Endpoint {
+    runtime: SharedDemiRuntime,
     layer2_endpoint: SharedLayer2Endpoint,
     arp: SharedArpPeer,
     icmpv4: SharedIcmpv4Peer,
-    local_ipv4_addr: Ipv4Addr,
+    icmpv6: SharedIcmpv6Peer,
+    igmp: SharedIgmpPeer,
+    /// Local IPv4 addresses, starting with the primary one.
+    local_ipv4_addrs: Vec<Ipv4Addr>,
+    local_ipv6_addr: Ipv6Addr,
+    /// Next hops and MTUs of IPv4 destinations.
+    routing_table: RoutingTable,
+    ipv4_reassembler: Ipv4Reassembler,
+    /// Identification field of the next fragmented IPv4 datagram.
+    ipv4_identification: u16,
 }
 
 #[derive(Clone)]
@@ -78,17 +134,52 @@ impl SharedLayer3Endpoint {
         rng_seed: [u8; 32],
     ) -> Result<Self, Fail> {
         let arp: SharedArpPeer = SharedArpPeer::new(config, runtime.clone(), layer2_endpoint.clone())?;
+        let local_ipv4_interfaces: Vec<Ipv4Interface> = config.local_ipv4_interfaces();
+        // The subnets of our addresses are on the local link.
+        let mut routes: Vec<Ipv4Route> = config.ipv4_routes();
+        for interface in &local_ipv4_interfaces {
+            if interface.get_prefix_len() < IPV4_PREFIX_MAX_LEN {
+                routes.push(interface.get_subnet_route()?);
+            }
+        }
+        let mtu: u16 = config.mtu().ok_or_else(|| Config::missing_option("mtu"))?;
+        let routing_table: RoutingTable = RoutingTable::new(routes, config.default_gateway(), mtu);
 
         Ok(SharedLayer3Endpoint(SharedObject::new(Layer3Endpoint {
             arp: arp.clone(),
-            icmpv4: SharedIcmpv4Peer::new(&config, runtime, layer2_endpoint.clone(), arp, rng_seed)?,
-            local_ipv4_addr: config.local_ipv4_addr()?,
+            icmpv4: SharedIcmpv4Peer::new(
+                &config,
+                runtime.clone(),
+                layer2_endpoint.clone(),
+                arp,
+                routing_table.clone(),
+                rng_seed,
+            )?,
+            icmpv6: SharedIcmpv6Peer::new(config, runtime.clone(), layer2_endpoint.clone(), rng_seed)?,
+            igmp: SharedIgmpPeer::new(config, runtime.clone(), layer2_endpoint.clone(), rng_seed)?,
+            local_ipv4_addrs: local_ipv4_interfaces
+                .iter()
+                .map(|interface: &Ipv4Interface| interface.get_addr())
+                .collect(),
+            local_ipv6_addr: config
+                .local_ipv6_addr()
+                .ok_or_else(|| Config::missing_option("local_ipv6_addr"))?,
+            routing_table,
+            ipv4_reassembler: Ipv4Reassembler::new(
+                DEFAULT_REASSEMBLY_TIMEOUT,
+                DEFAULT_REASSEMBLY_MAX_BYTES,
+                DEFAULT_REASSEMBLY_MAX_FLOWS,
+            ),
+            ipv4_identification: 0,
+            runtime,
             layer2_endpoint,
         })))
     }
 
-    pub fn receive(&mut self) -> Result<ArrayVec<(Ipv4Addr, IpProtocol, DemiBuffer), RECEIVE_BATCH_SIZE>, Fail> {
-        let mut batch: ArrayVec<(Ipv4Addr, IpProtocol, DemiBuffer), RECEIVE_BATCH_SIZE> = ArrayVec::new();
+    /// Receives a batch of datagrams for the transport layer, along with their source and destination addresses. IPv4
+    /// datagrams also come with their header, which ICMPv4 errors about them quote.
+    pub fn receive(&mut self) -> Result<ArrayVec<ReceivedDatagram, RECEIVE_BATCH_SIZE>, Fail> {
+        let mut batch: ArrayVec<ReceivedDatagram, RECEIVE_BATCH_SIZE> = ArrayVec::new();
         for (eth2_type, mut packet) in self.layer2_endpoint.receive()? {
             match eth2_type {
                 EtherType2::Arp => {
@@ -106,82 +197,352 @@ impl SharedLayer3Endpoint {
                     };
                     debug!("Ipv4 received {:?}", header);
 
-                    // Check that the destination matches our IP address; otherwise, discard.
-                    if header.get_dest_addr() != self.local_ipv4_addr && !header.get_dest_addr().is_broadcast() {
+                    // Check that the destination matches one of our IP addresses, a broadcast address or a multicast
+                    // group that we belong to; otherwise, discard.
+                    if !self.is_local_ipv4_addr(header.get_dest_addr())
+                        && !self.is_broadcast_ipv4_addr(header.get_dest_addr())
+                        && !self.igmp.is_member(header.get_dest_addr())
+                    {
                         let cause: String = format!("Invalid destination address");
                         warn!("dropping packet: {}", cause);
                         continue;
                     }
 
-                    // Check the the source is a valid IP address; otherwise, discard.
+                    // Check the the source is a valid IP address; otherwise, discard. Hosts that do not have an address
+                    // yet may send IGMP messages from the unspecified address, so let it through for IGMP.
                     if header.get_src_addr().is_broadcast()
                         || header.get_src_addr().is_multicast()
-                        || header.get_src_addr().is_unspecified()
+                        || (header.get_src_addr().is_unspecified() && header.get_protocol() != IpProtocol::IGMP)
                     {
                         let cause: String = format!("invalid remote address (remote={})", header.get_src_addr());
                         warn!("dropping packet: {}", &cause);
                         continue;
                     }
 
+                    // Hold fragments back until the whole datagram has arrived.
+                    let (header, packet): (Ipv4Header, DemiBuffer) = if header.is_fragment() {
+                        let now: Instant = self.runtime.get_now();
+                        match self.ipv4_reassembler.insert(now, header, packet) {
+                            // The NIC steers fragments by their addresses only, as only the first one carries the
+                            // ports, so the datagram may belong to the flow of another receive queue.
+                            Ok(Some((header, packet))) => match Self::transport_flow(&header, &packet) {
+                                Some((local, remote)) if !self.runtime.is_local_flow(local, remote) => {
+                                    self.forward_ipv4_datagram(local, remote, &header, &packet);
+                                    continue;
+                                },
+                                _ => (header, packet),
+                            },
+                            Ok(None) => continue,
+                            Err(e) => {
+                                warn!("dropping fragment: {:?}", e);
+                                continue;
+                            },
+                        }
+                    } else {
+                        (header, packet)
+                    };
+
                     let protocol: IpProtocol = header.get_protocol();
                     match protocol {
                         IpProtocol::ICMPv4 => {
                             self.icmpv4.receive(header, packet);
                             continue;
                         },
-                        _ => batch.push((header.get_src_addr(), protocol, packet)),
+                        IpProtocol::IGMP => {
+                            self.igmp.receive(header, packet);
+                            continue;
+                        },
+                        _ => batch.push((
+                            IpAddr::V4(header.get_src_addr()),
+                            IpAddr::V4(header.get_dest_addr()),
+                            protocol,
+                            Some(header),
+                            packet,
+                        )),
+                    }
+                },
+                EtherType2::Ipv6 => {
+                    let header = match Ipv6Header::parse_and_strip(&mut packet) {
+                        Ok(header) => header,
+                        Err(e) => {
+                            let cause: String = format!("Invalid IPv6 header: {:?}", e);
+                            warn!("dropping packet: {}", cause);
+                            continue;
+                        },
+                    };
+                    debug!("Ipv6 received {:?}", header);
+
+                    // Check that the destination matches our IP address or one of the multicast groups that we
+                    // implicitly join; otherwise, discard.
+                    if !self.is_local_ipv6_addr(&header.get_dest_addr()) {
+                        let cause: &str = "Invalid destination address";
+                        warn!("dropping packet: {}", cause);
+                        continue;
+                    }
+
+                    // Check the the source is a valid IP address; otherwise, discard. The unspecified address is used
+                    // by Neighbor Discovery, so let it through for ICMPv6.
+                    let protocol: IpProtocol = header.get_next_header();
+                    if header.get_src_addr().is_multicast()
+                        || (header.get_src_addr().is_unspecified() && protocol != IpProtocol::ICMPv6)
+                    {
+                        let cause: String = format!("invalid remote address (remote={})", header.get_src_addr());
+                        warn!("dropping packet: {}", &cause);
+                        continue;
+                    }
+
+                    match protocol {
+                        IpProtocol::ICMPv6 => {
+                            self.icmpv6.receive(header, packet);
+                            continue;
+                        },
+                        _ => batch.push((
+                            IpAddr::V6(header.get_src_addr()),
+                            IpAddr::V6(header.get_dest_addr()),
+                            protocol,
+                            None,
+                            packet,
+                        )),
                     }
                 },
-                EtherType2::Ipv6 => warn!("Ipv6 not supported yet"), // Ignore for now.
             }
         }
         Ok(batch)
     }
 
-    pub fn transmit_tcp_packet_nonblocking(&mut self, remote_ipv4_addr: Ipv4Addr, pkt: DemiBuffer) -> Result<(), Fail> {
-        let remote_link_addr: MacAddress = match self.arp.try_query(remote_ipv4_addr) {
-            Some(addr) => addr,
-            _ => return Err(Fail::new(libc::EAGAIN, "destination not in ARP cache")),
+    pub fn transmit_tcp_packet_nonblocking(
+        &mut self,
+        local_ip_addr: IpAddr,
+        remote_ip_addr: IpAddr,
+        pkt: DemiBuffer,
+    ) -> Result<(), Fail> {
+        let remote_link_addr: MacAddress = match remote_ip_addr {
+            IpAddr::V4(remote_ipv4_addr) if self.is_broadcast_ipv4_addr(remote_ipv4_addr) => MacAddress::broadcast(),
+            IpAddr::V4(remote_ipv4_addr) if remote_ipv4_addr.is_multicast() => {
+                ipv4_multicast_link_addr(remote_ipv4_addr)
+            },
+            IpAddr::V4(remote_ipv4_addr) => match self.arp.try_query(self.routing_table.lookup(remote_ipv4_addr).0) {
+                Some(addr) => addr,
+                _ => return Err(Fail::new(libc::EAGAIN, "destination not in ARP cache")),
+            },
+            IpAddr::V6(remote_ipv6_addr) => match self.icmpv6.try_query(remote_ipv6_addr) {
+                Some(addr) => addr,
+                _ => return Err(Fail::new(libc::EAGAIN, "destination not in neighbor cache")),
+            },
         };
 
-        self.transmit_packet(remote_ipv4_addr, remote_link_addr, IpProtocol::TCP, pkt)
+        self.transmit_packet(local_ip_addr, remote_ip_addr, remote_link_addr, IpProtocol::TCP, pkt)
     }
 
     pub async fn transmit_tcp_packet_blocking(
         &mut self,
-        remote_ipv4_addr: Ipv4Addr,
+        local_ip_addr: IpAddr,
+        remote_ip_addr: IpAddr,
         pkt: DemiBuffer,
     ) -> Result<(), Fail> {
-        let remote_link_addr: MacAddress = self.arp.query(remote_ipv4_addr).await?;
+        let remote_link_addr: MacAddress = self.resolve_link_addr(remote_ip_addr).await?;
 
-        self.transmit_packet(remote_ipv4_addr, remote_link_addr, IpProtocol::TCP, pkt)
+        self.transmit_packet(local_ip_addr, remote_ip_addr, remote_link_addr, IpProtocol::TCP, pkt)
     }
 
     pub async fn transmit_udp_packet_blocking(
         &mut self,
-        remote_ipv4_addr: Ipv4Addr,
+        local_ip_addr: IpAddr,
+        remote_ip_addr: IpAddr,
         pkt: DemiBuffer,
     ) -> Result<(), Fail> {
-        let remote_link_addr: MacAddress = self.arp.query(remote_ipv4_addr).await?;
+        let remote_link_addr: MacAddress = self.resolve_link_addr(remote_ip_addr).await?;
 
-        self.transmit_packet(remote_ipv4_addr, remote_link_addr, IpProtocol::UDP, pkt)
+        self.transmit_packet(local_ip_addr, remote_ip_addr, remote_link_addr, IpProtocol::UDP, pkt)
     }
 
+    /// Sends [pkt] from [local_ip_addr], which must be one of our addresses, to [remote_ip_addr].
     pub fn transmit_packet(
         &mut self,
-        remote_ipv4_addr: Ipv4Addr,
+        local_ip_addr: IpAddr,
+        remote_ip_addr: IpAddr,
         remote_link_addr: MacAddress,
         ip_protocol: IpProtocol,
         mut pkt: DemiBuffer,
     ) -> Result<(), Fail> {
-        let ipv4_header: Ipv4Header = Ipv4Header::new(self.local_ipv4_addr, remote_ipv4_addr, ip_protocol);
-        ipv4_header.serialize_and_attach(&mut pkt);
-        self.layer2_endpoint.transmit_ipv4_packet(remote_link_addr, pkt)
+        match (local_ip_addr, remote_ip_addr) {
+            (IpAddr::V4(local_ipv4_addr), IpAddr::V4(remote_ipv4_addr)) => {
+                debug_assert!(self.local_ipv4_addrs.contains(&local_ipv4_addr));
+                let (_, mtu): (Ipv4Addr, u16) = self.routing_table.lookup(remote_ipv4_addr);
+                if (IPV4_HEADER_MIN_SIZE as usize) + pkt.total_len() > mtu as usize {
+                    return self.transmit_ipv4_fragments(
+                        local_ipv4_addr,
+                        remote_ipv4_addr,
+                        remote_link_addr,
+                        ip_protocol,
+                        pkt,
+                        mtu,
+                    );
+                }
+                let ipv4_header: Ipv4Header = Ipv4Header::new(local_ipv4_addr, remote_ipv4_addr, ip_protocol);
+                ipv4_header.serialize_and_attach(&mut pkt);
+                self.layer2_endpoint.transmit_ipv4_packet(remote_link_addr, pkt)
+            },
+            (IpAddr::V6(local_ipv6_addr), IpAddr::V6(remote_ipv6_addr)) => {
+                let ipv6_header: Ipv6Header = Ipv6Header::new(local_ipv6_addr, remote_ipv6_addr, ip_protocol);
+                ipv6_header.serialize_and_attach(&mut pkt);
+                self.layer2_endpoint.transmit_ipv6_packet(remote_link_addr, pkt)
+            },
+            _ => {
+                let cause: String = format!(
+                    "address families do not match (local={:?}, remote={:?})",
+                    local_ip_addr, remote_ip_addr
+                );
+                error!("transmit_packet(): {}", &cause);
+                Err(Fail::new(libc::EAFNOSUPPORT, &cause))
+            },
+        }
+    }
+
+    /// Takes the oldest ICMPv4 error about a datagram that we sent, if any.
+    pub fn pop_icmpv4_error(&mut self) -> Option<Icmpv4Error> {
+        self.icmpv4.pop_error()
+    }
+
+    /// Tells the source of the datagram with [quoted_hdr] and [payload] with an ICMPv4 Destination Unreachable message
+    /// carrying [code] that we could not deliver it.
+    pub fn transmit_icmpv4_destination_unreachable(
+        &mut self,
+        code: u8,
+        quoted_hdr: &Ipv4Header,
+        payload: &DemiBuffer,
+    ) -> Result<(), Fail> {
+        self.icmpv4.send_destination_unreachable(code, quoted_hdr, payload)
+    }
+
+    /// Returns the local and remote ends of the flow of the TCP segment or UDP datagram in [packet], if it carries one.
+    fn transport_flow(header: &Ipv4Header, packet: &DemiBuffer) -> Option<(SocketAddr, SocketAddr)> {
+        if !matches!(header.get_protocol(), IpProtocol::TCP | IpProtocol::UDP) || packet.len() < 4 {
+            return None;
+        }
+        let remote_port: u16 = u16::from_be_bytes([packet[0], packet[1]]);
+        let local_port: u16 = u16::from_be_bytes([packet[2], packet[3]]);
+        Some((
+            SocketAddr::new(IpAddr::V4(header.get_dest_addr()), local_port),
+            SocketAddr::new(IpAddr::V4(header.get_src_addr()), remote_port),
+        ))
+    }
+
+    /// Hands a reassembled IPv4 datagram that [remote] sent to [local] to the runtime that owns the receive queue of
+    /// its flow, as an unfragmented frame.
+    fn forward_ipv4_datagram(&self, local: SocketAddr, remote: SocketAddr, header: &Ipv4Header, packet: &DemiBuffer) {
+        let headroom: usize = IPV4_HEADER_MIN_SIZE as usize + ETHERNET2_HEADER_SIZE;
+        let mut frame: DemiBuffer = match DemiBuffer::from_slice_with_headroom(&packet[..], headroom) {
+            Ok(frame) => frame,
+            Err(e) => {
+                warn!("dropping datagram: {:?}", e);
+                return;
+            },
+        };
+        Ipv4Header::new(header.get_src_addr(), header.get_dest_addr(), header.get_protocol())
+            .serialize_and_attach(&mut frame);
+        let local_link_addr: MacAddress = self.layer2_endpoint.get_local_link_addr();
+        Ethernet2Header::new(local_link_addr, local_link_addr, EtherType2::Ipv4).serialize_and_attach(&mut frame);
+        self.runtime.forward_frame(local, remote, &frame[..]);
+    }
+
+    /// Splits an IPv4 datagram that does not fit in the [mtu] of its route into fragments and sends them (see RFC 791).
+    fn transmit_ipv4_fragments(
+        &mut self,
+        local_ipv4_addr: Ipv4Addr,
+        remote_ipv4_addr: Ipv4Addr,
+        remote_link_addr: MacAddress,
+        ip_protocol: IpProtocol,
+        pkt: DemiBuffer,
+        mtu: u16,
+    ) -> Result<(), Fail> {
+        // All fragments but the last one must carry a multiple of 8 bytes.
+        let max_fragment_size: usize =
+            (mtu.saturating_sub(IPV4_HEADER_MIN_SIZE) as usize) & !(IPV4_FRAGMENT_UNIT_SIZE - 1);
+        if max_fragment_size == 0 {
+            let cause: String = format!("MTU is too small to fragment datagrams (mtu={})", mtu);
+            error!("transmit_ipv4_fragments(): {}", &cause);
+            return Err(Fail::new(libc::EMSGSIZE, &cause));
+        }
+        if (IPV4_HEADER_MIN_SIZE as usize) + pkt.total_len() > u16::MAX as usize {
+            let cause: String = format!("datagram is too large (len={})", pkt.total_len());
+            error!("transmit_ipv4_fragments(): {}", &cause);
+            return Err(Fail::new(libc::EMSGSIZE, &cause));
+        }
+        let pkt: DemiBuffer = pkt.linearize()?;
+
+        let identification: u16 = self.ipv4_identification;
+        self.ipv4_identification = self.ipv4_identification.wrapping_add(1);
+
+        let headroom: u16 = IPV4_HEADER_MIN_SIZE + ETHERNET2_HEADER_SIZE as u16;
+        for offset in (0..pkt.len()).step_by(max_fragment_size) {
+            let end: usize = (offset + max_fragment_size).min(pkt.len());
+            let mut fragment: DemiBuffer = DemiBuffer::new_with_headroom((end - offset) as u16, headroom);
+            fragment.copy_from_slice(&pkt[offset..end]);
+            let ipv4_header: Ipv4Header = Ipv4Header::new_fragment(
+                local_ipv4_addr,
+                remote_ipv4_addr,
+                ip_protocol,
+                identification,
+                offset,
+                end < pkt.len(),
+            );
+            ipv4_header.serialize_and_attach(&mut fragment);
+            self.layer2_endpoint.transmit_ipv4_packet(remote_link_addr, fragment)?;
+        }
+        Ok(())
+    }
+
+    /// Resolves the link-layer address of the next hop towards a remote peer, using ARP for IPv4 and Neighbor Discovery
+    /// for IPv6. Broadcasts go to every host on the link and multicasts to the members of their group.
+    async fn resolve_link_addr(&mut self, remote_ip_addr: IpAddr) -> Result<MacAddress, Fail> {
+        match remote_ip_addr {
+            IpAddr::V4(remote_ipv4_addr) if self.is_broadcast_ipv4_addr(remote_ipv4_addr) => {
+                Ok(MacAddress::broadcast())
+            },
+            IpAddr::V4(remote_ipv4_addr) if remote_ipv4_addr.is_multicast() => {
+                Ok(ipv4_multicast_link_addr(remote_ipv4_addr))
+            },
+            IpAddr::V4(remote_ipv4_addr) => {
+                let (next_hop_addr, _): (Ipv4Addr, u16) = self.routing_table.lookup(remote_ipv4_addr);
+                self.arp.query(next_hop_addr).await
+            },
+            IpAddr::V6(remote_ipv6_addr) => self.icmpv6.query(remote_ipv6_addr).await,
+        }
+    }
+
+    /// Joins the IPv4 multicast [group], so that datagrams sent to it get delivered to the transport layer.
+    pub fn join_ipv4_multicast_group(&mut self, group: Ipv4Addr) -> Result<(), Fail> {
+        self.igmp.join(group)
+    }
+
+    /// Leaves the IPv4 multicast [group], once for every time that it was joined.
+    pub fn leave_ipv4_multicast_group(&mut self, group: Ipv4Addr) -> Result<(), Fail> {
+        self.igmp.leave(group)
+    }
+
+    /// Checks whether [ipv4_addr] is one of our addresses.
+    pub fn is_local_ipv4_addr(&self, ipv4_addr: Ipv4Addr) -> bool {
+        self.local_ipv4_addrs.contains(&ipv4_addr)
+    }
+
+    /// Checks whether [ipv4_addr] is the limited broadcast address or the directed broadcast address of a subnet on the
+    /// local link.
+    pub fn is_broadcast_ipv4_addr(&self, ipv4_addr: Ipv4Addr) -> bool {
+        ipv4_addr.is_broadcast() || self.routing_table.is_directed_broadcast(ipv4_addr)
+    }
+
+    /// Checks whether [ipv6_addr] is our address, the all-nodes multicast address or the solicited-node multicast
+    /// address of our address (see RFC 4291 Section 2.8).
+    fn is_local_ipv6_addr(&self, ipv6_addr: &Ipv6Addr) -> bool {
+        *ipv6_addr == self.local_ipv6_addr
+            || *ipv6_addr == Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 1)
+            || *ipv6_addr == solicited_node_multicast_addr(&self.local_ipv6_addr)
     }
 
     #[cfg(test)]
     pub fn get_local_addr(&self) -> Ipv4Addr {
-        self.local_ipv4_addr
+        self.local_ipv4_addrs[0]
     }
 
     #[cfg(test)]
@@ -189,6 +550,25 @@ impl SharedLayer3Endpoint {
         self.icmpv4.ping(addr, timeout).await
     }
 
+    pub fn get_local_ipv6_addr(&self) -> Ipv6Addr {
+        self.local_ipv6_addr
+    }
+
+    #[cfg(test)]
+    pub async fn ping6(&mut self, addr: Ipv6Addr, timeout: Option<Duration>) -> Result<Duration, Fail> {
+        self.icmpv6.ping(addr, timeout).await
+    }
+
+    #[cfg(test)]
+    pub async fn ndp_query(&mut self, addr: Ipv6Addr) -> Result<MacAddress, Fail> {
+        self.icmpv6.query(addr).await
+    }
+
+    #[cfg(test)]
+    pub fn export_neighbor_cache(&self) -> HashMap<Ipv6Addr, MacAddress, RandomState> {
+        self.icmpv6.export_cache()
+    }
+
     #[cfg(test)]
     pub async fn arp_query(&mut self, addr: Ipv4Addr) -> Result<MacAddress, Fail> {
         self.arp.query(addr).await
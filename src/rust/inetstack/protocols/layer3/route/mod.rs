@@ -0,0 +1,9 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+mod table;
+
+#[cfg(test)]
+mod tests;
+
+pub use self::table::RoutingTable;
@@ -0,0 +1,66 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::network::types::Ipv4Route;
+use ::std::{
+    cmp::Reverse,
+    net::Ipv4Addr,
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Routing table for IPv4 destinations. Destinations that no route covers are on the local link.
+#[derive(Clone, Debug)]
+pub struct RoutingTable {
+    /// Routes, from the longest prefix to the shortest one.
+    routes: Vec<Ipv4Route>,
+    /// MTU of the local link.
+    link_mtu: u16,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl RoutingTable {
+    /// Creates a routing table with [routes] and a default route through [default_gateway] (if any), for a link with
+    /// [link_mtu].
+    pub fn new(mut routes: Vec<Ipv4Route>, default_gateway: Option<Ipv4Addr>, link_mtu: u16) -> Self {
+        if let Some(default_gateway) = default_gateway {
+            routes.push(Ipv4Route::new_default(default_gateway));
+        }
+        // Sorting is stable, so routes with the same prefix length keep the order in which they were configured.
+        routes.sort_by_key(|route: &Ipv4Route| Reverse(route.get_prefix_len()));
+        Self { routes, link_mtu }
+    }
+
+    /// Returns the address of the next hop towards [dst_addr], which is [dst_addr] itself if it is on the local link,
+    /// along with the MTU of the route.
+    pub fn lookup(&self, dst_addr: Ipv4Addr) -> (Ipv4Addr, u16) {
+        // Broadcast and multicast datagrams never leave the local link.
+        if dst_addr.is_broadcast() || dst_addr.is_multicast() {
+            return (dst_addr, self.link_mtu);
+        }
+
+        match self.routes.iter().find(|route: &&Ipv4Route| route.contains(dst_addr)) {
+            Some(route) => (
+                route.get_gateway().unwrap_or(dst_addr),
+                route.get_mtu().unwrap_or(self.link_mtu),
+            ),
+            None => (dst_addr, self.link_mtu),
+        }
+    }
+
+    /// Queries whether [addr] is the directed broadcast address of a subnet on the local link.
+    pub fn is_directed_broadcast(&self, addr: Ipv4Addr) -> bool {
+        self.routes.iter().any(|route: &Ipv4Route| {
+            route.get_gateway().is_none() && route.get_prefix_len() < 31 && route.get_broadcast_addr() == addr
+        })
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::protocols::layer3::route::RoutingTable,
+    runtime::network::types::Ipv4Route,
+};
+use ::anyhow::Result;
+use ::std::net::Ipv4Addr;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// MTU of the local link.
+const LINK_MTU: u16 = 1500;
+
+/// Default gateway.
+const DEFAULT_GATEWAY: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 1);
+
+//======================================================================================================================
+// Tests
+//======================================================================================================================
+
+/// Tests that routes are picked by longest prefix, and that uncovered destinations are on the local link.
+#[test]
+fn test_routing_table_lookup() -> Result<()> {
+    let routes: Vec<Ipv4Route> = vec![
+        Ipv4Route::new(
+            Ipv4Addr::new(10, 0, 0, 0),
+            8,
+            Some(Ipv4Addr::new(192, 168, 1, 2)),
+            Some(1400),
+        )?,
+        Ipv4Route::new(
+            Ipv4Addr::new(10, 1, 2, 3),
+            16,
+            Some(Ipv4Addr::new(192, 168, 1, 3)),
+            None,
+        )?,
+        Ipv4Route::new(Ipv4Addr::new(192, 168, 1, 0), 24, None, None)?,
+    ];
+    let table: RoutingTable = RoutingTable::new(routes, Some(DEFAULT_GATEWAY), LINK_MTU);
+
+    // Destinations on the local subnet are their own next hop.
+    crate::ensure_eq!(
+        table.lookup(Ipv4Addr::new(192, 168, 1, 20)),
+        (Ipv4Addr::new(192, 168, 1, 20), LINK_MTU)
+    );
+    // The longest prefix wins, and routes may override the MTU of the link.
+    crate::ensure_eq!(
+        table.lookup(Ipv4Addr::new(10, 1, 0, 1)),
+        (Ipv4Addr::new(192, 168, 1, 3), LINK_MTU)
+    );
+    crate::ensure_eq!(
+        table.lookup(Ipv4Addr::new(10, 2, 0, 1)),
+        (Ipv4Addr::new(192, 168, 1, 2), 1400)
+    );
+    // Everything else goes through the default gateway.
+    crate::ensure_eq!(table.lookup(Ipv4Addr::new(8, 8, 8, 8)), (DEFAULT_GATEWAY, LINK_MTU));
+    // Broadcasts stay on the local link.
+    crate::ensure_eq!(table.lookup(Ipv4Addr::BROADCAST), (Ipv4Addr::BROADCAST, LINK_MTU));
+
+    // Without a default gateway, destinations that no route covers are on the local link.
+    let table: RoutingTable = RoutingTable::new(Vec::new(), None, LINK_MTU);
+    crate::ensure_eq!(
+        table.lookup(Ipv4Addr::new(8, 8, 8, 8)),
+        (Ipv4Addr::new(8, 8, 8, 8), LINK_MTU)
+    );
+
+    Ok(())
+}
+
+/// Tests that only subnets on the local link have a directed broadcast address.
+#[test]
+fn test_routing_table_directed_broadcast() -> Result<()> {
+    let routes: Vec<Ipv4Route> = vec![
+        Ipv4Route::new(Ipv4Addr::new(10, 0, 0, 0), 8, Some(Ipv4Addr::new(192, 168, 1, 2)), None)?,
+        Ipv4Route::new(Ipv4Addr::new(192, 168, 1, 0), 24, None, None)?,
+    ];
+    let table: RoutingTable = RoutingTable::new(routes, Some(DEFAULT_GATEWAY), LINK_MTU);
+
+    crate::ensure_eq!(table.is_directed_broadcast(Ipv4Addr::new(192, 168, 1, 255)), true);
+    crate::ensure_eq!(table.is_directed_broadcast(Ipv4Addr::new(192, 168, 1, 254)), false);
+    crate::ensure_eq!(table.is_directed_broadcast(Ipv4Addr::new(10, 255, 255, 255)), false);
+
+    Ok(())
+}
+
+/// Tests parsing route destinations in CIDR notation.
+#[test]
+fn test_route_parse_destination() -> Result<()> {
+    crate::ensure_eq!(
+        Ipv4Route::parse_destination("10.1.0.0/16")?,
+        (Ipv4Addr::new(10, 1, 0, 0), 16)
+    );
+    crate::ensure_eq!(
+        Ipv4Route::parse_destination("10.1.2.3")?,
+        (Ipv4Addr::new(10, 1, 2, 3), 32)
+    );
+    crate::ensure_eq!(Ipv4Route::parse_destination("10.1.0.0/33").is_err(), true);
+    crate::ensure_eq!(Ipv4Route::parse_destination("10.1.0/16").is_err(), true);
+
+    // Host bits of the destination are cleared.
+    let route: Ipv4Route = Ipv4Route::new(Ipv4Addr::new(10, 1, 2, 3), 16, None, None)?;
+    crate::ensure_eq!(route.get_destination(), Ipv4Addr::new(10, 1, 0, 0));
+    crate::ensure_eq!(route.contains(Ipv4Addr::new(10, 1, 255, 255)), true);
+    crate::ensure_eq!(route.contains(Ipv4Addr::new(10, 2, 0, 0)), false);
+
+    Ok(())
+}
@@ -20,7 +20,9 @@ use crate::{
     expect_some,
     inetstack::protocols::{
         layer3::{
+            icmpv4::Icmpv4Error,
             ip::IpProtocol,
+            ReceivedDatagram,
             SharedLayer3Endpoint,
         },
         layer4::{
@@ -40,10 +42,7 @@ use crate::{
             DemiBuffer,
             MemoryRuntime,
         },
-        network::{
-            consts::RECEIVE_BATCH_SIZE,
-            unwrap_socketaddr,
-        },
+        network::consts::RECEIVE_BATCH_SIZE,
         SharedDemiRuntime,
     },
     timer,
@@ -54,14 +53,17 @@ use ::socket2::{
     Type,
 };
 use ::std::net::{
-    Ipv4Addr,
+    Shutdown,
     SocketAddr,
-    SocketAddrV4,
 };
 #[cfg(test)]
 use ::std::{
     collections::HashMap,
     hash::RandomState,
+    net::{
+        Ipv4Addr,
+        Ipv6Addr,
+    },
     time::Duration,
 };
 
@@ -93,10 +95,9 @@ impl Peer {
         config: &Config,
         runtime: SharedDemiRuntime,
         layer3_endpoint: SharedLayer3Endpoint,
-        rng_seed: [u8; 32],
     ) -> Result<Self, Fail> {
         let udp: SharedUdpPeer = SharedUdpPeer::new(config, runtime.clone(), layer3_endpoint.clone())?;
-        let tcp: SharedTcpPeer = SharedTcpPeer::new(config, runtime.clone(), layer3_endpoint.clone(), rng_seed)?;
+        let tcp: SharedTcpPeer = SharedTcpPeer::new(config, runtime.clone(), layer3_endpoint.clone())?;
 
         Ok(Peer {
             tcp,
@@ -115,23 +116,34 @@ impl Peer {
             Ok(_) => (),
             Err(_) => warn!("Could not receive from network interface, continuing ..."),
         }
+        while let Some(error) = self.layer3_endpoint.pop_icmpv4_error() {
+            self.receive_icmpv4_error(error);
+        }
     }
 
-    fn receive_batch(&mut self, batch: ArrayVec<(Ipv4Addr, IpProtocol, DemiBuffer), RECEIVE_BATCH_SIZE>) {
+    fn receive_batch(&mut self, batch: ArrayVec<ReceivedDatagram, RECEIVE_BATCH_SIZE>) {
         timer!("inetstack::poll_bg_work::for::for");
         trace!("found packets: {:?}", batch.len());
-        for (src_ipv4_addr, ip_type, payload) in batch {
+        for (src_ip_addr, dst_ip_addr, ip_type, ipv4_hdr, payload) in batch {
             match ip_type {
-                IpProtocol::TCP => self.tcp.receive(src_ipv4_addr, payload),
-                IpProtocol::UDP => self.udp.receive(src_ipv4_addr, payload),
+                IpProtocol::TCP => self.tcp.receive(src_ip_addr, payload),
+                IpProtocol::UDP => self.udp.receive(src_ip_addr, dst_ip_addr, ipv4_hdr, payload),
                 _ => unreachable!("Should have been handled at a lower layer"),
             }
         }
     }
 
+    /// Hands an ICMPv4 error about a datagram that we sent to the protocol that sent it.
+    fn receive_icmpv4_error(&mut self, error: Icmpv4Error) {
+        match error.get_protocol() {
+            IpProtocol::TCP => self.tcp.receive_icmpv4_error(&error),
+            IpProtocol::UDP => self.udp.receive_icmpv4_error(&error),
+            protocol => debug!("ignoring ICMPv4 error about {:?} datagram", protocol),
+        }
+    }
+
     pub fn socket(&mut self, domain: Domain, typ: Type) -> Result<Socket, Fail> {
-        // TODO: Remove this once we support Ipv6.
-        if domain != Domain::IPV4 {
+        if domain != Domain::IPV4 && domain != Domain::IPV6 {
             return Err(Fail::new(libc::ENOTSUP, "address family not supported"));
         }
         match typ {
@@ -145,11 +157,7 @@ impl Peer {
     pub fn set_socket_option(&mut self, sd: &mut Socket, option: SocketOption) -> Result<(), Fail> {
         match sd {
             Socket::Tcp(socket) => self.tcp.set_socket_option(socket, option),
-            Socket::Udp(_) => {
-                let cause: String = format!("Socket options are not supported on UDP sockets");
-                error!("get_socket_option(): {}", cause);
-                Err(Fail::new(libc::ENOTSUP, &cause))
-            },
+            Socket::Udp(socket) => self.udp.set_socket_option(socket, option),
         }
     }
 
@@ -158,22 +166,29 @@ impl Peer {
     pub fn get_socket_option(&mut self, sd: &mut Socket, option: SocketOption) -> Result<SocketOption, Fail> {
         match sd {
             Socket::Tcp(socket) => self.tcp.get_socket_option(socket, option),
-            Socket::Udp(_) => {
-                let cause: String = format!("Socket options are not supported on UDP sockets");
-                error!("get_socket_option(): {}", cause);
-                Err(Fail::new(libc::ENOTSUP, &cause))
-            },
+            Socket::Udp(socket) => self.udp.get_socket_option(socket, option),
         }
     }
 
-    pub fn getpeername(&mut self, sd: &mut Socket) -> Result<SocketAddrV4, Fail> {
+    pub fn getpeername(&mut self, sd: &mut Socket) -> Result<SocketAddr, Fail> {
         match sd {
             Socket::Tcp(socket) => self.tcp.getpeername(socket),
-            Socket::Udp(_) => {
-                let cause: String = format!("Getting peer address is not supported on UDP sockets");
-                error!("getpeername(): {}", cause);
-                Err(Fail::new(libc::ENOTSUP, &cause))
-            },
+            Socket::Udp(socket) => self.udp.getpeername(socket),
+        }
+    }
+
+    pub fn getsockname(&mut self, sd: &mut Socket) -> Result<SocketAddr, Fail> {
+        match sd {
+            Socket::Tcp(socket) => self.tcp.getsockname(socket),
+            Socket::Udp(socket) => self.udp.getsockname(socket),
+        }
+    }
+
+    /// Shuts down receiving, sending or both on a connected socket.
+    pub fn shutdown(&mut self, sd: &mut Socket, how: Shutdown) -> Result<(), Fail> {
+        match sd {
+            Socket::Tcp(socket) => self.tcp.shutdown(socket, how),
+            Socket::Udp(socket) => self.udp.shutdown(socket, how),
         }
     }
 
@@ -189,8 +204,14 @@ impl Peer {
     /// returned instead.
     ///
     pub fn bind(&mut self, sd: &mut Socket, local: SocketAddr) -> Result<(), Fail> {
-        // FIXME: add IPv6 support; https://github.com/microsoft/demikernel/issues/935
-        let local: SocketAddrV4 = unwrap_socketaddr(local)?;
+        // The libOS only checks IPv4 addresses against the local address, so check IPv6 addresses here.
+        if let SocketAddr::V6(addr) = local {
+            if !addr.ip().is_unspecified() && *addr.ip() != self.layer3_endpoint.get_local_ipv6_addr() {
+                let cause: String = format!("cannot bind to non-local address: {:?}", addr.ip());
+                error!("bind(): {}", &cause);
+                return Err(Fail::new(libc::EADDRNOTAVAIL, &cause));
+            }
+        }
 
         match sd {
             Socket::Tcp(socket) => self.tcp.bind(socket, local),
@@ -252,7 +273,7 @@ impl Peer {
             Socket::Tcp(socket) => {
                 let socket = self.tcp.accept(socket).await?;
                 let addr = expect_some!(socket.remote(), "accepted socket must have an endpoint");
-                Ok((Socket::Tcp(socket), addr.into()))
+                Ok((Socket::Tcp(socket), addr))
             },
             // This queue descriptor does not concern a TCP socket.
             _ => {
@@ -278,13 +299,20 @@ impl Peer {
     pub async fn connect(&mut self, sd: &mut Socket, remote: SocketAddr) -> Result<(), Fail> {
         trace!("connect(): remote={:?}", remote);
 
-        // FIXME: add IPv6 support; https://github.com/microsoft/demikernel/issues/935
-        let remote: SocketAddrV4 = unwrap_socketaddr(remote)?;
-
         match sd {
             Socket::Tcp(socket) => self.tcp.connect(socket, remote).await,
-            _ => Err(Fail::new(libc::EINVAL, "invalid queue type")),
+            Socket::Udp(socket) => self.udp.connect(socket, remote),
+        }
+    }
+
+    /// Rolls back a connect that was cancelled before it completed.
+    pub fn cancel_connect(&mut self, sd: &mut Socket) -> Result<(), Fail> {
+        match sd {
+            Socket::Tcp(socket) => self.tcp.cancel_connect(socket),
+            // Connecting a UDP socket completes synchronously, so there is nothing to roll back.
+            Socket::Udp(_) => (),
         }
+        Ok(())
     }
 
     ///
@@ -344,13 +372,25 @@ impl Peer {
         self.layer3_endpoint.ping(addr, timeout).await
     }
 
+    pub async fn ping6(&mut self, addr: Ipv6Addr, timeout: Option<Duration>) -> Result<Duration, Fail> {
+        self.layer3_endpoint.ping6(addr, timeout).await
+    }
+
     pub async fn arp_query(&mut self, addr: Ipv4Addr) -> Result<MacAddress, Fail> {
         self.layer3_endpoint.arp_query(addr).await
     }
 
+    pub async fn ndp_query(&mut self, addr: Ipv6Addr) -> Result<MacAddress, Fail> {
+        self.layer3_endpoint.ndp_query(addr).await
+    }
+
     pub fn export_arp_cache(&self) -> HashMap<Ipv4Addr, MacAddress, RandomState> {
         self.layer3_endpoint.export_arp_cache()
     }
+
+    pub fn export_neighbor_cache(&self) -> HashMap<Ipv6Addr, MacAddress, RandomState> {
+        self.layer3_endpoint.export_neighbor_cache()
+    }
 }
 
 //======================================================================================================================
@@ -12,23 +12,27 @@ use crate::{
     },
     expect_some,
     inetstack::protocols::{
-        layer3::SharedLayer3Endpoint,
+        layer3::{
+            icmpv4::Icmpv4Error,
+            SharedLayer3Endpoint,
+        },
         layer4::tcp::{
             constants::{
                 FALLBACK_MSS,
                 MAX_WINDOW_SCALE,
             },
             established::{
-                congestion_control::{
-                    self,
-                    CongestionControl,
-                },
+                congestion_control,
                 EstablishedSocket,
             },
             header::{
                 TcpHeader,
                 TcpOptions2,
             },
+            timestamp::{
+                get_timestamp_option,
+                TimestampClock,
+            },
             SeqNumber,
         },
         MAX_HEADER_SIZE,
@@ -52,8 +56,8 @@ use ::futures::{
 };
 use ::std::{
     net::{
-        Ipv4Addr,
-        SocketAddrV4,
+        IpAddr,
+        SocketAddr,
     },
     ops::{
         Deref,
@@ -72,18 +76,20 @@ enum State {
     Connecting,
     /// The socket is closed.
     Closed,
+    /// The network reported that the remote cannot be reached, with the error that connecting fails with.
+    Unreachable(libc::c_int),
 }
 
 pub struct ActiveOpenSocket {
     local_isn: SeqNumber,
-    local: SocketAddrV4,
-    remote: SocketAddrV4,
+    local: SocketAddr,
+    remote: SocketAddr,
     runtime: SharedDemiRuntime,
     layer3_endpoint: SharedLayer3Endpoint,
-    recv_queue: SharedAsyncQueue<(Ipv4Addr, TcpHeader, DemiBuffer)>,
-    ack_queue: SharedAsyncQueue<usize>,
+    recv_queue: SharedAsyncQueue<(IpAddr, TcpHeader, DemiBuffer)>,
     tcp_config: TcpConfig,
     socket_options: TcpSocketOptions,
+    timestamp_clock: TimestampClock,
     dead_socket_tx: mpsc::UnboundedSender<QDesc>,
     state: SharedAsyncValue<State>,
 }
@@ -98,18 +104,18 @@ pub struct SharedActiveOpenSocket(SharedObject<ActiveOpenSocket>);
 impl SharedActiveOpenSocket {
     pub fn new(
         local_isn: SeqNumber,
-        local: SocketAddrV4,
-        remote: SocketAddrV4,
+        local: SocketAddr,
+        remote: SocketAddr,
         runtime: SharedDemiRuntime,
         layer3_endpoint: SharedLayer3Endpoint,
-        recv_queue: SharedAsyncQueue<(Ipv4Addr, TcpHeader, DemiBuffer)>,
-        ack_queue: SharedAsyncQueue<usize>,
+        recv_queue: SharedAsyncQueue<(IpAddr, TcpHeader, DemiBuffer)>,
         tcp_config: TcpConfig,
         default_socket_options: TcpSocketOptions,
         dead_socket_tx: mpsc::UnboundedSender<QDesc>,
     ) -> Result<Self, Fail> {
         // TODO: Add fast path here when remote is already in the ARP cache (and subtract one retry).
 
+        let timestamp_clock: TimestampClock = TimestampClock::new(runtime.get_now());
         Ok(Self(SharedObject::<ActiveOpenSocket>::new(ActiveOpenSocket {
             local_isn,
             local,
@@ -117,9 +123,9 @@ impl SharedActiveOpenSocket {
             runtime: runtime.clone(),
             layer3_endpoint,
             recv_queue,
-            ack_queue,
             tcp_config,
             socket_options: default_socket_options,
+            timestamp_clock,
             dead_socket_tx,
             state: SharedAsyncValue::new(State::Connecting),
         })))
@@ -156,26 +162,37 @@ impl SharedActiveOpenSocket {
 
         let remote_seq_num = header.seq_num + SeqNumber::from(1);
 
+        // Our peer agrees on using timestamps if it sent them in the SYN+ACK (see RFC 7323 Section 3.2).
+        let ts_recent: Option<u32> = get_timestamp_option(&header).map(|(sender_timestamp, _)| sender_timestamp);
+
         let mut tcp_hdr = TcpHeader::new(self.local.port(), self.remote.port());
         tcp_hdr.ack = true;
         tcp_hdr.ack_num = remote_seq_num;
         tcp_hdr.window_size = self.tcp_config.get_receive_window_size();
         tcp_hdr.seq_num = self.local_isn + SeqNumber::from(1);
+        if let Some(ts_recent) = ts_recent {
+            tcp_hdr.push_option(TcpOptions2::Timestamp {
+                sender_timestamp: self.timestamp_clock.get_timestamp(self.runtime.get_now()),
+                echo_timestamp: ts_recent,
+            });
+        }
         debug!("Sending ACK: {:?}", tcp_hdr);
 
-        let dst_ipv4_addr: Ipv4Addr = self.remote.ip().clone();
+        let src_ip_addr: IpAddr = self.local.ip();
+        let dst_ip_addr: IpAddr = self.remote.ip();
         let mut pkt: DemiBuffer = DemiBuffer::new_with_headroom(0, MAX_HEADER_SIZE as u16);
         tcp_hdr.serialize_and_attach(
             &mut pkt,
-            self.local.ip(),
-            self.remote.ip(),
+            &self.local.ip(),
+            &self.remote.ip(),
             self.tcp_config.get_rx_checksum_offload(),
         );
         self.layer3_endpoint
-            .transmit_tcp_packet_nonblocking(dst_ipv4_addr, pkt)?;
+            .transmit_tcp_packet_nonblocking(src_ip_addr, dst_ip_addr, pkt)?;
 
         let mut remote_window_scale = None;
         let mut mss = FALLBACK_MSS;
+        let mut sack_permitted: bool = false;
         for option in header.iter_options() {
             match option {
                 TcpOptions2::WindowScale(w) => {
@@ -186,6 +203,10 @@ impl SharedActiveOpenSocket {
                     info!("Received advertised MSS: {}", m);
                     mss = *m as usize;
                 },
+                TcpOptions2::SelectiveAcknowlegementPermitted => {
+                    info!("Received SACK permitted");
+                    sack_permitted = true;
+                },
                 _ => continue,
             }
         }
@@ -231,7 +252,6 @@ impl SharedActiveOpenSocket {
             self.runtime.clone(),
             self.layer3_endpoint.clone(),
             self.recv_queue.clone(),
-            self.ack_queue.clone(),
             self.tcp_config.clone(),
             self.socket_options,
             remote_seq_num,
@@ -242,7 +262,10 @@ impl SharedActiveOpenSocket {
             tx_window_size,
             remote_window_scale,
             mss,
-            congestion_control::None::new,
+            sack_permitted,
+            self.timestamp_clock,
+            ts_recent,
+            congestion_control::get_constructor(self.socket_options.get_congestion_control()),
             None,
             self.dead_socket_tx.clone(),
             None,
@@ -269,19 +292,30 @@ impl SharedActiveOpenSocket {
             tcp_hdr.push_option(TcpOptions2::WindowScale(self.tcp_config.get_window_scale()));
             info!("Advertising window scale: {}", self.tcp_config.get_window_scale());
 
+            tcp_hdr.push_option(TcpOptions2::SelectiveAcknowlegementPermitted);
+            info!("Advertising SACK permitted");
+
+            // The echoed timestamp is zero, as we did not receive any timestamp from our peer yet.
+            tcp_hdr.push_option(TcpOptions2::Timestamp {
+                sender_timestamp: self.timestamp_clock.get_timestamp(self.runtime.get_now()),
+                echo_timestamp: 0,
+            });
+            info!("Advertising timestamps");
+
             debug!("Sending SYN {:?}", tcp_hdr);
-            let dst_ipv4_addr: Ipv4Addr = self.remote.ip().clone();
+            let src_ip_addr: IpAddr = self.local.ip();
+            let dst_ip_addr: IpAddr = self.remote.ip();
             let mut pkt: DemiBuffer = DemiBuffer::new_with_headroom(0, MAX_HEADER_SIZE as u16);
             tcp_hdr.serialize_and_attach(
                 &mut pkt,
-                self.local.ip(),
-                self.remote.ip(),
+                &self.local.ip(),
+                &self.remote.ip(),
                 self.tcp_config.get_rx_checksum_offload(),
             );
             // Send SYN.
             if let Err(e) = self
                 .layer3_endpoint
-                .transmit_tcp_packet_blocking(dst_ipv4_addr, pkt)
+                .transmit_tcp_packet_blocking(src_ip_addr, dst_ip_addr, pkt)
                 .await
             {
                 warn!("Could not send SYN: {:?}", e);
@@ -289,7 +323,7 @@ impl SharedActiveOpenSocket {
             }
 
             // Wait for either a response or timeout.
-            let mut recv_queue: SharedAsyncQueue<(Ipv4Addr, TcpHeader, DemiBuffer)> = self.recv_queue.clone();
+            let mut recv_queue: SharedAsyncQueue<(IpAddr, TcpHeader, DemiBuffer)> = self.recv_queue.clone();
             let mut state: SharedAsyncValue<State> = self.state.clone();
             select_biased! {
             r = state.wait_for_change(None).fuse() => if let Ok(r) = r {
@@ -298,6 +332,11 @@ impl SharedActiveOpenSocket {
                     warn!("{}", cause);
                     return Err(Fail::new(libc::ECONNABORTED, &cause));
                 }
+                if let State::Unreachable(errno) = r {
+                    let cause: String = format!("remote is unreachable (remote={:?})", self.remote);
+                    error!("connect(): {}", cause);
+                    return Err(Fail::new(errno, &cause));
+                }
             },
             r = recv_queue.pop(Some(handshake_timeout)).fuse() => match r {
                 Ok((_, header, _)) => match self.process_ack(header) {
@@ -324,8 +363,19 @@ impl SharedActiveOpenSocket {
         self.state.set(State::Closed);
     }
 
+    /// Processes an ICMPv4 error about the segment starting at [seq_num] that we sent. Only errors about our SYN are
+    /// genuine (see RFC 5927 Section 4.1).
+    pub fn receive_icmpv4_error(&mut self, seq_num: SeqNumber, error: &Icmpv4Error) {
+        if seq_num != self.local_isn || self.state.get() != State::Connecting {
+            return;
+        }
+        if let Some(errno) = error.get_errno() {
+            self.state.set(State::Unreachable(errno));
+        }
+    }
+
     /// Returns the addresses of the two ends of this connection.
-    pub fn endpoints(&self) -> (SocketAddrV4, SocketAddrV4) {
+    pub fn endpoints(&self) -> (SocketAddr, SocketAddr) {
         (self.local, self.remote)
     }
 }
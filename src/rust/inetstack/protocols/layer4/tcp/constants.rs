@@ -8,4 +8,5 @@ pub use crate::runtime::network::consts::{
     MAX_WINDOW_SCALE,
     MIN_MSS,
     MSL,
+    PAWS_IDLE_TIMEOUT,
 };
@@ -17,7 +17,6 @@ pub async fn acknowledger(mut cb: SharedControlBlock) -> Result<Never, Fail> {
         // - TCP should implement a delayed ACK
         // - The delay must be less than 500ms
         // - For a stream of full-sized segments, there should be an ack for every other segment.
-        // TODO: Implement SACKs
         match ack_deadline.wait_for_change_until(deadline).await {
             Ok(value) => {
                 deadline = value;
@@ -0,0 +1,68 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+use crate::{
+    collections::async_value::SharedAsyncValue,
+    inetstack::protocols::layer4::tcp::established::ctrlblk::SharedControlBlock,
+    runtime::{
+        conditional_yield_until,
+        fail::Fail,
+    },
+};
+use ::futures::never::Never;
+use ::std::time::{
+    Duration,
+    Instant,
+};
+
+pub async fn keepalive(mut cb: SharedControlBlock) -> Result<Never, Fail> {
+    // Watch the keepalive timers, as they change whenever the SO_KEEPALIVE option is set on the socket.
+    let mut keepalive_timers_watched: SharedAsyncValue<Option<(Duration, Duration)>> = cb.watch_keepalive_timers();
+    // Number of probes that we have sent since we last heard from our peer.
+    let mut num_probes: u32 = 0;
+    let mut last_receive_time: Instant = cb.get_last_receive_time();
+    loop {
+        // RFC 1122 Section 4.2.3.6: Send the first probe once the connection has been idle for the keepalive time and
+        // the next ones at every keepalive interval until our peer answers.
+        let deadline: Option<Instant> = keepalive_timers_watched
+            .get()
+            .map(|(time, interval)| last_receive_time + time + interval * num_probes);
+
+        match conditional_yield_until(keepalive_timers_watched.wait_for_change_until(None), deadline).await {
+            Ok(_) => {
+                // Keepalive was reconfigured, so start over.
+                num_probes = 0;
+                last_receive_time = cb.get_last_receive_time();
+            },
+            Err(Fail { errno, cause: _ }) if errno == libc::ETIMEDOUT => {
+                // Check whether our peer answered in the meantime.
+                if cb.get_last_receive_time() != last_receive_time {
+                    num_probes = 0;
+                    last_receive_time = cb.get_last_receive_time();
+                    continue;
+                }
+
+                if num_probes as usize >= cb.get_keepalive_probes() {
+                    let cause: String = format!(
+                        "connection timed out after {} unanswered keepalive probes (local={:?}, remote={:?})",
+                        num_probes,
+                        cb.get_local(),
+                        cb.get_remote()
+                    );
+                    error!("keepalive(): {}", cause);
+                    let e: Fail = Fail::new(libc::ETIMEDOUT, &cause);
+                    cb.abort(e.clone());
+                    return Err(e);
+                }
+
+                cb.send_keepalive_probe();
+                num_probes += 1;
+            },
+            Err(_) => {
+                unreachable!(
+                    "either the keepalive timers changed or the deadline passed, no other errors are possible!"
+                )
+            },
+        }
+    }
+}
@@ -2,11 +2,13 @@
 // Licensed under the MIT license.
 
 mod acknowledger;
+mod keepalive;
 mod retransmitter;
 mod sender;
 
 use self::{
     acknowledger::acknowledger,
+    keepalive::keepalive,
     retransmitter::retransmitter,
     sender::sender,
 };
@@ -31,6 +33,9 @@ pub async fn background(cb: SharedControlBlock, _dead_socket_tx: mpsc::Unbounded
     let sender = async_timer!("tcp::established::background::sender", sender(cb.clone())).fuse();
     pin_mut!(sender);
 
+    let keepalive = async_timer!("tcp::established::background::keepalive", keepalive(cb.clone())).fuse();
+    pin_mut!(keepalive);
+
     let mut cb2: SharedControlBlock = cb.clone();
     let receiver = async_timer!("tcp::established::background::receiver", cb2.poll()).fuse();
     pin_mut!(receiver);
@@ -40,6 +45,7 @@ pub async fn background(cb: SharedControlBlock, _dead_socket_tx: mpsc::Unbounded
         r = acknowledger => r,
         r = retransmitter => r,
         r = sender => r,
+        r = keepalive => r,
     };
     error!("Connection terminated: {:?}", r);
 }
@@ -56,6 +56,10 @@ pub async fn retransmitter(mut cb: SharedControlBlock) -> Result<Never, Fail> {
                 let send_unacknowledged = cb.get_send_unacked();
                 cb.congestion_control_on_rto(send_unacknowledged.get());
 
+                // RFC 2018 Section 8: Our peer may have reneged on the data that it selectively acknowledged, so stop
+                // trusting the SACK scoreboard.
+                cb.clear_sack_scoreboard();
+
                 // RFC 6298 Section 5.4: Retransmit earliest unacknowledged segment.
                 cb.retransmit();
 
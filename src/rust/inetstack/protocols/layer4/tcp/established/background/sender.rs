@@ -7,6 +7,7 @@ use crate::{
     inetstack::protocols::layer4::tcp::{
         established::{
             ctrlblk::SharedControlBlock,
+            pacer::Pacer,
             sender::UnackedSegment,
         },
         header::TcpHeader,
@@ -16,6 +17,7 @@ use crate::{
         conditional_yield_until,
         fail::Fail,
         memory::DemiBuffer,
+        yield_with_timeout,
     },
 };
 use ::futures::{
@@ -31,6 +33,8 @@ use ::std::{
 };
 
 pub async fn sender(mut cb: SharedControlBlock) -> Result<Never, Fail> {
+    // Spreads segments out over time when they are paced, rather than sending a whole window at line rate.
+    let mut pacer: Pacer = Pacer::new(cb.get_mss(), cb.get_now());
     'top: loop {
         // First, check to see if there's any unsent data.
         // TODO: Change this to just look at the unsent queue to see if it is empty or not.
@@ -138,6 +142,17 @@ pub async fn sender(mut cb: SharedControlBlock) -> Result<Never, Fail> {
             cmp::min((win_sz - sent_data) as usize, cb.get_mss()),
             (effective_cwnd - sent_data) as usize,
         );
+
+        // Sleep until the pacer has enough tokens for this segment.  Things may change in the meantime, so start over
+        // afterwards.
+        if let Some(rate) = cb.get_pacing_rate() {
+            let segment_size: usize = cmp::min(max_size, next_buf_size);
+            if let Some(delay) = pacer.get_delay(rate, segment_size, cb.get_now()) {
+                yield_with_timeout(delay).await;
+                continue 'top;
+            }
+        }
+
         let (segment_data, do_push): (DemiBuffer, bool) = expect_some!(
             cb.pop_unsent_segment(max_size),
             "No unsent data with sequence number gap?"
@@ -164,6 +179,9 @@ pub async fn sender(mut cb: SharedControlBlock) -> Result<Never, Fail> {
         // Update SND.NXT.
         cb.modify_send_next(|s| s + SeqNumber::from(segment_data_len));
 
+        // Take this segment out of the pacer's token bucket.
+        pacer.consume(segment_data.len());
+
         // Put this segment on the unacknowledged list.
         let unacked_segment = UnackedSegment {
             bytes: segment_data,
@@ -0,0 +1,480 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+// This is a rate-based congestion controller in the style of BBR (see draft-cardwell-iccrg-bbr-congestion-control).
+// Rather than reacting to losses, it builds a model of the path out of two estimates: the bottleneck bandwidth, which
+// is the highest delivery rate that we measured over the last few round trips, and the minimum round-trip time over
+// the last few seconds.  It then paces segments out at a multiple of the bottleneck bandwidth and caps the data in
+// flight at a multiple of the bandwidth-delay product.  The multipliers depend on the phase that we are in:
+//
+// - STARTUP grows the sending rate exponentially until the bandwidth estimate stops growing.
+// - DRAIN gets rid of the queue that STARTUP built up at the bottleneck.
+// - PROBE_BW cycles through rates slightly above and below the bandwidth estimate, to find out if more is available.
+// - PROBE_RTT briefly shrinks cwnd, so that the queue empties and we can measure the round-trip time again.
+//
+// We measure the delivery rate once per round trip, which is coarser than the per-segment rate samples of BBR.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    collections::async_value::SharedAsyncValue,
+    inetstack::protocols::layer4::tcp::{
+        established::congestion_control::{
+            CongestionControl,
+            FastRetransmitRecovery,
+            LimitedTransmit,
+            Options,
+            Pacing,
+            SlowStartCongestionAvoidance,
+        },
+        SeqNumber,
+    },
+};
+use ::std::{
+    cmp::{
+        max,
+        min,
+    },
+    collections::VecDeque,
+    fmt::Debug,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BbrState {
+    Startup,
+    Drain,
+    ProbeBw,
+    ProbeRtt,
+}
+
+#[derive(Debug)]
+pub struct Bbr {
+    mss: u32,
+    cwnd: SharedAsyncValue<u32>, // Congestion window: Max number of bytes that may be in flight.
+    initial_cwnd: u32,           // The initial value of cwnd, which we use until we have a model of the path.
+    state: BbrState,             // The phase of the algorithm that we are in.
+    pacing_gain: f64,            // Multiplier of the bottleneck bandwidth that gives the pacing rate.
+    cwnd_gain: f64,              // Multiplier of the bandwidth-delay product that gives cwnd.
+
+    // Path Model.
+    btl_bw_samples: VecDeque<u64>, // Delivery rates (in bytes per second) of the last few round trips.
+    min_rtt: Option<(Duration, Instant)>, // The minimum round-trip time and the time at which we measured it.
+    full_bw: u64,                  // The bottleneck bandwidth when it last grew significantly during STARTUP.
+    full_bw_count: u32,            // The number of round trips since the bottleneck bandwidth last grew.
+    filled_pipe: bool,             // Whether STARTUP has found the bottleneck bandwidth.
+
+    // Round Trip Tracking.
+    delivered: u64,                      // The number of bytes that have been acknowledged so far.
+    bytes_in_flight: u32,                // The number of bytes in flight, as of the last ACK.
+    round_end: SeqNumber,                // The current round trip ends once this sequence number has been acknowledged.
+    round_ended: bool,                   // Whether the round trip that ended still needs a delivery rate sample.
+    round_start: Option<(Instant, u64)>, // The start time of the current round trip and the bytes delivered by then.
+
+    // PROBE_BW and PROBE_RTT State.
+    cycle_index: usize,              // The current entry of the PROBE_BW gain cycle.
+    cycle_start: Option<Instant>,    // The time at which we moved to the current entry of the gain cycle.
+    probe_rtt_done: Option<Instant>, // The time at which we may leave PROBE_RTT.
+
+    // Fast Retransmit State.
+    duplicate_ack_count: u32, // The number of consecutive duplicate ACKs we've received.
+    fast_retransmit_now: SharedAsyncValue<bool>, // Flag to cause the retransmitter to retransmit a segment now.
+
+    limited_transmit_cwnd_increase: SharedAsyncValue<u32>, // Always zero, as cwnd is not what limits the sending rate.
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl CongestionControl for Bbr {
+    fn new(mss: usize, seq_no: SeqNumber, _options: Option<Options>) -> Box<dyn CongestionControl> {
+        let mss: u32 = mss.try_into().unwrap();
+        // The initial value of cwnd is set according to RFC5681, section 3.1, page 7.
+        let initial_cwnd: u32 = match mss {
+            0..=1095 => 4 * mss,
+            1096..=2190 => 3 * mss,
+            _ => 2 * mss,
+        };
+
+        Box::new(Self {
+            mss,
+            cwnd: SharedAsyncValue::new(initial_cwnd),
+            initial_cwnd,
+            state: BbrState::Startup,
+            pacing_gain: Self::STARTUP_GAIN,
+            cwnd_gain: Self::STARTUP_GAIN,
+
+            btl_bw_samples: VecDeque::with_capacity(Self::BTL_BW_FILTER_LENGTH),
+            min_rtt: None,
+            full_bw: 0,
+            full_bw_count: 0,
+            filled_pipe: false,
+
+            delivered: 0,
+            bytes_in_flight: 0,
+            round_end: seq_no,
+            round_ended: false,
+            round_start: None,
+
+            cycle_index: 0,
+            cycle_start: None,
+            probe_rtt_done: None,
+
+            duplicate_ack_count: 0,
+            fast_retransmit_now: SharedAsyncValue::new(false),
+
+            limited_transmit_cwnd_increase: SharedAsyncValue::new(0),
+        })
+    }
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Bbr {
+    // Number of round trips over which we take the maximum delivery rate.
+    const BTL_BW_FILTER_LENGTH: usize = 10;
+    const DRAIN_GAIN: f64 = 1.0 / Self::STARTUP_GAIN;
+    const DUP_ACK_THRESHOLD: u32 = 3;
+    // Number of round trips without growth of the bottleneck bandwidth after which STARTUP considers the pipe full.
+    const FULL_BW_COUNT: u32 = 3;
+    // Growth of the bottleneck bandwidth below which a round trip counts towards FULL_BW_COUNT.
+    const FULL_BW_THRESHOLD: f64 = 1.25;
+    const MIN_CWND_SEGMENTS: u32 = 4;
+    const MIN_RTT_FILTER_LENGTH: Duration = Duration::from_secs(10);
+    const PROBE_BW_CWND_GAIN: f64 = 2.0;
+    const PROBE_BW_GAIN_CYCLE: [f64; 8] = [1.25, 0.75, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0];
+    const PROBE_RTT_DURATION: Duration = Duration::from_millis(200);
+    // Gain that doubles the sending rate every round trip (2/ln(2)).
+    const STARTUP_GAIN: f64 = 2.885;
+
+    /// Returns the bottleneck bandwidth estimate in bytes per second, if we have one.
+    fn get_btl_bw(&self) -> Option<u64> {
+        self.btl_bw_samples.iter().copied().max()
+    }
+
+    /// Returns the bandwidth-delay product in bytes, if we have a model of the path.
+    fn get_bdp(&self) -> Option<u32> {
+        let btl_bw: u64 = self.get_btl_bw()?;
+        let (min_rtt, _): (Duration, Instant) = self.min_rtt?;
+        let bdp: u128 = btl_bw as u128 * min_rtt.as_nanos() / 1_000_000_000;
+        Some(min(bdp, u32::MAX as u128) as u32)
+    }
+
+    /// Returns the value that cwnd should converge to.
+    fn get_target_cwnd(&self) -> u32 {
+        let target_cwnd: u32 = match self.get_bdp() {
+            Some(bdp) => (self.cwnd_gain * bdp as f64) as u32,
+            None => self.initial_cwnd,
+        };
+        max(target_cwnd, Self::MIN_CWND_SEGMENTS * self.mss)
+    }
+
+    fn update_cwnd(&mut self, bytes_acknowledged: u32) {
+        let cwnd: u32 = self.cwnd.get();
+        let target_cwnd: u32 = self.get_target_cwnd();
+        let mut new_cwnd: u32 = if self.filled_pipe {
+            min(cwnd.saturating_add(bytes_acknowledged), target_cwnd)
+        } else {
+            // Keep growing while looking for the bottleneck bandwidth.
+            cwnd.saturating_add(bytes_acknowledged)
+        };
+        new_cwnd = max(new_cwnd, Self::MIN_CWND_SEGMENTS * self.mss);
+        if self.state == BbrState::ProbeRtt {
+            new_cwnd = min(new_cwnd, Self::MIN_CWND_SEGMENTS * self.mss);
+        }
+        if new_cwnd != cwnd {
+            self.cwnd.set(new_cwnd);
+        }
+    }
+
+    fn update_min_rtt(&mut self, rtt: Duration, now: Instant) {
+        let expired: bool = match self.min_rtt {
+            Some((_, timestamp)) => now.saturating_duration_since(timestamp) > Self::MIN_RTT_FILTER_LENGTH,
+            None => true,
+        };
+        match self.min_rtt {
+            Some((min_rtt, _)) if rtt > min_rtt && !expired => (),
+            _ => self.min_rtt = Some((rtt, now)),
+        }
+
+        // Go and measure the round-trip time again if we have not seen a lower one in a while.
+        if expired && self.state != BbrState::ProbeRtt && self.round_start.is_some() {
+            self.enter_state(BbrState::ProbeRtt, now);
+        }
+    }
+
+    fn update_btl_bw(&mut self, now: Instant) {
+        if let Some((start_time, start_delivered)) = self.round_start {
+            let interval: Duration = now.saturating_duration_since(start_time);
+            if interval > Duration::ZERO {
+                let delivery_rate: u128 =
+                    (self.delivered - start_delivered) as u128 * 1_000_000_000 / interval.as_nanos();
+                if self.btl_bw_samples.len() == Self::BTL_BW_FILTER_LENGTH {
+                    self.btl_bw_samples.pop_front();
+                }
+                self.btl_bw_samples
+                    .push_back(min(delivery_rate, u64::MAX as u128) as u64);
+                self.check_full_pipe();
+            }
+        }
+        self.round_start = Some((now, self.delivered));
+    }
+
+    /// Checks whether STARTUP has found the bottleneck bandwidth, which is when it stops growing.
+    fn check_full_pipe(&mut self) {
+        if self.filled_pipe {
+            return;
+        }
+        let btl_bw: u64 = self.get_btl_bw().unwrap_or(0);
+        if btl_bw as f64 >= self.full_bw as f64 * Self::FULL_BW_THRESHOLD {
+            self.full_bw = btl_bw;
+            self.full_bw_count = 0;
+            return;
+        }
+        self.full_bw_count += 1;
+        self.filled_pipe = self.full_bw_count >= Self::FULL_BW_COUNT;
+    }
+
+    fn update_state(&mut self, now: Instant) {
+        match self.state {
+            BbrState::Startup if self.filled_pipe => self.enter_state(BbrState::Drain, now),
+            BbrState::Drain if self.bytes_in_flight <= self.get_bdp().unwrap_or(0) => {
+                self.enter_state(BbrState::ProbeBw, now)
+            },
+            BbrState::ProbeBw => {
+                // Move on to the next gain every minimum round-trip time.
+                let min_rtt: Duration = self.min_rtt.map(|(min_rtt, _)| min_rtt).unwrap_or_default();
+                if let Some(cycle_start) = self.cycle_start {
+                    if now.saturating_duration_since(cycle_start) > min_rtt {
+                        self.cycle_index = (self.cycle_index + 1) % Self::PROBE_BW_GAIN_CYCLE.len();
+                        self.cycle_start = Some(now);
+                        self.pacing_gain = Self::PROBE_BW_GAIN_CYCLE[self.cycle_index];
+                    }
+                }
+            },
+            BbrState::ProbeRtt => {
+                if let Some(probe_rtt_done) = self.probe_rtt_done {
+                    if now >= probe_rtt_done {
+                        let next_state: BbrState = if self.filled_pipe {
+                            BbrState::ProbeBw
+                        } else {
+                            BbrState::Startup
+                        };
+                        self.enter_state(next_state, now);
+                    }
+                }
+            },
+            _ => (),
+        }
+    }
+
+    fn enter_state(&mut self, state: BbrState, now: Instant) {
+        trace!("bbr: {:?} -> {:?}", self.state, state);
+        self.state = state;
+        match state {
+            BbrState::Startup => {
+                self.pacing_gain = Self::STARTUP_GAIN;
+                self.cwnd_gain = Self::STARTUP_GAIN;
+            },
+            BbrState::Drain => {
+                self.pacing_gain = Self::DRAIN_GAIN;
+                self.cwnd_gain = Self::STARTUP_GAIN;
+            },
+            BbrState::ProbeBw => {
+                // Start the cycle at a neutral gain rather than with a probe for more bandwidth.
+                self.cycle_index = 2;
+                self.cycle_start = Some(now);
+                self.pacing_gain = Self::PROBE_BW_GAIN_CYCLE[self.cycle_index];
+                self.cwnd_gain = Self::PROBE_BW_CWND_GAIN;
+            },
+            BbrState::ProbeRtt => {
+                self.pacing_gain = 1.0;
+                self.cwnd_gain = 1.0;
+                self.probe_rtt_done = Some(now + Self::PROBE_RTT_DURATION);
+                let probe_rtt_cwnd: u32 = min(self.cwnd.get(), Self::MIN_CWND_SEGMENTS * self.mss);
+                self.cwnd.set(probe_rtt_cwnd);
+            },
+        }
+    }
+}
+
+impl SlowStartCongestionAvoidance for Bbr {
+    fn get_cwnd(&self) -> SharedAsyncValue<u32> {
+        self.cwnd.clone()
+    }
+
+    fn on_ack_received(
+        &mut self,
+        _rto: Duration,
+        send_unacked: SeqNumber,
+        send_next: SeqNumber,
+        ack_seq_no: SeqNumber,
+    ) {
+        // Ignore old ACKs and ACKs for data that we have yet to send.
+        if ack_seq_no < send_unacked || ack_seq_no > send_next {
+            return;
+        }
+
+        if ack_seq_no == send_unacked {
+            // We do not back off on losses, but we still have to repair them.
+            if send_next != send_unacked {
+                self.duplicate_ack_count += 1;
+                if self.duplicate_ack_count == Self::DUP_ACK_THRESHOLD {
+                    self.fast_retransmit_now.set(true);
+                }
+            }
+            return;
+        }
+
+        let bytes_acknowledged: u32 = (ack_seq_no - send_unacked).into();
+        self.duplicate_ack_count = 0;
+        self.delivered += bytes_acknowledged as u64;
+        self.bytes_in_flight = (send_next - ack_seq_no).into();
+
+        // A round trip ends when the data that was sent at its start is acknowledged.
+        if ack_seq_no >= self.round_end {
+            self.round_end = send_next;
+            self.round_ended = true;
+        }
+
+        self.update_cwnd(bytes_acknowledged);
+    }
+
+    fn on_rto(&mut self, _send_unacked: SeqNumber) {
+        // Our model of the path still holds, so cwnd grows back to its target as soon as data is acknowledged again.
+        self.cwnd.set(self.mss);
+        self.duplicate_ack_count = 0;
+    }
+
+    fn on_rtt_sample(&mut self, rtt: Duration, now: Instant) {
+        self.update_min_rtt(rtt, now);
+        if self.round_ended {
+            self.round_ended = false;
+            self.update_btl_bw(now);
+        }
+        self.update_state(now);
+    }
+}
+
+impl FastRetransmitRecovery for Bbr {
+    fn get_duplicate_ack_count(&self) -> u32 {
+        self.duplicate_ack_count
+    }
+
+    fn get_retransmit_now_flag(&self) -> SharedAsyncValue<bool> {
+        self.fast_retransmit_now.clone()
+    }
+
+    fn on_fast_retransmit(&mut self) {
+        self.fast_retransmit_now.set_without_notify(false);
+    }
+}
+
+impl LimitedTransmit for Bbr {
+    fn get_limited_transmit_cwnd_increase(&self) -> SharedAsyncValue<u32> {
+        self.limited_transmit_cwnd_increase.clone()
+    }
+}
+
+impl Pacing for Bbr {
+    fn get_pacing_rate(&self) -> Option<u64> {
+        let rate: f64 = match self.get_btl_bw() {
+            Some(btl_bw) => self.pacing_gain * btl_bw as f64,
+            // Until we have measured the bandwidth, pace the initial window out over the round-trip time.
+            None => {
+                let (min_rtt, _): (Duration, Instant) = self.min_rtt?;
+                self.pacing_gain * self.initial_cwnd as f64 / min_rtt.as_secs_f64()
+            },
+        };
+        Some(max(rate as u64, 1))
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::inetstack::protocols::layer4::tcp::{
+        established::congestion_control::{
+            Bbr,
+            CongestionControl,
+        },
+        SeqNumber,
+    };
+    use ::anyhow::Result;
+    use ::std::time::{
+        Duration,
+        Instant,
+    };
+
+    const MSS: u32 = 1000;
+    const RTO: Duration = Duration::from_secs(1);
+    const RTT: Duration = Duration::from_millis(10);
+
+    // Test that BBR finds the bottleneck bandwidth and then paces at that rate.
+    #[test]
+    fn converge_to_bottleneck_bandwidth() -> Result<()> {
+        let mut cc: Box<dyn CongestionControl> = Bbr::new(MSS as usize, SeqNumber::from(1), None);
+        let mut now: Instant = Instant::now();
+        crate::ensure_eq!(cc.get_pacing_rate(), None);
+
+        // The path delivers 10 segments every round trip, regardless of how much we send.
+        let bytes_per_round: u32 = 10 * MSS;
+        let btl_bw: u64 = bytes_per_round as u64 * 1_000_000_000 / RTT.as_nanos() as u64;
+        let mut send_unacked: SeqNumber = SeqNumber::from(1);
+        for round in 0..4 {
+            let send_next: SeqNumber = send_unacked + SeqNumber::from(bytes_per_round);
+            cc.on_ack_received(RTO, send_unacked, send_next, send_next);
+            cc.on_rtt_sample(RTT, now);
+            send_unacked = send_next;
+            now += RTT;
+
+            // STARTUP paces well above the delivery rate.
+            if round > 0 {
+                crate::ensure_eq!(cc.get_pacing_rate(), Some((Bbr::STARTUP_GAIN * btl_bw as f64) as u64));
+            }
+        }
+
+        // Once the bandwidth stops growing, we drain the queue and then pace at the bottleneck bandwidth, with a cwnd
+        // of twice the bandwidth-delay product.
+        for _ in 0..3 {
+            let send_next: SeqNumber = send_unacked + SeqNumber::from(bytes_per_round);
+            cc.on_ack_received(RTO, send_unacked, send_next, send_next);
+            cc.on_rtt_sample(RTT, now);
+            send_unacked = send_next;
+            now += RTT;
+        }
+        crate::ensure_eq!(cc.get_pacing_rate(), Some(btl_bw));
+        crate::ensure_eq!(cc.get_cwnd().get(), 2 * bytes_per_round);
+
+        Ok(())
+    }
+
+    // Test that BBR recovers its cwnd after a retransmission timeout.
+    #[test]
+    fn recover_from_rto() -> Result<()> {
+        let mut cc: Box<dyn CongestionControl> = Bbr::new(MSS as usize, SeqNumber::from(1), None);
+        let initial_cwnd: u32 = cc.get_cwnd().get();
+        cc.on_rto(SeqNumber::from(1));
+        crate::ensure_eq!(cc.get_cwnd().get(), MSS);
+
+        cc.on_ack_received(RTO, SeqNumber::from(1), SeqNumber::from(1001), SeqNumber::from(1001));
+        crate::ensure_eq!(cc.get_cwnd().get(), initial_cwnd);
+
+        Ok(())
+    }
+}
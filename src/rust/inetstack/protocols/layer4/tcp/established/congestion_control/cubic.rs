@@ -21,6 +21,7 @@ use crate::{
             FastRetransmitRecovery,
             LimitedTransmit,
             Options,
+            Pacing,
             SlowStartCongestionAvoidance,
         },
         SeqNumber,
@@ -366,3 +367,5 @@ impl LimitedTransmit for Cubic {
         self.limited_transmit_cwnd_increase.clone()
     }
 }
+
+impl Pacing for Cubic {}
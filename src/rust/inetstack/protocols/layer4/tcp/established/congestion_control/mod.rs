@@ -1,21 +1,29 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
+mod bbr;
 mod cubic;
+mod newreno;
 mod none;
 mod options;
 
 use crate::{
     collections::async_value::SharedAsyncValue,
     inetstack::protocols::layer4::tcp::SeqNumber,
+    runtime::network::socket::option::CongestionControlAlgorithm,
 };
 use ::std::{
     fmt::Debug,
-    time::Duration,
+    time::{
+        Duration,
+        Instant,
+    },
 };
 
 pub use self::{
+    bbr::Bbr,
     cubic::Cubic,
+    newreno::NewReno,
     none::None,
     options::{
         OptionValue,
@@ -43,6 +51,9 @@ pub trait SlowStartCongestionAvoidance {
 
     // Called immediately before a segment is sent for the 1st time.
     fn on_send(&mut self, _rto: Duration, _num_sent_bytes: u32) {}
+
+    // Called whenever an acknowledgement yields a round-trip time sample.
+    fn on_rtt_sample(&mut self, _rtt: Duration, _now: Instant) {}
 }
 
 pub trait FastRetransmitRecovery
@@ -65,10 +76,32 @@ where
     fn get_limited_transmit_cwnd_increase(&self) -> SharedAsyncValue<u32>;
 }
 
-pub trait CongestionControl: SlowStartCongestionAvoidance + FastRetransmitRecovery + LimitedTransmit + Debug {
+pub trait Pacing
+where
+    Self: SlowStartCongestionAvoidance,
+{
+    // Rate (in bytes per second) at which new segments should be spread out, or None if they may go out in bursts.
+    fn get_pacing_rate(&self) -> Option<u64> {
+        Option::None
+    }
+}
+
+pub trait CongestionControl:
+    SlowStartCongestionAvoidance + FastRetransmitRecovery + LimitedTransmit + Pacing + Debug
+{
     fn new(mss: usize, seq_no: SeqNumber, options: Option<options::Options>) -> Box<dyn CongestionControl>
     where
         Self: Sized;
 }
 
 pub type CongestionControlConstructor = fn(usize, SeqNumber, Option<options::Options>) -> Box<dyn CongestionControl>;
+
+/// Returns the constructor of the congestion control [algorithm].
+pub fn get_constructor(algorithm: CongestionControlAlgorithm) -> CongestionControlConstructor {
+    match algorithm {
+        CongestionControlAlgorithm::None => None::new,
+        CongestionControlAlgorithm::Cubic => Cubic::new,
+        CongestionControlAlgorithm::NewReno => NewReno::new,
+        CongestionControlAlgorithm::Bbr => Bbr::new,
+    }
+}
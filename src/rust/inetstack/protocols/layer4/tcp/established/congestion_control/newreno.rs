@@ -0,0 +1,347 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+// This is an implementation of the NewReno modification to TCP's fast recovery algorithm (see RFC 6582), on top of the
+// standard slow start and congestion avoidance algorithms (see RFC 5681).  Unlike Reno, NewReno stays in fast recovery
+// until all of the data that was outstanding when it started has been acknowledged, and retransmits a segment for
+// every partial acknowledgement that it receives in the meantime.  This lets it repair multiple losses in a single
+// window of data without waiting for a retransmission timeout.
+//
+// Note that we keep `recover` as the sequence number that follows the highest one that we sent, which makes the
+// comparisons below line up with acknowledgement numbers.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    collections::async_value::SharedAsyncValue,
+    inetstack::protocols::layer4::tcp::{
+        established::congestion_control::{
+            CongestionControl,
+            FastRetransmitRecovery,
+            LimitedTransmit,
+            Options,
+            Pacing,
+            SlowStartCongestionAvoidance,
+        },
+        SeqNumber,
+    },
+};
+use ::std::{
+    cmp::{
+        max,
+        min,
+    },
+    fmt::Debug,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+#[derive(Debug)]
+pub struct NewReno {
+    mss: u32,
+    // Slow Start / Congestion Avoidance State.
+    cwnd: SharedAsyncValue<u32>, // Congestion window: Max number of bytes that may be in flight to prevent congestion.
+    initial_cwnd: u32,           // The initial value of cwnd, which gets used if the connection goes idle.
+    ssthresh: u32, // The size of cwnd at which we will change from using slow start to congestion avoidance.
+    bytes_acked: u32, // Bytes acknowledged since cwnd last grew during congestion avoidance (see RFC 3465).
+    last_send_time: Instant, // The moment at which we last sent data.
+    rto_at_last_send: Duration, // The RTO at the moment we last sent data.
+    send_next: SeqNumber, // The sequence number that follows the highest one that we sent, as of the last ACK.
+    retransmitting_after_rto: bool, // Whether we are retransmitting after an RTO and haven't been acknowledged yet.
+
+    // Fast Recovery / Fast Retransmit State.
+    duplicate_ack_count: u32, // The number of consecutive duplicate ACKs we've received.
+    fast_retransmit_now: SharedAsyncValue<bool>, // Flag to cause the retransmitter to retransmit a segment now.
+    in_fast_recovery: bool,   // Are we currently in the `fast recovery` algorithm.
+    recover: SeqNumber,       // We only enter fast recovery on dup ACKs that acknowledge at least up to this number.
+
+    limited_transmit_cwnd_increase: SharedAsyncValue<u32>, // The amount by which limited transmit increases cwnd.
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl CongestionControl for NewReno {
+    fn new(mss: usize, seq_no: SeqNumber, _options: Option<Options>) -> Box<dyn CongestionControl> {
+        let mss: u32 = mss.try_into().unwrap();
+        // The initial value of cwnd is set according to RFC5681, section 3.1, page 7.
+        let initial_cwnd: u32 = match mss {
+            0..=1095 => 4 * mss,
+            1096..=2190 => 3 * mss,
+            _ => 2 * mss,
+        };
+
+        Box::new(Self {
+            mss,
+            cwnd: SharedAsyncValue::new(initial_cwnd),
+            initial_cwnd,
+            ssthresh: u32::MAX, // According to RFC5681 ssthresh should be initialised 'arbitrarily high'.
+            bytes_acked: 0,
+            last_send_time: Instant::now(),
+            rto_at_last_send: Duration::new(1, 0), // The default RTO is 1 sec.
+            send_next: seq_no,
+            retransmitting_after_rto: false,
+
+            duplicate_ack_count: 0,
+            fast_retransmit_now: SharedAsyncValue::new(false),
+            in_fast_recovery: false,
+            recover: seq_no, // Recover set to initial send sequence number according to RFC6582.
+
+            limited_transmit_cwnd_increase: SharedAsyncValue::new(0),
+        })
+    }
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl NewReno {
+    const DUP_ACK_THRESHOLD: u32 = 3;
+
+    /// Computes the slow start threshold after a congestion event (see RFC 5681, equation 4).
+    fn reduced_ssthresh(&self, bytes_outstanding: u32) -> u32 {
+        max(bytes_outstanding / 2, 2 * self.mss)
+    }
+
+    fn on_dup_ack_received(&mut self, send_unacked: SeqNumber, send_next: SeqNumber, ack_seq_no: SeqNumber) {
+        self.duplicate_ack_count += 1;
+
+        if self.in_fast_recovery {
+            // RFC 6582 Section 3.2, step 3: Inflate cwnd for every segment that has left the network.
+            self.cwnd.modify(|c| c.saturating_add(self.mss));
+        } else if self.duplicate_ack_count < Self::DUP_ACK_THRESHOLD {
+            // RFC 3042: Send new data on the first two duplicate ACKs.
+            self.limited_transmit_cwnd_increase.modify(|ltci| ltci + self.mss);
+        } else if self.duplicate_ack_count == Self::DUP_ACK_THRESHOLD && ack_seq_no >= self.recover {
+            // RFC 6582 Section 3.2, step 2: Only start a new fast recovery if this ACK covers the data that was
+            // outstanding during the previous one, so we don't retransmit the same losses twice.
+            let bytes_outstanding: u32 = (send_next - send_unacked).into();
+            self.ssthresh = self.reduced_ssthresh(bytes_outstanding);
+            self.recover = send_next;
+            self.in_fast_recovery = true;
+            self.cwnd.set(self.ssthresh + Self::DUP_ACK_THRESHOLD * self.mss);
+            self.limited_transmit_cwnd_increase.set_without_notify(0);
+            self.fast_retransmit_now.set(true);
+        }
+    }
+
+    fn on_ack_received_fast_recovery(&mut self, send_unacked: SeqNumber, send_next: SeqNumber, ack_seq_no: SeqNumber) {
+        let bytes_acknowledged: u32 = (ack_seq_no - send_unacked).into();
+
+        if ack_seq_no >= self.recover {
+            // RFC 6582 Section 3.2, step 4: Full acknowledgement.  Deflate cwnd and leave fast recovery.
+            let bytes_outstanding: u32 = (send_next - ack_seq_no).into();
+            self.cwnd
+                .set(min(self.ssthresh, max(bytes_outstanding, self.mss) + self.mss));
+            self.in_fast_recovery = false;
+        } else {
+            // RFC 6582 Section 3.2, step 5: Partial acknowledgement.  Retransmit the next missing segment, and deflate
+            // cwnd by the amount of new data acknowledged, adding back one MSS if that amount was at least one MSS.
+            self.fast_retransmit_now.set(true);
+            let cwnd: u32 = self.cwnd.get().saturating_sub(bytes_acknowledged);
+            if bytes_acknowledged >= self.mss {
+                self.cwnd.set(cwnd + self.mss);
+            } else {
+                self.cwnd.set(cwnd);
+            }
+        }
+    }
+
+    fn on_ack_received_ss_ca(&mut self, send_unacked: SeqNumber, ack_seq_no: SeqNumber) {
+        let bytes_acknowledged: u32 = (ack_seq_no - send_unacked).into();
+        let cwnd: u32 = self.cwnd.get();
+
+        if cwnd < self.ssthresh {
+            // Slow start.
+            self.cwnd.set(cwnd.saturating_add(min(bytes_acknowledged, self.mss)));
+        } else {
+            // Congestion avoidance: Grow cwnd by one MSS for every cwnd worth of acknowledged data.
+            self.bytes_acked += bytes_acknowledged;
+            if self.bytes_acked >= cwnd {
+                self.bytes_acked -= cwnd;
+                self.cwnd.set(cwnd.saturating_add(self.mss));
+            }
+        }
+    }
+}
+
+impl SlowStartCongestionAvoidance for NewReno {
+    fn get_cwnd(&self) -> SharedAsyncValue<u32> {
+        self.cwnd.clone()
+    }
+
+    fn on_cwnd_check_before_send(&mut self) {
+        // RFC 5681 Section 4.1: Restart from a smaller window if the connection has been idle for more than an RTO.
+        let long_time_since_send: bool = Instant::now().duration_since(self.last_send_time) > self.rto_at_last_send;
+        if long_time_since_send {
+            let restart_window: u32 = min(self.initial_cwnd, self.cwnd.get());
+            self.cwnd.set(restart_window);
+            self.limited_transmit_cwnd_increase.set_without_notify(0);
+        }
+    }
+
+    fn on_send(&mut self, rto: Duration, num_bytes_sent: u32) {
+        self.last_send_time = Instant::now();
+        self.rto_at_last_send = rto;
+        let new_value: u32 = self.limited_transmit_cwnd_increase.get().saturating_sub(num_bytes_sent);
+        self.limited_transmit_cwnd_increase.set_without_notify(new_value);
+    }
+
+    fn on_ack_received(
+        &mut self,
+        _rto: Duration,
+        send_unacked: SeqNumber,
+        send_next: SeqNumber,
+        ack_seq_no: SeqNumber,
+    ) {
+        // Ignore old ACKs and ACKs for data that we have yet to send.
+        if ack_seq_no < send_unacked || ack_seq_no > send_next {
+            return;
+        }
+        self.send_next = send_next;
+
+        if ack_seq_no == send_unacked {
+            // A duplicate ACK only tells us something if we have data in flight.
+            if send_next != send_unacked {
+                self.on_dup_ack_received(send_unacked, send_next, ack_seq_no);
+            }
+        } else {
+            self.duplicate_ack_count = 0;
+            self.retransmitting_after_rto = false;
+            self.limited_transmit_cwnd_increase.set_without_notify(0);
+
+            if self.in_fast_recovery {
+                self.on_ack_received_fast_recovery(send_unacked, send_next, ack_seq_no);
+            } else {
+                self.on_ack_received_ss_ca(send_unacked, ack_seq_no);
+            }
+        }
+    }
+
+    fn on_rto(&mut self, send_unacked: SeqNumber) {
+        // RFC 5681 Section 3.1: Only shrink ssthresh on the first retransmission of a segment.
+        if !self.retransmitting_after_rto {
+            let bytes_outstanding: u32 = (self.send_next - send_unacked).into();
+            self.ssthresh = self.reduced_ssthresh(bytes_outstanding);
+            self.retransmitting_after_rto = true;
+        }
+        self.cwnd.set(self.mss);
+        self.bytes_acked = 0;
+
+        // RFC 6582 Section 3.2, step 4: Duplicate ACKs for the data that we are about to retransmit must not trigger
+        // fast recovery.
+        self.recover = self.send_next;
+        self.in_fast_recovery = false;
+        self.duplicate_ack_count = 0;
+    }
+}
+
+impl FastRetransmitRecovery for NewReno {
+    fn get_duplicate_ack_count(&self) -> u32 {
+        self.duplicate_ack_count
+    }
+
+    fn get_retransmit_now_flag(&self) -> SharedAsyncValue<bool> {
+        self.fast_retransmit_now.clone()
+    }
+
+    fn on_fast_retransmit(&mut self) {
+        self.fast_retransmit_now.set_without_notify(false);
+    }
+}
+
+impl LimitedTransmit for NewReno {
+    fn get_limited_transmit_cwnd_increase(&self) -> SharedAsyncValue<u32> {
+        self.limited_transmit_cwnd_increase.clone()
+    }
+}
+
+impl Pacing for NewReno {}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::inetstack::protocols::layer4::tcp::{
+        established::congestion_control::{
+            CongestionControl,
+            NewReno,
+        },
+        SeqNumber,
+    };
+    use ::anyhow::Result;
+    use ::std::time::Duration;
+
+    const MSS: u32 = 1000;
+    const RTO: Duration = Duration::from_secs(1);
+
+    // Test that NewReno stays in fast recovery across partial acknowledgements.
+    #[test]
+    fn fast_recovery() -> Result<()> {
+        let mut cc: Box<dyn CongestionControl> = NewReno::new(MSS as usize, SeqNumber::from(1), None);
+        crate::ensure_eq!(cc.get_cwnd().get(), 4 * MSS);
+
+        // Slow start grows cwnd by one MSS per ACK.
+        cc.on_ack_received(RTO, SeqNumber::from(1), SeqNumber::from(4001), SeqNumber::from(1001));
+        crate::ensure_eq!(cc.get_cwnd().get(), 5 * MSS);
+
+        // The first two duplicate ACKs let us send new data, and the third one starts fast recovery.
+        let (send_unacked, send_next): (SeqNumber, SeqNumber) = (SeqNumber::from(1001), SeqNumber::from(5001));
+        cc.on_ack_received(RTO, send_unacked, send_next, send_unacked);
+        cc.on_ack_received(RTO, send_unacked, send_next, send_unacked);
+        crate::ensure_eq!(cc.get_limited_transmit_cwnd_increase().get(), 2 * MSS);
+        crate::ensure_eq!(cc.get_retransmit_now_flag().get(), false);
+        cc.on_ack_received(RTO, send_unacked, send_next, send_unacked);
+        crate::ensure_eq!(cc.get_retransmit_now_flag().get(), true);
+        crate::ensure_eq!(cc.get_cwnd().get(), 2 * MSS + 3 * MSS);
+        cc.on_fast_retransmit();
+
+        // Further duplicate ACKs inflate cwnd.
+        cc.on_ack_received(RTO, send_unacked, send_next, send_unacked);
+        crate::ensure_eq!(cc.get_cwnd().get(), 6 * MSS);
+
+        // A partial acknowledgement retransmits the next hole without leaving fast recovery.
+        cc.on_ack_received(RTO, send_unacked, send_next, SeqNumber::from(2001));
+        crate::ensure_eq!(cc.get_retransmit_now_flag().get(), true);
+        crate::ensure_eq!(cc.get_cwnd().get(), 6 * MSS);
+        cc.on_fast_retransmit();
+
+        // A full acknowledgement deflates cwnd to ssthresh.
+        cc.on_ack_received(RTO, SeqNumber::from(2001), send_next, send_next);
+        crate::ensure_eq!(cc.get_retransmit_now_flag().get(), false);
+        crate::ensure_eq!(cc.get_cwnd().get(), 2 * MSS);
+
+        Ok(())
+    }
+
+    // Test that duplicate ACKs for data sent before an RTO do not start fast recovery.
+    #[test]
+    fn no_fast_recovery_after_rto() -> Result<()> {
+        let mut cc: Box<dyn CongestionControl> = NewReno::new(MSS as usize, SeqNumber::from(1), None);
+        let (send_unacked, send_next): (SeqNumber, SeqNumber) = (SeqNumber::from(1), SeqNumber::from(4001));
+        cc.on_ack_received(RTO, send_unacked, send_next, send_unacked);
+        cc.on_rto(send_unacked);
+        crate::ensure_eq!(cc.get_cwnd().get(), MSS);
+
+        // Retransmitting the first segment fills the hole, but our peer still holds on to the segments after it.
+        cc.on_ack_received(RTO, send_unacked, send_next, SeqNumber::from(1001));
+        for _ in 0..3 {
+            cc.on_ack_received(RTO, SeqNumber::from(1001), send_next, SeqNumber::from(1001));
+        }
+        crate::ensure_eq!(cc.get_retransmit_now_flag().get(), false);
+
+        Ok(())
+    }
+}
@@ -13,6 +13,7 @@ use crate::{
             FastRetransmitRecovery,
             LimitedTransmit,
             Options,
+            Pacing,
             SlowStartCongestionAvoidance,
         },
         SeqNumber,
@@ -61,3 +62,4 @@ impl LimitedTransmit for None {
         self.limited_retransmit_cwnd_increase.clone()
     }
 }
+impl Pacing for None {}
@@ -15,9 +15,17 @@ use crate::{
     },
     expect_ok,
     inetstack::protocols::{
-        layer3::SharedLayer3Endpoint,
+        layer3::{
+            icmpv4::Icmpv4Error,
+            ipv4::IPV4_HEADER_MIN_SIZE,
+            SharedLayer3Endpoint,
+        },
         layer4::tcp::{
-            constants::MSL,
+            constants::{
+                MIN_MSS,
+                MSL,
+                PAWS_IDLE_TIMEOUT,
+            },
             established::{
                 congestion_control::{
                     self,
@@ -29,7 +37,19 @@ use crate::{
                     UnackedSegment,
                 },
             },
-            header::TcpHeader,
+            header::{
+                SelectiveAcknowlegement,
+                TcpHeader,
+                TcpOptions2,
+                MAX_SACK_BLOCKS,
+                MIN_TCP_HEADER_SIZE,
+            },
+            timestamp::{
+                get_timestamp_option,
+                timestamp_lt,
+                TimestampClock,
+                TIMESTAMP_OPTION_SIZE,
+            },
             SeqNumber,
         },
         MAX_HEADER_SIZE,
@@ -49,8 +69,9 @@ use crate::{
 use ::std::{
     collections::VecDeque,
     net::{
-        Ipv4Addr,
-        SocketAddrV4,
+        IpAddr,
+        Shutdown,
+        SocketAddr,
     },
     ops::{
         Deref,
@@ -61,7 +82,11 @@ use ::std::{
         Instant,
     },
 };
-use futures::never::Never;
+use futures::{
+    never::Never,
+    select_biased,
+    FutureExt,
+};
 
 //======================================================================================================================
 // Constants
@@ -138,15 +163,17 @@ impl Receiver {
 
     pub async fn pop(&mut self, size: Option<usize>) -> Result<DemiBuffer, Fail> {
         let buf: DemiBuffer = if let Some(size) = size {
-            let mut buf: DemiBuffer = self.recv_queue.pop(None).await?;
-            // Split the buffer if it's too big.
+            let mut buf: DemiBuffer = self.wait_for_data().await?;
+            // Split the buffer if it's too big, and leave the rest for the next read.
             if buf.len() > size {
-                buf.split_front(size)?
+                let front: DemiBuffer = buf.split_front(size)?;
+                self.recv_queue.push_front(buf);
+                front
             } else {
                 buf
             }
         } else {
-            self.recv_queue.pop(None).await?
+            self.wait_for_data().await?
         };
 
         self.reader_next = self.reader_next + SeqNumber::from(buf.len() as u32);
@@ -154,6 +181,17 @@ impl Receiver {
         Ok(buf)
     }
 
+    /// Waits until there is data in the receive queue. Idle connections are detected through keepalive, so we never
+    /// give up waiting here.
+    async fn wait_for_data(&mut self) -> Result<DemiBuffer, Fail> {
+        loop {
+            match self.recv_queue.pop(None).await {
+                Err(e) if e.errno == libc::ETIMEDOUT => continue,
+                result => return result,
+            }
+        }
+    }
+
     pub fn push(&mut self, buf: DemiBuffer) {
         let buf_len: u32 = buf.len() as u32;
         self.recv_queue.push(buf);
@@ -168,8 +206,8 @@ impl Receiver {
 /// Transmission control block for representing our TCP connection.
 // TODO: Make all public fields in this structure private.
 pub struct ControlBlock {
-    local: SocketAddrV4,
-    remote: SocketAddrV4,
+    local: SocketAddr,
+    remote: SocketAddr,
 
     layer3_endpoint: SharedLayer3Endpoint,
     #[allow(unused)]
@@ -204,6 +242,23 @@ pub struct ControlBlock {
     //
     out_of_order: VecDeque<(SeqNumber, DemiBuffer)>,
 
+    // The sequence number of the most recently stored out-of-order segment.  RFC 2018 requires the SACK block that
+    // contains this segment to be reported first.
+    last_out_of_order_seq_no: Option<SeqNumber>,
+
+    // Whether both ends agreed on using selective acknowledgements during the handshake.
+    sack_permitted: bool,
+
+    // Clock for the timestamps that we send to our peer.
+    timestamp_clock: TimestampClock,
+
+    // Most recent timestamp received from our peer (TS.Recent) and the time at which we stored it, or None if both ends
+    // did not agree on using timestamps during the handshake (see RFC 7323 Section 4.3).
+    ts_recent: Option<(u32, Instant)>,
+
+    // Acknowledgement number of the last segment that we sent (Last.ACK.sent in RFC 7323).
+    last_ack_sent: SeqNumber,
+
     // The sequence number of the FIN, if we received it out-of-order.
     // Note: This could just be a boolean to remember if we got a FIN; the sequence number is for checking correctness.
     pub out_of_order_fin: Option<SeqNumber>,
@@ -222,11 +277,26 @@ pub struct ControlBlock {
     // Retransmission Timeout (RTO) calculator.
     rto_calculator: RtoCalculator,
 
+    // Time at which we last received a segment from our peer.  Used to decide when to send keepalive probes.
+    last_receive_time: Instant,
+
+    // Idle time and probe interval for keepalive, or None if keepalive is disabled on this connection.
+    keepalive_timers: SharedAsyncValue<Option<(Duration, Duration)>>,
+
+    // Error that aborted this connection, if any.  Pending and future operations on the connection fail with it.
+    connection_error: SharedAsyncValue<Option<Fail>>,
+
+    // Whether the application shut down sending while it still receives on this connection.  The receive loop keeps
+    // processing segments in FIN-WAIT-1 and FIN-WAIT-2 until the application closes the connection.
+    half_closed: bool,
+
+    // Whether the application shut down receiving on this connection.  Pops see the end of file from then on.
+    receive_shutdown: bool,
+
     // Incoming packets for this connection.
-    recv_queue: SharedAsyncQueue<(Ipv4Addr, TcpHeader, DemiBuffer)>,
+    recv_queue: SharedAsyncQueue<(IpAddr, TcpHeader, DemiBuffer)>,
 
-    ack_queue: SharedAsyncQueue<usize>,
-    socket_queue: Option<SharedAsyncQueue<SocketAddrV4>>,
+    socket_queue: Option<SharedAsyncQueue<SocketAddr>>,
 }
 
 #[derive(Clone)]
@@ -235,8 +305,8 @@ pub struct SharedControlBlock(SharedObject<ControlBlock>);
 
 impl SharedControlBlock {
     pub fn new(
-        local: SocketAddrV4,
-        remote: SocketAddrV4,
+        local: SocketAddr,
+        remote: SocketAddr,
         runtime: SharedDemiRuntime,
         layer3_endpoint: SharedLayer3Endpoint,
         tcp_config: TcpConfig,
@@ -249,13 +319,22 @@ impl SharedControlBlock {
         sender_window_size: u32,
         sender_window_scale: u8,
         sender_mss: usize,
+        sack_permitted: bool,
+        timestamp_clock: TimestampClock,
+        ts_recent: Option<u32>,
         cc_constructor: CongestionControlConstructor,
         congestion_control_options: Option<congestion_control::Options>,
-        recv_queue: SharedAsyncQueue<(Ipv4Addr, TcpHeader, DemiBuffer)>,
-        ack_queue: SharedAsyncQueue<usize>,
-        socket_queue: Option<SharedAsyncQueue<SocketAddrV4>>,
+        recv_queue: SharedAsyncQueue<(IpAddr, TcpHeader, DemiBuffer)>,
+        socket_queue: Option<SharedAsyncQueue<SocketAddr>>,
     ) -> Self {
+        // The timestamps option takes up some of the space of every segment (see RFC 7323 Section 4.2).
+        let sender_mss: usize = match ts_recent {
+            Some(_) if sender_mss > TIMESTAMP_OPTION_SIZE => sender_mss - TIMESTAMP_OPTION_SIZE,
+            _ => sender_mss,
+        };
         let sender: Sender = Sender::new(sender_seq_no, sender_window_size, sender_window_scale, sender_mss);
+        let last_receive_time: Instant = runtime.get_now();
+        let keepalive_timers: Option<(Duration, Duration)> = default_socket_options.get_keepalive_timers(&tcp_config);
         Self(SharedObject::<ControlBlock>::new(ControlBlock {
             local,
             remote,
@@ -270,34 +349,54 @@ impl SharedControlBlock {
             receive_buffer_size: receiver_window_size,
             window_scale: receiver_window_scale,
             out_of_order: VecDeque::new(),
+            last_out_of_order_seq_no: None,
+            sack_permitted,
+            timestamp_clock,
+            ts_recent: ts_recent.map(|ts_recent| (ts_recent, last_receive_time)),
+            last_ack_sent: receiver_seq_no,
             out_of_order_fin: Option::None,
             receiver: Receiver::new(receiver_seq_no, receiver_seq_no),
             cc: cc_constructor(sender_mss, sender_seq_no, congestion_control_options),
             retransmit_deadline: SharedAsyncValue::new(None),
             rto_calculator: RtoCalculator::new(),
+            last_receive_time,
+            keepalive_timers: SharedAsyncValue::new(keepalive_timers),
+            connection_error: SharedAsyncValue::new(None),
+            half_closed: false,
+            receive_shutdown: false,
             recv_queue,
-            ack_queue,
             socket_queue,
         }))
     }
 
-    pub fn get_local(&self) -> SocketAddrV4 {
+    pub fn get_local(&self) -> SocketAddr {
         self.local
     }
 
-    pub fn get_remote(&self) -> SocketAddrV4 {
+    pub fn get_remote(&self) -> SocketAddr {
         self.remote
     }
 
-    pub fn send(&mut self, buf: DemiBuffer) -> Result<(), Fail> {
+    /// Sends [buf] and returns the sequence number that follows its last byte, which tells when it is acknowledged.
+    pub fn send(&mut self, buf: DemiBuffer) -> Result<SeqNumber, Fail> {
+        if self.half_closed {
+            let cause: &str = "cannot send after shutting down sending";
+            warn!("send(): {}", cause);
+            return Err(Fail::new(libc::EPIPE, cause));
+        }
         let self_: Self = self.clone();
-        self.sender.send(buf, self_)
+        self.sender.send(buf, self_)?;
+        Ok(self.sender.get_unsent_seq_no().get())
     }
 
     pub fn retransmit(&self) {
         self.sender.retransmit(self.clone())
     }
 
+    pub fn clear_sack_scoreboard(&self) {
+        self.sender.clear_sack_scoreboard()
+    }
+
     pub fn congestion_control_watch_retransmit_now_flag(&self) -> SharedAsyncValue<bool> {
         self.cc.get_retransmit_now_flag()
     }
@@ -326,6 +425,15 @@ impl SharedControlBlock {
         self.cc.get_limited_transmit_cwnd_increase()
     }
 
+    /// Returns the rate (in bytes per second) at which to pace segments out, which is the one that congestion control
+    /// asks for capped by the SO_MAX_PACING_RATE option, or None if segments may go out back to back.
+    pub fn get_pacing_rate(&self) -> Option<u64> {
+        match (self.cc.get_pacing_rate(), self.socket_options.get_max_pacing_rate()) {
+            (Some(rate), Some(max_rate)) => Some(rate.min(max_rate)),
+            (rate, max_rate) => rate.or(max_rate),
+        }
+    }
+
     pub fn get_mss(&self) -> usize {
         self.sender.get_mss()
     }
@@ -367,7 +475,9 @@ impl SharedControlBlock {
     }
 
     pub fn rto_add_sample(&mut self, rtt: Duration) {
-        self.rto_calculator.add_sample(rtt)
+        self.rto_calculator.add_sample(rtt);
+        let now: Instant = self.get_now();
+        self.cc.on_rtt_sample(rtt, now);
     }
 
     pub fn rto(&self) -> Duration {
@@ -394,8 +504,29 @@ impl SharedControlBlock {
         self.runtime.get_now()
     }
 
-    pub fn receive(&mut self, remote_ipv4_addr: Ipv4Addr, tcp_hdr: TcpHeader, buf: DemiBuffer) {
-        self.recv_queue.push((remote_ipv4_addr, tcp_hdr, buf));
+    pub fn receive(&mut self, remote_ip_addr: IpAddr, tcp_hdr: TcpHeader, buf: DemiBuffer) {
+        self.recv_queue.push((remote_ip_addr, tcp_hdr, buf));
+    }
+
+    pub fn get_last_receive_time(&self) -> Instant {
+        self.last_receive_time
+    }
+
+    pub fn watch_keepalive_timers(&self) -> SharedAsyncValue<Option<(Duration, Duration)>> {
+        self.keepalive_timers.clone()
+    }
+
+    pub fn get_keepalive_probes(&self) -> usize {
+        self.tcp_config.get_keepalive_probes()
+    }
+
+    /// Updates the SO_* options of this connection.
+    pub fn set_socket_options(&mut self, socket_options: TcpSocketOptions) {
+        let keepalive_timers: Option<(Duration, Duration)> = socket_options.get_keepalive_timers(&self.tcp_config);
+        if keepalive_timers != self.keepalive_timers.get() {
+            self.keepalive_timers.set(keepalive_timers);
+        }
+        self.socket_options = socket_options;
     }
 
     // This is the main TCP processing routine.
@@ -403,7 +534,7 @@ impl SharedControlBlock {
         // Normal data processing in the Established state.
         loop {
             let (header, data): (TcpHeader, DemiBuffer) = match self.recv_queue.pop(None).await {
-                Ok((_, header, data)) if self.state == State::Established => (header, data),
+                Ok((_, header, data)) if self.is_receiving() => (header, data),
                 Ok(result) => {
                     self.recv_queue.push_front(result);
                     let cause: String = format!(
@@ -413,6 +544,8 @@ impl SharedControlBlock {
                     error!("poll(): {}", cause);
                     return Err(Fail::new(libc::ECANCELED, &cause));
                 },
+                // The connection is idle.  Keepalive takes care of detecting dead peers, so keep waiting.
+                Err(e) if e.errno == libc::ETIMEDOUT => continue,
                 Err(e) => {
                     let cause: String = format!(
                         "ending receive polling loop for active connection (local={:?}, remote={:?})",
@@ -430,13 +563,21 @@ impl SharedControlBlock {
                 header
             );
 
+            // Any segment from our peer shows that the connection is still alive.
+            self.last_receive_time = self.get_now();
+
             match self.process_packet(header, data) {
-                Ok(()) => (),
+                Ok(()) => self.update_fin_wait_state(),
                 Err(e) if e.errno == libc::ECONNRESET => {
                     if let Some(mut socket_tx) = self.socket_queue.take() {
                         socket_tx.push(self.remote);
                     }
-                    self.state = State::CloseWait;
+                    self.update_fin_wait_state();
+                    self.state = match self.state {
+                        State::FinWait1 => State::Closing,
+                        State::FinWait2 => State::TimeWait,
+                        _ => State::CloseWait,
+                    };
                     let cause: String = format!(
                         "remote closed connection, stopping processing (local={:?}, remote={:?})",
                         self.local, self.remote
@@ -449,6 +590,26 @@ impl SharedControlBlock {
         }
     }
 
+    /// Returns whether the receive loop processes incoming segments. It does so until the connection closes, or until
+    /// our peer closes its side if the application shut down sending before.
+    fn is_receiving(&self) -> bool {
+        match self.state {
+            State::Established => true,
+            State::FinWait1 | State::FinWait2 => self.half_closed,
+            _ => false,
+        }
+    }
+
+    /// Moves from FIN-WAIT-1 to FIN-WAIT-2 once our peer acknowledged our FIN, which is the last thing that we send.
+    fn update_fin_wait_state(&mut self) {
+        if self.state == State::FinWait1
+            && self.sender.top_size_unsent().is_none()
+            && self.sender.get_send_unacked().get() == self.sender.get_send_next().get()
+        {
+            self.state = State::FinWait2;
+        }
+    }
+
     /// This is the main function for processing an incoming packet during the Established state when the connection is
     /// active. Each step in this function return Ok if there is further processing to be done and EBADMSG if the
     /// packet should be dropped after the step.
@@ -458,8 +619,11 @@ impl SharedControlBlock {
         let mut seg_end: SeqNumber = seg_start;
         let mut seg_len: u32 = data.len() as u32;
 
-        // Check if the segment is in the receive window and trim off everything else.
+        // Check for old duplicate segments, then check if the segment is in the receive window and trim off everything
+        // else.
+        self.check_timestamp(&header)?;
         self.check_segment_in_window(&mut header, &mut data, &mut seg_start, &mut seg_end, &mut seg_len)?;
+        self.update_ts_recent(&header);
         self.check_rst(&header)?;
         self.check_syn(&header)?;
         self.process_ack(&header)?;
@@ -618,6 +782,50 @@ impl SharedControlBlock {
         Ok(())
     }
 
+    // Protection Against Wrapped Sequences (PAWS): drop segments that carry a timestamp older than the most recent one
+    // that we received, as they are old duplicates (see RFC 7323 Section 5.3).  Unacceptable segments should be ACK'd
+    // (unless they are RSTs), and then dropped.
+    // Returns Ok if further processing is needed and EBADMSG if the packet is an old duplicate.
+    fn check_timestamp(&mut self, header: &TcpHeader) -> Result<(), Fail> {
+        // RSTs are acceptable regardless of their timestamp.  We also accept segments without timestamps.
+        let (ts_recent, ts_recent_age): (u32, Instant) = match self.ts_recent {
+            Some(ts_recent) if !header.rst => ts_recent,
+            _ => return Ok(()),
+        };
+        let sender_timestamp: u32 = match get_timestamp_option(header) {
+            Some((sender_timestamp, _)) => sender_timestamp,
+            None => return Ok(()),
+        };
+
+        if timestamp_lt(sender_timestamp, ts_recent) {
+            // TS.Recent is no longer valid once the connection has been idle for too long (see RFC 7323 Section 5.5).
+            if self.get_now() - ts_recent_age > PAWS_IDLE_TIMEOUT {
+                return Ok(());
+            }
+            trace!("check_timestamp(): send ack on old duplicate segment");
+            self.send_ack();
+            let cause: String = format!(
+                "old duplicate packet (TSval={}, TS.Recent={})",
+                sender_timestamp, ts_recent
+            );
+            error!("check_timestamp(): {}", cause);
+            return Err(Fail::new(libc::EBADMSG, &cause));
+        }
+        Ok(())
+    }
+
+    // Remember the timestamp of a segment that passed the PAWS and receive window checks, so that we echo it back to
+    // our peer.  We only remember the timestamp if the segment covers the last acknowledgement that we sent, so that we
+    // echo the timestamp of the oldest unacknowledged segment (see RFC 7323 Section 4.3).
+    fn update_ts_recent(&mut self, header: &TcpHeader) {
+        if self.ts_recent.is_none() || self.last_ack_sent < header.seq_num {
+            return;
+        }
+        if let Some((sender_timestamp, _)) = get_timestamp_option(header) {
+            self.ts_recent = Some((sender_timestamp, self.get_now()));
+        }
+    }
+
     // Check the RST bit.
     fn check_rst(&mut self, header: &TcpHeader) -> Result<(), Fail> {
         if header.rst {
@@ -690,9 +898,19 @@ impl SharedControlBlock {
                 // This segment acknowledges new data (possibly and/or FIN).
                 let bytes_acknowledged: u32 = (header.ack_num - send_unacknowledged).into();
 
+                // Take an RTT sample from the timestamp that our peer echoed.  Unlike the initial transmit time, this
+                // also works for retransmitted segments (see RFC 7323 Section 4.1).
+                let timestamp_rtt_sampled: bool = match self.get_timestamp_rtt_sample(header, now) {
+                    Some(rtt) => {
+                        self.rto_add_sample(rtt);
+                        true
+                    },
+                    None => false,
+                };
+
                 // Remove the now acknowledged data from the unacknowledged queue.
                 self.sender
-                    .remove_acknowledged_data(self.clone(), bytes_acknowledged, now);
+                    .remove_acknowledged_data(self.clone(), bytes_acknowledged, now, timestamp_rtt_sampled);
 
                 // Update SND.UNA to SEG.ACK.
                 self.sender.send_unacked.set(header.ack_num);
@@ -700,6 +918,11 @@ impl SharedControlBlock {
                 // Update our send window (SND.WND).
                 self.sender.update_send_window(&header);
 
+                // Update the SACK scoreboard, which also forgets about the now acknowledged data.
+                if self.sack_permitted {
+                    self.sender.update_sack_scoreboard(header);
+                }
+
                 if header.ack_num == send_next {
                     // This segment acknowledges everything we've sent so far (i.e. nothing is currently outstanding).
 
@@ -712,9 +935,6 @@ impl SharedControlBlock {
                     let deadline: Instant = now + self.rto_calculator.rto();
                     self.retransmit_deadline.set(Some(deadline));
                 }
-
-                let nbytes: usize = Into::<u32>::into(header.ack_num - send_unacknowledged) as usize;
-                self.ack_queue.push(nbytes);
             } else {
                 // This segment acknowledges data we have yet to send!?  Send an ACK and drop the segment.
                 // TODO: See RFC 5961, this could be a Blind Data Injection Attack.
@@ -727,10 +947,28 @@ impl SharedControlBlock {
             // Duplicate ACK (doesn't acknowledge anything new).  We can mostly ignore this, except for fast-retransmit.
             // TODO: Implement fast-retransmit.  In which case, we'd increment our dup-ack counter here.
             warn!("process_ack(): received duplicate ack ({:?})", header.ack_num);
+
+            // Duplicate ACKs carry SACK blocks for the out-of-order data that our peer has received.
+            if self.sack_permitted {
+                self.sender.update_sack_scoreboard(header);
+            }
         }
         Ok(())
     }
 
+    /// Computes the round-trip time from the TSecr field of [header], if timestamps are in use on this connection.
+    fn get_timestamp_rtt_sample(&self, header: &TcpHeader, now: Instant) -> Option<Duration> {
+        // Timestamps are only meaningful if both ends agreed on using them.
+        self.ts_recent?;
+        match get_timestamp_option(header) {
+            // A zero TSecr means that our peer did not echo any timestamp, as our timestamp clock starts at one.
+            Some((_, echo_timestamp)) if echo_timestamp != 0 => {
+                self.timestamp_clock.get_round_trip_time(now, echo_timestamp)
+            },
+            _ => None,
+        }
+    }
+
     fn process_data(
         &mut self,
         header: &mut TcpHeader,
@@ -784,10 +1022,57 @@ impl SharedControlBlock {
         header.ack = true;
         header.ack_num = self.receiver.receive_next;
 
+        // Stamp every segment with our clock and echo the most recent timestamp of our peer (see RFC 7323 Section 3).
+        if let Some((ts_recent, _)) = self.ts_recent {
+            header.push_option(TcpOptions2::Timestamp {
+                sender_timestamp: self.timestamp_clock.get_timestamp(self.get_now()),
+                echo_timestamp: ts_recent,
+            });
+        }
+
+        // Tell our peer about any out-of-order data that we are holding onto.
+        if self.sack_permitted && !self.out_of_order.is_empty() {
+            header.push_option(self.sack_option());
+        }
+
         // Return this header.
         header
     }
 
+    /// Builds a SACK option that reports the data in the out-of-order store. Adjacent segments are coalesced into a
+    /// single block, and the block that contains the most recently received segment goes first (see RFC 2018 Section
+    /// 4). When timestamps are in use, only three blocks fit next to the timestamps option.
+    fn sack_option(&self) -> TcpOptions2 {
+        let mut blocks: Vec<SelectiveAcknowlegement> = Vec::with_capacity(self.out_of_order.len());
+        for (start, buf) in self.out_of_order.iter() {
+            let end: SeqNumber = *start + SeqNumber::from(buf.len() as u32);
+            match blocks.last_mut() {
+                Some(block) if block.end >= *start => block.end = end,
+                _ => blocks.push(SelectiveAcknowlegement { begin: *start, end }),
+            }
+        }
+
+        if let Some(recent) = self.last_out_of_order_seq_no {
+            if let Some(index) = blocks
+                .iter()
+                .position(|block| block.begin <= recent && recent < block.end)
+            {
+                let block: SelectiveAcknowlegement = blocks.remove(index);
+                blocks.insert(0, block);
+            }
+        }
+
+        let max_sacks: usize = match self.ts_recent {
+            Some(_) => MAX_SACK_BLOCKS - 1,
+            None => MAX_SACK_BLOCKS,
+        };
+        let num_sacks: usize = blocks.len().min(max_sacks);
+        let mut sacks: [SelectiveAcknowlegement; MAX_SACK_BLOCKS] =
+            [SelectiveAcknowlegement::default(); MAX_SACK_BLOCKS];
+        sacks[..num_sacks].copy_from_slice(&blocks[..num_sacks]);
+        TcpOptions2::SelectiveAcknowlegement { num_sacks, sacks }
+    }
+
     /// Send an ACK to our peer, reflecting our current state.
     pub fn send_ack(&mut self) {
         trace!("sending ack");
@@ -799,6 +1084,69 @@ impl SharedControlBlock {
         self.emit(header, None);
     }
 
+    /// Send a keepalive probe to our peer. The probe carries an old sequence number, so our peer answers it with an
+    /// ACK (see RFC 1122 Section 4.2.3.6).
+    pub fn send_keepalive_probe(&mut self) {
+        trace!("sending keepalive probe");
+        let mut header: TcpHeader = self.tcp_header();
+        header.seq_num = self.get_send_next().get() - SeqNumber::from(1);
+        self.emit(header, None);
+    }
+
+    /// Shuts the connection down hard by sending a RST to our peer. Pending and future operations on the connection
+    /// fail with [cause].
+    pub fn abort(&mut self, cause: Fail) {
+        let mut header: TcpHeader = self.tcp_header();
+        header.seq_num = self.get_send_next().get();
+        header.rst = true;
+        self.emit(header, None);
+        self.fail(cause);
+    }
+
+    /// Shuts the connection down without telling our peer. Pending and future operations on the connection fail with
+    /// [cause].
+    fn fail(&mut self, cause: Fail) {
+        self.state = State::Closed;
+        self.set_retransmit_deadline(None);
+        if let Some(mut socket_tx) = self.socket_queue.take() {
+            socket_tx.push(self.remote);
+        }
+        self.connection_error.set(Some(cause));
+    }
+
+    /// Processes an ICMPv4 error about the segment starting at [seq_num] that we sent. Only errors about data in flight
+    /// are genuine (see RFC 5927 Section 4.1).
+    pub fn receive_icmpv4_error(&mut self, seq_num: SeqNumber, error: &Icmpv4Error) {
+        let in_flight: bool = seq_num >= self.get_send_unacked().get() && seq_num < self.get_send_next().get();
+        if !in_flight || self.state == State::Closed {
+            return;
+        }
+        if let Some(path_mtu) = error.get_next_hop_mtu() {
+            self.update_path_mtu(path_mtu);
+        } else if let Some(errno) = error.get_errno() {
+            let cause: String = format!("remote is unreachable (remote={:?})", self.remote);
+            error!("receive_icmpv4_error(): {}", &cause);
+            self.fail(Fail::new(errno, &cause));
+        }
+    }
+
+    /// Lowers the MSS of this connection so that segments fit in the [path_mtu] that a router reported, and sends the
+    /// segments that it dropped for being too large again (see RFC 1191 Section 6.3). The MSS never goes below the
+    /// minimum that every host has to accept.
+    fn update_path_mtu(&mut self, path_mtu: u16) {
+        let mut header_size: usize = IPV4_HEADER_MIN_SIZE as usize + MIN_TCP_HEADER_SIZE;
+        if self.ts_recent.is_some() {
+            header_size += TIMESTAMP_OPTION_SIZE;
+        }
+        let mss: usize = (path_mtu as usize).saturating_sub(header_size).max(MIN_MSS);
+        if mss >= self.sender.get_mss() {
+            return;
+        }
+        info!("lowering MSS to {} (path_mtu={})", mss, path_mtu);
+        let self_: Self = self.clone();
+        self.sender.reduce_mss(mss, self_);
+    }
+
     /// Transmit this message to our connected peer.
     pub fn emit(&mut self, header: TcpHeader, body: Option<DemiBuffer>) {
         // Only perform this debug print in debug builds.  debug_assertions is compiler set in non-optimized builds.
@@ -817,18 +1165,20 @@ impl SharedControlBlock {
         debug_assert!(header.ack);
 
         let sent_fin: bool = header.fin;
-        let remote_ipv4_addr: Ipv4Addr = self.remote.ip().clone();
+        let ack_num: SeqNumber = header.ack_num;
+        let local_ip_addr: IpAddr = self.local.ip();
+        let remote_ip_addr: IpAddr = self.remote.ip();
         header.serialize_and_attach(
             &mut pkt,
-            self.local.ip(),
-            self.remote.ip(),
+            &self.local.ip(),
+            &self.remote.ip(),
             self.tcp_config.get_tx_checksum_offload(),
         );
 
         // Call lower L3 layer to send the segment.
         if let Err(e) = self
             .layer3_endpoint
-            .transmit_tcp_packet_nonblocking(remote_ipv4_addr, pkt)
+            .transmit_tcp_packet_nonblocking(local_ip_addr, remote_ip_addr, pkt)
         {
             warn!("could not emit packet: {:?}", e);
             return;
@@ -839,6 +1189,7 @@ impl SharedControlBlock {
 
         // Since we sent an ACK, cancel any outstanding delayed ACK request.
         self.set_ack_deadline(None);
+        self.last_ack_sent = ack_num;
 
         // If we sent a FIN, update our protocol state.
         if sent_fin {
@@ -884,24 +1235,29 @@ impl SharedControlBlock {
         hdr_window_size
     }
 
-    pub async fn push(&mut self, mut nbytes: usize) -> Result<(), Fail> {
-        loop {
-            let n: usize = self.ack_queue.pop(None).await?;
-
-            if n > nbytes {
-                self.ack_queue.push_front(n - nbytes);
-                break Ok(());
-            }
-
-            nbytes -= n;
+    /// Blocks until the data that was sent up to the sequence number [end] is acknowledged.
+    pub async fn push(&mut self, end: SeqNumber) -> Result<(), Fail> {
+        let connection_error: SharedAsyncValue<Option<Fail>> = self.connection_error.clone();
+        select_biased! {
+            e = wait_for_connection_error(connection_error).fuse() => Err(e),
+            result = self.wait_for_ack(end).fuse() => result,
+        }
+    }
 
-            if nbytes == 0 {
-                break Ok(());
-            }
+    /// Waits on SND.UNA rather than counting acknowledged bytes, so pushes that stop waiting (e.g., because they were
+    /// cancelled) do not throw off the ones that come after them.
+    async fn wait_for_ack(&mut self, end: SeqNumber) -> Result<(), Fail> {
+        let mut send_unacked: SharedAsyncValue<SeqNumber> = self.sender.get_send_unacked();
+        while send_unacked.get() < end {
+            send_unacked.wait_for_change(None).await?;
         }
+        Ok(())
     }
 
     pub async fn pop(&mut self, size: Option<usize>) -> Result<DemiBuffer, Fail> {
+        if self.receive_shutdown {
+            return Ok(DemiBuffer::new(0));
+        }
         // TODO: Need to add a way to indicate that the other side closed (i.e. that we've received a FIN).
         // Should we do this via a zero-sized buffer?  Same as with the unsent and unacked queues on the send side?
         //
@@ -909,7 +1265,11 @@ impl SharedControlBlock {
         //  if self.receiver.reader_next.get() == self.receiver.receive_next.get() {
         // But that will think data is available to be read once we've received a FIN, because FINs consume sequence
         // number space.  Now we call is_empty() on the receive queue instead.
-        self.receiver.pop(size).await
+        let connection_error: SharedAsyncValue<Option<Fail>> = self.connection_error.clone();
+        select_biased! {
+            e = wait_for_connection_error(connection_error).fuse() => Err(e),
+            result = self.receiver.pop(size).fuse() => result,
+        }
     }
 
     // This routine remembers that we have received an out-of-order FIN.
@@ -923,6 +1283,7 @@ impl SharedControlBlock {
     // Note: Since this is not the "fast path", this is written for clarity over efficiency.
     //
     fn store_out_of_order_segment(&mut self, mut new_start: SeqNumber, mut new_end: SeqNumber, mut buf: DemiBuffer) {
+        self.last_out_of_order_seq_no = Some(new_start);
         let mut action_index: usize = self.out_of_order.len();
         let mut another_pass_neeeded: bool = true;
 
@@ -994,7 +1355,7 @@ impl SharedControlBlock {
                     }
                     // We have some data overlap between the new segment and the end of the out-of-order segment.
                     // Adjust the beginning of the new segment and continue on to check the next out-of-order segment.
-                    let duplicate: u32 = u32::from(stored_end - new_start);
+                    let duplicate: u32 = u32::from(stored_end - new_start) + 1;
                     new_start = new_start + SeqNumber::from(duplicate);
                     expect_ok!(
                         buf.adjust(duplicate as usize),
@@ -1116,17 +1477,53 @@ impl SharedControlBlock {
         // TODO: Remove this allocation.
         let fin_buf: DemiBuffer = DemiBuffer::new_with_headroom(0, MAX_HEADER_SIZE as u16);
         // Send.
-        if let Err(e) = self.send(fin_buf) {
+        let self_: Self = self.clone();
+        if let Err(e) = self.sender.send(fin_buf, self_) {
             warn!("send_fin(): failed to send fin ({:?})", e);
         }
     }
 
+    /// Shuts down receiving, sending or both on this connection. Shutting down sending sends a FIN, after which we keep
+    /// receiving until our peer sends its own FIN. Shutting down receiving wakes up pending pops with the end of file.
+    pub fn shutdown(&mut self, how: Shutdown) -> Result<(), Fail> {
+        if how != Shutdown::Read && !self.half_closed {
+            self.state = match self.state {
+                State::Established => State::FinWait1,
+                State::CloseWait => State::LastAck,
+                state => {
+                    let cause: String = format!("cannot shut down sending on a closing connection (state={:?})", state);
+                    error!("shutdown(): {}", cause);
+                    return Err(Fail::new(libc::ENOTCONN, &cause));
+                },
+            };
+            self.send_fin();
+            self.half_closed = true;
+        }
+        if how != Shutdown::Write && !self.receive_shutdown {
+            self.receive_shutdown = true;
+            self.receiver.recv_queue.push(DemiBuffer::new(0));
+        }
+        Ok(())
+    }
+
     // This coroutine runs the close protocol.
     pub async fn close(&mut self) -> Result<(), Fail> {
         // Assert we are in a valid state and move to new state.
         match self.state {
             State::Established => self.local_close().await,
             State::CloseWait => self.remote_already_closed().await,
+            // Sending was shut down before, so our FIN is already on its way and the close protocol takes over from the
+            // receive loop.
+            State::FinWait1 | State::FinWait2 | State::Closing | State::TimeWait if self.half_closed => {
+                self.half_closed = false;
+                self.wait_for_time_wait().await
+            },
+            State::LastAck if self.half_closed => {
+                self.half_closed = false;
+                self.wait_for_fin_ack().await
+            },
+            // The connection was aborted, so there is nothing left to do.
+            State::Closed if self.connection_error.get().is_some() => Ok(()),
             _ => {
                 let cause: String = format!("socket is already closing");
                 error!("close(): {}", cause);
@@ -1140,7 +1537,11 @@ impl SharedControlBlock {
         self.state = State::FinWait1;
         // 1. Send FIN.
         self.send_fin();
+        self.wait_for_time_wait().await
+    }
 
+    /// Waits until our peer acknowledged our FIN and sent its own, and then lingers in TIME-WAIT.
+    async fn wait_for_time_wait(&mut self) -> Result<(), Fail> {
         // 2. TIME_WAIT
         while self.state != State::TimeWait {
             // Wait for next packet.
@@ -1189,6 +1590,11 @@ impl SharedControlBlock {
         self.state = State::LastAck;
         // 1. Send FIN.
         self.send_fin();
+        self.wait_for_fin_ack().await
+    }
+
+    /// Waits until our peer acknowledged our FIN.
+    async fn wait_for_fin_ack(&mut self) -> Result<(), Fail> {
         // Wait for ACK of FIN.
         loop {
             // Wait for next packet.
@@ -1204,6 +1610,20 @@ impl SharedControlBlock {
     }
 }
 
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Waits until the connection is aborted and returns the error that caused it.
+async fn wait_for_connection_error(mut connection_error: SharedAsyncValue<Option<Fail>>) -> Fail {
+    loop {
+        if let Some(e) = connection_error.get() {
+            return e;
+        }
+        let _ = connection_error.wait_for_change_until(None).await;
+    }
+}
+
 //======================================================================================================================
 // Trait Implementations
 //======================================================================================================================
@@ -4,6 +4,7 @@
 mod background;
 pub mod congestion_control;
 mod ctrlblk;
+mod pacer;
 mod rto;
 mod sender;
 
@@ -15,6 +16,7 @@ use crate::{
             congestion_control::CongestionControlConstructor,
             established::ctrlblk::SharedControlBlock,
             header::TcpHeader,
+            timestamp::TimestampClock,
             SeqNumber,
         },
     },
@@ -36,8 +38,9 @@ use ::futures::{
 };
 use ::std::{
     net::{
-        Ipv4Addr,
-        SocketAddrV4,
+        IpAddr,
+        Shutdown,
+        SocketAddr,
     },
     time::Duration,
 };
@@ -45,7 +48,7 @@ use ::std::{
 #[derive(Clone)]
 pub struct EstablishedSocket {
     pub cb: SharedControlBlock,
-    recv_queue: SharedAsyncQueue<(Ipv4Addr, TcpHeader, DemiBuffer)>,
+    recv_queue: SharedAsyncQueue<(IpAddr, TcpHeader, DemiBuffer)>,
     // We need this to eventually stop the background task on close.
     #[allow(unused)]
     runtime: SharedDemiRuntime,
@@ -57,12 +60,11 @@ pub struct EstablishedSocket {
 
 impl EstablishedSocket {
     pub fn new(
-        local: SocketAddrV4,
-        remote: SocketAddrV4,
+        local: SocketAddr,
+        remote: SocketAddr,
         mut runtime: SharedDemiRuntime,
         layer3_endpoint: SharedLayer3Endpoint,
-        recv_queue: SharedAsyncQueue<(Ipv4Addr, TcpHeader, DemiBuffer)>,
-        ack_queue: SharedAsyncQueue<usize>,
+        recv_queue: SharedAsyncQueue<(IpAddr, TcpHeader, DemiBuffer)>,
         tcp_config: TcpConfig,
         default_socket_options: TcpSocketOptions,
         receiver_seq_no: SeqNumber,
@@ -73,10 +75,13 @@ impl EstablishedSocket {
         sender_window_size: u32,
         sender_window_scale: u8,
         sender_mss: usize,
+        sack_permitted: bool,
+        timestamp_clock: TimestampClock,
+        ts_recent: Option<u32>,
         cc_constructor: CongestionControlConstructor,
         congestion_control_options: Option<congestion_control::Options>,
         dead_socket_tx: mpsc::UnboundedSender<QDesc>,
-        socket_queue: Option<SharedAsyncQueue<SocketAddrV4>>,
+        socket_queue: Option<SharedAsyncQueue<SocketAddr>>,
     ) -> Result<Self, Fail> {
         // TODO: Maybe add the queue descriptor here.
         let cb = SharedControlBlock::new(
@@ -94,10 +99,12 @@ impl EstablishedSocket {
             sender_window_size,
             sender_window_scale,
             sender_mss,
+            sack_permitted,
+            timestamp_clock,
+            ts_recent,
             cc_constructor,
             congestion_control_options,
             recv_queue.clone(),
-            ack_queue.clone(),
             socket_queue,
         );
         let qt: QToken = runtime.insert_background_coroutine(
@@ -112,16 +119,16 @@ impl EstablishedSocket {
         })
     }
 
-    pub fn get_recv_queue(&self) -> SharedAsyncQueue<(Ipv4Addr, TcpHeader, DemiBuffer)> {
+    pub fn get_recv_queue(&self) -> SharedAsyncQueue<(IpAddr, TcpHeader, DemiBuffer)> {
         self.recv_queue.clone()
     }
 
-    pub fn send(&mut self, buf: DemiBuffer) -> Result<(), Fail> {
+    pub fn send(&mut self, buf: DemiBuffer) -> Result<SeqNumber, Fail> {
         self.cb.send(buf)
     }
 
-    pub async fn push(&mut self, nbytes: usize) -> Result<(), Fail> {
-        self.cb.push(nbytes).await
+    pub async fn push(&mut self, end: SeqNumber) -> Result<(), Fail> {
+        self.cb.push(end).await
     }
 
     pub async fn pop(&mut self, size: Option<usize>) -> Result<DemiBuffer, Fail> {
@@ -132,6 +139,14 @@ impl EstablishedSocket {
         self.cb.close().await
     }
 
+    pub fn shutdown(&mut self, how: Shutdown) -> Result<(), Fail> {
+        self.cb.shutdown(how)
+    }
+
+    pub fn set_socket_options(&mut self, socket_options: TcpSocketOptions) {
+        self.cb.set_socket_options(socket_options)
+    }
+
     pub fn remote_mss(&self) -> usize {
         self.cb.remote_mss()
     }
@@ -140,7 +155,7 @@ impl EstablishedSocket {
         self.cb.rto()
     }
 
-    pub fn endpoints(&self) -> (SocketAddrV4, SocketAddrV4) {
+    pub fn endpoints(&self) -> (SocketAddr, SocketAddr) {
         (self.cb.get_local(), self.cb.get_remote())
     }
 }
@@ -0,0 +1,105 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+use ::std::time::{
+    Duration,
+    Instant,
+};
+
+// Token-bucket pacer for outgoing segments.
+//
+// Tokens accumulate at the pacing rate, up to a small burst, and every segment that we send takes its length out of
+// the bucket.  A segment may only go out once the bucket holds enough tokens for it.  Tokens are kept in
+// byte-nanoseconds so that refilling is exact.
+
+/// Number of full-sized segments that may go out back to back.
+const PACING_BURST_SEGMENTS: u64 = 2;
+
+const NANOS_PER_SEC: i128 = 1_000_000_000;
+
+#[derive(Debug)]
+pub struct Pacer {
+    // Tokens in the bucket, in byte-nanoseconds.
+    tokens: i128,
+
+    // Size of the bucket, in byte-nanoseconds.
+    burst_size: i128,
+
+    // Time at which we last refilled the bucket.
+    last_refill: Instant,
+}
+
+impl Pacer {
+    /// Creates a pacer for segments of up to [mss] bytes. The bucket starts full.
+    pub fn new(mss: usize, now: Instant) -> Self {
+        let burst_size: i128 = (PACING_BURST_SEGMENTS * mss as u64) as i128 * NANOS_PER_SEC;
+        Self {
+            tokens: burst_size,
+            burst_size,
+            last_refill: now,
+        }
+    }
+
+    /// Returns how long we have to wait before sending a segment of [len] bytes at [rate] (in bytes per second), or
+    /// None if we may send it right away.
+    pub fn get_delay(&mut self, rate: u64, len: usize, now: Instant) -> Option<Duration> {
+        let elapsed: i128 = now.saturating_duration_since(self.last_refill).as_nanos() as i128;
+        self.tokens = (self.tokens + elapsed * rate as i128).min(self.burst_size);
+        self.last_refill = now;
+
+        // A segment never needs more than a full bucket, even if it is larger than we planned for.
+        let needed: i128 = (len as i128 * NANOS_PER_SEC).min(self.burst_size);
+        if self.tokens >= needed {
+            return None;
+        }
+        let rate: i128 = (rate as i128).max(1);
+        let delay_nanos: i128 = (needed - self.tokens + rate - 1) / rate;
+        Some(Duration::from_nanos(delay_nanos as u64))
+    }
+
+    /// Takes a segment of [len] bytes out of the bucket.
+    pub fn consume(&mut self, len: usize) {
+        self.tokens = (self.tokens - len as i128 * NANOS_PER_SEC).max(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pacer;
+    use ::anyhow::Result;
+    use ::std::time::{
+        Duration,
+        Instant,
+    };
+
+    // Test that the pacer lets a burst through and then spaces segments out at the pacing rate.
+    #[test]
+    fn pace_segments() -> Result<()> {
+        const RATE: u64 = 1_000_000;
+        let now: Instant = Instant::now();
+        let mut pacer: Pacer = Pacer::new(1000, now);
+
+        // The bucket starts with room for two segments.
+        for _ in 0..2 {
+            crate::ensure_eq!(pacer.get_delay(RATE, 1000, now), None);
+            pacer.consume(1000);
+        }
+
+        // Then every segment waits for its own length worth of tokens, which takes 1 ms per 1000 bytes at this rate.
+        crate::ensure_eq!(pacer.get_delay(RATE, 1000, now), Some(Duration::from_millis(1)));
+        crate::ensure_eq!(pacer.get_delay(RATE, 500, now), Some(Duration::from_micros(500)));
+        let now: Instant = now + Duration::from_millis(1);
+        crate::ensure_eq!(pacer.get_delay(RATE, 1000, now), None);
+        pacer.consume(1000);
+
+        // An idle connection only gets to send a burst again.
+        let now: Instant = now + Duration::from_secs(1);
+        for _ in 0..2 {
+            crate::ensure_eq!(pacer.get_delay(RATE, 1000, now), None);
+            pacer.consume(1000);
+        }
+        crate::ensure_eq!(pacer.get_delay(RATE, 1000, now), Some(Duration::from_millis(1)));
+
+        Ok(())
+    }
+}
@@ -6,7 +6,10 @@ use crate::{
     expect_ok,
     inetstack::protocols::layer4::tcp::{
         established::SharedControlBlock,
-        header::TcpHeader,
+        header::{
+            TcpHeader,
+            TcpOptions2,
+        },
         SeqNumber,
     },
     runtime::{
@@ -68,6 +71,11 @@ pub struct Sender {
     // Queue of unacknowledged sent data.  RFC 793 calls this the "retransmission queue".
     unacked_queue: RefCell<VecDeque<UnackedSegment>>,
 
+    // Scoreboard of unacknowledged sent data that our peer has selectively acknowledged (see RFC 2018).  Holds sorted,
+    // non-overlapping [begin, end) ranges of sequence space above SND.UNA.  Only data outside these ranges is
+    // retransmitted.
+    sack_scoreboard: RefCell<Vec<(SeqNumber, SeqNumber)>>,
+
     // Sequence Number of the next data to be sent.  In RFC 793 terms, this is SND.NXT.
     send_next: SharedAsyncValue<SeqNumber>,
 
@@ -85,8 +93,7 @@ pub struct Sender {
     // RFC 1323: Number of bits to shift advertised window, defaults to zero.
     window_scale: u8,
 
-    // Maximum Segment Size currently in use for this connection.
-    // TODO: Revisit this once we support path MTU discovery.
+    // Maximum Segment Size currently in use for this connection.  Path MTU discovery may lower it.
     mss: usize,
 }
 
@@ -108,6 +115,7 @@ impl Sender {
         Self {
             send_unacked: SharedAsyncValue::new(seq_no),
             unacked_queue: RefCell::new(VecDeque::new()),
+            sack_scoreboard: RefCell::new(Vec::new()),
             send_next: SharedAsyncValue::new(seq_no),
             unsent_queue: RefCell::new(VecDeque::new()),
             unsent_seq_no: SharedAsyncValue::new(seq_no),
@@ -151,21 +159,51 @@ impl Sender {
 
     // This is the main TCP send routine.
     //
-    pub fn send(&mut self, buf: DemiBuffer, mut cb: SharedControlBlock) -> Result<(), Fail> {
+    pub fn send(&mut self, buf: DemiBuffer, cb: SharedControlBlock) -> Result<(), Fail> {
         // If the user is done sending (i.e. has called close on this connection), then they shouldn't be sending.
 
-        // Our API supports send buffers up to usize (variable, depends upon architecture) in size.  While we could
-        // allow for larger send buffers, it is simpler and more practical to limit a single send to 1 GiB, which is
-        // also the maximum value a TCP can advertise as its receive window (with maximum window scaling).
-        // TODO: the below check just limits a single send to 4 GiB, not 1 GiB.  Check this doesn't break anything.
-        //
-        // Review: Move this check up the stack (i.e. closer to the user)?
-        //
-        let mut buf_len: u32 = buf
-            .len()
-            .try_into()
-            .map_err(|_| Fail::new(EINVAL, "buffer too large"))?;
+        // Too fast.
+        // TODO: We need to fix this the correct way: limit our send buffer size to the amount we're willing to buffer.
+        if self.unsent_queue.borrow().len() > UNSENT_QUEUE_CUTOFF {
+            return Err(Fail::new(EBUSY, "too many packets to send"));
+        }
+
+        // Send each buffer of a chain on its own, as the rest of the sender deals with contiguous segments.  Empty
+        // buffers are skipped, as they would be taken for the end-of-send marker.  The whole chain is checked before
+        // any of it is sent, so that a failed send leaves the stream untouched.
+        if buf.num_segments() > 1 {
+            let mut segments: Vec<(DemiBuffer, u32)> = Vec::with_capacity(buf.num_segments());
+            for segment in buf.into_segments() {
+                let segment_len: u32 = Self::check_len(&segment)?;
+                if segment_len > 0 {
+                    segments.push((segment, segment_len));
+                }
+            }
+            for (segment, segment_len) in segments {
+                self.send_segment(segment, segment_len, cb.clone());
+            }
+            return Ok(());
+        }
+
+        let buf_len: u32 = Self::check_len(&buf)?;
+        self.send_segment(buf, buf_len, cb);
 
+        Ok(())
+    }
+
+    // Our API supports send buffers up to usize (variable, depends upon architecture) in size.  While we could
+    // allow for larger send buffers, it is simpler and more practical to limit a single send to 1 GiB, which is
+    // also the maximum value a TCP can advertise as its receive window (with maximum window scaling).
+    // TODO: the below check just limits a single send to 4 GiB, not 1 GiB.  Check this doesn't break anything.
+    //
+    // Review: Move this check up the stack (i.e. closer to the user)?
+    //
+    fn check_len(buf: &DemiBuffer) -> Result<u32, Fail> {
+        buf.len().try_into().map_err(|_| Fail::new(EINVAL, "buffer too large"))
+    }
+
+    // Sends a contiguous buffer that has already been checked, or queues it for background processing.
+    fn send_segment(&mut self, buf: DemiBuffer, mut buf_len: u32, mut cb: SharedControlBlock) {
         // TODO: What we should do here:
         //
         // Conceptually, we should take the provided buffer and add it to the unsent queue.  Then calculate the amount
@@ -182,8 +220,8 @@ impl Sender {
         // it on the unsent queue and that's it.
         //
 
-        // Check for unsent data.
-        if self.unsent_queue.borrow().is_empty() {
+        // Check for unsent data.  Paced connections leave the timing of every segment to the background sender.
+        if self.unsent_queue.borrow().is_empty() && cb.get_pacing_rate().is_none() {
             // No unsent data queued up, so we can try to send this new buffer immediately.
 
             // Calculate amount of data in flight (SND.NXT - SND.UNA).
@@ -242,67 +280,205 @@ impl Sender {
                     cb.set_retransmit_deadline(Some(cb.get_now() + rto));
                 }
 
-                return Ok(());
+                return;
             }
         }
 
-        // Too fast.
-        // TODO: We need to fix this the correct way: limit our send buffer size to the amount we're willing to buffer.
-        if self.unsent_queue.borrow().len() > UNSENT_QUEUE_CUTOFF {
-            return Err(Fail::new(EBUSY, "too many packets to send"));
-        }
-
         // Slow path: Delegating sending the data to background processing.
         trace!("Queueing Send for background processing");
         self.unsent_queue.borrow_mut().push_back(buf);
         self.unsent_seq_no.modify(|s| s + SeqNumber::from(buf_len));
-
-        Ok(())
     }
 
-    /// Retransmits the earliest segment that has not (yet) been acknowledged by our peer.
+    /// Retransmits unacknowledged segments that our peer is missing.  Without SACK information, this is the earliest
+    /// segment that has not (yet) been acknowledged.  Otherwise, this is every segment that falls in a hole below the
+    /// highest selectively acknowledged sequence number.
     pub fn retransmit(&self, mut cb: SharedControlBlock) {
-        // Check that we have an unacknowledged segment.
-        if let Some(segment) = self.unacked_queue.borrow_mut().front_mut() {
-            // We're retransmitting this, so we can no longer use an ACK for it as an RTT measurement (as we can't tell
-            // if the ACK is for the original or the retransmission).  Remove the transmission timestamp from the entry.
-            segment.initial_tx.take();
-
-            // Clone the segment data for retransmission.
-            let data: DemiBuffer = segment.bytes.clone();
-
-            // TODO: Issue #198 Repacketization - we should send a full MSS (and set the FIN flag if applicable).
-
-            // Prepare and send the segment.
-            let mut header: TcpHeader = cb.tcp_header();
-            header.seq_num = self.send_unacked.get();
-            if data.len() == 0 {
-                // This buffer is the end-of-send marker.  Retransmit the FIN.
-                header.fin = true;
+        let scoreboard = self.sack_scoreboard.borrow();
+        let highest_sacked: Option<SeqNumber> = scoreboard.last().map(|(_, end)| *end);
+        let mut unacked_queue = self.unacked_queue.borrow_mut();
+        let mut seq_no: SeqNumber = self.send_unacked.get();
+        let mut num_retransmitted: usize = 0;
+
+        for segment in unacked_queue.iter_mut() {
+            // The end-of-send marker is an empty buffer that consumes one sequence number for the FIN.
+            let seg_len: u32 = (segment.bytes.len() as u32).max(1);
+            let seg_start: SeqNumber = seq_no;
+            seq_no = seq_no + SeqNumber::from(seg_len);
+
+            match highest_sacked {
+                // Data past the highest SACKed byte is not known to be missing.
+                Some(highest_sacked) if seg_start >= highest_sacked => break,
+                Some(_) => {
+                    if scoreboard
+                        .iter()
+                        .any(|(begin, end)| *begin <= seg_start && seq_no <= *end)
+                    {
+                        // Our peer already holds this segment.
+                        continue;
+                    }
+                },
+                None if num_retransmitted > 0 => break,
+                None => (),
+            }
+
+            Self::retransmit_segment(&mut cb, segment, seg_start);
+            num_retransmitted += 1;
+        }
+
+        // If our peer reneged on the data it selectively acknowledged, fall back to retransmitting the earliest
+        // segment.
+        if num_retransmitted == 0 {
+            if let Some(segment) = unacked_queue.front_mut() {
+                Self::retransmit_segment(&mut cb, segment, self.send_unacked.get());
             } else {
-                header.psh = true;
+                // We shouldn't enter the retransmit routine with an empty unacknowledged queue.  So maybe we should
+                // assert here?  But this is relatively benign if it happens, and could be the result of a
+                // race-condition or a mismanaged retransmission timer, so asserting would be over-reacting.
+                warn!("Retransmission with empty unacknowledged queue?");
             }
-            cb.emit(header, Some(data));
+        }
+    }
+
+    /// Retransmits a single segment from the unacknowledged queue that starts at [seq_no].
+    fn retransmit_segment(cb: &mut SharedControlBlock, segment: &mut UnackedSegment, seq_no: SeqNumber) {
+        // We're retransmitting this, so we can no longer use an ACK for it as an RTT measurement (as we can't tell
+        // if the ACK is for the original or the retransmission).  Remove the transmission timestamp from the entry.
+        segment.initial_tx.take();
+
+        // Clone the segment data for retransmission.
+        let data: DemiBuffer = segment.bytes.clone();
+
+        // TODO: Issue #198 Repacketization - we should send a full MSS (and set the FIN flag if applicable).
+
+        // Prepare and send the segment.
+        let mut header: TcpHeader = cb.tcp_header();
+        header.seq_num = seq_no;
+        if data.len() == 0 {
+            // This buffer is the end-of-send marker.  Retransmit the FIN.
+            header.fin = true;
         } else {
-            // We shouldn't enter the retransmit routine with an empty unacknowledged queue.  So maybe we should assert
-            // here?  But this is relatively benign if it happens, and could be the result of a race-condition or a
-            // mismanaged retransmission timer, so asserting would be over-reacting.
-            warn!("Retransmission with empty unacknowledged queue?");
+            header.psh = true;
+        }
+        cb.emit(header, Some(data));
+    }
+
+    /// Lowers the MSS of this connection after a router told us that our segments do not fit in the path to our peer.
+    /// Unacknowledged segments that are larger than the new MSS were dropped, so they are split up. We only send the
+    /// first piece again right away, and leave the rest to the retransmission timer and congestion control, as we do
+    /// for any other loss.
+    pub fn reduce_mss(&mut self, mss: usize, mut cb: SharedControlBlock) {
+        self.mss = mss;
+        let mut unacked_queue = self.unacked_queue.borrow_mut();
+        let mut segments: VecDeque<UnackedSegment> = VecDeque::with_capacity(unacked_queue.len());
+        let mut seq_no: SeqNumber = self.send_unacked.get();
+        let mut resent: bool = false;
+
+        for segment in unacked_queue.drain(..) {
+            if segment.bytes.len() <= mss {
+                // The end-of-send marker is an empty buffer that consumes one sequence number for the FIN.
+                seq_no = seq_no + SeqNumber::from((segment.bytes.len() as u32).max(1));
+                segments.push_back(segment);
+                continue;
+            }
+
+            let mut bytes: DemiBuffer = segment.bytes;
+            let mut pieces: Vec<DemiBuffer> = Vec::new();
+            while bytes.len() > mss {
+                match bytes.split_front(mss) {
+                    Ok(piece) => pieces.push(piece),
+                    Err(e) => {
+                        warn!("reduce_mss(): cannot split segment: {:?}", e);
+                        break;
+                    },
+                }
+            }
+            pieces.push(bytes);
+            for piece in pieces {
+                let mut piece: UnackedSegment = UnackedSegment {
+                    bytes: piece,
+                    initial_tx: None,
+                };
+                if !resent {
+                    Self::retransmit_segment(&mut cb, &mut piece, seq_no);
+                    resent = true;
+                }
+                seq_no = seq_no + SeqNumber::from(piece.bytes.len() as u32);
+                segments.push_back(piece);
+            }
+        }
+        *unacked_queue = segments;
+    }
+
+    /// Forgets all SACK blocks, so that retransmissions start again from the earliest unacknowledged segment.
+    pub fn clear_sack_scoreboard(&self) {
+        self.sack_scoreboard.borrow_mut().clear();
+    }
+
+    /// Records the SACK blocks carried by an acknowledgement in the scoreboard.  Blocks that do not cover data in
+    /// flight (e.g. D-SACK blocks from RFC 2883) are ignored, and ranges that are now cumulatively acknowledged are
+    /// dropped.
+    pub fn update_sack_scoreboard(&self, header: &TcpHeader) {
+        let send_unacked: SeqNumber = self.send_unacked.get();
+        let send_next: SeqNumber = self.send_next.get();
+        let mut scoreboard = self.sack_scoreboard.borrow_mut();
+
+        scoreboard.retain(|(_, end)| *end > send_unacked);
+
+        for option in header.iter_options() {
+            if let TcpOptions2::SelectiveAcknowlegement { num_sacks, sacks } = option {
+                for sack in &sacks[..*num_sacks] {
+                    if sack.begin >= sack.end || sack.begin < send_unacked || sack.end > send_next {
+                        debug!("update_sack_scoreboard(): ignoring SACK block {:?}", sack);
+                        continue;
+                    }
+
+                    // Absorb all ranges that overlap or touch the new one.
+                    let mut begin: SeqNumber = sack.begin;
+                    let mut end: SeqNumber = sack.end;
+                    scoreboard.retain(|(other_begin, other_end)| {
+                        if *other_end < begin || end < *other_begin {
+                            return true;
+                        }
+                        if *other_begin < begin {
+                            begin = *other_begin;
+                        }
+                        if *other_end > end {
+                            end = *other_end;
+                        }
+                        false
+                    });
+                    let index: usize = scoreboard
+                        .iter()
+                        .position(|(other_begin, _)| *other_begin > begin)
+                        .unwrap_or(scoreboard.len());
+                    scoreboard.insert(index, (begin, end));
+                }
+            }
         }
     }
 
-    // Remove acknowledged data from the unacknowledged (a.k.a. retransmission) queue.
+    // Remove acknowledged data from the unacknowledged (a.k.a. retransmission) queue.  Samples the RTT from the initial
+    // transmit time unless [timestamp_rtt_sampled] is set, which means the caller already took a sample from the TCP
+    // timestamps.
     //
-    pub fn remove_acknowledged_data(&self, mut cb: SharedControlBlock, bytes_acknowledged: u32, now: Instant) {
+    pub fn remove_acknowledged_data(
+        &self,
+        mut cb: SharedControlBlock,
+        bytes_acknowledged: u32,
+        now: Instant,
+        timestamp_rtt_sampled: bool,
+    ) {
         let mut bytes_remaining: usize = bytes_acknowledged as usize;
 
         while bytes_remaining != 0 {
             if let Some(segment) = self.unacked_queue.borrow_mut().front_mut() {
                 // Add sample for RTO if we have an initial transmit time.
                 // Note that in the case of repacketization, an ack for the first byte is enough for the time sample.
-                // TODO: TCP timestamp support.
-                if let Some(initial_tx) = segment.initial_tx {
-                    cb.rto_add_sample(now - initial_tx);
+                if !timestamp_rtt_sampled {
+                    if let Some(initial_tx) = segment.initial_tx {
+                        cb.rto_add_sample(now - initial_tx);
+                    }
                 }
 
                 if segment.bytes.len() > bytes_remaining {
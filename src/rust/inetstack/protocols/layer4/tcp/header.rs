@@ -3,7 +3,10 @@
 
 use crate::{
     inetstack::protocols::{
-        layer3::ip::IpProtocol,
+        layer3::{
+            ip::IpProtocol,
+            ipv6::Ipv6Header,
+        },
         layer4::tcp::SeqNumber,
     },
     runtime::{
@@ -17,15 +20,17 @@ use ::std::{
         Cursor,
         Read,
     },
-    net::Ipv4Addr,
+    net::IpAddr,
     slice::ChunksExact,
 };
 
 pub const MIN_TCP_HEADER_SIZE: usize = 20;
 pub const MAX_TCP_HEADER_SIZE: usize = 60;
 pub const MAX_TCP_OPTIONS: usize = 5;
+/// Maximum number of SACK blocks that fit in the TCP option space (see RFC 2018 Section 3).
+pub const MAX_SACK_BLOCKS: usize = 4;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub struct SelectiveAcknowlegement {
     pub begin: SeqNumber,
     pub end: SeqNumber,
@@ -40,7 +45,7 @@ pub enum TcpOptions2 {
     SelectiveAcknowlegementPermitted,
     SelectiveAcknowlegement {
         num_sacks: usize,
-        sacks: [SelectiveAcknowlegement; 4],
+        sacks: [SelectiveAcknowlegement; MAX_SACK_BLOCKS],
     },
     Timestamp {
         sender_timestamp: u32,
@@ -169,8 +174,8 @@ impl TcpHeader {
 
     /// Strip and parse the TCP header from the packet in [buf].
     pub fn parse_and_strip(
-        local_ipv4_addr: &Ipv4Addr,
-        remote_ipv4_addr: &Ipv4Addr,
+        local_ip_addr: &IpAddr,
+        remote_ip_addr: &IpAddr,
         buf: &mut DemiBuffer,
         rx_checksum_offload: bool,
     ) -> Result<Self, Fail> {
@@ -211,7 +216,7 @@ impl TcpHeader {
 
         if !rx_checksum_offload {
             let checksum: u16 = u16::from_be_bytes([hdr_buf[16], hdr_buf[17]]);
-            if checksum != tcp_checksum(local_ipv4_addr, remote_ipv4_addr, hdr_buf, data_buf) {
+            if checksum != tcp_checksum(local_ip_addr, remote_ip_addr, hdr_buf, data_buf) {
                 return Err(Fail::new(EBADMSG, "TCP checksum mismatch"));
             }
         }
@@ -270,10 +275,8 @@ impl TcpHeader {
                             10 | 18 | 26 | 34 => (option_length as usize - 2) / 8,
                             _ => return Err(Fail::new(EBADMSG, "invalid SACK size")),
                         };
-                        let mut sacks: [SelectiveAcknowlegement; 4] = [SelectiveAcknowlegement {
-                            begin: SeqNumber::from(0),
-                            end: SeqNumber::from(0),
-                        }; 4];
+                        let mut sacks: [SelectiveAcknowlegement; MAX_SACK_BLOCKS] =
+                            [SelectiveAcknowlegement::default(); MAX_SACK_BLOCKS];
                         for s in sacks.iter_mut().take(num_sacks) {
                             let mut temp: [u8; 4] = [0; 4];
                             option_rdr.read_exact(&mut temp)?;
@@ -339,8 +342,8 @@ impl TcpHeader {
     pub fn serialize_and_attach(
         &self,
         pkt: &mut DemiBuffer,
-        src_ipv4_addr: &Ipv4Addr,
-        dst_ipv4_addr: &Ipv4Addr,
+        src_ip_addr: &IpAddr,
+        dst_ip_addr: &IpAddr,
         tx_checksum_offload: bool,
     ) {
         let header_bytes: usize = self.compute_size();
@@ -405,7 +408,7 @@ impl TcpHeader {
 
         // Alright, we've fully filled out the header, time to compute the checksum.
         if !tx_checksum_offload {
-            let checksum: u16 = tcp_checksum(src_ipv4_addr, dst_ipv4_addr, &hdr_buf[..], &payload);
+            let checksum: u16 = tcp_checksum(src_ip_addr, dst_ip_addr, &hdr_buf[..], &payload);
             hdr_buf[16..18].copy_from_slice(&checksum.to_be_bytes());
         } else {
             hdr_buf[16] = 0;
@@ -439,25 +442,38 @@ impl TcpHeader {
     }
 }
 
-fn tcp_checksum(src_ipv4_addr: &Ipv4Addr, dst_ipv4_addr: &Ipv4Addr, header: &[u8], data: &[u8]) -> u16 {
-    let mut state: u32 = 0xffff;
-
-    // First, fold in a "pseudo-IP" header of...
-    // 1) Source address (4 bytes)
-    let src_octets: [u8; 4] = src_ipv4_addr.octets();
-    state += u16::from_be_bytes([src_octets[0], src_octets[1]]) as u32;
-    state += u16::from_be_bytes([src_octets[2], src_octets[3]]) as u32;
-
-    // 2) Destination address (4 bytes)
-    let dst_octets: [u8; 4] = dst_ipv4_addr.octets();
-    state += u16::from_be_bytes([dst_octets[0], dst_octets[1]]) as u32;
-    state += u16::from_be_bytes([dst_octets[2], dst_octets[3]]) as u32;
-
-    // 3) 1 byte of zeros and TCP protocol number (1 byte)
-    state += u16::from_be_bytes([0, IpProtocol::TCP as u8]) as u32;
-
-    // 4) TCP segment length (2 bytes)
-    state += (header.len() + data.len()) as u32;
+fn tcp_checksum(src_ip_addr: &IpAddr, dst_ip_addr: &IpAddr, header: &[u8], data: &[u8]) -> u16 {
+    let mut state: u32 = match (src_ip_addr, dst_ip_addr) {
+        (IpAddr::V4(src_ipv4_addr), IpAddr::V4(dst_ipv4_addr)) => {
+            let mut state: u32 = 0xffff;
+
+            // First, fold in a "pseudo-IP" header of...
+            // 1) Source address (4 bytes)
+            let src_octets: [u8; 4] = src_ipv4_addr.octets();
+            state += u16::from_be_bytes([src_octets[0], src_octets[1]]) as u32;
+            state += u16::from_be_bytes([src_octets[2], src_octets[3]]) as u32;
+
+            // 2) Destination address (4 bytes)
+            let dst_octets: [u8; 4] = dst_ipv4_addr.octets();
+            state += u16::from_be_bytes([dst_octets[0], dst_octets[1]]) as u32;
+            state += u16::from_be_bytes([dst_octets[2], dst_octets[3]]) as u32;
+
+            // 3) 1 byte of zeros and TCP protocol number (1 byte)
+            state += u16::from_be_bytes([0, IpProtocol::TCP as u8]) as u32;
+
+            // 4) TCP segment length (2 bytes)
+            state += (header.len() + data.len()) as u32;
+            state
+        },
+        // The IPv6 pseudo-header carries the same fields, but with wider addresses and length.
+        (IpAddr::V6(src_ipv6_addr), IpAddr::V6(dst_ipv6_addr)) => Ipv6Header::compute_pseudo_header_checksum(
+            src_ipv6_addr,
+            dst_ipv6_addr,
+            IpProtocol::TCP,
+            header.len() + data.len(),
+        ),
+        _ => unreachable!("source and destination addresses should belong to the same family"),
+    };
 
     let fixed_header: &[u8; MIN_TCP_HEADER_SIZE] = header[..MIN_TCP_HEADER_SIZE].try_into().unwrap();
 
@@ -1,47 +1,99 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
-use crate::inetstack::protocols::layer4::tcp::SeqNumber;
-#[allow(unused_imports)]
+use crate::inetstack::protocols::layer4::tcp::{
+    siphash::siphash24,
+    SeqNumber,
+};
 use std::{
-    hash::Hasher,
-    net::SocketAddrV4,
-    num::Wrapping,
+    net::{
+        IpAddr,
+        SocketAddr,
+    },
+    time::Instant,
 };
 
-#[allow(dead_code)]
+/// Generator of initial sequence numbers (see RFC 6528).
+#[derive(Clone, Copy)]
 pub struct IsnGenerator {
-    nonce: u32,
-    counter: Wrapping<u16>,
+    /// Secret key of the pseudorandom function.
+    key: (u64, u64),
+    /// Time at which the ISN timer started ticking.
+    origin: Instant,
 }
 
 impl IsnGenerator {
-    pub fn new(nonce: u32) -> Self {
-        Self {
-            nonce,
-            counter: Wrapping(0),
-        }
+    pub fn new(key: (u64, u64), origin: Instant) -> Self {
+        Self { key, origin }
     }
 
     #[cfg(test)]
-    pub fn generate(&mut self, _local: &SocketAddrV4, _remote: &SocketAddrV4) -> SeqNumber {
+    pub fn generate(&self, _local: &SocketAddr, _remote: &SocketAddr, _now: Instant) -> SeqNumber {
         SeqNumber::from(0)
     }
 
     #[cfg(not(test))]
-    pub fn generate(&mut self, local: &SocketAddrV4, remote: &SocketAddrV4) -> SeqNumber {
-        let crc: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_CKSUM);
-        let mut digest = crc.digest();
-        digest.update(&remote.ip().octets());
-        let remote_port: u16 = remote.port().into();
-        digest.update(&remote_port.to_be_bytes());
-        digest.update(&local.ip().octets());
-        let local_port: u16 = local.port().into();
-        digest.update(&local_port.to_be_bytes());
-        digest.update(&self.nonce.to_be_bytes());
-        let digest = digest.finalize();
-        let isn = SeqNumber::from(digest + self.counter.0 as u32);
-        self.counter += Wrapping(1);
-        isn
+    pub fn generate(&self, local: &SocketAddr, remote: &SocketAddr, now: Instant) -> SeqNumber {
+        self.compute(local, remote, now)
+    }
+
+    /// Computes the ISN of a connection as ISN = M + F(localip, localport, remoteip, remoteport, secretkey), where M is
+    /// a timer that ticks every 4 microseconds and F is a keyed hash (see RFC 6528 Section 3). The timer moves the ISN
+    /// ahead of the sequence space of any previous incarnation of the connection.
+    fn compute(&self, local: &SocketAddr, remote: &SocketAddr, now: Instant) -> SeqNumber {
+        let mut data: Vec<u8> = Vec::with_capacity(36);
+        match local.ip() {
+            IpAddr::V4(addr) => data.extend_from_slice(&addr.octets()),
+            IpAddr::V6(addr) => data.extend_from_slice(&addr.octets()),
+        }
+        data.extend_from_slice(&local.port().to_be_bytes());
+        match remote.ip() {
+            IpAddr::V4(addr) => data.extend_from_slice(&addr.octets()),
+            IpAddr::V6(addr) => data.extend_from_slice(&addr.octets()),
+        }
+        data.extend_from_slice(&remote.port().to_be_bytes());
+
+        // The timer wraps around along with the sequence number space.
+        let timer: u32 = (now.saturating_duration_since(self.origin).as_micros() / 4) as u32;
+        let hash: u32 = siphash24(self.key, &data) as u32;
+        SeqNumber::from(timer.wrapping_add(hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IsnGenerator;
+    use crate::inetstack::protocols::layer4::tcp::SeqNumber;
+    use ::anyhow::Result;
+    use ::std::{
+        net::SocketAddr,
+        time::{
+            Duration,
+            Instant,
+        },
+    };
+
+    // Test that the ISN depends on the secret key, on the connection endpoints and on time.
+    #[test]
+    fn isn_generation() -> Result<()> {
+        let now: Instant = Instant::now();
+        let local: SocketAddr = "192.168.1.1:12345".parse()?;
+        let remote: SocketAddr = "192.168.1.2:23456".parse()?;
+        let other_remote: SocketAddr = "192.168.1.2:23457".parse()?;
+        let generator: IsnGenerator = IsnGenerator::new((1, 2), now);
+        let other_generator: IsnGenerator = IsnGenerator::new((3, 4), now);
+
+        let isn: SeqNumber = generator.compute(&local, &remote, now);
+        crate::ensure_eq!(generator.compute(&local, &remote, now), isn);
+        crate::ensure_neq!(generator.compute(&local, &other_remote, now), isn);
+        crate::ensure_neq!(other_generator.compute(&local, &remote, now), isn);
+
+        // The timer ticks every 4 microseconds.
+        crate::ensure_eq!(
+            generator.compute(&local, &remote, now + Duration::from_micros(4000)),
+            isn + SeqNumber::from(1000)
+        );
+
+        Ok(())
     }
 }
@@ -9,7 +9,10 @@ mod isn_generator;
 mod passive_open;
 pub mod peer;
 mod sequence_number;
+mod siphash;
 pub mod socket;
+mod syn_cookie;
+mod timestamp;
 
 #[cfg(test)]
 mod tests;
@@ -19,10 +19,7 @@ use crate::{
         layer4::tcp::{
             constants::FALLBACK_MSS,
             established::{
-                congestion_control::{
-                    self,
-                    CongestionControl,
-                },
+                congestion_control,
                 EstablishedSocket,
             },
             header::{
@@ -30,6 +27,11 @@ use crate::{
                 TcpOptions2,
             },
             isn_generator::IsnGenerator,
+            syn_cookie::SynCookieGenerator,
+            timestamp::{
+                get_timestamp_option,
+                TimestampClock,
+            },
             SeqNumber,
         },
         MAX_HEADER_SIZE,
@@ -60,14 +62,17 @@ use ::libc::{
 use ::std::{
     collections::HashMap,
     net::{
-        Ipv4Addr,
-        SocketAddrV4,
+        IpAddr,
+        SocketAddr,
     },
     ops::{
         Deref,
         DerefMut,
     },
-    time::Duration,
+    time::{
+        Duration,
+        Instant,
+    },
 };
 
 //======================================================================================================================
@@ -86,12 +91,13 @@ enum State {
 pub struct PassiveSocket {
     // TCP Connection State.
     state: SharedAsyncValue<State>,
-    connections: HashMap<SocketAddrV4, SharedAsyncQueue<(Ipv4Addr, TcpHeader, DemiBuffer)>>,
-    recv_queue: SharedAsyncQueue<(Ipv4Addr, TcpHeader, DemiBuffer)>,
+    connections: HashMap<SocketAddr, SharedAsyncQueue<(IpAddr, TcpHeader, DemiBuffer)>>,
+    recv_queue: SharedAsyncQueue<(IpAddr, TcpHeader, DemiBuffer)>,
     ready: AsyncQueue<Result<EstablishedSocket, Fail>>,
     max_backlog: usize,
     isn_generator: IsnGenerator,
-    local: SocketAddrV4,
+    syn_cookie_generator: SynCookieGenerator,
+    local: SocketAddr,
     runtime: SharedDemiRuntime,
     layer3_endpoint: SharedLayer3Endpoint,
     tcp_config: TcpConfig,
@@ -100,7 +106,7 @@ pub struct PassiveSocket {
     dead_socket_tx: mpsc::UnboundedSender<QDesc>,
 
     background_task_qt: Option<QToken>,
-    socket_queue: SharedAsyncQueue<SocketAddrV4>,
+    socket_queue: SharedAsyncQueue<SocketAddr>,
 }
 
 #[derive(Clone)]
@@ -112,24 +118,26 @@ pub struct SharedPassiveSocket(SharedObject<PassiveSocket>);
 
 impl SharedPassiveSocket {
     pub fn new(
-        local: SocketAddrV4,
+        local: SocketAddr,
         max_backlog: usize,
         mut runtime: SharedDemiRuntime,
-        recv_queue: SharedAsyncQueue<(Ipv4Addr, TcpHeader, DemiBuffer)>,
+        recv_queue: SharedAsyncQueue<(IpAddr, TcpHeader, DemiBuffer)>,
         layer3_endpoint: SharedLayer3Endpoint,
         tcp_config: TcpConfig,
         default_socket_options: TcpSocketOptions,
         dead_socket_tx: mpsc::UnboundedSender<QDesc>,
-        nonce: u32,
+        isn_generator: IsnGenerator,
+        cookie_key: (u64, u64),
     ) -> Result<Self, Fail> {
-        let socket_queue: SharedAsyncQueue<SocketAddrV4> = SharedAsyncQueue::<SocketAddrV4>::default();
+        let socket_queue: SharedAsyncQueue<SocketAddr> = SharedAsyncQueue::<SocketAddr>::default();
         let mut me: Self = Self(SharedObject::<PassiveSocket>::new(PassiveSocket {
             state: SharedAsyncValue::new(State::Listening),
-            connections: HashMap::<SocketAddrV4, SharedAsyncQueue<(Ipv4Addr, TcpHeader, DemiBuffer)>>::new(),
+            connections: HashMap::<SocketAddr, SharedAsyncQueue<(IpAddr, TcpHeader, DemiBuffer)>>::new(),
             recv_queue,
             ready: AsyncQueue::<Result<EstablishedSocket, Fail>>::default(),
             max_backlog,
-            isn_generator: IsnGenerator::new(nonce),
+            isn_generator,
+            syn_cookie_generator: SynCookieGenerator::new(cookie_key, runtime.get_now()),
             local,
             runtime: runtime.clone(),
             layer3_endpoint,
@@ -146,7 +154,7 @@ impl SharedPassiveSocket {
     }
 
     /// Returns the address that the socket is bound to.
-    pub fn endpoint(&self) -> SocketAddrV4 {
+    pub fn endpoint(&self) -> SocketAddr {
         self.local
     }
 
@@ -163,8 +171,8 @@ impl SharedPassiveSocket {
 
     async fn poll(mut self) {
         loop {
-            let mut socket_queue: SharedAsyncQueue<SocketAddrV4> = self.socket_queue.clone();
-            let mut recv_queue: SharedAsyncQueue<(Ipv4Addr, TcpHeader, DemiBuffer)> = self.recv_queue.clone();
+            let mut socket_queue: SharedAsyncQueue<SocketAddr> = self.socket_queue.clone();
+            let mut recv_queue: SharedAsyncQueue<(IpAddr, TcpHeader, DemiBuffer)> = self.recv_queue.clone();
             let mut state: SharedAsyncValue<State> = self.state.clone();
             // Remove sockets that have been closed.
             futures::select! {
@@ -177,15 +185,22 @@ impl SharedPassiveSocket {
                 },
                 result = recv_queue.pop(None).fuse() => {
                     match result {
-                        Ok((ipv4_addr, tcp_hdr, buf)) =>  {
-                                    let remote: SocketAddrV4 = SocketAddrV4::new(ipv4_addr, tcp_hdr.src_port);
+                        Ok((ip_addr, tcp_hdr, buf)) =>  {
+                                    let remote: SocketAddr = SocketAddr::new(ip_addr, tcp_hdr.src_port);
                                     if let Some(recv_queue) = self.connections.get_mut(&remote) {
                                         // Packet is either for an inflight request or established connection.
-                                        recv_queue.push((ipv4_addr, tcp_hdr, buf));
+                                        recv_queue.push((ip_addr, tcp_hdr, buf));
                                         continue;
                                     }
 
-                                    // If not a SYN, then this packet is not for a new connection and we throw it away.
+                                    // If not a SYN, then this packet may complete a handshake that we answered with a
+                                    // SYN cookie.
+                                    let (tcp_hdr, buf) = match self.handle_syn_cookie_ack(ip_addr, tcp_hdr, buf) {
+                                        Some((tcp_hdr, buf)) => (tcp_hdr, buf),
+                                        None => continue,
+                                    };
+
+                                    // Otherwise, this packet is not for a new connection and we throw it away.
                                     if !tcp_hdr.syn || tcp_hdr.ack || tcp_hdr.rst {
                                         let cause: String = format!(
                                             "invalid TCP flags (syn={}, ack={}, rst={})",
@@ -221,12 +236,18 @@ impl SharedPassiveSocket {
         }
     }
 
-    fn handle_new_syn(&mut self, remote: SocketAddrV4, tcp_hdr: TcpHeader) {
+    fn handle_new_syn(&mut self, remote: SocketAddr, tcp_hdr: TcpHeader) {
         debug!("Received SYN: {:?}", tcp_hdr);
         let inflight_len: usize = self.connections.len();
         // Check backlog. Since we might receive data even on connections that have completed their handshake, all
         // ready sockets are also in the inflight table.
         if inflight_len >= self.max_backlog {
+            // Answer with a SYN cookie, so that we do not need to keep any state for this connection request.
+            if self.tcp_config.get_syn_cookies() {
+                debug!("handle_new_syn(): backlog full, sending SYN cookie");
+                self.send_syn_cookie(remote, tcp_hdr);
+                return;
+            }
             let cause: String = format!(
                 "backlog full (inflight={}, ready={}, backlog={})",
                 inflight_len,
@@ -239,17 +260,16 @@ impl SharedPassiveSocket {
         }
 
         // Send SYN+ACK.
-        let local: SocketAddrV4 = self.local.clone();
-        let local_isn = self.isn_generator.generate(&local, &remote);
+        let local: SocketAddr = self.local;
+        let local_isn = self.isn_generator.generate(&local, &remote, self.runtime.get_now());
         let remote_isn = tcp_hdr.seq_num;
 
         // Allocate a new coroutine to send the SYN+ACK and retry if necessary.
-        let recv_queue: SharedAsyncQueue<(Ipv4Addr, TcpHeader, DemiBuffer)> =
-            SharedAsyncQueue::<(Ipv4Addr, TcpHeader, DemiBuffer)>::default();
-        let ack_queue: SharedAsyncQueue<usize> = SharedAsyncQueue::<usize>::default();
+        let recv_queue: SharedAsyncQueue<(IpAddr, TcpHeader, DemiBuffer)> =
+            SharedAsyncQueue::<(IpAddr, TcpHeader, DemiBuffer)>::default();
         let future = self
             .clone()
-            .send_syn_ack_and_wait_for_ack(remote, remote_isn, local_isn, tcp_hdr, recv_queue.clone(), ack_queue)
+            .send_syn_ack_and_wait_for_ack(remote, remote_isn, local_isn, tcp_hdr, recv_queue.clone())
             .fuse();
         match self
             .runtime
@@ -266,8 +286,125 @@ impl SharedPassiveSocket {
         self.connections.insert(remote, recv_queue);
     }
 
+    /// Answers the SYN in `tcp_hdr` with a SYN+ACK that encodes the state of the connection request in its sequence
+    /// number. Since the cookie only fits the MSS, we do not advertise window scaling, SACK or timestamps.
+    fn send_syn_cookie(&mut self, remote: SocketAddr, tcp_hdr: TcpHeader) {
+        let mut mss: usize = FALLBACK_MSS;
+        for option in tcp_hdr.iter_options() {
+            if let TcpOptions2::MaximumSegmentSize(m) = option {
+                info!("Received advertised MSS: {}", m);
+                mss = *m as usize;
+            }
+        }
+
+        let now: Instant = self.runtime.get_now();
+        let (local_isn, _): (SeqNumber, usize) =
+            self.syn_cookie_generator
+                .generate(&self.local, &remote, tcp_hdr.seq_num, mss, now);
+
+        let mut syn_ack: TcpHeader = TcpHeader::new(self.local.port(), remote.port());
+        syn_ack.syn = true;
+        syn_ack.seq_num = local_isn;
+        syn_ack.ack = true;
+        syn_ack.ack_num = tcp_hdr.seq_num + SeqNumber::from(1);
+        syn_ack.window_size = self.tcp_config.get_receive_window_size();
+        let advertised_mss: u16 = self.tcp_config.get_advertised_mss() as u16;
+        syn_ack.push_option(TcpOptions2::MaximumSegmentSize(advertised_mss));
+
+        debug!("Sending SYN+ACK: {:?}", syn_ack);
+        let src_ip_addr: IpAddr = self.local.ip();
+        let dst_ip_addr: IpAddr = remote.ip();
+        let mut pkt: DemiBuffer = DemiBuffer::new_with_headroom(0, MAX_HEADER_SIZE as u16);
+        syn_ack.serialize_and_attach(
+            &mut pkt,
+            &self.local.ip(),
+            &remote.ip(),
+            self.tcp_config.get_rx_checksum_offload(),
+        );
+        if let Err(e) = self
+            .layer3_endpoint
+            .transmit_tcp_packet_nonblocking(src_ip_addr, dst_ip_addr, pkt)
+        {
+            warn!("Could not send SYN+ACK: {:?}", e);
+        }
+    }
+
+    /// Checks whether the segment in `tcp_hdr` is an ACK that echoes a valid SYN cookie, in which case it builds a new
+    /// connection from the cookie and queues it for accepting. Returns the segment if it needs further processing.
+    fn handle_syn_cookie_ack(
+        &mut self,
+        ip_addr: IpAddr,
+        tcp_hdr: TcpHeader,
+        buf: DemiBuffer,
+    ) -> Option<(TcpHeader, DemiBuffer)> {
+        if !self.tcp_config.get_syn_cookies() || !tcp_hdr.ack || tcp_hdr.syn || tcp_hdr.rst || tcp_hdr.fin {
+            return Some((tcp_hdr, buf));
+        }
+
+        let remote: SocketAddr = SocketAddr::new(ip_addr, tcp_hdr.src_port);
+        let remote_isn: SeqNumber = tcp_hdr.seq_num - SeqNumber::from(1);
+        let local_isn: SeqNumber = tcp_hdr.ack_num - SeqNumber::from(1);
+        let now: Instant = self.runtime.get_now();
+        let mss: usize = match self
+            .syn_cookie_generator
+            .validate(&self.local, &remote, remote_isn, local_isn, now)
+        {
+            Some(mss) => mss,
+            None => return Some((tcp_hdr, buf)),
+        };
+        // Cookies keep no state, so the accept queue is the only bound on the connections that they create. Drop the
+        // ACK if it is full; our peer retransmits it, and the cookie stays valid for a while.
+        if self.ready.len() >= self.max_backlog {
+            warn!(
+                "handle_syn_cookie_ack(): accept queue full, dropping ACK (ready={}, backlog={})",
+                self.ready.len(),
+                self.max_backlog
+            );
+            return None;
+        }
+        debug!("Received ACK with valid SYN cookie: {:?}", tcp_hdr);
+
+        let recv_queue: SharedAsyncQueue<(IpAddr, TcpHeader, DemiBuffer)> =
+            SharedAsyncQueue::<(IpAddr, TcpHeader, DemiBuffer)>::default();
+        let remote_window_size: u32 = tcp_hdr.window_size as u32;
+        // If there is data with the ACK, deliver it.
+        if !buf.is_empty() {
+            recv_queue.clone().push((ip_addr, tcp_hdr, buf));
+        }
+
+        let result: Result<EstablishedSocket, Fail> = EstablishedSocket::new(
+            self.local,
+            remote,
+            self.runtime.clone(),
+            self.layer3_endpoint.clone(),
+            recv_queue.clone(),
+            self.tcp_config.clone(),
+            self.socket_options,
+            remote_isn + SeqNumber::from(1),
+            self.tcp_config.get_ack_delay_timeout(),
+            self.tcp_config.get_receive_window_size() as u32,
+            0,
+            local_isn + SeqNumber::from(1),
+            remote_window_size,
+            0,
+            mss,
+            false,
+            TimestampClock::new(now),
+            None,
+            congestion_control::get_constructor(self.socket_options.get_congestion_control()),
+            None,
+            self.dead_socket_tx.clone(),
+            Some(self.socket_queue.clone()),
+        );
+        if result.is_ok() {
+            self.connections.insert(remote, recv_queue);
+        }
+        self.ready.push(result);
+        None
+    }
+
     /// Sends a RST segment to `remote`.
-    fn send_rst(&mut self, remote: &SocketAddrV4, tcp_hdr: TcpHeader) {
+    fn send_rst(&mut self, remote: &SocketAddr, tcp_hdr: TcpHeader) {
         debug!("send_rst(): sending RST to {:?}", remote);
 
         // If this is an inactive socket, then generate a RST segment.
@@ -287,7 +424,8 @@ impl SharedPassiveSocket {
         };
 
         // Create a RST segment.
-        let dst_ipv4_addr: Ipv4Addr = remote.ip().clone();
+        let src_ip_addr: IpAddr = self.local.ip();
+        let dst_ip_addr: IpAddr = remote.ip();
         let mut tcp_hdr: TcpHeader = TcpHeader::new(self.local.port(), remote.port());
         tcp_hdr.rst = true;
         tcp_hdr.seq_num = seq_num;
@@ -300,29 +438,33 @@ impl SharedPassiveSocket {
         let mut pkt: DemiBuffer = DemiBuffer::new_with_headroom(0, MAX_HEADER_SIZE as u16);
         tcp_hdr.serialize_and_attach(
             &mut pkt,
-            self.local.ip(),
-            remote.ip(),
+            &self.local.ip(),
+            &remote.ip(),
             self.tcp_config.get_rx_checksum_offload(),
         );
 
         // Pass on to send through the L2 layer.
-        if let Err(e) = self.layer3_endpoint.transmit_tcp_packet_nonblocking(dst_ipv4_addr, pkt) {
+        if let Err(e) = self
+            .layer3_endpoint
+            .transmit_tcp_packet_nonblocking(src_ip_addr, dst_ip_addr, pkt)
+        {
             warn!("Could not send RST: {:?}", e);
         }
     }
 
     async fn send_syn_ack_and_wait_for_ack(
         mut self,
-        remote: SocketAddrV4,
+        remote: SocketAddr,
         remote_isn: SeqNumber,
         local_isn: SeqNumber,
         tcp_hdr: TcpHeader,
-        recv_queue: SharedAsyncQueue<(Ipv4Addr, TcpHeader, DemiBuffer)>,
-        ack_queue: SharedAsyncQueue<usize>,
+        recv_queue: SharedAsyncQueue<(IpAddr, TcpHeader, DemiBuffer)>,
     ) {
         // Set up new inflight accept connection.
         let mut remote_window_scale = None;
         let mut mss = FALLBACK_MSS;
+        let mut sack_permitted: bool = false;
+        let mut remote_timestamp: Option<u32> = None;
         for option in tcp_hdr.iter_options() {
             match option {
                 TcpOptions2::WindowScale(w) => {
@@ -333,16 +475,35 @@ impl SharedPassiveSocket {
                     info!("Received advertised MSS: {}", m);
                     mss = *m as usize;
                 },
+                TcpOptions2::SelectiveAcknowlegementPermitted => {
+                    info!("Received SACK permitted");
+                    sack_permitted = true;
+                },
+                TcpOptions2::Timestamp { sender_timestamp, .. } => {
+                    info!("Received timestamp: {}", sender_timestamp);
+                    remote_timestamp = Some(*sender_timestamp);
+                },
                 _ => continue,
             }
         }
+        let timestamp_clock: TimestampClock = TimestampClock::new(self.runtime.get_now());
 
         let mut handshake_retries: usize = self.tcp_config.get_handshake_retries();
         let handshake_timeout: Duration = self.tcp_config.get_handshake_timeout();
 
         loop {
             // Send the SYN + ACK.
-            if let Err(e) = self.send_syn_ack(local_isn, remote_isn, remote).await {
+            if let Err(e) = self
+                .send_syn_ack(
+                    local_isn,
+                    remote_isn,
+                    remote,
+                    sack_permitted,
+                    timestamp_clock,
+                    remote_timestamp,
+                )
+                .await
+            {
                 self.ready.push(Err(e));
                 return;
             }
@@ -352,13 +513,15 @@ impl SharedPassiveSocket {
             // Wait for ACK in response.
             let ack = self.clone().wait_for_ack(
                 recv_queue.clone(),
-                ack_queue.clone(),
                 remote,
                 local_isn,
                 remote_isn,
                 tcp_hdr.window_size,
                 remote_window_scale,
                 mss,
+                sack_permitted,
+                timestamp_clock,
+                remote_timestamp,
             );
 
             // Either we get an ack or a timeout.
@@ -389,7 +552,10 @@ impl SharedPassiveSocket {
         &mut self,
         local_isn: SeqNumber,
         remote_isn: SeqNumber,
-        remote: SocketAddrV4,
+        remote: SocketAddr,
+        sack_permitted: bool,
+        timestamp_clock: TimestampClock,
+        remote_timestamp: Option<u32>,
     ) -> Result<(), Fail> {
         let mut tcp_hdr = TcpHeader::new(self.local.port(), remote.port());
         tcp_hdr.syn = true;
@@ -405,32 +571,50 @@ impl SharedPassiveSocket {
         tcp_hdr.push_option(TcpOptions2::WindowScale(self.tcp_config.get_window_scale()));
         info!("Advertising window scale: {}", self.tcp_config.get_window_scale());
 
+        // We may only send SACK permitted if our peer sent it in its SYN (see RFC 2018 Section 2).
+        if sack_permitted {
+            tcp_hdr.push_option(TcpOptions2::SelectiveAcknowlegementPermitted);
+            info!("Advertising SACK permitted");
+        }
+
+        // Likewise, we may only send timestamps if our peer sent them in its SYN (see RFC 7323 Section 3.2).
+        if let Some(remote_timestamp) = remote_timestamp {
+            tcp_hdr.push_option(TcpOptions2::Timestamp {
+                sender_timestamp: timestamp_clock.get_timestamp(self.runtime.get_now()),
+                echo_timestamp: remote_timestamp,
+            });
+            info!("Advertising timestamps");
+        }
+
         debug!("Sending SYN+ACK: {:?}", tcp_hdr);
-        let dst_ipv4_addr: Ipv4Addr = remote.ip().clone();
+        let src_ip_addr: IpAddr = self.local.ip();
+        let dst_ip_addr: IpAddr = remote.ip();
         let mut pkt: DemiBuffer = DemiBuffer::new_with_headroom(0, MAX_HEADER_SIZE as u16);
         tcp_hdr.serialize_and_attach(
             &mut pkt,
-            self.local.ip(),
-            remote.ip(),
+            &self.local.ip(),
+            &remote.ip(),
             self.tcp_config.get_rx_checksum_offload(),
         );
         self.layer3_endpoint
-            .transmit_tcp_packet_blocking(dst_ipv4_addr, pkt)
+            .transmit_tcp_packet_blocking(src_ip_addr, dst_ip_addr, pkt)
             .await
     }
 
     async fn wait_for_ack(
         self,
-        mut recv_queue: SharedAsyncQueue<(Ipv4Addr, TcpHeader, DemiBuffer)>,
-        ack_queue: SharedAsyncQueue<usize>,
-        remote: SocketAddrV4,
+        mut recv_queue: SharedAsyncQueue<(IpAddr, TcpHeader, DemiBuffer)>,
+        remote: SocketAddr,
         local_isn: SeqNumber,
         remote_isn: SeqNumber,
         header_window_size: u16,
         remote_window_scale: Option<u8>,
         mss: usize,
+        sack_permitted: bool,
+        timestamp_clock: TimestampClock,
+        remote_timestamp: Option<u32>,
     ) -> Result<EstablishedSocket, Fail> {
-        let (ipv4_hdr, tcp_hdr, buf) = recv_queue.pop(None).await?;
+        let (ip_hdr, tcp_hdr, buf) = recv_queue.pop(None).await?;
         debug!("Received ACK: {:?}", tcp_hdr);
 
         // Check the ack sequence number.
@@ -438,6 +622,12 @@ impl SharedPassiveSocket {
             return Err(Fail::new(EBADMSG, "invalid SYN+ACK seq num"));
         }
 
+        // Start echoing the most recent timestamp of our peer, if we agreed on using timestamps.
+        let ts_recent: Option<u32> = remote_timestamp.map(|remote_timestamp| match get_timestamp_option(&tcp_hdr) {
+            Some((sender_timestamp, _)) => sender_timestamp,
+            None => remote_timestamp,
+        });
+
         // Calculate the window.
         let (local_window_scale, remote_window_scale): (u32, u8) = match remote_window_scale {
             Some(remote_window_scale) => {
@@ -478,7 +668,7 @@ impl SharedPassiveSocket {
 
         // If there is data with the SYN+ACK, deliver it.
         if !buf.is_empty() {
-            recv_queue.push((ipv4_hdr, tcp_hdr, buf));
+            recv_queue.push((ip_hdr, tcp_hdr, buf));
         }
 
         let new_socket: EstablishedSocket = EstablishedSocket::new(
@@ -487,7 +677,6 @@ impl SharedPassiveSocket {
             self.runtime.clone(),
             self.layer3_endpoint.clone(),
             recv_queue.clone(),
-            ack_queue,
             self.tcp_config.clone(),
             self.socket_options,
             remote_isn + SeqNumber::from(1),
@@ -498,7 +687,10 @@ impl SharedPassiveSocket {
             remote_window_size,
             remote_window_scale,
             mss,
-            congestion_control::None::new,
+            sack_permitted,
+            timestamp_clock,
+            ts_recent,
+            congestion_control::get_constructor(self.socket_options.get_congestion_control()),
             None,
             self.dead_socket_tx.clone(),
             Some(self.socket_queue.clone()),
@@ -8,7 +8,10 @@
 use crate::{
     demikernel::config::Config,
     inetstack::protocols::{
-        layer3::SharedLayer3Endpoint,
+        layer3::{
+            icmpv4::Icmpv4Error,
+            SharedLayer3Endpoint,
+        },
         layer4::tcp::{
             header::TcpHeader,
             isn_generator::IsnGenerator,
@@ -36,15 +39,17 @@ use crate::{
 };
 use ::futures::channel::mpsc;
 use ::rand::{
-    prelude::SmallRng,
+    rngs::OsRng,
     Rng,
-    SeedableRng,
 };
 
 use ::std::{
     collections::HashMap,
     net::{
+        IpAddr,
         Ipv4Addr,
+        Ipv6Addr,
+        Shutdown,
         SocketAddr,
         SocketAddrV4,
     },
@@ -63,9 +68,9 @@ pub struct TcpPeer {
     isn_generator: IsnGenerator,
     layer3_endpoint: SharedLayer3Endpoint,
     local_ipv4_addr: Ipv4Addr,
+    local_ipv6_addr: Ipv6Addr,
     tcp_config: TcpConfig,
     default_socket_options: TcpSocketOptions,
-    rng: SmallRng,
     dead_socket_tx: mpsc::UnboundedSender<QDesc>,
     addresses: HashMap<SocketId, SharedTcpSocket>,
 }
@@ -82,19 +87,23 @@ impl SharedTcpPeer {
         config: &Config,
         runtime: SharedDemiRuntime,
         layer3_endpoint: SharedLayer3Endpoint,
-        rng_seed: [u8; 32],
     ) -> Result<Self, Fail> {
-        let mut rng: SmallRng = SmallRng::from_seed(rng_seed);
-        let nonce: u32 = rng.gen();
+        // Use a per-stack secret key for generating initial sequence numbers (see RFC 6528 Section 3). The key must not
+        // be predictable, so it comes from the operating system rather than from the seeded generator of the stack.
+        let isn_key: (u64, u64) = OsRng.gen();
         let (tx, _) = mpsc::unbounded();
         Ok(Self(SharedObject::<TcpPeer>::new(TcpPeer {
-            isn_generator: IsnGenerator::new(nonce),
+            isn_generator: IsnGenerator::new(isn_key, runtime.get_now()),
             runtime,
             layer3_endpoint,
-            local_ipv4_addr: config.local_ipv4_addr()?,
+            local_ipv4_addr: config
+                .local_ipv4_addr()
+                .ok_or_else(|| Config::missing_option("local_ipv4_addr"))?,
+            local_ipv6_addr: config
+                .local_ipv6_addr()
+                .ok_or_else(|| Config::missing_option("local_ipv6_addr"))?,
             tcp_config: TcpConfig::new(config)?,
             default_socket_options: TcpSocketOptions::new(config)?,
-            rng,
             dead_socket_tx: tx,
             addresses: HashMap::<SocketId, SharedTcpSocket>::new(),
         })))
@@ -126,14 +135,24 @@ impl SharedTcpPeer {
     }
 
     /// Gets a peer address on a TCP socket.
-    pub fn getpeername(&mut self, socket: &mut SharedTcpSocket) -> Result<SocketAddrV4, Fail> {
+    pub fn getpeername(&mut self, socket: &mut SharedTcpSocket) -> Result<SocketAddr, Fail> {
         socket.getpeername()
     }
 
+    /// Gets the local address of a TCP socket.
+    pub fn getsockname(&self, socket: &SharedTcpSocket) -> Result<SocketAddr, Fail> {
+        socket.getsockname()
+    }
+
+    /// Shuts down receiving, sending or both on a TCP connection.
+    pub fn shutdown(&mut self, socket: &mut SharedTcpSocket, how: Shutdown) -> Result<(), Fail> {
+        socket.shutdown(how)
+    }
+
     /// Binds a socket to a local address supplied by [local].
-    pub fn bind(&mut self, socket: &mut SharedTcpSocket, local: SocketAddrV4) -> Result<(), Fail> {
+    pub fn bind(&mut self, socket: &mut SharedTcpSocket, local: SocketAddr) -> Result<(), Fail> {
         // All other checks should have been done already.
-        debug_assert!(!Ipv4Addr::is_unspecified(local.ip()));
+        debug_assert!(!IpAddr::is_unspecified(&local.ip()));
         debug_assert!(local.port() != 0);
         debug_assert!(self.addresses.get(&SocketId::Passive(local)).is_none());
 
@@ -147,8 +166,9 @@ impl SharedTcpPeer {
     pub fn listen(&mut self, socket: &mut SharedTcpSocket, backlog: usize) -> Result<(), Fail> {
         // Most checks should have been performed already
         debug_assert!(socket.local().is_some());
-        let nonce: u32 = self.rng.gen();
-        socket.listen(backlog, nonce)
+        // Each listening socket authenticates its SYN cookies with its own secret key.
+        let cookie_key: (u64, u64) = OsRng.gen();
+        socket.listen(backlog, self.isn_generator, cookie_key)
     }
 
     /// Runs until a new connection is accepted.
@@ -167,24 +187,30 @@ impl SharedTcpPeer {
     }
 
     /// Runs until the connect to remote is made or times out.
-    pub async fn connect(&mut self, socket: &mut SharedTcpSocket, remote: SocketAddrV4) -> Result<(), Fail> {
+    pub async fn connect(&mut self, socket: &mut SharedTcpSocket, remote: SocketAddr) -> Result<(), Fail> {
         // Check whether we need to allocate an ephemeral port.
-        let local: SocketAddrV4 = match socket.local() {
+        let local: SocketAddr = match socket.local() {
+            Some(addr) if addr.is_ipv4() != remote.is_ipv4() => {
+                let cause: String = format!("address family does not match bound address (remote={:?})", remote);
+                error!("connect(): {}", &cause);
+                return Err(Fail::new(libc::EAFNOSUPPORT, &cause));
+            },
             Some(addr) => {
                 // If socket is already bound to a local address, use it but remove the old binding.
                 self.addresses.remove(&SocketId::Passive(addr));
                 addr
             },
             None => {
-                let local_port: u16 = self.runtime.alloc_ephemeral_port()?;
-                SocketAddrV4::new(self.local_ipv4_addr, local_port)
+                let local_ip_addr: IpAddr = self.get_local_ip_addr(&remote.ip());
+                let local_port: u16 = self.runtime.alloc_ephemeral_port_for(local_ip_addr, remote)?;
+                SocketAddr::new(local_ip_addr, local_port)
             },
         };
         // Insert the connection to receive incoming packets for this address pair.
         // Should we remove the passive entry for the local address if the socket was previously bound?
         if self
             .addresses
-            .insert(SocketId::Active(local, remote.clone()), socket.clone())
+            .insert(SocketId::Active(local, remote), socket.clone())
             .is_some()
         {
             // We should panic here because the ephemeral port allocator should not allocate the same port more than
@@ -194,10 +220,10 @@ impl SharedTcpPeer {
                 local, remote
             );
         }
-        let local_isn: SeqNumber = self.isn_generator.generate(&local, &remote);
+        let local_isn: SeqNumber = self.isn_generator.generate(&local, &remote, self.runtime.get_now());
         // Wait for connect to complete.
         if let Err(e) = socket.connect(local, remote, local_isn).await {
-            self.addresses.remove(&SocketId::Active(local, remote.clone()));
+            self.addresses.remove(&SocketId::Active(local, remote));
             Err(e)
         } else {
             Ok(())
@@ -209,7 +235,9 @@ impl SharedTcpPeer {
         // TODO: Remove this copy after merging with the transport trait.
         // Wait for push to complete.
         socket.push(buf.clone()).await?;
-        buf.trim(buf.len())
+        // Clear out the original buffer, which may be a chain.
+        *buf = DemiBuffer::new(0);
+        Ok(())
     }
 
     /// Sets up a coroutine for popping data from the socket.
@@ -225,9 +253,22 @@ impl SharedTcpPeer {
         Ok((None, incoming))
     }
 
+    /// Rolls back a connect that was cancelled before it completed, so that the socket can connect again.
+    pub fn cancel_connect(&mut self, socket: &mut SharedTcpSocket) {
+        if let Some((local, remote, bound)) = socket.cancel_connect() {
+            self.addresses.remove(&SocketId::Active(local, remote));
+            if bound {
+                // Restore the binding that connect() took over.
+                self.addresses.insert(SocketId::Passive(local), socket.clone());
+            } else {
+                self.free_ephemeral_port(&SocketId::Active(local, remote));
+            }
+        }
+    }
+
     /// Frees an ephemeral port (if any) allocated to a given socket.
     fn free_ephemeral_port(&mut self, socket_id: &SocketId) {
-        let local: &SocketAddrV4 = match socket_id {
+        let local: &SocketAddr = match socket_id {
             SocketId::Active(local, _) => local,
             SocketId::Passive(local) => local,
         };
@@ -262,12 +303,13 @@ impl SharedTcpPeer {
     }
 
     /// Processes an incoming TCP segment.
-    pub fn receive(&mut self, src_ipv4_addr: Ipv4Addr, mut buf: DemiBuffer) {
-        // We can assume that the destination is our local IPv4 address; otherwise, the IP layer would have discarded
+    pub fn receive(&mut self, src_ip_addr: IpAddr, mut buf: DemiBuffer) {
+        // We can assume that the destination is our local IP address; otherwise, the IP layer would have discarded
         // the packet already.
+        let local_ip_addr: IpAddr = self.get_local_ip_addr(&src_ip_addr);
         let tcp_hdr: TcpHeader = match TcpHeader::parse_and_strip(
-            &src_ipv4_addr,
-            &self.local_ipv4_addr,
+            &src_ip_addr,
+            &local_ip_addr,
             &mut buf,
             self.tcp_config.get_rx_checksum_offload(),
         ) {
@@ -279,8 +321,8 @@ impl SharedTcpPeer {
             },
         };
         debug!("TCP received {:?}", tcp_hdr);
-        let local: SocketAddrV4 = SocketAddrV4::new(self.local_ipv4_addr, tcp_hdr.dst_port);
-        let remote: SocketAddrV4 = SocketAddrV4::new(src_ipv4_addr, tcp_hdr.src_port);
+        let local: SocketAddr = SocketAddr::new(local_ip_addr, tcp_hdr.dst_port);
+        let remote: SocketAddr = SocketAddr::new(src_ip_addr, tcp_hdr.src_port);
 
         // Retrieve the queue descriptor based on the incoming segment.
         let socket: &mut SharedTcpSocket = match self.addresses.get_mut(&SocketId::Active(local, remote)) {
@@ -296,7 +338,32 @@ impl SharedTcpPeer {
         };
 
         // Dispatch to further processing depending on the socket state.
-        socket.receive(src_ipv4_addr, tcp_hdr, buf)
+        socket.receive(src_ip_addr, tcp_hdr, buf)
+    }
+
+    /// Processes an ICMPv4 error about a segment that we sent.
+    pub fn receive_icmpv4_error(&mut self, error: &Icmpv4Error) {
+        let (local, remote): (SocketAddrV4, SocketAddrV4) = error.get_endpoints();
+        let socket_id: SocketId = SocketId::Active(SocketAddr::V4(local), SocketAddr::V4(remote));
+        let socket: &mut SharedTcpSocket = match self.addresses.get_mut(&socket_id) {
+            Some(socket) => socket,
+            None => {
+                debug!("ignoring ICMPv4 error for unknown connection (remote={})", remote);
+                return;
+            },
+        };
+        // The quoted bytes of our segment hold its sequence number, which tells whether the error is genuine.
+        let payload: &[u8] = error.get_payload();
+        let seq_num: SeqNumber = SeqNumber::from(u32::from_be_bytes([payload[4], payload[5], payload[6], payload[7]]));
+        socket.receive_icmpv4_error(seq_num, error)
+    }
+
+    /// Returns our local address in the same address family as [remote_ip_addr].
+    fn get_local_ip_addr(&self, remote_ip_addr: &IpAddr) -> IpAddr {
+        match remote_ip_addr {
+            IpAddr::V4(_) => IpAddr::V4(self.local_ipv4_addr),
+            IpAddr::V6(_) => IpAddr::V6(self.local_ipv6_addr),
+        }
     }
 }
 
@@ -0,0 +1,78 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Computes the SipHash-2-4 of [data] under [key].
+pub fn siphash24(key: (u64, u64), data: &[u8]) -> u64 {
+    let (k0, k1): (u64, u64) = key;
+    let mut v: [u64; 4] = [
+        k0 ^ 0x736f6d6570736575,
+        k1 ^ 0x646f72616e646f6d,
+        k0 ^ 0x6c7967656e657261,
+        k1 ^ 0x7465646279746573,
+    ];
+
+    let chunks = data.chunks_exact(8);
+    // The last word holds the remaining bytes and the length of the data.
+    let mut last: u64 = (data.len() as u64) << 56;
+    for (i, byte) in chunks.remainder().iter().enumerate() {
+        last |= (*byte as u64) << (8 * i);
+    }
+    for chunk in chunks {
+        let mut bytes: [u8; 8] = [0; 8];
+        bytes.copy_from_slice(chunk);
+        let m: u64 = u64::from_le_bytes(bytes);
+        v[3] ^= m;
+        sipround(&mut v);
+        sipround(&mut v);
+        v[0] ^= m;
+    }
+    v[3] ^= last;
+    sipround(&mut v);
+    sipround(&mut v);
+    v[0] ^= last;
+
+    v[2] ^= 0xff;
+    for _ in 0..4 {
+        sipround(&mut v);
+    }
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
+
+fn sipround(v: &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13) ^ v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16) ^ v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21) ^ v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17) ^ v[2];
+    v[2] = v[2].rotate_left(32);
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::siphash24;
+    use ::anyhow::Result;
+
+    // Test SipHash-2-4 against the reference test vectors.
+    #[test]
+    fn siphash_test_vectors() -> Result<()> {
+        let key: (u64, u64) = (0x0706050403020100, 0x0f0e0d0c0b0a0908);
+        let data: Vec<u8> = (0..16).collect();
+        crate::ensure_eq!(siphash24(key, &data[..0]), 0x726fdb47dd0e0e31);
+        crate::ensure_eq!(siphash24(key, &data[..8]), 0x93f5f5799a932462);
+        crate::ensure_eq!(siphash24(key, &data[..15]), 0xa129ca6149be45e5);
+
+        Ok(())
+    }
+}
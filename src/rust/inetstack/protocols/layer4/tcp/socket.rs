@@ -9,11 +9,15 @@ use crate::{
     collections::async_queue::SharedAsyncQueue,
     expect_some,
     inetstack::protocols::{
-        layer3::SharedLayer3Endpoint,
+        layer3::{
+            icmpv4::Icmpv4Error,
+            SharedLayer3Endpoint,
+        },
         layer4::tcp::{
             active_open::SharedActiveOpenSocket,
             established::EstablishedSocket,
             header::TcpHeader,
+            isn_generator::IsnGenerator,
             passive_open::SharedPassiveSocket,
             SeqNumber,
         },
@@ -40,8 +44,9 @@ use ::futures::channel::mpsc;
 use ::std::{
     fmt::Debug,
     net::{
-        Ipv4Addr,
-        SocketAddrV4,
+        IpAddr,
+        Shutdown,
+        SocketAddr,
     },
     ops::{
         Deref,
@@ -56,7 +61,7 @@ use ::std::{
 
 pub enum SocketState {
     Unbound,
-    Bound(SocketAddrV4),
+    Bound(SocketAddr),
     Listening(SharedPassiveSocket),
     Connecting(SharedActiveOpenSocket),
     Established(EstablishedSocket),
@@ -70,7 +75,9 @@ pub enum SocketState {
 /// Per-queue metadata for the TCP socket.
 pub struct TcpSocket {
     state: SocketState,
-    recv_queue: Option<SharedAsyncQueue<(Ipv4Addr, TcpHeader, DemiBuffer)>>,
+    /// Whether the socket was bound before it started to connect, so that a cancelled connect can restore the binding.
+    bound_before_connect: bool,
+    recv_queue: Option<SharedAsyncQueue<(IpAddr, TcpHeader, DemiBuffer)>>,
     runtime: SharedDemiRuntime,
     layer3_endpoint: SharedLayer3Endpoint,
     tcp_config: TcpConfig,
@@ -95,6 +102,7 @@ impl SharedTcpSocket {
     ) -> Self {
         Self(SharedObject::<TcpSocket>::new(TcpSocket {
             state: SocketState::Unbound,
+            bound_before_connect: false,
             recv_queue: None,
             runtime,
             layer3_endpoint,
@@ -112,9 +120,10 @@ impl SharedTcpSocket {
         default_socket_options: TcpSocketOptions,
         dead_socket_tx: mpsc::UnboundedSender<QDesc>,
     ) -> Self {
-        let recv_queue: SharedAsyncQueue<(Ipv4Addr, TcpHeader, DemiBuffer)> = socket.get_recv_queue();
+        let recv_queue: SharedAsyncQueue<(IpAddr, TcpHeader, DemiBuffer)> = socket.get_recv_queue();
         Self(SharedObject::<TcpSocket>::new(TcpSocket {
             state: SocketState::Established(socket),
+            bound_before_connect: false,
             recv_queue: Some(recv_queue),
             runtime,
             layer3_endpoint,
@@ -130,6 +139,41 @@ impl SharedTcpSocket {
             SocketOption::Linger(linger) => self.socket_options.set_linger(linger),
             SocketOption::KeepAlive(keep_alive) => self.socket_options.set_keepalive(keep_alive),
             SocketOption::NoDelay(no_delay) => self.socket_options.set_nodelay(no_delay),
+            SocketOption::CongestionControl(congestion_control) => {
+                // The congestion control algorithm is picked when a connection is set up, so it has to be selected
+                // before calling connect() or listen().
+                let can_change: bool = matches!(self.state, SocketState::Unbound | SocketState::Bound(_));
+                if !can_change && congestion_control != self.socket_options.get_congestion_control() {
+                    let cause: String = format!(
+                        "cannot change congestion control after connect() or listen() (algorithm={})",
+                        congestion_control
+                    );
+                    error!("set_socket_option(): {}", cause);
+                    return Err(Fail::new(libc::EISCONN, &cause));
+                }
+                self.socket_options.set_congestion_control(congestion_control)
+            },
+            SocketOption::MaxPacingRate(Some(0)) => {
+                let cause: &str = "maximum pacing rate must be positive";
+                error!("set_socket_option(): {}", cause);
+                return Err(Fail::new(libc::EINVAL, cause));
+            },
+            SocketOption::MaxPacingRate(max_pacing_rate) => self.socket_options.set_max_pacing_rate(max_pacing_rate),
+            SocketOption::RecvBufSize(_)
+            | SocketOption::SendBufSize(_)
+            | SocketOption::Broadcast(_)
+            | SocketOption::ReuseAddr(_)
+            | SocketOption::AddMembership(..)
+            | SocketOption::DropMembership(..) => {
+                let cause: String = format!("option is not supported on TCP sockets (option={:?})", option);
+                error!("set_socket_option(): {}", cause);
+                return Err(Fail::new(libc::ENOPROTOOPT, &cause));
+            },
+        }
+        // Established connections keep their own copy of the options.
+        let socket_options: TcpSocketOptions = self.socket_options;
+        if let SocketState::Established(ref mut socket) = self.state {
+            socket.set_socket_options(socket_options);
         }
         Ok(())
     }
@@ -141,14 +185,30 @@ impl SharedTcpSocket {
             SocketOption::Linger(_) => Ok(SocketOption::Linger(self.socket_options.get_linger())),
             SocketOption::KeepAlive(_) => Ok(SocketOption::KeepAlive(self.socket_options.get_keepalive())),
             SocketOption::NoDelay(_) => Ok(SocketOption::NoDelay(self.socket_options.get_nodelay())),
+            SocketOption::CongestionControl(_) => Ok(SocketOption::CongestionControl(
+                self.socket_options.get_congestion_control(),
+            )),
+            SocketOption::MaxPacingRate(_) => {
+                Ok(SocketOption::MaxPacingRate(self.socket_options.get_max_pacing_rate()))
+            },
+            SocketOption::RecvBufSize(_)
+            | SocketOption::SendBufSize(_)
+            | SocketOption::Broadcast(_)
+            | SocketOption::ReuseAddr(_)
+            | SocketOption::AddMembership(..)
+            | SocketOption::DropMembership(..) => {
+                let cause: String = format!("option is not supported on TCP sockets (option={:?})", option);
+                error!("get_socket_option(): {}", cause);
+                Err(Fail::new(libc::ENOPROTOOPT, &cause))
+            },
         }
     }
 
     /// Gets the peer address of the socket.
-    pub fn getpeername(&mut self) -> Result<SocketAddrV4, Fail> {
+    pub fn getpeername(&mut self) -> Result<SocketAddr, Fail> {
         match self.state {
             SocketState::Established(ref mut socket) => {
-                let (_, remote_endpoint): (SocketAddrV4, SocketAddrV4) = socket.endpoints();
+                let (_, remote_endpoint): (SocketAddr, SocketAddr) = socket.endpoints();
                 return Ok(remote_endpoint);
             },
             _ => {
@@ -159,16 +219,40 @@ impl SharedTcpSocket {
         }
     }
 
+    /// Gets the local address of the socket.
+    pub fn getsockname(&self) -> Result<SocketAddr, Fail> {
+        match self.local() {
+            Some(local) => Ok(local),
+            None => {
+                let cause: &str = "socket is not bound";
+                error!("getsockname(): {}", cause);
+                Err(Fail::new(libc::EINVAL, cause))
+            },
+        }
+    }
+
+    /// Shuts down receiving, sending or both on the connection.
+    pub fn shutdown(&mut self, how: Shutdown) -> Result<(), Fail> {
+        match self.state {
+            SocketState::Established(ref mut socket) => socket.shutdown(how),
+            _ => {
+                let cause: &str = "socket is not in established state";
+                error!("shutdown(): {}", cause);
+                Err(Fail::new(libc::ENOTCONN, cause))
+            },
+        }
+    }
+
     /// Binds the target queue to `local` address.
-    pub fn bind(&mut self, local: SocketAddrV4) -> Result<(), Fail> {
+    pub fn bind(&mut self, local: SocketAddr) -> Result<(), Fail> {
         self.state = SocketState::Bound(local);
         Ok(())
     }
 
     /// Sets the target queue to listen for incoming connections.
-    pub fn listen(&mut self, backlog: usize, nonce: u32) -> Result<(), Fail> {
-        let recv_queue: SharedAsyncQueue<(Ipv4Addr, TcpHeader, DemiBuffer)> =
-            SharedAsyncQueue::<(Ipv4Addr, TcpHeader, DemiBuffer)>::default();
+    pub fn listen(&mut self, backlog: usize, isn_generator: IsnGenerator, cookie_key: (u64, u64)) -> Result<(), Fail> {
+        let recv_queue: SharedAsyncQueue<(IpAddr, TcpHeader, DemiBuffer)> =
+            SharedAsyncQueue::<(IpAddr, TcpHeader, DemiBuffer)>::default();
         self.state = SocketState::Listening(SharedPassiveSocket::new(
             expect_some!(
                 self.local(),
@@ -181,7 +265,8 @@ impl SharedTcpSocket {
             self.tcp_config.clone(),
             self.socket_options.clone(),
             self.dead_socket_tx.clone(),
-            nonce,
+            isn_generator,
+            cookie_key,
         )?);
         self.recv_queue = Some(recv_queue);
         Ok(())
@@ -206,15 +291,9 @@ impl SharedTcpSocket {
         Ok(new_queue)
     }
 
-    pub async fn connect(
-        &mut self,
-        local: SocketAddrV4,
-        remote: SocketAddrV4,
-        local_isn: SeqNumber,
-    ) -> Result<(), Fail> {
-        let recv_queue: SharedAsyncQueue<(Ipv4Addr, TcpHeader, DemiBuffer)> =
-            SharedAsyncQueue::<(Ipv4Addr, TcpHeader, DemiBuffer)>::default();
-        let ack_queue: SharedAsyncQueue<usize> = SharedAsyncQueue::<usize>::default();
+    pub async fn connect(&mut self, local: SocketAddr, remote: SocketAddr, local_isn: SeqNumber) -> Result<(), Fail> {
+        let recv_queue: SharedAsyncQueue<(IpAddr, TcpHeader, DemiBuffer)> =
+            SharedAsyncQueue::<(IpAddr, TcpHeader, DemiBuffer)>::default();
         // Create active socket.
         let socket: SharedActiveOpenSocket = SharedActiveOpenSocket::new(
             local_isn,
@@ -223,11 +302,11 @@ impl SharedTcpSocket {
             self.runtime.clone(),
             self.layer3_endpoint.clone(),
             recv_queue.clone(),
-            ack_queue,
             self.tcp_config.clone(),
             self.socket_options.clone(),
             self.dead_socket_tx.clone(),
         )?;
+        self.bound_before_connect = matches!(self.state, SocketState::Bound(_));
         self.state = SocketState::Connecting(socket.clone());
         self.recv_queue = Some(recv_queue);
         let new_socket = socket.connect().await?;
@@ -235,15 +314,33 @@ impl SharedTcpSocket {
         Ok(())
     }
 
+    /// Rolls back a connect that was cancelled before it completed. On success, this returns the endpoints of the
+    /// connection and whether the socket was bound before it started to connect.
+    pub fn cancel_connect(&mut self) -> Option<(SocketAddr, SocketAddr, bool)> {
+        let (local, remote): (SocketAddr, SocketAddr) = match self.state {
+            SocketState::Connecting(ref mut socket) => {
+                socket.close();
+                socket.endpoints()
+            },
+            _ => return None,
+        };
+        self.state = if self.bound_before_connect {
+            SocketState::Bound(local)
+        } else {
+            SocketState::Unbound
+        };
+        self.recv_queue = None;
+        Some((local, remote, self.bound_before_connect))
+    }
+
     pub async fn push(&mut self, buf: DemiBuffer) -> Result<(), Fail> {
         // Send synchronously.
         match self.state {
             SocketState::Established(ref mut socket) => {
-                let size: usize = buf.len();
                 // Send the packet.
-                socket.send(buf)?;
+                let end: SeqNumber = socket.send(buf)?;
                 // Wait for ack.
-                socket.push(size).await
+                socket.push(end).await
             },
             _ => unreachable!("State machine check should ensure that this socket is connected"),
         }
@@ -326,7 +423,7 @@ impl SharedTcpSocket {
         }
     }
 
-    pub fn endpoints(&self) -> Result<(SocketAddrV4, SocketAddrV4), Fail> {
+    pub fn endpoints(&self) -> Result<(SocketAddr, SocketAddr), Fail> {
         match self.state {
             SocketState::Established(ref socket) => Ok(socket.endpoints()),
             SocketState::Connecting(ref socket) => Ok(socket.endpoints()),
@@ -334,7 +431,7 @@ impl SharedTcpSocket {
         }
     }
 
-    pub fn receive(&mut self, ip_hdr: Ipv4Addr, tcp_hdr: TcpHeader, buf: DemiBuffer) {
+    pub fn receive(&mut self, ip_hdr: IpAddr, tcp_hdr: TcpHeader, buf: DemiBuffer) {
         // If this queue has an allocated receive queue, then direct the packet there.
         if let Some(recv_queue) = self.recv_queue.as_mut() {
             recv_queue.push((ip_hdr, tcp_hdr, buf));
@@ -342,8 +439,17 @@ impl SharedTcpSocket {
         }
     }
 
+    /// Processes an ICMPv4 error about the segment starting at [seq_num] that we sent on this connection.
+    pub fn receive_icmpv4_error(&mut self, seq_num: SeqNumber, error: &Icmpv4Error) {
+        match self.state {
+            SocketState::Connecting(ref mut socket) => socket.receive_icmpv4_error(seq_num, error),
+            SocketState::Established(ref mut socket) => socket.cb.receive_icmpv4_error(seq_num, error),
+            _ => (),
+        }
+    }
+
     /// Returns the local address to which the target queue is bound.
-    pub fn local(&self) -> Option<SocketAddrV4> {
+    pub fn local(&self) -> Option<SocketAddr> {
         match self.state {
             SocketState::Unbound => None,
             SocketState::Bound(addr) => Some(addr),
@@ -355,7 +461,7 @@ impl SharedTcpSocket {
     }
 
     /// Returns the remote address to which the target queue is connected to.
-    pub fn remote(&self) -> Option<SocketAddrV4> {
+    pub fn remote(&self) -> Option<SocketAddr> {
         match self.state {
             SocketState::Unbound => None,
             SocketState::Bound(_) => None,
@@ -0,0 +1,271 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+// This file defines the generator of SYN cookies.
+//
+// When the backlog of a passive socket is full, we can answer a SYN without keeping any state around by encoding that
+// state in the initial sequence number of our SYN+ACK (see RFC 4987, Section 3.6).  Our peer echoes this number back in
+// the ACK that completes the handshake, at which point we validate it and rebuild the connection from it.  A cookie is
+// laid out as follows:
+//
+//   31      27 26  24 23                                            0
+//  +----------+------+-----------------------------------------------+
+//  |  count   | mss  |                       hash                    |
+//  +----------+------+-----------------------------------------------+
+//
+// where "count" is the low bits of a counter that ticks every 64 seconds, "mss" indexes a table of common MSS values,
+// and "hash" is a keyed hash (SipHash-2-4) of the connection endpoints, the initial sequence number of our peer, the
+// full counter and the MSS index under a 128-bit secret key.  The counter lets us reject cookies that are too old.
+// Since there is no room for anything else, connections that are established from cookies do not use window scaling,
+// SACK or timestamps.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::inetstack::protocols::layer4::tcp::{
+    constants::FALLBACK_MSS,
+    siphash::siphash24,
+    SeqNumber,
+};
+use ::std::{
+    net::{
+        IpAddr,
+        SocketAddr,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// MSS values that we can encode in a cookie.
+const MSS_TABLE: [usize; 8] = [FALLBACK_MSS, 1024, 1220, 1300, 1400, 1440, 1460, 8960];
+
+/// Period of the counter that we encode in a cookie.
+const COUNTER_PERIOD: Duration = Duration::from_secs(64);
+
+/// Number of counter periods for which a cookie remains valid.
+const MAX_COOKIE_AGE: u32 = 2;
+
+const COUNTER_SHIFT: u32 = 27;
+const COUNTER_MASK: u32 = 0x1f;
+const MSS_SHIFT: u32 = 24;
+const MSS_MASK: u32 = 0x7;
+const HASH_MASK: u32 = 0x00ff_ffff;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Generator and validator of SYN cookies.
+pub struct SynCookieGenerator {
+    /// Secret key of the keyed hash.
+    key: (u64, u64),
+    origin: Instant,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl SynCookieGenerator {
+    /// Creates a SYN cookie generator that authenticates cookies with [key]. The counter that we encode in cookies
+    /// starts at [origin].
+    pub fn new(key: (u64, u64), origin: Instant) -> Self {
+        Self { key, origin }
+    }
+
+    /// Generates the initial sequence number of a SYN+ACK that answers a SYN from [remote] carrying [remote_isn] and
+    /// advertising [mss]. Returns the cookie along with the MSS that it encodes, which is the one that we should use
+    /// for the connection.
+    pub fn generate(
+        &self,
+        local: &SocketAddr,
+        remote: &SocketAddr,
+        remote_isn: SeqNumber,
+        mss: usize,
+        now: Instant,
+    ) -> (SeqNumber, usize) {
+        // Pick the largest MSS in the table that our peer is able to handle.
+        let mss_index: u32 = MSS_TABLE.iter().rposition(|entry| *entry <= mss).unwrap_or(0) as u32;
+        let counter: u32 = self.get_counter(now);
+        let hash: u32 = self.hash(local, remote, remote_isn, counter, mss_index);
+        let cookie: u32 = ((counter & COUNTER_MASK) << COUNTER_SHIFT) | (mss_index << MSS_SHIFT) | (hash & HASH_MASK);
+        (SeqNumber::from(cookie), MSS_TABLE[mss_index as usize])
+    }
+
+    /// Validates the [cookie] that [remote] echoed back in the ACK that completes a handshake. Returns the MSS that we
+    /// encoded in the cookie or None if the cookie is not valid.
+    pub fn validate(
+        &self,
+        local: &SocketAddr,
+        remote: &SocketAddr,
+        remote_isn: SeqNumber,
+        cookie: SeqNumber,
+        now: Instant,
+    ) -> Option<usize> {
+        let cookie: u32 = u32::from(cookie);
+        let counter: u32 = self.get_counter(now);
+
+        // Recover the full counter from its low bits, and check that the cookie is recent enough.
+        let age: u32 = counter.wrapping_sub(cookie >> COUNTER_SHIFT) & COUNTER_MASK;
+        if age > MAX_COOKIE_AGE {
+            return None;
+        }
+        let counter: u32 = counter.wrapping_sub(age);
+
+        let mss_index: u32 = (cookie >> MSS_SHIFT) & MSS_MASK;
+        let hash: u32 = self.hash(local, remote, remote_isn, counter, mss_index);
+        if cookie & HASH_MASK != hash & HASH_MASK {
+            return None;
+        }
+        Some(MSS_TABLE[mss_index as usize])
+    }
+
+    /// Returns the value of the cookie counter at [now].
+    fn get_counter(&self, now: Instant) -> u32 {
+        (now.saturating_duration_since(self.origin).as_secs() / COUNTER_PERIOD.as_secs()) as u32
+    }
+
+    /// Computes the digest that authenticates a cookie.
+    fn hash(
+        &self,
+        local: &SocketAddr,
+        remote: &SocketAddr,
+        remote_isn: SeqNumber,
+        counter: u32,
+        mss_index: u32,
+    ) -> u32 {
+        let mut data: Vec<u8> = Vec::with_capacity(48);
+        match remote.ip() {
+            IpAddr::V4(addr) => data.extend_from_slice(&addr.octets()),
+            IpAddr::V6(addr) => data.extend_from_slice(&addr.octets()),
+        }
+        data.extend_from_slice(&remote.port().to_be_bytes());
+        match local.ip() {
+            IpAddr::V4(addr) => data.extend_from_slice(&addr.octets()),
+            IpAddr::V6(addr) => data.extend_from_slice(&addr.octets()),
+        }
+        data.extend_from_slice(&local.port().to_be_bytes());
+        data.extend_from_slice(&u32::from(remote_isn).to_be_bytes());
+        data.extend_from_slice(&counter.to_be_bytes());
+        data.extend_from_slice(&mss_index.to_be_bytes());
+        siphash24(self.key, &data) as u32
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::inetstack::protocols::layer4::tcp::{
+        syn_cookie::SynCookieGenerator,
+        SeqNumber,
+    };
+    use ::anyhow::Result;
+    use ::std::{
+        net::SocketAddr,
+        time::{
+            Duration,
+            Instant,
+        },
+    };
+
+    const LOCAL: &str = "192.168.1.1:12345";
+    const REMOTE: &str = "192.168.1.2:23456";
+
+    // Test that a cookie is valid for the connection request that it answers.
+    #[test]
+    fn validate_cookie() -> Result<()> {
+        let now: Instant = Instant::now();
+        let local: SocketAddr = LOCAL.parse()?;
+        let remote: SocketAddr = REMOTE.parse()?;
+        let generator: SynCookieGenerator = SynCookieGenerator::new((1, 2), now);
+
+        let (cookie, mss): (SeqNumber, usize) = generator.generate(&local, &remote, SeqNumber::from(1000), 1450, now);
+        crate::ensure_eq!(mss, 1440);
+        crate::ensure_eq!(
+            generator.validate(
+                &local,
+                &remote,
+                SeqNumber::from(1000),
+                cookie,
+                now + Duration::from_secs(100)
+            ),
+            Some(1440)
+        );
+
+        Ok(())
+    }
+
+    // Test that a cookie is not valid for other connection requests.
+    #[test]
+    fn reject_forged_cookie() -> Result<()> {
+        let now: Instant = Instant::now();
+        let local: SocketAddr = LOCAL.parse()?;
+        let remote: SocketAddr = REMOTE.parse()?;
+        let other_remote: SocketAddr = "192.168.1.2:23457".parse()?;
+        let generator: SynCookieGenerator = SynCookieGenerator::new((1, 2), now);
+        let other_generator: SynCookieGenerator = SynCookieGenerator::new((3, 4), now);
+
+        let (cookie, _): (SeqNumber, usize) = generator.generate(&local, &remote, SeqNumber::from(1000), 1460, now);
+        crate::ensure_eq!(
+            generator.validate(&local, &other_remote, SeqNumber::from(1000), cookie, now),
+            None
+        );
+        crate::ensure_eq!(
+            generator.validate(&local, &remote, SeqNumber::from(1001), cookie, now),
+            None
+        );
+        crate::ensure_eq!(
+            generator.validate(&local, &remote, SeqNumber::from(1000), cookie + SeqNumber::from(1), now),
+            None
+        );
+        crate::ensure_eq!(
+            other_generator.validate(&local, &remote, SeqNumber::from(1000), cookie, now),
+            None
+        );
+
+        Ok(())
+    }
+
+    // Test that a cookie expires.
+    #[test]
+    fn reject_expired_cookie() -> Result<()> {
+        let now: Instant = Instant::now();
+        let local: SocketAddr = LOCAL.parse()?;
+        let remote: SocketAddr = REMOTE.parse()?;
+        let generator: SynCookieGenerator = SynCookieGenerator::new((1, 2), now);
+
+        let (cookie, _): (SeqNumber, usize) = generator.generate(&local, &remote, SeqNumber::from(1000), 536, now);
+        crate::ensure_eq!(
+            generator.validate(
+                &local,
+                &remote,
+                SeqNumber::from(1000),
+                cookie,
+                now + Duration::from_secs(191)
+            ),
+            Some(536)
+        );
+        crate::ensure_eq!(
+            generator.validate(
+                &local,
+                &remote,
+                SeqNumber::from(1000),
+                cookie,
+                now + Duration::from_secs(192)
+            ),
+            None
+        );
+
+        Ok(())
+    }
+}
@@ -17,10 +17,15 @@ use crate::{
                 ipv4::Ipv4Header,
             },
             layer4::{
-                tcp::header::{
-                    TcpHeader,
-                    TcpOptions2,
-                    MAX_TCP_OPTIONS,
+                tcp::{
+                    header::{
+                        SelectiveAcknowlegement,
+                        TcpHeader,
+                        TcpOptions2,
+                        MAX_SACK_BLOCKS,
+                        MAX_TCP_OPTIONS,
+                    },
+                    SeqNumber,
                 },
                 udp::header::UdpHeader,
             },
@@ -37,6 +42,7 @@ use crate::{
     },
     runtime::{
         memory::DemiBuffer,
+        network::socket::option::SocketOption,
         OperationResult,
     },
     MacAddress,
@@ -55,6 +61,8 @@ use nettest::glue::{
     PacketEvent,
     PushArgs,
     PushToArgs,
+    SetSockOptArgs,
+    ShutdownArgs,
     SocketArgs,
     SyscallEvent,
     TcpPacket,
@@ -72,7 +80,9 @@ use std::{
         BufReader,
     },
     net::{
+        IpAddr,
         Ipv4Addr,
+        Shutdown,
         SocketAddrV4,
     },
     path::{
@@ -202,6 +212,8 @@ struct Simulation {
     engine: SharedEngine,
     now: Instant,
     inflight: Option<QToken>,
+    /// Outgoing frames that were emitted together but have not been checked yet.
+    outgoing_frames: VecDeque<DemiBuffer>,
     steps: Vec<String>,
 }
 
@@ -236,6 +248,7 @@ impl Simulation {
             local_qd: None,
             remote_qd: None,
             inflight: None,
+            outgoing_frames: VecDeque::new(),
             local_sockaddr: SocketAddrV4::new(local_ipv4.clone(), local_ephemeral_port),
             local_port,
             remote_sockaddr: SocketAddrV4::new(remote_ipv4.clone(), remote_ephemeral_port),
@@ -274,7 +287,8 @@ impl Simulation {
         }
 
         // Ensure that there are no more events to be processed.
-        let frames: VecDeque<DemiBuffer> = self.engine.pop_all_frames();
+        let mut frames: VecDeque<DemiBuffer> = self.outgoing_frames.split_off(0);
+        frames.append(&mut self.engine.pop_all_frames());
         if !frames.is_empty() {
             for frame in &frames {
                 info!("run(): {:?}", frame);
@@ -314,6 +328,10 @@ impl Simulation {
             nettest::glue::DemikernelSyscall::Pop(args, ret) => self.run_pop_syscall(args, ret.clone())?,
             nettest::glue::DemikernelSyscall::Wait(args, ret) => self.run_wait_syscall(args, ret.clone())?,
             nettest::glue::DemikernelSyscall::Close(args, ret) => self.run_close_syscall(args, ret.clone())?,
+            nettest::glue::DemikernelSyscall::Shutdown(args, ret) => self.run_shutdown_syscall(args, ret.clone())?,
+            nettest::glue::DemikernelSyscall::SetSockOpt(args, ret) => {
+                self.run_setsockopt_syscall(args, ret.clone())?
+            },
             nettest::glue::DemikernelSyscall::Unsupported => {
                 error!("Unsupported syscall");
             },
@@ -576,9 +594,9 @@ impl Simulation {
             },
         };
 
-        // Extract remote queue descriptor.
-        let remote_qd: QDesc = match self.remote_qd {
-            Some((_, qd)) => qd.unwrap(),
+        // Extract queue descriptor.
+        let remote_qd: QDesc = match args.qd {
+            Some(qd) => qd.into(),
             None => {
                 anyhow::bail!("remote queue descriptor must have been previously assigned");
             },
@@ -759,6 +777,66 @@ impl Simulation {
         }
     }
 
+    /// Runs a shutdown system call.
+    fn run_shutdown_syscall(&mut self, args: &ShutdownArgs, ret: i32) -> Result<()> {
+        // Extract queue descriptor.
+        let args_qd: QDesc = args.qd.into();
+
+        // Convert shutdown direction.
+        let how: Shutdown = match args.how.as_str() {
+            "SHUT_RD" => Shutdown::Read,
+            "SHUT_WR" => Shutdown::Write,
+            "SHUT_RDWR" => Shutdown::Both,
+            _ => {
+                let cause: String = format!("unsupported shutdown direction (how={:?})", args.how);
+                info!("run_shutdown_syscall(): {:?}", cause);
+                anyhow::bail!(cause);
+            },
+        };
+
+        match self.engine.tcp_shutdown(args_qd, how) {
+            Ok(()) if ret == 0 => Ok(()),
+            Err(err) if ret as i32 == err.errno => Ok(()),
+            _ => {
+                let cause: String = format!("unexpected return for shutdown syscall");
+                info!("run_shutdown_syscall(): ret={:?}", ret);
+                anyhow::bail!(cause);
+            },
+        }
+    }
+
+    /// Runs a setsockopt system call.
+    fn run_setsockopt_syscall(&mut self, args: &SetSockOptArgs, ret: i32) -> Result<()> {
+        // Extract queue descriptor.
+        let args_qd: QDesc = args.qd.into();
+
+        // Convert socket option.
+        let option: SocketOption = match (args.level.as_str(), args.name.as_str()) {
+            #[cfg(target_os = "linux")]
+            ("SOL_SOCKET", "SO_KEEPALIVE") => SocketOption::KeepAlive(args.value != 0),
+            ("IPPROTO_TCP", "TCP_NODELAY") => SocketOption::NoDelay(args.value != 0),
+            ("SOL_SOCKET", "SO_MAX_PACING_RATE") => SocketOption::MaxPacingRate(Some(args.value as u64)),
+            _ => {
+                let cause: String = format!(
+                    "unsupported socket option (level={:?}, name={:?})",
+                    args.level, args.name
+                );
+                info!("run_setsockopt_syscall(): {:?}", cause);
+                anyhow::bail!(cause);
+            },
+        };
+
+        match self.engine.tcp_set_socket_option(args_qd, option) {
+            Ok(()) if ret == 0 => Ok(()),
+            Err(err) if ret as i32 == err.errno => Ok(()),
+            _ => {
+                let cause: String = format!("unexpected return for setsockopt syscall");
+                info!("run_setsockopt_syscall(): ret={:?}", ret);
+                anyhow::bail!(cause);
+            },
+        }
+    }
+
     // Build options list.
     fn build_tcp_options(&self, options: &Vec<nettest::glue::TcpOption>) -> ([TcpOptions2; MAX_TCP_OPTIONS], usize) {
         let mut option_list: Vec<TcpOptions2> = Vec::new();
@@ -772,6 +850,7 @@ impl Simulation {
                     option_list.push(TcpOptions2::WindowScale(wscale.clone()))
                 },
                 nettest::glue::TcpOption::SackOk => option_list.push(TcpOptions2::SelectiveAcknowlegementPermitted),
+                nettest::glue::TcpOption::Sack(blocks) => option_list.push(Self::build_sack_option(blocks)),
                 nettest::glue::TcpOption::Timestamp(sender, echo) => option_list.push(TcpOptions2::Timestamp {
                     sender_timestamp: sender.clone(),
                     echo_timestamp: echo.clone(),
@@ -790,6 +869,20 @@ impl Simulation {
         (option_list.try_into().unwrap(), num_options)
     }
 
+    /// Builds a SACK option.
+    fn build_sack_option(blocks: &Vec<(u32, u32)>) -> TcpOptions2 {
+        let mut sacks: [SelectiveAcknowlegement; MAX_SACK_BLOCKS] =
+            [SelectiveAcknowlegement::default(); MAX_SACK_BLOCKS];
+        for (sack, (begin, end)) in sacks.iter_mut().zip(blocks.iter()) {
+            sack.begin = SeqNumber::from(*begin);
+            sack.end = SeqNumber::from(*end);
+        }
+        TcpOptions2::SelectiveAcknowlegement {
+            num_sacks: blocks.len(),
+            sacks,
+        }
+    }
+
     /// Builds an Ethernet 2 header.
     fn build_ethernet_header(&self) -> Ethernet2Header {
         let (src_addr, dst_addr) = { (self.remote_mac, self.local_mac) };
@@ -858,7 +951,12 @@ impl Simulation {
         } else {
             DemiBuffer::new_with_headroom(0, MAX_HEADER_SIZE as u16)
         };
-        tcp_hdr.serialize_and_attach(&mut pkt, self.remote_sockaddr.ip(), self.local_sockaddr.ip(), false);
+        tcp_hdr.serialize_and_attach(
+            &mut pkt,
+            &IpAddr::V4(*self.remote_sockaddr.ip()),
+            &IpAddr::V4(*self.local_sockaddr.ip()),
+            false,
+        );
         self.prepend_ipv4_header(IpProtocol::TCP, &mut pkt);
         self.prepend_ethernet_header(&mut pkt);
         pkt
@@ -870,7 +968,12 @@ impl Simulation {
         let udp_hdr: UdpHeader = self.build_udp_header(&udp_packet);
         let mut pkt: DemiBuffer = Self::cook_buffer(udp_packet.len as usize, None);
         // This is an incoming packet, so the source is the remote address and the destination is the local address.
-        udp_hdr.serialize_and_attach(&mut pkt, &self.remote_sockaddr.ip(), &self.local_sockaddr.ip(), false);
+        udp_hdr.serialize_and_attach(
+            &mut pkt,
+            &IpAddr::V4(*self.remote_sockaddr.ip()),
+            &IpAddr::V4(*self.local_sockaddr.ip()),
+            false,
+        );
         self.prepend_ipv4_header(IpProtocol::UDP, &mut pkt);
         self.prepend_ethernet_header(&mut pkt);
         pkt
@@ -967,6 +1070,9 @@ impl Simulation {
                 nettest::glue::TcpOption::SackOk => {
                     crate::ensure_eq!(tcp_header.option_list[i], TcpOptions2::SelectiveAcknowlegementPermitted);
                 },
+                nettest::glue::TcpOption::Sack(ref blocks) => {
+                    crate::ensure_eq!(tcp_header.option_list[i], Self::build_sack_option(blocks));
+                },
                 nettest::glue::TcpOption::Timestamp(sender, echo) => {
                     crate::ensure_eq!(
                         tcp_header.option_list[i],
@@ -1006,8 +1112,8 @@ impl Simulation {
     /// Runs an outgoing TCP packet.
     fn run_outgoing_packet(&mut self, tcp_packet: &TcpPacket) -> Result<()> {
         let mut n: usize = 0;
-        let mut frames: VecDeque<DemiBuffer> = loop {
-            let frames: VecDeque<DemiBuffer> = self.engine.pop_all_frames();
+        while self.outgoing_frames.is_empty() {
+            let mut frames: VecDeque<DemiBuffer> = self.engine.pop_all_frames();
             if frames.is_empty() {
                 if n > MAX_POP_RETRIES {
                     anyhow::bail!("did not emit a frame after {:?} loops", MAX_POP_RETRIES);
@@ -1016,21 +1122,21 @@ impl Simulation {
                     n += 1;
                 }
             } else {
-                // FIXME: We currently do not support multi-frame segments.
-                crate::ensure_eq!(frames.len(), 1);
-                break frames;
+                // Several segments may be emitted at once (e.g. when retransmitting multiple holes), so we check them
+                // one at a time.
+                self.outgoing_frames.append(&mut frames);
             }
-        };
-        let mut pkt: DemiBuffer = frames.pop_front().unwrap();
+        }
+        let mut pkt: DemiBuffer = self.outgoing_frames.pop_front().unwrap();
         let eth2_header: Ethernet2Header = Ethernet2Header::parse_and_strip(&mut pkt)?;
         self.check_ethernet2_header(&eth2_header)?;
 
         let ipv4_header: Ipv4Header = Ipv4Header::parse_and_strip(&mut pkt)?;
         self.check_ipv4_header(&ipv4_header, IpProtocol::TCP)?;
 
-        let src_ipv4_addr: Ipv4Addr = ipv4_header.get_src_addr();
-        let dest_ipv4_addr: Ipv4Addr = ipv4_header.get_dest_addr();
-        let tcp_header: TcpHeader = TcpHeader::parse_and_strip(&src_ipv4_addr, &dest_ipv4_addr, &mut pkt, true)?;
+        let src_ip_addr: IpAddr = IpAddr::V4(ipv4_header.get_src_addr());
+        let dest_ip_addr: IpAddr = IpAddr::V4(ipv4_header.get_dest_addr());
+        let tcp_header: TcpHeader = TcpHeader::parse_and_strip(&src_ip_addr, &dest_ip_addr, &mut pkt, true)?;
         crate::ensure_eq!(tcp_packet.seqnum.win as usize, pkt.len());
         self.check_tcp_header(&tcp_header, &tcp_packet)?;
 
@@ -1062,8 +1168,12 @@ impl Simulation {
         let ipv4_header: Ipv4Header = Ipv4Header::parse_and_strip(&mut pkt)?;
         self.check_ipv4_header(&ipv4_header, IpProtocol::UDP)?;
 
-        let udp_header: UdpHeader =
-            UdpHeader::parse_and_strip(&self.local_sockaddr.ip(), &self.remote_sockaddr.ip(), &mut pkt, true)?;
+        let udp_header: UdpHeader = UdpHeader::parse_and_strip(
+            &IpAddr::V4(*self.local_sockaddr.ip()),
+            &IpAddr::V4(*self.remote_sockaddr.ip()),
+            &mut pkt,
+            true,
+        )?;
         crate::ensure_eq!(udp_packet.len as usize, pkt.len());
         self.check_udp_header(&udp_header, &udp_packet)?;
 
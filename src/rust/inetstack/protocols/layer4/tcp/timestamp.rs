@@ -0,0 +1,87 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+// This file defines the clock that we use for the TCP Timestamps option.
+//
+// RFC 7323, Section 3 defines the Timestamps option.  Each segment carries the current value of the sender's timestamp
+// clock (TSval) and echoes the most recent timestamp received from the peer (TSecr).  The timestamp clock only has to
+// be monotonic and tick at a rate between 1 ms and 1 second, so we simply count milliseconds since the connection was
+// opened.  Timestamps are 32-bit values that wrap around, so they must be compared modulo 2^32 (see RFC 7323, Section
+// 5.2).
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::inetstack::protocols::layer4::tcp::header::{
+    TcpHeader,
+    TcpOptions2,
+};
+use ::std::time::{
+    Duration,
+    Instant,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Space that the Timestamps option takes up in the TCP header, including padding.
+pub const TIMESTAMP_OPTION_SIZE: usize = 12;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Timestamp clock of a TCP connection.
+#[derive(Clone, Copy, Debug)]
+pub struct TimestampClock {
+    origin: Instant,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl TimestampClock {
+    /// Creates a timestamp clock that starts ticking at [origin].
+    pub fn new(origin: Instant) -> Self {
+        Self { origin }
+    }
+
+    /// Returns the value of the timestamp clock at [now]. The clock starts at one, so that a zero TSecr always means
+    /// that the peer did not echo any of our timestamps.
+    pub fn get_timestamp(&self, now: Instant) -> u32 {
+        (now.saturating_duration_since(self.origin).as_millis() as u32).wrapping_add(1)
+    }
+
+    /// Computes the round-trip time from a timestamp that was echoed back by our peer at [now]. Returns None if the
+    /// echoed timestamp lies in the future, as our peer must have made it up.
+    pub fn get_round_trip_time(&self, now: Instant, echo_timestamp: u32) -> Option<Duration> {
+        let now: u32 = self.get_timestamp(now);
+        if timestamp_lt(now, echo_timestamp) {
+            return None;
+        }
+        Some(Duration::from_millis(now.wrapping_sub(echo_timestamp) as u64))
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Checks whether timestamp [a] is older than timestamp [b].
+pub fn timestamp_lt(a: u32, b: u32) -> bool {
+    (a.wrapping_sub(b) as i32) < 0
+}
+
+/// Returns the TSval and TSecr fields of the Timestamps option in [header], if any.
+pub fn get_timestamp_option(header: &TcpHeader) -> Option<(u32, u32)> {
+    header.iter_options().find_map(|option| match option {
+        TcpOptions2::Timestamp {
+            sender_timestamp,
+            echo_timestamp,
+        } => Some((*sender_timestamp, *echo_timestamp)),
+        _ => None,
+    })
+}
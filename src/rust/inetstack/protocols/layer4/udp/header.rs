@@ -6,7 +6,10 @@
 //======================================================================================================================
 
 use crate::{
-    inetstack::protocols::layer3::ip::IpProtocol,
+    inetstack::protocols::layer3::{
+        ip::IpProtocol,
+        ipv6::Ipv6Header,
+    },
     runtime::{
         fail::Fail,
         memory::DemiBuffer,
@@ -14,7 +17,8 @@ use crate::{
 };
 use ::libc::EBADMSG;
 use ::std::{
-    net::Ipv4Addr,
+    iter,
+    net::IpAddr,
     slice::ChunksExact,
 };
 
@@ -61,8 +65,8 @@ impl UdpHeader {
 
     /// Parses and strips the UDP header off of the packet in [buf].
     pub fn parse_and_strip(
-        src_ipv4_addr: &Ipv4Addr,
-        dst_ipv4_addr: &Ipv4Addr,
+        src_ip_addr: &IpAddr,
+        dst_ip_addr: &IpAddr,
         buf: &mut DemiBuffer,
         checksum_offload: bool,
     ) -> Result<Self, Fail> {
@@ -84,10 +88,13 @@ impl UdpHeader {
         if !checksum_offload {
             let payload_buf: &[u8] = &buf[UDP_HEADER_SIZE..];
             let checksum: u16 = u16::from_be_bytes([hdr_buf[6], hdr_buf[7]]);
-            // Check if we should skip checksum verification.
+            // Check if we should skip checksum verification. This is only allowed for IPv4 (see RFC 8200 Section 8.1).
+            if checksum == 0 && src_ip_addr.is_ipv6() {
+                return Err(Fail::new(EBADMSG, "UDP checksum missing"));
+            }
             if checksum != 0 {
                 // No, so check if checksum value matches what we expect.
-                if checksum != Self::checksum(src_ipv4_addr, dst_ipv4_addr, hdr_buf, payload_buf) {
+                if checksum != Self::checksum(src_ip_addr, dst_ip_addr, hdr_buf, iter::once(payload_buf)) {
                     return Err(Fail::new(EBADMSG, "UDP checksum mismatch"));
                 }
             }
@@ -103,18 +110,16 @@ impl UdpHeader {
     pub fn serialize_and_attach(
         &self,
         buf: &mut DemiBuffer,
-        src_ipv4_addr: &Ipv4Addr,
-        dst_ipv4_addr: &Ipv4Addr,
+        src_ip_addr: &IpAddr,
+        dst_ip_addr: &IpAddr,
         checksum_offload: bool,
     ) {
-        // Create room for the header in the packet.
+        // Create room for the header in the packet. The payload may span several buffers.
         buf.prepend(UDP_HEADER_SIZE).expect("Should have enough headroom");
-        let buf_size_bytes: usize = buf.len();
+        let buf_size_bytes: usize = buf.total_len();
 
-        // Split the packet up into two slices: one for the header and one for the payload.
-        let (hdr_buf, payload): (&mut [u8], &mut [u8]) = buf[..].split_at_mut(UDP_HEADER_SIZE);
-
-        let fixed_buf: &mut [u8; UDP_HEADER_SIZE] = (&mut hdr_buf[..UDP_HEADER_SIZE]).try_into().unwrap();
+        // Assemble the header on the side, as the checksum has to read the payload.
+        let mut fixed_buf: [u8; UDP_HEADER_SIZE] = [0; UDP_HEADER_SIZE];
 
         // Write source port.
         fixed_buf[0..2].copy_from_slice(&self.src_port.to_be_bytes());
@@ -129,9 +134,16 @@ impl UdpHeader {
         let checksum: u16 = if checksum_offload {
             0
         } else {
-            Self::checksum(src_ipv4_addr, dst_ipv4_addr, &fixed_buf[..], payload)
+            // The header sits at the front of the first buffer, followed by the payload.
+            Self::checksum(
+                src_ip_addr,
+                dst_ip_addr,
+                &fixed_buf[..],
+                iter::once(&buf[UDP_HEADER_SIZE..]).chain(buf.segments().skip(1)),
+            )
         };
         fixed_buf[6..8].copy_from_slice(&checksum.to_be_bytes());
+        buf[..UDP_HEADER_SIZE].copy_from_slice(&fixed_buf);
         trace!("UDP header: {:?} packet size: {:?} bytes", self, buf_size_bytes);
     }
 
@@ -142,25 +154,43 @@ impl UdpHeader {
     /// data,  padded  with zero octets at the end (if  necessary)  to  make  a
     /// multiple of two octets.
     ///
+    /// The data may be scattered across several slices.
+    ///
     /// TODO: Write a unit test for this function.
-    fn checksum(src_ipv4_addr: &Ipv4Addr, dst_ipv4_addr: &Ipv4Addr, udp_hdr: &[u8], data: &[u8]) -> u16 {
-        let mut state: u32 = 0xffff;
-
-        // Source address (4 bytes)
-        let src_octets: [u8; 4] = src_ipv4_addr.octets();
-        state += u16::from_be_bytes([src_octets[0], src_octets[1]]) as u32;
-        state += u16::from_be_bytes([src_octets[2], src_octets[3]]) as u32;
-
-        // Destination address (4 bytes)
-        let dst_octets: [u8; 4] = dst_ipv4_addr.octets();
-        state += u16::from_be_bytes([dst_octets[0], dst_octets[1]]) as u32;
-        state += u16::from_be_bytes([dst_octets[2], dst_octets[3]]) as u32;
-
-        // Padding zeros (1 byte) and UDP protocol number (1 byte)
-        state += u16::from_be_bytes([0, IpProtocol::UDP as u8]) as u32;
-
-        // UDP segment length (2 bytes)
-        state += (udp_hdr.len() + data.len()) as u32;
+    fn checksum<'a, I>(src_ip_addr: &IpAddr, dst_ip_addr: &IpAddr, udp_hdr: &[u8], data: I) -> u16
+    where
+        I: Iterator<Item = &'a [u8]> + Clone,
+    {
+        let data_len: usize = data.clone().map(|slice: &[u8]| slice.len()).sum();
+        let mut state: u32 = match (src_ip_addr, dst_ip_addr) {
+            (IpAddr::V4(src_ipv4_addr), IpAddr::V4(dst_ipv4_addr)) => {
+                let mut state: u32 = 0xffff;
+
+                // Source address (4 bytes)
+                let src_octets: [u8; 4] = src_ipv4_addr.octets();
+                state += u16::from_be_bytes([src_octets[0], src_octets[1]]) as u32;
+                state += u16::from_be_bytes([src_octets[2], src_octets[3]]) as u32;
+
+                // Destination address (4 bytes)
+                let dst_octets: [u8; 4] = dst_ipv4_addr.octets();
+                state += u16::from_be_bytes([dst_octets[0], dst_octets[1]]) as u32;
+                state += u16::from_be_bytes([dst_octets[2], dst_octets[3]]) as u32;
+
+                // Padding zeros (1 byte) and UDP protocol number (1 byte)
+                state += u16::from_be_bytes([0, IpProtocol::UDP as u8]) as u32;
+
+                // UDP segment length (2 bytes)
+                state += (udp_hdr.len() + data_len) as u32;
+                state
+            },
+            (IpAddr::V6(src_ipv6_addr), IpAddr::V6(dst_ipv6_addr)) => Ipv6Header::compute_pseudo_header_checksum(
+                src_ipv6_addr,
+                dst_ipv6_addr,
+                IpProtocol::UDP,
+                udp_hdr.len() + data_len,
+            ),
+            _ => unreachable!("source and destination addresses should belong to the same family"),
+        };
 
         // Switch to UDP header.
         let fixed_header: &[u8; UDP_HEADER_SIZE] = udp_hdr.try_into().unwrap();
@@ -177,13 +207,29 @@ impl UdpHeader {
         // Checksum (2 bytes, all zeros)
         state += 0;
 
-        // Payload.
-        let mut chunks_iter: ChunksExact<u8> = data.chunks_exact(2);
-        while let Some(chunk) = chunks_iter.next() {
-            state += u16::from_be_bytes([chunk[0], chunk[1]]) as u32;
+        // Payload. A slice with an odd number of bytes leaves a byte that pairs up with the start of the next one.
+        let mut odd_byte: Option<u8> = None;
+        for mut slice in data {
+            if let Some(b) = odd_byte.take() {
+                match slice.split_first() {
+                    Some((&first, rest)) => {
+                        state += u16::from_be_bytes([b, first]) as u32;
+                        slice = rest;
+                    },
+                    None => {
+                        odd_byte = Some(b);
+                        continue;
+                    },
+                }
+            }
+            let mut chunks_iter: ChunksExact<u8> = slice.chunks_exact(2);
+            while let Some(chunk) = chunks_iter.next() {
+                state += u16::from_be_bytes([chunk[0], chunk[1]]) as u32;
+            }
+            odd_byte = chunks_iter.remainder().get(0).copied();
         }
         // Pad with zeros with payload has an odd number of bytes.
-        if let Some(&b) = chunks_iter.remainder().get(0) {
+        if let Some(b) = odd_byte {
             state += u16::from_be_bytes([b, 0]) as u32;
         }
 
@@ -195,7 +241,11 @@ impl UdpHeader {
         while state > 0xFFFF {
             state -= 0xFFFF;
         }
-        !state as u16
+        // A computed checksum of zero is transmitted as all ones, as zero means that no checksum was computed.
+        match !state as u16 {
+            0 => 0xffff,
+            checksum => checksum,
+        }
     }
 }
 
@@ -207,14 +257,17 @@ impl UdpHeader {
 mod test {
     use crate::inetstack::protocols::layer4::udp::header::*;
     use ::anyhow::Result;
-    use ::std::net::Ipv4Addr;
+    use ::std::net::{
+        Ipv4Addr,
+        Ipv6Addr,
+    };
 
     /// Tets UDP serialization.
     #[test]
     fn test_udp_header_serialization() -> Result<()> {
         const UDP_HEADER_SIZE: usize = 8;
-        let src_addr: Ipv4Addr = Ipv4Addr::new(198, 0, 0, 1);
-        let dst_addr: Ipv4Addr = Ipv4Addr::new(198, 0, 0, 2);
+        let src_addr: IpAddr = IpAddr::V4(Ipv4Addr::new(198, 0, 0, 1));
+        let dst_addr: IpAddr = IpAddr::V4(Ipv4Addr::new(198, 0, 0, 2));
 
         // Build fake UDP header.
         let src_port: u16 = 0x32;
@@ -237,8 +290,8 @@ mod test {
     #[test]
     fn test_udp_header_parsing() -> Result<()> {
         // Build fake IPv4 header.
-        let src_addr: Ipv4Addr = Ipv4Addr::new(198, 0, 0, 1);
-        let dst_addr: Ipv4Addr = Ipv4Addr::new(198, 0, 0, 2);
+        let src_addr: IpAddr = IpAddr::V4(Ipv4Addr::new(198, 0, 0, 1));
+        let dst_addr: IpAddr = IpAddr::V4(Ipv4Addr::new(198, 0, 0, 2));
 
         // Build fake UDP header.
         let src_port: u16 = 0x32;
@@ -264,4 +317,37 @@ mod test {
 
         Ok(())
     }
+
+    /// Tests UDP checksum computation over an IPv6 pseudo-header.
+    #[test]
+    fn test_udp_header_ipv6_checksum() -> Result<()> {
+        const UDP_HEADER_SIZE: usize = 8;
+        let src_addr: IpAddr = IpAddr::V6(Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 1));
+        let dst_addr: IpAddr = IpAddr::V6(Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 2));
+        let checksum_offload: bool = false;
+        let udp_hdr: UdpHeader = UdpHeader::new(0x32, 0x45);
+
+        // Payload with an odd number of bytes.
+        let data: [u8; 7] = [0x0, 0x1, 0x0, 0x1, 0x0, 0x1, 0x0];
+        let mut buf: DemiBuffer = DemiBuffer::from_slice_with_headroom(&data, UDP_HEADER_SIZE)?;
+        udp_hdr.serialize_and_attach(&mut buf, &src_addr, &dst_addr, checksum_offload);
+        crate::ensure_neq!(buf[6..8], [0x0, 0x0]);
+
+        // The checksum covers the addresses of the pseudo-header.
+        let mut corrupted: DemiBuffer = buf.clone();
+        if UdpHeader::parse_and_strip(&dst_addr, &dst_addr, &mut corrupted, checksum_offload).is_ok() {
+            anyhow::bail!("parsing should fail");
+        }
+
+        match UdpHeader::parse_and_strip(&src_addr, &dst_addr, &mut buf, checksum_offload) {
+            Ok(udp_hdr) => {
+                crate::ensure_eq!(udp_hdr.src_port(), 0x32);
+                crate::ensure_eq!(udp_hdr.dest_port(), 0x45);
+                crate::ensure_eq!(buf.len(), data.len());
+            },
+            Err(e) => anyhow::bail!("could not parse: {:?}", e),
+        };
+
+        Ok(())
+    }
 }
@@ -7,16 +7,28 @@
 
 use crate::{
     demikernel::config::Config,
+    expect_some,
     inetstack::protocols::{
-        layer3::SharedLayer3Endpoint,
+        layer3::{
+            icmpv4::{
+                Icmpv4Error,
+                ICMPV4_UNREACHABLE_PORT,
+            },
+            Ipv4Header,
+            SharedLayer3Endpoint,
+        },
         layer4::udp::{
-            header::UdpHeader,
+            header::{
+                UdpHeader,
+                UDP_HEADER_SIZE,
+            },
             socket::SharedUdpSocket,
         },
     },
     runtime::{
         fail::Fail,
         memory::DemiBuffer,
+        network::socket::option::SocketOption,
         SharedDemiRuntime,
         SharedObject,
     },
@@ -26,7 +38,10 @@ use crate::{
 use ::std::{
     collections::HashMap,
     net::{
+        IpAddr,
         Ipv4Addr,
+        Ipv6Addr,
+        Shutdown,
         SocketAddr,
         SocketAddrV4,
     },
@@ -43,14 +58,18 @@ use ::std::{
 
 /// UDP Peer
 pub struct UdpPeer {
+    /// Shared DemiRuntime.
+    runtime: SharedDemiRuntime,
     /// Underlying transport.
     layer3_endpoint: SharedLayer3Endpoint,
     /// Local IPv4 address.
     local_ipv4_addr: Ipv4Addr,
+    /// Local IPv6 address.
+    local_ipv6_addr: Ipv6Addr,
     /// Offload checksum to hardware?
     checksum_offload: bool,
-    /// Incoming routing table.
-    addresses: HashMap<SocketAddrV4, SharedUdpSocket>,
+    /// Incoming routing table. Several sockets may share an address if all of them allow it.
+    addresses: HashMap<SocketAddr, Vec<SharedUdpSocket>>,
 }
 
 #[derive(Clone)]
@@ -65,14 +84,22 @@ pub struct SharedUdpPeer(SharedObject<UdpPeer>);
 impl SharedUdpPeer {
     pub fn new(
         config: &Config,
-        _runtime: SharedDemiRuntime,
+        runtime: SharedDemiRuntime,
         layer3_endpoint: SharedLayer3Endpoint,
     ) -> Result<Self, Fail> {
         Ok(Self(SharedObject::<UdpPeer>::new(UdpPeer {
+            runtime,
             layer3_endpoint,
-            local_ipv4_addr: config.local_ipv4_addr()?,
-            checksum_offload: config.udp_checksum_offload()?,
-            addresses: HashMap::<SocketAddrV4, SharedUdpSocket>::new(),
+            local_ipv4_addr: config
+                .local_ipv4_addr()
+                .ok_or_else(|| Config::missing_option("local_ipv4_addr"))?,
+            local_ipv6_addr: config
+                .local_ipv6_addr()
+                .ok_or_else(|| Config::missing_option("local_ipv6_addr"))?,
+            checksum_offload: config
+                .udp_checksum_offload()
+                .ok_or_else(|| Config::missing_option("udp_checksum_offload"))?,
+            addresses: HashMap::<SocketAddr, Vec<SharedUdpSocket>>::new(),
         })))
     }
 
@@ -80,28 +107,116 @@ impl SharedUdpPeer {
     pub fn socket(&mut self) -> Result<SharedUdpSocket, Fail> {
         SharedUdpSocket::new(
             self.local_ipv4_addr,
+            self.local_ipv6_addr,
             self.layer3_endpoint.clone(),
             self.checksum_offload,
         )
     }
 
     /// Binds a UDP socket to a local endpoint address.
-    pub fn bind(&mut self, socket: &mut SharedUdpSocket, addr: SocketAddrV4) -> Result<(), Fail> {
+    pub fn bind(&mut self, socket: &mut SharedUdpSocket, addr: SocketAddr) -> Result<(), Fail> {
         if let Some(_) = socket.local() {
             let cause: String = format!("cannot bind to already bound socket");
             error!("bind(): {}", cause);
             return Err(Fail::new(libc::EADDRINUSE, &cause));
         }
 
+        // Sockets may only share an address if all of them allow it.
+        if let Some(sockets) = self.addresses.get(&addr) {
+            if !socket.get_reuse_addr() || sockets.iter().any(|other: &SharedUdpSocket| !other.get_reuse_addr()) {
+                let cause: String = format!("address is already bound to a socket (addr={:?})", addr);
+                error!("bind(): {}", cause);
+                return Err(Fail::new(libc::EADDRINUSE, &cause));
+            }
+        }
+
         socket.bind(addr)?;
-        self.addresses.insert(addr.clone(), socket.clone());
+        self.addresses.entry(addr).or_default().push(socket.clone());
         Ok(())
     }
 
+    /// Connects a UDP socket to [remote], which becomes the default destination of its datagrams and the only source
+    /// that it receives from. Unbound sockets get bound to an ephemeral port.
+    pub fn connect(&mut self, socket: &mut SharedUdpSocket, remote: SocketAddr) -> Result<(), Fail> {
+        if remote.ip().is_unspecified() || remote.port() == 0 {
+            let cause: String = format!("invalid remote address (remote={:?})", remote);
+            error!("connect(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        let local: Option<SocketAddr> = match socket.local() {
+            Some(addr) if addr.is_ipv4() != remote.is_ipv4() => {
+                let cause: String = format!("address family does not match bound address (remote={:?})", remote);
+                error!("connect(): {}", &cause);
+                return Err(Fail::new(libc::EAFNOSUPPORT, &cause));
+            },
+            Some(_) => None,
+            None => {
+                let local_ip_addr: IpAddr = match remote.ip() {
+                    IpAddr::V4(_) => IpAddr::V4(self.local_ipv4_addr),
+                    IpAddr::V6(_) => IpAddr::V6(self.local_ipv6_addr),
+                };
+                let local_port: u16 = self.runtime.alloc_ephemeral_port_for(local_ip_addr, remote)?;
+                let local: SocketAddr = SocketAddr::new(local_ip_addr, local_port);
+                self.addresses.entry(local).or_default().push(socket.clone());
+                Some(local)
+            },
+        };
+        socket.connect(local, remote)
+    }
+
+    /// Gets the remote address that a UDP socket is connected to.
+    pub fn getpeername(&self, socket: &SharedUdpSocket) -> Result<SocketAddr, Fail> {
+        match socket.remote() {
+            Some(remote) => Ok(remote),
+            None => {
+                let cause: &str = "socket is not connected";
+                error!("getpeername(): {}", cause);
+                Err(Fail::new(libc::ENOTCONN, cause))
+            },
+        }
+    }
+
+    /// Gets the local address that a UDP socket is bound to.
+    pub fn getsockname(&self, socket: &SharedUdpSocket) -> Result<SocketAddr, Fail> {
+        match socket.local() {
+            Some(local) => Ok(local),
+            None => {
+                let cause: &str = "socket is not bound";
+                error!("getsockname(): {}", cause);
+                Err(Fail::new(libc::EINVAL, cause))
+            },
+        }
+    }
+
+    /// Shuts down receiving, sending or both on a connected UDP socket.
+    pub fn shutdown(&mut self, socket: &mut SharedUdpSocket, how: Shutdown) -> Result<(), Fail> {
+        socket.shutdown(how)
+    }
+
+    /// Sets an option on a UDP socket.
+    pub fn set_socket_option(&mut self, socket: &mut SharedUdpSocket, option: SocketOption) -> Result<(), Fail> {
+        socket.set_socket_option(option)
+    }
+
+    /// Gets an option on a UDP socket.
+    pub fn get_socket_option(&mut self, socket: &SharedUdpSocket, option: SocketOption) -> Result<SocketOption, Fail> {
+        socket.get_socket_option(option)
+    }
+
     /// Closes a UDP socket.
     pub fn hard_close(&mut self, socket: &mut SharedUdpSocket) -> Result<(), Fail> {
+        socket.leave_multicast_groups();
         if let Some(addr) = socket.local() {
-            self.addresses.remove(&addr);
+            if let Some(sockets) = self.addresses.get_mut(&addr) {
+                sockets.retain(|other: &SharedUdpSocket| other != socket);
+                if sockets.is_empty() {
+                    self.addresses.remove(&addr);
+                }
+            }
+            // The libOS only frees the ephemeral ports of sockets that it bound.
+            if socket.is_bound_on_connect() && self.runtime.free_ephemeral_port(addr.port()).is_err() {
+                warn!("hard_close(): leaking ephemeral port (port={})", addr.port());
+            }
         }
         Ok(())
     }
@@ -127,7 +242,9 @@ impl SharedUdpPeer {
         }
         // TODO: Remove copy once we actually use push coroutine for send.
         socket.push(remote, buf.clone()).await?;
-        buf.trim(buf.len())
+        // Clear out the original buffer, which may be a chain.
+        *buf = DemiBuffer::new(0);
+        Ok(())
     }
 
     /// Pops data from a socket.
@@ -137,16 +254,28 @@ impl SharedUdpPeer {
         size: usize,
     ) -> Result<(Option<SocketAddr>, DemiBuffer), Fail> {
         let (addr, buf) = socket.pop(size).await?;
-        Ok((Some(addr.into()), buf))
+        Ok((Some(addr), buf))
     }
 
-    /// Consumes the payload from a buffer.
-    pub fn receive(&mut self, src_ipv4_addr: Ipv4Addr, mut buf: DemiBuffer) {
+    /// Consumes the payload from a buffer that [src_ip_addr] sent to [dst_ip_addr]. IPv4 datagrams come with their
+    /// header in [ipv4_hdr].
+    pub fn receive(
+        &mut self,
+        src_ip_addr: IpAddr,
+        dst_ip_addr: IpAddr,
+        ipv4_hdr: Option<Ipv4Header>,
+        mut buf: DemiBuffer,
+    ) {
         timer!("udp::receive");
-        // Parse datagram. Safe to use the local IP address here because the lower IP layer would have discarded the
-        // packet if the destination did not match the local IP.
+        // Parse datagram. Safe to use the destination address here because the lower IP layer would have discarded the
+        // packet if the destination was neither one of our addresses nor a broadcast or multicast address.
+        let local_ip_addr: IpAddr = dst_ip_addr;
+        let unspecified_ip_addr: IpAddr = match src_ip_addr {
+            IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        };
         let hdr: UdpHeader =
-            match UdpHeader::parse_and_strip(&src_ipv4_addr, &self.local_ipv4_addr, &mut buf, self.checksum_offload) {
+            match UdpHeader::parse_and_strip(&src_ip_addr, &local_ip_addr, &mut buf, self.checksum_offload) {
                 Ok(header) => header,
                 Err(e) => {
                     let cause: String = format!("dropping packet: unable to parse UDP header");
@@ -156,34 +285,111 @@ impl SharedUdpPeer {
             };
         debug!("UDP received {:?}", hdr);
 
-        let local: SocketAddrV4 = SocketAddrV4::new(self.local_ipv4_addr, hdr.dest_port());
-        let remote: SocketAddrV4 = SocketAddrV4::new(src_ipv4_addr, hdr.src_port());
+        let local: SocketAddr = SocketAddr::new(local_ip_addr, hdr.dest_port());
+        let remote: SocketAddr = SocketAddr::new(src_ip_addr, hdr.src_port());
 
-        let socket: &mut SharedUdpSocket = match self.get_socket_from_addr(&local) {
-            Some(queue) => queue,
+        // Every socket that shares the address gets a copy of broadcasts and multicasts, but only the one that bound
+        // last gets unicasts. Sockets that are connected to the source take precedence over the others.
+        let is_broadcast: bool = match dst_ip_addr {
+            IpAddr::V4(dst_ipv4_addr) => {
+                self.layer3_endpoint.is_broadcast_ipv4_addr(dst_ipv4_addr) || dst_ipv4_addr.is_multicast()
+            },
+            IpAddr::V6(_) => false,
+        };
+
+        // Handle wildcard address. Connected sockets do not receive from other sources, so skip addresses where all
+        // sockets are connected elsewhere.
+        let wildcard: SocketAddr = SocketAddr::new(unspecified_ip_addr, hdr.dest_port());
+        let is_accepting = |addr: &SocketAddr| -> bool {
+            match self.addresses.get(addr) {
+                Some(sockets) => sockets
+                    .iter()
+                    .any(|socket: &SharedUdpSocket| socket.accepts_from(remote)),
+                None => false,
+            }
+        };
+        let sockets: &mut Vec<SharedUdpSocket> = match [local, wildcard].into_iter().find(is_accepting) {
+            Some(addr) => expect_some!(self.addresses.get_mut(&addr), "address should be bound"),
             None => {
-                // Handle wildcard address.
-                let local: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, hdr.dest_port());
-                match self.get_socket_from_addr(&local) {
-                    Some(queue) => queue,
-                    None => {
-                        // RFC 792 specifies that an ICMP message may be sent in response to a packet sent to an unbound
-                        // port. However, we simply drop the datagram as this could be a port-scan attack, and not
-                        // sending an ICMP message is a valid action. See https://www.rfc-editor.org/rfc/rfc792 for more
-                        // details.
-                        let cause: String = format!("dropping packet: port not bound");
-                        warn!("{}: {:?}", cause, local);
-                        return;
-                    },
-                }
+                let cause: &str = "dropping packet: port not bound";
+                warn!("{}: {:?}", cause, local);
+                self.send_port_unreachable(ipv4_hdr, buf);
+                return;
             },
         };
-        // TODO: Drop this packet if local address/port pair is not bound.
-        socket.receive(remote, buf)
+        if is_broadcast {
+            for socket in sockets.iter_mut() {
+                if socket.accepts_from(remote) {
+                    socket.receive(remote, buf.clone());
+                }
+            }
+        } else if let Some(socket) = Self::find_socket_for(sockets, remote) {
+            socket.receive(remote, buf);
+        }
+    }
+
+    /// Picks the socket among [sockets] that unicasts from [remote] go to: the last one that connected to [remote], or
+    /// else the last unconnected one that bound.
+    fn find_socket_for(sockets: &mut [SharedUdpSocket], remote: SocketAddr) -> Option<&mut SharedUdpSocket> {
+        let position: usize = sockets
+            .iter()
+            .rposition(|socket: &SharedUdpSocket| socket.remote() == Some(remote))
+            .or_else(|| {
+                sockets
+                    .iter()
+                    .rposition(|socket: &SharedUdpSocket| socket.accepts_from(remote))
+            })?;
+        sockets.get_mut(position)
+    }
+
+    /// Tells the source of the datagram with [ipv4_hdr] and [buf] that nobody listens on the port that it sent the
+    /// datagram to (see RFC 1122 Section 4.1.3.1). We only do so for IPv4 datagrams that were addressed to us alone
+    /// (see RFC 1122 Section 3.2.2). When this runtime owns one of several receive queues, the port may well be bound
+    /// by the runtime of another queue, so we stay silent.
+    fn send_port_unreachable(&mut self, ipv4_hdr: Option<Ipv4Header>, mut buf: DemiBuffer) {
+        let ipv4_hdr: Ipv4Header = match ipv4_hdr {
+            Some(ipv4_hdr) => ipv4_hdr,
+            None => return,
+        };
+        if !self.layer3_endpoint.is_local_ipv4_addr(ipv4_hdr.get_dest_addr()) || self.runtime.is_flow_steered() {
+            return;
+        }
+        // Parsing only moved past the UDP header, so put it back in front of the payload that we quote.
+        if let Err(e) = buf.prepend(UDP_HEADER_SIZE) {
+            warn!("cannot quote UDP header: {:?}", e);
+            return;
+        }
+        if let Err(e) =
+            self.layer3_endpoint
+                .transmit_icmpv4_destination_unreachable(ICMPV4_UNREACHABLE_PORT, &ipv4_hdr, &buf)
+        {
+            warn!("cannot send port unreachable message: {:?}", e);
+        }
     }
 
-    fn get_socket_from_addr(&mut self, local: &SocketAddrV4) -> Option<&mut SharedUdpSocket> {
-        self.addresses.get_mut(local)
+    /// Processes an ICMPv4 error about a datagram that we sent. The next push to the same remote fails with it.
+    pub fn receive_icmpv4_error(&mut self, error: &Icmpv4Error) {
+        let errno: libc::c_int = match error.get_errno() {
+            Some(errno) => errno,
+            None => return,
+        };
+        let (local, remote): (SocketAddrV4, SocketAddrV4) = error.get_endpoints();
+        let wildcard: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), local.port());
+        let sockets: &mut Vec<SharedUdpSocket> = match self.addresses.get_mut(&SocketAddr::V4(local)) {
+            Some(sockets) => sockets,
+            None => match self.addresses.get_mut(&wildcard) {
+                Some(sockets) => sockets,
+                None => {
+                    debug!("ignoring ICMPv4 error for unbound address: {:?}", local);
+                    return;
+                },
+            },
+        };
+        let socket: &mut SharedUdpSocket = match Self::find_socket_for(sockets, SocketAddr::V4(remote)) {
+            Some(socket) => socket,
+            None => return,
+        };
+        socket.receive_error(SocketAddr::V4(remote), errno)
     }
 }
 
@@ -14,37 +14,30 @@ use crate::{
     runtime::{
         fail::Fail,
         memory::DemiBuffer,
-        network::unwrap_socketaddr,
+        network::socket::option::{
+            SocketOption,
+            UdpSocketOptions,
+        },
         SharedObject,
     },
 };
 use ::std::{
     fmt::Debug,
+    mem,
     net::{
+        IpAddr,
         Ipv4Addr,
+        Ipv6Addr,
+        Shutdown,
         SocketAddr,
-        SocketAddrV4,
     },
     ops::{
         Deref,
         DerefMut,
     },
+    ptr,
 };
 
-//======================================================================================================================
-// Constants
-//======================================================================================================================
-
-// Maximum size for receive queues (in messages).
-// TODO: Support max size on async queues.
-#[allow(dead_code)]
-const RECV_QUEUE_MAX_SIZE: usize = 1024;
-
-// Maximum size for send queues (in messages).
-// TODO: Support max size on async queues.
-#[allow(dead_code)]
-const SEND_QUEUE_MAX_SIZE: usize = 1024;
-
 //======================================================================================================================
 // Structures
 //======================================================================================================================
@@ -52,15 +45,44 @@ const SEND_QUEUE_MAX_SIZE: usize = 1024;
 /// Per-queue metadata for a UDP socket.
 pub struct UdpSocket {
     local_ipv4_addr: Ipv4Addr,
-    bound: Option<SocketAddrV4>,
+    local_ipv6_addr: Ipv6Addr,
+    bound: Option<SocketAddr>,
+    // Remote address that the socket is connected to, which is the default destination and the only source that it
+    // receives from.
+    connected: Option<SocketAddr>,
+    // Was the socket bound to an ephemeral port when it connected?
+    bound_on_connect: bool,
     layer3_endpoint: SharedLayer3Endpoint,
-    // A queue of incoming packets as remote address and data buffer pairs.
-    recv_queue: AsyncQueue<(SocketAddrV4, DemiBuffer)>,
+    // A queue of incoming packets as remote address and data buffer pairs. Empty entries wake up pop() to report an
+    // error on connected sockets.
+    recv_queue: AsyncQueue<Option<(SocketAddr, DemiBuffer)>>,
+    // Number of payload bytes in the receive queue. This may not go over the size of the receive buffer.
+    recv_queue_len: usize,
+    // Number of datagrams that we dropped because the receive queue was full.
+    recv_drops: u64,
+    // Number of payload bytes that were pushed but not handed to the lower layer yet. This may not go over the size of
+    // the send buffer.
+    send_queue_len: usize,
+    options: UdpSocketOptions,
     checksum_offload: bool,
+    // Error that the network reported about the last datagram that could not reach its remote, along with that remote.
+    pending_error: Option<(SocketAddr, libc::c_int)>,
+    // IPv4 multicast groups that this socket joined.
+    memberships: Vec<Ipv4Addr>,
+    // Did the application shut down receiving or sending on this connected socket?
+    receive_shutdown: bool,
+    send_shutdown: bool,
 }
 #[derive(Clone)]
 pub struct SharedUdpSocket(SharedObject<UdpSocket>);
 
+/// Room that a datagram holds in the send buffer of [socket] until it is dropped, which also happens when the push
+/// that holds it is cancelled.
+struct SendBufferRoom {
+    socket: SharedUdpSocket,
+    len: usize,
+}
+
 //======================================================================================================================
 // Associated Functions
 //======================================================================================================================
@@ -68,57 +90,200 @@ pub struct SharedUdpSocket(SharedObject<UdpSocket>);
 impl SharedUdpSocket {
     pub fn new(
         local_ipv4_addr: Ipv4Addr,
+        local_ipv6_addr: Ipv6Addr,
         layer3_endpoint: SharedLayer3Endpoint,
         checksum_offload: bool,
     ) -> Result<Self, Fail> {
         Ok(Self(SharedObject::new(UdpSocket {
             local_ipv4_addr,
+            local_ipv6_addr,
             bound: None,
+            connected: None,
+            bound_on_connect: false,
             layer3_endpoint,
-            recv_queue: AsyncQueue::<(SocketAddrV4, DemiBuffer)>::default(),
+            recv_queue: AsyncQueue::<Option<(SocketAddr, DemiBuffer)>>::default(),
+            recv_queue_len: 0,
+            recv_drops: 0,
+            send_queue_len: 0,
+            options: UdpSocketOptions::default(),
             checksum_offload,
+            pending_error: None,
+            memberships: Vec::new(),
+            receive_shutdown: false,
+            send_shutdown: false,
         })))
     }
 
-    pub fn bind(&mut self, local: SocketAddrV4) -> Result<(), Fail> {
+    pub fn bind(&mut self, local: SocketAddr) -> Result<(), Fail> {
         self.bound = Some(local);
         Ok(())
     }
 
+    /// Connects the socket to [remote]. If the socket was not bound, it has been bound to [local] on the way.
+    pub fn connect(&mut self, local: Option<SocketAddr>, remote: SocketAddr) -> Result<(), Fail> {
+        if let Some(local) = local {
+            self.bound = Some(local);
+            self.bound_on_connect = true;
+        }
+        self.connected = Some(remote);
+        Ok(())
+    }
+
+    /// Shuts down receiving, sending or both on the connected socket. Pops see an end of file and pushes fail from then
+    /// on.
+    pub fn shutdown(&mut self, how: Shutdown) -> Result<(), Fail> {
+        if self.connected.is_none() {
+            let cause: &str = "socket is not connected";
+            error!("shutdown(): {}", cause);
+            return Err(Fail::new(libc::ENOTCONN, cause));
+        }
+        if how != Shutdown::Read {
+            self.send_shutdown = true;
+        }
+        if how != Shutdown::Write && !self.receive_shutdown {
+            self.receive_shutdown = true;
+            // Wake up pop(), which reports the end of file.
+            self.recv_queue.push(None);
+        }
+        Ok(())
+    }
+
+    /// Sets an SO_* option on the socket.
+    pub fn set_socket_option(&mut self, option: SocketOption) -> Result<(), Fail> {
+        match option {
+            SocketOption::RecvBufSize(size) => self.options.set_recv_buf_size(size),
+            SocketOption::SendBufSize(size) => self.options.set_send_buf_size(size),
+            SocketOption::Broadcast(broadcast) => self.options.set_broadcast(broadcast),
+            SocketOption::ReuseAddr(_) if self.is_bound() => {
+                let cause: &str = "cannot change address reuse after bind()";
+                error!("set_socket_option(): {}", cause);
+                return Err(Fail::new(libc::EINVAL, cause));
+            },
+            SocketOption::ReuseAddr(reuse_addr) => self.options.set_reuse_addr(reuse_addr),
+            SocketOption::AddMembership(group, interface) => self.join_multicast_group(group, interface)?,
+            SocketOption::DropMembership(group, _) => self.leave_multicast_group(group)?,
+            _ => {
+                let cause: String = format!("option is not supported on UDP sockets (option={:?})", option);
+                error!("set_socket_option(): {}", cause);
+                return Err(Fail::new(libc::ENOPROTOOPT, &cause));
+            },
+        }
+        Ok(())
+    }
+
+    /// Gets an SO_* option on the socket. The option should be passed in as [option] and the value is returned in
+    /// [option].
+    pub fn get_socket_option(&self, option: SocketOption) -> Result<SocketOption, Fail> {
+        match option {
+            SocketOption::RecvBufSize(_) => Ok(SocketOption::RecvBufSize(self.options.get_recv_buf_size())),
+            SocketOption::SendBufSize(_) => Ok(SocketOption::SendBufSize(self.options.get_send_buf_size())),
+            SocketOption::Broadcast(_) => Ok(SocketOption::Broadcast(self.options.get_broadcast())),
+            SocketOption::ReuseAddr(_) => Ok(SocketOption::ReuseAddr(self.options.get_reuse_addr())),
+            _ => {
+                let cause: String = format!("option is not supported on UDP sockets (option={:?})", option);
+                error!("get_socket_option(): {}", cause);
+                Err(Fail::new(libc::ENOPROTOOPT, &cause))
+            },
+        }
+    }
+
     pub async fn push(&mut self, remote: Option<SocketAddr>, mut buf: DemiBuffer) -> Result<(), Fail> {
-        let remote: SocketAddrV4 = if let Some(remote) = remote {
-            unwrap_socketaddr(remote)?
+        if self.send_shutdown {
+            let cause: &str = "cannot send after shutting down sending";
+            error!("pushto(): {}", cause);
+            return Err(Fail::new(libc::EPIPE, cause));
+        }
+        // Datagrams without a destination go to the remote that the socket is connected to.
+        let remote: SocketAddr = if let Some(remote) = remote.or(self.connected) {
+            remote
         } else {
             let cause: String = format!("udp socket requires a remote address");
             error!("pushto(): {}", &cause);
-            return Err(Fail::new(libc::ENOTSUP, &cause));
+            return Err(Fail::new(libc::EDESTADDRREQ, &cause));
         };
         // Check that the socket is bound.
         let port: u16 = if let Some(addr) = self.local() {
+            // The socket can only talk to remotes of the address family that it is bound to.
+            if addr.is_ipv4() != remote.is_ipv4() {
+                let cause: String = format!("address family does not match bound address (remote={:?})", remote);
+                error!("pushto(): {}", &cause);
+                return Err(Fail::new(libc::EAFNOSUPPORT, &cause));
+            }
             addr.port()
         } else {
             let cause: String = format!("queue is not bound");
             error!("pushto(): {}", &cause);
             return Err(Fail::new(libc::ENOTSUP, &cause));
         };
+        // Report that an earlier datagram could not reach this remote.
+        if let Some((_, errno)) = self.pending_error.take_if(|(addr, _)| *addr == remote) {
+            let cause: String = format!("remote is unreachable (remote={:?})", remote);
+            error!("pushto(): {}", &cause);
+            return Err(Fail::new(errno, &cause));
+        }
+        // Only sockets that asked for it may send to broadcast addresses.
+        if let IpAddr::V4(remote_ipv4_addr) = remote.ip() {
+            if !self.options.get_broadcast() && self.layer3_endpoint.is_broadcast_ipv4_addr(remote_ipv4_addr) {
+                let cause: String = format!("broadcast is not enabled on this socket (remote={:?})", remote);
+                error!("pushto(): {}", &cause);
+                return Err(Fail::new(libc::EACCES, &cause));
+            }
+        }
+        // Check that the datagram fits in the send buffer.
+        let len: usize = buf.total_len();
+        if len > self.options.get_send_buf_size() {
+            let cause: String = format!("datagram does not fit in the send buffer (len={})", len);
+            error!("pushto(): {}", &cause);
+            return Err(Fail::new(libc::EMSGSIZE, &cause));
+        }
+        if self.send_queue_len + len > self.options.get_send_buf_size() {
+            let cause: String = format!("send buffer is full (len={})", len);
+            error!("pushto(): {}", &cause);
+            return Err(Fail::new(libc::ENOBUFS, &cause));
+        }
+        // Send from the address that the socket is bound to, or from our primary address if it is bound to the wildcard
+        // or to a multicast group.
+        let local_ip_addr: IpAddr = match (self.local().map(|addr: SocketAddr| addr.ip()), remote.ip()) {
+            (Some(addr), _) if !addr.is_unspecified() && !addr.is_multicast() => addr,
+            (_, IpAddr::V4(_)) => IpAddr::V4(self.local_ipv4_addr),
+            (_, IpAddr::V6(_)) => IpAddr::V6(self.local_ipv6_addr),
+        };
         let udp_header: UdpHeader = UdpHeader::new(port, remote.port());
         debug!("UDP send {:?}", udp_header);
-        udp_header.serialize_and_attach(&mut buf, &self.local_ipv4_addr, remote.ip(), self.checksum_offload);
-        // Send the packet to the lower layer.
+        udp_header.serialize_and_attach(&mut buf, &local_ip_addr, &remote.ip(), self.checksum_offload);
+        // Send the packet to the lower layer. It holds on to its room in the send buffer until then.
+        let _room: SendBufferRoom = SendBufferRoom::new(self.clone(), len);
         self.layer3_endpoint
-            .transmit_udp_packet_blocking(remote.ip().clone(), buf)
+            .transmit_udp_packet_blocking(local_ip_addr, remote.ip(), buf)
             .await
     }
 
-    pub async fn pop(&mut self, size: usize) -> Result<(SocketAddrV4, DemiBuffer), Fail> {
+    pub async fn pop(&mut self, size: usize) -> Result<(SocketAddr, DemiBuffer), Fail> {
         loop {
+            // Pops see an end of file once receiving has been shut down.
+            if self.receive_shutdown {
+                let remote: SocketAddr = self
+                    .connected
+                    .unwrap_or(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0));
+                return Ok((remote, DemiBuffer::new(0)));
+            }
+            // Connected sockets report that their remote is unreachable, unless a push got to it first.
+            if let Some(remote) = self.connected {
+                if let Some((_, errno)) = self.pending_error.take_if(|(addr, _)| *addr == remote) {
+                    let cause: String = format!("remote is unreachable (remote={:?})", remote);
+                    error!("pop(): {}", &cause);
+                    return Err(Fail::new(errno, &cause));
+                }
+            }
             match self.recv_queue.pop(None).await {
-                Ok(msg) => {
-                    let remote: SocketAddrV4 = msg.0;
+                Ok(None) => continue,
+                Ok(Some(msg)) => {
+                    let remote: SocketAddr = msg.0;
                     let mut buf: DemiBuffer = msg.1;
+                    self.recv_queue_len -= buf.total_len();
                     // We got more bytes than expected, so we trim the buffer.
                     if size < buf.len() {
-                        buf.trim(size - buf.len())?;
+                        buf.trim(buf.len() - size)?;
                     };
                     return Ok((remote, buf));
                 },
@@ -127,10 +292,96 @@ impl SharedUdpSocket {
         }
     }
 
-    pub fn receive(&mut self, remote: SocketAddrV4, buf: DemiBuffer) {
+    pub fn receive(&mut self, remote: SocketAddr, buf: DemiBuffer) {
+        // Drop the datagram if it does not fit in the receive buffer.
+        let len: usize = buf.total_len();
+        if self.recv_queue_len + len > self.options.get_recv_buf_size() {
+            self.recv_drops += 1;
+            warn!(
+                "dropping datagram: receive buffer is full (local={:?}, drops={})",
+                self.bound, self.recv_drops
+            );
+            return;
+        }
         // Push data to the receiver-side shared queue. This will cause the
         // associated pool operation to be ready.
-        self.recv_queue.push((remote, buf));
+        self.recv_queue_len += len;
+        self.recv_queue.push(Some((remote, buf)));
+    }
+
+    /// Records that the network could not deliver a datagram that we sent to [remote].
+    pub fn receive_error(&mut self, remote: SocketAddr, errno: libc::c_int) {
+        self.pending_error = Some((remote, errno));
+        // Wake up pop() on connected sockets, which reports the error.
+        if self.connected == Some(remote) {
+            self.recv_queue.push(None);
+        }
+    }
+
+    /// Queries whether or not the socket receives datagrams from [remote]. Connected sockets only receive from the
+    /// remote that they are connected to.
+    pub fn accepts_from(&self, remote: SocketAddr) -> bool {
+        self.connected.map_or(true, |connected: SocketAddr| connected == remote)
+    }
+
+    /// Queries whether or not the socket was bound to an ephemeral port when it connected.
+    pub fn is_bound_on_connect(&self) -> bool {
+        self.bound_on_connect
+    }
+
+    /// Leaves all multicast groups that this socket joined. This is called when the socket gets closed.
+    pub fn leave_multicast_groups(&mut self) {
+        let memberships: Vec<Ipv4Addr> = mem::take(&mut self.memberships);
+        for group in memberships {
+            if let Err(e) = self.layer3_endpoint.leave_ipv4_multicast_group(group) {
+                warn!("leave_multicast_groups(): {:?} (group={:?})", e, group);
+            }
+        }
+    }
+
+    /// Joins the multicast [group] on the interface with address [interface], or on the one of our choosing if it is
+    /// unspecified.
+    fn join_multicast_group(&mut self, group: Ipv4Addr, interface: Ipv4Addr) -> Result<(), Fail> {
+        if !group.is_multicast() {
+            let cause: String = format!("not a multicast address (group={:?})", group);
+            error!("set_socket_option(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        if !interface.is_unspecified() && !self.layer3_endpoint.is_local_ipv4_addr(interface) {
+            let cause: String = format!("not a local address (interface={:?})", interface);
+            error!("set_socket_option(): {}", cause);
+            return Err(Fail::new(libc::EADDRNOTAVAIL, &cause));
+        }
+        if self.memberships.contains(&group) {
+            let cause: String = format!("already a member of multicast group (group={:?})", group);
+            error!("set_socket_option(): {}", cause);
+            return Err(Fail::new(libc::EADDRINUSE, &cause));
+        }
+        self.layer3_endpoint.join_ipv4_multicast_group(group)?;
+        self.memberships.push(group);
+        Ok(())
+    }
+
+    /// Leaves the multicast [group], which this socket must have joined.
+    fn leave_multicast_group(&mut self, group: Ipv4Addr) -> Result<(), Fail> {
+        if !self.memberships.contains(&group) {
+            let cause: String = format!("not a member of multicast group (group={:?})", group);
+            error!("set_socket_option(): {}", cause);
+            return Err(Fail::new(libc::EADDRNOTAVAIL, &cause));
+        }
+        self.layer3_endpoint.leave_ipv4_multicast_group(group)?;
+        self.memberships.retain(|member: &Ipv4Addr| *member != group);
+        Ok(())
+    }
+
+    /// Queries whether or not other sockets may bind to the same local address as this one.
+    pub fn get_reuse_addr(&self) -> bool {
+        self.options.get_reuse_addr()
+    }
+
+    /// Returns the number of datagrams that this socket dropped because its receive buffer was full.
+    pub fn get_recv_drops(&self) -> u64 {
+        self.recv_drops
     }
 
     pub fn is_bound(&self) -> bool {
@@ -138,14 +389,21 @@ impl SharedUdpSocket {
     }
 
     /// Returns the local address to which the target queue is bound.
-    pub fn local(&self) -> Option<SocketAddrV4> {
+    pub fn local(&self) -> Option<SocketAddr> {
         self.bound
     }
 
     /// Returns the remote address to which the target queue is connected to.
-    /// TODO: Add later if we support connected UDP sockets.
-    pub fn remote(&self) -> Option<SocketAddrV4> {
-        None
+    pub fn remote(&self) -> Option<SocketAddr> {
+        self.connected
+    }
+}
+
+impl SendBufferRoom {
+    /// Takes room for a datagram of [len] bytes in the send buffer of [socket].
+    fn new(mut socket: SharedUdpSocket, len: usize) -> Self {
+        socket.send_queue_len += len;
+        Self { socket, len }
     }
 }
 
@@ -167,8 +425,20 @@ impl DerefMut for SharedUdpSocket {
     }
 }
 
+impl PartialEq for SharedUdpSocket {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self.0.deref(), other.0.deref())
+    }
+}
+
 impl Debug for SharedUdpSocket {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "UDP socket local={:?} remote={:?}", self.local(), self.remote())
     }
 }
+
+impl Drop for SendBufferRoom {
+    fn drop(&mut self) {
+        self.socket.send_queue_len -= self.len;
+    }
+}
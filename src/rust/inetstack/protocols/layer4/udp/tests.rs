@@ -3,7 +3,11 @@
 
 use crate::{
     inetstack::{
-        protocols::MAX_HEADER_SIZE,
+        protocols::{
+            layer2::ETHERNET2_HEADER_SIZE,
+            layer3::ipv4::IPV4_HEADER_MIN_SIZE,
+            MAX_HEADER_SIZE,
+        },
         test_helpers::{
             self,
             engine::{
@@ -13,22 +17,40 @@ use crate::{
         },
     },
     runtime::{
+        limits,
         memory::DemiBuffer,
+        network::rss::{
+            FlowSteering,
+            FrameReplication,
+            RSS_KEY,
+        },
         queue::{
             OperationResult,
             QDesc,
             QToken,
         },
     },
+    SocketOption,
 };
 use ::anyhow::Result;
 use ::libc::{
+    EACCES,
     EADDRINUSE,
+    EADDRNOTAVAIL,
     EBADF,
+    ECONNREFUSED,
+    EINVAL,
+    EMSGSIZE,
+    ENOTCONN,
+    EPIPE,
+    ETIMEDOUT,
 };
 use ::std::{
+    collections::VecDeque,
     net::{
         Ipv4Addr,
+        Shutdown,
+        SocketAddr,
         SocketAddrV4,
     },
     time::{
@@ -110,12 +132,11 @@ fn udp_push_pop() -> Result<()> {
     carrie.push_frame(bob.pop_frame());
     let carrie_qt: QToken = carrie.udp_pop(carrie_fd)?;
 
-    let (remote_addr, received_buf): (Option<SocketAddrV4>, DemiBuffer) =
-        match carrie.wait(carrie_qt, DEFAULT_TIMEOUT)? {
-            (_, OperationResult::Pop(addr, buf)) => (addr, buf),
-            _ => anyhow::bail!("Pop failed"),
-        };
-    assert_eq!(remote_addr.unwrap(), bob_addr);
+    let (remote_addr, received_buf): (Option<SocketAddr>, DemiBuffer) = match carrie.wait(carrie_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Pop(addr, buf)) => (addr, buf),
+        _ => anyhow::bail!("Pop failed"),
+    };
+    assert_eq!(remote_addr.unwrap(), SocketAddr::V4(bob_addr));
     assert_eq!(received_buf[..], buf[..]);
 
     // Close peers.
@@ -161,12 +182,11 @@ fn udp_push_pop_wildcard_address() -> Result<()> {
     // Take a packet from Bob and deliver to Carrie.
     carrie.push_frame(bob.pop_frame());
     let carrie_qt: QToken = carrie.udp_pop(carrie_fd)?;
-    let (remote_addr, received_buf): (Option<SocketAddrV4>, DemiBuffer) =
-        match carrie.wait(carrie_qt, DEFAULT_TIMEOUT)? {
-            (_, OperationResult::Pop(addr, buf)) => (addr, buf),
-            _ => anyhow::bail!("Pop failed"),
-        };
-    assert_eq!(remote_addr.unwrap(), bob_addr);
+    let (remote_addr, received_buf): (Option<SocketAddr>, DemiBuffer) = match carrie.wait(carrie_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Pop(addr, buf)) => (addr, buf),
+        _ => anyhow::bail!("Pop failed"),
+    };
+    assert_eq!(remote_addr.unwrap(), SocketAddr::V4(bob_addr));
     assert_eq!(received_buf[..], buf[..]);
     // Close peers.
     bob.udp_close(bob_fd)?;
@@ -175,6 +195,194 @@ fn udp_push_pop_wildcard_address() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn udp_push_pop_secondary_address() -> Result<()> {
+    let mut now: Instant = Instant::now();
+
+    // Setup Bob.
+    let mut bob: SharedEngine = test_helpers::new_bob(now);
+    let bob_port: u16 = 80;
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, bob_port);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    bob.udp_bind(bob_fd, bob_addr)?;
+
+    // Setup Carrie on her secondary address.
+    let mut carrie: SharedEngine = test_helpers::new_carrie(now);
+    let carrie_port: u16 = 80;
+    let carrie_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::CARRIE_SECONDARY_IPV4, carrie_port);
+    let carrie_fd: QDesc = carrie.udp_socket()?;
+    carrie.udp_bind(carrie_fd, carrie_addr)?;
+
+    // Bob does not know the link address of Carrie's secondary address, so he asks for it and Carrie answers.
+    let buf: DemiBuffer = DemiBuffer::from_slice_with_headroom(&vec![0x5a; 32][..], MAX_HEADER_SIZE)
+        .expect("slice should fit in DemiBuffer");
+    let bob_qt: QToken = bob.udp_pushto(bob_fd, buf.clone(), carrie_addr)?;
+    bob.poll();
+    bob.poll();
+    carrie.push_frame(bob.pop_frame());
+    carrie.poll();
+    carrie.poll();
+    bob.push_frame(carrie.pop_frame());
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+    now += Duration::from_micros(1);
+
+    // Take a packet from Bob and deliver to Carrie.
+    carrie.push_frame(bob.pop_frame());
+    let carrie_qt: QToken = carrie.udp_pop(carrie_fd)?;
+    let (remote_addr, received_buf): (Option<SocketAddr>, DemiBuffer) = match carrie.wait(carrie_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Pop(addr, buf)) => (addr, buf),
+        _ => anyhow::bail!("Pop failed"),
+    };
+    crate::ensure_eq!(remote_addr, Some(SocketAddr::V4(bob_addr)));
+    crate::ensure_eq!(received_buf[..], buf[..]);
+
+    // Carrie replies from her secondary address.
+    let carrie_qt: QToken = carrie.udp_pushto(carrie_fd, buf.clone(), bob_addr)?;
+    match carrie.wait(carrie_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+    bob.push_frame(carrie.pop_frame());
+    let bob_qt: QToken = bob.udp_pop(bob_fd)?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Pop(addr, _)) => crate::ensure_eq!(addr, Some(SocketAddr::V4(carrie_addr))),
+        _ => anyhow::bail!("Pop failed"),
+    };
+
+    // Close peers.
+    bob.udp_close(bob_fd)?;
+    carrie.udp_close(carrie_fd)?;
+
+    Ok(())
+}
+
+//======================================================================================================================
+// Push & Pop Fragmented
+//======================================================================================================================
+
+#[test]
+fn udp_push_pop_fragmented() -> Result<()> {
+    const DATAGRAM_SIZE: usize = 9000;
+    let mut now: Instant = Instant::now();
+
+    // Setup Bob.
+    let mut bob: SharedEngine = test_helpers::new_bob(now);
+    let bob_port: u16 = 80;
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, bob_port);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    bob.udp_bind(bob_fd, bob_addr)?;
+
+    // Setup Carrie.
+    let mut carrie: SharedEngine = test_helpers::new_carrie(now);
+    let carrie_port: u16 = 80;
+    let carrie_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::CARRIE_IPV4, carrie_port);
+    let carrie_fd: QDesc = carrie.udp_socket()?;
+    carrie.udp_bind(carrie_fd, carrie_addr)?;
+
+    // Send a datagram that does not fit in the MTU to Carrie.
+    let data: Vec<u8> = (0..DATAGRAM_SIZE).map(|i| i as u8).collect();
+    let buf: DemiBuffer =
+        DemiBuffer::from_slice_with_headroom(&data[..], MAX_HEADER_SIZE).expect("slice should fit in DemiBuffer");
+    let bob_qt: QToken = bob.udp_pushto(bob_fd, buf.clone(), carrie_addr)?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+    now += Duration::from_micros(1);
+
+    // Take all fragments from Bob and deliver them to Carrie in reverse order.
+    let frames: VecDeque<DemiBuffer> = bob.pop_all_frames();
+    crate::ensure_eq!(frames.len(), 7);
+    for frame in frames.into_iter().rev() {
+        carrie.push_frame(frame);
+    }
+    let carrie_qt: QToken = carrie.udp_pop(carrie_fd)?;
+
+    let (remote_addr, received_buf): (Option<SocketAddr>, DemiBuffer) = match carrie.wait(carrie_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Pop(addr, buf)) => (addr, buf),
+        _ => anyhow::bail!("Pop failed"),
+    };
+    // The datagram is truncated to the maximum receive size.
+    assert_eq!(remote_addr.unwrap(), SocketAddr::V4(bob_addr));
+    assert_eq!(received_buf[..], buf[..limits::POP_SIZE_MAX]);
+
+    // Close peers.
+    bob.udp_close(bob_fd)?;
+    carrie.udp_close(carrie_fd)?;
+
+    Ok(())
+}
+
+#[test]
+fn udp_push_pop_fragmented_other_queue() -> Result<()> {
+    const DATAGRAM_SIZE: usize = 9000;
+    let now: Instant = Instant::now();
+
+    // Setup Bob.
+    let mut bob: SharedEngine = test_helpers::new_bob(now);
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, 80);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    bob.udp_bind(bob_fd, bob_addr)?;
+
+    // Setup Carrie, who owns the first of two receive queues, and pick a port whose flow belongs to the second one.
+    let mut carrie: SharedEngine = test_helpers::new_carrie(now);
+    let flow_steering: FlowSteering = FlowSteering::new(RSS_KEY, 128, 2, 0)?;
+    let replications: Vec<FrameReplication> = FrameReplication::new(2)?;
+    let carrie_port: u16 = (49152..=u16::MAX)
+        .find(|port: &u16| {
+            let local: SocketAddr = SocketAddr::V4(SocketAddrV4::new(test_helpers::CARRIE_IPV4, *port));
+            !flow_steering.is_local_flow(local, SocketAddr::V4(bob_addr))
+        })
+        .expect("some flow should belong to the second queue");
+    let carrie_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::CARRIE_IPV4, carrie_port);
+    carrie
+        .get_runtime()
+        .set_flow_steering(flow_steering, replications[0].clone());
+
+    // Send a datagram that does not fit in the MTU to Carrie.
+    let data: Vec<u8> = (0..DATAGRAM_SIZE).map(|i| i as u8).collect();
+    let buf: DemiBuffer =
+        DemiBuffer::from_slice_with_headroom(&data[..], MAX_HEADER_SIZE).expect("slice should fit in DemiBuffer");
+    let bob_qt: QToken = bob.udp_pushto(bob_fd, buf, carrie_addr)?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+    for frame in bob.pop_all_frames() {
+        carrie.push_frame(frame);
+    }
+
+    // Carrie hands the reassembled datagram to the second queue, without telling Bob that the port is unreachable.
+    let frame: Vec<u8> = match replications[1].try_take() {
+        Some(frame) => frame,
+        None => anyhow::bail!("datagram should have been handed to the second queue"),
+    };
+    let headers_size: usize = ETHERNET2_HEADER_SIZE + IPV4_HEADER_MIN_SIZE as usize + 8;
+    crate::ensure_eq!(frame.len(), headers_size + DATAGRAM_SIZE);
+    crate::ensure_eq!(&frame[headers_size..], &data[..]);
+    crate::ensure_eq!(carrie.pop_all_frames().len(), 0);
+
+    // The runtime of another queue may have bound the port of a datagram that Carrie's queue receives, so Carrie does
+    // not tell Bob that it is unreachable either.
+    let buf: DemiBuffer = DemiBuffer::from_slice_with_headroom(&vec![0x5a; 32][..], MAX_HEADER_SIZE)
+        .expect("slice should fit in DemiBuffer");
+    let bob_qt: QToken = bob.udp_pushto(bob_fd, buf, SocketAddrV4::new(test_helpers::CARRIE_IPV4, 80))?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+    carrie.push_frame(bob.pop_frame());
+    crate::ensure_eq!(carrie.pop_all_frames().len(), 0);
+
+    // Close peers.
+    bob.udp_close(bob_fd)?;
+
+    Ok(())
+}
+
 //======================================================================================================================
 // Ping Pong
 //======================================================================================================================
@@ -212,12 +420,12 @@ fn udp_ping_pong() -> Result<()> {
     let carrie_qt: QToken = carrie.udp_pop(carrie_fd)?;
     carrie.poll();
 
-    let (remote_addr, received_buf_a): (Option<SocketAddrV4>, DemiBuffer) =
+    let (remote_addr, received_buf_a): (Option<SocketAddr>, DemiBuffer) =
         match carrie.wait(carrie_qt, DEFAULT_TIMEOUT)? {
             (_, OperationResult::Pop(addr, buf)) => (addr, buf),
             _ => anyhow::bail!("Pop failed"),
         };
-    assert_eq!(remote_addr.unwrap(), bob_addr);
+    assert_eq!(remote_addr.unwrap(), SocketAddr::V4(bob_addr));
     assert_eq!(received_buf_a[..], buf_a[..]);
 
     now += Duration::from_micros(1);
@@ -236,11 +444,11 @@ fn udp_ping_pong() -> Result<()> {
     // Take a packet from Carrie and deliver to Bob.
     bob.push_frame(carrie.pop_frame());
     let bob_qt: QToken = bob.udp_pop(bob_fd)?;
-    let (remote_addr, received_buf_b): (Option<SocketAddrV4>, DemiBuffer) = match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+    let (remote_addr, received_buf_b): (Option<SocketAddr>, DemiBuffer) = match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
         (_, OperationResult::Pop(addr, buf)) => (addr, buf),
         _ => anyhow::bail!("Pop failed"),
     };
-    assert_eq!(remote_addr.unwrap(), carrie_addr);
+    assert_eq!(remote_addr.unwrap(), SocketAddr::V4(carrie_addr));
     assert_eq!(received_buf_b[..], buf_b[..]);
 
     // Close peers.
@@ -346,12 +554,12 @@ fn udp_loop2_push_pop() -> Result<()> {
         // Take a packet from Bob and deliver to Carrie.
         carrie.push_frame(bob.pop_frame());
         let carrie_qt: QToken = carrie.udp_pop(carrie_fd)?;
-        let (remote_addr, received_buf): (Option<SocketAddrV4>, DemiBuffer) =
+        let (remote_addr, received_buf): (Option<SocketAddr>, DemiBuffer) =
             match carrie.wait(carrie_qt, DEFAULT_TIMEOUT)? {
                 (_, OperationResult::Pop(addr, buf)) => (addr, buf),
                 _ => anyhow::bail!("Pop failed"),
             };
-        assert_eq!(remote_addr.unwrap(), bob_addr);
+        assert_eq!(remote_addr.unwrap(), SocketAddr::V4(bob_addr));
         assert_eq!(received_buf[..], buf[..]);
     }
 
@@ -411,12 +619,12 @@ fn udp_loop2_ping_pong() -> Result<()> {
         // Take a packet from Bob and deliver to Carrie.
         carrie.push_frame(bob.pop_frame());
         let carrie_qt: QToken = carrie.udp_pop(carrie_fd)?;
-        let (remote_addr, received_buf_a): (Option<SocketAddrV4>, DemiBuffer) =
+        let (remote_addr, received_buf_a): (Option<SocketAddr>, DemiBuffer) =
             match carrie.wait(carrie_qt, DEFAULT_TIMEOUT)? {
                 (_, OperationResult::Pop(addr, buf)) => (addr, buf),
                 _ => anyhow::bail!("Pop failed"),
             };
-        assert_eq!(remote_addr.unwrap(), bob_addr);
+        assert_eq!(remote_addr.unwrap(), SocketAddr::V4(bob_addr));
         assert_eq!(received_buf_a[..], buf_a[..]);
 
         now += Duration::from_micros(1);
@@ -435,12 +643,11 @@ fn udp_loop2_ping_pong() -> Result<()> {
         // Take a packet from Carrie and deliver to Bob.
         bob.push_frame(carrie.pop_frame());
         let bob_qt: QToken = bob.udp_pop(bob_fd)?;
-        let (remote_addr, received_buf_b): (Option<SocketAddrV4>, DemiBuffer) =
-            match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
-                (_, OperationResult::Pop(addr, buf)) => (addr, buf),
-                _ => anyhow::bail!("Pop failed"),
-            };
-        assert_eq!(remote_addr.unwrap(), carrie_addr);
+        let (remote_addr, received_buf_b): (Option<SocketAddr>, DemiBuffer) = match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Pop(addr, buf)) => (addr, buf),
+            _ => anyhow::bail!("Pop failed"),
+        };
+        assert_eq!(remote_addr.unwrap(), SocketAddr::V4(carrie_addr));
         assert_eq!(received_buf_b[..], buf_b[..]);
     }
 
@@ -478,6 +685,26 @@ fn udp_bind_address_in_use() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn udp_bind_non_local_address() -> Result<()> {
+    let now = Instant::now();
+
+    // Setup Bob, who only has one address.
+    let mut bob: SharedEngine = test_helpers::new_bob(now);
+    let bob_fd: QDesc = bob.udp_socket()?;
+
+    // Try to bind Bob to Carrie's secondary address.
+    match bob.udp_bind(bob_fd, SocketAddrV4::new(test_helpers::CARRIE_SECONDARY_IPV4, 80)) {
+        Err(e) if e.errno == EADDRNOTAVAIL => {},
+        _ => anyhow::bail!("bind should have failed"),
+    };
+
+    // Close peers.
+    bob.udp_close(bob_fd)?;
+
+    Ok(())
+}
+
 #[test]
 fn udp_bind_bad_file_descriptor() -> Result<()> {
     let now = Instant::now();
@@ -529,45 +756,650 @@ fn udp_udp_close_bad_file_descriptor() -> Result<()> {
 }
 
 //======================================================================================================================
-// Bad Pop
+// Socket Options
 //======================================================================================================================
 
 #[test]
-fn udp_pop_not_bound() -> Result<()> {
-    let mut now = Instant::now();
+fn udp_recv_buf_size_drops_datagrams() -> Result<()> {
+    let now: Instant = Instant::now();
 
     // Setup Bob.
     let mut bob: SharedEngine = test_helpers::new_bob(now);
-    let bob_port = 80;
-    let bob_addr = SocketAddrV4::new(test_helpers::BOB_IPV4, bob_port);
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, 80);
     let bob_fd: QDesc = bob.udp_socket()?;
     bob.udp_bind(bob_fd, bob_addr)?;
 
-    // Setup Carrie.
+    // Setup Carrie with room for a single datagram.
     let mut carrie: SharedEngine = test_helpers::new_carrie(now);
-    let carrie_port = 80;
-    let carrie_addr = SocketAddrV4::new(test_helpers::CARRIE_IPV4, carrie_port);
-    // Carrie does not create a socket.
+    let carrie_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::CARRIE_IPV4, 80);
+    let carrie_fd: QDesc = carrie.udp_socket()?;
+    carrie.udp_set_socket_option(carrie_fd, SocketOption::RecvBufSize(32))?;
+    carrie.udp_bind(carrie_fd, carrie_addr)?;
+    match carrie.udp_get_socket_option(carrie_fd, SocketOption::RecvBufSize(0))? {
+        SocketOption::RecvBufSize(size) => crate::ensure_eq!(size, 32),
+        option => anyhow::bail!("unexpected option: {:?}", option),
+    };
 
-    // Send data to Carrie.
+    // Bob sends two datagrams, but only the first one fits in Carrie's receive buffer.
+    for byte in [0x5a, 0xa5] {
+        let buf: DemiBuffer = DemiBuffer::from_slice_with_headroom(&vec![byte; 32][..], MAX_HEADER_SIZE)
+            .expect("slice should fit in DemiBuffer");
+        let bob_qt: QToken = bob.udp_pushto(bob_fd, buf, carrie_addr)?;
+        match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Push) => {},
+            _ => anyhow::bail!("Push failed"),
+        };
+        carrie.push_frame(bob.pop_frame());
+    }
+
+    let carrie_qt: QToken = carrie.udp_pop(carrie_fd)?;
+    match carrie.wait(carrie_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Pop(_, buf)) => crate::ensure_eq!(buf[..], vec![0x5a; 32][..]),
+        _ => anyhow::bail!("Pop failed"),
+    };
+
+    // The second datagram was dropped, so there is nothing left to pop.
+    let carrie_qt: QToken = carrie.udp_pop(carrie_fd)?;
+    match carrie.wait(carrie_qt, Duration::from_millis(10)) {
+        Err(e) if e.errno == ETIMEDOUT => {},
+        _ => anyhow::bail!("Pop should have timed out"),
+    };
+
+    // Close peers.
+    bob.udp_close(bob_fd)?;
+    carrie.udp_close(carrie_fd)?;
+
+    Ok(())
+}
+
+#[test]
+fn udp_send_buf_size_too_small() -> Result<()> {
+    let now: Instant = Instant::now();
+
+    // Setup Bob with a send buffer that is smaller than the datagram.
+    let mut bob: SharedEngine = test_helpers::new_bob(now);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    bob.udp_set_socket_option(bob_fd, SocketOption::SendBufSize(16))?;
+    bob.udp_bind(bob_fd, SocketAddrV4::new(test_helpers::BOB_IPV4, 80))?;
+
+    let buf: DemiBuffer = DemiBuffer::from_slice_with_headroom(&vec![0x5a; 32][..], MAX_HEADER_SIZE)
+        .expect("slice should fit in DemiBuffer");
+    let carrie_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::CARRIE_IPV4, 80);
+    let bob_qt: QToken = bob.udp_pushto(bob_fd, buf, carrie_addr)?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Failed(e)) if e.errno == EMSGSIZE => {},
+        _ => anyhow::bail!("Push should have failed"),
+    };
+
+    // Close peers.
+    bob.udp_close(bob_fd)?;
+
+    Ok(())
+}
+
+#[test]
+fn udp_push_cancelled_releases_send_buf() -> Result<()> {
+    let now: Instant = Instant::now();
+
+    // Setup Bob with a send buffer that fits a single datagram.
+    let mut bob: SharedEngine = test_helpers::new_bob(now);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    bob.udp_set_socket_option(bob_fd, SocketOption::SendBufSize(32))?;
+    bob.udp_bind(bob_fd, SocketAddrV4::new(test_helpers::BOB_IPV4, 80))?;
+
+    // Bob does not know Alice's link address, so the push blocks on ARP while it holds the send buffer.
     let buf: DemiBuffer = DemiBuffer::from_slice_with_headroom(&vec![0x5a; 32][..], MAX_HEADER_SIZE)
         .expect("slice should fit in DemiBuffer");
+    let alice_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, 80);
+    let bob_qt: QToken = bob.udp_pushto(bob_fd, buf.clone(), alice_addr)?;
+    match bob.wait(bob_qt, Duration::from_millis(1)) {
+        Err(e) if e.errno == ETIMEDOUT => {},
+        _ => anyhow::bail!("Push should be waiting for ARP"),
+    };
+
+    // Cancelling the push gives its room in the send buffer back.
+    bob.get_runtime().cancel(bob_qt)?;
+    let carrie_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::CARRIE_IPV4, 80);
     let bob_qt: QToken = bob.udp_pushto(bob_fd, buf, carrie_addr)?;
     match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
         (_, OperationResult::Push) => {},
         _ => anyhow::bail!("Push failed"),
     };
-    bob.poll();
 
-    now += Duration::from_micros(1);
+    // Close peers.
+    bob.udp_close(bob_fd)?;
+
+    Ok(())
+}
+
+#[test]
+fn udp_push_broadcast() -> Result<()> {
+    let now: Instant = Instant::now();
+
+    // Setup Bob.
+    let mut bob: SharedEngine = test_helpers::new_bob(now);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    bob.udp_bind(bob_fd, SocketAddrV4::new(test_helpers::BOB_IPV4, 80))?;
+
+    // Bob cannot broadcast until he asks for it.
+    let buf: DemiBuffer = DemiBuffer::from_slice_with_headroom(&vec![0x5a; 32][..], MAX_HEADER_SIZE)
+        .expect("slice should fit in DemiBuffer");
+    let broadcast_addr: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::BROADCAST, 80);
+    let bob_qt: QToken = bob.udp_pushto(bob_fd, buf.clone(), broadcast_addr)?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Failed(e)) if e.errno == EACCES => {},
+        _ => anyhow::bail!("Push should have failed"),
+    };
+
+    bob.udp_set_socket_option(bob_fd, SocketOption::Broadcast(true))?;
+    let bob_qt: QToken = bob.udp_pushto(bob_fd, buf, broadcast_addr)?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+
+    // Close peers.
+    bob.udp_close(bob_fd)?;
+
+    Ok(())
+}
+
+#[test]
+fn udp_bind_reuse_addr() -> Result<()> {
+    let now: Instant = Instant::now();
+
+    // Setup Bob with two sockets that share an address.
+    let mut bob: SharedEngine = test_helpers::new_bob(now);
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, 80);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    bob.udp_set_socket_option(bob_fd, SocketOption::ReuseAddr(true))?;
+    bob.udp_bind(bob_fd, bob_addr)?;
+    let other_fd: QDesc = bob.udp_socket()?;
+    bob.udp_set_socket_option(other_fd, SocketOption::ReuseAddr(true))?;
+    bob.udp_bind(other_fd, bob_addr)?;
+
+    // A third socket that does not ask for reuse cannot join them.
+    let third_fd: QDesc = bob.udp_socket()?;
+    match bob.udp_bind(third_fd, bob_addr) {
+        Err(e) if e.errno == EADDRINUSE => {},
+        _ => anyhow::bail!("bind should have failed"),
+    };
+
+    // Reuse cannot be changed once the socket is bound.
+    match bob.udp_set_socket_option(bob_fd, SocketOption::ReuseAddr(false)) {
+        Err(e) if e.errno == EINVAL => {},
+        _ => anyhow::bail!("setsockopt should have failed"),
+    };
+
+    // Close peers.
+    bob.udp_close(bob_fd)?;
+    bob.udp_close(other_fd)?;
+    bob.udp_close(third_fd)?;
+
+    Ok(())
+}
+
+#[test]
+fn udp_push_pop_multicast() -> Result<()> {
+    let now: Instant = Instant::now();
+    let group_addr: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(239, 1, 2, 3), 80);
+
+    // Setup Bob.
+    let mut bob: SharedEngine = test_helpers::new_bob(now);
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, 80);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    bob.udp_bind(bob_fd, bob_addr)?;
+
+    // Setup Carrie, who binds to the group and joins it.
+    let mut carrie: SharedEngine = test_helpers::new_carrie(now);
+    let carrie_fd: QDesc = carrie.udp_socket()?;
+    carrie.udp_bind(carrie_fd, group_addr)?;
+    match carrie.udp_set_socket_option(
+        carrie_fd,
+        SocketOption::AddMembership(test_helpers::CARRIE_IPV4, Ipv4Addr::UNSPECIFIED),
+    ) {
+        Err(e) if e.errno == EINVAL => {},
+        _ => anyhow::bail!("joining a unicast address should have failed"),
+    };
+    carrie.udp_set_socket_option(
+        carrie_fd,
+        SocketOption::AddMembership(*group_addr.ip(), Ipv4Addr::UNSPECIFIED),
+    )?;
+    carrie.pop_all_frames();
+
+    // Send data to the group.
+    let buf: DemiBuffer = DemiBuffer::from_slice_with_headroom(&vec![0x5a; 32][..], MAX_HEADER_SIZE)
+        .expect("slice should fit in DemiBuffer");
+    let bob_qt: QToken = bob.udp_pushto(bob_fd, buf.clone(), group_addr)?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+
+    // Take a packet from Bob and deliver to Carrie.
+    carrie.push_frame(bob.pop_frame());
+    let carrie_qt: QToken = carrie.udp_pop(carrie_fd)?;
+    match carrie.wait(carrie_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Pop(addr, received_buf)) => {
+            crate::ensure_eq!(addr, Some(SocketAddr::V4(bob_addr)));
+            crate::ensure_eq!(received_buf[..], buf[..]);
+        },
+        _ => anyhow::bail!("Pop failed"),
+    };
+
+    // Once Carrie leaves the group, the datagrams no longer reach her.
+    carrie.udp_set_socket_option(
+        carrie_fd,
+        SocketOption::DropMembership(*group_addr.ip(), Ipv4Addr::UNSPECIFIED),
+    )?;
+    match carrie.udp_set_socket_option(
+        carrie_fd,
+        SocketOption::DropMembership(*group_addr.ip(), Ipv4Addr::UNSPECIFIED),
+    ) {
+        Err(e) if e.errno == EADDRNOTAVAIL => {},
+        _ => anyhow::bail!("leaving a group twice should have failed"),
+    };
+    let bob_qt: QToken = bob.udp_pushto(bob_fd, buf, group_addr)?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+    carrie.push_frame(bob.pop_frame());
+    match carrie.wait(carrie_qt, DEFAULT_TIMEOUT) {
+        Err(e) if e.errno == ETIMEDOUT => {},
+        _ => anyhow::bail!("Pop should have timed out"),
+    };
+
+    // Close peers.
+    bob.udp_close(bob_fd)?;
+    carrie.udp_close(carrie_fd)?;
+
+    Ok(())
+}
+
+//======================================================================================================================
+// Bad Pop
+//======================================================================================================================
+
+#[test]
+fn udp_pop_not_bound() -> Result<()> {
+    let mut now = Instant::now();
+
+    // Setup Bob.
+    let mut bob: SharedEngine = test_helpers::new_bob(now);
+    let bob_port = 80;
+    let bob_addr = SocketAddrV4::new(test_helpers::BOB_IPV4, bob_port);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    bob.udp_bind(bob_fd, bob_addr)?;
+
+    // Setup Carrie.
+    let mut carrie: SharedEngine = test_helpers::new_carrie(now);
+    let carrie_port = 80;
+    let carrie_addr = SocketAddrV4::new(test_helpers::CARRIE_IPV4, carrie_port);
+    // Carrie does not create a socket.
+
+    // Send data to Carrie.
+    let buf: DemiBuffer = DemiBuffer::from_slice_with_headroom(&vec![0x5a; 32][..], MAX_HEADER_SIZE)
+        .expect("slice should fit in DemiBuffer");
+    let bob_qt: QToken = bob.udp_pushto(bob_fd, buf, carrie_addr)?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+    bob.poll();
+
+    now += Duration::from_micros(1);
+
+    // Take a packet from Bob and deliver to Carrie.
+    // TODO: check that Carrie drops this packet.
+    // FIXME: https://github.com/microsoft/demikernel/issues/1065
+    carrie.push_frame(bob.pop_frame());
+    // Close peers.
+    bob.udp_close(bob_fd)?;
+    // Carrie does not have a socket.
+
+    Ok(())
+}
+
+//======================================================================================================================
+// Connect
+//======================================================================================================================
+
+#[test]
+fn udp_connect_push_pop() -> Result<()> {
+    let mut now: Instant = Instant::now();
+
+    // Setup Bob, who connects without binding first.
+    let mut bob: SharedEngine = test_helpers::new_bob(now);
+    let bob_fd: QDesc = bob.udp_socket()?;
+
+    // Setup Carrie.
+    let mut carrie: SharedEngine = test_helpers::new_carrie(now);
+    let carrie_port: u16 = 80;
+    let carrie_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::CARRIE_IPV4, carrie_port);
+    let carrie_fd: QDesc = carrie.udp_socket()?;
+    carrie.udp_bind(carrie_fd, carrie_addr)?;
+
+    // Connect Bob to Carrie.
+    let bob_qt: QToken = bob.udp_connect(bob_fd, carrie_addr)?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Connect) => {},
+        _ => anyhow::bail!("Connect failed"),
+    };
+    crate::ensure_eq!(bob.udp_getpeername(bob_fd)?, SocketAddr::V4(carrie_addr));
+
+    // Send data to Carrie without an address.
+    let buf: DemiBuffer = DemiBuffer::from_slice_with_headroom(&vec![0x5a; 32][..], MAX_HEADER_SIZE)
+        .expect("slice should fit in DemiBuffer");
+    let bob_qt: QToken = bob.udp_push(bob_fd, buf.clone())?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+    now += Duration::from_micros(1);
+
+    // Carrie receives the data from the address that Bob was bound to on connect.
+    carrie.push_frame(bob.pop_frame());
+    let carrie_qt: QToken = carrie.udp_pop(carrie_fd)?;
+    let bob_addr: SocketAddrV4 = match carrie.wait(carrie_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Pop(Some(SocketAddr::V4(addr)), received_buf)) => {
+            crate::ensure_eq!(received_buf[..], buf[..]);
+            addr
+        },
+        _ => anyhow::bail!("Pop failed"),
+    };
+    crate::ensure_eq!(*bob_addr.ip(), test_helpers::BOB_IPV4);
+
+    // Carrie replies.
+    let carrie_qt: QToken = carrie.udp_pushto(carrie_fd, buf.clone(), bob_addr)?;
+    match carrie.wait(carrie_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+    now += Duration::from_micros(1);
+
+    bob.push_frame(carrie.pop_frame());
+    let bob_qt: QToken = bob.udp_pop(bob_fd)?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Pop(addr, received_buf)) => {
+            crate::ensure_eq!(addr, Some(SocketAddr::V4(carrie_addr)));
+            crate::ensure_eq!(received_buf[..], buf[..]);
+        },
+        _ => anyhow::bail!("Pop failed"),
+    };
+
+    // Close peers.
+    bob.udp_close(bob_fd)?;
+    carrie.udp_close(carrie_fd)?;
+
+    Ok(())
+}
+
+#[test]
+fn udp_connect_filters_other_sources() -> Result<()> {
+    let mut now: Instant = Instant::now();
+
+    // Setup Bob.
+    let mut bob: SharedEngine = test_helpers::new_bob(now);
+    let bob_port: u16 = 80;
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, bob_port);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    bob.udp_bind(bob_fd, bob_addr)?;
+
+    // Setup Carrie with two sockets.
+    let mut carrie: SharedEngine = test_helpers::new_carrie(now);
+    let carrie_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::CARRIE_IPV4, 80);
+    let carrie_other_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::CARRIE_IPV4, 81);
+    let carrie_other_fd: QDesc = carrie.udp_socket()?;
+    carrie.udp_bind(carrie_other_fd, carrie_other_addr)?;
+
+    // Connect Bob to the first address of Carrie.
+    let bob_qt: QToken = bob.udp_connect(bob_fd, carrie_addr)?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Connect) => {},
+        _ => anyhow::bail!("Connect failed"),
+    };
+
+    // Send data to Bob from the other address.
+    let buf: DemiBuffer = DemiBuffer::from_slice_with_headroom(&vec![0x5a; 32][..], MAX_HEADER_SIZE)
+        .expect("slice should fit in DemiBuffer");
+    let carrie_qt: QToken = carrie.udp_pushto(carrie_other_fd, buf, bob_addr)?;
+    match carrie.wait(carrie_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+    now += Duration::from_micros(1);
+
+    // Bob drops the datagram, so there is nothing to pop.
+    bob.push_frame(carrie.pop_frame());
+    let bob_qt: QToken = bob.udp_pop(bob_fd)?;
+    match bob.wait(bob_qt, Duration::from_millis(10)) {
+        Err(e) if e.errno == ETIMEDOUT => {},
+        _ => anyhow::bail!("Pop should have timed out"),
+    };
+
+    // Close peers.
+    bob.udp_close(bob_fd)?;
+    carrie.udp_close(carrie_other_fd)?;
+
+    Ok(())
+}
+
+#[test]
+fn udp_connect_pop_port_unreachable() -> Result<()> {
+    let mut now: Instant = Instant::now();
+
+    // Setup Bob.
+    let mut bob: SharedEngine = test_helpers::new_bob(now);
+    let bob_port: u16 = 80;
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, bob_port);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    bob.udp_bind(bob_fd, bob_addr)?;
+
+    // Setup Carrie.
+    let mut carrie: SharedEngine = test_helpers::new_carrie(now);
+    let carrie_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::CARRIE_IPV4, 80);
+    // Carrie does not create a socket.
+
+    // Connect Bob to Carrie and send data.
+    let bob_qt: QToken = bob.udp_connect(bob_fd, carrie_addr)?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Connect) => {},
+        _ => anyhow::bail!("Connect failed"),
+    };
+    let buf: DemiBuffer = DemiBuffer::from_slice_with_headroom(&vec![0x5a; 32][..], MAX_HEADER_SIZE)
+        .expect("slice should fit in DemiBuffer");
+    let bob_qt: QToken = bob.udp_push(bob_fd, buf)?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+    now += Duration::from_micros(1);
+
+    // A pending pop fails once Carrie tells Bob that the port is unreachable.
+    let bob_qt: QToken = bob.udp_pop(bob_fd)?;
+    carrie.push_frame(bob.pop_frame());
+    bob.push_frame(carrie.pop_frame());
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Failed(e)) if e.errno == ECONNREFUSED => {},
+        _ => anyhow::bail!("Pop should have failed"),
+    };
+
+    // Close peers.
+    bob.udp_close(bob_fd)?;
+
+    Ok(())
+}
+
+#[test]
+fn udp_getpeername_not_connected() -> Result<()> {
+    let now: Instant = Instant::now();
+
+    // Setup Bob.
+    let mut bob: SharedEngine = test_helpers::new_bob(now);
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, 80);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    bob.udp_bind(bob_fd, bob_addr)?;
+
+    match bob.udp_getpeername(bob_fd) {
+        Err(e) if e.errno == ENOTCONN => {},
+        _ => anyhow::bail!("getpeername() should have failed"),
+    };
+
+    // Close peers.
+    bob.udp_close(bob_fd)?;
+
+    Ok(())
+}
+
+//======================================================================================================================
+// Sockname & Shutdown
+//======================================================================================================================
+
+#[test]
+fn udp_getsockname_after_connect() -> Result<()> {
+    let now: Instant = Instant::now();
+
+    // Setup Bob, who connects without binding first.
+    let mut bob: SharedEngine = test_helpers::new_bob(now);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    let carrie_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::CARRIE_IPV4, 80);
+
+    match bob.udp_getsockname(bob_fd) {
+        Err(e) if e.errno == EINVAL => {},
+        _ => anyhow::bail!("getsockname() should have failed"),
+    };
+
+    // Connect Bob to Carrie, which binds Bob to an ephemeral port.
+    let bob_qt: QToken = bob.udp_connect(bob_fd, carrie_addr)?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Connect) => {},
+        _ => anyhow::bail!("Connect failed"),
+    };
+    match bob.udp_getsockname(bob_fd)? {
+        SocketAddr::V4(addr) => {
+            crate::ensure_eq!(*addr.ip(), test_helpers::BOB_IPV4);
+            crate::ensure_neq!(addr.port(), 0);
+        },
+        addr => anyhow::bail!("unexpected local address (addr={:?})", addr),
+    };
+
+    // Close peers.
+    bob.udp_close(bob_fd)?;
+
+    Ok(())
+}
+
+#[test]
+fn udp_shutdown() -> Result<()> {
+    let now: Instant = Instant::now();
+
+    // Setup Bob.
+    let mut bob: SharedEngine = test_helpers::new_bob(now);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    let carrie_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::CARRIE_IPV4, 80);
+
+    // Shutting down an unconnected socket fails.
+    match bob.udp_shutdown(bob_fd, Shutdown::Both) {
+        Err(e) if e.errno == ENOTCONN => {},
+        _ => anyhow::bail!("shutdown() should have failed"),
+    };
+
+    // Connect Bob to Carrie.
+    let bob_qt: QToken = bob.udp_connect(bob_fd, carrie_addr)?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Connect) => {},
+        _ => anyhow::bail!("Connect failed"),
+    };
+
+    // A pending pop sees the end of file once receiving is shut down.
+    let bob_pop_qt: QToken = bob.udp_pop(bob_fd)?;
+    bob.poll();
+    bob.udp_shutdown(bob_fd, Shutdown::Read)?;
+    match bob.wait(bob_pop_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Pop(_, received_buf)) => crate::ensure_eq!(received_buf.len(), 0),
+        _ => anyhow::bail!("Pop failed"),
+    };
+
+    // Pushes fail once sending is shut down.
+    bob.udp_shutdown(bob_fd, Shutdown::Write)?;
+    let buf: DemiBuffer = DemiBuffer::from_slice_with_headroom(&vec![0x5a; 32][..], MAX_HEADER_SIZE)
+        .expect("slice should fit in DemiBuffer");
+    let bob_qt: QToken = bob.udp_push(bob_fd, buf)?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Failed(e)) if e.errno == EPIPE => {},
+        _ => anyhow::bail!("push() should have failed"),
+    };
+
+    // Close peers.
+    bob.udp_close(bob_fd)?;
+
+    Ok(())
+}
+
+//======================================================================================================================
+// Port Unreachable
+//======================================================================================================================
+
+/// Size of the header of ICMPv4 messages.
+const ICMPV4_HEADER_SIZE: usize = 8;
+
+/// Size of the IPv4 and UDP headers of a datagram without IPv4 options.
+const QUOTED_HEADERS_SIZE: usize = IPV4_HEADER_MIN_SIZE as usize + 8;
+
+#[test]
+fn udp_push_port_unreachable() -> Result<()> {
+    let mut now: Instant = Instant::now();
+
+    // Setup Bob.
+    let mut bob: SharedEngine = test_helpers::new_bob(now);
+    let bob_port: u16 = 80;
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, bob_port);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    bob.udp_bind(bob_fd, bob_addr)?;
+
+    // Setup Carrie.
+    let mut carrie: SharedEngine = test_helpers::new_carrie(now);
+    let carrie_port: u16 = 80;
+    let carrie_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::CARRIE_IPV4, carrie_port);
+    // Carrie does not create a socket.
+
+    // Send data to Carrie. The datagram is too big to quote in full.
+    let buf: DemiBuffer = DemiBuffer::from_slice_with_headroom(&vec![0x5a; 1024][..], MAX_HEADER_SIZE)
+        .expect("slice should fit in DemiBuffer");
+    let bob_qt: QToken = bob.udp_pushto(bob_fd, buf.clone(), carrie_addr)?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+    now += Duration::from_micros(1);
+
+    // Carrie tells Bob that the port is unreachable. The message quotes the IPv4 and UDP headers of the datagram that
+    // Bob sent, as they were sent.
+    let sent: Vec<u8> = bob.pop_frame().to_vec();
+    carrie.push_frame(DemiBuffer::from_slice(&sent)?);
+    let reply: DemiBuffer = carrie.pop_frame();
+    let sent_headers: &[u8] = &sent[ETHERNET2_HEADER_SIZE..][..QUOTED_HEADERS_SIZE];
+    let quoted_headers: &[u8] = &reply[ETHERNET2_HEADER_SIZE + IPV4_HEADER_MIN_SIZE as usize + ICMPV4_HEADER_SIZE..];
+    crate::ensure_eq!(&quoted_headers[..QUOTED_HEADERS_SIZE], sent_headers);
+    bob.push_frame(reply);
+
+    // The next push to Carrie fails.
+    let bob_qt: QToken = bob.udp_pushto(bob_fd, buf.clone(), carrie_addr)?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Failed(e)) if e.errno == ECONNREFUSED => {},
+        _ => anyhow::bail!("Push should have failed"),
+    };
+
+    // The error is only reported once.
+    let bob_qt: QToken = bob.udp_pushto(bob_fd, buf, carrie_addr)?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
 
-    // Take a packet from Bob and deliver to Carrie.
-    // TODO: check that Carrie drops this packet.
-    // FIXME: https://github.com/microsoft/demikernel/issues/1065
-    carrie.push_frame(bob.pop_frame());
     // Close peers.
     bob.udp_close(bob_fd)?;
-    // Carrie does not have a socket.
 
     Ok(())
 }
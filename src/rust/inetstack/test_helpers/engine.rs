@@ -17,7 +17,10 @@ use crate::{
             DemiBuffer,
             MemoryRuntime,
         },
-        network::types::MacAddress,
+        network::{
+            socket::option::SocketOption,
+            types::MacAddress,
+        },
         OperationResult,
         QDesc,
         QToken,
@@ -37,6 +40,8 @@ use ::std::{
     },
     net::{
         Ipv4Addr,
+        Shutdown,
+        SocketAddr,
         SocketAddrV4,
     },
     ops::{
@@ -69,7 +74,7 @@ impl SharedEngine {
         let transport: SharedInetStack = SharedInetStack::new_test(&config, runtime.clone(), layer1_endpoint.clone())?;
 
         Ok(Self(SharedObject::new(Engine {
-            libos: SharedNetworkLibOS::<SharedInetStack>::new(config.local_ipv4_addr()?, runtime, transport),
+            libos: SharedNetworkLibOS::<SharedInetStack>::new(config.local_ipv4_addrs(), runtime, transport),
             layer1_endpoint,
         })))
     }
@@ -82,6 +87,10 @@ impl SharedEngine {
         self.layer1_endpoint.pop_all_frames()
     }
 
+    pub fn is_multicast_member(&self, link_addr: MacAddress) -> bool {
+        self.layer1_endpoint.is_multicast_member(link_addr)
+    }
+
     pub fn advance_clock(&mut self, now: Instant) {
         self.libos.get_runtime().advance_clock(now)
     }
@@ -103,6 +112,11 @@ impl SharedEngine {
         self.libos.pushto(qd, &data, to.into())
     }
 
+    pub fn udp_push(&mut self, qd: QDesc, buf: DemiBuffer) -> Result<QToken, Fail> {
+        let data: demi_sgarray_t = self.libos.get_transport().into_sgarray(buf)?;
+        self.libos.push(qd, &data)
+    }
+
     pub fn udp_pop(&mut self, qd: QDesc) -> Result<QToken, Fail> {
         self.libos.pop(qd, None)
     }
@@ -115,6 +129,30 @@ impl SharedEngine {
         self.libos.bind(socket_fd, endpoint.into())
     }
 
+    pub fn udp_connect(&mut self, socket_fd: QDesc, remote_endpoint: SocketAddrV4) -> Result<QToken, Fail> {
+        self.libos.connect(socket_fd, remote_endpoint.into())
+    }
+
+    pub fn udp_getpeername(&mut self, socket_fd: QDesc) -> Result<SocketAddr, Fail> {
+        self.libos.getpeername(socket_fd)
+    }
+
+    pub fn udp_getsockname(&mut self, socket_fd: QDesc) -> Result<SocketAddr, Fail> {
+        self.libos.getsockname(socket_fd)
+    }
+
+    pub fn udp_shutdown(&mut self, socket_fd: QDesc, how: Shutdown) -> Result<(), Fail> {
+        self.libos.shutdown(socket_fd, how)
+    }
+
+    pub fn udp_set_socket_option(&mut self, socket_fd: QDesc, option: SocketOption) -> Result<(), Fail> {
+        self.libos.set_socket_option(socket_fd, option)
+    }
+
+    pub fn udp_get_socket_option(&mut self, socket_fd: QDesc, option: SocketOption) -> Result<SocketOption, Fail> {
+        self.libos.get_socket_option(socket_fd, option)
+    }
+
     pub fn udp_close(&mut self, socket_fd: QDesc) -> Result<(), Fail> {
         let qt = self.libos.async_close(socket_fd)?;
         match self.wait(qt, DEFAULT_TIMEOUT)? {
@@ -152,10 +190,18 @@ impl SharedEngine {
         self.libos.async_close(socket_fd)
     }
 
+    pub fn tcp_shutdown(&mut self, socket_fd: QDesc, how: Shutdown) -> Result<(), Fail> {
+        self.libos.shutdown(socket_fd, how)
+    }
+
     pub fn tcp_listen(&mut self, socket_fd: QDesc, backlog: usize) -> Result<(), Fail> {
         self.libos.listen(socket_fd, backlog)
     }
 
+    pub fn tcp_set_socket_option(&mut self, socket_fd: QDesc, option: SocketOption) -> Result<(), Fail> {
+        self.libos.set_socket_option(socket_fd, option)
+    }
+
     pub async fn arp_query(self, ipv4_addr: Ipv4Addr) -> Result<MacAddress, Fail> {
         self.libos.get_transport().arp_query(ipv4_addr).await
     }
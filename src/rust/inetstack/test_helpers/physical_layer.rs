@@ -7,7 +7,6 @@
 
 use crate::{
     demi_sgarray_t,
-    demi_sgaseg_t,
     inetstack::protocols::{
         layer1::PhysicalLayer,
         MAX_HEADER_SIZE,
@@ -16,19 +15,25 @@ use crate::{
         fail::Fail,
         logging,
         memory::{
+            alloc_buffer_chain,
+            sgarray_from_buffer,
             DemiBuffer,
             MemoryRuntime,
         },
-        network::consts::RECEIVE_BATCH_SIZE,
+        network::{
+            consts::RECEIVE_BATCH_SIZE,
+            types::MacAddress,
+        },
         SharedDemiRuntime,
         SharedObject,
     },
 };
 use ::arrayvec::ArrayVec;
-use ::libc::c_void;
 use ::std::{
-    collections::VecDeque,
-    mem,
+    collections::{
+        HashSet,
+        VecDeque,
+    },
     ops::{
         Deref,
         DerefMut,
@@ -44,6 +49,8 @@ pub struct TestPhysicalLayer {
     incoming: VecDeque<DemiBuffer>,
     outgoing: VecDeque<DemiBuffer>,
     runtime: SharedDemiRuntime,
+    /// Multicast link addresses that the network stack asked us to receive frames for.
+    multicast_link_addrs: HashSet<MacAddress>,
 }
 
 #[derive(Clone)]
@@ -60,6 +67,7 @@ impl SharedTestPhysicalLayer {
             incoming: VecDeque::new(),
             outgoing: VecDeque::new(),
             runtime: SharedDemiRuntime::new(now),
+            multicast_link_addrs: HashSet::new(),
         }))
     }
 
@@ -82,6 +90,11 @@ impl SharedTestPhysicalLayer {
         self.incoming.push_back(pkt);
     }
 
+    /// Queries whether or not the network stack asked us to receive frames that are sent to [link_addr].
+    pub fn is_multicast_member(&self, link_addr: MacAddress) -> bool {
+        self.multicast_link_addrs.contains(&link_addr)
+    }
+
     /// Get the underlying DemiRuntime.
     pub fn get_runtime(&self) -> SharedDemiRuntime {
         self.runtime.clone()
@@ -97,12 +110,15 @@ impl PhysicalLayer for SharedTestPhysicalLayer {
         debug!(
             "transmit frame: {:?} total packet size: {:?}",
             self.outgoing.len(),
-            pkt.len()
+            pkt.total_len()
         );
 
         // The packet header and body must fit into whatever physical media we're transmitting over.
         // For this test harness, we 2^16 bytes (u16::MAX) as our limit.
-        assert!(pkt.len() < u16::MAX as usize);
+        assert!(pkt.total_len() < u16::MAX as usize);
+
+        // Hand out contiguous frames, so that tests can parse them.
+        let pkt: DemiBuffer = pkt.linearize()?;
 
         self.outgoing.push_back(pkt);
         Ok(())
@@ -115,6 +131,16 @@ impl PhysicalLayer for SharedTestPhysicalLayer {
         }
         Ok(out)
     }
+
+    fn join_multicast_group(&mut self, link_addr: MacAddress) -> Result<(), Fail> {
+        self.multicast_link_addrs.insert(link_addr);
+        Ok(())
+    }
+
+    fn leave_multicast_group(&mut self, link_addr: MacAddress) -> Result<(), Fail> {
+        self.multicast_link_addrs.remove(&link_addr);
+        Ok(())
+    }
 }
 
 //======================================================================================================================
@@ -138,36 +164,10 @@ impl DerefMut for SharedTestPhysicalLayer {
 impl MemoryRuntime for SharedTestPhysicalLayer {
     /// Allocates a scatter-gather array.
     fn sgaalloc(&self, size: usize) -> Result<demi_sgarray_t, Fail> {
-        // TODO: Allocate an array of buffers if requested size is too large for a single buffer.
-
-        // We can't allocate a zero-sized buffer.
-        if size == 0 {
-            let cause: String = format!("cannot allocate a zero-sized buffer");
-            error!("sgaalloc(): {}", cause);
-            return Err(Fail::new(libc::EINVAL, &cause));
-        }
-
-        // We can't allocate more than a single buffer.
-        if size > u16::MAX as usize {
-            return Err(Fail::new(libc::EINVAL, "size too large for a single demi_sgaseg_t"));
-        }
-
-        // First allocate the underlying DemiBuffer.
-        let buf: DemiBuffer = DemiBuffer::new_with_headroom(size as u16, MAX_HEADER_SIZE as u16);
-
-        // Create a scatter-gather segment to expose the DemiBuffer to the user.
-        let data: *const u8 = buf.as_ptr();
-        let sga_seg: demi_sgaseg_t = demi_sgaseg_t {
-            sgaseg_buf: data as *mut c_void,
-            sgaseg_len: size as u32,
-        };
-
-        // Create and return a new scatter-gather array (which inherits the DemiBuffer's reference).
-        Ok(demi_sgarray_t {
-            sga_buf: buf.into_raw().as_ptr() as *mut c_void,
-            sga_numsegs: 1,
-            sga_segs: [sga_seg],
-            sga_addr: unsafe { mem::zeroed() },
-        })
+        let max_segment_size: usize = u16::MAX as usize - MAX_HEADER_SIZE;
+        let buf: DemiBuffer = alloc_buffer_chain(size, max_segment_size, |segment_size: u16| {
+            Ok(DemiBuffer::new_with_headroom(segment_size, MAX_HEADER_SIZE as u16))
+        })?;
+        sgarray_from_buffer(buf)
     }
 }
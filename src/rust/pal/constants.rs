@@ -23,6 +23,27 @@ pub const SOL_SOCKET: i32 = WinSock::SOL_SOCKET;
 #[cfg(target_os = "windows")]
 pub const SO_LINGER: i32 = WinSock::SO_LINGER;
 
+#[cfg(target_os = "windows")]
+pub const SO_RCVBUF: i32 = WinSock::SO_RCVBUF;
+
+#[cfg(target_os = "windows")]
+pub const SO_SNDBUF: i32 = WinSock::SO_SNDBUF;
+
+#[cfg(target_os = "windows")]
+pub const SO_BROADCAST: i32 = WinSock::SO_BROADCAST;
+
+#[cfg(target_os = "windows")]
+pub const SO_REUSEADDR: i32 = WinSock::SO_REUSEADDR;
+
+#[cfg(target_os = "windows")]
+pub const SHUT_RD: i32 = WinSock::SD_RECEIVE.0;
+
+#[cfg(target_os = "windows")]
+pub const SHUT_WR: i32 = WinSock::SD_SEND.0;
+
+#[cfg(target_os = "windows")]
+pub const SHUT_RDWR: i32 = WinSock::SD_BOTH.0;
+
 //======================================================================================================================
 // Linux constants
 //======================================================================================================================
@@ -41,3 +62,24 @@ pub const SOL_SOCKET: i32 = libc::SOL_SOCKET;
 
 #[cfg(target_os = "linux")]
 pub const SO_LINGER: i32 = libc::SO_LINGER;
+
+#[cfg(target_os = "linux")]
+pub const SO_RCVBUF: i32 = libc::SO_RCVBUF;
+
+#[cfg(target_os = "linux")]
+pub const SO_SNDBUF: i32 = libc::SO_SNDBUF;
+
+#[cfg(target_os = "linux")]
+pub const SO_BROADCAST: i32 = libc::SO_BROADCAST;
+
+#[cfg(target_os = "linux")]
+pub const SO_REUSEADDR: i32 = libc::SO_REUSEADDR;
+
+#[cfg(target_os = "linux")]
+pub const SHUT_RD: i32 = libc::SHUT_RD;
+
+#[cfg(target_os = "linux")]
+pub const SHUT_WR: i32 = libc::SHUT_WR;
+
+#[cfg(target_os = "linux")]
+pub const SHUT_RDWR: i32 = libc::SHUT_RDWR;
@@ -13,10 +13,10 @@
 // Note: if compiled without the "libdpdk" feature defined, the DPDK-specific functionality won't be present.
 
 // Note on buffer chain support:
-// DPDK has a concept of MBuf chaining where multiple MBufs may be linked together to form a "packet".  While the
-// DemiBuffer routines for heap-allocated buffers also now support this functionality, it isn't yet exposed via the
-// DemiBuffer interface.
-// TODO: Expose buffer chain support once we have a solid use case.
+// DPDK has a concept of MBuf chaining where multiple MBufs may be linked together to form a "packet".  The DemiBuffer
+// routines for heap-allocated buffers support this functionality as well.  Chains are built with append() and taken
+// apart with into_segments().  Note that len() and the Deref implementations only cover the first segment of a chain,
+// while total_len() and segments() cover all of them.
 
 // Note on intrusive queueing:
 // Since all DemiBuffer types keep the metadata for each "view" in a separate allocated region, they can be queued
@@ -46,6 +46,7 @@ use ::dpdk_rs::{
     rte_mbuf,
     rte_mempool,
     rte_pktmbuf_adj,
+    rte_pktmbuf_chain,
     rte_pktmbuf_clone,
     rte_pktmbuf_free,
     rte_pktmbuf_prepend,
@@ -272,7 +273,7 @@ impl MetaData {
 // DemiBuffer type tags.
 // Since our MetaData structure is 64-byte aligned, the lower 6 bits of a pointer to it are guaranteed to be zero.
 // We currently only use the lower 2 of those bits to hold the type tag.
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
 enum Tag {
     Heap = 1,
     #[cfg(feature = "libdpdk")]
@@ -533,6 +534,16 @@ impl DemiBuffer {
         self.as_metadata().data_len as usize
     }
 
+    /// Returns the length of the data stored in all segments of the `DemiBuffer` chain.
+    pub fn total_len(&self) -> usize {
+        self.as_metadata().pkt_len as usize
+    }
+
+    /// Returns the number of segments in the `DemiBuffer` chain.
+    pub fn num_segments(&self) -> usize {
+        self.as_metadata().nb_segs as usize
+    }
+
     /// Removes `nbytes` bytes from the beginning of the `DemiBuffer` chain.
     // Note: If `nbytes` is greater than the length of the first segment in the chain, then this function will fail and
     // return an error, rather than remove the remaining bytes from subsequent segments in the chain.  This is to match
@@ -652,6 +663,106 @@ impl DemiBuffer {
         Ok(())
     }
 
+    /// Appends the segments of `tail` to the end of the `DemiBuffer` chain.
+    pub fn append(&mut self, tail: DemiBuffer) -> Result<(), Fail> {
+        if self.get_tag() != tail.get_tag() {
+            let cause: &str = "cannot chain buffers of different types";
+            error!("append(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, cause));
+        }
+
+        match self.get_tag() {
+            Tag::Heap => {
+                let md_first: &mut MetaData = self.as_metadata();
+                let md_tail: &mut MetaData = tail.as_metadata();
+                let nb_segs: usize = md_first.nb_segs as usize + md_tail.nb_segs as usize;
+                if nb_segs > u16::MAX as usize {
+                    return Err(Fail::new(libc::EOVERFLOW, "too many segments in buffer chain"));
+                }
+                // The segment count check above also means that pkt_len cannot overflow.
+                md_first.nb_segs = nb_segs as u16;
+                md_first.pkt_len += md_tail.pkt_len;
+                md_first.get_last_segment().next = Some(tail.get_ptr::<MetaData>());
+            },
+            #[cfg(feature = "libdpdk")]
+            Tag::Dpdk => {
+                // Safety: rte_pktmbuf_chain is a FFI, which is safe since we call it with actual MBuf pointers.
+                let ret: libc::c_int = unsafe { rte_pktmbuf_chain(self.as_mbuf(), tail.as_mbuf()) };
+                if ret != 0 {
+                    let cause: String = format!("too many segments in buffer chain: {:?}", ret);
+                    warn!("append(): {}", cause);
+                    return Err(Fail::new(libc::EOVERFLOW, &cause));
+                }
+            },
+        }
+
+        // The chain now holds the reference of `tail`, so don't run the DemiBuffer destructor on it.
+        mem::forget(tail);
+        Ok(())
+    }
+
+    /// Consumes the `DemiBuffer` chain, returning each of its segments as a `DemiBuffer` of its own.
+    pub fn into_segments(self) -> Vec<Self> {
+        let tag: Tag = self.get_tag();
+        let mut segments: Vec<Self> = Vec::with_capacity(self.num_segments());
+
+        // The segments take over the references that the chain holds, so don't run the DemiBuffer destructor on it.
+        let mut next_entry: Option<NonNull<MetaData>> = Some(self.get_ptr::<MetaData>());
+        mem::forget(self);
+
+        // MBufs share the layout of MetaData, so we unlink both types of buffers in the same way.
+        while let Some(mut entry) = next_entry {
+            // Safety: This is safe, as `entry` is aligned, dereferenceable, and the MetaData struct it points to is
+            // initialized.
+            let metadata: &mut MetaData = unsafe { entry.as_mut() };
+            next_entry = metadata.next.take();
+            metadata.nb_segs = 1;
+            metadata.pkt_len = metadata.data_len as u32;
+
+            segments.push(DemiBuffer {
+                tagged_ptr: entry.with_addr(entry.addr() | tag),
+                _phantom: PhantomData,
+            });
+        }
+
+        segments
+    }
+
+    /// Returns an iterator over the data of the segments in the `DemiBuffer` chain.
+    pub fn segments(&self) -> DemiBufferSegments<'_> {
+        DemiBufferSegments {
+            next: Some(self.get_ptr::<MetaData>()),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Copies the data of the `DemiBuffer` chain into a new heap-allocated `DemiBuffer` with a single segment. A
+    /// `DemiBuffer` that has a single segment already is returned as it is.
+    pub fn linearize(self) -> Result<Self, Fail> {
+        if !self.is_multi_segment() {
+            return Ok(self);
+        }
+
+        let total_len: usize = self.total_len();
+        if total_len > u16::MAX as usize {
+            let cause: String = format!(
+                "buffer chain is larger than a DemiBuffer can hold (len={:?})",
+                total_len
+            );
+            error!("linearize(): {}", &cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+
+        let mut buf: DemiBuffer = DemiBuffer::new(total_len as u16);
+        let mut offset: usize = 0;
+        for segment in self.segments() {
+            buf[offset..offset + segment.len()].copy_from_slice(segment);
+            offset += segment.len();
+        }
+
+        Ok(buf)
+    }
+
     ///
     /// **Description**
     ///
@@ -842,6 +953,43 @@ impl DemiBuffer {
     }
 }
 
+// ----------------
+// Segment Iterator
+// ----------------
+
+/// Iterator over the data of the segments in a `DemiBuffer` chain.
+#[derive(Clone)]
+pub struct DemiBufferSegments<'a> {
+    // Pointer to the MetaData of the next segment (if any).
+    next: Option<NonNull<MetaData>>,
+    // Hint to compiler that this struct borrows the DemiBuffer.
+    _phantom: PhantomData<&'a DemiBuffer>,
+}
+
+impl<'a> Iterator for DemiBufferSegments<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry: NonNull<MetaData> = self.next?;
+        // Safety: This is safe, as `entry` is aligned, dereferenceable, and the MetaData struct it points to is
+        // initialized.  MBufs share the layout of MetaData, so this holds for both types of buffers.
+        let metadata: &MetaData = unsafe { entry.as_ref() };
+        self.next = metadata.next;
+
+        if metadata.data_len == 0 {
+            return Some(&[]);
+        }
+        // Safety: the call to from_raw_parts is safe, as its arguments refer to a valid readable memory region of the
+        // size specified, which lives for as long as the borrowed DemiBuffer.
+        Some(unsafe {
+            slice::from_raw_parts(
+                metadata.buf_addr.offset(metadata.data_off as isize),
+                metadata.data_len as usize,
+            )
+        })
+    }
+}
+
 // ----------------
 // Helper Functions
 // ----------------
@@ -1407,4 +1555,46 @@ mod tests {
 
         Ok(())
     }
+
+    // Test building a buffer chain, walking its segments, and taking it apart again.
+    #[test]
+    fn chain() -> Result<()> {
+        let str: String = String::from("word one two three four five six seven eight nine");
+        let slice: &[u8] = str.as_bytes();
+        let mut buf: DemiBuffer = DemiBuffer::from_slice(&slice[..24])?;
+        buf.append(DemiBuffer::from_slice(&slice[24..33])?)?;
+        buf.append(DemiBuffer::from_slice(&slice[33..])?)?;
+
+        // The length only covers the first segment, while the total length covers all of them.
+        crate::ensure_eq!(buf.num_segments(), 3);
+        crate::ensure_eq!(buf.len(), 24);
+        crate::ensure_eq!(buf.total_len(), str.len());
+        let segments: Vec<&[u8]> = buf.segments().collect();
+        crate::ensure_eq!(segments, vec![&slice[..24], &slice[24..33], &slice[33..]]);
+
+        // Clones have the same segments, and copying them out yields the original data.
+        let clone: DemiBuffer = buf.clone();
+        crate::ensure_eq!(clone.num_segments(), 3);
+        let linear: DemiBuffer = clone.linearize()?;
+        crate::ensure_eq!(linear.num_segments(), 1);
+        crate::ensure_eq!(&linear[..], slice);
+
+        // Adjusting works on the first segment and trimming on the last one.
+        buf.adjust(5)?;
+        buf.trim(5)?;
+        crate::ensure_eq!(buf.total_len(), str.len() - 10);
+
+        // Each segment stands on its own once the chain is taken apart.
+        let segments: Vec<DemiBuffer> = buf.into_segments();
+        crate::ensure_eq!(segments.len(), 3);
+        crate::ensure_eq!(&segments[0][..], &slice[5..24]);
+        crate::ensure_eq!(&segments[1][..], &slice[24..33]);
+        crate::ensure_eq!(&segments[2][..], &slice[33..44]);
+        for segment in segments.iter() {
+            crate::ensure_eq!(segment.num_segments(), 1);
+            crate::ensure_eq!(segment.total_len(), segment.len());
+        }
+
+        Ok(())
+    }
 }
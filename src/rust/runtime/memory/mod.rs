@@ -14,6 +14,7 @@ use crate::runtime::{
     types::{
         demi_sgarray_t,
         demi_sgaseg_t,
+        DEMI_SGARRAY_MAXLEN,
     },
 };
 use ::libc::c_void;
@@ -23,6 +24,7 @@ use ::std::{
         self,
         NonNull,
     },
+    vec,
 };
 
 //======================================================================================================================
@@ -45,136 +47,269 @@ pub use self::{
 pub trait MemoryRuntime {
     /// Converts a buffer into a scatter-gather array.
     fn into_sgarray(&self, buf: DemiBuffer) -> Result<demi_sgarray_t, Fail> {
-        // Create a scatter-gather segment to expose the DemiBuffer to the user.
-        let data: *const u8 = buf.as_ptr();
-        let sga_seg: demi_sgaseg_t = demi_sgaseg_t {
-            sgaseg_buf: data as *mut c_void,
-            sgaseg_len: buf.len() as u32,
-        };
-
-        // Create and return a new scatter-gather array (which inherits the DemiBuffer's reference).
-        Ok(demi_sgarray_t {
-            sga_buf: buf.into_raw().as_ptr() as *mut c_void,
-            sga_numsegs: 1,
-            sga_segs: [sga_seg],
-            sga_addr: unsafe { mem::zeroed() },
-        })
+        sgarray_from_buffer(buf)
     }
 
     /// Allocates a scatter-gather array.
     fn sgaalloc(&self, size: usize) -> Result<demi_sgarray_t, Fail> {
-        // TODO: Allocate an array of buffers if requested size is too large for a single buffer.
+        let buf: DemiBuffer = alloc_buffer_chain(size, u16::MAX as usize, |segment_size: u16| {
+            Ok(DemiBuffer::new(segment_size))
+        })?;
+        sgarray_from_buffer(buf)
+    }
 
-        // We can't allocate a zero-sized buffer.
-        if size == 0 {
-            let cause: String = format!("cannot allocate a zero-sized buffer");
-            error!("sgaalloc(): {}", cause);
-            return Err(Fail::new(libc::EINVAL, &cause));
-        }
+    /// Releases a scatter-gather array.
+    fn sgafree(&self, sga: demi_sgarray_t) -> Result<(), Fail> {
+        free_sgarray(sga)
+    }
 
-        // We can't allocate more than a single buffer.
-        if size > u16::MAX as usize {
-            return Err(Fail::new(libc::EINVAL, "size too large for a single demi_sgaseg_t"));
-        }
+    /// Clones a scatter-gather array.
+    fn clone_sgarray(&self, sga: &demi_sgarray_t) -> Result<DemiBuffer, Fail> {
+        buffer_from_sgarray(sga)
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Allocates a buffer chain that holds [size] bytes, in segments of at most [max_segment_size] bytes that
+/// [alloc_segment] allocates. The chain must fit in a scatter-gather array.
+pub fn alloc_buffer_chain<F>(size: usize, max_segment_size: usize, mut alloc_segment: F) -> Result<DemiBuffer, Fail>
+where
+    F: FnMut(u16) -> Result<DemiBuffer, Fail>,
+{
+    debug_assert!(max_segment_size > 0 && max_segment_size <= u16::MAX as usize);
 
-        // First allocate the underlying DemiBuffer.
-        let buf: DemiBuffer = DemiBuffer::new(size as u16);
-
-        // Create a scatter-gather segment to expose the DemiBuffer to the user.
-        let data: *const u8 = buf.as_ptr();
-        let sga_seg: demi_sgaseg_t = demi_sgaseg_t {
-            sgaseg_buf: data as *mut c_void,
-            sgaseg_len: size as u32,
-        };
-
-        // Create and return a new scatter-gather array (which inherits the DemiBuffer's reference).
-        Ok(demi_sgarray_t {
-            sga_buf: buf.into_raw().as_ptr() as *mut c_void,
-            sga_numsegs: 1,
-            sga_segs: [sga_seg],
-            sga_addr: unsafe { mem::zeroed() },
-        })
+    // We can't allocate a zero-sized buffer.
+    if size == 0 {
+        let cause: &str = "cannot allocate a zero-sized buffer";
+        error!("sgaalloc(): {}", cause);
+        return Err(Fail::new(libc::EINVAL, cause));
     }
 
-    /// Releases a scatter-gather array.
-    fn sgafree(&self, sga: demi_sgarray_t) -> Result<(), Fail> {
-        // Check arguments.
-        // TODO: Drop this check once we support scatter-gather arrays with multiple segments.
-        if sga.sga_numsegs != 1 {
-            return Err(Fail::new(libc::EINVAL, "demi_sgarray_t has invalid segment count"));
-        }
+    // We can't allocate more segments than a scatter-gather array can describe.
+    if size.div_ceil(max_segment_size) > DEMI_SGARRAY_MAXLEN {
+        let cause: String = format!("size too large for a demi_sgarray_t (size={:?})", size);
+        error!("sgaalloc(): {}", cause);
+        return Err(Fail::new(libc::EINVAL, &cause));
+    }
 
-        if sga.sga_buf == ptr::null_mut() {
-            return Err(Fail::new(libc::EINVAL, "demi_sgarray_t has invalid DemiBuffer token"));
-        }
+    let mut buf: DemiBuffer = alloc_segment(size.min(max_segment_size) as u16)?;
+    let mut remaining: usize = size - buf.len();
+    while remaining > 0 {
+        let segment: DemiBuffer = alloc_segment(remaining.min(max_segment_size) as u16)?;
+        remaining -= segment.len();
+        buf.append(segment)?;
+    }
 
-        // Convert back to a DemiBuffer and drop it.
-        // Safety: The `NonNull::new_unchecked()` call is safe, as we verified `sga.sga_buf` is not null above.
-        let token: NonNull<u8> = unsafe { NonNull::new_unchecked(sga.sga_buf as *mut u8) };
-        // Safety: The `DemiBuffer::from_raw()` call *should* be safe, as the `sga_buf` field in the `demi_sgarray_t`
-        // contained a valid `DemiBuffer` token when we provided it to the user (and the user shouldn't change it).
-        let buf: DemiBuffer = unsafe { DemiBuffer::from_raw(token) };
-        drop(buf);
+    Ok(buf)
+}
 
-        Ok(())
+/// Converts a buffer chain into a scatter-gather array with one segment for each buffer in the chain. The
+/// scatter-gather array inherits the reference of the chain.
+pub fn sgarray_from_buffer(buf: DemiBuffer) -> Result<demi_sgarray_t, Fail> {
+    // Copy chains that are too long for a scatter-gather array into a single buffer.
+    let buf: DemiBuffer = if buf.num_segments() > DEMI_SGARRAY_MAXLEN {
+        buf.linearize()?
+    } else {
+        buf
+    };
+
+    // Create a scatter-gather segment to expose each buffer in the chain to the user.
+    let mut sga_segs: [demi_sgaseg_t; DEMI_SGARRAY_MAXLEN] = [demi_sgaseg_t {
+        sgaseg_buf: ptr::null_mut(),
+        sgaseg_len: 0,
+    }; DEMI_SGARRAY_MAXLEN];
+    for (sga_seg, segment) in sga_segs.iter_mut().zip(buf.segments()) {
+        sga_seg.sgaseg_buf = segment.as_ptr() as *mut c_void;
+        sga_seg.sgaseg_len = segment.len() as u32;
     }
 
-    /// Clones a scatter-gather array.
-    fn clone_sgarray(&self, sga: &demi_sgarray_t) -> Result<DemiBuffer, Fail> {
-        // Check arguments.
-        // TODO: Drop this check once we support scatter-gather arrays with multiple segments.
-        if sga.sga_numsegs != 1 {
-            return Err(Fail::new(libc::EINVAL, "demi_sgarray_t has invalid segment count"));
+    // Create and return a new scatter-gather array (which inherits the DemiBuffer's reference).
+    Ok(demi_sgarray_t {
+        sga_numsegs: buf.num_segments() as u32,
+        sga_buf: buf.into_raw().as_ptr() as *mut c_void,
+        sga_segs,
+        sga_addr: unsafe { mem::zeroed() },
+    })
+}
+
+/// Releases a scatter-gather array along with the buffer chain that backs it.
+pub fn free_sgarray(sga: demi_sgarray_t) -> Result<(), Fail> {
+    // Convert back to a DemiBuffer and drop it.
+    let buf: DemiBuffer = unsafe { take_sgarray_buffer(&sga)? };
+    drop(buf);
+
+    Ok(())
+}
+
+/// Clones the buffer chain that backs a scatter-gather array, taking into account any changes that the user made to
+/// the segments since we provided them.
+pub fn buffer_from_sgarray(sga: &demi_sgarray_t) -> Result<DemiBuffer, Fail> {
+    let buf: DemiBuffer = unsafe { take_sgarray_buffer(sga)? };
+    let clone: DemiBuffer = buf.clone();
+
+    // Don't drop buf, as it holds the same reference to the data as the sgarray (which should keep it).
+    mem::forget(buf);
+
+    // The user may have dropped segments from the end of the scatter-gather array, but not added any.
+    let num_segments: usize = sga.sga_numsegs as usize;
+    if num_segments > clone.num_segments() {
+        return Err(Fail::new(
+            libc::EINVAL,
+            "demi_sgarray_t has more segments than its backing buffer",
+        ));
+    }
+
+    // Adjust each buffer to its segment, and leave out the segments that the user emptied.
+    let mut segments: Vec<DemiBuffer> = Vec::with_capacity(num_segments);
+    for (sga_seg, mut segment) in sga.sga_segs[..num_segments].iter().zip(clone.into_segments()) {
+        adjust_to_sgaseg(&mut segment, sga_seg)?;
+        if !segment.is_empty() {
+            segments.push(segment);
         }
+    }
+
+    // Chain the buffers back together.
+    let mut segments: vec::IntoIter<DemiBuffer> = segments.into_iter();
+    let mut buf: DemiBuffer = match segments.next() {
+        Some(buf) => buf,
+        None => return Ok(DemiBuffer::new(0)),
+    };
+    for segment in segments {
+        buf.append(segment)?;
+    }
+
+    Ok(buf)
+}
+
+/// Converts the token of a scatter-gather array back into the buffer chain that backs it. The returned buffer takes
+/// the reference of the scatter-gather array.
+///
+/// # Safety
+///
+/// The token in `sga_buf` must have been provided by us and not been released yet.
+unsafe fn take_sgarray_buffer(sga: &demi_sgarray_t) -> Result<DemiBuffer, Fail> {
+    // Check arguments.
+    if sga.sga_numsegs == 0 || sga.sga_numsegs as usize > DEMI_SGARRAY_MAXLEN {
+        return Err(Fail::new(libc::EINVAL, "demi_sgarray_t has invalid segment count"));
+    }
+
+    if sga.sga_buf == ptr::null_mut() {
+        return Err(Fail::new(libc::EINVAL, "demi_sgarray_t has invalid DemiBuffer token"));
+    }
+
+    // Safety: The `NonNull::new_unchecked()` call is safe, as we verified `sga.sga_buf` is not null above.
+    let token: NonNull<u8> = NonNull::new_unchecked(sga.sga_buf as *mut u8);
+    // Safety: The `DemiBuffer::from_raw()` call *should* be safe, as the `sga_buf` field in the `demi_sgarray_t`
+    // contained a valid `DemiBuffer` token when we provided it to the user (and the user shouldn't change it).
+    Ok(DemiBuffer::from_raw(token))
+}
+
+/// Adjusts a buffer to the data that a scatter-gather segment describes.
+fn adjust_to_sgaseg(buf: &mut DemiBuffer, sga_seg: &demi_sgaseg_t) -> Result<(), Fail> {
+    // Check to see if the user has reduced the size of the buffer described by the sgarray segment since we
+    // provided it to them.  They could have increased the starting address of the buffer (`sgaseg_buf`),
+    // decreased the ending address of the buffer (`sgaseg_buf + sgaseg_len`), or both.
+    let sga_data: *const u8 = sga_seg.sgaseg_buf as *const u8;
+    let sga_len: usize = sga_seg.sgaseg_len as usize;
+    let buf_data: *const u8 = buf.as_ptr();
+    let mut buf_len: usize = buf.len();
+    if sga_data != buf_data || sga_len != buf_len {
+        // We need to adjust the DemiBuffer to match the user's changes.
 
-        if sga.sga_buf == ptr::null_mut() {
-            return Err(Fail::new(libc::EINVAL, "demi_sgarray_t has invalid DemiBuffer token"));
+        // First check that the user didn't do something non-sensical, like change the buffer description to
+        // reference address space outside of the DemiBuffer's allocated memory area.
+        if sga_data < buf_data || sga_data.addr() + sga_len > buf_data.addr() + buf_len {
+            return Err(Fail::new(
+                libc::EINVAL,
+                "demi_sgarray_t describes data outside backing buffer's allocated region",
+            ));
         }
 
-        // Convert back to a DemiBuffer.
-        // Safety: The `NonNull::new_unchecked()` call is safe, as we verified `sga.sga_buf` is not null above.
-        let token: NonNull<u8> = unsafe { NonNull::new_unchecked(sga.sga_buf as *mut u8) };
-        // Safety: The `DemiBuffer::from_raw()` call *should* be safe, as the `sga_buf` field in the `demi_sgarray_t`
-        // contained a valid `DemiBuffer` token when we provided it to the user (and the user shouldn't change it).
-        let buf: DemiBuffer = unsafe { DemiBuffer::from_raw(token) };
-        let mut clone: DemiBuffer = buf.clone();
-
-        // Don't drop buf, as it holds the same reference to the data as the sgarray (which should keep it).
-        mem::forget(buf);
-
-        // Check to see if the user has reduced the size of the buffer described by the sgarray segment since we
-        // provided it to them.  They could have increased the starting address of the buffer (`sgaseg_buf`),
-        // decreased the ending address of the buffer (`sgaseg_buf + sgaseg_len`), or both.
-        let sga_data: *const u8 = sga.sga_segs[0].sgaseg_buf as *const u8;
-        let sga_len: usize = sga.sga_segs[0].sgaseg_len as usize;
-        let clone_data: *const u8 = clone.as_ptr();
-        let mut clone_len: usize = clone.len();
-        if sga_data != clone_data || sga_len != clone_len {
-            // We need to adjust the DemiBuffer to match the user's changes.
-
-            // First check that the user didn't do something non-sensical, like change the buffer description to
-            // reference address space outside of the DemiBuffer's allocated memory area.
-            if sga_data < clone_data || sga_data.addr() + sga_len > clone_data.addr() + clone_len {
-                return Err(Fail::new(
-                    libc::EINVAL,
-                    "demi_sgarray_t describes data outside backing buffer's allocated region",
-                ));
-            }
-
-            // Calculate the amount the new starting address is ahead of the old.  And then adjust `clone` to match.
-            let adjustment_amount: usize = sga_data.addr() - clone_data.addr();
-            clone.adjust(adjustment_amount)?;
-
-            // An adjustment above would have reduced clone.len() by the adjustment amount.
-            clone_len -= adjustment_amount;
-            debug_assert_eq!(clone_len, clone.len());
-
-            // Trim the clone down to size.
-            let trim_amount: usize = clone_len - sga_len;
-            clone.trim(trim_amount)?;
+        // Calculate the amount the new starting address is ahead of the old.  And then adjust `buf` to match.
+        let adjustment_amount: usize = sga_data.addr() - buf_data.addr();
+        buf.adjust(adjustment_amount)?;
+
+        // An adjustment above would have reduced buf.len() by the adjustment amount.
+        buf_len -= adjustment_amount;
+        debug_assert_eq!(buf_len, buf.len());
+
+        // Trim the buffer down to size.
+        let trim_amount: usize = buf_len - sga_len;
+        buf.trim(trim_amount)?;
+    }
+
+    Ok(())
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::{
+        memory::{
+            alloc_buffer_chain,
+            buffer_from_sgarray,
+            free_sgarray,
+            sgarray_from_buffer,
+            DemiBuffer,
+        },
+        types::{
+            demi_sgarray_t,
+            DEMI_SGARRAY_MAXLEN,
+        },
+    };
+    use ::anyhow::Result;
+
+    // Test that a buffer chain round-trips through a scatter-gather array, including changes that the user makes to
+    // its segments.
+    #[test]
+    fn sgarray_segments() -> Result<()> {
+        // Allocating 10 bytes in segments of at most 4 bytes takes 3 segments.
+        let buf: DemiBuffer = alloc_buffer_chain(10, 4, |segment_size: u16| Ok(DemiBuffer::new(segment_size)))?;
+        crate::ensure_eq!(buf.num_segments(), 3);
+        let mut sga: demi_sgarray_t = sgarray_from_buffer(buf)?;
+        crate::ensure_eq!({ sga.sga_numsegs }, 3);
+        crate::ensure_eq!({ sga.sga_segs[0].sgaseg_len }, 4);
+        crate::ensure_eq!({ sga.sga_segs[1].sgaseg_len }, 4);
+        crate::ensure_eq!({ sga.sga_segs[2].sgaseg_len }, 2);
+
+        // Write to every segment and drop the first byte and the last segment.
+        for (i, sga_seg) in sga.sga_segs[..3].iter().enumerate() {
+            let data: &mut [u8] =
+                unsafe { std::slice::from_raw_parts_mut(sga_seg.sgaseg_buf as *mut u8, sga_seg.sgaseg_len as usize) };
+            data.fill(i as u8);
         }
+        sga.sga_segs[0].sgaseg_buf = unsafe { (sga.sga_segs[0].sgaseg_buf as *mut u8).add(1) } as *mut libc::c_void;
+        sga.sga_segs[0].sgaseg_len = 3;
+        sga.sga_numsegs = 2;
+        let clone: DemiBuffer = buffer_from_sgarray(&sga)?;
+        crate::ensure_eq!(clone.num_segments(), 2);
+        crate::ensure_eq!(clone.total_len(), 7);
+        crate::ensure_eq!(clone.linearize()?[..], [0, 0, 0, 1, 1, 1, 1]);
+
+        // Emptying every segment leaves an empty buffer.
+        sga.sga_segs[0].sgaseg_len = 0;
+        sga.sga_segs[1].sgaseg_len = 0;
+        crate::ensure_eq!(buffer_from_sgarray(&sga)?.total_len(), 0);
 
-        // Return the clone.
-        Ok(clone)
+        // Segments past the end of the chain are rejected.
+        sga.sga_numsegs = 4;
+        crate::ensure_eq!(buffer_from_sgarray(&sga).is_err(), true);
+        sga.sga_numsegs = 2;
+        free_sgarray(sga)?;
+
+        // Sizes that need more segments than a scatter-gather array holds are rejected.
+        let size: usize = 4 * DEMI_SGARRAY_MAXLEN + 1;
+        crate::ensure_eq!(
+            alloc_buffer_chain(size, 4, |segment_size: u16| Ok(DemiBuffer::new(segment_size))).is_err(),
+            true
+        );
+
+        Ok(())
     }
 }
@@ -13,6 +13,7 @@ pub mod memory;
 pub mod network;
 pub mod queue;
 pub mod scheduler;
+pub mod submission;
 pub mod types;
 pub use condition_variable::SharedConditionVariable;
 mod poll;
@@ -37,6 +38,10 @@ pub use dpdk_rs as libdpdk;
 
 use crate::runtime::network::{
     ephemeral::EphemeralPorts,
+    rss::{
+        FlowSteering,
+        FrameReplication,
+    },
     socket::SocketId,
     NetworkQueueTable,
 };
@@ -45,6 +50,7 @@ use crate::runtime::network::{
 use crate::coroutine_timer;
 
 use crate::{
+    expect_ok,
     expect_some,
     runtime::{
         fail::Fail,
@@ -55,8 +61,20 @@ use crate::{
         },
         scheduler::{
             SharedScheduler,
+            Task,
             TaskWithResult,
         },
+        submission::{
+            PushHandler,
+            Submission,
+            SubmissionQueue,
+            Submitter,
+            SUBMISSION_RING_SIZE,
+        },
+        timer::{
+            SharedTimer,
+            TimerGuard,
+        },
     },
 };
 use ::futures::{
@@ -68,14 +86,22 @@ use ::futures::{
 
 use ::std::{
     any::Any,
-    collections::HashMap,
-    net::SocketAddrV4,
+    collections::{
+        HashMap,
+        HashSet,
+    },
+    mem,
+    net::{
+        IpAddr,
+        SocketAddr,
+    },
     ops::{
         Deref,
         DerefMut,
     },
     pin::pin,
     rc::Rc,
+    sync::Arc,
     time::{
         Duration,
         Instant,
@@ -105,14 +131,41 @@ pub struct DemiRuntime {
     scheduler: SharedScheduler,
     /// Shared ephemeral port allocator.
     ephemeral_ports: EphemeralPorts,
+    /// Flow steering of the receive queue that this runtime owns, if the NIC spreads flows over several queues.
+    flow_steering: Option<FlowSteering>,
+    /// Handoff of frames to the receive queues of other runtimes, if the NIC spreads flows over several queues.
+    frame_replication: Option<FrameReplication>,
     /// Shared table for mapping from underlying transport identifiers to queue descriptors.
     network_table: NetworkQueueTable,
+    /// Timer of this runtime, which is independent from the timers of other runtimes.
+    timer: SharedTimer,
     /// Number of iterations that we have polled since advancing the clock.
     ts_iters: usize,
     /// Tasks that have been completed and removed from the
     completed_tasks: HashMap<QToken, (QDesc, OperationResult)>,
+    /// Work that other threads submit to this runtime.
+    submissions: Arc<SubmissionQueue<Submission>>,
+    /// Handler that pushes data that other threads submit, which the LibOS installs while it polls or waits.
+    push_handler: Option<PushHandler>,
+    /// Tasks whose completion nobody waits for, such as pushes that other threads submit.
+    detached_tasks: HashSet<QToken>,
+    /// Queue descriptors of the I/O tasks that are still running, so that cancelled tasks can complete on their queue.
+    pending_qds: HashMap<QToken, QDesc>,
+    /// Handler that blocks the thread while this runtime is idle, if polling is adaptive.
+    idle_handler: Option<IdleHandler>,
+    /// Number of quanta without completions after which this runtime blocks in the idle handler.
+    idle_streak: usize,
+    /// Number of quanta in a row without completions.
+    idle_quanta: usize,
+    /// Number of times in a row that this runtime blocked without completing any task in between.
+    idle_sleeps: usize,
 }
 
+/// Handler that blocks the thread of an idle runtime until there may be work for it, for at most the given time. It
+/// also gets the number of times in a row that the runtime blocked without completing any task, so that handlers which
+/// cannot tell when work arrives may back off.
+pub type IdleHandler = Box<dyn FnMut(Duration, usize)>;
+
 #[derive(Clone)]
 pub struct SharedDemiRuntime(SharedObject<DemiRuntime>);
 
@@ -139,24 +192,42 @@ impl DemiRuntime {
 impl SharedDemiRuntime {
     #[cfg(test)]
     pub fn new(now: Instant) -> Self {
-        timer::global_set_time(now);
+        let mut timer: SharedTimer = SharedTimer::default();
+        timer.set_time(now);
         Self(SharedObject::<DemiRuntime>::new(DemiRuntime {
             qtable: IoQueueTable::default(),
             scheduler: SharedScheduler::default(),
             ephemeral_ports: EphemeralPorts::default(),
+            flow_steering: None,
+            frame_replication: None,
             network_table: NetworkQueueTable::default(),
+            timer,
             ts_iters: 0,
             completed_tasks: HashMap::<QToken, (QDesc, OperationResult)>::new(),
+            submissions: Arc::new(expect_ok!(
+                SubmissionQueue::new(SUBMISSION_RING_SIZE),
+                "should be able to create submission queue"
+            )),
+            push_handler: None,
+            detached_tasks: HashSet::new(),
+            pending_qds: HashMap::new(),
+            idle_handler: None,
+            idle_streak: 0,
+            idle_quanta: 0,
+            idle_sleeps: 0,
         }))
     }
 
-    /// Inserts the `coroutine` named `task_name` into the scheduler.
+    /// Inserts the `coroutine` named `task_name`, which runs an operation on `qd`, into the scheduler.
     pub fn insert_io_coroutine<F: FusedFuture<Output = (QDesc, OperationResult)> + 'static>(
         &mut self,
         task_name: &'static str,
+        qd: QDesc,
         coroutine: Pin<Box<F>>,
     ) -> Result<QToken, Fail> {
-        self.insert_coroutine(task_name, coroutine)
+        let qt: QToken = self.insert_coroutine(task_name, coroutine)?;
+        self.pending_qds.insert(qt, qd);
+        Ok(qt)
     }
 
     /// Inserts the background `coroutine` named `task_name` into the scheduler
@@ -216,6 +287,7 @@ impl SharedDemiRuntime {
         self.advance_clock_to_now();
 
         loop {
+            let _guard: TimerGuard = TimerGuard::enter(&self.timer);
             if let Some(boxed_task) = self.scheduler.get_next_completed_task(TIMER_RESOLUTION) {
                 // Perform bookkeeping for the completed and removed task.
                 trace!("Removing coroutine: {:?}", boxed_task.get_name());
@@ -224,6 +296,7 @@ impl SharedDemiRuntime {
                 if let Ok(mut operation_task) = OperationTask::try_from(boxed_task.as_any()) {
                     let (qd, result): (QDesc, OperationResult) =
                         expect_some!(operation_task.get_result(), "coroutine not finished");
+                    self.pending_qds.remove(&completed_qt);
 
                     // Check whether it matches any of the queue tokens that we are waiting on.
                     if completed_qt == qt {
@@ -277,6 +350,7 @@ impl SharedDemiRuntime {
             if let Some((i, qd, result)) = self.run_any(qts, remaining_time) {
                 return Ok((i, qts[i], qd, result));
             }
+            self.idle(remaining_time);
             // Otherwise, move time forward.
             self.advance_clock_to_now();
             let now: Instant = self.get_now();
@@ -295,6 +369,44 @@ impl SharedDemiRuntime {
         self.completed_tasks.remove(qt)
     }
 
+    /// Cancels the pending I/O operation of [qt]. The coroutine of the operation is removed from the scheduler and
+    /// dropped without running any further, and the next wait on [qt] reports ECANCELED. An operation that already
+    /// completed cannot be cancelled, because its result may hold data that it took from the queue, so this fails with
+    /// EALREADY and the next wait on [qt] reports the result as usual.
+    pub fn cancel(&mut self, qt: QToken) -> Result<(), Fail> {
+        trace!("cancel(): qt={:?}", qt);
+
+        if self.completed_tasks.contains_key(&qt) {
+            let cause: String = format!("{:?} has already completed", qt);
+            warn!("cancel(): {}", cause);
+            return Err(Fail::new(libc::EALREADY, &cause));
+        }
+        let qd: QDesc = match self.pending_qds.remove(&qt) {
+            Some(qd) => qd,
+            None => {
+                let cause: String = format!("{:?} is not a valid queue token", qt);
+                warn!("cancel(): {}", cause);
+                return Err(Fail::new(libc::EINVAL, &cause));
+            },
+        };
+        let task: Box<dyn Task> = expect_some!(
+            self.scheduler.remove_task(TaskId::from(qt)),
+            "pending operations should be in the scheduler"
+        );
+        trace!("Cancelling coroutine: {:?}", task.get_name());
+
+        // Nobody waits for detached tasks, so there is nobody to report the cancellation to either.
+        if !self.detached_tasks.remove(&qt) {
+            let cause: String = format!("operation cancelled (qt={:?})", qt);
+            self.completed_tasks
+                .insert(qt, (qd, OperationResult::Failed(Fail::new(libc::ECANCELED, &cause))));
+        }
+        // Dropping the coroutine rolls back the operation, which may call back into the runtime (e.g., to free an
+        // ephemeral port), so do it last.
+        drop(task);
+        Ok(())
+    }
+
     /// Waits until the next task is complete, passing the result to `acceptor`. The acceptor may return true to
     /// continue waiting or false to exit the wait. The method will return when either the acceptor returns false
     /// (returning Ok) or the timeout has expired (returning a Fail indicating timeout).
@@ -322,6 +434,8 @@ impl SharedDemiRuntime {
                 if acceptor(qt, qd, result) == false {
                     return Ok(());
                 }
+            } else {
+                self.idle(remaining_time);
             }
             // Otherwise, move time forward.
             self.advance_clock_to_now();
@@ -362,17 +476,23 @@ impl SharedDemiRuntime {
             timeout if timeout.as_secs() > 0 => TIMER_RESOLUTION,
             _ => TIMER_FINER_RESOLUTION,
         };
+        let _guard: TimerGuard = TimerGuard::enter(&self.timer);
+        self.run_submissions();
         if let Some(boxed_task) = self.scheduler.get_next_completed_task(iterations) {
             // Perform bookkeeping for the completed and removed task.
             trace!("Removing coroutine: {:?}", boxed_task.get_name());
             let qt: QToken = boxed_task.get_id().into();
+            self.reset_idle();
 
             // If an operation task, then take a look at the result.
             if let Ok(mut operation_task) = OperationTask::try_from(boxed_task.as_any()) {
                 let (qd, result): (QDesc, OperationResult) =
                     expect_some!(operation_task.get_result(), "coroutine not finished");
+                self.pending_qds.remove(&qt);
 
-                return Some((qt, qd, result));
+                if !self.complete_detached_task(qt, qd, &result) {
+                    return Some((qt, qd, result));
+                }
             }
         }
 
@@ -381,17 +501,118 @@ impl SharedDemiRuntime {
 
     /// Performs a single pool on the underlying scheduler.
     pub fn poll(&mut self) {
+        let _guard: TimerGuard = TimerGuard::enter(&self.timer);
+        self.run_submissions();
         // For all ready tasks that were removed from the scheduler, add to our completed task list.
         for boxed_task in self.scheduler.poll_all() {
             trace!("Completed while polling coroutine: {:?}", boxed_task.get_name());
             let qt: QToken = boxed_task.get_id().into();
+            self.reset_idle();
 
             if let Ok(mut operation_task) = OperationTask::try_from(boxed_task.as_any()) {
                 let (qd, result): (QDesc, OperationResult) =
                     expect_some!(operation_task.get_result(), "coroutine not finished");
-                self.completed_tasks.insert(qt, (qd, result));
+                self.pending_qds.remove(&qt);
+                if !self.complete_detached_task(qt, qd, &result) {
+                    self.completed_tasks.insert(qt, (qd, result));
+                }
+            }
+        }
+    }
+
+    /// Returns a handle through which other threads submit work to this runtime.
+    pub fn submitter(&self) -> Submitter {
+        Submitter::new(self.submissions.clone())
+    }
+
+    /// Replaces the handler that pushes data that other threads submit to this runtime, and returns the previous one.
+    pub fn replace_push_handler(&mut self, push_handler: Option<PushHandler>) -> Option<PushHandler> {
+        mem::replace(&mut self.push_handler, push_handler)
+    }
+
+    /// Runs the work that other threads submitted to this runtime, and returns whether there was any.
+    fn run_submissions(&mut self) -> bool {
+        let submissions: Arc<SubmissionQueue<Submission>> = self.submissions.clone();
+        let mut ran: bool = false;
+        while let Some(submission) = submissions.try_take() {
+            ran = true;
+            match submission {
+                Submission::Push(qd, data) => {
+                    // Take the handler out while it runs, because it schedules the push on this runtime.
+                    let mut push_handler: PushHandler = match self.push_handler.take() {
+                        Some(push_handler) => push_handler,
+                        None => {
+                            warn!("run_submissions(): dropping push to {:?}, no push handler", qd);
+                            continue;
+                        },
+                    };
+                    match push_handler(qd, &data) {
+                        Ok(qt) => {
+                            self.detached_tasks.insert(qt);
+                        },
+                        Err(e) => warn!("run_submissions(): failed to push to {:?} (error={:?})", qd, e),
+                    }
+                    self.push_handler = Some(push_handler);
+                },
+                Submission::Call(f) => f(self),
             }
         }
+        ran
+    }
+
+    /// Makes polling adaptive: once this runtime completes no task for [idle_streak] quanta in a row, it blocks in
+    /// [idle_handler] instead of busy-polling.
+    pub fn set_idle_handler(&mut self, idle_streak: usize, idle_handler: IdleHandler) {
+        self.idle_streak = idle_streak;
+        self.idle_handler = Some(idle_handler);
+        self.reset_idle();
+    }
+
+    /// Counts a quantum without completions and, if polling is adaptive and this runtime has been idle for long
+    /// enough, blocks the thread for at most [timeout]. The thread wakes up in time for the next time out.
+    fn idle(&mut self, timeout: Duration) {
+        if self.idle_handler.is_none() {
+            return;
+        }
+        self.idle_quanta += 1;
+        // Once idle, this runtime blocks after every quantum until it completes a task again.
+        if self.idle_sleeps == 0 && self.idle_quanta < self.idle_streak {
+            return;
+        }
+
+        // Quiet the doorbell before looking for submissions, so that submissions that we miss ring it again.
+        self.submissions.doorbell().clear();
+        if self.run_submissions() {
+            return;
+        }
+
+        let timeout: Duration = match self.timer.next_deadline() {
+            Some(deadline) => timeout.min(deadline.saturating_duration_since(Instant::now())),
+            None => timeout,
+        };
+        let idle_sleeps: usize = self.idle_sleeps;
+        if let Some(idle_handler) = self.idle_handler.as_mut() {
+            trace!("idle(): blocking for {:?} (idle_sleeps={:?})", timeout, idle_sleeps);
+            idle_handler(timeout, idle_sleeps);
+        }
+        self.idle_sleeps += 1;
+    }
+
+    /// Records that this runtime completed a task, so it is not idle anymore.
+    fn reset_idle(&mut self) {
+        self.idle_quanta = 0;
+        self.idle_sleeps = 0;
+    }
+
+    /// Completes the task [qt] if nobody waits for it, and returns whether it did so.
+    fn complete_detached_task(&mut self, qt: QToken, qd: QDesc, result: &OperationResult) -> bool {
+        if !self.detached_tasks.remove(&qt) {
+            return false;
+        }
+        if let OperationResult::Failed(e) = result {
+            warn!("complete_detached_task(): {:?} on {:?} failed (error={:?})", qt, qd, e);
+        }
+        true
     }
 
     /// Allocates a queue of type `T` and returns the associated queue descriptor.
@@ -443,6 +664,63 @@ impl SharedDemiRuntime {
         }
     }
 
+    /// Allocates a port from the shared ephemeral port allocator for a connection from [local_ip] to [remote]. If the
+    /// runtime owns one of several receive queues, the port is chosen so that the packets of the connection arrive on
+    /// that queue.
+    ///
+    /// The queue of a port depends on [local_ip] and [remote], so this cannot precompute the ports of each queue.
+    /// Instead, it scans the pool in order and hashes the flow of each port until one lands on this queue. With N
+    /// queues, about one port in N does, and the ports that it skips stay at the head of the pool, so after k
+    /// connections to the same remote, a call hashes about N * (k + 1) ports. Each hash takes a few hundred bit
+    /// operations.
+    pub fn alloc_ephemeral_port_for(&mut self, local_ip: IpAddr, remote: SocketAddr) -> Result<u16, Fail> {
+        let flow_steering: FlowSteering = match self.flow_steering {
+            Some(ref flow_steering) => flow_steering.clone(),
+            None => return self.alloc_ephemeral_port(),
+        };
+        match self
+            .ephemeral_ports
+            .alloc_if(|port: u16| flow_steering.is_local_flow(SocketAddr::new(local_ip, port), remote))
+        {
+            Ok(port) => {
+                trace!("Allocating ephemeral port: {:?} (remote={:?})", port, remote);
+                Ok(port)
+            },
+            Err(e) => {
+                warn!("Could not allocate ephemeral port: remote={:?} error={:?}", remote, e);
+                Err(e)
+            },
+        }
+    }
+
+    /// Sets the flow steering of the receive queue that this runtime owns, and the [frame_replication] through which
+    /// it hands frames to the queues of other runtimes.
+    pub fn set_flow_steering(&mut self, flow_steering: FlowSteering, frame_replication: FrameReplication) {
+        self.flow_steering = Some(flow_steering);
+        self.frame_replication = Some(frame_replication);
+    }
+
+    /// Checks whether this runtime owns one of several receive queues, and thus only receives some of the flows.
+    pub fn is_flow_steered(&self) -> bool {
+        self.flow_steering.is_some()
+    }
+
+    /// Checks whether the packets that [remote] sends to [local] arrive on the receive queue of this runtime.
+    pub fn is_local_flow(&self, local: SocketAddr, remote: SocketAddr) -> bool {
+        match self.flow_steering {
+            Some(ref flow_steering) => flow_steering.is_local_flow(local, remote),
+            None => true,
+        }
+    }
+
+    /// Hands [frame], which carries a packet that [remote] sent to [local], to the runtime that owns the receive queue
+    /// of that flow.
+    pub fn forward_frame(&self, local: SocketAddr, remote: SocketAddr, frame: &[u8]) {
+        if let (Some(flow_steering), Some(frame_replication)) = (&self.flow_steering, &self.frame_replication) {
+            frame_replication.forward(flow_steering.queue_for_flow(local, remote), frame);
+        }
+    }
+
     /// Reserves a specific port if it is free.
     pub fn reserve_ephemeral_port(&mut self, port: u16) -> Result<(), Fail> {
         match self.ephemeral_ports.reserve(port) {
@@ -478,7 +756,7 @@ impl SharedDemiRuntime {
 
     /// Moves time forward deterministically.
     pub fn advance_clock(&mut self, now: Instant) {
-        timer::global_advance_clock(now)
+        self.timer.advance_clock(now)
     }
 
     /// Moves time forward to the current real time.
@@ -491,7 +769,7 @@ impl SharedDemiRuntime {
 
     /// Gets the current time according to our internal timer.
     pub fn get_now(&self) -> Instant {
-        timer::global_get_time()
+        self.timer.now()
     }
 
     /// Checks if an identifier is in use and returns the queue descriptor if it is.
@@ -531,7 +809,7 @@ impl SharedDemiRuntime {
         }
     }
 
-    pub fn addr_in_use(&self, local: SocketAddrV4) -> bool {
+    pub fn addr_in_use(&self, local: SocketAddr) -> bool {
         trace!("Check address in use: {:?}", local);
         self.network_table.addr_in_use(local)
     }
@@ -541,6 +819,11 @@ impl<T> SharedObject<T> {
     pub fn new(object: T) -> Self {
         Self(Rc::new(object))
     }
+
+    /// Returns true if both references share the same object.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
 }
 
 impl<T: ?Sized> SharedBox<T> {
@@ -590,14 +873,27 @@ pub async fn poll_yield() {
 
 impl Default for SharedDemiRuntime {
     fn default() -> Self {
-        timer::global_set_time(Instant::now());
         Self(SharedObject::<DemiRuntime>::new(DemiRuntime {
             qtable: IoQueueTable::default(),
             scheduler: SharedScheduler::default(),
             ephemeral_ports: EphemeralPorts::default(),
+            flow_steering: None,
+            frame_replication: None,
             network_table: NetworkQueueTable::default(),
+            timer: SharedTimer::default(),
             ts_iters: 0,
             completed_tasks: HashMap::<QToken, (QDesc, OperationResult)>::new(),
+            submissions: Arc::new(expect_ok!(
+                SubmissionQueue::new(SUBMISSION_RING_SIZE),
+                "should be able to create submission queue"
+            )),
+            push_handler: None,
+            detached_tasks: HashSet::new(),
+            pending_qds: HashMap::new(),
+            idle_handler: None,
+            idle_streak: 0,
+            idle_quanta: 0,
+            idle_sleeps: 0,
         }))
     }
 }
@@ -695,14 +991,37 @@ pub trait Runtime: Clone + Unpin + 'static {}
 
 #[cfg(test)]
 mod tests {
-    use crate::runtime::{
-        poll_yield,
-        OperationResult,
-        QDesc,
-        QToken,
-        SharedDemiRuntime,
+    use crate::{
+        ensure_eq,
+        runtime::{
+            fail::Fail,
+            poll_yield,
+            submission::Submitter,
+            yield_with_timeout,
+            OperationResult,
+            QDesc,
+            QToken,
+            SharedDemiRuntime,
+        },
+    };
+    use ::anyhow::Result;
+    use ::std::{
+        cell::RefCell,
+        rc::Rc,
+        sync::{
+            atomic::{
+                AtomicBool,
+                AtomicUsize,
+                Ordering,
+            },
+            Arc,
+        },
+        thread,
+        time::{
+            Duration,
+            Instant,
+        },
     };
-    use ::std::time::Duration;
     use futures::FutureExt;
     use test::Bencher;
 
@@ -719,11 +1038,192 @@ mod tests {
         }
     }
 
+    /// Cancels pending operations, and refuses to cancel operations that already completed or that are not I/O.
+    #[test]
+    fn test_cancel() -> Result<()> {
+        let mut runtime: SharedDemiRuntime = SharedDemiRuntime::default();
+
+        // A pending operation completes with ECANCELED on its queue.
+        let qt: QToken = runtime.insert_io_coroutine(
+            "pending coroutine",
+            QDesc::from(3),
+            Box::pin(dummy_coroutine(1000000000).fuse()),
+        )?;
+        runtime.poll();
+        runtime.cancel(qt)?;
+        ensure_eq!(runtime.cancel(qt).map_err(|e: Fail| e.errno), Err(libc::EALREADY));
+        match runtime.wait(qt, Duration::ZERO)? {
+            (0, completed_qt, qd, OperationResult::Failed(e)) => {
+                ensure_eq!(completed_qt, qt);
+                ensure_eq!(qd, QDesc::from(3));
+                ensure_eq!(e.errno, libc::ECANCELED);
+            },
+            result => anyhow::bail!("cancelled operation should fail with ECANCELED: {:?}", result),
+        }
+        ensure_eq!(runtime.cancel(qt).map_err(|e: Fail| e.errno), Err(libc::EINVAL));
+
+        // A completed operation keeps its result.
+        let qt: QToken =
+            runtime.insert_io_coroutine("short coroutine", QDesc::from(3), Box::pin(dummy_coroutine(0).fuse()))?;
+        runtime.poll();
+        ensure_eq!(runtime.cancel(qt).map_err(|e: Fail| e.errno), Err(libc::EALREADY));
+        match runtime.wait(qt, Duration::ZERO)? {
+            (0, _, _, OperationResult::Close) => (),
+            result => anyhow::bail!("completed operation should keep its result: {:?}", result),
+        }
+
+        // Background tasks are not operations of the application.
+        let qt: QToken = runtime
+            .insert_background_coroutine("background coroutine", Box::pin(dummy_background_coroutine().fuse()))?;
+        ensure_eq!(runtime.cancel(qt).map_err(|e: Fail| e.errno), Err(libc::EINVAL));
+        ensure_eq!(runtime.pending_qds.is_empty(), true);
+        Ok(())
+    }
+
+    /// Runs the closures and pushes that another thread submits, and completes the pushes in the background.
+    #[test]
+    fn test_submissions() -> Result<()> {
+        let mut runtime: SharedDemiRuntime = SharedDemiRuntime::default();
+        let calls: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(0));
+        let pushes: Rc<RefCell<Vec<(QDesc, Vec<u8>)>>> = Rc::new(RefCell::new(Vec::new()));
+
+        // The handler schedules a short coroutine for each push.
+        {
+            let pushes: Rc<RefCell<Vec<(QDesc, Vec<u8>)>>> = pushes.clone();
+            let mut handler_runtime: SharedDemiRuntime = runtime.clone();
+            runtime.replace_push_handler(Some(Box::new(move |qd: QDesc, data: &[u8]| {
+                pushes.borrow_mut().push((qd, data.to_vec()));
+                handler_runtime.insert_io_coroutine("push coroutine", qd, Box::pin(dummy_coroutine(1).fuse()))
+            })));
+        }
+
+        let submitter: Submitter = runtime.submitter();
+        let producer: thread::JoinHandle<()> = {
+            let calls: Arc<AtomicUsize> = calls.clone();
+            thread::spawn(move || {
+                submitter
+                    .push(QDesc::from(7), b"hello")
+                    .expect("should be able to push");
+                submitter
+                    .call(move |_: &mut SharedDemiRuntime| {
+                        calls.fetch_add(1, Ordering::Relaxed);
+                    })
+                    .expect("should be able to call");
+            })
+        };
+        if producer.join().is_err() {
+            anyhow::bail!("producer thread panicked");
+        }
+        ensure_eq!(runtime.submitter().doorbell().clear(), true);
+
+        // Nothing runs until the runtime polls.
+        ensure_eq!(calls.load(Ordering::Relaxed), 0);
+        for _ in 0..3 {
+            runtime.poll();
+        }
+        ensure_eq!(calls.load(Ordering::Relaxed), 1);
+        ensure_eq!(pushes.borrow().as_slice(), &[(QDesc::from(7), b"hello".to_vec())]);
+
+        // Nobody waits for the push, so its completion is not kept around.
+        ensure_eq!(runtime.completed_tasks.is_empty(), true);
+        ensure_eq!(runtime.detached_tasks.is_empty(), true);
+        Ok(())
+    }
+
+    /// Blocks an idle runtime in its idle handler, and wakes it up in time for the next time out.
+    #[test]
+    fn test_adaptive_polling_timer() -> Result<()> {
+        const TIMEOUT: Duration = Duration::from_millis(50);
+        let mut runtime: SharedDemiRuntime = SharedDemiRuntime::default();
+        let sleeps: Rc<RefCell<Vec<Duration>>> = Rc::new(RefCell::new(Vec::new()));
+        {
+            let sleeps: Rc<RefCell<Vec<Duration>>> = sleeps.clone();
+            runtime.set_idle_handler(
+                1,
+                Box::new(move |timeout: Duration, _: usize| {
+                    sleeps.borrow_mut().push(timeout);
+                    thread::sleep(timeout);
+                }),
+            );
+        }
+
+        let qt: QToken = runtime.insert_io_coroutine(
+            "timeout coroutine",
+            QDesc::from(0),
+            Box::pin(
+                async {
+                    yield_with_timeout(TIMEOUT).await;
+                    (QDesc::from(0), OperationResult::Close)
+                }
+                .fuse(),
+            ),
+        )?;
+        let start: Instant = Instant::now();
+        runtime.wait(qt, Duration::from_secs(10))?;
+        ensure_eq!(start.elapsed() < Duration::from_secs(5), true);
+        ensure_eq!(sleeps.borrow().is_empty(), false);
+        ensure_eq!(sleeps.borrow().iter().all(|sleep: &Duration| *sleep <= TIMEOUT), true);
+        Ok(())
+    }
+
+    /// Wakes up an idle runtime that sleeps on its doorbell when another thread submits work.
+    #[test]
+    fn test_adaptive_polling_doorbell() -> Result<()> {
+        let mut runtime: SharedDemiRuntime = SharedDemiRuntime::default();
+        let submitter: Submitter = runtime.submitter();
+        let idle_sleeps: Rc<RefCell<usize>> = Rc::new(RefCell::new(0));
+        {
+            let submitter: Submitter = submitter.clone();
+            let idle_sleeps: Rc<RefCell<usize>> = idle_sleeps.clone();
+            runtime.set_idle_handler(
+                4,
+                Box::new(move |timeout: Duration, _: usize| {
+                    *idle_sleeps.borrow_mut() += 1;
+                    submitter.doorbell().wait(timeout);
+                }),
+            );
+        }
+
+        // The coroutine completes once the closure that another thread submits has run.
+        let done: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+        let qt: QToken = {
+            let done: Arc<AtomicBool> = done.clone();
+            runtime.insert_io_coroutine(
+                "flag coroutine",
+                QDesc::from(0),
+                Box::pin(
+                    async move {
+                        while !done.load(Ordering::Relaxed) {
+                            poll_yield().await;
+                        }
+                        (QDesc::from(0), OperationResult::Close)
+                    }
+                    .fuse(),
+                ),
+            )?
+        };
+        let producer: thread::JoinHandle<()> = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            submitter
+                .call(move |_: &mut SharedDemiRuntime| done.store(true, Ordering::Relaxed))
+                .expect("should be able to call");
+        });
+
+        let start: Instant = Instant::now();
+        runtime.wait(qt, Duration::from_secs(10))?;
+        ensure_eq!(start.elapsed() < Duration::from_secs(5), true);
+        ensure_eq!(*idle_sleeps.borrow() > 0, true);
+        if producer.join().is_err() {
+            anyhow::bail!("producer thread panicked");
+        }
+        Ok(())
+    }
+
     #[bench]
     fn benchmark_insert_io_coroutine(b: &mut Bencher) {
         let mut runtime: SharedDemiRuntime = SharedDemiRuntime::default();
 
-        b.iter(|| runtime.insert_io_coroutine("dummy coroutine", Box::pin(dummy_coroutine(10).fuse())));
+        b.iter(|| runtime.insert_io_coroutine("dummy coroutine", QDesc::from(0), Box::pin(dummy_coroutine(10).fuse())));
     }
 
     #[bench]
@@ -747,7 +1247,11 @@ mod tests {
         for i in 0..NUM_TASKS {
             // Make the arg big enough that the coroutine doesn't exit.
             qts[i] = runtime
-                .insert_io_coroutine("dummy coroutine", Box::pin(dummy_coroutine(1000000000).fuse()))
+                .insert_io_coroutine(
+                    "dummy coroutine",
+                    QDesc::from(0),
+                    Box::pin(dummy_coroutine(1000000000).fuse()),
+                )
                 .expect("should be able to insert tasks");
         }
 
@@ -764,7 +1268,11 @@ mod tests {
         for i in 0..NUM_TASKS {
             // Make the arg big enough that the coroutine doesn't exit.
             qts[i] = runtime
-                .insert_io_coroutine("dummy coroutine", Box::pin(dummy_coroutine(1000000000).fuse()))
+                .insert_io_coroutine(
+                    "dummy coroutine",
+                    QDesc::from(0),
+                    Box::pin(dummy_coroutine(1000000000).fuse()),
+                )
                 .expect("should be able to insert tasks");
         }
 
@@ -781,7 +1289,11 @@ mod tests {
         for i in 0..NUM_TASKS {
             // Make the arg big enough that the coroutine doesn't exit.
             qts[i] = runtime
-                .insert_io_coroutine("dummy coroutine", Box::pin(dummy_coroutine(1000000000).fuse()))
+                .insert_io_coroutine(
+                    "dummy coroutine",
+                    QDesc::from(0),
+                    Box::pin(dummy_coroutine(1000000000).fuse()),
+                )
                 .expect("should be able to insert tasks");
         }
 
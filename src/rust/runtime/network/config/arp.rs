@@ -45,11 +45,11 @@ pub struct ArpConfig {
 impl ArpConfig {
     /// Creates an ARP Configuration Descriptor.
     pub fn new(config: &Config) -> Result<Self, Fail> {
-        if let Some(initial_values) = config.arp_table()? {
+        if let Some(initial_values) = config.arp_table() {
             Ok(Self {
-                cache_ttl: config.arp_cache_ttl()?,
-                request_timeout: config.arp_request_timeout()?,
-                retry_count: config.arp_request_retries()?,
+                cache_ttl: config.arp_cache_ttl(),
+                request_timeout: config.arp_request_timeout(),
+                retry_count: config.arp_request_retries(),
                 initial_values,
                 disable_arp: false,
             })
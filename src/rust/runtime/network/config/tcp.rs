@@ -15,6 +15,9 @@ use crate::{
             MIN_MSS,
             TCP_ACK_DELAY_TIMEOUT,
             TCP_HANDSHAKE_TIMEOUT,
+            TCP_KEEPALIVE_INTERVAL,
+            TCP_KEEPALIVE_PROBES,
+            TCP_KEEPALIVE_TIME,
         },
     },
 };
@@ -43,6 +46,14 @@ pub struct TcpConfig {
     rx_checksum_offload: bool,
     /// Offload Checksum to Hardware When Sending?
     tx_checksum_offload: bool,
+    /// Idle Time Before Sending Keepalive Probes
+    keepalive_time: Duration,
+    /// Interval Between Keepalive Probes
+    keepalive_interval: Duration,
+    /// Number of Unanswered Keepalive Probes Before Dropping the Connection
+    keepalive_probes: usize,
+    /// Answer Connection Requests with SYN Cookies When the Backlog is Full?
+    syn_cookies: bool,
 }
 
 //======================================================================================================================
@@ -55,15 +66,40 @@ impl TcpConfig {
     pub fn new(config: &Config) -> Result<Self, Fail> {
         let mut options = Self::default();
 
-        if let Ok(value) = config.mss() {
-            assert!(value >= MIN_MSS);
-            assert!(value <= MAX_MSS);
+        if let Some(value) = config.mss() {
+            if !(MIN_MSS..=MAX_MSS).contains(&value) {
+                let cause: String = format!("invalid MSS (mss={:?}, min={:?}, max={:?})", value, MIN_MSS, MAX_MSS);
+                error!("new(): {}", cause);
+                return Err(Fail::new(libc::ERANGE, &cause));
+            }
             options.advertised_mss = value;
         }
-        if let Ok(value) = config.tcp_checksum_offload() {
+        if let Some(value) = config.window_scale() {
+            options.window_scale = value;
+        }
+        if let Some(value) = config.tcp_checksum_offload() {
             options.rx_checksum_offload = value;
             options.tx_checksum_offload = value;
         }
+        if let Some(value) = config.tcp_syn_cookies() {
+            options.syn_cookies = value;
+        }
+        // A zero value in the configuration file means that the default should be used.
+        if let Some(value) = config.tcp_keepalive_time() {
+            if !value.is_zero() {
+                options.keepalive_time = value;
+            }
+        }
+        if let Some(value) = config.tcp_keepalive_interval() {
+            if !value.is_zero() {
+                options.keepalive_interval = value;
+            }
+        }
+        if let Some(value) = config.tcp_keepalive_probes() {
+            if value != 0 {
+                options.keepalive_probes = value;
+            }
+        }
 
         Ok(options)
     }
@@ -107,6 +143,26 @@ impl TcpConfig {
     pub fn get_rx_checksum_offload(&self) -> bool {
         self.rx_checksum_offload
     }
+
+    /// Gets the idle time before sending keepalive probes in the target [TcpConfig].
+    pub fn get_keepalive_time(&self) -> Duration {
+        self.keepalive_time
+    }
+
+    /// Gets the interval between keepalive probes in the target [TcpConfig].
+    pub fn get_keepalive_interval(&self) -> Duration {
+        self.keepalive_interval
+    }
+
+    /// Gets the number of unanswered keepalive probes before dropping the connection in the target [TcpConfig].
+    pub fn get_keepalive_probes(&self) -> usize {
+        self.keepalive_probes
+    }
+
+    /// Gets the SYN cookies option in the target [TcpConfig].
+    pub fn get_syn_cookies(&self) -> bool {
+        self.syn_cookies
+    }
 }
 
 //======================================================================================================================
@@ -126,6 +182,10 @@ impl Default for TcpConfig {
             window_scale: 0,
             rx_checksum_offload: false,
             tx_checksum_offload: false,
+            keepalive_time: TCP_KEEPALIVE_TIME,
+            keepalive_interval: TCP_KEEPALIVE_INTERVAL,
+            keepalive_probes: TCP_KEEPALIVE_PROBES,
+            syn_cookies: false,
         }
     }
 }
@@ -154,6 +214,10 @@ mod tests {
         crate::ensure_eq!(config.get_window_scale(), 0);
         crate::ensure_eq!(config.get_rx_checksum_offload(), false);
         crate::ensure_eq!(config.get_tx_checksum_offload(), false);
+        crate::ensure_eq!(config.get_keepalive_time(), Duration::from_secs(7200));
+        crate::ensure_eq!(config.get_keepalive_interval(), Duration::from_secs(75));
+        crate::ensure_eq!(config.get_keepalive_probes(), 9);
+        crate::ensure_eq!(config.get_syn_cookies(), false);
 
         Ok(())
     }
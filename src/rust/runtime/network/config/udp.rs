@@ -31,7 +31,9 @@ pub struct UdpConfig {
 impl UdpConfig {
     /// Creates a UDP Configuration Descriptor.
     pub fn new(config: &Config) -> Result<Self, Fail> {
-        let offload = config.udp_checksum_offload()?;
+        let offload = config
+            .udp_checksum_offload()
+            .ok_or_else(|| Config::missing_option("udp_checksum_offload"))?;
         Ok(Self {
             rx_checksum: offload,
             tx_checksum: offload,
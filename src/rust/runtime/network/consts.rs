@@ -20,10 +20,21 @@ pub const MIN_MSS: usize = FALLBACK_MSS;
 /// Maximum MSS Parameter for TCP
 pub const MAX_MSS: usize = u16::max_value() as usize;
 
+/// Minimum MTU of links that carry IPv4 datagrams.
+/// See: https://www.rfc-editor.org/rfc/rfc791.txt
+pub const MIN_MTU: usize = 68;
+
+/// Maximum MTU
+pub const MAX_MTU: usize = u16::MAX as usize;
+
 /// Maximum Segment Lifetime
 /// See: https://www.rfc-editor.org/rfc/rfc793.txt
 pub const MSL: Duration = Duration::from_secs(2);
 
+/// Idle time after which the most recent timestamp received on a TCP connection is no longer valid for PAWS.
+/// See: https://www.rfc-editor.org/rfc/rfc7323#section-5.5
+pub const PAWS_IDLE_TIMEOUT: Duration = Duration::from_secs(24 * 24 * 60 * 60);
+
 /// Delay timeout for TCP ACKs.
 /// See: https://www.rfc-editor.org/rfc/rfc5681#section-4.2
 pub const TCP_ACK_DELAY_TIMEOUT: Duration = Duration::from_millis(500);
@@ -31,6 +42,16 @@ pub const TCP_ACK_DELAY_TIMEOUT: Duration = Duration::from_millis(500);
 /// Handshake timeout for tcp.
 pub const TCP_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(3);
 
+/// Idle time before a TCP connection starts sending keepalive probes.
+/// See: https://www.rfc-editor.org/rfc/rfc1122#section-4.2.3.6
+pub const TCP_KEEPALIVE_TIME: Duration = Duration::from_secs(7200);
+
+/// Interval between unanswered TCP keepalive probes.
+pub const TCP_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(75);
+
+/// Number of unanswered TCP keepalive probes before the connection is dropped.
+pub const TCP_KEEPALIVE_PROBES: usize = 9;
+
 /// Default MSS Parameter for TCP
 ///
 /// TODO: Auto-Discovery MTU Size
@@ -5,7 +5,10 @@
 // Imports
 //======================================================================================================================
 
-use crate::runtime::fail::Fail;
+use crate::{
+    expect_some,
+    runtime::fail::Fail,
+};
 use ::std::collections::VecDeque;
 #[cfg(not(debug_assertions))]
 use ::rand::prelude::{
@@ -52,6 +55,17 @@ impl EphemeralPorts {
         ))
     }
 
+    /// Allocates the first ephemeral port in the pool that satisfies [predicate].
+    pub fn alloc_if<P: FnMut(u16) -> bool>(&mut self, mut predicate: P) -> Result<u16, Fail> {
+        match self.ports.iter().position(|port: &u16| predicate(*port)) {
+            Some(index) => Ok(expect_some!(self.ports.remove(index), "index should be in the pool")),
+            None => Err(Fail::new(
+                libc::EADDRINUSE,
+                "all matching port numbers in the ephemeral port range are currently in use",
+            )),
+        }
+    }
+
     /// Allocates the specified port from the pool.
     pub fn reserve(&mut self, port: u16) -> Result<(), Fail> {
         // Check if port is not in the pool.
@@ -139,6 +153,41 @@ mod test {
         Ok(())
     }
 
+    /// Attempts to allocate all ephemeral ports that satisfy a predicate and then release them.
+    #[test]
+    fn test_alloc_if_all() -> Result<()> {
+        let mut ports: EphemeralPorts = EphemeralPorts::default();
+        let is_even = |port: u16| port % 2 == 0;
+
+        // Allocate all even ports.
+        for _ in (FIRST_PRIVATE_PORT..=LAST_PRIVATE_PORT).filter(|port: &u16| is_even(*port)) {
+            match ports.alloc_if(is_even) {
+                Ok(port) if is_even(port) => (),
+                Ok(port) => anyhow::bail!("allocated port should satisfy the predicate (port={:?})", port),
+                Err(e) => anyhow::bail!("failed to allocate an ephemeral port (error={:?})", &e),
+            }
+        }
+
+        // All even ports should be allocated, but odd ports should still be free.
+        if ports.alloc_if(is_even).is_ok() {
+            anyhow::bail!("all matching ports should be allocated");
+        }
+        let port: u16 = match ports.alloc() {
+            Ok(port) => port,
+            Err(e) => anyhow::bail!("failed to allocate an ephemeral port ({:?})", &e),
+        };
+        crate::ensure_eq!(is_even(port), false);
+
+        // Free all ports.
+        for port in (FIRST_PRIVATE_PORT..=LAST_PRIVATE_PORT).filter(|port: &u16| is_even(*port)) {
+            if let Err(e) = ports.free(port) {
+                anyhow::bail!("failed to free ephemeral port (port={:?}, error={:?})", port, &e);
+            }
+        }
+
+        Ok(())
+    }
+
     /// Attempts to allocate a specific ephemeral port and then release it.
     #[test]
     fn test_alloc_port_and_free() -> Result<()> {
@@ -9,6 +9,7 @@ pub mod config;
 pub mod consts;
 pub mod ephemeral;
 pub mod ring;
+pub mod rss;
 pub mod socket;
 pub mod transport;
 pub mod types;
@@ -62,7 +63,7 @@ impl NetworkQueueTable {
     }
 
     /// Checks if the given `local` address is in use.
-    pub fn addr_in_use(&self, local: SocketAddrV4) -> bool {
+    pub fn addr_in_use(&self, local: SocketAddr) -> bool {
         for (socket_id, _) in &self.mappings {
             match socket_id {
                 SocketId::Passive(addr) | SocketId::Active(addr, _) if *addr == local => return true,
@@ -88,10 +89,9 @@ impl Default for NetworkQueueTable {
 ///
 /// **Brief**
 ///
-/// Since IPv6 is not supported, this method simply unwraps a SocketAddr into a
-/// SocketAddrV4 or returns an error indicating the address family is not
-/// supported. This method should be removed when IPv6 support is added; see
-/// https://github.com/microsoft/demikernel/issues/935
+/// Unwraps a SocketAddr into a SocketAddrV4 or returns an error indicating the
+/// address family is not supported. This is used by transports that do not
+/// support IPv6 yet; see https://github.com/microsoft/demikernel/issues/935
 ///
 pub fn unwrap_socketaddr(addr: SocketAddr) -> Result<SocketAddrV4, Fail> {
     match addr {
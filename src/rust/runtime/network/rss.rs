@@ -0,0 +1,577 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Receive Side Scaling (RSS) flow steering.
+//!
+//! When the NIC spreads incoming packets across several receive queues, it computes the Toeplitz hash of the
+//! addresses and ports of each packet and looks the result up in its redirection table (RETA). [FlowSteering] mirrors
+//! this computation in software, so that a per-core stack can tell which queue receives the packets of a flow and
+//! pick ephemeral ports whose flows come back to its own queue.
+//!
+//! The NIC only spreads IP packets over the queues, and delivers all other frames, such as ARP, to the first queue.
+//! [FrameReplication] hands copies of these frames to the stacks on the other queues. It does the same for IPv6
+//! Neighbor Discovery messages, which every stack needs, and forwards the packets that the NIC can only steer by their
+//! addresses, such as ICMP errors, to the stack that owns their flow.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    submission::{
+        SubmissionQueue,
+        SUBMISSION_RING_SIZE,
+    },
+};
+use ::arrayvec::ArrayVec;
+use ::std::{
+    net::{
+        IpAddr,
+        Ipv4Addr,
+        Ipv6Addr,
+        SocketAddr,
+    },
+    sync::Arc,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Length of the RSS hash key, which covers the 36 bytes of an IPv6 flow plus the 4 bytes of the sliding window.
+pub const RSS_KEY_LEN: usize = 40;
+
+/// RSS hash key that we program in the NIC. This is the key of the Microsoft RSS verification suite.
+pub const RSS_KEY: [u8; RSS_KEY_LEN] = [
+    0x6d, 0x5a, 0x56, 0xda, 0x25, 0x5b, 0x0e, 0xc2, 0x41, 0x67, 0x25, 0x3d, 0x43, 0xa3, 0x8f, 0xb0, 0xd0, 0xca, 0x2b,
+    0xcb, 0xae, 0x7b, 0x30, 0xb4, 0x77, 0xcb, 0x2d, 0xa3, 0x80, 0x30, 0xf2, 0x0c, 0x6a, 0x42, 0xb7, 0x3b, 0xbe, 0xac,
+    0x01, 0xfa,
+];
+
+/// Maximum length of the hash input, which is the source and destination IPv6 addresses and ports.
+const MAX_INPUT_LEN: usize = 36;
+
+/// Offset of the EtherType in an Ethernet II frame.
+const ETHER_TYPE_OFFSET: usize = 12;
+
+/// Size of the header of an Ethernet II frame.
+const ETHER_HEADER_SIZE: usize = 14;
+
+/// EtherTypes of the frames that the NIC spreads over the queues.
+const IPV4_ETHER_TYPE: u16 = 0x0800;
+const IPV6_ETHER_TYPE: u16 = 0x86dd;
+
+/// Size of the fixed IPv6 header, which is followed by the ICMPv6 header in the messages that we look into.
+const IPV6_HEADER_SIZE: usize = 40;
+
+/// Size of the ICMPv4 and ICMPv6 headers, which error messages follow with the quote of the offending packet.
+const ICMP_HEADER_SIZE: usize = 8;
+
+/// IP protocol numbers.
+const ICMPV4_PROTOCOL: u8 = 1;
+const TCP_PROTOCOL: u8 = 6;
+const UDP_PROTOCOL: u8 = 17;
+const ICMPV6_PROTOCOL: u8 = 58;
+
+/// ICMPv4 error messages, which are Destination Unreachable, Time Exceeded and Parameter Problem (see RFC 792).
+const ICMPV4_ERROR_TYPES: [u8; 3] = [3, 11, 12];
+
+/// ICMPv6 error messages, which are Destination Unreachable, Packet Too Big, Time Exceeded and Parameter Problem (see
+/// RFC 4443 Section 2.1).
+const ICMPV6_ERROR_TYPES: [u8; 4] = [1, 2, 3, 4];
+
+/// ICMPv6 Neighbor Discovery messages, from Router Solicitation to Redirect (see RFC 4861 Section 4).
+const NDP_TYPES: [u8; 5] = [133, 134, 135, 136, 137];
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Flow-to-queue mapping of a NIC with [num_queues] receive queues, as seen from the stack that owns [queue_id]. The
+/// redirection table assigns its entries to the queues round-robin.
+#[derive(Clone, Debug)]
+pub struct FlowSteering {
+    key: [u8; RSS_KEY_LEN],
+    reta_size: u16,
+    num_queues: u16,
+    queue_id: u16,
+}
+
+/// Replication of the frames that the NIC only delivers to one queue of a port to the other queues, as seen from the
+/// stack that owns [queue_id].
+#[derive(Clone)]
+pub struct FrameReplication {
+    /// Frames that the other queues handed to each queue.
+    queues: Arc<Vec<SubmissionQueue<Vec<u8>>>>,
+    queue_id: u16,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl FlowSteering {
+    /// Creates the flow steering of [queue_id] for a NIC that hashes with [key] into a redirection table of
+    /// [reta_size] entries, spread over [num_queues] queues.
+    pub fn new(key: [u8; RSS_KEY_LEN], reta_size: u16, num_queues: u16, queue_id: u16) -> Result<Self, Fail> {
+        if num_queues == 0 || num_queues > reta_size {
+            let cause: String = format!(
+                "cannot spread {} queues over a redirection table of {} entries",
+                num_queues, reta_size
+            );
+            error!("new(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        if queue_id >= num_queues {
+            let cause: String = format!("invalid queue (queue_id={}, num_queues={})", queue_id, num_queues);
+            error!("new(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        Ok(Self {
+            key,
+            reta_size,
+            num_queues,
+            queue_id,
+        })
+    }
+
+    /// Returns the queue of the redirection table entry at [index].
+    pub fn reta_entry(&self, index: u16) -> u16 {
+        index % self.num_queues
+    }
+
+    /// Returns the queue that receives the packets that [remote] sends to [local].
+    pub fn queue_for_flow(&self, local: SocketAddr, remote: SocketAddr) -> u16 {
+        // The hash input is in the order of the fields in the headers of incoming packets: the source address, the
+        // destination address, the source port and the destination port.
+        let mut input: ArrayVec<u8, MAX_INPUT_LEN> = ArrayVec::new();
+        for addr in [remote.ip(), local.ip()] {
+            match addr {
+                IpAddr::V4(addr) => input.extend(addr.octets()),
+                IpAddr::V6(addr) => input.extend(addr.octets()),
+            }
+        }
+        input.extend(remote.port().to_be_bytes());
+        input.extend(local.port().to_be_bytes());
+
+        let hash: u32 = toeplitz_hash(&self.key, &input);
+        self.reta_entry((hash % self.reta_size as u32) as u16)
+    }
+
+    /// Checks whether the packets that [remote] sends to [local] arrive on the queue of this stack.
+    pub fn is_local_flow(&self, local: SocketAddr, remote: SocketAddr) -> bool {
+        self.queue_for_flow(local, remote) == self.queue_id
+    }
+
+    /// Returns the queue of the flow that the ICMP error message in [frame] reports on, if [frame] carries one. The
+    /// NIC steers these messages by their addresses only, but they belong to the flow of the packet that they quote.
+    pub fn queue_for_icmp_error(&self, frame: &[u8]) -> Option<u16> {
+        let (local, remote): (SocketAddr, SocketAddr) = quoted_flow(frame)?;
+        Some(self.queue_for_flow(local, remote))
+    }
+}
+
+impl FrameReplication {
+    /// Creates the replication between [num_queues] queues, and returns the replication of each queue.
+    pub fn new(num_queues: u16) -> Result<Vec<Self>, Fail> {
+        let queues: Arc<Vec<SubmissionQueue<Vec<u8>>>> = Arc::new(
+            (0..num_queues)
+                .map(|_| SubmissionQueue::new(SUBMISSION_RING_SIZE))
+                .collect::<Result<_, _>>()?,
+        );
+        Ok((0..num_queues)
+            .map(|queue_id: u16| Self {
+                queues: queues.clone(),
+                queue_id,
+            })
+            .collect())
+    }
+
+    /// Hands a copy of [frame] to each of the other queues if the NIC does not deliver frames like it to all of them.
+    /// These are the frames that the NIC does not spread over the queues, which only reach the first one, and the
+    /// Neighbor Discovery messages, which reach the queue of their addresses. A queue that is full misses the frame, as
+    /// if the NIC had dropped it.
+    pub fn replicate(&self, frame: &[u8]) {
+        if !is_ndp_frame(frame) && (self.queue_id != 0 || is_steered_frame(frame)) {
+            return;
+        }
+        for (queue_id, queue) in self.queues.iter().enumerate() {
+            if queue_id == self.queue_id as usize {
+                continue;
+            }
+            if let Err(e) = queue.try_submit(frame.to_vec()) {
+                warn!("replicate(): dropping frame ({:?})", e);
+            }
+        }
+    }
+
+    /// Hands [frame] to [queue_id], which owns the flow that it belongs to although the NIC delivered it to this queue.
+    /// A queue that is full misses the frame, as if the NIC had dropped it.
+    pub fn forward(&self, queue_id: u16, frame: &[u8]) {
+        let queue: &SubmissionQueue<Vec<u8>> = match self.queues.get(queue_id as usize) {
+            Some(queue) => queue,
+            None => {
+                warn!("forward(): invalid queue (queue_id={})", queue_id);
+                return;
+            },
+        };
+        if let Err(e) = queue.try_submit(frame.to_vec()) {
+            warn!("forward(): dropping frame ({:?})", e);
+        }
+    }
+
+    /// Takes the next frame that another queue handed to this queue, if any.
+    pub fn try_take(&self) -> Option<Vec<u8>> {
+        self.queues[self.queue_id as usize].try_take()
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Returns the EtherType of [frame], if it is long enough to have one.
+fn ether_type(frame: &[u8]) -> Option<u16> {
+    let ether_type: &[u8] = frame.get(ETHER_TYPE_OFFSET..ETHER_TYPE_OFFSET + 2)?;
+    Some(u16::from_be_bytes([ether_type[0], ether_type[1]]))
+}
+
+/// Checks whether the NIC spreads frames like [frame] over the queues, which it only does for IP packets.
+fn is_steered_frame(frame: &[u8]) -> bool {
+    matches!(ether_type(frame), Some(IPV4_ETHER_TYPE | IPV6_ETHER_TYPE))
+}
+
+/// Checks whether [frame] carries an IPv6 Neighbor Discovery message. We do not look past extension headers, which
+/// these messages do not carry.
+fn is_ndp_frame(frame: &[u8]) -> bool {
+    ether_type(frame) == Some(IPV6_ETHER_TYPE)
+        && frame.get(ETHER_HEADER_SIZE + 6) == Some(&ICMPV6_PROTOCOL)
+        && matches!(frame.get(ETHER_HEADER_SIZE + IPV6_HEADER_SIZE), Some(icmp_type) if NDP_TYPES.contains(icmp_type))
+}
+
+/// Parses the flow of the TCP segment or UDP datagram that the ICMPv4 or ICMPv6 error message in [frame] quotes. We
+/// sent the quoted packet, so its source is the local end of the flow and its destination the remote one.
+fn quoted_flow(frame: &[u8]) -> Option<(SocketAddr, SocketAddr)> {
+    let packet: &[u8] = frame.get(ETHER_HEADER_SIZE..)?;
+    let (protocol, src_addr, dst_addr, ports): (u8, IpAddr, IpAddr, &[u8]) = match ether_type(frame)? {
+        IPV4_ETHER_TYPE => {
+            // Fragments other than the first one do not start with the ICMPv4 header.
+            let ihl: usize = ((packet.first()? & 0xf) as usize) << 2;
+            let fragment_offset: u16 = u16::from_be_bytes([*packet.get(6)?, *packet.get(7)?]) & 0x1fff;
+            if *packet.get(9)? != ICMPV4_PROTOCOL
+                || fragment_offset != 0
+                || !ICMPV4_ERROR_TYPES.contains(packet.get(ihl)?)
+            {
+                return None;
+            }
+            let quote: &[u8] = packet.get(ihl + ICMP_HEADER_SIZE..)?;
+            let quote_ihl: usize = ((quote.first()? & 0xf) as usize) << 2;
+            (
+                *quote.get(9)?,
+                IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(quote.get(12..16)?).ok()?)),
+                IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(quote.get(16..20)?).ok()?)),
+                quote.get(quote_ihl..quote_ihl + 4)?,
+            )
+        },
+        IPV6_ETHER_TYPE => {
+            if *packet.get(6)? != ICMPV6_PROTOCOL || !ICMPV6_ERROR_TYPES.contains(packet.get(IPV6_HEADER_SIZE)?) {
+                return None;
+            }
+            let quote: &[u8] = packet.get(IPV6_HEADER_SIZE + ICMP_HEADER_SIZE..)?;
+            (
+                *quote.get(6)?,
+                IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(quote.get(8..24)?).ok()?)),
+                IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(quote.get(24..40)?).ok()?)),
+                quote.get(IPV6_HEADER_SIZE..IPV6_HEADER_SIZE + 4)?,
+            )
+        },
+        _ => return None,
+    };
+    if protocol != TCP_PROTOCOL && protocol != UDP_PROTOCOL {
+        return None;
+    }
+    let src_port: u16 = u16::from_be_bytes([ports[0], ports[1]]);
+    let dst_port: u16 = u16::from_be_bytes([ports[2], ports[3]]);
+    Some((SocketAddr::new(src_addr, src_port), SocketAddr::new(dst_addr, dst_port)))
+}
+
+/// Computes the Toeplitz hash of [input] with [key]. Each bit that is set in the input contributes the 32 bits of the
+/// key that start at the same position.
+pub fn toeplitz_hash(key: &[u8; RSS_KEY_LEN], input: &[u8]) -> u32 {
+    debug_assert!(input.len() <= MAX_INPUT_LEN);
+    let mut hash: u32 = 0;
+    let mut window: u32 = u32::from_be_bytes([key[0], key[1], key[2], key[3]]);
+    for (i, byte) in input.iter().enumerate() {
+        let next: u8 = key.get(i + 4).copied().unwrap_or(0);
+        for bit in (0..8).rev() {
+            if byte & (1 << bit) != 0 {
+                hash ^= window;
+            }
+            window = (window << 1) | ((next >> bit) & 1) as u32;
+        }
+    }
+    hash
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ensure_eq,
+        ensure_neq,
+        runtime::network::rss::{
+            toeplitz_hash,
+            FlowSteering,
+            FrameReplication,
+            RSS_KEY,
+        },
+    };
+    use ::anyhow::Result;
+    use ::std::net::{
+        IpAddr,
+        Ipv4Addr,
+        Ipv6Addr,
+        SocketAddr,
+    };
+
+    /// Builds an Ethernet frame of [ether_type] that carries [packet].
+    fn frame(ether_type: u16, packet: &[u8]) -> Vec<u8> {
+        let mut frame: Vec<u8> = vec![0; 12];
+        frame.extend(ether_type.to_be_bytes());
+        frame.extend(packet);
+        frame
+    }
+
+    /// Builds the header of an IP packet of [protocol] from [src] to [dst], without checksums or lengths.
+    fn ip_header(protocol: u8, src: IpAddr, dst: IpAddr) -> Vec<u8> {
+        match (src, dst) {
+            (IpAddr::V4(src), IpAddr::V4(dst)) => {
+                let mut header: Vec<u8> = vec![0x45, 0, 0, 0, 0, 0, 0, 0, 64, protocol, 0, 0];
+                header.extend(src.octets());
+                header.extend(dst.octets());
+                header
+            },
+            (IpAddr::V6(src), IpAddr::V6(dst)) => {
+                let mut header: Vec<u8> = vec![0x60, 0, 0, 0, 0, 0, protocol, 64];
+                header.extend(src.octets());
+                header.extend(dst.octets());
+                header
+            },
+            _ => unreachable!("addresses should be of the same family"),
+        }
+    }
+
+    /// Builds the frame of an ICMP error message of [icmp_type] that [remote] sends back about the UDP datagram that
+    /// [local] sent to it.
+    fn icmp_error_frame(icmp_type: u8, local: SocketAddr, remote: SocketAddr) -> Vec<u8> {
+        let (ether_type, protocol): (u16, u8) = match local {
+            SocketAddr::V4(_) => (0x0800, 1),
+            SocketAddr::V6(_) => (0x86dd, 58),
+        };
+        let mut packet: Vec<u8> = ip_header(protocol, remote.ip(), local.ip());
+        packet.extend([icmp_type, 0, 0, 0, 0, 0, 0, 0]);
+        packet.extend(ip_header(17, local.ip(), remote.ip()));
+        packet.extend(local.port().to_be_bytes());
+        packet.extend(remote.port().to_be_bytes());
+        packet.extend([0, 8, 0, 0]);
+        frame(ether_type, &packet)
+    }
+
+    /// Builds the hash input of a flow from [src] to [dst], optionally including the ports.
+    fn hash_input(src: SocketAddr, dst: SocketAddr, with_ports: bool) -> Vec<u8> {
+        let mut input: Vec<u8> = Vec::new();
+        for addr in [src.ip(), dst.ip()] {
+            match addr {
+                IpAddr::V4(addr) => input.extend(addr.octets()),
+                IpAddr::V6(addr) => input.extend(addr.octets()),
+            }
+        }
+        if with_ports {
+            input.extend(src.port().to_be_bytes());
+            input.extend(dst.port().to_be_bytes());
+        }
+        input
+    }
+
+    /// Checks the hash against the IPv4 vectors of the Microsoft RSS verification suite.
+    #[test]
+    fn test_toeplitz_hash_ipv4() -> Result<()> {
+        // (destination, source, hash of the addresses, hash of the addresses and ports)
+        let vectors: [(&str, &str, u32, u32); 5] = [
+            ("161.142.100.80:1766", "66.9.149.187:2794", 0x323e8fc2, 0x51ccc178),
+            ("65.69.140.83:4739", "199.92.111.2:14230", 0xd718262a, 0xc626b0ea),
+            ("12.22.207.184:38024", "24.19.198.95:12898", 0xd2d0a5de, 0x5c2b394a),
+            ("209.142.163.6:2217", "38.27.205.30:48228", 0x82989176, 0xafc7327f),
+            ("202.188.127.2:1303", "153.39.163.191:44251", 0x5d1809c5, 0x10e828a2),
+        ];
+        for (dst, src, ip_hash, l4_hash) in vectors {
+            let dst: SocketAddr = dst.parse()?;
+            let src: SocketAddr = src.parse()?;
+            ensure_eq!(toeplitz_hash(&RSS_KEY, &hash_input(src, dst, false)), ip_hash);
+            ensure_eq!(toeplitz_hash(&RSS_KEY, &hash_input(src, dst, true)), l4_hash);
+        }
+        Ok(())
+    }
+
+    /// Checks the hash against the IPv6 vectors of the Microsoft RSS verification suite.
+    #[test]
+    fn test_toeplitz_hash_ipv6() -> Result<()> {
+        // (destination, source, hash of the addresses, hash of the addresses and ports)
+        let vectors: [(&str, &str, u32, u32); 3] = [
+            (
+                "[3ffe:2501:200:3::1]:1766",
+                "[3ffe:2501:200:1fff::7]:2794",
+                0x2cc18cd5,
+                0x40207d3d,
+            ),
+            (
+                "[ff02::1]:4739",
+                "[3ffe:501:8::260:97ff:fe40:efab]:14230",
+                0x0f0c461c,
+                0xdde51bbf,
+            ),
+            (
+                "[fe80::200:f8ff:fe21:67cf]:38024",
+                "[3ffe:1900:4545:3:200:f8ff:fe21:67cf]:44251",
+                0x4b61e985,
+                0x02d1feef,
+            ),
+        ];
+        for (dst, src, ip_hash, l4_hash) in vectors {
+            let dst: SocketAddr = dst.parse()?;
+            let src: SocketAddr = src.parse()?;
+            ensure_eq!(toeplitz_hash(&RSS_KEY, &hash_input(src, dst, false)), ip_hash);
+            ensure_eq!(toeplitz_hash(&RSS_KEY, &hash_input(src, dst, true)), l4_hash);
+        }
+        Ok(())
+    }
+
+    /// Checks that every flow maps to exactly one of the queues, and that all queues receive flows.
+    #[test]
+    fn test_queue_for_flow() -> Result<()> {
+        let num_queues: u16 = 4;
+        let steerings: Vec<FlowSteering> = (0..num_queues)
+            .map(|queue_id: u16| FlowSteering::new(RSS_KEY, 128, num_queues, queue_id))
+            .collect::<Result<_, _>>()?;
+        let remote: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 2)), 80);
+        let mut flows_per_queue: Vec<usize> = vec![0; num_queues as usize];
+        for port in 49152..=65535 {
+            let local: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)), port);
+            let queue_id: u16 = steerings[0].queue_for_flow(local, remote);
+            let owners: usize = steerings
+                .iter()
+                .filter(|steering: &&FlowSteering| steering.is_local_flow(local, remote))
+                .count();
+            ensure_eq!(owners, 1);
+            flows_per_queue[queue_id as usize] += 1;
+        }
+        for flows in flows_per_queue {
+            ensure_neq!(flows, 0);
+        }
+
+        // IPv6 flows are steered as well.
+        let remote: SocketAddr = SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 80);
+        let local: SocketAddr = SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 49152);
+        ensure_eq!(steerings[0].queue_for_flow(local, remote) < num_queues, true);
+        Ok(())
+    }
+
+    /// Rejects queues that the redirection table cannot address.
+    #[test]
+    fn test_flow_steering_bad_queues() -> Result<()> {
+        ensure_eq!(FlowSteering::new(RSS_KEY, 128, 0, 0).is_err(), true);
+        ensure_eq!(FlowSteering::new(RSS_KEY, 128, 4, 4).is_err(), true);
+        ensure_eq!(FlowSteering::new(RSS_KEY, 2, 4, 0).is_err(), true);
+        Ok(())
+    }
+
+    /// Replicates the frames that only reach the first of several queues, such as ARP, to the other queues.
+    #[test]
+    fn test_frame_replication() -> Result<()> {
+        let replications: Vec<FrameReplication> = FrameReplication::new(3)?;
+        let mut arp_frame: Vec<u8> = vec![0; 42];
+        arp_frame[12..14].copy_from_slice(&0x0806u16.to_be_bytes());
+        let mut ipv4_frame: Vec<u8> = vec![0; 34];
+        ipv4_frame[12..14].copy_from_slice(&0x0800u16.to_be_bytes());
+
+        // The NIC spreads IP packets over the queues itself, so only the ARP frame is replicated.
+        replications[0].replicate(&ipv4_frame);
+        replications[0].replicate(&arp_frame);
+        for replication in &replications[1..] {
+            ensure_eq!(replication.try_take(), Some(arp_frame.clone()));
+            ensure_eq!(replication.try_take(), None);
+        }
+        ensure_eq!(replications[0].try_take(), None);
+
+        // Only the first queue receives the frames that the NIC does not spread.
+        replications[1].replicate(&arp_frame);
+        for replication in &replications {
+            ensure_eq!(replication.try_take(), None);
+        }
+        Ok(())
+    }
+
+    /// Replicates the Neighbor Discovery messages that any of the queues receives to the other queues.
+    #[test]
+    fn test_ndp_frame_replication() -> Result<()> {
+        let replications: Vec<FrameReplication> = FrameReplication::new(3)?;
+        let src: IpAddr = IpAddr::V6("fe80::2".parse()?);
+        let dst: IpAddr = IpAddr::V6("fe80::1".parse()?);
+        let mut ndp_packet: Vec<u8> = ip_header(58, src, dst);
+        ndp_packet.extend([136, 0, 0, 0, 0, 0, 0, 0]);
+        let ndp_frame: Vec<u8> = frame(0x86dd, &ndp_packet);
+        let mut echo_packet: Vec<u8> = ip_header(58, src, dst);
+        echo_packet.extend([128, 0, 0, 0, 0, 0, 0, 0]);
+        let echo_frame: Vec<u8> = frame(0x86dd, &echo_packet);
+
+        // Other ICMPv6 messages are only for the queue that the NIC steers them to.
+        replications[2].replicate(&echo_frame);
+        replications[2].replicate(&ndp_frame);
+        for replication in &replications[..2] {
+            ensure_eq!(replication.try_take(), Some(ndp_frame.clone()));
+            ensure_eq!(replication.try_take(), None);
+        }
+        ensure_eq!(replications[2].try_take(), None);
+        Ok(())
+    }
+
+    /// Steers ICMP errors to the queue of the flow that they report on, and forwards them there.
+    #[test]
+    fn test_queue_for_icmp_error() -> Result<()> {
+        let num_queues: u16 = 4;
+        let steering: FlowSteering = FlowSteering::new(RSS_KEY, 128, num_queues, 0)?;
+        let replications: Vec<FrameReplication> = FrameReplication::new(num_queues)?;
+        for (local_ip, remote) in [
+            (
+                IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)),
+                "192.168.1.2:53".parse::<SocketAddr>()?,
+            ),
+            (IpAddr::V6("fe80::1".parse()?), "[fe80::2]:53".parse::<SocketAddr>()?),
+        ] {
+            let error_type: u8 = if local_ip.is_ipv4() { 3 } else { 1 };
+            for port in 49152..49184 {
+                let local: SocketAddr = SocketAddr::new(local_ip, port);
+                let error_frame: Vec<u8> = icmp_error_frame(error_type, local, remote);
+                let queue_id: u16 = steering.queue_for_flow(local, remote);
+                ensure_eq!(steering.queue_for_icmp_error(&error_frame), Some(queue_id));
+
+                replications[0].forward(queue_id, &error_frame);
+                ensure_eq!(replications[queue_id as usize].try_take(), Some(error_frame));
+            }
+        }
+
+        // Other ICMP messages do not belong to a flow.
+        let local: SocketAddr = "192.168.1.1:49152".parse()?;
+        let remote: SocketAddr = "192.168.1.2:53".parse()?;
+        ensure_eq!(steering.queue_for_icmp_error(&icmp_error_frame(0, local, remote)), None);
+        ensure_eq!(
+            steering.queue_for_icmp_error(&icmp_error_frame(3, local, remote)[..40]),
+            None
+        );
+        Ok(())
+    }
+}
@@ -13,7 +13,7 @@ pub mod state;
 // Imports
 //======================================================================================================================
 
-use ::std::net::SocketAddrV4;
+use ::std::net::SocketAddr;
 
 //======================================================================================================================
 // Structures
@@ -21,6 +21,6 @@ use ::std::net::SocketAddrV4;
 
 #[derive(PartialEq, Eq, Hash, Debug)]
 pub enum SocketId {
-    Active(SocketAddrV4, SocketAddrV4),
-    Passive(SocketAddrV4),
+    Active(SocketAddr, SocketAddr),
+    Passive(SocketAddr),
 }
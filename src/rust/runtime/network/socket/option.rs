@@ -10,9 +10,17 @@
 use crate::{
     demikernel::config::Config,
     pal::data_structures::KeepAlive,
-    runtime::fail::Fail,
+    runtime::{
+        fail::Fail,
+        network::config::TcpConfig,
+    },
+};
+use ::std::{
+    fmt,
+    net::Ipv4Addr,
+    str::FromStr,
+    time::Duration,
 };
-use ::std::time::Duration;
 #[cfg(target_os = "windows")]
 use ::windows::Win32::Networking::WinSock::tcp_keepalive;
 
@@ -30,6 +38,14 @@ const DEFAULT_KEEP_ALIVE: tcp_keepalive = tcp_keepalive {
     keepaliveinterval: 1000,
 };
 const DEFAULT_NO_DELAY: bool = true;
+const DEFAULT_CONGESTION_CONTROL: CongestionControlAlgorithm = CongestionControlAlgorithm::None;
+const DEFAULT_MAX_PACING_RATE: Option<u64> = None;
+/// Default size of the receive buffer of UDP sockets, in bytes of payload. This is what Linux uses.
+const DEFAULT_UDP_RECV_BUF_SIZE: usize = 212992;
+/// Default size of the send buffer of UDP sockets, in bytes of payload. This is what Linux uses.
+const DEFAULT_UDP_SEND_BUF_SIZE: usize = 212992;
+const DEFAULT_BROADCAST: bool = false;
+const DEFAULT_REUSE_ADDR: bool = false;
 
 //======================================================================================================================
 // Structures
@@ -42,6 +58,31 @@ pub enum SocketOption {
     Linger(Option<Duration>),
     KeepAlive(KeepAlive),
     NoDelay(bool),
+    CongestionControl(CongestionControlAlgorithm),
+    /// Maximum rate (in bytes per second) at which to pace segments out, or None if it is up to congestion control.
+    MaxPacingRate(Option<u64>),
+    /// Size of the receive buffer, in bytes.
+    RecvBufSize(usize),
+    /// Size of the send buffer, in bytes.
+    SendBufSize(usize),
+    /// Whether or not datagrams may be sent to broadcast addresses.
+    Broadcast(bool),
+    /// Whether or not other sockets may bind to the same local address.
+    ReuseAddr(bool),
+    /// Joins an IPv4 multicast group (first address) on the interface with a local address (second address), as with
+    /// IP_ADD_MEMBERSHIP. The unspecified interface address leaves the choice of the interface to the transport.
+    AddMembership(Ipv4Addr, Ipv4Addr),
+    /// Leaves an IPv4 multicast group that was joined with [SocketOption::AddMembership], as with IP_DROP_MEMBERSHIP.
+    DropMembership(Ipv4Addr, Ipv4Addr),
+}
+
+/// Congestion control algorithms that a TCP connection may use. These are selected by name, as with TCP_CONGESTION.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CongestionControlAlgorithm {
+    None,
+    Cubic,
+    NewReno,
+    Bbr,
 }
 
 /// A structure to store the values of the SO_* socket options.
@@ -50,6 +91,8 @@ pub struct TcpSocketOptions {
     linger: Option<Duration>,
     keep_alive: KeepAlive,
     no_delay: bool,
+    congestion_control: CongestionControlAlgorithm,
+    max_pacing_rate: Option<u64>,
 }
 
 impl TcpSocketOptions {
@@ -58,6 +101,8 @@ impl TcpSocketOptions {
             linger: config.linger().unwrap_or(DEFAULT_LINGER),
             keep_alive: config.tcp_keepalive().unwrap_or(DEFAULT_KEEP_ALIVE),
             no_delay: config.no_delay().unwrap_or(DEFAULT_NO_DELAY),
+            congestion_control: config.congestion_control().unwrap_or(DEFAULT_CONGESTION_CONTROL),
+            max_pacing_rate: DEFAULT_MAX_PACING_RATE,
         })
     }
 
@@ -77,6 +122,34 @@ impl TcpSocketOptions {
         self.keep_alive = keep_alive;
     }
 
+    /// Returns the idle time and the probe interval for keepalive, or None if keepalive is disabled. Values that are
+    /// not set on the socket are taken from [tcp_config].
+    #[cfg(target_os = "linux")]
+    pub fn get_keepalive_timers(&self, tcp_config: &TcpConfig) -> Option<(Duration, Duration)> {
+        match self.keep_alive {
+            true => Some((tcp_config.get_keepalive_time(), tcp_config.get_keepalive_interval())),
+            false => None,
+        }
+    }
+
+    /// Returns the idle time and the probe interval for keepalive, or None if keepalive is disabled. Values that are
+    /// not set on the socket are taken from [tcp_config].
+    #[cfg(target_os = "windows")]
+    pub fn get_keepalive_timers(&self, tcp_config: &TcpConfig) -> Option<(Duration, Duration)> {
+        if self.keep_alive.onoff == 0 {
+            return None;
+        }
+        let time: Duration = match self.keep_alive.keepalivetime {
+            0 => tcp_config.get_keepalive_time(),
+            millis => Duration::from_millis(millis as u64),
+        };
+        let interval: Duration = match self.keep_alive.keepaliveinterval {
+            0 => tcp_config.get_keepalive_interval(),
+            millis => Duration::from_millis(millis as u64),
+        };
+        Some((time, interval))
+    }
+
     pub fn get_nodelay(&self) -> bool {
         self.no_delay
     }
@@ -84,6 +157,76 @@ impl TcpSocketOptions {
     pub fn set_nodelay(&mut self, nodelay: bool) {
         self.no_delay = nodelay;
     }
+
+    pub fn get_congestion_control(&self) -> CongestionControlAlgorithm {
+        self.congestion_control
+    }
+
+    pub fn set_congestion_control(&mut self, congestion_control: CongestionControlAlgorithm) {
+        self.congestion_control = congestion_control;
+    }
+
+    pub fn get_max_pacing_rate(&self) -> Option<u64> {
+        self.max_pacing_rate
+    }
+
+    pub fn set_max_pacing_rate(&mut self, max_pacing_rate: Option<u64>) {
+        self.max_pacing_rate = max_pacing_rate;
+    }
+}
+
+/// A structure to store the values of the SO_* socket options of UDP sockets.
+#[derive(Debug, Clone, Copy)]
+pub struct UdpSocketOptions {
+    recv_buf_size: usize,
+    send_buf_size: usize,
+    broadcast: bool,
+    reuse_addr: bool,
+}
+
+impl UdpSocketOptions {
+    pub fn get_recv_buf_size(&self) -> usize {
+        self.recv_buf_size
+    }
+
+    pub fn set_recv_buf_size(&mut self, recv_buf_size: usize) {
+        self.recv_buf_size = recv_buf_size;
+    }
+
+    pub fn get_send_buf_size(&self) -> usize {
+        self.send_buf_size
+    }
+
+    pub fn set_send_buf_size(&mut self, send_buf_size: usize) {
+        self.send_buf_size = send_buf_size;
+    }
+
+    pub fn get_broadcast(&self) -> bool {
+        self.broadcast
+    }
+
+    pub fn set_broadcast(&mut self, broadcast: bool) {
+        self.broadcast = broadcast;
+    }
+
+    pub fn get_reuse_addr(&self) -> bool {
+        self.reuse_addr
+    }
+
+    pub fn set_reuse_addr(&mut self, reuse_addr: bool) {
+        self.reuse_addr = reuse_addr;
+    }
+}
+
+impl CongestionControlAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CongestionControlAlgorithm::None => "none",
+            CongestionControlAlgorithm::Cubic => "cubic",
+            CongestionControlAlgorithm::NewReno => "newreno",
+            CongestionControlAlgorithm::Bbr => "bbr",
+        }
+    }
 }
 
 impl Default for TcpSocketOptions {
@@ -92,6 +235,44 @@ impl Default for TcpSocketOptions {
             linger: DEFAULT_LINGER,
             keep_alive: DEFAULT_KEEP_ALIVE,
             no_delay: DEFAULT_NO_DELAY,
+            congestion_control: DEFAULT_CONGESTION_CONTROL,
+            max_pacing_rate: DEFAULT_MAX_PACING_RATE,
         }
     }
 }
+
+impl Default for UdpSocketOptions {
+    fn default() -> Self {
+        Self {
+            recv_buf_size: DEFAULT_UDP_RECV_BUF_SIZE,
+            send_buf_size: DEFAULT_UDP_SEND_BUF_SIZE,
+            broadcast: DEFAULT_BROADCAST,
+            reuse_addr: DEFAULT_REUSE_ADDR,
+        }
+    }
+}
+
+impl FromStr for CongestionControlAlgorithm {
+    type Err = Fail;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "none" => Ok(CongestionControlAlgorithm::None),
+            "cubic" => Ok(CongestionControlAlgorithm::Cubic),
+            // Linux calls NewReno just "reno".
+            "newreno" | "reno" => Ok(CongestionControlAlgorithm::NewReno),
+            "bbr" => Ok(CongestionControlAlgorithm::Bbr),
+            _ => {
+                let cause: String = format!("unknown congestion control algorithm (name={:?})", name);
+                error!("from_str(): {}", cause);
+                Err(Fail::new(libc::ENOENT, &cause))
+            },
+        }
+    }
+}
+
+impl fmt::Display for CongestionControlAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
@@ -156,7 +156,8 @@ impl SocketStateMachine {
 
         if self.typ == Type::STREAM {
             self.ensure_established()?;
-        } else {
+        } else if self.current.get() != SocketState::ActiveEstablished {
+            // Connected datagram sockets are bound as well.
             self.ensure_bound()?;
         }
 
@@ -179,6 +180,33 @@ impl SocketStateMachine {
         }
     }
 
+    /// Asserts whether the target [SocketState] may shut down receiving or sending.
+    pub fn may_shutdown(&self) -> Result<(), Fail> {
+        self.ensure_not_closing()?;
+        self.ensure_not_closed()?;
+        self.ensure_established()?;
+        Ok(())
+    }
+
+    /// Returns whether the socket is connecting to a remote.
+    pub fn is_connecting(&self) -> bool {
+        self.current.get() == SocketState::ActiveConnecting
+    }
+
+    /// Returns a socket whose connect was cancelled to the state that it had before connecting, which is bound if
+    /// [bound] is set and unbound otherwise. Does nothing if the socket is no longer connecting.
+    pub fn cancel_connect(&mut self, bound: bool) {
+        if self.is_connecting() {
+            let state: SocketState = if bound {
+                SocketState::Bound
+            } else {
+                SocketState::Unbound
+            };
+            self.current.set(state);
+            self.next = None;
+        }
+    }
+
     /// Commits to moving into the prepared state
     pub fn commit(&mut self) {
         let current: SocketState = self.current.get();
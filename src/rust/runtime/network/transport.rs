@@ -21,8 +21,8 @@ use ::socket2::{
 use ::std::{
     fmt::Debug,
     net::{
+        Shutdown,
         SocketAddr,
-        SocketAddrV4,
     },
 };
 
@@ -49,7 +49,10 @@ pub trait NetworkTransport: Clone + 'static + MemoryRuntime {
         option: SocketOption,
     ) -> Result<SocketOption, Fail>;
 
-    fn getpeername(&mut self, sd: &mut Self::SocketDescriptor) -> Result<SocketAddrV4, Fail>;
+    fn getpeername(&mut self, sd: &mut Self::SocketDescriptor) -> Result<SocketAddr, Fail>;
+
+    /// Gets the local address to which the socket is bound.
+    fn getsockname(&mut self, sd: &mut Self::SocketDescriptor) -> Result<SocketAddr, Fail>;
 
     /// Bind an address to the socket.
     fn bind(&mut self, sd: &mut Self::SocketDescriptor, local: SocketAddr) -> Result<(), Fail>;
@@ -57,6 +60,10 @@ pub trait NetworkTransport: Clone + 'static + MemoryRuntime {
     /// Listen on this socket in the network transport layer.
     fn listen(&mut self, sd: &mut Self::SocketDescriptor, backlog: usize) -> Result<(), Fail>;
 
+    /// Shut down receiving, sending or both on a connected socket. Pops see an end of file once receiving is shut down
+    /// and pushes fail once sending is shut down.
+    fn shutdown(&mut self, sd: &mut Self::SocketDescriptor, how: Shutdown) -> Result<(), Fail>;
+
     /// Forcibly close this socket in the network transport layer. This function should only be used in Drop and other
     /// internal functions, never exposed to the application.
     fn hard_close(&mut self, sd: &mut Self::SocketDescriptor) -> Result<(), Fail>;
@@ -74,6 +81,9 @@ pub trait NetworkTransport: Clone + 'static + MemoryRuntime {
         remote: SocketAddr,
     ) -> impl std::future::Future<Output = Result<(), Fail>>;
 
+    /// Roll back a connect that was cancelled before it completed, so that this socket can connect again.
+    fn cancel_connect(&mut self, sd: &mut Self::SocketDescriptor) -> Result<(), Fail>;
+
     /// Push data to a connected socket.
     fn push(
         &mut self,
@@ -0,0 +1,72 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    network::types::{
+        Ipv4Route,
+        IPV4_PREFIX_MAX_LEN,
+    },
+};
+use ::libc::EINVAL;
+use ::std::net::Ipv4Addr;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Local IPv4 address, along with the subnet that it belongs to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Ipv4Interface {
+    /// Local address.
+    addr: Ipv4Addr,
+    /// Length of the subnet mask of the local address.
+    prefix_len: u8,
+}
+
+//======================================================================================================================
+// Associate Functions
+//======================================================================================================================
+
+/// Associate Functions for IPv4 Interfaces
+impl Ipv4Interface {
+    /// Creates an interface for the local address [addr] in the subnet [addr]/[prefix_len].
+    pub fn new(addr: Ipv4Addr, prefix_len: u8) -> Result<Self, Fail> {
+        if addr.is_unspecified() || addr.is_broadcast() || addr.is_multicast() {
+            let cause: String = format!("invalid local address (addr={:?})", addr);
+            error!("new(): {}", cause);
+            return Err(Fail::new(EINVAL, &cause));
+        }
+        if prefix_len > IPV4_PREFIX_MAX_LEN {
+            let cause: String = format!("invalid prefix length (prefix_len={:?})", prefix_len);
+            error!("new(): {}", cause);
+            return Err(Fail::new(EINVAL, &cause));
+        }
+        Ok(Self { addr, prefix_len })
+    }
+
+    /// Parses an interface in CIDR notation (e.g., "192.168.1.2/24"). A plain address is alone in its subnet.
+    pub fn parse(interface: &str) -> Result<Self, Fail> {
+        let (addr, prefix_len): (Ipv4Addr, u8) = Ipv4Route::parse_destination(interface)?;
+        Self::new(addr, prefix_len)
+    }
+
+    /// Returns the local address.
+    pub fn get_addr(&self) -> Ipv4Addr {
+        self.addr
+    }
+
+    /// Returns the length of the subnet mask of the local address.
+    pub fn get_prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Returns the route to the other hosts in the subnet of the local address, which are on the local link.
+    pub fn get_subnet_route(&self) -> Result<Ipv4Route, Fail> {
+        Ipv4Route::new(self.addr, self.prefix_len, None, None)
+    }
+}
@@ -1,14 +1,21 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
+mod interface;
 mod macaddr;
 mod portnum;
+mod route;
 
 //======================================================================================================================
 // Exports
 //======================================================================================================================
 
 pub use self::{
+    interface::Ipv4Interface,
     macaddr::MacAddress,
     portnum::Port16,
+    route::{
+        Ipv4Route,
+        IPV4_PREFIX_MAX_LEN,
+    },
 };
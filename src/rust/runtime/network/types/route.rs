@@ -0,0 +1,134 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::fail::Fail;
+use ::libc::EINVAL;
+use ::std::net::Ipv4Addr;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Length of the longest IPv4 prefix.
+pub const IPV4_PREFIX_MAX_LEN: u8 = 32;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// IPv4 Route
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Ipv4Route {
+    /// Network address of the destinations that this route covers.
+    destination: Ipv4Addr,
+    /// Length of the subnet mask of the destinations that this route covers.
+    prefix_len: u8,
+    /// Router that forwards datagrams to these destinations, or None if they are on the local link.
+    gateway: Option<Ipv4Addr>,
+    /// Maximum size of a datagram that we send on this route, or None to use the MTU of the link.
+    mtu: Option<u16>,
+}
+
+//======================================================================================================================
+// Associate Functions
+//======================================================================================================================
+
+/// Associate Functions for IPv4 Routes
+impl Ipv4Route {
+    /// Creates a route to the destinations in [destination]/[prefix_len]. Host bits of [destination] are cleared.
+    pub fn new(
+        destination: Ipv4Addr,
+        prefix_len: u8,
+        gateway: Option<Ipv4Addr>,
+        mtu: Option<u16>,
+    ) -> Result<Self, Fail> {
+        if prefix_len > IPV4_PREFIX_MAX_LEN {
+            let cause: String = format!("invalid prefix length (prefix_len={:?})", prefix_len);
+            error!("new(): {}", cause);
+            return Err(Fail::new(EINVAL, &cause));
+        }
+        Ok(Self {
+            destination: Ipv4Addr::from(u32::from(destination) & Self::netmask(prefix_len)),
+            prefix_len,
+            gateway,
+            mtu,
+        })
+    }
+
+    /// Creates a default route through [gateway].
+    pub fn new_default(gateway: Ipv4Addr) -> Self {
+        Self {
+            destination: Ipv4Addr::UNSPECIFIED,
+            prefix_len: 0,
+            gateway: Some(gateway),
+            mtu: None,
+        }
+    }
+
+    /// Parses a route destination in CIDR notation (e.g., "192.168.0.0/16"). A plain address is a host route.
+    pub fn parse_destination(destination: &str) -> Result<(Ipv4Addr, u8), Fail> {
+        let (addr, prefix_len): (&str, Option<&str>) = match destination.split_once('/') {
+            Some((addr, prefix_len)) => (addr, Some(prefix_len)),
+            None => (destination, None),
+        };
+        let addr: Ipv4Addr = match addr.parse() {
+            Ok(addr) => addr,
+            Err(_) => {
+                let cause: String = format!("invalid route destination (destination={:?})", destination);
+                error!("parse_destination(): {}", cause);
+                return Err(Fail::new(EINVAL, &cause));
+            },
+        };
+        let prefix_len: u8 = match prefix_len.map(|prefix_len: &str| prefix_len.parse::<u8>()) {
+            None => IPV4_PREFIX_MAX_LEN,
+            Some(Ok(prefix_len)) if prefix_len <= IPV4_PREFIX_MAX_LEN => prefix_len,
+            Some(_) => {
+                let cause: String = format!("invalid route prefix length (destination={:?})", destination);
+                error!("parse_destination(): {}", cause);
+                return Err(Fail::new(EINVAL, &cause));
+            },
+        };
+        Ok((addr, prefix_len))
+    }
+
+    /// Returns the network address of the destinations that this route covers.
+    pub fn get_destination(&self) -> Ipv4Addr {
+        self.destination
+    }
+
+    /// Returns the length of the subnet mask of the destinations that this route covers.
+    pub fn get_prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Returns the router that forwards datagrams on this route, or None if its destinations are on the local link.
+    pub fn get_gateway(&self) -> Option<Ipv4Addr> {
+        self.gateway
+    }
+
+    /// Returns the MTU of this route, if it overrides the one of the link.
+    pub fn get_mtu(&self) -> Option<u16> {
+        self.mtu
+    }
+
+    /// Queries whether or not this route covers [addr].
+    pub fn contains(&self, addr: Ipv4Addr) -> bool {
+        u32::from(addr) & Self::netmask(self.prefix_len) == u32::from(self.destination)
+    }
+
+    /// Returns the directed broadcast address of the destinations that this route covers.
+    pub fn get_broadcast_addr(&self) -> Ipv4Addr {
+        Ipv4Addr::from(u32::from(self.destination) | !Self::netmask(self.prefix_len))
+    }
+
+    /// Returns the subnet mask for [prefix_len].
+    fn netmask(prefix_len: u8) -> u32 {
+        u32::MAX
+            .checked_shl((IPV4_PREFIX_MAX_LEN - prefix_len) as u32)
+            .unwrap_or(0)
+    }
+}
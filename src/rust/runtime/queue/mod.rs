@@ -21,7 +21,7 @@ use ::slab::{
 };
 use ::std::{
     any::Any,
-    net::SocketAddrV4,
+    net::SocketAddr,
 };
 
 //======================================================================================================================
@@ -54,8 +54,8 @@ pub trait IoQueue: Any {
 }
 
 pub trait NetworkQueue: IoQueue {
-    fn local(&self) -> Option<SocketAddrV4>;
-    fn remote(&self) -> Option<SocketAddrV4>;
+    fn local(&self) -> Option<SocketAddr>;
+    fn remote(&self) -> Option<SocketAddr>;
 }
 
 /// I/O queue descriptors table.
@@ -0,0 +1,405 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Cross-thread submissions to a runtime.
+//!
+//! A runtime and its coroutines are bound to a single thread. Other threads hand work to it through a
+//! [SubmissionQueue], a multi-producer, single-consumer ring of boxed items, and wake it through the [Doorbell] of the
+//! queue. The runtime drains its queue whenever it polls.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    collections::concurrent_ring::ConcurrentRingBuffer,
+    runtime::{
+        fail::Fail,
+        QDesc,
+        QToken,
+        SharedDemiRuntime,
+    },
+};
+use ::std::{
+    marker::PhantomData,
+    mem,
+    sync::Arc,
+    time::Duration,
+};
+
+#[cfg(not(target_os = "linux"))]
+use ::std::sync::{
+    Condvar,
+    Mutex,
+    MutexGuard,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Size of the ring of a submission queue in bytes. Each submission takes a pointer and a 2-byte header, so this
+/// holds a few thousand pending submissions.
+pub const SUBMISSION_RING_SIZE: usize = 64 * 1024;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Handler that pushes data that another thread submitted to a queue, and returns the queue token of the push.
+pub type PushHandler = Box<dyn FnMut(QDesc, &[u8]) -> Result<QToken, Fail>>;
+
+/// Work that another thread submits to a runtime.
+pub enum Submission {
+    /// Pushes a copy of the data to a queue of the runtime. The push completes in the background, and the runtime
+    /// reports failures in its log.
+    Push(QDesc, Vec<u8>),
+    /// Runs a closure on the thread of the runtime.
+    Call(Box<dyn FnOnce(&mut SharedDemiRuntime) + Send>),
+}
+
+/// Event counter that wakes a thread sleeping on it, in the fashion of an eventfd. Rings that happen while nobody
+/// sleeps are remembered until the next wait.
+pub struct Doorbell {
+    #[cfg(target_os = "linux")]
+    fd: libc::c_int,
+    #[cfg(not(target_os = "linux"))]
+    rung: Mutex<bool>,
+    #[cfg(not(target_os = "linux"))]
+    condvar: Condvar,
+}
+
+/// Multi-producer, single-consumer queue of items of type [T], which rings its doorbell on every submission. Items
+/// are boxed and travel through the ring as pointers.
+pub struct SubmissionQueue<T: Send> {
+    ring: ConcurrentRingBuffer,
+    doorbell: Doorbell,
+    _items: PhantomData<T>,
+}
+
+/// Handle through which other threads submit work to a runtime.
+#[derive(Clone)]
+pub struct Submitter(Arc<SubmissionQueue<Submission>>);
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Doorbell {
+    #[cfg(target_os = "linux")]
+    pub fn new() -> Result<Self, Fail> {
+        let fd: libc::c_int = unsafe { libc::eventfd(0, libc::EFD_NONBLOCK | libc::EFD_CLOEXEC) };
+        if fd < 0 {
+            let errno: libc::c_int = unsafe { *libc::__errno_location() };
+            let cause: String = format!("failed to create eventfd (errno={:?})", errno);
+            error!("new(): {}", cause);
+            return Err(Fail::new(errno, &cause));
+        }
+        Ok(Self { fd })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn new() -> Result<Self, Fail> {
+        Ok(Self {
+            rung: Mutex::new(false),
+            condvar: Condvar::new(),
+        })
+    }
+
+    /// Rings the doorbell, which wakes the thread that sleeps on it.
+    #[cfg(target_os = "linux")]
+    pub fn ring(&self) {
+        let value: u64 = 1;
+        // The write only fails if the counter is about to overflow, in which case the doorbell is already rung.
+        unsafe {
+            libc::write(
+                self.fd,
+                &value as *const u64 as *const libc::c_void,
+                mem::size_of::<u64>(),
+            )
+        };
+    }
+
+    /// Rings the doorbell, which wakes the thread that sleeps on it.
+    #[cfg(not(target_os = "linux"))]
+    pub fn ring(&self) {
+        *self.lock() = true;
+        self.condvar.notify_one();
+    }
+
+    /// Sleeps until the doorbell rings or [timeout] expires, and returns whether it rang. Either way, the doorbell is
+    /// quiet afterwards.
+    #[cfg(target_os = "linux")]
+    pub fn wait(&self, timeout: Duration) -> bool {
+        let mut pollfd: libc::pollfd = libc::pollfd {
+            fd: self.fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // Idle runtimes back off with sleeps that are much shorter than a millisecond, so we need the precision of
+        // ppoll.
+        let timeout: libc::timespec = libc::timespec {
+            tv_sec: timeout.as_secs().min(libc::time_t::MAX as u64) as libc::time_t,
+            tv_nsec: timeout.subsec_nanos() as libc::c_long,
+        };
+        unsafe { libc::ppoll(&mut pollfd, 1, &timeout, ::std::ptr::null()) };
+        self.clear()
+    }
+
+    /// Sleeps until the doorbell rings or [timeout] expires, and returns whether it rang. Either way, the doorbell is
+    /// quiet afterwards.
+    #[cfg(not(target_os = "linux"))]
+    pub fn wait(&self, timeout: Duration) -> bool {
+        let rung: MutexGuard<bool> = self.lock();
+        let mut rung: MutexGuard<bool> = match self.condvar.wait_timeout_while(rung, timeout, |rung: &mut bool| !*rung)
+        {
+            Ok((rung, _)) => rung,
+            Err(e) => e.into_inner().0,
+        };
+        mem::replace(&mut *rung, false)
+    }
+
+    /// Quiets the doorbell without sleeping, and returns whether it had rung.
+    #[cfg(target_os = "linux")]
+    pub fn clear(&self) -> bool {
+        let mut value: u64 = 0;
+        let ret: isize = unsafe {
+            libc::read(
+                self.fd,
+                &mut value as *mut u64 as *mut libc::c_void,
+                mem::size_of::<u64>(),
+            )
+        };
+        ret == mem::size_of::<u64>() as isize
+    }
+
+    /// Quiets the doorbell without sleeping, and returns whether it had rung.
+    #[cfg(not(target_os = "linux"))]
+    pub fn clear(&self) -> bool {
+        mem::replace(&mut *self.lock(), false)
+    }
+
+    /// Returns the underlying eventfd, which becomes readable when the doorbell rings. This is meant to be watched
+    /// along with other file descriptors, such as in an epoll set.
+    #[cfg(target_os = "linux")]
+    pub fn as_raw_fd(&self) -> libc::c_int {
+        self.fd
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn lock(&self) -> MutexGuard<bool> {
+        match self.rung.lock() {
+            Ok(rung) => rung,
+            Err(e) => e.into_inner(),
+        }
+    }
+}
+
+impl<T: Send> SubmissionQueue<T> {
+    /// Creates a submission queue whose ring takes [size] bytes.
+    pub fn new(size: usize) -> Result<Self, Fail> {
+        Ok(Self {
+            ring: ConcurrentRingBuffer::new(size)?,
+            doorbell: Doorbell::new()?,
+            _items: PhantomData,
+        })
+    }
+
+    /// Submits [item] and rings the doorbell. This fails with EAGAIN if the queue is full.
+    pub fn try_submit(&self, item: T) -> Result<(), Fail> {
+        let ptr: *mut T = Box::into_raw(Box::new(item));
+        if let Err(e) = self.ring.try_push(&(ptr as usize).to_ne_bytes()) {
+            // Safety: the pointer was not handed over to the ring, so we still own the item.
+            drop(unsafe { Box::from_raw(ptr) });
+            return Err(e);
+        }
+        self.doorbell.ring();
+        Ok(())
+    }
+
+    /// Takes the next item, if any. Only the thread that owns the queue should take items.
+    pub fn try_take(&self) -> Option<T> {
+        let mut bytes: [u8; mem::size_of::<usize>()] = [0; mem::size_of::<usize>()];
+        match self.ring.try_pop(&mut bytes) {
+            Ok(len) => {
+                debug_assert_eq!(len, bytes.len());
+                // Safety: the ring only holds pointers that [try_submit] leaked, and each of them is popped once.
+                let item: Box<T> = unsafe { Box::from_raw(usize::from_ne_bytes(bytes) as *mut T) };
+                Some(*item)
+            },
+            Err(_) => None,
+        }
+    }
+
+    /// Returns the doorbell that rings when items are submitted.
+    pub fn doorbell(&self) -> &Doorbell {
+        &self.doorbell
+    }
+}
+
+impl Submitter {
+    pub fn new(queue: Arc<SubmissionQueue<Submission>>) -> Self {
+        Self(queue)
+    }
+
+    /// Pushes a copy of [data] to the queue [qd] on the thread of the runtime.
+    pub fn push(&self, qd: QDesc, data: &[u8]) -> Result<(), Fail> {
+        if data.is_empty() {
+            let cause: &str = "zero-length buffer";
+            warn!("push(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, cause));
+        }
+        self.0.try_submit(Submission::Push(qd, data.to_vec()))
+    }
+
+    /// Runs [f] on the thread of the runtime.
+    pub fn call<F: FnOnce(&mut SharedDemiRuntime) + Send + 'static>(&self, f: F) -> Result<(), Fail> {
+        self.0.try_submit(Submission::Call(Box::new(f)))
+    }
+
+    /// Returns the doorbell that rings when work is submitted.
+    pub fn doorbell(&self) -> &Doorbell {
+        self.0.doorbell()
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+#[cfg(target_os = "linux")]
+impl Drop for Doorbell {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.fd) };
+    }
+}
+
+/// Items are only taken by the thread that owns the queue, so sharing the queue only requires items to be sendable.
+unsafe impl<T: Send> Sync for SubmissionQueue<T> {}
+
+impl<T: Send> Drop for SubmissionQueue<T> {
+    /// Releases the items that were never taken.
+    fn drop(&mut self) {
+        while let Some(item) = self.try_take() {
+            drop(item);
+        }
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ensure_eq,
+        runtime::submission::{
+            SubmissionQueue,
+            SUBMISSION_RING_SIZE,
+        },
+    };
+    use ::anyhow::Result;
+    use ::std::{
+        sync::{
+            atomic::{
+                AtomicUsize,
+                Ordering,
+            },
+            Arc,
+        },
+        thread,
+        time::Duration,
+    };
+
+    /// Takes the items that several threads submit, in submission order for each thread.
+    #[test]
+    fn test_submit_from_threads() -> Result<()> {
+        const NUM_THREADS: usize = 4;
+        const NUM_ITEMS: usize = 1000;
+        let queue: Arc<SubmissionQueue<(usize, usize)>> = Arc::new(SubmissionQueue::new(SUBMISSION_RING_SIZE)?);
+
+        let producers: Vec<thread::JoinHandle<()>> = (0..NUM_THREADS)
+            .map(|producer: usize| {
+                let queue: Arc<SubmissionQueue<(usize, usize)>> = queue.clone();
+                thread::spawn(move || {
+                    for i in 0..NUM_ITEMS {
+                        while queue.try_submit((producer, i)).is_err() {
+                            thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        let mut next: Vec<usize> = vec![0; NUM_THREADS];
+        let mut taken: usize = 0;
+        while taken < NUM_THREADS * NUM_ITEMS {
+            match queue.try_take() {
+                Some((producer, i)) => {
+                    ensure_eq!(i, next[producer]);
+                    next[producer] += 1;
+                    taken += 1;
+                },
+                None => thread::yield_now(),
+            }
+        }
+        for producer in producers {
+            if producer.join().is_err() {
+                anyhow::bail!("producer thread panicked");
+            }
+        }
+        ensure_eq!(queue.try_take().is_none(), true);
+        Ok(())
+    }
+
+    /// Wakes a thread that sleeps on the doorbell.
+    #[test]
+    fn test_doorbell_wakes_sleeper() -> Result<()> {
+        let queue: Arc<SubmissionQueue<usize>> = Arc::new(SubmissionQueue::new(SUBMISSION_RING_SIZE)?);
+
+        // Nothing was submitted, so the doorbell stays quiet.
+        ensure_eq!(queue.doorbell().wait(Duration::from_millis(1)), false);
+
+        let producer: thread::JoinHandle<()> = {
+            let queue: Arc<SubmissionQueue<usize>> = queue.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(10));
+                queue.try_submit(42).expect("queue should have space");
+            })
+        };
+        ensure_eq!(queue.doorbell().wait(Duration::from_secs(60)), true);
+        ensure_eq!(queue.try_take(), Some(42));
+        if producer.join().is_err() {
+            anyhow::bail!("producer thread panicked");
+        }
+
+        // The doorbell was quieted by the wait.
+        ensure_eq!(queue.doorbell().clear(), false);
+        Ok(())
+    }
+
+    /// Releases the items that were never taken.
+    #[test]
+    fn test_drop_pending_items() -> Result<()> {
+        let drops: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(0));
+        struct Item(Arc<AtomicUsize>);
+        impl Drop for Item {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let queue: SubmissionQueue<Item> = SubmissionQueue::new(SUBMISSION_RING_SIZE)?;
+        for _ in 0..3 {
+            if queue.try_submit(Item(drops.clone())).is_err() {
+                anyhow::bail!("queue should have space");
+            }
+        }
+        drop(queue.try_take());
+        ensure_eq!(drops.load(Ordering::Relaxed), 1);
+        drop(queue);
+        ensure_eq!(drops.load(Ordering::Relaxed), 3);
+        Ok(())
+    }
+}
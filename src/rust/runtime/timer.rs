@@ -1,19 +1,23 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
-//! This module implements a global timer for the Demikernel system. In order to keep the networking stack and other
-//! parts of the system deterministic, we control time and time out events from thisn single file.
+//! This module implements the timers of the Demikernel system. In order to keep the networking stack and other
+//! parts of the system deterministic, we control time and time out events from thisn single file. Each runtime owns a
+//! timer and installs it as the current timer of the thread while it runs coroutines, so that independent runtimes on
+//! the same thread do not share a sense of time.
 
 //======================================================================================================================
 // Imports
 //======================================================================================================================
 use crate::{
-    expect_some,
+    collections::timing_wheel::{
+        TimerKey,
+        TimingWheel,
+    },
     runtime::SharedObject,
 };
-use ::core::cmp::Reverse;
 use ::std::{
-    collections::BinaryHeap,
+    cell::RefCell,
     future::Future,
     ops::{
         Deref,
@@ -36,8 +40,9 @@ use ::std::{
 //======================================================================================================================
 
 thread_local! {
-/// This is our shared sense of time. It is explicitly moved forward ONLY by the runtime and used to trigger time outs.
-static THREAD_TIME: SharedTimer = SharedTimer::default();
+/// This is the sense of time of the runtime that is currently running coroutines on this thread. It is explicitly moved
+/// forward ONLY by the runtime and used to trigger time outs.
+static THREAD_TIME: RefCell<SharedTimer> = RefCell::new(SharedTimer::default());
 }
 
 //======================================================================================================================
@@ -48,84 +53,75 @@ static THREAD_TIME: SharedTimer = SharedTimer::default();
 /// The state of the coroutine using this condition variable.
 enum YieldState {
     Running,
-    Yielded(YieldPointId),
+    Yielded(TimerKey),
 }
 
-#[derive(Eq, PartialEq, Clone, Copy)]
-struct YieldPointId(u64);
-
 struct YieldPoint {
+    /// The timer that triggers the time out.
+    timer: SharedTimer,
     /// The time out.
     expiry: Instant,
     /// State of the yield.
     state: YieldState,
 }
 
-struct TimerQueueEntry {
-    expiry: Instant,
-    id: YieldPointId,
-    waker: Waker,
-}
-
 /// Timer that holds one or more events for future wake up.
 pub struct Timer {
     now: Instant,
-    // Use a reverse to get a min heap.
-    heap: BinaryHeap<Reverse<TimerQueueEntry>>,
-    // Monotonically increasing identifier for yield points.
-    last_id: YieldPointId,
+    // Wakers of yield points, keyed by their time out.
+    wheel: TimingWheel<Waker>,
 }
 
 #[derive(Clone)]
 pub struct SharedTimer(SharedObject<Timer>);
 
+/// Installs a timer as the current timer of the thread and restores the previous one when dropped.
+pub struct TimerGuard {
+    prev: Option<SharedTimer>,
+}
+
 //======================================================================================================================
 // Associated Functions
 //======================================================================================================================
 
-impl YieldPointId {
-    pub fn increment(&mut self) {
-        self.0 = self.0 + 1;
-    }
-}
-
 impl SharedTimer {
     /// This sets the time but is only used for initialization.
-    fn set_time(&mut self, now: Instant) {
+    pub fn set_time(&mut self, now: Instant) {
         // Clear out existing timers because they are meaningless once time has been moved in a non-monotonically
         // increasing manner.
-        self.heap.clear();
+        self.wheel.reset(now);
         self.now = now;
     }
 
-    fn advance_clock(&mut self, now: Instant) {
+    pub fn advance_clock(&mut self, now: Instant) {
         assert!(self.now <= now);
-        while let Some(Reverse(entry)) = self.heap.peek() {
-            if now < entry.expiry {
-                break;
-            }
-            let entry: TimerQueueEntry =
-                expect_some!(self.heap.pop(), "should have an entry because we were able to peek").0;
-            entry.waker.wake_by_ref();
-        }
+        self.wheel.advance(now, |waker: Waker| waker.wake());
         self.now = now;
     }
 
-    fn now(&self) -> Instant {
+    pub fn now(&self) -> Instant {
         self.now
     }
 
-    fn add_timeout(&mut self, expiry: Instant, waker: Waker) -> YieldPointId {
-        let id = self.last_id;
-        self.last_id.increment();
+    /// Returns a time by which the earliest time out fires, if any. Idle runtimes sleep until then at the latest.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.wheel.next_deadline()
+    }
 
-        let entry: TimerQueueEntry = TimerQueueEntry { expiry, id, waker };
-        self.heap.push(Reverse(entry));
-        id
+    fn add_timeout(&mut self, expiry: Instant, waker: Waker) -> TimerKey {
+        self.wheel.insert(expiry, waker)
     }
 
-    fn remove_timeout(&mut self, id: YieldPointId) {
-        self.heap.retain(|entry| entry.0.id != id);
+    fn remove_timeout(&mut self, key: TimerKey) {
+        self.wheel.remove(key);
+    }
+}
+
+impl TimerGuard {
+    /// Makes [timer] the current timer of the thread until the returned guard is dropped.
+    pub fn enter(timer: &SharedTimer) -> Self {
+        let prev: SharedTimer = THREAD_TIME.with(|s| s.replace(timer.clone()));
+        Self { prev: Some(prev) }
     }
 }
 
@@ -133,23 +129,20 @@ impl SharedTimer {
 // Associated Functions
 //======================================================================================================================
 
-/// Sets the global time in the Demikernel system to [now].
-pub fn global_set_time(now: Instant) {
-    THREAD_TIME.with(|s| {
-        s.clone().set_time(now);
-    })
+/// Returns the current timer of the thread.
+fn current_timer() -> SharedTimer {
+    THREAD_TIME.with(|s| s.borrow().clone())
 }
 
-/// Causes global time in the Demikernel system to move forward and triggers all timeouts that have passed.
+/// Causes time on the current timer to move forward and triggers all timeouts that have passed.
+#[cfg(test)]
 pub fn global_advance_clock(now: Instant) {
-    THREAD_TIME.with(|s| {
-        s.clone().advance_clock(now);
-    })
+    current_timer().advance_clock(now);
 }
 
-/// Gets the current global time in the Demikernel system.
+/// Gets the current time of the current timer.
 pub fn global_get_time() -> Instant {
-    THREAD_TIME.with(|s| s.now())
+    current_timer().now()
 }
 
 /// Blocks until the system time moves
@@ -160,6 +153,7 @@ pub async fn wait(timeout: Duration) {
 
 pub async fn wait_until(expiry: Instant) {
     YieldPoint {
+        timer: current_timer(),
         expiry,
         state: YieldState::Running,
     }
@@ -172,10 +166,10 @@ pub async fn wait_until(expiry: Instant) {
 
 impl Default for SharedTimer {
     fn default() -> Self {
+        let now: Instant = Instant::now();
         Self(SharedObject::<Timer>::new(Timer {
-            now: Instant::now(),
-            heap: BinaryHeap::new(),
-            last_id: YieldPointId(0),
+            now,
+            wheel: TimingWheel::new(now),
         }))
     }
 }
@@ -194,29 +188,6 @@ impl DerefMut for SharedTimer {
     }
 }
 
-impl PartialEq for TimerQueueEntry {
-    fn eq(&self, other: &TimerQueueEntry) -> bool {
-        // This is technically not correct. However for the usage in this module
-        // we only need to compare timers by expiration.
-        self.expiry == other.expiry
-    }
-}
-
-impl Eq for TimerQueueEntry {}
-
-impl PartialOrd for TimerQueueEntry {
-    fn partial_cmp(&self, other: &TimerQueueEntry) -> Option<core::cmp::Ordering> {
-        // Compare timer queue entries by expiration time
-        self.expiry.partial_cmp(&other.expiry)
-    }
-}
-
-impl Ord for TimerQueueEntry {
-    fn cmp(&self, other: &TimerQueueEntry) -> core::cmp::Ordering {
-        self.expiry.cmp(&other.expiry)
-    }
-}
-
 impl Future for YieldPoint {
     type Output = ();
 
@@ -225,17 +196,16 @@ impl Future for YieldPoint {
         match self_.state {
             YieldState::Running => {
                 // If the timer expired while we were running and before we yielded, just return.
-                if self_.expiry <= global_get_time() {
+                if self_.expiry <= self_.timer.now() {
                     Poll::Ready(())
                 } else {
-                    let id: YieldPointId =
-                        THREAD_TIME.with(|s| s.clone().add_timeout(self_.expiry, context.waker().clone()));
+                    let id: TimerKey = self_.timer.add_timeout(self_.expiry, context.waker().clone());
                     self_.state = YieldState::Yielded(id);
                     Poll::Pending
                 }
             },
             YieldState::Yielded(_) => {
-                if global_get_time() >= self_.expiry {
+                if self_.timer.now() >= self_.expiry {
                     Poll::Ready(())
                 } else {
                     // Spurious wake up because we wake all blocked yield points in a task.
@@ -249,7 +219,15 @@ impl Future for YieldPoint {
 impl Drop for YieldPoint {
     fn drop(&mut self) {
         if let YieldState::Yielded(id) = self.state {
-            THREAD_TIME.with(|s| s.clone().remove_timeout(id));
+            self.timer.remove_timeout(id);
+        }
+    }
+}
+
+impl Drop for TimerGuard {
+    fn drop(&mut self) {
+        if let Some(prev) = self.prev.take() {
+            THREAD_TIME.with(|s| s.replace(prev));
         }
     }
 }
@@ -263,6 +241,8 @@ mod tests {
     use crate::runtime::timer::{
         global_advance_clock,
         wait,
+        SharedTimer,
+        TimerGuard,
     };
     use ::anyhow::Result;
     use futures::task::noop_waker_ref;
@@ -334,4 +314,38 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_independent_timers() -> Result<()> {
+        let mut ctx = Context::from_waker(noop_waker_ref());
+        let now = Instant::now();
+        let mut timer1: SharedTimer = SharedTimer::default();
+        let mut timer2: SharedTimer = SharedTimer::default();
+        timer1.set_time(now);
+        timer2.set_time(now);
+
+        // Add a time out at start of test + 1 second on each timer.
+        let wait_future1 = wait(Duration::from_secs(1));
+        futures::pin_mut!(wait_future1);
+        let wait_future2 = wait(Duration::from_secs(1));
+        futures::pin_mut!(wait_future2);
+        {
+            let _guard: TimerGuard = TimerGuard::enter(&timer1);
+            crate::ensure_eq!(Future::poll(Pin::new(&mut wait_future1), &mut ctx).is_ready(), false);
+        }
+        {
+            let _guard: TimerGuard = TimerGuard::enter(&timer2);
+            crate::ensure_eq!(Future::poll(Pin::new(&mut wait_future2), &mut ctx).is_ready(), false);
+        }
+
+        // Move time of the first timer to start of test + 2 seconds.
+        timer1.advance_clock(now + Duration::from_secs(2));
+
+        // Check that only the time out of the first timer has triggered, regardless of the current timer.
+        crate::ensure_eq!(Future::poll(Pin::new(&mut wait_future1), &mut ctx).is_ready(), true);
+        crate::ensure_eq!(Future::poll(Pin::new(&mut wait_future2), &mut ctx).is_ready(), false);
+        crate::ensure_eq!(timer2.now(), now);
+
+        Ok(())
+    }
 }
@@ -30,6 +30,9 @@ pub use self::{
 // Structures
 //======================================================================================================================
 
+/// An opaque handle to a LibOS instance.
+pub type demi_instance_t = u32;
+
 /// A callback function.
 pub type demi_callback_t = extern "C" fn(*const std::ffi::c_char, u32, u64);
 
@@ -39,6 +42,11 @@ pub struct demi_args_t {
     pub argc: core::ffi::c_int,
     pub argv: *const *const core::ffi::c_char,
     pub callback: Option<demi_callback_t>,
+    /// Name of the LibOS to instantiate. If null, the `LIBOS` environment variable is used instead.
+    pub libos_name: *const core::ffi::c_char,
+    /// Configuration in YAML format. If null, the file that the `CONFIG_PATH` environment variable points to is used
+    /// instead.
+    pub config: *const core::ffi::c_char,
 }
 
 impl Default for demi_args_t {
@@ -47,6 +55,8 @@ impl Default for demi_args_t {
             argc: 0,
             argv: std::ptr::null(),
             callback: None,
+            libos_name: std::ptr::null(),
+            config: std::ptr::null(),
         }
     }
 }
@@ -67,9 +77,17 @@ mod test {
         const DEMIARGS_ARGV_SIZE: usize = 8;
         // Size of a c char.
         const DEMIARGS_CALLBACK_SIZE: usize = 8;
+        // Size of a c char pointer.
+        const DEMIARGS_LIBOS_NAME_SIZE: usize = 8;
+        // Size of a c char pointer.
+        const DEMIARGS_CONFIG_SIZE: usize = 8;
 
         // The expected size of the `DemiArgs` structure.
-        const DEMIARGS_SIZE: usize = DEMIARGS_ARGC_SIZE + DEMIARGS_ARGV_SIZE + DEMIARGS_CALLBACK_SIZE;
+        const DEMIARGS_SIZE: usize = DEMIARGS_ARGC_SIZE
+            + DEMIARGS_ARGV_SIZE
+            + DEMIARGS_CALLBACK_SIZE
+            + DEMIARGS_LIBOS_NAME_SIZE
+            + DEMIARGS_CONFIG_SIZE;
 
         // Check if the sizes match.
         assert_eq!(std::mem::size_of::<crate::runtime::types::demi_args_t>(), DEMIARGS_SIZE);
@@ -103,11 +103,10 @@ mod test {
         const QR_RET_SIZE: usize = 8;
         // Size of a demi_qr_value_t structure.
         const QR_VALUE_SIZE: usize = mem::size_of::<demi_qr_value_t>();
-        // Size of a demi_qresult_t structure.
-        crate::ensure_eq!(
-            mem::size_of::<demi_qresult_t>(),
-            QR_OPCODE_SIZE + QR_QD_SIZE + QR_QT_SIZE + QR_RET_SIZE + QR_VALUE_SIZE
-        );
+        // Size of the fields of a demi_qresult_t structure.
+        const QR_FIELDS_SIZE: usize = QR_OPCODE_SIZE + QR_QD_SIZE + QR_QT_SIZE + QR_RET_SIZE + QR_VALUE_SIZE;
+        // Size of a demi_qresult_t structure, which is padded to the alignment of its 8-byte fields.
+        crate::ensure_eq!(mem::size_of::<demi_qresult_t>(), QR_FIELDS_SIZE.next_multiple_of(8));
         Ok(())
     }
 }
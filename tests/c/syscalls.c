@@ -164,6 +164,47 @@ static bool inval_getpeername(void)
     return (demi_getpeername(qd, NULL, NULL) != 0);
 }
 
+/*
+* @brief Issues an invalid call to getsockname().
+*/
+static bool inval_getsockname(void)
+{
+    int qd = -1;
+
+    return (demi_getsockname(qd, NULL, NULL) != 0);
+}
+
+/**
+ * @brief Issues an invalid call to demi_shutdown().
+ */
+static bool inval_shutdown(void)
+{
+    int qd = -1;
+    int how = -1;
+
+    return (demi_shutdown(qd, how) != 0);
+}
+
+/**
+ * @brief Issues an invalid call to demi_init_instance().
+ */
+static bool inval_init_instance(void)
+{
+    demi_instance_t *instance = NULL;
+
+    return (demi_init_instance(instance, NULL) != 0);
+}
+
+/**
+ * @brief Issues an invalid call to demi_select_instance().
+ */
+static bool inval_select_instance(void)
+{
+    demi_instance_t instance = UINT32_MAX;
+
+    return (demi_select_instance(instance) != 0);
+}
+
 /*===================================================================================================================*
  * System Calls in demi/sga.h                                                                                        *
  *===================================================================================================================*/
@@ -242,7 +283,10 @@ static struct test tests_libos[] = {{inval_socket, "invalid demi_socket()"},   {
                                     {inval_connect, "invalid demi_connect()"}, {inval_listen, "invalid demi_listen()"},
                                     {inval_pop, "invalid demi_pop()"},         {inval_push, "invalid demi_push()"},
                                     {inval_pushto, "invalid demi_pushto()"},   {inval_getpeername, "invalid demi_getpeername()"},
-                                    {inval_setsockopt, "invalid demi_setsockopt()"}, {inval_getsockopt, "invalid demi_getsockopt()}"}};
+                                    {inval_setsockopt, "invalid demi_setsockopt()"}, {inval_getsockopt, "invalid demi_getsockopt()}"},
+                                    {inval_getsockname, "invalid demi_getsockname()"}, {inval_shutdown, "invalid demi_shutdown()"},
+                                    {inval_init_instance, "invalid demi_init_instance()"},
+                                    {inval_select_instance, "invalid demi_select_instance()"}};
 
 /**
  * @brief Tests for system calls in demi/sga.h
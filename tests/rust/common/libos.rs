@@ -72,7 +72,7 @@ impl DummyLibOS {
         logging::initialize();
         let transport = SharedInetStack::new_test(&config, runtime.clone(), network)?;
         Ok(Self(SharedNetworkLibOS::<SharedInetStack>::new(
-            config.local_ipv4_addr()?,
+            config.local_ipv4_addrs(),
             runtime,
             transport,
         )))
@@ -80,8 +80,11 @@ impl DummyLibOS {
 
     /// Cooks a buffer.
     pub fn cook_data(&self, size: usize) -> Result<demi_sgarray_t, Fail> {
-        let fill_char: u8 = b'a';
+        self.cook_data_with(size, b'a')
+    }
 
+    /// Cooks a buffer filled with [fill_char].
+    pub fn cook_data_with(&self, size: usize, fill_char: u8) -> Result<demi_sgarray_t, Fail> {
         let mut buf: DemiBuffer = DemiBuffer::new_with_headroom(size as u16, MAX_HEADER_SIZE as u16);
         for a in &mut buf[..] {
             *a = fill_char;
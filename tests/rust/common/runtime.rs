@@ -8,7 +8,6 @@
 use ::arrayvec::ArrayVec;
 use ::demikernel::{
     demi_sgarray_t,
-    demi_sgaseg_t,
     inetstack::protocols::{
         layer1::PhysicalLayer,
         MAX_HEADER_SIZE,
@@ -16,6 +15,8 @@ use ::demikernel::{
     runtime::{
         fail::Fail,
         memory::{
+            alloc_buffer_chain,
+            sgarray_from_buffer,
             DemiBuffer,
             MemoryRuntime,
         },
@@ -23,14 +24,9 @@ use ::demikernel::{
         SharedObject,
     },
 };
-use ::libc::c_void;
-use ::log::error;
-use ::std::{
-    mem,
-    ops::{
-        Deref,
-        DerefMut,
-    },
+use ::std::ops::{
+    Deref,
+    DerefMut,
 };
 
 //======================================================================================================================
@@ -76,7 +72,10 @@ impl PhysicalLayer for SharedDummyRuntime {
     fn transmit(&mut self, pkt: DemiBuffer) -> Result<(), Fail> {
         // The packet header and body must fit into whatever physical media we're transmitting over.
         // For this test harness, we 2^16 bytes (u16::MAX) as our limit.
-        assert!(pkt.len() < u16::MAX as usize);
+        assert!(pkt.total_len() < u16::MAX as usize);
+
+        // Hand out contiguous frames, so that the receiving side can parse them.
+        let pkt: DemiBuffer = pkt.linearize()?;
 
         match self.outgoing.try_send(pkt) {
             Ok(_) => Ok(()),
@@ -99,38 +98,12 @@ impl PhysicalLayer for SharedDummyRuntime {
 impl MemoryRuntime for SharedDummyRuntime {
     /// Allocates a scatter-gather array.
     fn sgaalloc(&self, size: usize) -> Result<demi_sgarray_t, Fail> {
-        // TODO: Allocate an array of buffers if requested size is too large for a single buffer.
-
-        // We can't allocate a zero-sized buffer.
-        if size == 0 {
-            let cause: String = format!("cannot allocate a zero-sized buffer");
-            error!("sgaalloc(): {}", cause);
-            return Err(Fail::new(libc::EINVAL, &cause));
-        }
-
-        // We can't allocate more than a single buffer.
-        if size > u16::MAX as usize {
-            return Err(Fail::new(libc::EINVAL, "size too large for a single demi_sgaseg_t"));
-        }
-
-        // First allocate the underlying DemiBuffer.
         // Always allocate with header space for now even if we do not need it.
-        let buf: DemiBuffer = DemiBuffer::new_with_headroom(size as u16, MAX_HEADER_SIZE as u16);
-
-        // Create a scatter-gather segment to expose the DemiBuffer to the user.
-        let data: *const u8 = buf.as_ptr();
-        let sga_seg: demi_sgaseg_t = demi_sgaseg_t {
-            sgaseg_buf: data as *mut c_void,
-            sgaseg_len: size as u32,
-        };
-
-        // Create and return a new scatter-gather array (which inherits the DemiBuffer's reference).
-        Ok(demi_sgarray_t {
-            sga_buf: buf.into_raw().as_ptr() as *mut c_void,
-            sga_numsegs: 1,
-            sga_segs: [sga_seg],
-            sga_addr: unsafe { mem::zeroed() },
-        })
+        let max_segment_size: usize = u16::MAX as usize - MAX_HEADER_SIZE;
+        let buf: DemiBuffer = alloc_buffer_chain(size, max_segment_size, |segment_size: u16| {
+            Ok(DemiBuffer::new_with_headroom(segment_size, MAX_HEADER_SIZE as u16))
+        })?;
+        sgarray_from_buffer(buf)
     }
 }
 
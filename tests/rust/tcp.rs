@@ -151,7 +151,7 @@ mod test {
             let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
 
             let qd: QDesc = match qr {
-                OperationResult::Accept((qd, addr)) if addr.ip() == &BOB_IP => qd,
+                OperationResult::Accept((qd, addr)) if addr.ip() == BOB_IP => qd,
                 _ => {
                     // Close socket on error.
                     // FIXME: https://github.com/demikernel/demikernel/issues/633
@@ -229,7 +229,7 @@ mod test {
             let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
 
             let qd: QDesc = match qr {
-                OperationResult::Accept((qd, addr)) if addr.ip() == &BOB_IP => qd,
+                OperationResult::Accept((qd, addr)) if addr.ip() == BOB_IP => qd,
                 _ => {
                     // Close socket on error.
                     // FIXME: https://github.com/demikernel/demikernel/issues/633
@@ -313,7 +313,7 @@ mod test {
             let qt: QToken = safe_accept(&mut libos, sockqd)?;
             let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
             let qd: QDesc = match qr {
-                OperationResult::Accept((qd, addr)) if addr.ip() == &BOB_IP => qd,
+                OperationResult::Accept((qd, addr)) if addr.ip() == BOB_IP => qd,
                 _ => {
                     // Close socket on error.
                     // FIXME: https://github.com/demikernel/demikernel/issues/633
@@ -389,6 +389,253 @@ mod test {
         Ok(())
     }
 
+    /// Tests if a pending pop can be cancelled without losing data, and if pops of a fixed size keep the rest of the
+    /// data for the next pop.
+    #[test]
+    fn tcp_cancel_pop() -> Result<()> {
+        let (alice_tx, alice_rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+        let (bob_tx, bob_rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+
+        let bob_barrier: Arc<Barrier> = Arc::new(Barrier::new(2));
+        let alice_barrier: Arc<Barrier> = bob_barrier.clone();
+
+        let alice: JoinHandle<Result<()>> = thread::spawn(move || {
+            let mut libos: DummyLibOS = match DummyLibOS::new_test(ALICE_CONFIG_PATH, alice_tx, bob_rx) {
+                Ok(libos) => libos,
+                Err(e) => anyhow::bail!("Could not create inetstack: {:?}", e),
+            };
+
+            let port: u16 = PORT_BASE;
+            let local: SocketAddr = SocketAddr::new(ALICE_IP, port);
+
+            // Open connection.
+            let sockqd: QDesc = safe_socket(&mut libos)?;
+            safe_bind(&mut libos, sockqd, local)?;
+            safe_listen(&mut libos, sockqd)?;
+            let qt: QToken = safe_accept(&mut libos, sockqd)?;
+            let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
+            let qd: QDesc = match qr {
+                OperationResult::Accept((qd, addr)) if addr.ip() == BOB_IP => qd,
+                _ => anyhow::bail!("accept() has failed"),
+            };
+
+            // Cancel a pop before it runs.
+            let qt: QToken = safe_pop(&mut libos, qd)?;
+            if let Err(e) = libos.cancel(qt) {
+                anyhow::bail!("cancel() failed: {:?}", e)
+            }
+            match safe_wait(&mut libos, qt)? {
+                (pop_qd, OperationResult::Failed(e)) if pop_qd == qd && e.errno == libc::ECANCELED => (),
+                (_, qr) => anyhow::bail!("cancelled pop() should fail with ECANCELED {:?}", qr),
+            }
+
+            // Pop the data in two halves.
+            for _ in 0..2 {
+                let qt: QToken = match libos.pop(qd, Some(16)) {
+                    Ok(qt) => qt,
+                    Err(e) => anyhow::bail!("pop() failed: {:?}", e),
+                };
+                match safe_wait(&mut libos, qt)? {
+                    (_, OperationResult::Pop(_, buf)) if buf.len() == 16 => (),
+                    (_, qr) => anyhow::bail!("pop() has has failed {:?}", qr),
+                }
+            }
+
+            // Close connection.
+            safe_close_active(&mut libos, qd)?;
+            safe_close_passive(&mut libos, sockqd)?;
+            alice_barrier.wait();
+            Ok(())
+        });
+
+        let bob: JoinHandle<Result<()>> = thread::spawn(move || {
+            let mut libos: DummyLibOS = match DummyLibOS::new_test(BOB_CONFIG_PATH, bob_tx, alice_rx) {
+                Ok(libos) => libos,
+                Err(e) => anyhow::bail!("Could not create inetstack: {:?}", e),
+            };
+
+            let port: u16 = PORT_BASE;
+            let remote: SocketAddr = SocketAddr::new(ALICE_IP, port);
+
+            // Open connection.
+            let sockqd: QDesc = safe_socket(&mut libos)?;
+            let qt: QToken = safe_connect(&mut libos, sockqd, remote)?;
+            let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
+            match qr {
+                OperationResult::Connect => (),
+                _ => anyhow::bail!("connect() has failed"),
+            }
+
+            // Cook some data and push.
+            let buf = libos.cook_data(32)?;
+            let qt: QToken = safe_push(&mut libos, sockqd, buf)?;
+            let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
+            match qr {
+                OperationResult::Push => (),
+                _ => anyhow::bail!("push() has failed"),
+            }
+
+            // Close connection.
+            safe_close_active(&mut libos, sockqd)?;
+            bob_barrier.wait();
+
+            Ok(())
+        });
+        // It is safe to use unwrap here because there should not be any reason that we can't join the thread and if there
+        // is, there is nothing to clean up here on the main thread.
+        alice.join().unwrap()?;
+        bob.join().unwrap()?;
+
+        Ok(())
+    }
+
+    /// Tests if a push that is cancelled before it runs is withdrawn, and if a push that is cancelled after it started
+    /// sending completes in the background, without throwing off later pushes.
+    #[test]
+    fn tcp_cancel_push() -> Result<()> {
+        let (alice_tx, alice_rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+        let (bob_tx, bob_rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+
+        let bob_barrier: Arc<Barrier> = Arc::new(Barrier::new(2));
+        let alice_barrier: Arc<Barrier> = bob_barrier.clone();
+
+        let alice: JoinHandle<Result<()>> = thread::spawn(move || {
+            let mut libos: DummyLibOS = match DummyLibOS::new_test(ALICE_CONFIG_PATH, alice_tx, bob_rx) {
+                Ok(libos) => libos,
+                Err(e) => anyhow::bail!("Could not create inetstack: {:?}", e),
+            };
+
+            let port: u16 = PORT_BASE;
+            let local: SocketAddr = SocketAddr::new(ALICE_IP, port);
+
+            // Open connection.
+            let sockqd: QDesc = safe_socket(&mut libos)?;
+            safe_bind(&mut libos, sockqd, local)?;
+            safe_listen(&mut libos, sockqd)?;
+            let qt: QToken = safe_accept(&mut libos, sockqd)?;
+            let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
+            let qd: QDesc = match qr {
+                OperationResult::Accept((qd, addr)) if addr.ip() == BOB_IP => qd,
+                _ => anyhow::bail!("accept() has failed"),
+            };
+
+            // Only the data of the pushes that were not withdrawn should arrive.
+            let mut received: usize = 0;
+            while received < 32 {
+                let qt: QToken = safe_pop(&mut libos, qd)?;
+                match safe_wait(&mut libos, qt)? {
+                    (_, OperationResult::Pop(_, buf)) if buf.iter().all(|b: &u8| *b == b'b') => received += buf.len(),
+                    (_, qr) => anyhow::bail!("pop() has has failed {:?}", qr),
+                }
+            }
+
+            // Close connection.
+            safe_close_active(&mut libos, qd)?;
+            safe_close_passive(&mut libos, sockqd)?;
+            alice_barrier.wait();
+            Ok(())
+        });
+
+        let bob: JoinHandle<Result<()>> = thread::spawn(move || {
+            let mut libos: DummyLibOS = match DummyLibOS::new_test(BOB_CONFIG_PATH, bob_tx, alice_rx) {
+                Ok(libos) => libos,
+                Err(e) => anyhow::bail!("Could not create inetstack: {:?}", e),
+            };
+
+            let port: u16 = PORT_BASE;
+            let remote: SocketAddr = SocketAddr::new(ALICE_IP, port);
+
+            // Open connection.
+            let sockqd: QDesc = safe_socket(&mut libos)?;
+            let qt: QToken = safe_connect(&mut libos, sockqd, remote)?;
+            let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
+            match qr {
+                OperationResult::Connect => (),
+                _ => anyhow::bail!("connect() has failed"),
+            }
+
+            // Cancel a push before it runs.
+            let buf = libos.cook_data_with(32, b'a')?;
+            let qt: QToken = safe_push(&mut libos, sockqd, buf)?;
+            if let Err(e) = libos.cancel(qt) {
+                anyhow::bail!("cancel() failed: {:?}", e)
+            }
+            match safe_wait(&mut libos, qt)? {
+                (push_qd, OperationResult::Failed(e)) if push_qd == sockqd && e.errno == libc::ECANCELED => (),
+                (_, qr) => anyhow::bail!("cancelled push() should fail with ECANCELED {:?}", qr),
+            }
+
+            // Cancel a push after it started sending, unless it already completed.
+            let buf = libos.cook_data_with(16, b'b')?;
+            let qt: QToken = safe_push(&mut libos, sockqd, buf)?;
+            match libos.wait(qt, Some(BAD_WAIT_TIMEOUT)) {
+                Ok((_, OperationResult::Push)) => (),
+                Err(e) if e.errno == libc::ETIMEDOUT => match libos.cancel(qt) {
+                    Ok(()) => match safe_wait(&mut libos, qt)? {
+                        (_, OperationResult::Failed(e)) if e.errno == libc::ECANCELED => (),
+                        (_, qr) => anyhow::bail!("cancelled push() should fail with ECANCELED {:?}", qr),
+                    },
+                    Err(e) if e.errno == libc::EALREADY => _ = safe_wait(&mut libos, qt)?,
+                    Err(e) => anyhow::bail!("cancel() failed: {:?}", e),
+                },
+                result => anyhow::bail!("push() has failed {:?}", result),
+            }
+
+            // The next push should complete as usual.
+            let buf = libos.cook_data_with(16, b'b')?;
+            let qt: QToken = safe_push(&mut libos, sockqd, buf)?;
+            match safe_wait(&mut libos, qt)? {
+                (_, OperationResult::Push) => (),
+                (_, qr) => anyhow::bail!("push() has failed {:?}", qr),
+            }
+
+            // Close connection.
+            safe_close_active(&mut libos, sockqd)?;
+            bob_barrier.wait();
+
+            Ok(())
+        });
+        // It is safe to use unwrap here because there should not be any reason that we can't join the thread and if there
+        // is, there is nothing to clean up here on the main thread.
+        alice.join().unwrap()?;
+        bob.join().unwrap()?;
+
+        Ok(())
+    }
+
+    /// Tests if a connect that is cancelled while it waits for the remote returns its socket to the state that the
+    /// socket had before connecting.
+    #[test]
+    fn tcp_cancel_connect() -> Result<()> {
+        // Nobody answers on the other end, so connects do not complete.
+        let (tx, rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+        let mut libos: DummyLibOS = match DummyLibOS::new_test(ALICE_CONFIG_PATH, tx, rx) {
+            Ok(libos) => libos,
+            Err(e) => anyhow::bail!("Could not create inetstack: {:?}", e),
+        };
+
+        let local: SocketAddr = SocketAddr::new(ALICE_IP, PORT_BASE);
+        let remote: SocketAddr = SocketAddr::new(BOB_IP, PORT_BASE);
+
+        // An unbound socket can connect again after a cancelled connect.
+        let sockqd: QDesc = safe_socket(&mut libos)?;
+        for _ in 0..2 {
+            let qt: QToken = safe_connect(&mut libos, sockqd, remote)?;
+            safe_cancel_connect(&mut libos, sockqd, qt)?;
+        }
+        safe_close_active(&mut libos, sockqd)?;
+
+        // A bound socket gets its binding back after a cancelled connect, so it can listen on it.
+        let sockqd: QDesc = safe_socket(&mut libos)?;
+        safe_bind(&mut libos, sockqd, local)?;
+        let qt: QToken = safe_connect(&mut libos, sockqd, remote)?;
+        safe_cancel_connect(&mut libos, sockqd, qt)?;
+        safe_listen(&mut libos, sockqd)?;
+        safe_close_passive(&mut libos, sockqd)?;
+
+        Ok(())
+    }
+
     //======================================================================================================================
     // Bad Socket
     //======================================================================================================================
@@ -419,7 +666,7 @@ mod test {
             libc::AF_IB,
             libc::AF_IEEE802154,
             // libc::AF_INET,
-            libc::AF_INET6,
+            // libc::AF_INET6,
             libc::AF_IPX,
             libc::AF_IRDA,
             libc::AF_ISDN,
@@ -455,7 +702,7 @@ mod test {
             WinSock::AF_APPLETALK as i32,
             WinSock::AF_DECnet as i32,
             // WinSock::AF_INET as i32,
-            WinSock::AF_INET6.0 as i32,
+            // WinSock::AF_INET6.0 as i32,
             WinSock::AF_IPX as i32,
             WinSock::AF_IRDA as i32,
             WinSock::AF_SNA as i32,
@@ -519,11 +766,11 @@ mod test {
         let local2: SocketAddr = SocketAddr::new(ALICE_IP, port2);
         let localv6: SocketAddr = SocketAddr::V6(SocketAddrV6::new(Ipv6Addr::LOCALHOST, port, 0, 0));
 
-        // IPv6 unsupported
+        // Can't bind to a non-local IPv6 address
         let sockqd: QDesc = safe_socket(&mut libos)?;
         match libos.bind(sockqd, localv6) {
-            Err(e) if e.errno == libc::EINVAL => (),
-            _ => anyhow::bail!("invalid call to bind should fail with EINVAL"),
+            Err(e) if e.errno == libc::EADDRNOTAVAIL => (),
+            _ => anyhow::bail!("invalid call to bind should fail with EADDRNOTAVAIL"),
         }
 
         // Can't re-bind an address
@@ -675,7 +922,7 @@ mod test {
             let qt: QToken = safe_accept(&mut libos, sockqd)?;
             let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
             let qd: QDesc = match qr {
-                OperationResult::Accept((qd, addr)) if addr.ip() == &BOB_IP => qd,
+                OperationResult::Accept((qd, addr)) if addr.ip() == BOB_IP => qd,
                 _ => {
                     // Close socket on error.
                     // FIXME: https://github.com/demikernel/demikernel/issues/633
@@ -780,7 +1027,7 @@ mod test {
             let qt: QToken = safe_accept(&mut libos, sockqd)?;
             let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
             let qd: QDesc = match qr {
-                OperationResult::Accept((qd, addr)) if addr.ip() == &BOB_IP => qd,
+                OperationResult::Accept((qd, addr)) if addr.ip() == BOB_IP => qd,
                 _ => {
                     // Close socket if error.
                     // FIXME: https://github.com/demikernel/demikernel/issues/633
@@ -888,7 +1135,7 @@ mod test {
             let qt: QToken = safe_accept(&mut libos, sockqd)?;
             let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
             let qd: QDesc = match qr {
-                OperationResult::Accept((qd, addr)) if addr.ip() == &BOB_IP => qd,
+                OperationResult::Accept((qd, addr)) if addr.ip() == BOB_IP => qd,
                 _ => {
                     // Close socket if error.
                     // FIXME: https://github.com/demikernel/demikernel/issues/633
@@ -1023,7 +1270,7 @@ mod test {
             let qt: QToken = safe_accept(&mut libos, sockqd)?;
             let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
             let qd: QDesc = match qr {
-                OperationResult::Accept((qd, addr)) if addr.ip() == &BOB_IP => qd,
+                OperationResult::Accept((qd, addr)) if addr.ip() == BOB_IP => qd,
                 _ => {
                     // Close socket if error.
                     // FIXME: https://github.com/demikernel/demikernel/issues/633
@@ -1204,6 +1451,21 @@ mod test {
         }
     }
 
+    /// Lets a connect start, then cancels it.
+    fn safe_cancel_connect(libos: &mut DummyLibOS, sockqd: QDesc, qt: QToken) -> Result<()> {
+        match libos.wait(qt, Some(BAD_WAIT_TIMEOUT)) {
+            Err(e) if e.errno == libc::ETIMEDOUT => (),
+            result => anyhow::bail!("connect() should not complete {:?}", result),
+        }
+        if let Err(e) = libos.cancel(qt) {
+            anyhow::bail!("cancel() failed: {:?}", e)
+        }
+        match safe_wait(libos, qt)? {
+            (qd, OperationResult::Failed(e)) if qd == sockqd && e.errno == libc::ECANCELED => Ok(()),
+            (_, qr) => anyhow::bail!("cancelled connect() should fail with ECANCELED {:?}", qr),
+        }
+    }
+
     /// Safe call to `close()` on passive socket.
     fn safe_close_passive(libos: &mut DummyLibOS, sockqd: QDesc) -> Result<(QDesc, OperationResult)> {
         match libos.async_close(sockqd) {